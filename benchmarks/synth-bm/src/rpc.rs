@@ -51,6 +51,7 @@ pub async fn view_access_key(
     let request = RpcQueryRequest {
         block_reference: BlockReference::latest(),
         request: QueryRequest::ViewAccessKey { account_id, public_key },
+        decode: None,
     };
     let response = client.call(request).await?;
     match response.kind {
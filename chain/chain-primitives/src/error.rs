@@ -52,6 +52,11 @@ pub enum QueryError {
         requested_account_id: near_primitives::types::AccountId,
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
+    },    #[error("Invalid state override: {error_message}")]
+    InvalidStateOverride {
+        error_message: String,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
     },
 }
 
@@ -0,0 +1,65 @@
+use near_async::time::Duration;
+use near_primitives::types::ShardId;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+/// Number of recent apply durations kept per shard.
+const APPLY_CHUNK_DURATIONS_WINDOW: usize = 100;
+
+/// Rolling window of the durations of applying the new chunks of each shard, shared between
+/// the chunk application jobs recording them and the chunk producer reading them.
+#[derive(Clone, Default)]
+pub struct ApplyChunkDurations {
+    durations: Arc<Mutex<HashMap<ShardId, VecDeque<Duration>>>>,
+}
+
+impl ApplyChunkDurations {
+    pub fn record(&self, shard_id: ShardId, duration: Duration) {
+        let mut durations = self.durations.lock().unwrap();
+        let shard_durations = durations.entry(shard_id).or_default();
+        if shard_durations.len() == APPLY_CHUNK_DURATIONS_WINDOW {
+            shard_durations.pop_front();
+        }
+        shard_durations.push_back(duration);
+    }
+
+    /// Returns the given percentile, between 0 and 1, of the recent apply durations of the
+    /// shard, or `None` if no chunk of the shard was applied yet.
+    pub fn percentile(&self, shard_id: ShardId, percentile: f64) -> Option<Duration> {
+        let durations = self.durations.lock().unwrap();
+        let mut shard_durations: Vec<_> = durations.get(&shard_id)?.iter().copied().collect();
+        if shard_durations.is_empty() {
+            return None;
+        }
+        shard_durations.sort();
+        let index = (percentile.clamp(0.0, 1.0) * (shard_durations.len() - 1) as f64).round();
+        Some(shard_durations[index as usize])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ApplyChunkDurations, APPLY_CHUNK_DURATIONS_WINDOW};
+    use near_async::time::Duration;
+    use near_primitives::types::ShardId;
+
+    #[test]
+    fn test_apply_chunk_durations_percentile() {
+        let durations = ApplyChunkDurations::default();
+        let shard_id = ShardId::new(0);
+        assert_eq!(durations.percentile(shard_id, 0.9), None);
+        for ms in (1..=10).rev() {
+            durations.record(shard_id, Duration::milliseconds(ms));
+        }
+        assert_eq!(durations.percentile(shard_id, 0.0), Some(Duration::milliseconds(1)));
+        assert_eq!(durations.percentile(shard_id, 0.5), Some(Duration::milliseconds(6)));
+        assert_eq!(durations.percentile(shard_id, 1.0), Some(Duration::milliseconds(10)));
+        assert_eq!(durations.percentile(ShardId::new(1), 0.5), None);
+
+        // Only the most recent durations are kept.
+        for _ in 0..APPLY_CHUNK_DURATIONS_WINDOW {
+            durations.record(shard_id, Duration::milliseconds(100));
+        }
+        assert_eq!(durations.percentile(shard_id, 0.0), Some(Duration::milliseconds(100)));
+    }
+}
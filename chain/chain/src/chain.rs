@@ -1,3 +1,4 @@
+use crate::apply_chunk_durations::ApplyChunkDurations;
 use crate::approval_verification::verify_approval_with_approvers_info;
 use crate::block_processing_utils::{
     ApplyChunksDoneWaiter, ApplyChunksStillApplying, BlockPreprocessInfo, BlockProcessingArtifact,
@@ -6,12 +7,14 @@ use crate::block_processing_utils::{
 use crate::blocks_delay_tracker::BlocksDelayTracker;
 use crate::chain_update::ChainUpdate;
 use crate::crypto_hash_timer::CryptoHashTimer;
+use crate::delayed_receipts_ages::DelayedReceiptsAges;
 use crate::lightclient::get_epoch_block_producers_view;
 use crate::migrations::check_if_block_is_first_with_chunk_of_version;
 use crate::missing_chunks::MissingChunksPool;
 use crate::orphan::{Orphan, OrphanBlockPool};
 use crate::rayon_spawner::RayonAsyncComputationSpawner;
 use crate::resharding::manager::ReshardingManager;
+use crate::resharding::shard_uid_mapping::check_shard_uid_mappings;
 use crate::resharding::types::ReshardingSender;
 use crate::sharding::shuffle_receipt_proofs;
 use crate::signature_verification::{
@@ -76,8 +79,9 @@ use near_primitives::sharding::{
 };
 use near_primitives::state_part::PartId;
 use near_primitives::state_sync::{
-    get_num_state_parts, ReceiptProofResponse, RootProof, ShardStateSyncResponseHeader,
-    ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
+    get_num_state_parts, BatchedRootProof, ReceiptProofResponse, RootProof, RootProofs,
+    ShardStateSyncResponseHeader, ShardStateSyncResponseHeaderV2, ShardStateSyncResponseHeaderV3,
+    StateHeaderKey, StatePartKey,
 };
 use near_primitives::stateless_validation::state_witness::{
     ChunkStateWitness, ChunkStateWitnessSize,
@@ -286,6 +290,18 @@ pub struct Chain {
 
     /// Manages all tasks related to resharding.
     pub resharding_manager: ReshardingManager,
+
+    /// Recent durations of applying the new chunks of each shard, used by the chunk producer
+    /// to adapt the time spent adding transactions.
+    pub apply_chunk_durations: ApplyChunkDurations,
+
+    /// Ages of the oldest delayed receipts of the tracked shards, reported as metrics after
+    /// the chunks of a block are applied.
+    delayed_receipts_ages: DelayedReceiptsAges,
+
+    /// Shards whose state transition data isn't saved when the node doesn't produce their
+    /// chunks, see `ChainConfig::skip_state_transition_data_for_shards`.
+    skip_state_transition_data_for_shards: HashSet<ShardId>,
 }
 
 impl Drop for Chain {
@@ -378,6 +394,7 @@ impl Chain {
         let resharding_manager = ReshardingManager::new(
             store.clone(),
             epoch_manager.clone(),
+            shard_tracker.clone(),
             runtime_adapter.clone(),
             MutableConfigValue::new(Default::default(), "resharding_config"),
             noop().into_multi_sender(),
@@ -406,6 +423,9 @@ impl Chain {
             requested_state_parts: StateRequestTracker::new(),
             snapshot_callbacks: None,
             resharding_manager,
+            apply_chunk_durations: Default::default(),
+            delayed_receipts_ages: Default::default(),
+            skip_state_transition_data_for_shards: HashSet::new(),
         })
     }
 
@@ -437,6 +457,11 @@ impl Chain {
             chain_genesis.height,
             chain_config.save_trie_changes,
         );
+        chain_store.set_save_account_receipts(chain_config.save_account_receipts);
+        chain_store.set_secondary_indexes(chain_config.secondary_indexes);
+        chain_store.set_save_access_key_usage(chain_config.save_access_key_usage);
+        let skip_state_transition_data_for_shards =
+            chain_config.skip_state_transition_data_for_shards.iter().copied().collect();
         let mut store_update = chain_store.store_update();
         let (block_head, header_head) = match store_update.head() {
             Ok(block_head) => {
@@ -565,6 +590,7 @@ impl Chain {
         let resharding_manager = ReshardingManager::new(
             chain_store.store().clone(),
             epoch_manager.clone(),
+            shard_tracker.clone(),
             runtime_adapter.clone(),
             chain_config.resharding_config,
             resharding_sender,
@@ -593,9 +619,38 @@ impl Chain {
             requested_state_parts: StateRequestTracker::new(),
             snapshot_callbacks,
             resharding_manager,
+            apply_chunk_durations: Default::default(),
+            delayed_receipts_ages: Default::default(),
+            skip_state_transition_data_for_shards,
         })
     }
 
+    /// Resumes the resharding of the state interrupted by a restart of the node, if any. Must
+    /// be called after the flat storage is initialized.
+    pub fn resume_interrupted_resharding(&mut self) -> Result<(), Error> {
+        self.resharding_manager.resume_interrupted_split_shards(
+            &mut self.chain_store,
+            self.runtime_adapter.get_tries(),
+        )
+    }
+
+    /// Reports the inconsistencies of the State mapping of the shards left by past reshardings.
+    /// They are not repaired, as that may hide a bug, but the
+    /// `neard database check-shard-uid-mappings --repair` command does so.
+    pub fn check_shard_uid_mappings(&self) -> Result<(), Error> {
+        let genesis_protocol_version =
+            self.epoch_manager.get_epoch_protocol_version(self.genesis.header().epoch_id())?;
+        let issues = check_shard_uid_mappings(
+            self.chain_store.store(),
+            self.epoch_manager.as_ref(),
+            genesis_protocol_version,
+        )?;
+        for issue in issues {
+            tracing::warn!(target: "chain", ?issue, "inconsistent State mapping of a shard");
+        }
+        Ok(())
+    }
+
     #[cfg(feature = "test_features")]
     pub fn adv_disable_doomslug(&mut self) {
         self.doomslug_threshold_mode = DoomslugThresholdMode::NoApprovals
@@ -1920,16 +1975,14 @@ impl Chain {
         block_preprocess_info: BlockPreprocessInfo,
         apply_results: Vec<(ShardId, Result<ShardUpdateResult, Error>)>,
     ) -> Result<Option<Tip>, Error> {
-        // Save state transition data to the database only if it might later be needed
-        // for generating a state witness. Storage space optimization.
-        let should_save_state_transition_data =
-            self.should_produce_state_witness_for_this_or_next_epoch(me, block.header())?;
+        let shards_to_save_state_transition_data =
+            self.get_shards_to_save_state_transition_data(me, block.header())?;
         let mut chain_update = self.chain_update();
         let new_head = chain_update.postprocess_block(
             &block,
             block_preprocess_info,
             apply_results,
-            should_save_state_transition_data,
+            &shards_to_save_state_transition_data,
         )?;
         chain_update.commit()?;
         Ok(new_head)
@@ -1973,10 +2026,20 @@ impl Chain {
         // TODO(wacban): do the above todo
         for (shard_id, apply_result) in apply_results.iter() {
             let shard_index = shard_layout.get_shard_index(*shard_id)?;
-            if let Err(err) = apply_result {
-                if err.is_bad_data() {
-                    let chunk = block.chunks()[shard_index].clone();
-                    block_processing_artifacts.invalid_chunks.push(chunk);
+            match apply_result {
+                Ok(ShardUpdateResult::NewChunk(NewChunkResult { apply_result, .. }))
+                | Ok(ShardUpdateResult::OldChunk(OldChunkResult { apply_result, .. })) => {
+                    self.delayed_receipts_ages.record_and_report(
+                        *shard_id,
+                        block.header().height(),
+                        &apply_result.delayed_receipts_indices,
+                    );
+                }
+                Err(err) => {
+                    if err.is_bad_data() {
+                        let chunk = block.chunks()[shard_index].clone();
+                        block_processing_artifacts.invalid_chunks.push(chunk);
+                    }
                 }
             }
         }
@@ -2025,6 +2088,7 @@ impl Chain {
             if need_storage_update {
                 self.resharding_manager.start_resharding(
                     self.chain_store.store_update(),
+                    me.as_ref(),
                     &block,
                     shard_uid,
                     self.runtime_adapter.get_tries(),
@@ -2776,6 +2840,37 @@ impl Chain {
         Ok(shard_state_header)
     }
 
+    /// Converts a state sync header to the format with the proofs of the outgoing receipts
+    /// roots batched per block. Only the peers which advertised that they can decode it get a
+    /// header in this format, so the stored and dumped headers stay in the `V2` format.
+    pub fn batch_state_response_header_proofs(
+        &self,
+        header: ShardStateSyncResponseHeaderV2,
+    ) -> Result<ShardStateSyncResponseHeaderV3, Error> {
+        let root_proofs = header
+            .incoming_receipts_proofs
+            .iter()
+            .map(|ReceiptProofResponse(block_hash, _)| {
+                let block = self.get_block(block_hash)?;
+                let outgoing_receipts_roots = block
+                    .chunks()
+                    .iter_deprecated()
+                    .map(|chunk| chunk.prev_outgoing_receipts_root())
+                    .collect();
+                Ok(BatchedRootProof { outgoing_receipts_roots })
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(ShardStateSyncResponseHeaderV3 {
+            chunk: header.chunk,
+            chunk_proof: header.chunk_proof,
+            prev_chunk_header: header.prev_chunk_header,
+            prev_chunk_proof: header.prev_chunk_proof,
+            incoming_receipts_proofs: header.incoming_receipts_proofs,
+            root_proofs,
+            state_root_node: header.state_root_node,
+        })
+    }
+
     pub fn get_state_response_part(
         &mut self,
         shard_id: ShardId,
@@ -2917,9 +3012,8 @@ impl Chain {
 
         // 4. Proving incoming receipts validity
         // 4a. Checking len of proofs
-        if shard_state_header.root_proofs().len()
-            != shard_state_header.incoming_receipts_proofs().len()
-        {
+        let root_proofs = shard_state_header.root_proofs();
+        if root_proofs.len() != shard_state_header.incoming_receipts_proofs().len() {
             byzantine_assert!(false);
             return Err(Error::Other("set_shard_state failed: invalid proofs".into()));
         }
@@ -2941,12 +3035,27 @@ impl Chain {
 
             let block_header = self.get_block_header(block_hash)?;
             // 4c. Checking len of receipt_proofs for current block
-            if receipt_proofs.len() != shard_state_header.root_proofs()[i].len()
+            let num_root_proofs = match root_proofs {
+                RootProofs::PerChunk(root_proofs) => root_proofs[i].len(),
+                RootProofs::Batched(_) => receipt_proofs.len(),
+            };
+            if receipt_proofs.len() != num_root_proofs
                 || receipt_proofs.len() != block_header.chunks_included() as usize
             {
                 byzantine_assert!(false);
                 return Err(Error::Other("set_shard_state failed: invalid proofs".into()));
             }
+            // 4c'. With batched proofs, the outgoing receipts roots of all the chunks in the
+            // block are proven at once against the block's prev_chunk_outgoing_receipts_root.
+            if let RootProofs::Batched(root_proofs) = root_proofs {
+                let roots = &root_proofs[i].outgoing_receipts_roots;
+                if &merklize(roots).0 != block_header.prev_chunk_outgoing_receipts_root() {
+                    byzantine_assert!(false);
+                    return Err(Error::Other("set_shard_state failed: invalid proofs".into()));
+                }
+            }
+            let block_shard_layout =
+                self.epoch_manager.get_shard_layout(block_header.epoch_id())?;
             // We know there were exactly `block_header.chunks_included` chunks included
             // on the height of block `block_hash`.
             // There were no other proofs except for included chunks.
@@ -2964,19 +3073,43 @@ impl Chain {
                     }
                     _ => visited_shard_ids.insert(*from_shard_id),
                 };
-                let RootProof(root, block_proof) = &shard_state_header.root_proofs()[i][j];
+                let root = match root_proofs {
+                    RootProofs::PerChunk(root_proofs) => {
+                        let RootProof(root, block_proof) = &root_proofs[i][j];
+                        // 4f. Proving the outgoing_receipts_root matches that in the block
+                        if !verify_path(
+                            *block_header.prev_chunk_outgoing_receipts_root(),
+                            block_proof,
+                            root,
+                        ) {
+                            byzantine_assert!(false);
+                            return Err(Error::Other(
+                                "set_shard_state failed: invalid proofs".into(),
+                            ));
+                        }
+                        *root
+                    }
+                    RootProofs::Batched(root_proofs) => {
+                        // 4f. The outgoing_receipts_root has already been proven in 4c',
+                        // it only needs to be looked up by the index of the sending shard.
+                        let root = block_shard_layout
+                            .get_shard_index(*from_shard_id)
+                            .ok()
+                            .and_then(|from_shard_index| {
+                                root_proofs[i].outgoing_receipts_roots.get(from_shard_index)
+                            });
+                        let Some(root) = root else {
+                            byzantine_assert!(false);
+                            return Err(Error::Other(
+                                "set_shard_state failed: invalid proofs".into(),
+                            ));
+                        };
+                        *root
+                    }
+                };
                 let receipts_hash = CryptoHash::hash_borsh(ReceiptList(shard_id, receipts));
                 // 4e. Proving the set of receipts is the subset of outgoing_receipts of shard `shard_id`
-                if !verify_path(*root, proof, &receipts_hash) {
-                    byzantine_assert!(false);
-                    return Err(Error::Other("set_shard_state failed: invalid proofs".into()));
-                }
-                // 4f. Proving the outgoing_receipts_root matches that in the block
-                if !verify_path(
-                    *block_header.prev_chunk_outgoing_receipts_root(),
-                    block_proof,
-                    root,
-                ) {
+                if !verify_path(root, proof, &receipts_hash) {
                     byzantine_assert!(false);
                     return Err(Error::Other("set_shard_state failed: invalid proofs".into()));
                 }
@@ -3287,16 +3420,14 @@ impl Chain {
         results: Vec<Result<ShardUpdateResult, Error>>,
     ) -> Result<(), Error> {
         let block = self.chain_store.get_block(block_hash)?;
-        // Save state transition data to the database only if it might later be needed
-        // for generating a state witness. Storage space optimization.
-        let should_save_state_transition_data =
-            self.should_produce_state_witness_for_this_or_next_epoch(me, block.header())?;
+        let shards_to_save_state_transition_data =
+            self.get_shards_to_save_state_transition_data(me, block.header())?;
         let mut chain_update = self.chain_update();
         let results = results.into_iter().collect::<Result<Vec<_>, Error>>()?;
         chain_update.apply_chunk_postprocessing(
             &block,
             results,
-            should_save_state_transition_data,
+            &shards_to_save_state_transition_data,
         )?;
         chain_update.commit()?;
 
@@ -3318,6 +3449,7 @@ impl Chain {
                 let shard_uid = self.epoch_manager.shard_id_to_uid(shard_id, &epoch_id)?;
                 self.resharding_manager.start_resharding(
                     self.chain_store.store_update(),
+                    me.as_ref(),
                     &block,
                     shard_uid,
                     self.runtime_adapter.get_tries(),
@@ -3640,6 +3772,54 @@ impl Chain {
             || self.epoch_manager.is_chunk_producer_for_epoch(&next_epoch_id, account_id)?)
     }
 
+    /// Returns the shards whose state transition data for the block should be generated and
+    /// saved to the database. It is saved only if it might later be needed for generating a
+    /// state witness, that is on the chunk producers of this or the next epoch, as a storage
+    /// space optimization. The chunk producers tracking more shards than the ones they produce
+    /// chunks for skip the shards in `skip_state_transition_data_for_shards` unless they
+    /// produce their chunks in this or the next epoch.
+    fn get_shards_to_save_state_transition_data(
+        &self,
+        me: &Option<AccountId>,
+        block_header: &BlockHeader,
+    ) -> Result<HashSet<ShardId>, Error> {
+        if !self.should_produce_state_witness_for_this_or_next_epoch(me, block_header)? {
+            return Ok(HashSet::new());
+        }
+        let epoch_id = block_header.epoch_id();
+        let shard_ids = self.epoch_manager.shard_ids(epoch_id)?;
+        let Some(account_id) = me.as_ref() else { return Ok(shard_ids.into_iter().collect()) };
+        if self.skip_state_transition_data_for_shards.is_empty()
+            || cfg!(feature = "shadow_chunk_validation")
+        {
+            return Ok(shard_ids.into_iter().collect());
+        }
+
+        let next_epoch_id =
+            self.epoch_manager.get_next_epoch_id_from_prev_block(block_header.prev_hash())?;
+        // The shard ids of the next epoch can't be matched with the current ones across a
+        // resharding, in which case all the shards are saved.
+        let is_same_shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?
+            == self.epoch_manager.get_shard_layout(&next_epoch_id)?;
+        let mut shards_to_save = HashSet::new();
+        for shard_id in shard_ids {
+            let can_skip = self.skip_state_transition_data_for_shards.contains(&shard_id)
+                && is_same_shard_layout
+                && !self
+                    .epoch_manager
+                    .get_epoch_chunk_producers_for_shard(epoch_id, shard_id)?
+                    .contains(account_id)
+                && !self
+                    .epoch_manager
+                    .get_epoch_chunk_producers_for_shard(&next_epoch_id, shard_id)?
+                    .contains(account_id);
+            if !can_skip {
+                shards_to_save.insert(shard_id);
+            }
+        }
+        Ok(shards_to_save)
+    }
+
     /// Creates jobs which will update shards for the given block and incoming
     /// receipts aggregated for it.
     fn apply_chunks_preprocessing(
@@ -3658,6 +3838,9 @@ impl Chain {
 
         let epoch_id = block.header().epoch_id();
         let shard_layout = self.epoch_manager.get_shard_layout(&epoch_id)?;
+        // The storage proof is only generated for the state transition data to save.
+        let shards_to_save_state_transition_data =
+            self.get_shards_to_save_state_transition_data(me, block.header())?;
 
         let mut maybe_jobs = vec![];
         for (shard_index, (chunk_header, prev_chunk_header)) in
@@ -3668,8 +3851,11 @@ impl Chain {
             let state_patch = state_patch.take();
             let shard_id = shard_layout.get_shard_id(shard_index)?;
 
-            let storage_context =
-                StorageContext { storage_data_source: StorageDataSource::Db, state_patch };
+            let storage_context = StorageContext {
+                storage_data_source: StorageDataSource::Db,
+                state_patch,
+                keep_storage_proof: shards_to_save_state_transition_data.contains(&shard_id),
+            };
             let stateful_job = self.get_update_shard_job(
                 me,
                 block,
@@ -3846,15 +4032,24 @@ impl Chain {
         };
 
         let runtime = self.runtime_adapter.clone();
+        let clock = self.clock.clone();
+        // Only the new chunks are recorded, as they are what the chunk producer waits for.
+        let apply_chunk_durations = matches!(shard_update_reason, ShardUpdateReason::NewChunk(_))
+            .then(|| self.apply_chunk_durations.clone());
         Ok(Some((
             shard_id,
             Box::new(move |parent_span| -> Result<ShardUpdateResult, Error> {
-                Ok(process_shard_update(
+                let start_time = clock.now();
+                let result = process_shard_update(
                     parent_span,
                     runtime.as_ref(),
                     shard_update_reason,
                     shard_context,
-                )?)
+                )?;
+                if let Some(apply_chunk_durations) = apply_chunk_durations {
+                    apply_chunk_durations.record(shard_id, clock.now() - start_time);
+                }
+                Ok(result)
             }),
         )))
     }
@@ -23,6 +23,7 @@ use near_primitives::state_sync::{ReceiptProofResponse, ShardStateSyncResponseHe
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{BlockExtra, BlockHeight, BlockHeightDelta, ShardId};
 use near_primitives::views::LightClientBlockView;
+use std::collections::HashSet;
 use std::sync::Arc;
 use tracing::{debug, info, warn};
 
@@ -82,12 +83,12 @@ impl<'a> ChainUpdate<'a> {
         &mut self,
         block: &Block,
         apply_results: Vec<ShardUpdateResult>,
-        should_save_state_transition_data: bool,
+        shards_to_save_state_transition_data: &HashSet<ShardId>,
     ) -> Result<(), Error> {
         let _span = tracing::debug_span!(target: "chain", "apply_chunk_postprocessing", height=block.header().height()).entered();
         Self::bandwidth_scheduler_state_sanity_check(&apply_results);
         for result in apply_results {
-            self.process_apply_chunk_result(block, result, should_save_state_transition_data)?;
+            self.process_apply_chunk_result(block, result, shards_to_save_state_transition_data)?;
         }
         Ok(())
     }
@@ -97,7 +98,7 @@ impl<'a> ChainUpdate<'a> {
         &mut self,
         block: &Block,
         result: ShardUpdateResult,
-        should_save_state_transition_data: bool,
+        shards_to_save_state_transition_data: &HashSet<ShardId>,
     ) -> Result<(), Error> {
         let block_hash = block.hash();
         let prev_hash = block.header().prev_hash();
@@ -144,6 +145,24 @@ impl<'a> ChainUpdate<'a> {
                     shard_id,
                     apply_result.outgoing_receipts,
                 );
+                if let Some(chunk_header) = block
+                    .chunks()
+                    .iter_deprecated()
+                    .find(|h| h.shard_id() == shard_id && h.height_included() == height)
+                {
+                    self.chain_store_update.save_account_receipts(
+                        height,
+                        block_hash,
+                        shard_id,
+                        &chunk_header.chunk_hash(),
+                        &apply_result.outcomes,
+                    )?;
+                    self.chain_store_update.save_access_key_usage(
+                        height,
+                        &chunk_header.chunk_hash(),
+                        &apply_result.outcomes,
+                    )?;
+                }
                 // Save receipt and transaction results.
                 self.chain_store_update.save_outcomes_with_proofs(
                     block_hash,
@@ -151,7 +170,7 @@ impl<'a> ChainUpdate<'a> {
                     apply_result.outcomes,
                     outcome_paths,
                 );
-                if should_save_state_transition_data {
+                if shards_to_save_state_transition_data.contains(&shard_id) {
                     self.chain_store_update.save_state_transition_data(
                         *block_hash,
                         shard_id,
@@ -181,7 +200,7 @@ impl<'a> ChainUpdate<'a> {
 
                 self.chain_store_update.save_chunk_extra(block_hash, &shard_uid, new_extra);
                 self.chain_store_update.save_trie_changes(apply_result.trie_changes);
-                if should_save_state_transition_data {
+                if shards_to_save_state_transition_data.contains(&shard_uid.shard_id()) {
                     self.chain_store_update.save_state_transition_data(
                         *block_hash,
                         shard_uid.shard_id(),
@@ -226,7 +245,7 @@ impl<'a> ChainUpdate<'a> {
         block: &Block,
         block_preprocess_info: BlockPreprocessInfo,
         apply_chunks_results: Vec<(ShardId, Result<ShardUpdateResult, Error>)>,
-        should_save_state_transition_data: bool,
+        shards_to_save_state_transition_data: &HashSet<ShardId>,
     ) -> Result<Option<Tip>, Error> {
         let prev_hash = block.header().prev_hash();
         let results = apply_chunks_results.into_iter().map(|(shard_id, x)| {
@@ -235,7 +254,8 @@ impl<'a> ChainUpdate<'a> {
             }
             x
         }).collect::<Result<Vec<_>, Error>>()?;
-        self.apply_chunk_postprocessing(block, results, should_save_state_transition_data)?;
+        self.apply_chunk_postprocessing(block, results, shards_to_save_state_transition_data)?;
+        self.chain_store_update.save_secondary_indexes(block)?;
 
         let BlockPreprocessInfo {
             is_caught_up,
@@ -497,6 +517,9 @@ impl<'a> ChainUpdate<'a> {
             ShardStateSyncResponseHeader::V2(shard_state_header) => {
                 (shard_state_header.chunk, shard_state_header.incoming_receipts_proofs)
             }
+            ShardStateSyncResponseHeader::V3(shard_state_header) => {
+                (shard_state_header.chunk, shard_state_header.incoming_receipts_proofs)
+            }
         };
 
         let block_header = self
@@ -593,6 +616,18 @@ impl<'a> ChainUpdate<'a> {
             shard_id,
             apply_result.outgoing_receipts,
         );
+        self.chain_store_update.save_account_receipts(
+            chunk_header.height_included(),
+            block_header.hash(),
+            shard_id,
+            &chunk_header.chunk_hash(),
+            &apply_result.outcomes,
+        )?;
+        self.chain_store_update.save_access_key_usage(
+            chunk_header.height_included(),
+            &chunk_header.chunk_hash(),
+            &apply_result.outcomes,
+        )?;
         // Saving transaction results.
         self.chain_store_update.save_outcomes_with_proofs(
             block_header.hash(),
@@ -0,0 +1,100 @@
+use crate::metrics;
+use near_primitives::receipt::TrieQueueIndices;
+use near_primitives::types::{BlockHeight, ShardId};
+use std::collections::{HashMap, VecDeque};
+
+/// Estimates the age of the oldest receipt in the delayed receipts queue of each shard, which
+/// isn't stored in state. Updated by the chain after applying the chunks of a block, so it only
+/// covers the shards the node tracks.
+#[derive(Default)]
+pub struct DelayedReceiptsAges {
+    /// For each shard, the heights at which the queue grew, along with the queue index past the
+    /// last receipt delayed at that height.
+    heights: HashMap<ShardId, VecDeque<(u64, BlockHeight)>>,
+}
+
+impl DelayedReceiptsAges {
+    /// Records the queue indices of the shard after applying the chunk at `block_height` and
+    /// returns the age of the oldest delayed receipt, in blocks.
+    pub fn record(
+        &mut self,
+        shard_id: ShardId,
+        block_height: BlockHeight,
+        indices: &TrieQueueIndices,
+    ) -> BlockHeight {
+        let heights = self.heights.entry(shard_id).or_default();
+        // The queue shrank past receipts that were never popped, e.g. after
+        // switching to another fork. Start over rather than report bogus ages.
+        if heights.back().is_some_and(|&(index, _)| index > indices.next_available_index) {
+            heights.clear();
+        }
+        if heights.back().map_or(true, |&(index, _)| index < indices.next_available_index) {
+            heights.push_back((indices.next_available_index, block_height));
+        }
+        while heights.front().is_some_and(|&(index, _)| index <= indices.first_index) {
+            heights.pop_front();
+        }
+        let oldest_height = heights.front().map_or(block_height, |&(_, height)| height);
+        block_height.saturating_sub(oldest_height)
+    }
+
+    /// Records the queue indices like `record` and reports the queue length and the age of the
+    /// oldest delayed receipt of the shard as metrics.
+    pub fn record_and_report(
+        &mut self,
+        shard_id: ShardId,
+        block_height: BlockHeight,
+        indices: &TrieQueueIndices,
+    ) {
+        let age = self.record(shard_id, block_height, indices);
+        let shard_label = shard_id.to_string();
+        metrics::DELAYED_RECEIPTS_QUEUE_LEN
+            .with_label_values(&[&shard_label])
+            .set(i64::try_from(indices.len()).unwrap_or(i64::MAX));
+        metrics::DELAYED_RECEIPTS_QUEUE_OLDEST_AGE_BLOCKS
+            .with_label_values(&[&shard_label])
+            .set(i64::try_from(age).unwrap_or(i64::MAX));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DelayedReceiptsAges;
+    use near_primitives::receipt::TrieQueueIndices;
+    use near_primitives::types::ShardId;
+
+    fn indices(first_index: u64, next_available_index: u64) -> TrieQueueIndices {
+        TrieQueueIndices { first_index, next_available_index }
+    }
+
+    #[test]
+    fn test_delayed_receipts_ages() {
+        let mut ages = DelayedReceiptsAges::default();
+        let shard_id = ShardId::new(0);
+        // Empty queue.
+        assert_eq!(ages.record(shard_id, 10, &indices(0, 0)), 0);
+        // Two receipts delayed at height 11, one more at height 13.
+        assert_eq!(ages.record(shard_id, 11, &indices(0, 2)), 0);
+        assert_eq!(ages.record(shard_id, 12, &indices(0, 2)), 1);
+        assert_eq!(ages.record(shard_id, 13, &indices(0, 3)), 2);
+        // Popping one of the receipts delayed at height 11 keeps the age.
+        assert_eq!(ages.record(shard_id, 14, &indices(1, 3)), 3);
+        // Popping both makes the receipt delayed at height 13 the oldest.
+        assert_eq!(ages.record(shard_id, 15, &indices(2, 3)), 2);
+        // Other shards are tracked separately.
+        assert_eq!(ages.record(ShardId::new(1), 15, &indices(5, 6)), 0);
+        // The queue drains.
+        assert_eq!(ages.record(shard_id, 16, &indices(3, 3)), 0);
+    }
+
+    #[test]
+    fn test_delayed_receipts_ages_fork_switch() {
+        let mut ages = DelayedReceiptsAges::default();
+        let shard_id = ShardId::new(0);
+        assert_eq!(ages.record(shard_id, 10, &indices(0, 5)), 0);
+        assert_eq!(ages.record(shard_id, 12, &indices(0, 5)), 2);
+        // A block on another fork, where fewer receipts were delayed.
+        assert_eq!(ages.record(shard_id, 13, &indices(0, 3)), 0);
+        assert_eq!(ages.record(shard_id, 14, &indices(0, 3)), 1);
+    }
+}
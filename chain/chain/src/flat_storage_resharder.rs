@@ -2,18 +2,26 @@
 //!
 //! See [FlatStorageResharder] for more details about how the resharding takes place.
 
+use std::collections::VecDeque;
 use std::sync::{Arc, Mutex};
 
-use near_chain_configs::{MutableConfigValue, ReshardingConfig, ReshardingHandle};
+use near_chain_configs::{
+    MutableConfigValue, ReshardingConfig, ReshardingHandle, ReshardingHandleState,
+};
 use near_chain_primitives::Error;
 
 use tracing::{debug, error, info, warn};
 
 use crate::resharding::event_type::{ReshardingEventType, ReshardingSplitShardParams};
+use crate::resharding::events::{ReshardingEvent, ReshardingEvents};
+use crate::resharding::status::{
+    get_resharding_statuses, update_resharding_status, ReshardingPhase, ReshardingStatus,
+};
 use crate::resharding::types::{
     FlatStorageShardCatchupRequest, FlatStorageSplitShardRequest, MemtrieReloadRequest,
-    ReshardingSender,
+    ReshardingSender, SplitShardValidationRequest,
 };
+use crate::resharding::validation::{save_split_shard_validation, validate_split_shard};
 use crate::types::RuntimeAdapter;
 use crate::{ChainStore, ChainStoreAccess};
 use itertools::Itertools;
@@ -31,13 +39,14 @@ use near_primitives::trie_key::trie_key_parsers::{
 use near_primitives::types::BlockHeightDelta;
 use near_primitives::types::{AccountId, BlockHeight};
 use near_store::adapter::flat_store::{FlatStoreAdapter, FlatStoreUpdateAdapter};
-use near_store::adapter::StoreAdapter;
+use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
 use near_store::flat::{
     BlockInfo, FlatStateChanges, FlatStorageError, FlatStorageReadyStatus,
     FlatStorageReshardingShardCatchUpMetrics, FlatStorageReshardingShardSplitMetrics,
     FlatStorageReshardingStatus, FlatStorageStatus, ParentSplitParameters,
 };
-use near_store::{ShardUId, StorageError};
+use near_store::{ShardUId, StorageError, StoreUpdate};
+use rayon::iter::{IntoParallelRefIterator, ParallelIterator};
 use std::fmt::{Debug, Formatter};
 use std::iter;
 
@@ -66,17 +75,32 @@ use std::iter;
 /// - Resilience to chain forks.
 ///     - Resharding events will perform changes on the state only after their resharding block
 ///       becomes final.  
+///     - If the epoch ends on competing forks, the same event is recorded for each resharding
+///       block and performed on the one which becomes final.
+/// - Multiple splits: a shard layout change may split several parent shards. Only one event is
+///   performed at a time, the splits of the other parents wait in a queue and are performed one
+///   after the other.
+/// - Tracked children only: the flat storage of a child the node won't track is never created.
+///   The children being created are the ones in `CreatingChild` state, so they are known again
+///   when a split is resumed.
 #[derive(Clone)]
 pub struct FlatStorageResharder {
     runtime: Arc<dyn RuntimeAdapter>,
     /// The current active resharding event.
     resharding_event: Arc<Mutex<Option<FlatStorageReshardingEventStatus>>>,
+    /// Resharding blocks of competing forks on which the current event happened as well.
+    competing_resharding_blocks: Arc<Mutex<Vec<BlockInfo>>>,
+    /// Splits of other parent shards of the same shard layout change as the current event,
+    /// waiting for it to finish.
+    pending_split_shards: Arc<Mutex<VecDeque<PendingSplitShard>>>,
     /// Sender responsible to convey requests to the dedicated resharding actor.
     sender: ReshardingSender,
     /// Controls cancellation of background processing.
     pub controller: FlatStorageResharderController,
     /// Configuration for resharding.
     resharding_config: MutableConfigValue<ReshardingConfig>,
+    /// Broadcasts the lifecycle events of resharding.
+    pub events: ReshardingEvents,
     #[cfg(feature = "test_features")]
     /// TEST ONLY.
     /// If non zero, the start of schedulable tasks (such as split parent) will be postponed by
@@ -99,12 +123,17 @@ impl FlatStorageResharder {
         resharding_config: MutableConfigValue<ReshardingConfig>,
     ) -> Self {
         let resharding_event = Arc::new(Mutex::new(None));
+        let competing_resharding_blocks = Arc::new(Mutex::new(vec![]));
+        let pending_split_shards = Arc::new(Mutex::new(VecDeque::new()));
         Self {
             runtime,
             resharding_event,
+            competing_resharding_blocks,
+            pending_split_shards,
             sender,
             controller,
             resharding_config,
+            events: ReshardingEvents::new(),
             #[cfg(feature = "test_features")]
             adv_task_delay_by_blocks: 0,
         }
@@ -112,18 +141,24 @@ impl FlatStorageResharder {
 
     /// Starts a resharding event.
     ///
-    /// For now, only splitting a shard is supported.
-    ///
     /// # Args:
     /// * `event_type`: the type of resharding event
     /// * `shard_layout`: the new shard layout
+    /// * `tracked_children`: the children shards the node will track, the other children of the
+    ///   event are ignored
     pub fn start_resharding(
         &self,
         event_type: ReshardingEventType,
         shard_layout: &ShardLayout,
+        tracked_children: &[ShardUId],
     ) -> Result<(), Error> {
+        if self.add_competing_resharding_block(&event_type) {
+            return Ok(());
+        }
         match event_type {
-            ReshardingEventType::SplitShard(params) => self.split_shard(params, shard_layout),
+            ReshardingEventType::SplitShard(params) => {
+                self.split_shard(params, shard_layout, tracked_children)
+            }
         }
     }
 
@@ -146,7 +181,9 @@ impl FlatStorageResharder {
             FlatStorageReshardingStatus::SplittingParent(status) => {
                 let parent_shard_uid = shard_uid;
                 info!(target: "resharding", ?parent_shard_uid, ?status, "resuming flat storage shard split");
-                self.check_new_event_is_allowed()?;
+                if !self.is_sibling_split(parent_shard_uid, &status.shard_layout) {
+                    self.check_new_event_is_allowed()?;
+                }
                 // On resume, flat storage status is already set correctly and read from DB.
                 // Thus, we don't need to care about cancelling other existing resharding events.
                 // However, we don't know the current state of children shards,
@@ -156,6 +193,7 @@ impl FlatStorageResharder {
             }
             FlatStorageReshardingStatus::CatchingUp(_) => {
                 info!(target: "resharding", ?shard_uid, ?status, "resuming flat storage shard catchup");
+                self.update_status(shard_uid, |status| status.phase = ReshardingPhase::CatchingUp);
                 // Send a request to schedule the execution of `shard_catchup_task` for this shard.
                 self.sender
                     .flat_storage_shard_catchup_sender
@@ -165,11 +203,12 @@ impl FlatStorageResharder {
         Ok(())
     }
 
-    /// Starts the event of splitting a parent shard flat storage into two children.
+    /// Starts the event of splitting a parent shard flat storage into its tracked children.
     fn split_shard(
         &self,
         split_params: ReshardingSplitShardParams,
         shard_layout: &ShardLayout,
+        tracked_children: &[ShardUId],
     ) -> Result<(), Error> {
         let ReshardingSplitShardParams {
             parent_shard,
@@ -180,9 +219,13 @@ impl FlatStorageResharder {
         } = split_params;
         info!(target: "resharding", ?split_params, "initiating flat storage shard split");
 
-        self.check_new_event_is_allowed()?;
-        // Cancel any scheduled, not yet started event.
-        self.cancel_scheduled_event();
+        // The split of another parent in the same shard layout change is not replaced, the new
+        // split waits for it instead.
+        if !self.is_sibling_split(parent_shard, shard_layout) {
+            self.check_new_event_is_allowed()?;
+            // Cancel any scheduled, not yet started event.
+            self.cancel_scheduled_event();
+        }
 
         // Change parent and children shards flat storage status.
         let store = self.runtime.store().flat_store();
@@ -201,20 +244,65 @@ impl FlatStorageResharder {
                 split_params.clone(),
             )),
         );
-        store_update.set_flat_storage_status(
-            left_child_shard,
-            FlatStorageStatus::Resharding(FlatStorageReshardingStatus::CreatingChild),
-        );
-        store_update.set_flat_storage_status(
-            right_child_shard,
-            FlatStorageStatus::Resharding(FlatStorageReshardingStatus::CreatingChild),
-        );
+        for child_shard in [left_child_shard, right_child_shard] {
+            if tracked_children.contains(&child_shard) {
+                store_update.set_flat_storage_status(
+                    child_shard,
+                    FlatStorageStatus::Resharding(FlatStorageReshardingStatus::CreatingChild),
+                );
+            }
+        }
         store_update.commit()?;
 
         self.schedule_split_shard(parent_shard, &split_params);
         Ok(())
     }
 
+    /// If `event_type` is the scheduled event happening on the resharding block of a competing
+    /// fork, records the block so that the event can be performed on it if it becomes final.
+    ///
+    /// Returns `true` if `event_type` doesn't need to be scheduled.
+    fn add_competing_resharding_block(&self, event_type: &ReshardingEventType) -> bool {
+        let ReshardingEventType::SplitShard(params) = event_type;
+        let mut pending_split_shards = self.pending_split_shards.lock().unwrap();
+        if let Some(pending) = pending_split_shards.iter_mut().find(|pending| {
+            pending.parent_shard == params.parent_shard
+                && pending.split_params.left_child_shard == params.left_child_shard
+                && pending.split_params.right_child_shard == params.right_child_shard
+        }) {
+            let resharding_block = params.resharding_block;
+            if resharding_block.hash != pending.split_params.resharding_block.hash {
+                info!(target: "resharding", ?pending, ?resharding_block, "recording competing resharding block of pending split");
+                pending.competing_resharding_blocks.push(resharding_block);
+            }
+            return true;
+        }
+        drop(pending_split_shards);
+        let Some(current_event) = self.resharding_event() else {
+            return false;
+        };
+        if current_event.has_started() {
+            return false;
+        }
+        let resharding_block = match (&current_event, event_type) {
+            (
+                FlatStorageReshardingEventStatus::SplitShard(parent_shard, split_params, ..),
+                ReshardingEventType::SplitShard(params),
+            ) if *parent_shard == params.parent_shard
+                && split_params.left_child_shard == params.left_child_shard
+                && split_params.right_child_shard == params.right_child_shard =>
+            {
+                params.resharding_block
+            }
+            _ => return false,
+        };
+        if resharding_block.hash != current_event.resharding_hash() {
+            info!(target: "resharding", ?current_event, ?resharding_block, "recording competing resharding block");
+            self.competing_resharding_blocks.lock().unwrap().push(resharding_block);
+        }
+        true
+    }
+
     /// Returns `Ok` if:
     /// - no resharding event exists.
     /// - a resharding event already exists, but it's not in progress yet.
@@ -232,8 +320,28 @@ impl FlatStorageResharder {
         Ok(())
     }
 
-    fn set_resharding_event(&self, event: FlatStorageReshardingEventStatus) {
+    /// Returns `true` if the current event is the split of another parent shard than
+    /// `parent_shard` into `shard_layout`, i.e. both splits are part of the same shard layout
+    /// change.
+    fn is_sibling_split(&self, parent_shard: ShardUId, shard_layout: &ShardLayout) -> bool {
+        let Some(FlatStorageReshardingEventStatus::SplitShard(
+            current_parent_shard,
+            split_params,
+            ..,
+        )) = self.resharding_event()
+        else {
+            return false;
+        };
+        current_parent_shard != parent_shard && split_params.shard_layout == *shard_layout
+    }
+
+    fn set_resharding_event(
+        &self,
+        event: FlatStorageReshardingEventStatus,
+        competing_resharding_blocks: Vec<BlockInfo>,
+    ) {
         *self.resharding_event.lock().unwrap() = Some(event);
+        *self.competing_resharding_blocks.lock().unwrap() = competing_resharding_blocks;
     }
 
     /// Returns the current in-progress resharding event, if any.
@@ -249,15 +357,9 @@ impl FlatStorageResharder {
             .map(|event| event.set_execution_status(new_status));
     }
 
-    /// Schedules a task to split a shard.
+    /// Schedules a task to split a shard. If the current event is the split of another parent of
+    /// the same shard layout change, the split is queued until that one is done.
     fn schedule_split_shard(&self, parent_shard: ShardUId, split_params: &ParentSplitParameters) {
-        let event = FlatStorageReshardingEventStatus::SplitShard(
-            parent_shard,
-            split_params.clone(),
-            TaskExecutionStatus::NotStarted,
-        );
-        self.set_resharding_event(event);
-
         let metrics = FlatStorageReshardingShardSplitMetrics::new(
             parent_shard,
             split_params.left_child_shard,
@@ -265,7 +367,44 @@ impl FlatStorageResharder {
         );
         metrics.update_shards_status(&self.runtime.get_flat_storage_manager());
 
-        info!(target: "resharding", ?parent_shard, ?split_params,"scheduling flat storage shard split");
+        if self.is_sibling_split(parent_shard, &split_params.shard_layout) {
+            info!(target: "resharding", ?parent_shard, ?split_params, "queueing flat storage shard split");
+            self.pending_split_shards.lock().unwrap().push_back(PendingSplitShard {
+                parent_shard,
+                split_params: split_params.clone(),
+                competing_resharding_blocks: vec![],
+            });
+            return;
+        }
+        self.send_split_shard_request(parent_shard, split_params.clone(), vec![]);
+    }
+
+    /// Schedules the next queued split, if any.
+    fn schedule_pending_split_shard(&self) {
+        let Some(pending) = self.pending_split_shards.lock().unwrap().pop_front() else {
+            return;
+        };
+        self.send_split_shard_request(
+            pending.parent_shard,
+            pending.split_params,
+            pending.competing_resharding_blocks,
+        );
+    }
+
+    fn send_split_shard_request(
+        &self,
+        parent_shard: ShardUId,
+        split_params: ParentSplitParameters,
+        competing_resharding_blocks: Vec<BlockInfo>,
+    ) {
+        info!(target: "resharding", ?parent_shard, ?split_params, "scheduling flat storage shard split");
+        let event = FlatStorageReshardingEventStatus::SplitShard(
+            parent_shard,
+            split_params,
+            TaskExecutionStatus::NotStarted,
+        );
+        self.set_resharding_event(event, competing_resharding_blocks);
+
         let resharder = self.clone();
         // Send a request to schedule the execution of `split_shard_task`, to do the bulk of the
         // splitting work.
@@ -306,6 +445,21 @@ impl FlatStorageResharder {
         }
     }
 
+    /// The children of the split whose flat storage is being created, i.e. the ones tracked by
+    /// the node when the split started.
+    fn children_being_created(&self, split_params: &ParentSplitParameters) -> Vec<ShardUId> {
+        let flat_store = self.runtime.store().flat_store();
+        [split_params.left_child_shard, split_params.right_child_shard]
+            .into_iter()
+            .filter(|child_shard| {
+                matches!(
+                    flat_store.get_flat_storage_status(*child_shard),
+                    Ok(FlatStorageStatus::Resharding(FlatStorageReshardingStatus::CreatingChild))
+                )
+            })
+            .collect()
+    }
+
     /// Task to perform the actual split of a flat storage shard. This may be a long operation
     /// time-wise.
     ///
@@ -315,19 +469,19 @@ impl FlatStorageResharder {
         info!(target: "resharding", "flat storage shard split task execution");
 
         // Make sure that the resharding block is final.
-        let resharding_hash = self
-            .resharding_event()
-            .expect("flat storage resharding event must exist!")
-            .resharding_hash();
-        match self.compute_scheduled_task_status(&resharding_hash, chain_store) {
+        match self.compute_event_scheduling_status(chain_store) {
             FlatStorageReshardingTaskSchedulingStatus::CanStart => {
                 info!(target: "resharding", "flat storage shard split task ready to perform bulk processing");
             }
             FlatStorageReshardingTaskSchedulingStatus::Failed => {
-                // It's important to cancel the scheduled event in case of failure.
-                self.cancel_scheduled_event();
+                // It's important to cancel the scheduled event in case of failure. The pending
+                // splits share the resharding blocks of the current one, so they fail as well.
+                for parent_shard in self.cancel_scheduled_event() {
+                    self.update_status(parent_shard, |status| {
+                        status.phase = ReshardingPhase::Failed
+                    });
+                }
                 error!(target: "resharding", "flat storage shard split task failed during scheduling!");
-                // TODO(resharding): return failed only if scheduling of all resharding blocks have failed.
                 return FlatStorageReshardingTaskResult::Failed;
             }
             FlatStorageReshardingTaskSchedulingStatus::Postponed => {
@@ -338,6 +492,10 @@ impl FlatStorageResharder {
 
         #[cfg(feature = "test_features")]
         {
+            let resharding_hash = self
+                .resharding_event()
+                .expect("flat storage resharding event must exist!")
+                .resharding_hash();
             if self.adv_should_delay_task(&resharding_hash, chain_store) {
                 info!(target: "resharding", "flat storage shard split task has been artificially postponed!");
                 return FlatStorageReshardingTaskResult::Postponed;
@@ -353,9 +511,23 @@ impl FlatStorageResharder {
             split_params.left_child_shard,
             split_params.right_child_shard,
         );
+        let resharding_block = split_params.resharding_block;
+        let bytes_to_copy =
+            self.estimate_state_size(chain_store, parent_shard, &resharding_block.hash);
+        self.update_status(parent_shard, |status| {
+            status.start_copy(resharding_block, bytes_to_copy)
+        });
 
-        let task_status = self.split_shard_task_impl(parent_shard, &split_params, &metrics);
-        self.split_shard_task_postprocessing(parent_shard, split_params, &metrics, task_status);
+        let children = self.children_being_created(&split_params);
+        let task_status =
+            self.split_shard_task_impl(parent_shard, &split_params, &children, &metrics);
+        self.split_shard_task_postprocessing(
+            parent_shard,
+            split_params,
+            &children,
+            &metrics,
+            task_status,
+        );
         info!(target: "resharding", ?task_status, "flat storage shard split task finished");
         task_status
     }
@@ -367,6 +539,7 @@ impl FlatStorageResharder {
         &self,
         parent_shard: ShardUId,
         split_params: &ParentSplitParameters,
+        children: &[ShardUId],
         metrics: &FlatStorageReshardingShardSplitMetrics,
     ) -> FlatStorageReshardingTaskResult {
         self.set_resharding_event_execution_status(TaskExecutionStatus::Started);
@@ -376,14 +549,10 @@ impl FlatStorageResharder {
             return FlatStorageReshardingTaskResult::Cancelled;
         }
 
-        // Determines after how many bytes worth of key-values the process stops to commit changes
-        // and to check cancellation.
-        let batch_size = self.resharding_config.get().batch_size.as_u64() as usize;
-        metrics.set_split_shard_batch_size(batch_size);
-        // Delay between every batch.
-        let batch_delay = self.resharding_config.get().batch_delay.unsigned_abs();
+        let mut throttling = self.batch_throttling(None);
+        metrics.set_split_shard_batch_size(throttling.batch_size);
 
-        info!(target: "resharding", ?parent_shard, ?split_params, ?batch_delay, ?batch_size, "flat storage shard split task: starting key-values copy");
+        info!(target: "resharding", ?parent_shard, ?split_params, ?throttling, "flat storage shard split task: starting key-values copy");
 
         // Prepare the store object for commits and the iterator over parent's flat storage.
         let flat_store = self.runtime.store().flat_store();
@@ -409,25 +578,21 @@ impl FlatStorageResharder {
                 "split_shard_task_impl/batch",
                 batch_id = ?num_batches_done)
             .entered();
-            let mut store_update = flat_store.store_update();
+            if num_batches_done > 0 {
+                throttling = self.batch_throttling(Some(throttling));
+                metrics.set_split_shard_batch_size(throttling.batch_size);
+            }
             let mut processed_size = 0;
+            let mut entries = vec![];
 
-            // Process a `batch_size` worth of key value pairs.
-            while processed_size < batch_size && !iter_exhausted {
+            // Read a `batch_size` worth of key value pairs.
+            while processed_size < throttling.batch_size && !iter_exhausted {
                 match iter.next() {
                     // Stop iterating and commit the batch.
                     Some(FlatStorageAndDeltaIterItem::CommitPoint) => break,
                     Some(FlatStorageAndDeltaIterItem::Entry(Ok((key, value)))) => {
                         processed_size += key.len() + value.as_ref().map_or(0, |v| v.size());
-                        if let Err(err) = shard_split_handle_key_value(
-                            key,
-                            value,
-                            &mut store_update,
-                            &split_params,
-                        ) {
-                            error!(target: "resharding", ?err, "failed to handle flat storage key");
-                            return FlatStorageReshardingTaskResult::Failed;
-                        }
+                        entries.push((key, value));
                     }
                     Some(FlatStorageAndDeltaIterItem::Entry(Err(err))) => {
                         error!(target: "resharding", ?err, "failed to read flat storage value from parent shard");
@@ -438,6 +603,38 @@ impl FlatStorageResharder {
                     }
                 }
             }
+            let processed_keys = entries.len() as u64;
+
+            // Write the batch to the children on separate threads, each with its own store
+            // update, and commit them at once.
+            let child_updates = children
+                .par_iter()
+                .map(|child| {
+                    let mut child_update = flat_store.store_update();
+                    for (key, value) in &entries {
+                        shard_split_handle_key_value(
+                            key,
+                            value,
+                            &mut child_update,
+                            &split_params,
+                            std::slice::from_ref(child),
+                        )?;
+                    }
+                    Ok(child_update.into())
+                })
+                .collect::<Result<Vec<StoreUpdate>, Error>>();
+            let mut store_update = flat_store.store_update();
+            match child_updates {
+                Ok(child_updates) => {
+                    for child_update in child_updates {
+                        store_update.store_update().merge(child_update);
+                    }
+                }
+                Err(err) => {
+                    error!(target: "resharding", ?err, "failed to handle flat storage key");
+                    return FlatStorageReshardingTaskResult::Failed;
+                }
+            }
 
             // Make a pause to commit and check if the routine should stop.
             if let Err(err) = store_update.commit() {
@@ -448,18 +645,21 @@ impl FlatStorageResharder {
             num_batches_done += 1;
             metrics.set_split_shard_processed_batches(num_batches_done);
             metrics.inc_split_shard_processed_bytes_by(processed_size);
+            self.update_status(parent_shard, |status| {
+                status.add_copied(processed_keys, processed_size)
+            });
 
             // If `iter`` is exhausted we can exit after the store commit.
             if iter_exhausted {
                 return FlatStorageReshardingTaskResult::Successful { num_batches_done };
             }
-            if self.controller.is_cancelled() {
+            if self.controller.is_cancelled_once_resumed() {
                 return FlatStorageReshardingTaskResult::Cancelled;
             }
 
             // Sleep between batches in order to throttle resharding and leave some resource for the
             // regular node operation.
-            std::thread::sleep(batch_delay);
+            std::thread::sleep(throttling.batch_delay);
         }
     }
 
@@ -475,6 +675,7 @@ impl FlatStorageResharder {
         &self,
         parent_shard: ShardUId,
         split_params: ParentSplitParameters,
+        children: &[ShardUId],
         metrics: &FlatStorageReshardingShardSplitMetrics,
         task_status: FlatStorageReshardingTaskResult,
     ) {
@@ -503,7 +704,7 @@ impl FlatStorageResharder {
                     store_update.remove_flat_storage(parent_shard);
                 }
                 // Children must perform catchup.
-                for child_shard in [left_child_shard, right_child_shard] {
+                for &child_shard in children {
                     store_update.set_flat_storage_status(
                         child_shard,
                         FlatStorageStatus::Resharding(FlatStorageReshardingStatus::CatchingUp(
@@ -539,7 +740,60 @@ impl FlatStorageResharder {
         }
         store_update.commit().unwrap();
         self.remove_resharding_event();
+        self.update_status(parent_shard, |status| {
+            status.phase = task_status.resharding_phase();
+            // Without tracked children there is nothing to catch up.
+            if status.phase == ReshardingPhase::CatchingUp && children.is_empty() {
+                status.phase = ReshardingPhase::Done;
+            }
+        });
         metrics.update_shards_status(&self.runtime.get_flat_storage_manager());
+        // Move on to the split of the next parent shard, if any.
+        self.schedule_pending_split_shard();
+    }
+
+    /// Rough estimate of the size of the state of `shard_uid` at `block_hash`, taken from the
+    /// memory usage recorded in the root of its trie.
+    fn estimate_state_size(
+        &self,
+        chain_store: &ChainStore,
+        shard_uid: ShardUId,
+        block_hash: &CryptoHash,
+    ) -> Option<u64> {
+        let chunk_extra = chain_store.get_chunk_extra(block_hash, &shard_uid).ok()?;
+        let trie =
+            self.runtime.get_tries().get_view_trie_for_shard(shard_uid, *chunk_extra.state_root());
+        trie.retrieve_root_node().ok().map(|root| root.memory_usage)
+    }
+
+    /// Reads the throttling of the background tasks from the resharding config. It is read again
+    /// before every batch, given the `previous` throttling, so that operators can slow down or
+    /// speed up a resharding in progress by reloading the config.
+    fn batch_throttling(&self, previous: Option<BatchThrottling>) -> BatchThrottling {
+        let config = self.resharding_config.get();
+        let throttling = BatchThrottling {
+            batch_size: config.batch_size.as_u64() as usize,
+            batch_delay: config.batch_delay.unsigned_abs(),
+            catch_up_blocks: config.catch_up_blocks,
+        };
+        if previous.is_some_and(|previous| previous != throttling) {
+            info!(target: "resharding", ?previous, ?throttling, "flat storage resharding throttling updated");
+        }
+        throttling
+    }
+
+    /// Updates the persisted [ReshardingStatus] of the resharding event of `shard_uid`, a parent
+    /// or child shard, emitting the event of the phase transition, if any.
+    fn update_status(&self, shard_uid: ShardUId, update: impl FnOnce(&mut ReshardingStatus)) {
+        let mut event = None;
+        update_resharding_status(self.runtime.store(), shard_uid, |status| {
+            let old_phase = status.phase;
+            update(status);
+            event = ReshardingEvent::from_phase_change(old_phase, status);
+        });
+        if let Some(event) = event {
+            self.events.emit(event);
+        }
     }
 
     /// Returns an iterator over a shard's flat storage at the given block hash. This
@@ -621,6 +875,7 @@ impl FlatStorageResharder {
                 (num_batches_done, tip)
             }
             Ok(ShardCatchupApplyDeltasOutcome::Cancelled) => {
+                self.update_status(shard_uid, |status| status.phase = ReshardingPhase::Cancelled);
                 return FlatStorageReshardingTaskResult::Cancelled;
             }
             Ok(ShardCatchupApplyDeltasOutcome::Postponed) => {
@@ -628,6 +883,7 @@ impl FlatStorageResharder {
             }
             Err(err) => {
                 error!(target: "resharding", ?shard_uid, ?err, "flat storage shard catchup delta application failed!");
+                self.update_status(shard_uid, |status| status.phase = ReshardingPhase::Failed);
                 return FlatStorageReshardingTaskResult::Failed;
             }
         };
@@ -635,12 +891,74 @@ impl FlatStorageResharder {
             Ok(_) => {
                 let task_status = FlatStorageReshardingTaskResult::Successful { num_batches_done };
                 info!(target: "resharding", ?shard_uid, ?task_status, "flat storage shard catchup task finished");
+                self.update_status(shard_uid, |status| status.mark_shard_done(shard_uid));
                 // At this point we can trigger the reload of memtries.
                 self.sender.memtrie_reload_sender.send(MemtrieReloadRequest { shard_uid });
                 task_status
             }
             Err(err) => {
                 error!(target: "resharding", ?shard_uid, ?err, "flat storage shard catchup finalize failed!");
+                self.update_status(shard_uid, |status| status.phase = ReshardingPhase::Failed);
+                FlatStorageReshardingTaskResult::Failed
+            }
+        }
+    }
+
+    /// Schedules the validation of the children state of a split, to be performed once the flat
+    /// storage resharding is done.
+    pub fn schedule_split_shard_validation(&self, split_params: ReshardingSplitShardParams) {
+        info!(target: "resharding", ?split_params, "scheduling split shard validation");
+        self.sender
+            .split_shard_validation_sender
+            .send(SplitShardValidationRequest { resharder: self.clone(), split_params });
+    }
+
+    /// Task to validate the children state of a split, see [validate_split_shard]. It is
+    /// postponed until the flat storage resharding is done, and the result is persisted.
+    pub fn split_shard_validation_task(
+        &self,
+        split_params: &ReshardingSplitShardParams,
+        chain_store: &ChainStore,
+    ) -> FlatStorageReshardingTaskResult {
+        if self.controller.is_cancelled() {
+            return FlatStorageReshardingTaskResult::Cancelled;
+        }
+        let store = self.runtime.store();
+        let status = match get_resharding_statuses(store) {
+            Ok(statuses) => match statuses
+                .into_iter()
+                .find(|status| status.parent_shards == [split_params.parent_shard])
+            {
+                Some(status) => status,
+                // Replaced by another resharding.
+                None => return FlatStorageReshardingTaskResult::Cancelled,
+            },
+            Err(err) => {
+                error!(target: "resharding", ?err, "failed to read the resharding status");
+                return FlatStorageReshardingTaskResult::Failed;
+            }
+        };
+        match status.phase {
+            ReshardingPhase::Done => {}
+            ReshardingPhase::Failed | ReshardingPhase::Cancelled => {
+                return FlatStorageReshardingTaskResult::Cancelled;
+            }
+            _ => return FlatStorageReshardingTaskResult::Postponed,
+        }
+
+        // The status holds the resharding block which became final.
+        let result = validate_split_shard(
+            chain_store,
+            &self.runtime.get_tries(),
+            split_params,
+            status.resharding_block,
+            &mut rand::thread_rng(),
+        )
+        .and_then(|result| Ok(save_split_shard_validation(store, &result)?));
+        match result {
+            Ok(()) => FlatStorageReshardingTaskResult::Successful { num_batches_done: 0 },
+            Err(err) => {
+                error!(target: "resharding", ?split_params, ?err, "split shard validation failed");
                 FlatStorageReshardingTaskResult::Failed
             }
         }
@@ -669,12 +987,9 @@ impl FlatStorageResharder {
         chain_store: &ChainStore,
         metrics: &FlatStorageReshardingShardCatchUpMetrics,
     ) -> Result<ShardCatchupApplyDeltasOutcome, Error> {
-        // How many block heights of deltas are applied in a single commit.
-        let catch_up_blocks = self.resharding_config.get().catch_up_blocks;
-        // Delay between every batch.
-        let batch_delay = self.resharding_config.get().batch_delay.unsigned_abs();
+        let mut throttling = self.batch_throttling(None);
 
-        info!(target: "resharding", ?shard_uid, ?batch_delay, ?catch_up_blocks, "flat storage shard catchup: starting delta apply");
+        info!(target: "resharding", ?shard_uid, ?throttling, "flat storage shard catchup: starting delta apply");
 
         let mut num_batches_done: usize = 0;
 
@@ -701,6 +1016,9 @@ impl FlatStorageResharder {
                 ?flat_head,
                 batch_id = ?num_batches_done)
             .entered();
+            if num_batches_done > 0 {
+                throttling = self.batch_throttling(Some(throttling));
+            }
             let chain_final_head = chain_store.final_head()?;
 
             // If we reached the desired new flat head, we can terminate the delta application step.
@@ -717,7 +1035,7 @@ impl FlatStorageResharder {
             let mut postpone = false;
 
             // Merge deltas from the next blocks until we reach chain final head.
-            for _ in 0..catch_up_blocks {
+            for _ in 0..throttling.catch_up_blocks {
                 debug_assert!(
                     flat_head.height <= chain_final_head.height,
                     "flat head: {:?}",
@@ -761,13 +1079,13 @@ impl FlatStorageResharder {
             if postpone {
                 return Ok(ShardCatchupApplyDeltasOutcome::Postponed);
             }
-            if self.controller.is_cancelled() {
+            if self.controller.is_cancelled_once_resumed() {
                 return Ok(ShardCatchupApplyDeltasOutcome::Cancelled);
             }
 
             // Sleep between batches in order to throttle resharding and leave some resource for the
             // regular node operation.
-            std::thread::sleep(batch_delay);
+            std::thread::sleep(throttling.batch_delay);
         }
     }
 
@@ -817,34 +1135,93 @@ impl FlatStorageResharder {
         Ok(())
     }
 
-    /// Cancels the current event, if it exists and it hasn't started yet.
-    fn cancel_scheduled_event(&self) {
+    /// Cancels the current event, if it exists and it hasn't started yet, together with the
+    /// pending splits. Returns the parent shards of the cancelled events.
+    fn cancel_scheduled_event(&self) -> Vec<ShardUId> {
+        let mut cancelled_parent_shards = vec![];
+        let pending_split_shards = std::mem::take(&mut *self.pending_split_shards.lock().unwrap());
+        for pending in pending_split_shards {
+            info!(target: "resharding", ?pending, "cancelling pending flat storage shard split");
+            self.reset_split_shard_status(pending.parent_shard, &pending.split_params);
+            cancelled_parent_shards.push(pending.parent_shard);
+        }
         let Some(current_event) = self.resharding_event() else {
-            return;
+            return cancelled_parent_shards;
         };
         info!(target: "resharding", ?current_event, "cancelling current scheduled resharding event");
         debug_assert!(!current_event.has_started());
         // Clean up the database state.
         match current_event {
             FlatStorageReshardingEventStatus::SplitShard(parent_shard, split_status, ..) => {
-                let flat_store = self.runtime.store().flat_store();
-                let mut store_update = flat_store.store_update();
-                // Parent go back to Ready state.
-                store_update.set_flat_storage_status(
-                    parent_shard,
-                    FlatStorageStatus::Ready(FlatStorageReadyStatus {
-                        flat_head: split_status.flat_head,
-                    }),
-                );
-                // Remove children shards status.
-                for child_shard in [split_status.left_child_shard, split_status.right_child_shard] {
-                    store_update.remove_status(child_shard);
-                }
-                store_update.commit().unwrap();
+                self.reset_split_shard_status(parent_shard, &split_status);
+                cancelled_parent_shards.insert(0, parent_shard);
             }
         }
         // Clean up the resharding event.
         self.remove_resharding_event();
+        cancelled_parent_shards
+    }
+
+    /// Puts back the flat storage status of a parent shard whose split didn't start.
+    fn reset_split_shard_status(
+        &self,
+        parent_shard: ShardUId,
+        split_status: &ParentSplitParameters,
+    ) {
+        let flat_store = self.runtime.store().flat_store();
+        let mut store_update = flat_store.store_update();
+        // Parent go back to Ready state.
+        store_update.set_flat_storage_status(
+            parent_shard,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus { flat_head: split_status.flat_head }),
+        );
+        // Remove children shards status.
+        for child_shard in [split_status.left_child_shard, split_status.right_child_shard] {
+            store_update.remove_status(child_shard);
+        }
+        store_update.commit().unwrap();
+    }
+
+    /// Computes the scheduling status of the current event. If its resharding block ends up in a
+    /// discarded fork, the event is moved to a competing resharding block, so that scheduling
+    /// fails only if all the resharding blocks end up in discarded forks.
+    fn compute_event_scheduling_status(
+        &self,
+        chain_store: &ChainStore,
+    ) -> FlatStorageReshardingTaskSchedulingStatus {
+        let resharding_hash = self
+            .resharding_event()
+            .expect("flat storage resharding event must exist!")
+            .resharding_hash();
+        let status = self.compute_scheduled_task_status(&resharding_hash, chain_store);
+        if !matches!(status, FlatStorageReshardingTaskSchedulingStatus::Failed) {
+            return status;
+        }
+
+        let competing_resharding_blocks =
+            std::mem::take(&mut *self.competing_resharding_blocks.lock().unwrap());
+        let mut pending_resharding_blocks = vec![];
+        for resharding_block in competing_resharding_blocks {
+            match self.compute_scheduled_task_status(&resharding_block.hash, chain_store) {
+                FlatStorageReshardingTaskSchedulingStatus::CanStart => {
+                    info!(target: "resharding", ?resharding_block, "moving flat storage resharding event to the final resharding block");
+                    self.set_resharding_block(resharding_block);
+                    return FlatStorageReshardingTaskSchedulingStatus::CanStart;
+                }
+                FlatStorageReshardingTaskSchedulingStatus::Postponed => {
+                    pending_resharding_blocks.push(resharding_block);
+                }
+                FlatStorageReshardingTaskSchedulingStatus::Failed => {}
+            }
+        }
+        // Wait for one of the resharding blocks which are not final yet, keeping the others as
+        // competing blocks.
+        let Some((resharding_block, others)) = pending_resharding_blocks.split_first() else {
+            return FlatStorageReshardingTaskSchedulingStatus::Failed;
+        };
+        self.set_resharding_block(*resharding_block);
+        self.competing_resharding_blocks.lock().unwrap().extend_from_slice(others);
+        FlatStorageReshardingTaskSchedulingStatus::Postponed
     }
 
     /// Computes the scheduling status of a task waiting to be started. The task will be ready to
@@ -891,6 +1268,26 @@ impl FlatStorageResharder {
 
     fn remove_resharding_event(&self) {
         *self.resharding_event.lock().unwrap() = None;
+        self.competing_resharding_blocks.lock().unwrap().clear();
+    }
+
+    /// Moves the current event, which hasn't started yet, to another resharding block.
+    fn set_resharding_block(&self, resharding_block: BlockInfo) {
+        let mut event = self.resharding_event.lock().unwrap();
+        match event.as_mut() {
+            Some(FlatStorageReshardingEventStatus::SplitShard(parent_shard, split_params, ..)) => {
+                split_params.resharding_block = resharding_block;
+                let mut store_update = self.runtime.store().flat_store().store_update();
+                store_update.set_flat_storage_status(
+                    *parent_shard,
+                    FlatStorageStatus::Resharding(FlatStorageReshardingStatus::SplittingParent(
+                        split_params.clone(),
+                    )),
+                );
+                store_update.commit().unwrap();
+            }
+            None => {}
+        }
     }
 
     #[cfg(feature = "test_features")]
@@ -931,6 +1328,7 @@ impl Debug for FlatStorageResharder {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("FlatStorageResharder")
             .field("event", &self.resharding_event())
+            .field("pending_split_shards", &self.pending_split_shards.lock().unwrap())
             .field("controller", &self.controller)
             .finish()
     }
@@ -950,12 +1348,14 @@ fn retrieve_shard_flat_head(shard: ShardUId, store: &FlatStoreAdapter) -> Result
     }
 }
 
-/// Handles the inheritance of a key-value pair from parent shard to children shards.
+/// Handles the inheritance of a key-value pair from parent shard to children shards. Only the
+/// given `children` are written to.
 fn shard_split_handle_key_value(
-    key: Vec<u8>,
-    value: Option<FlatStateValue>,
+    key: &[u8],
+    value: &Option<FlatStateValue>,
     store_update: &mut FlatStoreUpdateAdapter,
     split_params: &ParentSplitParameters,
+    children: &[ShardUId],
 ) -> Result<(), Error> {
     if key.is_empty() {
         panic!("flat storage key is empty!")
@@ -965,6 +1365,7 @@ fn shard_split_handle_key_value(
     match key_column_prefix {
         col::ACCOUNT => copy_kv_to_child(
             &split_params,
+            children,
             key,
             value,
             store_update,
@@ -972,6 +1373,7 @@ fn shard_split_handle_key_value(
         )?,
         col::CONTRACT_DATA => copy_kv_to_child(
             &split_params,
+            children,
             key,
             value,
             store_update,
@@ -979,6 +1381,7 @@ fn shard_split_handle_key_value(
         )?,
         col::CONTRACT_CODE => copy_kv_to_child(
             &split_params,
+            children,
             key,
             value,
             store_update,
@@ -986,6 +1389,7 @@ fn shard_split_handle_key_value(
         )?,
         col::ACCESS_KEY => copy_kv_to_child(
             &split_params,
+            children,
             key,
             value,
             store_update,
@@ -993,6 +1397,7 @@ fn shard_split_handle_key_value(
         )?,
         col::RECEIVED_DATA => copy_kv_to_child(
             &split_params,
+            children,
             key,
             value,
             store_update,
@@ -1001,26 +1406,31 @@ fn shard_split_handle_key_value(
         col::POSTPONED_RECEIPT_ID
         | col::PENDING_DATA_COUNT
         | col::POSTPONED_RECEIPT
-        | col::PROMISE_YIELD_RECEIPT => {
-            copy_kv_to_child(&split_params, key, value, store_update, |raw_key: &[u8]| {
+        | col::PROMISE_YIELD_RECEIPT => copy_kv_to_child(
+            &split_params,
+            children,
+            key,
+            value,
+            store_update,
+            |raw_key: &[u8]| {
                 parse_account_id_from_trie_key_with_separator(
                     key_column_prefix,
                     raw_key,
                     &format!("col at index {}", key_column_prefix),
                 )
-            })?
-        }
+            },
+        )?,
         col::DELAYED_RECEIPT_OR_INDICES
         | col::PROMISE_YIELD_INDICES
         | col::PROMISE_YIELD_TIMEOUT
         | col::BANDWIDTH_SCHEDULER_STATE => {
-            copy_kv_to_all_children(&split_params, key, value, store_update)
+            copy_kv_to_all_children(&split_params, children, key, value, store_update)
         }
         col::BUFFERED_RECEIPT_INDICES
         | col::BUFFERED_RECEIPT
         | col::BUFFERED_RECEIPT_GROUPS_QUEUE_DATA
         | col::BUFFERED_RECEIPT_GROUPS_QUEUE_ITEM => {
-            copy_kv_to_left_child(&split_params, key, value, store_update)
+            copy_kv_to_left_child(&split_params, children, key, value, store_update)
         }
         _ => unreachable!("key: {:?} should not appear in flat store!", key),
     }
@@ -1030,15 +1440,16 @@ fn shard_split_handle_key_value(
 /// Copies a key-value pair to the correct child shard by matching the account-id to the provided shard layout.
 fn copy_kv_to_child(
     split_params: &ParentSplitParameters,
-    key: Vec<u8>,
-    value: Option<FlatStateValue>,
+    children: &[ShardUId],
+    key: &[u8],
+    value: &Option<FlatStateValue>,
     store_update: &mut FlatStoreUpdateAdapter,
     account_id_parser: impl FnOnce(&[u8]) -> Result<AccountId, std::io::Error>,
 ) -> Result<(), Error> {
     let ParentSplitParameters { left_child_shard, right_child_shard, shard_layout, .. } =
         &split_params;
     // Derive the shard uid for this account in the new shard layout.
-    let account_id = account_id_parser(&key)?;
+    let account_id = account_id_parser(key)?;
     let new_shard_id = shard_layout.account_id_to_shard_id(&account_id);
     let new_shard_uid = ShardUId::from_shard_id_and_layout(new_shard_id, &shard_layout);
 
@@ -1056,29 +1467,38 @@ fn copy_kv_to_child(
         return Ok(());
     }
     // Add the new flat store entry.
-    store_update.set(new_shard_uid, key, value);
+    if children.contains(&new_shard_uid) {
+        store_update.set(new_shard_uid, key.to_vec(), value.clone());
+    }
     Ok(())
 }
 
 /// Copies a key-value pair to both children.
 fn copy_kv_to_all_children(
     split_params: &ParentSplitParameters,
-    key: Vec<u8>,
-    value: Option<FlatStateValue>,
+    children: &[ShardUId],
+    key: &[u8],
+    value: &Option<FlatStateValue>,
     store_update: &mut FlatStoreUpdateAdapter,
 ) {
-    store_update.set(split_params.left_child_shard, key.clone(), value.clone());
-    store_update.set(split_params.right_child_shard, key, value);
+    for child_shard in [split_params.left_child_shard, split_params.right_child_shard] {
+        if children.contains(&child_shard) {
+            store_update.set(child_shard, key.to_vec(), value.clone());
+        }
+    }
 }
 
 /// Copies a key-value pair to the child on the left of the account boundary (also called 'first child').
 fn copy_kv_to_left_child(
     split_params: &ParentSplitParameters,
-    key: Vec<u8>,
-    value: Option<FlatStateValue>,
+    children: &[ShardUId],
+    key: &[u8],
+    value: &Option<FlatStateValue>,
     store_update: &mut FlatStoreUpdateAdapter,
 ) {
-    store_update.set(split_params.left_child_shard, key, value);
+    if children.contains(&split_params.left_child_shard) {
+        store_update.set(split_params.left_child_shard, key.to_vec(), value.clone());
+    }
 }
 
 /// Returns `true` if a flat head at `flat_head_block_hash` has reached the necessary height to be
@@ -1134,6 +1554,16 @@ impl FlatStorageReshardingEventStatus {
     }
 }
 
+/// Split of a parent shard waiting for the current event to finish, see
+/// [FlatStorageResharder::schedule_split_shard].
+#[derive(Clone, Debug)]
+struct PendingSplitShard {
+    parent_shard: ShardUId,
+    split_params: ParentSplitParameters,
+    /// Resharding blocks of competing forks on which the split happened as well.
+    competing_resharding_blocks: Vec<BlockInfo>,
+}
+
 /// All different states of task execution for [FlatStorageReshardingEventStatus].
 #[derive(Clone, Debug, Copy, Eq, PartialEq)]
 pub enum TaskExecutionStatus {
@@ -1141,6 +1571,19 @@ pub enum TaskExecutionStatus {
     NotStarted,
 }
 
+/// Throttling of the batches of the flat storage resharding tasks, see
+/// [FlatStorageResharder::batch_throttling].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct BatchThrottling {
+    /// Determines after how many bytes worth of key-values the copy stops to commit changes and
+    /// to check cancellation.
+    batch_size: usize,
+    /// Delay between every batch.
+    batch_delay: std::time::Duration,
+    /// How many block heights of deltas are applied in a single commit during catchup.
+    catch_up_blocks: BlockHeightDelta,
+}
+
 /// Result of a schedulable flat storage resharding task.
 #[derive(Clone, Debug, Copy, Eq, PartialEq)]
 pub enum FlatStorageReshardingTaskResult {
@@ -1150,6 +1593,18 @@ pub enum FlatStorageReshardingTaskResult {
     Postponed,
 }
 
+impl FlatStorageReshardingTaskResult {
+    /// Phase of the resharding after the copy of key-values finished with this result.
+    fn resharding_phase(&self) -> ReshardingPhase {
+        match self {
+            Self::Successful { .. } => ReshardingPhase::CatchingUp,
+            Self::Failed => ReshardingPhase::Failed,
+            Self::Cancelled => ReshardingPhase::Cancelled,
+            Self::Postponed => ReshardingPhase::Scheduled,
+        }
+    }
+}
+
 /// Status of scheduling of a flat storage resharding tasks.
 /// It is useful to know whether or not a task can start or has to be delayed.
 enum FlatStorageReshardingTaskSchedulingStatus {
@@ -1192,6 +1647,18 @@ impl FlatStorageResharderController {
     pub fn is_cancelled(&self) -> bool {
         !self.handle.get()
     }
+
+    /// Blocks while resharding is paused by the operator, then returns whether or not
+    /// background task is cancelled. Called between batches, so that a paused task holds no
+    /// resources besides its thread.
+    pub fn is_cancelled_once_resumed(&self) -> bool {
+        if self.handle.state() == ReshardingHandleState::Paused {
+            info!(target: "resharding", "flat storage resharding paused");
+            let keep_going = self.handle.wait_while_paused();
+            info!(target: "resharding", keep_going, "flat storage resharding no longer paused");
+        }
+        !self.handle.get()
+    }
 }
 
 #[cfg(test)]
@@ -1199,7 +1666,7 @@ mod tests {
     use std::collections::BTreeMap;
 
     use near_async::time::Clock;
-    use near_chain_configs::{Genesis, MutableConfigValue};
+    use near_chain_configs::{Genesis, MutableConfigValue, ReshardingHandleState};
     use near_epoch_manager::{
         shard_tracker::{ShardTracker, TrackedConfig},
         EpochManager,
@@ -1222,6 +1689,7 @@ mod tests {
         test_utils::create_test_store,
     };
 
+    use crate::resharding::status::set_resharding_status;
     use crate::{
         rayon_spawner::RayonAsyncComputationSpawner, runtime::NightshadeRuntime,
         types::ChainConfig, Chain, ChainGenesis, DoomslugThresholdMode,
@@ -1229,7 +1697,7 @@ mod tests {
 
     use super::*;
     use assert_matches::assert_matches;
-    use more_asserts::assert_gt;
+    use more_asserts::{assert_gt, assert_lt};
     use near_async::messaging::{CanSend, IntoMultiSender};
     use near_crypto::{KeyType, PublicKey};
 
@@ -1244,6 +1712,7 @@ mod tests {
         CanSend<FlatStorageSplitShardRequest>
         + CanSend<FlatStorageShardCatchupRequest>
         + CanSend<MemtrieReloadRequest>
+        + CanSend<SplitShardValidationRequest>
     {
         fn new(chain_store: ChainStore) -> Self;
     }
@@ -1275,6 +1744,10 @@ mod tests {
         fn send(&self, _: MemtrieReloadRequest) {}
     }
 
+    impl CanSend<SplitShardValidationRequest> for SimpleSender {
+        fn send(&self, _: SplitShardValidationRequest) {}
+    }
+
     /// A sender that doesn't execute tasks immediately. Tasks execution must be invoked
     /// manually.
     struct DelayedSender {
@@ -1297,8 +1770,10 @@ mod tests {
 
     impl DelayedSender {
         fn call_split_shard_task(&self) -> FlatStorageReshardingTaskResult {
-            let request = self.split_shard_request.lock().unwrap();
-            request.as_ref().unwrap().resharder.split_shard_task(&self.chain_store.lock().unwrap())
+            // The task may send the request of the next split, so the lock must be released.
+            let resharder =
+                self.split_shard_request.lock().unwrap().as_ref().unwrap().resharder.clone();
+            resharder.split_shard_task(&self.chain_store.lock().unwrap())
         }
 
         fn call_shard_catchup_tasks(&self) -> Vec<FlatStorageReshardingTaskResult> {
@@ -1343,6 +1818,10 @@ mod tests {
         }
     }
 
+    impl CanSend<SplitShardValidationRequest> for DelayedSender {
+        fn send(&self, _: SplitShardValidationRequest) {}
+    }
+
     /// Simple shard layout with two shards.
     fn simple_shard_layout() -> ShardLayout {
         let s0 = ShardId::new(0);
@@ -1366,6 +1845,23 @@ mod tests {
         )
     }
 
+    /// Derived from [simple_shard_layout] by splitting both shards.
+    fn shard_layout_after_double_split() -> ShardLayout {
+        let s0 = ShardId::new(0);
+        let s1 = ShardId::new(1);
+        let s2 = ShardId::new(2);
+        let s3 = ShardId::new(3);
+        let s4 = ShardId::new(4);
+        let s5 = ShardId::new(5);
+
+        let shards_split_map = BTreeMap::from([(s0, vec![s2, s3]), (s1, vec![s4, s5])]);
+        ShardLayout::v2(
+            vec![account!("cc"), account!("ff"), account!("pp")],
+            vec![s2, s3, s4, s5],
+            Some(shards_split_map),
+        )
+    }
+
     /// Generic test setup. It creates an instance of chain, a FlatStorageResharder and a sender.
     fn create_chain_resharder_sender<T: TestSender>(
         shard_layout: ShardLayout,
@@ -1416,6 +1912,11 @@ mod tests {
         (chain, resharder, sender)
     }
 
+    /// All the shards of the layout, to have the node track all the children of a split.
+    fn all_shards(shard_layout: &ShardLayout) -> Vec<ShardUId> {
+        shard_layout.shard_uids().collect()
+    }
+
     /// Utility function to derive the resharding event type from chain and shard layout.
     fn event_type_from_chain_and_layout(
         chain: &Chain,
@@ -1423,6 +1924,8 @@ mod tests {
     ) -> ReshardingEventType {
         ReshardingEventType::from_shard_layout(&new_shard_layout, chain.head().unwrap().into())
             .unwrap()
+            .into_iter()
+            .exactly_one()
             .unwrap()
     }
 
@@ -1475,7 +1978,11 @@ mod tests {
         let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
 
         assert!(resharder
-            .start_resharding(resharding_event_type.clone(), &new_shard_layout)
+            .start_resharding(
+                resharding_event_type.clone(),
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
             .is_ok());
 
         // Immediately cancel the resharding and call the resharding task.
@@ -1489,7 +1996,13 @@ mod tests {
         resharder.split_shard_task_impl(parent_shard, &split_params, &metrics);
 
         assert!(resharder.resharding_event().is_some());
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_err());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_err());
     }
 
     /// Flat storage shard status should be set correctly upon starting a shard split.
@@ -1502,7 +2015,13 @@ mod tests {
         let flat_store = resharder.runtime.store().flat_store();
         let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
 
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
 
         let resharding_event = resharder.resharding_event();
         match resharding_event.unwrap() {
@@ -1617,7 +2136,13 @@ mod tests {
         );
 
         // Perform resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check final status of parent flat storage.
@@ -1682,6 +2207,142 @@ mod tests {
         }
     }
 
+    /// Only the flat storage of the children tracked by the node is created by a split.
+    #[test]
+    fn split_shard_only_tracked_child() {
+        init_test_logger();
+        let (mut chain, resharder, sender) =
+            create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+        let new_shard_layout = shard_layout_after_split();
+        let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+        let left_child = ShardUId { version: 3, shard_id: 2 };
+        let right_child = ShardUId { version: 3, shard_id: 3 };
+        let flat_store = resharder.runtime.store().flat_store();
+
+        // Add two blocks on top of genesis. This will make the resharding block (height 0) final.
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::ChainHead,
+            NextBlockHeight::ChainHeadPlusOne,
+        );
+
+        // Perform resharding, tracking only the right child.
+        assert!(resharder
+            .start_resharding(resharding_event_type, &new_shard_layout, &[right_child])
+            .is_ok());
+        assert_eq!(flat_store.get_flat_storage_status(left_child), Ok(FlatStorageStatus::Empty));
+        assert_matches!(
+            sender.call_split_shard_task(),
+            FlatStorageReshardingTaskResult::Successful { .. }
+        );
+        sender.call_shard_catchup_tasks();
+
+        // The parent is gone and only the right child has a flat storage.
+        let parent = ShardUId { version: 3, shard_id: 1 };
+        assert_eq!(flat_store.get_flat_storage_status(parent), Ok(FlatStorageStatus::Empty));
+        assert_eq!(flat_store.iter(parent).count(), 0);
+        assert_eq!(flat_store.get_flat_storage_status(left_child), Ok(FlatStorageStatus::Empty));
+        assert_eq!(flat_store.iter(left_child).count(), 0);
+        assert_matches!(
+            flat_store.get_flat_storage_status(right_child),
+            Ok(FlatStorageStatus::Ready(_))
+        );
+        let account_vv_key = TrieKey::Account { account_id: account!("vv") };
+        assert!(flat_store
+            .get(right_child, &account_vv_key.to_vec())
+            .is_ok_and(|val| val.is_some()));
+        assert_eq!(sender.memtrie_reload_requests(), vec![right_child]);
+    }
+
+    /// The splits of several shards in the same shard layout change are performed one after the
+    /// other, each with its own status.
+    #[test]
+    fn split_multiple_shards() {
+        init_test_logger();
+        let (mut chain, resharder, sender) =
+            create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+        let new_shard_layout = shard_layout_after_double_split();
+        let resharding_event_types =
+            ReshardingEventType::from_shard_layout(&new_shard_layout, chain.head().unwrap().into())
+                .unwrap();
+        assert_eq!(resharding_event_types.len(), 2);
+        let store = resharder.runtime.store().clone();
+        let flat_store = store.flat_store();
+        for resharding_event_type in &resharding_event_types {
+            let ReshardingEventType::SplitShard(split_params) = resharding_event_type;
+            let status = ReshardingStatus::new(
+                split_params.resharding_block,
+                vec![split_params.parent_shard],
+                split_params.children_shards(),
+            );
+            set_resharding_status(&store, &status).unwrap();
+        }
+
+        // Add two blocks on top of genesis. This will make the resharding block (height 0) final.
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::ChainHead,
+            NextBlockHeight::ChainHeadPlusOne,
+        );
+
+        // The split of the second parent waits for the first one.
+        for resharding_event_type in resharding_event_types {
+            assert!(resharder
+                .start_resharding(
+                    resharding_event_type,
+                    &new_shard_layout,
+                    &all_shards(&new_shard_layout)
+                )
+                .is_ok());
+        }
+        let first_parent = ShardUId { version: 3, shard_id: 0 };
+        let second_parent = ShardUId { version: 3, shard_id: 1 };
+        for parent in [first_parent, second_parent] {
+            assert_matches!(
+                flat_store.get_flat_storage_status(parent),
+                Ok(FlatStorageStatus::Resharding(FlatStorageReshardingStatus::SplittingParent(_)))
+            );
+        }
+        assert_matches!(
+            resharder.resharding_event(),
+            Some(FlatStorageReshardingEventStatus::SplitShard(parent, ..)) if parent == first_parent
+        );
+
+        assert_matches!(
+            sender.call_split_shard_task(),
+            FlatStorageReshardingTaskResult::Successful { .. }
+        );
+        assert_matches!(
+            resharder.resharding_event(),
+            Some(FlatStorageReshardingEventStatus::SplitShard(parent, ..)) if parent == second_parent
+        );
+        assert_matches!(
+            sender.call_split_shard_task(),
+            FlatStorageReshardingTaskResult::Successful { .. }
+        );
+        assert!(resharder.resharding_event().is_none());
+
+        // Catchup all the children.
+        sender.call_shard_catchup_tasks();
+        for parent in [first_parent, second_parent] {
+            assert_eq!(flat_store.get_flat_storage_status(parent), Ok(FlatStorageStatus::Empty));
+        }
+        for child in new_shard_layout.shard_uids() {
+            assert_matches!(
+                flat_store.get_flat_storage_status(child),
+                Ok(FlatStorageStatus::Ready(_))
+            );
+        }
+        let statuses = get_resharding_statuses(&store).unwrap();
+        assert_eq!(statuses.len(), 2);
+        for status in statuses {
+            assert_eq!(status.phase, ReshardingPhase::Done);
+            assert_gt!(status.keys_copied, 0);
+        }
+    }
+
     /// Split shard task should run in batches.
     #[test]
     fn split_shard_batching() {
@@ -1697,7 +2358,13 @@ mod tests {
         resharder.resharding_config.update(config);
 
         // Perform resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
 
         // Check that more than one batch has been processed.
         let FlatStorageReshardingTaskResult::Successful { num_batches_done } =
@@ -1709,6 +2376,196 @@ mod tests {
         assert_gt!(num_batches_done, 1);
     }
 
+    /// The children are written in parallel, batch by batch. The split must produce the same
+    /// children flat storages whatever the number of batches.
+    #[test]
+    fn split_shard_batches_write_same_children() {
+        init_test_logger();
+        let run_split = |batch_size: bytesize::ByteSize| {
+            let (chain, resharder, sender) =
+                create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+            let new_shard_layout = shard_layout_after_split();
+            let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+            let mut config = resharder.resharding_config.get();
+            config.batch_size = batch_size;
+            resharder.resharding_config.update(config);
+
+            assert!(resharder
+                .start_resharding(
+                    resharding_event_type,
+                    &new_shard_layout,
+                    &all_shards(&new_shard_layout)
+                )
+                .is_ok());
+            assert_matches!(
+                sender.call_split_shard_task(),
+                FlatStorageReshardingTaskResult::Successful { .. }
+            );
+            let flat_store = resharder.runtime.store().flat_store();
+            [ShardUId { version: 3, shard_id: 2 }, ShardUId { version: 3, shard_id: 3 }]
+                .map(|child| flat_store.iter(child).map(|entry| entry.unwrap()).collect_vec())
+        };
+
+        let [left_batched, right_batched] = run_split(bytesize::ByteSize(1));
+        let [left, right] = run_split(bytesize::ByteSize::mb(100));
+        assert!(!left.is_empty());
+        assert!(!right.is_empty());
+        assert_eq!(left_batched, left);
+        assert_eq!(right_batched, right);
+    }
+
+    /// A paused split shard task should block between batches until resumed.
+    #[test]
+    fn pause_and_resume_split_shard() {
+        init_test_logger();
+        let (chain, resharder, sender) =
+            create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+        let new_shard_layout = shard_layout_after_split();
+        let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+
+        // Make smaller batches, so that the task goes through pause points.
+        let mut config = resharder.resharding_config.get();
+        config.batch_size = bytesize::ByteSize(1);
+        resharder.resharding_config.update(config);
+
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
+        resharder.controller.handle.pause();
+        std::thread::scope(|scope| {
+            let task = scope.spawn(|| sender.call_split_shard_task());
+            // The task blocks after committing its first batch.
+            resharder.controller.handle.wait_for_paused_tasks(1);
+            assert!(!task.is_finished());
+
+            assert_eq!(resharder.controller.handle.resume(), ReshardingHandleState::Running);
+            let FlatStorageReshardingTaskResult::Successful { num_batches_done } =
+                task.join().unwrap()
+            else {
+                panic!("split shard task must succeed once resumed");
+            };
+            assert_gt!(num_batches_done, 1);
+        });
+    }
+
+    /// Changes of the throttling config should apply to the split shard task in progress, from
+    /// the next batch.
+    #[test]
+    fn split_shard_rereads_throttling_config() {
+        init_test_logger();
+        let run_split = |reload_batch_size: Option<bytesize::ByteSize>| {
+            let (chain, resharder, sender) =
+                create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+            let new_shard_layout = shard_layout_after_split();
+            let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+            let mut config = resharder.resharding_config.get();
+            config.batch_size = bytesize::ByteSize(1);
+            resharder.resharding_config.update(config.clone());
+
+            assert!(resharder
+                .start_resharding(
+                    resharding_event_type,
+                    &new_shard_layout,
+                    &all_shards(&new_shard_layout)
+                )
+                .is_ok());
+            // Block the task after its first batch, while the config is reloaded.
+            resharder.controller.handle.pause();
+            std::thread::scope(|scope| {
+                let task = scope.spawn(|| sender.call_split_shard_task());
+                resharder.controller.handle.wait_for_paused_tasks(1);
+                if let Some(batch_size) = reload_batch_size {
+                    config.batch_size = batch_size;
+                    resharder.resharding_config.update(config);
+                }
+                resharder.controller.handle.resume();
+                let FlatStorageReshardingTaskResult::Successful { num_batches_done } =
+                    task.join().unwrap()
+                else {
+                    panic!("split shard task must succeed");
+                };
+                num_batches_done
+            })
+        };
+
+        let num_batches_unchanged = run_split(None);
+        let num_batches_reloaded = run_split(Some(bytesize::ByteSize::mb(100)));
+        assert_gt!(num_batches_unchanged, 2);
+        assert_lt!(num_batches_reloaded, num_batches_unchanged);
+    }
+
+    /// The persisted resharding status should follow the progress of the split.
+    #[test]
+    fn split_shard_updates_resharding_status() {
+        init_test_logger();
+        let (mut chain, resharder, sender) =
+            create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+        let new_shard_layout = shard_layout_after_split();
+        let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+        let ReshardingEventType::SplitShard(split_params) = &resharding_event_type;
+        let store = resharder.runtime.store().clone();
+        let get_resharding_status =
+            || get_resharding_statuses(&store).unwrap().into_iter().exactly_one().unwrap();
+
+        // The status is created by the resharding manager when the resharding block is processed.
+        let status = ReshardingStatus::new(
+            split_params.resharding_block,
+            vec![split_params.parent_shard],
+            split_params.children_shards(),
+        );
+        set_resharding_status(&store, &status).unwrap();
+        let mut events = resharder.events.subscribe();
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::ChainHead,
+            NextBlockHeight::ChainHeadPlusOne,
+        );
+
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
+        let status = get_resharding_status();
+        assert_eq!(status.phase, ReshardingPhase::Scheduled);
+
+        sender.call_split_shard_task();
+        let status = get_resharding_status();
+        assert_eq!(status.phase, ReshardingPhase::CatchingUp);
+        assert_gt!(status.keys_copied, 0);
+        assert_gt!(status.bytes_copied, 0);
+        assert!(status.bytes_to_copy.is_some());
+        assert!(status.shards_done.is_empty());
+
+        sender.call_shard_catchup_tasks();
+        let status = get_resharding_status();
+        assert_eq!(status.phase, ReshardingPhase::Done);
+        assert_eq!(status.shards_done.len(), 2);
+
+        // Each phase transition is announced to the subscribers.
+        let children_shards = split_params.children_shards();
+        let resharding_block = status.resharding_block;
+        assert_eq!(
+            events.try_recv().unwrap(),
+            ReshardingEvent::FlatStorageCopied {
+                resharding_block,
+                children_shards: children_shards.clone()
+            }
+        );
+        assert_eq!(
+            events.try_recv().unwrap(),
+            ReshardingEvent::Finished { resharding_block, children_shards }
+        );
+        assert!(events.try_recv().is_err());
+    }
+
     #[test]
     fn cancel_split_shard() {
         init_test_logger();
@@ -1718,7 +2575,13 @@ mod tests {
         let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
 
         // Perform resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         let (parent_shard, split_params) = resharder.get_parent_shard_and_split_params().unwrap();
         let ParentSplitParameters { left_child_shard, right_child_shard, flat_head, .. } =
             split_params;
@@ -1753,7 +2616,13 @@ mod tests {
         let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
 
         // Perform resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         let (_, split_params) = resharder.get_parent_shard_and_split_params().unwrap();
         let ParentSplitParameters { left_child_shard, right_child_shard, .. } = split_params;
 
@@ -1801,7 +2670,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Trigger resharding and it should fail.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_err());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_err());
     }
 
     /// Verify the correctness of a shard split in the presence of flat storage deltas in the parent
@@ -1961,7 +2836,13 @@ mod tests {
             .unwrap();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         assert_eq!(
             sender.call_split_shard_task(),
             FlatStorageReshardingTaskResult::Successful { num_batches_done: 3 }
@@ -2084,7 +2965,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check each child has the correct keys assigned to itself.
@@ -2131,7 +3018,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check that flat storages of both children contain the delayed receipt.
@@ -2206,7 +3099,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check that flat storages of both children contain the promise yield timeout and indices.
@@ -2271,7 +3170,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check that only the first child contain the buffered receipt.
@@ -2306,7 +3211,13 @@ mod tests {
         let manager = chain.runtime_adapter.get_flat_storage_manager();
 
         // Do resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
 
         // Trigger the task to perform the parent split.
         sender.call_split_shard_task();
@@ -2510,7 +3421,13 @@ mod tests {
         let ReshardingSplitShardParams { parent_shard, .. } = match resharding_event_type.clone() {
             ReshardingEventType::SplitShard(params) => params,
         };
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         assert_eq!(sender.call_split_shard_task(), FlatStorageReshardingTaskResult::Postponed);
         assert_gt!(flat_store.iter(parent_shard).count(), 0);
 
@@ -2556,7 +3473,13 @@ mod tests {
         let ReshardingSplitShardParams { parent_shard, .. } = match resharding_event_type.clone() {
             ReshardingEventType::SplitShard(params) => params,
         };
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         assert_eq!(sender.call_split_shard_task(), FlatStorageReshardingTaskResult::Postponed);
         assert_gt!(flat_store.iter(parent_shard).count(), 0);
 
@@ -2573,7 +3496,13 @@ mod tests {
         let ReshardingSplitShardParams { parent_shard, .. } = match resharding_event_type.clone() {
             ReshardingEventType::SplitShard(params) => params,
         };
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         assert_eq!(sender.call_split_shard_task(), FlatStorageReshardingTaskResult::Postponed);
         assert_gt!(flat_store.iter(parent_shard).count(), 0);
 
@@ -2594,6 +3523,64 @@ mod tests {
         assert_eq!(flat_store.iter(parent_shard).count(), 0);
     }
 
+    /// Test to verify that the same resharding event happening on the resharding block of a
+    /// competing fork doesn't replace the scheduled event if the latter's resharding block is the
+    /// one becoming final.
+    #[test]
+    fn competing_resharding_block_does_not_replace_scheduled_event() {
+        init_test_logger();
+        let (mut chain, resharder, sender) =
+            create_chain_resharder_sender::<DelayedSender>(simple_shard_layout());
+        let new_shard_layout = shard_layout_after_split();
+        let flat_store = resharder.runtime.store().flat_store();
+
+        // Add two blocks on top of genesis and take the resharding event at block 2.
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::ChainHead,
+            NextBlockHeight::ChainHeadPlusOne,
+        );
+        let losing_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+
+        // Add two blocks on top of the first block (simulate a fork) and take the resharding
+        // event at the new head.
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::Fixed(1),
+            NextBlockHeight::Fixed(3),
+        );
+        let winning_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
+        let ReshardingSplitShardParams { parent_shard, resharding_block, .. } =
+            match winning_event_type.clone() {
+                ReshardingEventType::SplitShard(params) => params,
+            };
+
+        // Trigger resharding on the winning fork first, then on the losing one.
+        assert!(resharder
+            .start_resharding(winning_event_type, &new_shard_layout, &all_shards(&new_shard_layout))
+            .is_ok());
+        assert!(resharder
+            .start_resharding(losing_event_type, &new_shard_layout, &all_shards(&new_shard_layout))
+            .is_ok());
+        assert_eq!(resharder.resharding_event().unwrap().resharding_hash(), resharding_block.hash);
+        assert_eq!(sender.call_split_shard_task(), FlatStorageReshardingTaskResult::Postponed);
+
+        // Make the resharding block of the winning fork final.
+        add_blocks_to_chain(
+            &mut chain,
+            2,
+            PreviousBlockHeight::ChainHead,
+            NextBlockHeight::ChainHeadPlusOne,
+        );
+        assert_matches!(
+            sender.call_split_shard_task(),
+            FlatStorageReshardingTaskResult::Successful { .. }
+        );
+        assert_eq!(flat_store.iter(parent_shard).count(), 0);
+    }
+
     /// In this test we make sure that after a task whose scheduling has failed the cleanup logic is
     /// executed correctly.
     #[test]
@@ -2613,7 +3600,13 @@ mod tests {
 
         // Trigger resharding at block 2.
         let resharding_event_type = event_type_from_chain_and_layout(&chain, &new_shard_layout);
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         let (parent_shard, split_params) = resharder.get_parent_shard_and_split_params().unwrap();
         let ParentSplitParameters { flat_head, .. } = split_params;
         assert_eq!(sender.call_split_shard_task(), FlatStorageReshardingTaskResult::Postponed);
@@ -2669,7 +3662,13 @@ mod tests {
         store_update.commit().unwrap();
 
         // Perform resharding.
-        assert!(resharder.start_resharding(resharding_event_type, &new_shard_layout).is_ok());
+        assert!(resharder
+            .start_resharding(
+                resharding_event_type,
+                &new_shard_layout,
+                &all_shards(&new_shard_layout)
+            )
+            .is_ok());
         sender.call_split_shard_task();
 
         // Check final status of parent flat storage.
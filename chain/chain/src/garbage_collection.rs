@@ -2,18 +2,26 @@ use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use std::{fmt, io};
 
+use borsh::BorshDeserialize;
 use near_chain_configs::GCConfig;
 use near_chain_primitives::Error;
+use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::Block;
+use near_primitives::epoch_info::EpochInfo;
+use near_primitives::epoch_manager::AGGREGATOR_KEY;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::get_block_shard_uid;
 use near_primitives::state_sync::{StateHeaderKey, StatePartKey};
-use near_primitives::types::{BlockHeight, BlockHeightDelta, EpochId, NumBlocks, ShardId};
+use near_primitives::types::{
+    AccountId, BlockHeight, BlockHeightDelta, EpochHeight, EpochId, NumBlocks, ShardId,
+};
 use near_primitives::utils::{get_block_shard_id, get_outcome_id_block_hash, index_to_bytes};
 use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
+use near_store::flat::FlatStorageStatus;
 use near_store::{DBCol, KeyForStateChanges, ShardTries, ShardUId};
 
+use crate::store::{get_account_index_key, get_block_timestamp_key};
 use crate::types::RuntimeAdapter;
 use crate::{metrics, Chain, ChainStore, ChainStoreAccess, ChainStoreUpdate};
 
@@ -255,6 +263,180 @@ impl ChainStore {
         chain_store_update.commit()
     }
 
+    /// Prunes EpochInfo, EpochStart and EpochValidatorInfo of old epochs, as configured by
+    /// `GCConfig::epoch_data_num_epochs_to_keep`.
+    ///
+    /// Besides the configured number of epochs, this always keeps the genesis epoch, the
+    /// epochs of the blocks which were not garbage collected yet and the epochs with height
+    /// of at least `min_epoch_height_to_keep`, which the caller needs e.g. to derive epoch
+    /// sync proofs. Must not be used on archival nodes, as these columns are not copied to
+    /// the cold storage.
+    pub fn clear_epoch_data(
+        &mut self,
+        gc_config: &GCConfig,
+        epoch_manager: &dyn EpochManagerAdapter,
+        min_epoch_height_to_keep: EpochHeight,
+    ) -> Result<(), Error> {
+        let Some(num_epochs_to_keep) = gc_config.epoch_data_num_epochs_to_keep else {
+            return Ok(());
+        };
+        let _span =
+            tracing::debug_span!(target: "garbage_collection", "clear_epoch_data").entered();
+        let num_epochs_to_keep = num_epochs_to_keep.max(gc_config.gc_num_epochs_to_keep());
+
+        let head = self.head()?;
+        let head_epoch_height = epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height();
+        let tail_hash = self.get_block_hash_by_height(self.tail()?)?;
+        let tail_epoch_id = epoch_manager.get_epoch_id(&tail_hash)?;
+        let tail_epoch_height = epoch_manager.get_epoch_info(&tail_epoch_id)?.epoch_height();
+        let floor_epoch_height = head_epoch_height
+            .saturating_sub(num_epochs_to_keep)
+            .min(tail_epoch_height)
+            .min(min_epoch_height_to_keep);
+        metrics::EPOCH_DATA_GC_FLOOR_EPOCH_HEIGHT.set(floor_epoch_height as i64);
+
+        let store = self.store();
+        let mut store_update = store.store_update();
+        let mut num_pruned_epochs = 0;
+        let mut reclaimed_bytes = 0;
+        for item in store.iter(DBCol::EpochInfo) {
+            let (key, value) = item?;
+            if key.as_ref() == AGGREGATOR_KEY || key.as_ref() == EpochId::default().as_ref() {
+                continue;
+            }
+            let epoch_info = EpochInfo::try_from_slice(&value)?;
+            if epoch_info.epoch_height() >= floor_epoch_height {
+                continue;
+            }
+            reclaimed_bytes += key.len() + value.len();
+            for col in [DBCol::EpochStart, DBCol::EpochValidatorInfo] {
+                if let Some(value) = store.get(col, &key)? {
+                    reclaimed_bytes += key.len() + value.len();
+                }
+                store_update.delete(col, &key);
+            }
+            store_update.delete(DBCol::EpochInfo, &key);
+            num_pruned_epochs += 1;
+        }
+        store_update.commit()?;
+
+        tracing::debug!(target: "garbage_collection", floor_epoch_height, num_pruned_epochs, reclaimed_bytes, "Pruned epoch data");
+        metrics::EPOCH_DATA_GC_PRUNED_EPOCHS.inc_by(num_pruned_epochs);
+        metrics::EPOCH_DATA_GC_RECLAIMED_BYTES.inc_by(reclaimed_bytes as u64);
+        Ok(())
+    }
+
+    /// Removes the data left by the shards which are not part of the shard layout anymore,
+    /// i.e. the parents of resharding: the State stored with their `ShardUId` prefix, the
+    /// `ShardUId` mapping of their children and the leftovers of their flat storage.
+    ///
+    /// Only runs once the blocks of the previous shard layouts are garbage collected, the flat
+    /// storage resharding is finished and no shard is being caught up or state synced. The
+    /// children of a split keep reading and writing the State of the parent through the
+    /// mapping, so the State of a parent is only removed once none of its children is tracked
+    /// by `shard_tracker` in this or the next epoch, and none of them has flat storage data
+    /// left. Must not be used on archival nodes without split storage, as the State is not
+    /// copied to the cold storage.
+    ///
+    /// Returns false if the data could not be checked yet and the call should be retried.
+    pub fn clear_resharding_data(
+        &mut self,
+        tries: &ShardTries,
+        epoch_manager: &dyn EpochManagerAdapter,
+        shard_tracker: &ShardTracker,
+        me: Option<&AccountId>,
+    ) -> Result<bool, Error> {
+        let _span =
+            tracing::debug_span!(target: "garbage_collection", "clear_resharding_data").entered();
+        let head = self.head()?;
+        let shard_layout = epoch_manager.get_shard_layout(&head.epoch_id)?;
+        let tail_hash = self.get_block_hash_by_height(self.tail()?)?;
+        let tail_epoch_id = epoch_manager.get_epoch_id(&tail_hash)?;
+        if epoch_manager.get_shard_layout(&tail_epoch_id)? != shard_layout {
+            // Blocks of the previous shard layout can still be read.
+            return Ok(false);
+        }
+        if epoch_manager.get_shard_layout(&head.next_epoch_id)? != shard_layout {
+            // The shards of the next epoch are about to be created from the current ones.
+            return Ok(false);
+        }
+        let store = self.store();
+        if !self.iterate_state_sync_infos()?.is_empty()
+            || store.iter(DBCol::BlocksToCatchup).next().is_some()
+        {
+            // The State of the shards being caught up or state synced is incomplete, and may be
+            // written through the mapping of their parent.
+            return Ok(false);
+        }
+        let current_shards: HashSet<ShardUId> = shard_layout.shard_uids().collect();
+
+        // Any shard which is not current but has flat storage data is a candidate for removal.
+        // The current shards with flat storage data may still use the State of their parent.
+        let mut shards_with_flat_storage = HashSet::new();
+        let mut removed_shards = HashSet::new();
+        for item in store.iter(DBCol::FlatStorageStatus) {
+            let (key, value) = item?;
+            let shard_uid = ShardUId::try_from_slice(&key)?;
+            let status = FlatStorageStatus::try_from_slice(&value)?;
+            if matches!(status, FlatStorageStatus::Resharding(_)) {
+                // The flat storage resharding still reads the parent.
+                return Ok(false);
+            }
+            if !current_shards.contains(&shard_uid) {
+                removed_shards.insert(shard_uid);
+            } else if status != FlatStorageStatus::Empty {
+                shards_with_flat_storage.insert(shard_uid);
+            }
+        }
+        for item in store.iter(DBCol::FlatStateDeltaMetadata) {
+            let (key, _) = item?;
+            let shard_uid = ShardUId::try_from_slice(&key[..8])?;
+            if !current_shards.contains(&shard_uid) {
+                removed_shards.insert(shard_uid);
+            }
+        }
+
+        // The State of removed shards is stored with the prefix of their own `ShardUId` or,
+        // for the children of a split, of their ancestor's.
+        let mut mapped_shards: HashMap<ShardUId, Vec<ShardUId>> = HashMap::new();
+        for item in store.iter(DBCol::StateShardUIdMapping) {
+            let (key, value) = item?;
+            let child_shard_uid = ShardUId::try_from_slice(&key)?;
+            let prefix_shard_uid = ShardUId::try_from_slice(&value)?;
+            mapped_shards.entry(prefix_shard_uid).or_default().push(child_shard_uid);
+        }
+        removed_shards
+            .extend(mapped_shards.keys().filter(|shard_uid| !current_shards.contains(shard_uid)));
+
+        let is_used = |shard_uid: &ShardUId| {
+            let shard_id = shard_uid.shard_id();
+            shards_with_flat_storage.contains(shard_uid)
+                || shard_tracker.care_about_shard(me, &head.last_block_hash, shard_id, true)
+                || shard_tracker.will_care_about_shard(me, &head.last_block_hash, shard_id, true)
+        };
+        let mut store_update = store.store_update();
+        let mut num_cleared_shards = 0;
+        for shard_uid in removed_shards {
+            let children = mapped_shards.get(&shard_uid).map(Vec::as_slice).unwrap_or_default();
+            if let Some(used_child) = children.iter().find(|child| is_used(child)) {
+                tracing::debug!(target: "garbage_collection", ?shard_uid, ?used_child, "Keeping State of removed shard used by a current shard");
+                continue;
+            }
+            tracing::info!(target: "garbage_collection", ?shard_uid, ?children, "Clearing data of removed shard");
+            tries.unload_mem_trie(&shard_uid);
+            let mut trie_store_update = store_update.trie_store_update();
+            trie_store_update.delete_shard_uid_prefixed_state(shard_uid);
+            for child_shard_uid in children {
+                trie_store_update.delete_shard_uid_mapping(*child_shard_uid);
+            }
+            store_update.flat_store_update().remove_flat_storage(shard_uid);
+            num_cleared_shards += 1;
+        }
+        store_update.commit()?;
+        metrics::RESHARDING_DATA_GC_CLEARED_SHARDS.inc_by(num_cleared_shards);
+        Ok(true)
+    }
+
     fn clear_forks_data(
         &mut self,
         tries: ShardTries,
@@ -644,6 +826,12 @@ impl<'a> ChainStoreUpdate<'a> {
             self.gc_col(DBCol::StateChanges, &key);
         }
         self.gc_col(DBCol::BlockRefCount, block_hash.as_bytes());
+        if self.chain_store().secondary_indexes().blocks_by_timestamp {
+            self.gc_col(
+                DBCol::BlocksByTimestamp,
+                &get_block_timestamp_key(block.header().raw_timestamp(), &block_hash),
+            );
+        }
         self.gc_outcomes(&block)?;
         match gc_mode {
             GCMode::StateSync { clear_block_info: false } => {}
@@ -863,6 +1051,11 @@ impl<'a> ChainStoreUpdate<'a> {
             let outcome_ids =
                 self.chain_store().get_outcomes_by_block_hash_and_shard_id(block_hash, shard_id)?;
             for outcome_id in outcome_ids {
+                if self.chain_store().save_account_receipts_enabled()
+                    || self.chain_store().secondary_indexes().transactions_by_account
+                {
+                    self.gc_outcome_indexes(block, &outcome_id)?;
+                }
                 self.gc_col(
                     DBCol::TransactionResultForBlock,
                     &get_outcome_id_block_hash(&outcome_id, block_hash),
@@ -874,6 +1067,35 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
+    /// Removes the entries added for the outcome to `DBCol::AccountReceipts`, see
+    /// `ChainStoreUpdate::save_account_receipts`, and to the secondary indexes by account,
+    /// see `ChainStoreUpdate::save_secondary_indexes`. Deleting the keys which were never
+    /// added, e.g. the incoming entry of a transaction, is a no-op.
+    fn gc_outcome_indexes(&mut self, block: &Block, outcome_id: &CryptoHash) -> Result<(), Error> {
+        let Some(outcome_with_proof) =
+            self.chain_store().get_outcome_by_id_and_block_hash(outcome_id, block.hash())?
+        else {
+            return Ok(());
+        };
+        let executor_id = &outcome_with_proof.outcome.executor_id;
+        let height = block.header().height();
+        let outcome_key = get_account_index_key(executor_id, height, outcome_id);
+        if self.chain_store().save_account_receipts_enabled() {
+            self.gc_col(DBCol::AccountReceipts, &outcome_key);
+            for receipt_id in &outcome_with_proof.outcome.receipt_ids {
+                self.gc_col(
+                    DBCol::AccountReceipts,
+                    &get_account_index_key(executor_id, height, receipt_id),
+                );
+            }
+        }
+        let secondary_indexes = self.chain_store().secondary_indexes();
+        if secondary_indexes.transactions_by_account {
+            self.gc_col(DBCol::TransactionsByAccount, &outcome_key);
+        }
+        Ok(())
+    }
+
     fn gc_col(&mut self, col: DBCol, key: &[u8]) {
         let mut store_update = self.store().store_update();
         match col {
@@ -991,12 +1213,22 @@ impl<'a> ChainStoreUpdate<'a> {
             DBCol::StateSyncNewChunks => {
                 store_update.delete(col, key);
             }
+            DBCol::AccountReceipts => {
+                store_update.delete(col, key);
+            }
+            DBCol::TransactionsByAccount => {
+                store_update.delete(col, key);
+            }
+            DBCol::BlocksByTimestamp => {
+                store_update.delete(col, key);
+            }
             DBCol::DbVersion
             | DBCol::BlockMisc
             | DBCol::_GCCount
             | DBCol::BlockHeight  // block sync needs it + genesis should be accessible
             | DBCol::_Peers
             | DBCol::RecentOutboundConnections
+            | DBCol::ConnectionAuditLog
             | DBCol::BlockMerkleTree
             | DBCol::AccountAnnouncements
             | DBCol::EpochLightClientBlocks
@@ -1023,9 +1255,15 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::Misc
             | DBCol::_ReceiptIdToShardId
             | DBCol::StateShardUIdMapping
+            // Needed by archival nodes to resolve the State of past blocks.
+            | DBCol::StateShardUIdMappingHistory
             // Note that StateSyncHashes should not ever have too many keys in them
             // because we remove unneeded keys as we add new ones.
             | DBCol::StateSyncHashes
+            // AccessKeyUsage only keeps the latest use of every key.
+            | DBCol::AccessKeyUsage
+            // TrieRecorderSpill is cleaned up by the resharding which wrote it.
+            | DBCol::TrieRecorderSpill
             => unreachable!(),
         }
         self.merge(store_update);
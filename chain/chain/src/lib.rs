@@ -1,5 +1,6 @@
 #![cfg_attr(enable_const_type_id, feature(const_type_id))]
 
+pub use apply_chunk_durations::ApplyChunkDurations;
 pub use block_processing_utils::BlockProcessingArtifact;
 pub use chain::{check_known, collect_receipts, Chain};
 pub use chain_update::ChainUpdate;
@@ -8,18 +9,21 @@ pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_
 pub use near_chain_primitives::{self, Error};
 pub use near_primitives::receipt::ReceiptResult;
 pub use store::{
-    ChainStore, ChainStoreAccess, ChainStoreUpdate, LatestWitnessesInfo, MerkleProofAccess,
-    ReceiptFilter,
+    AccountIndexScan, AccountReceiptIndexEntry, BlocksByTimestampBackfill, ChainStore,
+    ChainStoreAccess, ChainStoreUpdate, LatestWitnessesInfo, MerkleProofAccess, ReceiptFilter,
+    SecondaryIndexEntry, MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES,
 };
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, LatestKnown, Provenance};
 
+mod apply_chunk_durations;
 mod approval_verification;
 mod block_processing_utils;
 pub mod blocks_delay_tracker;
 pub mod chain;
 mod chain_update;
 pub mod crypto_hash_timer;
+mod delayed_receipts_ages;
 mod doomslug;
 pub mod flat_storage_init;
 pub mod flat_storage_resharder;
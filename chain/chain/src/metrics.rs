@@ -1,8 +1,10 @@
 use near_o11y::metrics::{
     exponential_buckets, processing_time_buckets, try_create_histogram, try_create_histogram_vec,
-    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_gauge,
-    try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter, IntGauge, IntGaugeVec,
+    try_create_histogram_with_buckets, try_create_int_counter, try_create_int_counter_vec,
+    try_create_int_gauge, try_create_int_gauge_vec, Histogram, HistogramVec, IntCounter,
+    IntCounterVec, IntGauge, IntGaugeVec,
 };
+use near_primitives::utils::compression::DecodeError;
 use std::sync::LazyLock;
 
 pub static BLOCK_PROCESSING_ATTEMPTS_TOTAL: LazyLock<IntCounter> = LazyLock::new(|| {
@@ -74,6 +76,34 @@ pub static FORK_TAIL_HEIGHT: LazyLock<IntGauge> =
     LazyLock::new(|| try_create_int_gauge("near_fork_tail_height", "Height of fork tail").unwrap());
 pub static GC_STOP_HEIGHT: LazyLock<IntGauge> =
     LazyLock::new(|| try_create_int_gauge("near_gc_stop_height", "Target height of gc").unwrap());
+pub static EPOCH_DATA_GC_FLOOR_EPOCH_HEIGHT: LazyLock<IntGauge> = LazyLock::new(|| {
+    try_create_int_gauge(
+        "near_epoch_data_gc_floor_epoch_height",
+        "Lowest epoch height whose epoch data is kept by gc",
+    )
+    .unwrap()
+});
+pub static EPOCH_DATA_GC_PRUNED_EPOCHS: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_epoch_data_gc_pruned_epochs_total",
+        "Number of epochs whose EpochInfo, EpochStart and EpochValidatorInfo were pruned by gc",
+    )
+    .unwrap()
+});
+pub static EPOCH_DATA_GC_RECLAIMED_BYTES: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_epoch_data_gc_reclaimed_bytes_total",
+        "Total size of the keys and values of the epoch data pruned by gc",
+    )
+    .unwrap()
+});
+pub static RESHARDING_DATA_GC_CLEARED_SHARDS: LazyLock<IntCounter> = LazyLock::new(|| {
+    try_create_int_counter(
+        "near_resharding_data_gc_cleared_shards_total",
+        "Number of shards removed by resharding whose State and flat storage were cleared by gc",
+    )
+    .unwrap()
+});
 pub static CHUNK_RECEIVED_DELAY: LazyLock<HistogramVec> = LazyLock::new(|| {
     try_create_histogram_vec(
         "near_chunk_receive_delay_seconds",
@@ -172,3 +202,121 @@ pub(crate) static SHARD_LAYOUT_NUM_SHARDS: LazyLock<IntGauge> = LazyLock::new(||
     )
     .unwrap()
 });
+
+pub(crate) static RESHARDING_PHASE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_phase",
+        "Phase of the resharding events of the latest resharding, by first parent shard, set to 1 for the current phase and 0 for the others",
+        &["parent_shard", "phase"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_SHARDS_DONE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_shards_done",
+        "Number of children shards of the resharding events of the latest resharding whose flat storage is ready, by first parent shard",
+        &["parent_shard"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_KEYS_COPIED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_keys_copied",
+        "Number of key-values copied to the children flat storages in the latest resharding, by first parent shard",
+        &["parent_shard"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_BYTES_COPIED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_bytes_copied",
+        "Size of the key-values copied to the children flat storages in the latest resharding, by first parent shard",
+        &["parent_shard"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_ETA_SECONDS: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_eta_seconds",
+        "Estimated time left to copy the key-values in the latest resharding, by first parent shard, -1 if unknown",
+        &["parent_shard"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_VALIDATION_KEYS_CHECKED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_validation_keys_checked",
+        "Number of keys sampled by the validation of the latest shard splits, by parent shard and source",
+        &["parent_shard", "source"],
+    )
+    .unwrap()
+});
+
+pub(crate) static RESHARDING_VALIDATION_MISMATCHES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_resharding_validation_mismatches",
+        "Number of sampled keys for which the children state of the latest shard splits is wrong, by parent shard",
+        &["parent_shard"],
+    )
+    .unwrap()
+});
+
+pub(crate) static DELAYED_RECEIPTS_QUEUE_LEN: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_delayed_receipts_queue_len",
+        "Number of receipts in the delayed receipts queue of a shard after the last applied chunk.",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static DELAYED_RECEIPTS_QUEUE_OLDEST_AGE_BLOCKS: LazyLock<IntGaugeVec> = LazyLock::new(
+    || {
+        try_create_int_gauge_vec(
+            "near_delayed_receipts_queue_oldest_age_blocks",
+            "Number of blocks the oldest receipt in the delayed receipts queue of a shard has been waiting for. Receipts delayed before the node started count from the first block the node processed.",
+            &["shard_id"],
+        )
+        .unwrap()
+    },
+);
+
+static COMPRESSED_DATA_COMPRESSION_RATIO: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_compressed_data_compression_ratio",
+        "Ratio of the borsh-serialized size to the compressed size of the encoded data",
+        &["type"],
+        Some(exponential_buckets(1.0, 1.5, 12).unwrap()),
+    )
+    .unwrap()
+});
+
+static COMPRESSED_DATA_DECODE_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_compressed_data_decode_failures_total",
+        "Number of compressed data which failed to decode",
+        &["type", "reason"],
+    )
+    .unwrap()
+});
+
+/// Records the compression ratio of data encoded as the `CompressedData` named `name`.
+pub fn record_compression_ratio(name: &str, uncompressed_size: usize, compressed_size: usize) {
+    if compressed_size > 0 {
+        COMPRESSED_DATA_COMPRESSION_RATIO
+            .with_label_values(&[name])
+            .observe(uncompressed_size as f64 / compressed_size as f64);
+    }
+}
+
+/// Records the failure to decode the `CompressedData` named `name`, if `err` is one.
+pub fn record_decode_failure(name: &str, err: &std::io::Error) {
+    if let Some(reason) = DecodeError::reason(err) {
+        COMPRESSED_DATA_DECODE_FAILURES.with_label_values(&[name, reason]).inc();
+    }
+}
@@ -1,4 +1,6 @@
 use near_async::futures::AsyncComputationSpawner;
+use near_chain_configs::ThreadPoolConfig;
+use std::sync::Arc;
 
 pub struct RayonAsyncComputationSpawner;
 
@@ -8,3 +10,48 @@ impl AsyncComputationSpawner for RayonAsyncComputationSpawner {
         rayon::spawn(move || tracing::dispatcher::with_default(&dispatcher, f))
     }
 }
+
+/// Spawns the computations on a dedicated rayon thread pool rather than on the global one.
+pub struct RayonThreadPoolAsyncComputationSpawner {
+    pool: rayon::ThreadPool,
+}
+
+impl RayonThreadPoolAsyncComputationSpawner {
+    /// Creates the thread pool `name` of the given size, with its threads pinned to the CPUs of
+    /// the given set if any.
+    pub fn new(
+        name: &'static str,
+        config: &ThreadPoolConfig,
+    ) -> Result<Self, rayon::ThreadPoolBuildError> {
+        let mut builder = rayon::ThreadPoolBuilder::new()
+            .thread_name(move |index| format!("{name}-{index}"))
+            // Same as for the global pool, see neard's main.
+            .stack_size(8 * 1024 * 1024)
+            .num_threads(config.num_threads.unwrap_or(0));
+        if let Some(cpu_set) = config.cpu_set.clone() {
+            builder = builder.start_handler(move |_| {
+                near_performance_metrics::affinity::pin_current_thread(name, &cpu_set)
+            });
+        }
+        Ok(Self { pool: builder.build()? })
+    }
+
+    /// Returns the spawner to use for the thread pool `name`, which is the global rayon pool
+    /// unless the pool is configured.
+    pub fn from_config(
+        name: &'static str,
+        config: &ThreadPoolConfig,
+    ) -> Result<Arc<dyn AsyncComputationSpawner>, rayon::ThreadPoolBuildError> {
+        if *config == ThreadPoolConfig::default() {
+            return Ok(Arc::new(RayonAsyncComputationSpawner));
+        }
+        Ok(Arc::new(Self::new(name, config)?))
+    }
+}
+
+impl AsyncComputationSpawner for RayonThreadPoolAsyncComputationSpawner {
+    fn spawn_boxed(&self, _name: &str, f: Box<dyn FnOnce() + Send>) {
+        let dispatcher = tracing::dispatcher::get_default(|it| it.clone());
+        self.pool.spawn(move || tracing::dispatcher::with_default(&dispatcher, f))
+    }
+}
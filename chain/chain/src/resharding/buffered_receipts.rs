@@ -0,0 +1,129 @@
+//! Accounting of the buffered receipts of the shards involved in a split.
+//!
+//! The congestion info of the children of a split is derived from the [ReceiptGroupsQueue]s of
+//! the parent, see `ReshardingManager::get_child_congestion_info`. Dumping the queues of the
+//! parent and of the children at the resharding block, next to their congestion info, shows
+//! where the buffered receipts gas and bytes went when the congestion info of a child doesn't
+//! add up. It is exposed through the `/debug/api/resharding_buffered_receipts` page.
+
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{ShardId, StateRoot};
+use near_store::trie::outgoing_metadata::ReceiptGroupsQueue;
+use near_store::trie::receipts_column_helper::ShardsOutgoingReceiptBuffer;
+use near_store::{ShardUId, StorageError, TrieAccess};
+
+/// Buffered receipts of a shard to one target shard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetShardBufferedReceipts {
+    pub target_shard: ShardId,
+    /// Number of receipts in the outgoing buffer, according to its indices.
+    pub buffer_len: Option<u64>,
+    /// Number, gas and size of the receipts according to the receipt groups queue.
+    pub num_receipts: u64,
+    pub gas: u128,
+    pub bytes: u64,
+}
+
+/// Buffered receipts of a shard at some state root, by target shard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ShardBufferedReceipts {
+    pub shard_uid: ShardUId,
+    pub state_root: StateRoot,
+    /// Gas of the buffered receipts according to the congestion info of the shard.
+    pub congestion_info_buffered_gas: Option<u128>,
+    /// Size of the delayed and buffered receipts according to the congestion info of the shard.
+    pub congestion_info_receipt_bytes: Option<u64>,
+    /// Only the target shards with a receipt groups queue are listed.
+    pub target_shards: Vec<TargetShardBufferedReceipts>,
+}
+
+/// Buffered receipts of the parent shard of a split and of its children at the resharding
+/// block. Shards whose state isn't available, e.g. untracked children, are omitted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitBufferedReceipts {
+    pub parent: Option<ShardBufferedReceipts>,
+    pub children: Vec<ShardBufferedReceipts>,
+}
+
+/// Reads the receipt groups queues of `shard_uid` to the given target shards.
+pub fn get_shard_buffered_receipts(
+    trie: &dyn TrieAccess,
+    shard_uid: ShardUId,
+    chunk_extra: &ChunkExtra,
+    target_shards: impl IntoIterator<Item = ShardId>,
+) -> Result<ShardBufferedReceipts, StorageError> {
+    let outgoing_buffers = ShardsOutgoingReceiptBuffer::load(trie)?;
+    let mut result = vec![];
+    for target_shard in target_shards {
+        let Some(receipt_groups) = ReceiptGroupsQueue::load(trie, target_shard)? else {
+            continue;
+        };
+        result.push(TargetShardBufferedReceipts {
+            target_shard,
+            buffer_len: outgoing_buffers.buffer_len(target_shard),
+            num_receipts: receipt_groups.total_receipts_num(),
+            gas: receipt_groups.total_gas(),
+            bytes: receipt_groups.total_size(),
+        });
+    }
+    let congestion_info = chunk_extra.congestion_info();
+    Ok(ShardBufferedReceipts {
+        shard_uid,
+        state_root: *chunk_extra.state_root(),
+        congestion_info_buffered_gas: congestion_info.map(|info| info.buffered_receipts_gas()),
+        congestion_info_receipt_bytes: congestion_info.map(|info| info.receipt_bytes()),
+        target_shards: result,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use bytesize::ByteSize;
+    use near_primitives::congestion_info::CongestionInfo;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::shard_layout::ShardLayout;
+    use near_primitives::types::chunk_extra::ChunkExtra;
+    use near_store::test_utils::TestTriesBuilder;
+    use near_store::trie::outgoing_metadata::{ReceiptGroupsConfig, ReceiptGroupsQueue};
+    use near_store::{Trie, TrieUpdate};
+
+    use super::{get_shard_buffered_receipts, TargetShardBufferedReceipts};
+
+    #[test]
+    fn test_get_shard_buffered_receipts() {
+        let shard_layout = ShardLayout::multi_shard(3, 1);
+        let shard_uid = shard_layout.shard_uids().next().unwrap();
+        let tries = TestTriesBuilder::new().with_shard_layout(shard_layout.clone()).build();
+        let mut trie_update =
+            TrieUpdate::new(tries.get_trie_for_shard(shard_uid, Trie::EMPTY_ROOT));
+
+        let config = ReceiptGroupsConfig::default_config();
+        let target_shard = shard_layout.shard_ids().nth(1).unwrap();
+        let mut queue = ReceiptGroupsQueue::new(target_shard);
+        queue.update_on_receipt_pushed(ByteSize::b(100), 10, &mut trie_update, &config).unwrap();
+        queue.update_on_receipt_pushed(ByteSize::b(200), 20, &mut trie_update, &config).unwrap();
+
+        let chunk_extra = ChunkExtra::new_with_only_state_root(&CryptoHash::default());
+        let buffered = get_shard_buffered_receipts(
+            &trie_update,
+            shard_uid,
+            &chunk_extra,
+            shard_layout.shard_ids(),
+        )
+        .unwrap();
+        assert_eq!(
+            buffered.target_shards,
+            vec![TargetShardBufferedReceipts {
+                target_shard,
+                buffer_len: None,
+                num_receipts: 2,
+                gas: 30,
+                bytes: 300,
+            }]
+        );
+        assert_eq!(
+            buffered.congestion_info_buffered_gas,
+            Some(CongestionInfo::default().buffered_receipts_gas())
+        );
+    }
+}
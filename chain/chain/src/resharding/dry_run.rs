@@ -0,0 +1,205 @@
+//! Dry run of a shard split, to evaluate a candidate boundary account.
+//!
+//! [estimate_split_shard] retains both sides of the parent trie around the boundary account
+//! without committing anything, and scans the parent flat storage to measure the state each
+//! child would hold. Everything is read-only, so it can run against the database of a stopped
+//! node, see `neard database resharding-dry-run`. The parent memtrie is used if it is loaded,
+//! like during the actual split, otherwise the on-disk trie is.
+
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+
+use near_chain_configs::ReshardingConfig;
+use near_primitives::errors::StorageError;
+use near_primitives::trie_key::col;
+use near_primitives::types::{AccountId, StateRoot};
+use near_store::adapter::StoreAdapter;
+use near_store::trie::mem::mem_trie_update::TrackingMode;
+use near_store::trie::ops::resharding::{is_key_retained_after_split, RetainMode};
+use near_store::trie::TrieRecorder;
+use near_store::{ShardTries, ShardUId};
+
+use crate::Error;
+
+/// What a child of the split would look like.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ChildShardEstimate {
+    /// State root of the child, as computed at the resharding block.
+    pub state_root: StateRoot,
+    /// Number of flat storage entries of the parent kept by the child.
+    pub num_keys: u64,
+    /// Total size of the keys and values of these entries, in bytes.
+    pub state_size: u64,
+    /// Number of accounts kept by the child.
+    pub num_accounts: u64,
+    /// Size of the trie nodes read to retain the child, which are part of the state witness of
+    /// the resharding block.
+    pub proof_size: usize,
+}
+
+/// Result of [estimate_split_shard].
+#[derive(Debug, Clone)]
+pub struct SplitShardEstimate {
+    pub parent_shard_uid: ShardUId,
+    pub boundary_account: AccountId,
+    pub left_child: ChildShardEstimate,
+    pub right_child: ChildShardEstimate,
+    /// Time taken to retain both children in the parent trie, which is done while processing
+    /// the resharding block.
+    pub retain_duration: Duration,
+    /// Time taken to read the whole parent flat storage.
+    pub flat_state_scan_duration: Duration,
+    /// Estimate of the total duration of the split: the retain, plus the background split of
+    /// the flat storage, which reads the whole parent flat storage in batches of
+    /// `batch_size` separated by `batch_delay`. The writes to the children flat storages are
+    /// not accounted for, so this is a lower bound.
+    pub estimated_split_duration: Duration,
+}
+
+/// Simulates the split of the given parent state at `boundary_account`, see the module
+/// documentation. The flat storage is read at its head, which may be a few blocks behind
+/// `parent_state_root`.
+pub fn estimate_split_shard(
+    tries: &ShardTries,
+    parent_shard_uid: ShardUId,
+    parent_state_root: StateRoot,
+    boundary_account: &AccountId,
+    resharding_config: &ReshardingConfig,
+) -> Result<SplitShardEstimate, Error> {
+    let _span = tracing::debug_span!(
+        target: "resharding", "estimate_split_shard", ?parent_shard_uid, %boundary_account)
+    .entered();
+
+    let retain_start = Instant::now();
+    let mut left_child = retain_child(
+        tries,
+        parent_shard_uid,
+        parent_state_root,
+        boundary_account,
+        RetainMode::Left,
+    )?;
+    let mut right_child = retain_child(
+        tries,
+        parent_shard_uid,
+        parent_state_root,
+        boundary_account,
+        RetainMode::Right,
+    )?;
+    let retain_duration = retain_start.elapsed();
+
+    let scan_start = Instant::now();
+    let flat_store = tries.store().flat_store();
+    let mut processed_size = 0;
+    for item in flat_store.iter(parent_shard_uid) {
+        let (key, value) = item.map_err(StorageError::from)?;
+        // Same accounting of the processed size as the flat storage resharder.
+        processed_size += key.len() + value.size();
+        let child = if is_key_retained_after_split(&key, boundary_account, RetainMode::Left) {
+            &mut left_child
+        } else {
+            &mut right_child
+        };
+        child.num_keys += 1;
+        child.state_size += (key.len() + value.value_len()) as u64;
+        if key.first() == Some(&col::ACCOUNT) {
+            child.num_accounts += 1;
+        }
+    }
+    let flat_state_scan_duration = scan_start.elapsed();
+
+    let batch_size = (resharding_config.batch_size.as_u64() as usize).max(1);
+    let num_batches = processed_size.div_ceil(batch_size) as u32;
+    let batch_delay = resharding_config.batch_delay.unsigned_abs();
+    let estimated_split_duration =
+        retain_duration + flat_state_scan_duration + batch_delay * num_batches;
+
+    Ok(SplitShardEstimate {
+        parent_shard_uid,
+        boundary_account: boundary_account.clone(),
+        left_child,
+        right_child,
+        retain_duration,
+        flat_state_scan_duration,
+        estimated_split_duration,
+    })
+}
+
+/// Retains one side of the parent trie without applying the changes.
+fn retain_child(
+    tries: &ShardTries,
+    parent_shard_uid: ShardUId,
+    parent_state_root: StateRoot,
+    boundary_account: &AccountId,
+    retain_mode: RetainMode,
+) -> Result<ChildShardEstimate, Error> {
+    let mut trie_recorder = TrieRecorder::new(None);
+    let trie_changes = if let Some(mem_tries) = tries.get_mem_tries(parent_shard_uid) {
+        let mem_tries = mem_tries.read().unwrap();
+        let mode = TrackingMode::RefcountsAndAccesses(&mut trie_recorder);
+        let mem_trie_update = mem_tries.update(parent_state_root, mode)?;
+        mem_trie_update.retain_split_shard(boundary_account, retain_mode)
+    } else {
+        let parent_trie = tries
+            .get_trie_for_shard(parent_shard_uid, parent_state_root)
+            .recording_reads_with_recorder(RefCell::new(trie_recorder));
+        let trie_changes = parent_trie.retain_split_shard(boundary_account, retain_mode)?;
+        trie_recorder = parent_trie.take_recorder().unwrap().into_inner();
+        trie_changes
+    };
+    Ok(ChildShardEstimate {
+        state_root: trie_changes.new_root,
+        proof_size: trie_recorder.recorded_storage_size(),
+        ..Default::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use near_chain_configs::ReshardingConfig;
+    use near_primitives::state::FlatStateValue;
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+    use near_store::adapter::StoreAdapter;
+    use near_store::test_utils::{create_test_store, test_populate_trie, TestTriesBuilder};
+    use near_store::{ShardUId, Trie};
+
+    use super::estimate_split_shard;
+
+    fn account_key(account_id: &str) -> Vec<u8> {
+        let account_id: AccountId = account_id.parse().unwrap();
+        TrieKey::Account { account_id }.to_vec()
+    }
+
+    #[test]
+    fn estimate_split_shard_counts_children_accounts() {
+        let store = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let shard_uid = ShardUId { version: 3, shard_id: 1 };
+        let keys: Vec<_> = ["aa", "bb", "cc", "xx", "yy"].into_iter().map(account_key).collect();
+        let changes = keys.iter().map(|key| (key.clone(), Some(key.clone()))).collect();
+        let state_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+        let mut store_update = store.flat_store().store_update();
+        for key in &keys {
+            store_update.set(shard_uid, key.clone(), Some(FlatStateValue::inlined(key)));
+        }
+        store_update.commit().unwrap();
+
+        let boundary_account = "mm".parse().unwrap();
+        let estimate = estimate_split_shard(
+            &tries,
+            shard_uid,
+            state_root,
+            &boundary_account,
+            &ReshardingConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(estimate.left_child.num_accounts, 3);
+        assert_eq!(estimate.right_child.num_accounts, 2);
+        assert_eq!(estimate.left_child.num_keys + estimate.right_child.num_keys, 5);
+        let total_size: u64 = keys.iter().map(|key| 2 * key.len() as u64).sum();
+        assert_eq!(estimate.left_child.state_size + estimate.right_child.state_size, total_size);
+        assert_ne!(estimate.left_child.state_root, state_root);
+        assert_ne!(estimate.left_child.state_root, estimate.right_child.state_root);
+        assert!(estimate.estimated_split_duration >= estimate.retain_duration);
+    }
+}
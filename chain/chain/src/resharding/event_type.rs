@@ -44,12 +44,13 @@ impl ReshardingEventType {
     /// * `next_shard_layout`: the new shard layout
     /// * `resharding_hash`: hash of the last block with the shard layout before `next_shard_layout`
     ///
-    /// Returns a [ReshardingEventType] if exactly one resharding change is contained in
-    /// `next_shard_layout`, otherwise returns `None`.
+    /// Returns one [ReshardingEventType] for every parent shard split in `next_shard_layout`,
+    /// ordered by parent shard id. The splits of different parents are independent of each
+    /// other.
     pub fn from_shard_layout(
         next_shard_layout: &ShardLayout,
         resharding_block: BlockInfo,
-    ) -> Result<Option<ReshardingEventType>, Error> {
+    ) -> Result<Vec<ReshardingEventType>, Error> {
         let log_and_error = |err_msg: &str| {
             error!(target: "resharding", ?next_shard_layout, err_msg);
             Err(Error::ReshardingError(err_msg.to_owned()))
@@ -68,16 +69,13 @@ impl ReshardingEventType {
             }
         };
 
-        let mut event = None;
+        let mut events = vec![];
 
-        // Look for a shard having exactly two children, to detect a split.
+        // Look for the shards having exactly two children, to detect splits.
         for (parent_id, children_ids) in shards_split_map {
             match children_ids.len() {
                 1 => {}
                 2 => {
-                    if event.is_some() {
-                        return log_and_error("can't perform two reshardings at the same time!");
-                    }
                     // Parent shard is no longer part of this shard layout.
                     //
                     // Please note the use of the next shard layout version.
@@ -99,7 +97,7 @@ impl ReshardingEventType {
                         ));
                     };
                     let boundary_account = boundary_accounts[boundary_account_index].clone();
-                    event = Some(ReshardingEventType::SplitShard(ReshardingSplitShardParams {
+                    events.push(ReshardingEventType::SplitShard(ReshardingSplitShardParams {
                         parent_shard,
                         left_child_shard,
                         right_child_shard,
@@ -115,8 +113,26 @@ impl ReshardingEventType {
             }
         }
 
-        // We may have found at least one resharding event by now.
-        Ok(event)
+        // We may have found some resharding events by now.
+        Ok(events)
+    }
+
+    /// Same as [Self::from_shard_layout], but only returns the event `parent_shard` takes part
+    /// in, if any.
+    pub fn from_shard_layout_for_parent(
+        next_shard_layout: &ShardLayout,
+        resharding_block: BlockInfo,
+        parent_shard: ShardUId,
+    ) -> Result<Option<ReshardingEventType>, Error> {
+        let events = Self::from_shard_layout(next_shard_layout, resharding_block)?;
+        Ok(events.into_iter().find(|event| event.parent_shards().contains(&parent_shard)))
+    }
+
+    /// Shards which no longer exist after this event.
+    pub fn parent_shards(&self) -> Vec<ShardUId> {
+        match self {
+            ReshardingEventType::SplitShard(params) => vec![params.parent_shard],
+        }
     }
 }
 
@@ -125,7 +141,7 @@ mod tests {
     use super::*;
     use near_primitives::hash::CryptoHash;
     use near_primitives::shard_layout::ShardLayout;
-    use near_primitives::types::{AccountId, ShardId};
+    use near_primitives::types::AccountId;
     use near_store::ShardUId;
     use std::collections::BTreeMap;
 
@@ -165,7 +181,7 @@ mod tests {
         let shards_split_map = BTreeMap::from([(s0, vec![s0])]);
         let layout = ShardLayout::v2(vec![], vec![s0], Some(shards_split_map));
         assert!(ReshardingEventType::from_shard_layout(&layout, block)
-            .is_ok_and(|event| event.is_none()));
+            .is_ok_and(|events| events.is_empty()));
 
         // Single split shard is ok.
         let shards_split_map = BTreeMap::from([(s0, vec![s0]), (s1, vec![s2, s3])]);
@@ -175,25 +191,57 @@ mod tests {
             Some(shards_split_map),
         );
 
-        let event_type = ReshardingEventType::from_shard_layout(&layout, block).unwrap();
+        let event_types = ReshardingEventType::from_shard_layout(&layout, block).unwrap();
         assert_eq!(
-            event_type,
-            Some(ReshardingEventType::SplitShard(ReshardingSplitShardParams {
+            event_types,
+            vec![ReshardingEventType::SplitShard(ReshardingSplitShardParams {
                 parent_shard: ShardUId { version: 3, shard_id: 1 },
                 left_child_shard: ShardUId { version: 3, shard_id: 2 },
                 right_child_shard: ShardUId { version: 3, shard_id: 3 },
                 resharding_block: block,
                 boundary_account: account!("pp")
-            }))
+            })]
         );
 
-        // Double split shard is not ok.
+        // Double split shard is ok, each parent having its own event.
         let shards_split_map = BTreeMap::from([(s0, vec![s2, s3]), (s1, vec![s4, s5])]);
         let layout = ShardLayout::v2(
             vec![account!("ff"), account!("pp"), account!("ss")],
             vec![s2, s3, s4, s5],
             Some(shards_split_map),
         );
-        assert!(ReshardingEventType::from_shard_layout(&layout, block).is_err());
+        let second_split = ReshardingEventType::SplitShard(ReshardingSplitShardParams {
+            parent_shard: ShardUId { version: 3, shard_id: 1 },
+            left_child_shard: ShardUId { version: 3, shard_id: 4 },
+            right_child_shard: ShardUId { version: 3, shard_id: 5 },
+            resharding_block: block,
+            boundary_account: account!("ss"),
+        });
+        let event_types = ReshardingEventType::from_shard_layout(&layout, block).unwrap();
+        assert_eq!(
+            event_types,
+            vec![
+                ReshardingEventType::SplitShard(ReshardingSplitShardParams {
+                    parent_shard: ShardUId { version: 3, shard_id: 0 },
+                    left_child_shard: ShardUId { version: 3, shard_id: 2 },
+                    right_child_shard: ShardUId { version: 3, shard_id: 3 },
+                    resharding_block: block,
+                    boundary_account: account!("ff"),
+                }),
+                second_split.clone(),
+            ]
+        );
+        let parent_shard = ShardUId { version: 3, shard_id: 1 };
+        assert_eq!(
+            ReshardingEventType::from_shard_layout_for_parent(&layout, block, parent_shard)
+                .unwrap(),
+            Some(second_split)
+        );
+        let unknown_shard = ShardUId { version: 3, shard_id: 2 };
+        assert_eq!(
+            ReshardingEventType::from_shard_layout_for_parent(&layout, block, unknown_shard)
+                .unwrap(),
+            None
+        );
     }
 }
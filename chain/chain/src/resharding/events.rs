@@ -0,0 +1,148 @@
+//! Lifecycle events of resharding.
+//!
+//! The events are broadcast to the subscribers of [ReshardingManager::subscribe_events], such
+//! as the client, which keeps the latest ones for the debug page, so that the history of
+//! resharding can be followed without parsing the logs. Unlike the persisted [ReshardingStatus], the events are not replayed after
+//! a restart, and a subscriber lagging by more than [RESHARDING_EVENTS_CAPACITY] events misses
+//! the oldest ones.
+//!
+//! [ReshardingManager::subscribe_events]: super::manager::ReshardingManager::subscribe_events
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::StateRoot;
+use near_store::flat::BlockInfo;
+use near_store::ShardUId;
+use tokio::sync::broadcast;
+
+use super::status::{ReshardingPhase, ReshardingStatus};
+
+/// Number of events buffered for each subscriber.
+pub const RESHARDING_EVENTS_CAPACITY: usize = 64;
+
+#[derive(serde::Serialize, Debug, Clone, PartialEq, Eq, strum::IntoStaticStr)]
+pub enum ReshardingEvent {
+    /// The resharding block was processed for the first time.
+    Started {
+        resharding_block: BlockInfo,
+        parent_shards: Vec<ShardUId>,
+        children_shards: Vec<ShardUId>,
+    },
+    /// The state of a child was created on top of a resharding block. Happens once for every
+    /// competing resharding block.
+    ChildMemtrieCreated { block_hash: CryptoHash, child_shard: ShardUId, state_root: StateRoot },
+    /// The key-values of the parents were copied to the children flat storages, which now catch
+    /// up with the chain.
+    FlatStorageCopied { resharding_block: BlockInfo, children_shards: Vec<ShardUId> },
+    /// All children flat storages are ready.
+    Finished { resharding_block: BlockInfo, children_shards: Vec<ShardUId> },
+    /// The resharding of the flat storage failed.
+    Failed { resharding_block: BlockInfo, parent_shards: Vec<ShardUId> },
+}
+
+impl ReshardingEvent {
+    /// Returns the event corresponding to a transition of the resharding phase to the phase of
+    /// `status`, if any.
+    pub(crate) fn from_phase_change(
+        old_phase: ReshardingPhase,
+        status: &ReshardingStatus,
+    ) -> Option<Self> {
+        let resharding_block = status.resharding_block;
+        match (old_phase, status.phase) {
+            (old_phase, new_phase) if old_phase == new_phase => None,
+            (ReshardingPhase::CopyingKeyValues, ReshardingPhase::CatchingUp) => {
+                Some(Self::FlatStorageCopied {
+                    resharding_block,
+                    children_shards: status.children_shards.clone(),
+                })
+            }
+            (_, ReshardingPhase::Done) => Some(Self::Finished {
+                resharding_block,
+                children_shards: status.children_shards.clone(),
+            }),
+            (_, ReshardingPhase::Failed) => {
+                Some(Self::Failed { resharding_block, parent_shards: status.parent_shards.clone() })
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Broadcasts [ReshardingEvent]s to the current subscribers.
+#[derive(Clone, Debug)]
+pub struct ReshardingEvents {
+    sender: broadcast::Sender<ReshardingEvent>,
+}
+
+impl ReshardingEvents {
+    pub fn new() -> Self {
+        let (sender, _) = broadcast::channel(RESHARDING_EVENTS_CAPACITY);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<ReshardingEvent> {
+        self.sender.subscribe()
+    }
+
+    pub(crate) fn emit(&self, event: ReshardingEvent) {
+        tracing::debug!(target: "resharding", ?event, "resharding event");
+        // Fails only if there are no subscribers.
+        let _ = self.sender.send(event);
+    }
+}
+
+impl Default for ReshardingEvents {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_store::flat::BlockInfo;
+    use near_store::ShardUId;
+
+    use super::{ReshardingEvent, ReshardingEvents};
+    use crate::resharding::status::{ReshardingPhase, ReshardingStatus};
+
+    #[test]
+    fn resharding_events_from_phase_changes() {
+        let resharding_block = BlockInfo::genesis(Default::default(), 10);
+        let parent_shards = vec![ShardUId { version: 3, shard_id: 1 }];
+        let children_shards =
+            vec![ShardUId { version: 4, shard_id: 1 }, ShardUId { version: 4, shard_id: 2 }];
+        let mut status =
+            ReshardingStatus::new(resharding_block, parent_shards.clone(), children_shards.clone());
+        let events = ReshardingEvents::new();
+        let mut receiver = events.subscribe();
+
+        for (old_phase, new_phase) in [
+            (ReshardingPhase::Scheduled, ReshardingPhase::CopyingKeyValues),
+            (ReshardingPhase::CopyingKeyValues, ReshardingPhase::CatchingUp),
+            (ReshardingPhase::CatchingUp, ReshardingPhase::CatchingUp),
+            (ReshardingPhase::CatchingUp, ReshardingPhase::Done),
+            (ReshardingPhase::CatchingUp, ReshardingPhase::Failed),
+        ] {
+            status.phase = new_phase;
+            if let Some(event) = ReshardingEvent::from_phase_change(old_phase, &status) {
+                events.emit(event);
+            }
+        }
+
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            ReshardingEvent::FlatStorageCopied {
+                resharding_block,
+                children_shards: children_shards.clone()
+            }
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            ReshardingEvent::Finished { resharding_block, children_shards }
+        );
+        assert_eq!(
+            receiver.try_recv().unwrap(),
+            ReshardingEvent::Failed { resharding_block, parent_shards }
+        );
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -1,34 +1,85 @@
 use std::cell::RefCell;
+use std::collections::HashSet;
 use std::io;
 use std::sync::Arc;
 
+use super::buffered_receipts::{
+    get_shard_buffered_receipts, ShardBufferedReceipts, SplitBufferedReceipts,
+};
 use super::event_type::{ReshardingEventType, ReshardingSplitShardParams};
+use super::events::ReshardingEvent;
+use super::progress::{
+    finish_split_shard_progress, get_processed_resharding_blocks, get_split_shard_progresses,
+    save_split_shard_progress, SplitShardProgress,
+};
+use super::status::{
+    get_resharding_statuses, set_resharding_status, update_resharding_status, ReshardingPhase,
+    ReshardingStatus,
+};
 use super::types::ReshardingSender;
+use super::validation::get_split_shard_validation;
 use crate::flat_storage_resharder::{FlatStorageResharder, FlatStorageResharderController};
 use crate::types::RuntimeAdapter;
-use crate::ChainStoreUpdate;
+use crate::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
 use itertools::Itertools;
 use near_chain_configs::{MutableConfigValue, ReshardingConfig, ReshardingHandle};
 use near_chain_primitives::Error;
+use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::Block;
-use near_primitives::challenge::PartialState;
+use near_primitives::block_header::BlockHeader;
 use near_primitives::congestion_info::CongestionInfo;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::{get_block_shard_uid, ShardLayout};
-use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::stateless_validation::contract_distribution::{CodeBytes, CodeHash};
+use near_primitives::types::chunk_extra::{
+    ChildChunkExtraField, ChildChunkExtraFields, ChunkExtra,
+};
+use near_primitives::types::{AccountId, BlockHeight, StateRoot};
+use near_primitives::utils::get_block_shard_id;
 use near_store::adapter::trie_store::get_shard_uid_mapping;
 use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
-use near_store::flat::BlockInfo;
+use near_store::flat::{BlockInfo, FlatStorageStatus};
+use near_store::trie::mem::loading::apply_flat_state_changes_to_memtrie;
 use near_store::trie::mem::mem_trie_update::TrackingMode;
+use near_store::trie::mem::mem_tries::MemTries;
 use near_store::trie::ops::resharding::RetainMode;
 use near_store::trie::outgoing_metadata::ReceiptGroupsQueue;
-use near_store::trie::TrieRecorder;
-use near_store::{DBCol, ShardTries, ShardUId, Store, TrieAccess};
+use near_store::trie::{delete_trie_recorder_spill, TrieRecorder};
+use near_store::{DBCol, ShardTries, ShardUId, Store, TrieAccess, TrieChanges};
+use node_runtime::bootstrap_congestion_info;
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
+
+/// Inputs shared by the creation of both children of a split shard.
+struct ChildShardParams<'a> {
+    block: &'a Block,
+    parent_shard_uid: ShardUId,
+    parent_chunk_extra: Arc<ChunkExtra>,
+    parent_shard_layout: &'a ShardLayout,
+    child_shard_layout: &'a ShardLayout,
+    boundary_account: &'a AccountId,
+    tries: &'a ShardTries,
+    has_mem_tries: bool,
+    is_competing_block: bool,
+}
+
+/// State of a child shard created at the resharding block, committed together with the state
+/// of the other child.
+struct ChildShardUpdate {
+    shard_uid: ShardUId,
+    chunk_extra: ChunkExtra,
+    /// Borsh serialized `StoredChunkStateTransitionData` of the child, see
+    /// [ReshardingManager::serialize_state_transition_data].
+    state_transition_data: Vec<u8>,
+    trie_changes: TrieChanges,
+}
 
 pub struct ReshardingManager {
     store: Store,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
+    /// Used to skip resharding the children shards the node will not track.
+    shard_tracker: ShardTracker,
+    runtime_adapter: Arc<dyn RuntimeAdapter>,
     /// Configuration for resharding.
     pub resharding_config: MutableConfigValue<ReshardingConfig>,
     /// A handle that allows the main process to interrupt resharding if needed.
@@ -36,30 +87,61 @@ pub struct ReshardingManager {
     pub resharding_handle: ReshardingHandle,
     /// Takes care of performing resharding on the flat storage.
     pub flat_storage_resharder: FlatStorageResharder,
+    /// Resharding blocks whose parent shard is still being caught up, together with the parent
+    /// shard. The resharding runs once the catchup applied the resharding block.
+    waiting_for_catchup: HashSet<(CryptoHash, ShardUId)>,
 }
 
 impl ReshardingManager {
     pub fn new(
         store: Store,
         epoch_manager: Arc<dyn EpochManagerAdapter>,
+        shard_tracker: ShardTracker,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         resharding_config: MutableConfigValue<ReshardingConfig>,
         resharding_sender: ReshardingSender,
     ) -> Self {
+        // Publish the metrics of the latest resharding, which may have been in progress before a
+        // restart.
+        for status in get_resharding_statuses(&store).unwrap_or_default() {
+            status.update_metrics();
+            if let Ok(Some(validation)) =
+                get_split_shard_validation(&store, status.parent_shards[0])
+            {
+                validation.update_metrics();
+            }
+        }
         let resharding_handle = ReshardingHandle::new();
         let flat_storage_resharder = FlatStorageResharder::new(
-            runtime_adapter,
+            runtime_adapter.clone(),
             resharding_sender,
             FlatStorageResharderController::from_resharding_handle(resharding_handle.clone()),
             resharding_config.clone(),
         );
-        Self { store, epoch_manager, resharding_config, flat_storage_resharder, resharding_handle }
+        Self {
+            store,
+            epoch_manager,
+            shard_tracker,
+            runtime_adapter,
+            resharding_config,
+            flat_storage_resharder,
+            resharding_handle,
+            waiting_for_catchup: HashSet::new(),
+        }
+    }
+
+    /// Subscribes to the lifecycle events of the resharding events processed from now on.
+    pub fn subscribe_events(&self) -> tokio::sync::broadcast::Receiver<ReshardingEvent> {
+        self.flat_storage_resharder.events.subscribe()
     }
 
-    /// Trigger resharding if shard layout changes after the given block.
+    /// Trigger resharding if shard layout changes after the given block. Called for every shard
+    /// of the block, it performs the resharding event `shard_uid` is a parent of, if any. Several
+    /// shards may be split by the same shard layout change, each of them independently.
     pub fn start_resharding(
         &mut self,
         chain_store_update: ChainStoreUpdate,
+        me: Option<&AccountId>,
         block: &Block,
         shard_uid: ShardUId,
         tries: ShardTries,
@@ -94,12 +176,24 @@ impl ReshardingManager {
             height: block.header().height(),
             prev_hash: *block.header().prev_hash(),
         };
-        let resharding_event_type =
-            ReshardingEventType::from_shard_layout(&next_shard_layout, block_info)?;
+        let resharding_event_type = ReshardingEventType::from_shard_layout_for_parent(
+            &next_shard_layout,
+            block_info,
+            shard_uid,
+        )?;
+        if let Some(event_type) = &resharding_event_type {
+            if !self.is_parent_state_available(block_hash, event_type)? {
+                tracing::info!(target: "resharding", ?shard_uid, "parent shard not caught up yet, waiting for the catchup");
+                self.waiting_for_catchup.insert((*block_hash, shard_uid));
+                return Ok(());
+            }
+            self.waiting_for_catchup.remove(&(*block_hash, shard_uid));
+        }
         match resharding_event_type {
             Some(ReshardingEventType::SplitShard(split_shard_event)) => {
                 self.split_shard(
                     chain_store_update,
+                    me,
                     block,
                     shard_uid,
                     tries,
@@ -108,7 +202,7 @@ impl ReshardingManager {
                 )?;
             }
             None => {
-                tracing::warn!(target: "resharding", ?resharding_event_type, "unsupported resharding event type, skipping");
+                tracing::debug!(target: "resharding", "shard is not resharded, skipping");
             }
         };
         Ok(())
@@ -117,6 +211,7 @@ impl ReshardingManager {
     fn split_shard(
         &mut self,
         chain_store_update: ChainStoreUpdate,
+        me: Option<&AccountId>,
         block: &Block,
         shard_uid: ShardUId,
         tries: ShardTries,
@@ -128,24 +223,227 @@ impl ReshardingManager {
             tracing::debug!(target: "resharding", ?parent_shard, "ShardUId does not match event parent shard, skipping");
             return Ok(());
         }
+        let Some(is_competing_block) = self.check_resharding_block(block.hash(), shard_uid)? else {
+            return Ok(());
+        };
+        // Only the children tracked in the next epoch need their state to be resharded, the
+        // state of the other children is never read by this node.
+        let tracked_children = split_shard_event
+            .children_shards()
+            .into_iter()
+            .filter(|child_shard_uid| {
+                self.shard_tracker.care_about_shard(
+                    me,
+                    block.hash(),
+                    child_shard_uid.shard_id(),
+                    true,
+                )
+            })
+            .collect_vec();
+        if !is_competing_block {
+            self.start_resharding_status(ReshardingStatus::new(
+                split_shard_event.resharding_block,
+                vec![shard_uid],
+                tracked_children.clone(),
+            ))?;
+        }
 
-        // Reshard the State column by setting ShardUId mapping from children to ancestor.
-        self.set_state_shard_uid_mapping(&split_shard_event)?;
+        // The resharding block is already committed, persist the progress so that the split is
+        // resumed if the node restarts before it's done.
+        let progress = SplitShardProgress::new(
+            split_shard_event.resharding_block,
+            shard_uid,
+            tracked_children,
+        );
+        let mut store_update = self.store.store_update();
+        save_split_shard_progress(&mut store_update, &progress)?;
+        store_update.commit()?;
 
-        // Create temporary children memtries by freezing parent memtrie and referencing it.
-        self.process_memtrie_resharding_storage_update(
+        self.continue_split_shard(
             chain_store_update,
             block,
-            shard_uid,
             tries,
-            split_shard_event.clone(),
-        )?;
+            split_shard_event,
+            next_shard_layout,
+            progress,
+            is_competing_block,
+        )
+    }
+
+    /// Resumes the splits of the shards interrupted by a restart of the node, if any. Must be
+    /// called once the flat storage is initialized, see [SplitShardProgress].
+    pub fn resume_interrupted_split_shards(
+        &mut self,
+        chain_store: &mut ChainStore,
+        tries: ShardTries,
+    ) -> Result<(), Error> {
+        for progress in get_split_shard_progresses(&self.store)? {
+            // The nodes spilled by a recording interrupted by the restart are recorded again.
+            let mut store_update = self.store.store_update();
+            for child in &progress.tracked_children {
+                if !progress.children_done.contains(child) {
+                    let key_prefix =
+                        Self::trie_recorder_spill_prefix(&progress.resharding_block.hash, child);
+                    delete_trie_recorder_spill(&mut store_update, &key_prefix);
+                }
+            }
+            store_update.commit()?;
+            self.resume_interrupted_split_shard(
+                chain_store.store_update(),
+                tries.clone(),
+                progress,
+            )?;
+        }
+        self.restart_cancelled_split_shards(chain_store)
+    }
+
+    /// Restarts the flat storage splits cancelled by an operator abort, see
+    /// `ReshardingControl::Abort`, or by the shutdown of the node. The cancellation resets the parent flat storage, so the split starts again from the copy of
+    /// key-values. Splits aborted during catchup are resumed by the flat storage initialization.
+    fn restart_cancelled_split_shards(&mut self, chain_store: &ChainStore) -> Result<(), Error> {
+        for status in get_resharding_statuses(&self.store)? {
+            if status.phase != ReshardingPhase::Cancelled {
+                continue;
+            }
+            let block_header = chain_store.get_block_header(&status.resharding_block.hash)?;
+            let next_epoch_id =
+                self.epoch_manager.get_next_epoch_id_from_prev_block(block_header.prev_hash())?;
+            let next_shard_layout = self.epoch_manager.get_shard_layout(&next_epoch_id)?;
+            for &parent_shard in &status.parent_shards {
+                let flat_storage_status = self
+                    .store
+                    .flat_store()
+                    .get_flat_storage_status(parent_shard)
+                    .map_err(|err| Error::StorageError(err.into()))?;
+                if !matches!(flat_storage_status, FlatStorageStatus::Ready(_)) {
+                    continue;
+                }
+                let Some(event_type) = ReshardingEventType::from_shard_layout_for_parent(
+                    &next_shard_layout,
+                    status.resharding_block,
+                    parent_shard,
+                )?
+                else {
+                    continue;
+                };
+                tracing::info!(target: "resharding", ?parent_shard, resharding_block = ?status.resharding_block, "restarting aborted split shard");
+                update_resharding_status(&self.store, parent_shard, |status| {
+                    status.phase = ReshardingPhase::Scheduled
+                });
+                self.flat_storage_resharder.start_resharding(
+                    event_type,
+                    &next_shard_layout,
+                    &status.children_shards,
+                )?;
+            }
+        }
+        Ok(())
+    }
+
+    fn resume_interrupted_split_shard(
+        &mut self,
+        chain_store_update: ChainStoreUpdate,
+        tries: ShardTries,
+        progress: SplitShardProgress,
+    ) -> Result<(), Error> {
+        tracing::info!(target: "resharding", ?progress, "resuming interrupted split shard");
+
+        // Scheduling the flat storage resharding is the last step, and the scheduled resharding
+        // is resumed by the flat storage initialization.
+        let flat_storage_status = self
+            .store
+            .flat_store()
+            .get_flat_storage_status(progress.parent_shard)
+            .map_err(|err| Error::StorageError(err.into()))?;
+        if !matches!(flat_storage_status, FlatStorageStatus::Ready(_)) {
+            tracing::info!(target: "resharding", ?flat_storage_status, "flat storage resharding already scheduled, nothing to resume");
+            finish_split_shard_progress(&self.store, &progress)?;
+            return Ok(());
+        }
+
+        let block = chain_store_update.get_block(&progress.resharding_block.hash)?;
+        let next_epoch_id =
+            self.epoch_manager.get_next_epoch_id_from_prev_block(block.header().prev_hash())?;
+        let next_shard_layout = self.epoch_manager.get_shard_layout(&next_epoch_id)?;
+        let Some(ReshardingEventType::SplitShard(split_shard_event)) =
+            ReshardingEventType::from_shard_layout_for_parent(
+                &next_shard_layout,
+                progress.resharding_block,
+                progress.parent_shard,
+            )?
+        else {
+            return Err(Error::Other(format!(
+                "interrupted split of {} at {:?} not found in the next shard layout",
+                progress.parent_shard, progress.resharding_block
+            )));
+        };
+        // Memtries don't survive restarts, so the parent memtrie must be frozen again, as for
+        // the first resharding block.
+        self.continue_split_shard(
+            chain_store_update,
+            &block,
+            tries,
+            split_shard_event,
+            next_shard_layout,
+            progress,
+            false,
+        )
+    }
+
+    /// Performs the steps of the split not done yet according to `progress`, and persists the
+    /// progress after each of them.
+    fn continue_split_shard(
+        &mut self,
+        chain_store_update: ChainStoreUpdate,
+        block: &Block,
+        tries: ShardTries,
+        split_shard_event: ReshardingSplitShardParams,
+        next_shard_layout: ShardLayout,
+        mut progress: SplitShardProgress,
+        is_competing_block: bool,
+    ) -> Result<(), Error> {
+        let parent_shard = progress.parent_shard;
+        if progress.tracked_children.is_empty() {
+            tracing::debug!(target: "resharding", ?split_shard_event, "no children shards will be tracked, skipping the state update");
+        } else {
+            if !progress.state_mapping_done {
+                // Reshard the State column by setting ShardUId mapping from children to ancestor.
+                self.set_state_shard_uid_mapping(
+                    block.header().height(),
+                    parent_shard,
+                    &progress.tracked_children,
+                )?;
+                progress.state_mapping_done = true;
+                let mut store_update = self.store.store_update();
+                save_split_shard_progress(&mut store_update, &progress)?;
+                store_update.commit()?;
+            }
+
+            if !progress.pending_children().is_empty() {
+                // Create temporary children memtries by freezing parent memtrie and referencing it.
+                self.process_memtrie_resharding_storage_update(
+                    chain_store_update,
+                    block,
+                    parent_shard,
+                    tries,
+                    split_shard_event.clone(),
+                    &mut progress,
+                    is_competing_block,
+                )?;
+            }
+        }
 
         // Trigger resharding of flat storage.
         self.flat_storage_resharder.start_resharding(
-            ReshardingEventType::SplitShard(split_shard_event),
+            ReshardingEventType::SplitShard(split_shard_event.clone()),
             &next_shard_layout,
+            &progress.tracked_children,
         )?;
+        // Once the flat storage is resharded, check the children state against the parent.
+        if !is_competing_block && !progress.tracked_children.is_empty() {
+            self.flat_storage_resharder.schedule_split_shard_validation(split_shard_event);
+        }
+        finish_split_shard_progress(&self.store, &progress)?;
 
         Ok(())
     }
@@ -153,23 +451,41 @@ impl ReshardingManager {
     /// Store in the database the mapping of ShardUId from children to the parent shard,
     /// so that subsequent accesses to the State will use the ancestor's ShardUId prefix
     /// as a prefix for the database key.
+    ///
+    /// The mapping is also recorded in the history of the mapping, from the first block of the
+    /// new shard layout on, so that archival nodes keep resolving the State of past blocks if
+    /// the mapping changes later.
     // TODO(resharding) add testloop where grandparent ShardUId is used
     fn set_state_shard_uid_mapping(
         &mut self,
-        split_shard_event: &ReshardingSplitShardParams,
+        resharding_block_height: BlockHeight,
+        parent_shard_uid: ShardUId,
+        children_shard_uids: &[ShardUId],
     ) -> io::Result<()> {
         let mut store_update = self.store.trie_store().store_update();
-        let parent_shard_uid = split_shard_event.parent_shard;
         let parent_shard_uid_prefix = get_shard_uid_mapping(&self.store, parent_shard_uid);
-        // TODO(resharding) No need to set the mapping for children shards that we won't track just after resharding?
-        for child_shard_uid in split_shard_event.children_shards() {
-            store_update.set_shard_uid_mapping(child_shard_uid, parent_shard_uid_prefix);
+        for child_shard_uid in children_shard_uids {
+            store_update.set_shard_uid_mapping(*child_shard_uid, parent_shard_uid_prefix);
+            store_update.set_shard_uid_mapping_from_height(
+                *child_shard_uid,
+                resharding_block_height + 1,
+                parent_shard_uid_prefix,
+            );
         }
         store_update.commit()
     }
 
-    /// Creates temporary memtries for new shards to be able to process them in the next epoch.
-    /// Note this doesn't complete memtries resharding, proper memtries are to be created later.
+    /// Creates temporary memtries for the tracked new shards to be able to process them in the
+    /// next epoch. Note this doesn't complete memtries resharding, proper memtries are to be
+    /// created later. Only the state of the children not done yet according to `progress` is
+    /// created, and the progress is committed together with it.
+    ///
+    /// Nodes which don't keep the parent memtrie loaded, such as RPC and archival nodes, compute
+    /// the children state roots from the on-disk trie of the parent instead. Retaining a part of
+    /// the trie only rewrites the nodes on the path of the boundary account.
+    ///
+    /// The children are created in parallel on the rayon thread pool, as they only share the
+    /// frozen parent, and their state is committed at once.
     fn process_memtrie_resharding_storage_update(
         &mut self,
         mut chain_store_update: ChainStoreUpdate,
@@ -177,6 +493,8 @@ impl ReshardingManager {
         parent_shard_uid: ShardUId,
         tries: ShardTries,
         split_shard_event: ReshardingSplitShardParams,
+        progress: &mut SplitShardProgress,
+        is_competing_block: bool,
     ) -> Result<(), Error> {
         let block_hash = block.hash();
         let block_height = block.header().height();
@@ -185,22 +503,117 @@ impl ReshardingManager {
             ?block_hash, block_height, ?parent_shard_uid)
         .entered();
 
-        // TODO(resharding): what if node doesn't have memtrie? just pause
-        // processing?
-        // TODO(resharding): fork handling. if epoch is finalized on different
-        // blocks, the second finalization will crash.
-        tries.freeze_mem_tries(parent_shard_uid, split_shard_event.children_shards())?;
-
-        let parent_chunk_extra = self.get_chunk_extra(block_hash, &parent_shard_uid)?;
-        let boundary_account = split_shard_event.boundary_account;
+        // The parent memtrie is frozen only once. On competing resharding blocks the children
+        // memtries already exist, unless the node restarted since the first resharding block.
+        let has_mem_tries = tries.get_mem_tries(parent_shard_uid).is_some();
+        let has_children_mem_tries =
+            progress.tracked_children.iter().all(|child| tries.get_mem_tries(*child).is_some());
+        if has_mem_tries && !has_children_mem_tries {
+            tries.freeze_mem_tries(parent_shard_uid, progress.tracked_children.clone())?;
+        }
+        let pending_children = progress.pending_children();
 
-        let mut trie_store_update = self.store.store_update();
+        let parent_shard_layout = self.epoch_manager.get_shard_layout(block.header().epoch_id())?;
+        let child_epoch_id = self.epoch_manager.get_next_epoch_id(block_hash)?;
+        let child_shard_layout = self.epoch_manager.get_shard_layout(&child_epoch_id)?;
+        let child_params = ChildShardParams {
+            block,
+            parent_shard_uid,
+            parent_chunk_extra: self.get_chunk_extra(block_hash, &parent_shard_uid)?,
+            parent_shard_layout: &parent_shard_layout,
+            child_shard_layout: &child_shard_layout,
+            boundary_account: &split_shard_event.boundary_account,
+            tries: &tries,
+            has_mem_tries,
+            is_competing_block,
+        };
 
-        // TODO(resharding): leave only tracked shards.
-        for (new_shard_uid, retain_mode) in [
+        let this = &*self;
+        let child_updates = [
             (split_shard_event.left_child_shard, RetainMode::Left),
             (split_shard_event.right_child_shard, RetainMode::Right),
-        ] {
+        ]
+        .into_iter()
+        .filter(|(new_shard_uid, _)| pending_children.contains(new_shard_uid))
+        .collect_vec()
+        .into_par_iter()
+        .map(|(new_shard_uid, retain_mode)| {
+            this.create_child_shard_update(&child_params, new_shard_uid, retain_mode)
+        })
+        .collect::<Result<Vec<_>, Error>>()?;
+
+        let mut trie_store_update = self.store.store_update();
+        for child_update in child_updates {
+            let ChildShardUpdate { shard_uid, chunk_extra, state_transition_data, trie_changes } =
+                child_update;
+            chain_store_update.save_chunk_extra(block_hash, &shard_uid, chunk_extra);
+            trie_store_update.set(
+                DBCol::StateTransitionData,
+                &get_block_shard_id(block_hash, shard_uid.shard_id()),
+                &state_transition_data,
+            );
+
+            // Commit `TrieChanges` directly. They are needed to serve reads of
+            // new nodes from `DBCol::State` while memtrie is properly created
+            // from flat storage.
+            tries.apply_insertions(
+                &trie_changes,
+                shard_uid,
+                &mut trie_store_update.trie_store_update(),
+            );
+            // Save the insertions so that they are reverted if the resharding block ends up on
+            // a discarded fork. The deleted nodes still belong to the parent.
+            trie_store_update.set_ser(
+                DBCol::TrieChanges,
+                &get_block_shard_uid(block_hash, &shard_uid),
+                &trie_changes.without_deletions(),
+            )?;
+        }
+
+        progress.children_done.extend(pending_children);
+        save_split_shard_progress(&mut trie_store_update, progress)?;
+        chain_store_update.merge(trie_store_update);
+        chain_store_update.commit()?;
+
+        Ok(())
+    }
+
+    /// Creates the state of a child shard by retaining its side of the parent trie. Runs
+    /// concurrently for both children, see [Self::process_memtrie_resharding_storage_update].
+    fn create_child_shard_update(
+        &self,
+        params: &ChildShardParams,
+        new_shard_uid: ShardUId,
+        retain_mode: RetainMode,
+    ) -> Result<ChildShardUpdate, Error> {
+        let ChildShardParams {
+            block,
+            parent_shard_uid,
+            parent_chunk_extra,
+            parent_shard_layout,
+            child_shard_layout,
+            boundary_account,
+            tries,
+            has_mem_tries,
+            is_competing_block,
+        } = params;
+        let block_hash = block.hash();
+        let block_height = block.header().height();
+        let parent_state_root = *parent_chunk_extra.state_root();
+        let _span = tracing::debug_span!(
+            target: "resharding", "create_child_shard_update",
+            ?block_hash, ?parent_shard_uid, ?new_shard_uid)
+        .entered();
+
+        // The recorded nodes can exceed the memory of the node for large shards, they are spilled
+        // to the database past the limit.
+        let recorder_memory_limit = self.resharding_config.get().recorder_memory_limit;
+        let mut trie_recorder = TrieRecorder::new_spilling(
+            self.store.clone(),
+            Self::trie_recorder_spill_prefix(block_hash, &new_shard_uid),
+            recorder_memory_limit.as_u64() as usize,
+        );
+        let (trie_changes, new_state_root) = if *has_mem_tries {
             let Some(mem_tries) = tries.get_mem_tries(new_shard_uid) else {
                 tracing::error!(
                     "Memtrie not loaded. Cannot process memtrie resharding storage
@@ -216,82 +629,235 @@ impl ReshardingManager {
                 "Creating child memtrie by retaining nodes in parent memtrie..."
             );
             let mut mem_tries = mem_tries.write().unwrap();
-            let mut trie_recorder = TrieRecorder::new(None);
+            if *is_competing_block {
+                self.add_parent_state_root_to_child_mem_tries(
+                    block_hash,
+                    *parent_shard_uid,
+                    &mut mem_tries,
+                )?;
+            }
             let mode = TrackingMode::RefcountsAndAccesses(&mut trie_recorder);
-            let mem_trie_update = mem_tries.update(*parent_chunk_extra.state_root(), mode)?;
+            let mem_trie_update = mem_tries.update(parent_state_root, mode)?;
 
-            let trie_changes = mem_trie_update.retain_split_shard(&boundary_account, retain_mode);
+            let trie_changes = mem_trie_update.retain_split_shard(boundary_account, retain_mode);
             let mem_changes = trie_changes.mem_trie_changes.as_ref().unwrap();
             let new_state_root = mem_tries.apply_memtrie_changes(block_height, mem_changes);
-            drop(mem_tries);
-
-            // Get the congestion info for the child.
-            let parent_epoch_id = block.header().epoch_id();
-            let parent_shard_layout = self.epoch_manager.get_shard_layout(&parent_epoch_id)?;
-            let parent_state_root = *parent_chunk_extra.state_root();
-            let parent_trie = tries.get_trie_for_shard(parent_shard_uid, parent_state_root);
-            let parent_congestion_info =
-                parent_chunk_extra.congestion_info().expect("The congestion info must exist!");
-
-            let trie_recorder = RefCell::new(trie_recorder);
-            let parent_trie = parent_trie.recording_reads_with_recorder(trie_recorder);
-
-            let child_epoch_id = self.epoch_manager.get_next_epoch_id(block.hash())?;
-            let child_shard_layout = self.epoch_manager.get_shard_layout(&child_epoch_id)?;
-            let child_congestion_info = Self::get_child_congestion_info(
-                &parent_trie,
-                &parent_shard_layout,
-                parent_congestion_info,
-                &child_shard_layout,
+            (trie_changes, new_state_root)
+        } else {
+            tracing::info!(
+                target: "resharding", ?new_shard_uid, ?retain_mode,
+                "Memtrie not loaded, creating child trie by retaining nodes in parent on-disk trie..."
+            );
+            let parent_trie = tries
+                .get_trie_for_shard(*parent_shard_uid, parent_state_root)
+                .recording_reads_with_recorder(RefCell::new(trie_recorder));
+            let trie_changes = parent_trie.retain_split_shard(boundary_account, retain_mode)?;
+            trie_recorder = parent_trie.take_recorder().unwrap().into_inner();
+            let new_state_root = trie_changes.new_root;
+            (trie_changes, new_state_root)
+        };
+
+        // Get the congestion info for the child.
+        let parent_trie = tries.get_trie_for_shard(*parent_shard_uid, parent_state_root);
+        let parent_congestion_info =
+            parent_chunk_extra.congestion_info().expect("The congestion info must exist!");
+
+        let trie_recorder = RefCell::new(trie_recorder);
+        let parent_trie = parent_trie.recording_reads_with_recorder(trie_recorder);
+
+        let child_congestion_info = Self::get_child_congestion_info(
+            &parent_trie,
+            parent_shard_layout,
+            parent_congestion_info,
+            child_shard_layout,
+            new_shard_uid,
+            retain_mode,
+        )?;
+
+        // The child state is only readable once applied to the child memtrie.
+        if cfg!(debug_assertions) && *has_mem_tries {
+            self.debug_check_child_congestion_info(
+                block,
+                tries,
                 new_shard_uid,
-                retain_mode,
+                new_state_root,
+                &child_congestion_info,
             )?;
+        }
 
-            let trie_recorder = parent_trie.take_recorder().unwrap();
-            let partial_storage = trie_recorder.borrow_mut().recorded_storage();
-            let partial_state_len = match &partial_storage.nodes {
-                PartialState::TrieValues(values) => values.len(),
-            };
+        let trie_recorder = parent_trie.take_recorder().unwrap();
+        let (state_transition_data, partial_state_len) =
+            Self::serialize_state_transition_data(&mut trie_recorder.borrow_mut())?;
 
-            // TODO(resharding): set all fields of `ChunkExtra`. Consider stronger
-            // typing. Clarify where it should happen when `State` and
-            // `FlatState` update is implemented.
-            let mut child_chunk_extra = ChunkExtra::clone(&parent_chunk_extra);
-            *child_chunk_extra.state_root_mut() = new_state_root;
-            *child_chunk_extra.congestion_info_mut().expect("The congestion info must exist!") =
-                child_congestion_info;
-
-            chain_store_update.save_chunk_extra(block_hash, &new_shard_uid, child_chunk_extra);
-            chain_store_update.save_state_transition_data(
-                *block_hash,
-                new_shard_uid.shard_id(),
-                Some(partial_storage),
-                CryptoHash::default(),
-                // No contract code is accessed or deployed during resharding.
-                // TODO(#11099): Confirm if sending no contracts is ok here.
-                Default::default(),
-            );
+        let child_chunk_extra = ChunkExtra::new_child(
+            parent_chunk_extra,
+            Self::child_chunk_extra_fields(new_state_root, child_congestion_info),
+        );
 
-            // Commit `TrieChanges` directly. They are needed to serve reads of
-            // new nodes from `DBCol::State` while memtrie is properly created
-            // from flat storage.
-            tries.apply_insertions(
-                &trie_changes,
-                new_shard_uid,
-                &mut trie_store_update.trie_store_update(),
-            );
-            tracing::info!(
-                target: "resharding", ?new_shard_uid, ?new_state_root, ?partial_state_len,
-                "Child memtrie created"
-            );
+        tracing::info!(
+            target: "resharding", ?new_shard_uid, ?new_state_root, ?partial_state_len,
+            "Child memtrie created"
+        );
+        self.flat_storage_resharder.events.emit(ReshardingEvent::ChildMemtrieCreated {
+            block_hash: *block.hash(),
+            child_shard: new_shard_uid,
+            state_root: new_state_root,
+        });
+        Ok(ChildShardUpdate {
+            shard_uid: new_shard_uid,
+            chunk_extra: child_chunk_extra,
+            state_transition_data,
+            trie_changes,
+        })
+    }
+
+    /// Serializes the `StoredChunkStateTransitionData` of a child shard, with the nodes recorded
+    /// while creating it as the base state, and returns it along with the number of nodes. The
+    /// nodes are streamed into the output instead of being collected into a `PartialStorage`,
+    /// as the recording of a large shard is spilled to the store.
+    fn serialize_state_transition_data(
+        trie_recorder: &mut TrieRecorder,
+    ) -> Result<(Vec<u8>, usize), Error> {
+        // The variant index of `StoredChunkStateTransitionData::V1`, then the fields of
+        // `StoredChunkStateTransitionDataV1`, starting with the base state.
+        let mut state_transition_data = vec![0];
+        let num_nodes = trie_recorder.write_recorded_storage(&mut state_transition_data)?;
+        // No contract code is accessed or deployed during resharding.
+        // TODO(#11099): Confirm if sending no contracts is ok here.
+        let (contract_accesses, contract_deploys): (Vec<CodeHash>, Vec<CodeBytes>) =
+            Default::default();
+        borsh::to_writer(
+            &mut state_transition_data,
+            &(CryptoHash::default(), contract_accesses, contract_deploys),
+        )?;
+        Ok((state_transition_data, num_nodes))
+    }
+
+    /// Prefix of the keys of the nodes spilled by the trie recorder of a child shard.
+    fn trie_recorder_spill_prefix(block_hash: &CryptoHash, child_shard_uid: &ShardUId) -> Vec<u8> {
+        [block_hash.as_ref(), &child_shard_uid.to_bytes()].concat()
+    }
+
+    /// Persists the status of a new resharding event and announces its start.
+    fn start_resharding_status(&self, status: ReshardingStatus) -> Result<(), Error> {
+        set_resharding_status(&self.store, &status)?;
+        self.flat_storage_resharder.events.emit(ReshardingEvent::Started {
+            resharding_block: status.resharding_block,
+            parent_shards: status.parent_shards,
+            children_shards: status.children_shards,
+        });
+        Ok(())
+    }
+
+    /// Whether the state of all the parent shards of the event is available at the resharding
+    /// block. A node which did not track a parent shard in the epoch of the resharding, e.g.
+    /// because it state synced it to track one of the children in the next epoch, only has the
+    /// parent state once the catchup applied the resharding block. The catchup then starts the
+    /// resharding again.
+    fn is_parent_state_available(
+        &self,
+        block_hash: &CryptoHash,
+        event_type: &ReshardingEventType,
+    ) -> Result<bool, Error> {
+        for parent_shard_uid in event_type.parent_shards() {
+            let key = get_block_shard_uid(block_hash, &parent_shard_uid);
+            if !self.store.exists(DBCol::ChunkExtra, &key)? {
+                return Ok(false);
+            }
         }
+        Ok(true)
+    }
 
-        chain_store_update.merge(trie_store_update);
-        chain_store_update.commit()?;
+    /// Whether the resharding of the parent shard waits for its catchup, see
+    /// [Self::is_parent_state_available].
+    pub fn is_waiting_for_catchup(&self, parent_shard_uid: ShardUId) -> bool {
+        self.waiting_for_catchup.iter().any(|(_, shard_uid)| *shard_uid == parent_shard_uid)
+    }
+
+    /// Returns `None` if the resharding block was already processed for the parent shard, which
+    /// makes resharding idempotent. Otherwise returns whether another resharding block was
+    /// processed for the parent shard, which happens if the epoch ends on competing forks. The
+    /// processed blocks are persisted, see [finish_split_shard_progress].
+    ///
+    /// The state created for the children on the forks which end up being discarded is garbage
+    /// collected together with the resharding block, see `DBCol::TrieChanges`.
+    fn check_resharding_block(
+        &self,
+        block_hash: &CryptoHash,
+        parent_shard_uid: ShardUId,
+    ) -> Result<Option<bool>, Error> {
+        let processed_blocks = get_processed_resharding_blocks(&self.store, parent_shard_uid)?;
+        if processed_blocks.contains(block_hash) {
+            tracing::debug!(target: "resharding", ?block_hash, ?parent_shard_uid, "resharding block already processed, skipping");
+            return Ok(None);
+        }
+        let is_competing_block = !processed_blocks.is_empty();
+        if is_competing_block {
+            tracing::info!(target: "resharding", ?block_hash, ?parent_shard_uid, "processing competing resharding block");
+        }
+        Ok(Some(is_competing_block))
+    }
+
+    /// Makes sure that the state root of the parent shard at `block_hash` is present in the
+    /// memtrie of a child.
+    ///
+    /// The children memtries are created from the parent memtrie frozen on the first resharding
+    /// block. The parent state on a competing fork may have been created after that, in which
+    /// case it is rebuilt by replaying the parent flat storage deltas on top of the last state
+    /// root known to the child memtrie.
+    fn add_parent_state_root_to_child_mem_tries(
+        &self,
+        block_hash: &CryptoHash,
+        parent_shard_uid: ShardUId,
+        mem_tries: &mut MemTries,
+    ) -> Result<(), Error> {
+        let mut blocks_to_replay = vec![];
+        let mut hash = *block_hash;
+        let mut state_root = *self.get_chunk_extra(&hash, &parent_shard_uid)?.state_root();
+        while state_root != StateRoot::default() && !mem_tries.contains_root(&state_root) {
+            let header = self.get_block_header(&hash)?;
+            blocks_to_replay.push((hash, header.height(), state_root));
+            hash = *header.prev_hash();
+            state_root = *self.get_chunk_extra(&hash, &parent_shard_uid)?.state_root();
+        }
 
+        let flat_store = self.store.flat_store();
+        for (hash, height, expected_state_root) in blocks_to_replay.into_iter().rev() {
+            // Blocks without a new chunk may have no delta, as the state doesn't change.
+            let changes = flat_store
+                .get_delta(parent_shard_uid, hash)
+                .map_err(|err| Error::StorageError(err.into()))?
+                .unwrap_or_default();
+            state_root =
+                apply_flat_state_changes_to_memtrie(mem_tries, state_root, height, changes)?;
+            if state_root != expected_state_root {
+                return Err(Error::ReshardingError(format!(
+                    "state root mismatch when replaying block {hash} for {parent_shard_uid:?}: expected {expected_state_root}, got {state_root}"
+                )));
+            }
+        }
         Ok(())
     }
 
+    /// The fields of the `ChunkExtra` of a child created by a split. Besides the
+    /// state root and the congestion info, the child reports the results of applying the last
+    /// chunk of its parent, like the parent would have, so they are all inherited.
+    fn child_chunk_extra_fields(
+        state_root: StateRoot,
+        congestion_info: CongestionInfo,
+    ) -> ChildChunkExtraFields {
+        ChildChunkExtraFields {
+            state_root,
+            congestion_info,
+            outcome_root: ChildChunkExtraField::Inherit,
+            validator_proposals: ChildChunkExtraField::Inherit,
+            gas_used: ChildChunkExtraField::Inherit,
+            gas_limit: ChildChunkExtraField::Inherit,
+            balance_burnt: ChildChunkExtraField::Inherit,
+            bandwidth_requests: ChildChunkExtraField::Inherit,
+        }
+    }
+
     pub fn get_child_congestion_info(
         parent_trie: &dyn TrieAccess,
         parent_shard_layout: &ShardLayout,
@@ -321,7 +887,9 @@ impl ReshardingManager {
     // Get the congestion info for the child shard. The congestion info can be
     // inferred efficiently from the combination of the parent shard's
     // congestion info and the receipt group metadata, that is available in the
-    // parent shard's trie.
+    // parent shard's trie. It only reads one receipt groups queue per shard,
+    // instead of bootstrapping the congestion info from the whole child state,
+    // see `debug_check_child_congestion_info`.
     fn get_child_congestion_info_not_finalized(
         parent_trie: &dyn TrieAccess,
         parent_shard_layout: &ShardLayout,
@@ -362,6 +930,91 @@ impl ReshardingManager {
         Ok(congestion_info)
     }
 
+    /// Checks the congestion info of a child computed by `get_child_congestion_info` against
+    /// the congestion info bootstrapped from all the delayed and buffered receipts of the child
+    /// state. This is O(state), so it's only done in debug builds.
+    fn debug_check_child_congestion_info(
+        &self,
+        block: &Block,
+        tries: &ShardTries,
+        child_shard_uid: ShardUId,
+        child_state_root: StateRoot,
+        child_congestion_info: &CongestionInfo,
+    ) -> Result<(), Error> {
+        let protocol_version =
+            self.epoch_manager.get_epoch_protocol_version(block.header().epoch_id())?;
+        let runtime_config = self.runtime_adapter.get_runtime_config(protocol_version)?;
+        let child_trie = tries.get_trie_for_shard(child_shard_uid, child_state_root);
+        let bootstrapped_congestion_info =
+            bootstrap_congestion_info(&child_trie, &runtime_config, child_shard_uid.shard_id())?;
+        assert_eq!(
+            child_congestion_info.delayed_receipts_gas(),
+            bootstrapped_congestion_info.delayed_receipts_gas(),
+            "delayed receipts gas mismatch for {child_shard_uid}"
+        );
+        assert_eq!(
+            child_congestion_info.buffered_receipts_gas(),
+            bootstrapped_congestion_info.buffered_receipts_gas(),
+            "buffered receipts gas mismatch for {child_shard_uid}"
+        );
+        assert_eq!(
+            child_congestion_info.receipt_bytes(),
+            bootstrapped_congestion_info.receipt_bytes(),
+            "receipt bytes mismatch for {child_shard_uid}"
+        );
+        Ok(())
+    }
+
+    /// Buffered receipts of the parent shard of a split and of its children at the resharding
+    /// block, by target shard, according to their `ReceiptGroupsQueue`s and to their congestion
+    /// info. Used to debug mismatches in the congestion info of the children.
+    pub fn get_split_buffered_receipts(
+        &self,
+        resharding_block_hash: &CryptoHash,
+        parent_shard_uid: ShardUId,
+        children_shards: &[ShardUId],
+    ) -> Result<SplitBufferedReceipts, Error> {
+        let block_header = self.get_block_header(resharding_block_hash)?;
+        let parent_shard_layout = self.epoch_manager.get_shard_layout(block_header.epoch_id())?;
+        let child_epoch_id =
+            self.epoch_manager.get_next_epoch_id_from_prev_block(block_header.prev_hash())?;
+        let child_shard_layout = self.epoch_manager.get_shard_layout(&child_epoch_id)?;
+        // The buffers of the left child target the shards of the parent layout until they are
+        // forwarded, so the queues to the shards of both layouts are read.
+        let target_shards = parent_shard_layout
+            .shard_ids()
+            .chain(child_shard_layout.shard_ids())
+            .unique()
+            .collect_vec();
+
+        let tries = self.runtime_adapter.get_tries();
+        let get_shard_buffered_receipts_at_block =
+            |shard_uid: ShardUId| -> Result<Option<ShardBufferedReceipts>, Error> {
+                let chunk_extra = match self.get_chunk_extra(resharding_block_hash, &shard_uid) {
+                    Ok(chunk_extra) => chunk_extra,
+                    Err(Error::DBNotFoundErr(_)) => return Ok(None),
+                    Err(err) => return Err(err),
+                };
+                let trie = tries.get_view_trie_for_shard(shard_uid, *chunk_extra.state_root());
+                let buffered_receipts = get_shard_buffered_receipts(
+                    &trie,
+                    shard_uid,
+                    &chunk_extra,
+                    target_shards.iter().copied(),
+                )?;
+                Ok(Some(buffered_receipts))
+            };
+
+        let parent = get_shard_buffered_receipts_at_block(parent_shard_uid)?;
+        let mut children = vec![];
+        for child_shard_uid in children_shards {
+            if let Some(child) = get_shard_buffered_receipts_at_block(*child_shard_uid)? {
+                children.push(child);
+            }
+        }
+        Ok(SplitBufferedReceipts { parent, children })
+    }
+
     fn finalize_allowed_shard(
         child_shard_layout: &ShardLayout,
         child_shard_uid: ShardUId,
@@ -386,6 +1039,17 @@ impl ReshardingManager {
         Ok(())
     }
 
+    // TODO(store): Use proper store interface
+    fn get_block_header(&self, block_hash: &CryptoHash) -> Result<BlockHeader, Error> {
+        let value = self
+            .store
+            .get_ser(DBCol::BlockHeader, block_hash.as_ref())
+            .map_err(|e| Error::DBNotFoundErr(e.to_string()))?;
+        value.ok_or_else(|| {
+            Error::DBNotFoundErr(format_args!("BLOCK HEADER: {}", block_hash).to_string())
+        })
+    }
+
     // TODO(store): Use proper store interface
     fn get_chunk_extra(
         &self,
@@ -1,7 +1,14 @@
+pub mod buffered_receipts;
+pub mod dry_run;
 pub mod event_type;
+pub mod events;
 pub mod manager;
+pub mod progress;
 pub mod resharding_actor;
 pub mod resharding_v2;
+pub mod shard_uid_mapping;
+pub mod status;
 pub mod types;
+pub mod validation;
 
 pub use resharding_v2 as v2;
@@ -0,0 +1,185 @@
+//! Progress of the split of the shards at the resharding block.
+//!
+//! Splitting a shard at the resharding block takes several steps committed separately: the
+//! State mapping of the children, the creation of the children state and the scheduling of the
+//! flat storage resharding. The resharding block itself is committed before them, so a node
+//! restarting in the middle would never process it again and would leave the children shards
+//! half-built. The progress is persisted before the first step and removed after the last one,
+//! so that [ReshardingManager] can resume the split on startup. Each parent shard has its own
+//! progress, as several shards may be split at the same resharding block.
+//!
+//! Once the split is done, the resharding block is recorded as processed for the parent shard
+//! instead, so that it's not split again on the same block, even after a restart.
+//!
+//! [ReshardingManager]: super::manager::ReshardingManager
+
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+use near_schema_checker_lib::ProtocolSchema;
+use near_store::db::{RESHARDING_PROCESSED_BLOCKS_KEY, RESHARDING_SPLIT_PROGRESS_KEY};
+use near_store::flat::BlockInfo;
+use near_store::{DBCol, ShardUId, Store, StoreUpdate};
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq, ProtocolSchema)]
+pub struct SplitShardProgress {
+    pub resharding_block: BlockInfo,
+    pub parent_shard: ShardUId,
+    /// Children shards whose state must be created, i.e. the ones the node will track.
+    pub tracked_children: Vec<ShardUId>,
+    /// The State mapping from the tracked children to the parent is set.
+    pub state_mapping_done: bool,
+    /// Tracked children whose state root, chunk extra and trie nodes are saved.
+    pub children_done: Vec<ShardUId>,
+}
+
+impl SplitShardProgress {
+    pub fn new(
+        resharding_block: BlockInfo,
+        parent_shard: ShardUId,
+        tracked_children: Vec<ShardUId>,
+    ) -> Self {
+        Self {
+            resharding_block,
+            parent_shard,
+            tracked_children,
+            state_mapping_done: false,
+            children_done: vec![],
+        }
+    }
+
+    /// Tracked children whose state is not created yet.
+    pub fn pending_children(&self) -> Vec<ShardUId> {
+        self.tracked_children
+            .iter()
+            .filter(|child| !self.children_done.contains(child))
+            .copied()
+            .collect()
+    }
+}
+
+fn split_shard_progress_key(parent_shard: ShardUId) -> Vec<u8> {
+    [RESHARDING_SPLIT_PROGRESS_KEY, &parent_shard.to_bytes()].concat()
+}
+
+/// Returns the progress of the splits interrupted by a restart of the node, ordered by parent
+/// shard. There is more than one if several shards are split at the same resharding block.
+pub fn get_split_shard_progresses(store: &Store) -> io::Result<Vec<SplitShardProgress>> {
+    let mut progresses = store
+        .iter_prefix_ser(DBCol::Misc, RESHARDING_SPLIT_PROGRESS_KEY)
+        .map(|item| item.map(|(_, progress)| progress))
+        .collect::<io::Result<Vec<SplitShardProgress>>>()?;
+    progresses.sort_by_key(|progress| progress.parent_shard);
+    Ok(progresses)
+}
+
+/// Adds the progress to `store_update`, so that it's committed atomically with the step it
+/// records.
+pub(crate) fn save_split_shard_progress(
+    store_update: &mut StoreUpdate,
+    progress: &SplitShardProgress,
+) -> io::Result<()> {
+    store_update.set_ser(DBCol::Misc, &split_shard_progress_key(progress.parent_shard), progress)
+}
+
+fn processed_resharding_blocks_key(parent_shard: ShardUId) -> Vec<u8> {
+    [RESHARDING_PROCESSED_BLOCKS_KEY, &parent_shard.to_bytes()].concat()
+}
+
+/// Returns the resharding blocks at which the split of `parent_shard` is done. There is more
+/// than one if the epoch ends on competing forks.
+pub(crate) fn get_processed_resharding_blocks(
+    store: &Store,
+    parent_shard: ShardUId,
+) -> io::Result<Vec<CryptoHash>> {
+    Ok(store
+        .get_ser(DBCol::Misc, &processed_resharding_blocks_key(parent_shard))?
+        .unwrap_or_default())
+}
+
+/// Records the resharding block of `progress` as processed for its parent shard and removes the
+/// progress, atomically.
+pub(crate) fn finish_split_shard_progress(
+    store: &Store,
+    progress: &SplitShardProgress,
+) -> io::Result<()> {
+    let parent_shard = progress.parent_shard;
+    let mut processed_blocks = get_processed_resharding_blocks(store, parent_shard)?;
+    if !processed_blocks.contains(&progress.resharding_block.hash) {
+        processed_blocks.push(progress.resharding_block.hash);
+    }
+    let mut store_update = store.store_update();
+    store_update.set_ser(
+        DBCol::Misc,
+        &processed_resharding_blocks_key(parent_shard),
+        &processed_blocks,
+    )?;
+    store_update.delete(DBCol::Misc, &split_shard_progress_key(parent_shard));
+    store_update.commit()
+}
+
+#[cfg(test)]
+mod tests {
+    use near_store::test_utils::create_test_store;
+
+    use super::*;
+
+    /// Verify that the progress is persisted for each parent, that the children done are not
+    /// pending and that finishing the progress records the resharding block as processed.
+    #[test]
+    fn save_and_finish_split_shard_progress() {
+        let store = create_test_store();
+        assert_eq!(get_split_shard_progresses(&store).unwrap(), vec![]);
+
+        let block = BlockInfo {
+            hash: CryptoHash::hash_bytes(&[1]),
+            height: 1,
+            prev_hash: CryptoHash::hash_bytes(&[2]),
+        };
+        let parent = ShardUId { version: 3, shard_id: 0 };
+        let left_child = ShardUId { version: 3, shard_id: 1 };
+        let right_child = ShardUId { version: 3, shard_id: 2 };
+        let mut progress = SplitShardProgress::new(block, parent, vec![left_child, right_child]);
+        assert_eq!(progress.pending_children(), vec![left_child, right_child]);
+
+        progress.state_mapping_done = true;
+        progress.children_done.push(left_child);
+        assert_eq!(progress.pending_children(), vec![right_child]);
+
+        let other_parent = ShardUId { version: 3, shard_id: 3 };
+        let other_progress = SplitShardProgress::new(
+            block,
+            other_parent,
+            vec![ShardUId { version: 3, shard_id: 4 }],
+        );
+        let mut store_update = store.store_update();
+        save_split_shard_progress(&mut store_update, &other_progress).unwrap();
+        save_split_shard_progress(&mut store_update, &progress).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(
+            get_split_shard_progresses(&store).unwrap(),
+            vec![progress.clone(), other_progress.clone()]
+        );
+
+        assert_eq!(get_processed_resharding_blocks(&store, parent).unwrap(), vec![]);
+        finish_split_shard_progress(&store, &progress).unwrap();
+        assert_eq!(get_split_shard_progresses(&store).unwrap(), vec![other_progress.clone()]);
+        assert_eq!(get_processed_resharding_blocks(&store, parent).unwrap(), vec![block.hash]);
+        assert_eq!(get_processed_resharding_blocks(&store, other_parent).unwrap(), vec![]);
+
+        // A competing resharding block of the same parent is recorded next to the first one,
+        // and finishing it twice is idempotent.
+        let competing_block = BlockInfo { hash: CryptoHash::hash_bytes(&[3]), ..block };
+        let competing_progress = SplitShardProgress::new(competing_block, parent, vec![]);
+        finish_split_shard_progress(&store, &competing_progress).unwrap();
+        finish_split_shard_progress(&store, &competing_progress).unwrap();
+        assert_eq!(
+            get_processed_resharding_blocks(&store, parent).unwrap(),
+            vec![block.hash, competing_block.hash]
+        );
+
+        finish_split_shard_progress(&store, &other_progress).unwrap();
+        assert_eq!(get_split_shard_progresses(&store).unwrap(), vec![]);
+    }
+}
@@ -1,5 +1,7 @@
+use super::event_type::ReshardingSplitShardParams;
 use super::types::{
     FlatStorageShardCatchupRequest, FlatStorageSplitShardRequest, MemtrieReloadRequest,
+    SplitShardValidationRequest,
 };
 use crate::flat_storage_resharder::{FlatStorageResharder, FlatStorageReshardingTaskResult};
 use crate::ChainStore;
@@ -37,6 +39,16 @@ impl HandlerWithContext<FlatStorageShardCatchupRequest> for ReshardingActor {
     }
 }
 
+impl HandlerWithContext<SplitShardValidationRequest> for ReshardingActor {
+    fn handle(
+        &mut self,
+        msg: SplitShardValidationRequest,
+        ctx: &mut dyn DelayedActionRunner<Self>,
+    ) {
+        self.handle_split_shard_validation(msg.resharder, msg.split_params, ctx);
+    }
+}
+
 impl Handler<MemtrieReloadRequest> for ReshardingActor {
     fn handle(&mut self, _msg: MemtrieReloadRequest) {
         // TODO(resharding)
@@ -107,4 +119,33 @@ impl ReshardingActor {
             }
         }
     }
+
+    fn handle_split_shard_validation(
+        &self,
+        resharder: FlatStorageResharder,
+        split_params: ReshardingSplitShardParams,
+        ctx: &mut dyn DelayedActionRunner<Self>,
+    ) {
+        match resharder.split_shard_validation_task(&split_params, &self.chain_store) {
+            FlatStorageReshardingTaskResult::Successful { .. } => {
+                // All good.
+            }
+            FlatStorageReshardingTaskResult::Failed => {
+                // The validation is informational only, the failure is already logged.
+            }
+            FlatStorageReshardingTaskResult::Cancelled => {
+                // The resharding didn't complete. Nothing else to do.
+            }
+            FlatStorageReshardingTaskResult::Postponed => {
+                // The flat storage resharding is still in progress, check again later.
+                ctx.run_later(
+                    "ReshardingActor SplitShardValidation",
+                    Duration::seconds(10),
+                    move |act, ctx| {
+                        act.handle_split_shard_validation(resharder, split_params, ctx);
+                    },
+                );
+            }
+        }
+    }
 }
@@ -218,7 +218,8 @@ impl Chain {
         let mut batch_count = 0;
 
         loop {
-            if !handle.get() {
+            // Blocks while resharding is paused by the operator.
+            if !handle.wait_while_paused() {
                 // The keep_going is set to false, interrupt processing.
                 tracing::info!(target: "resharding-v2", ?shard_uid, "build_state_for_split_shards_impl interrupted");
                 return Err(Error::Other("Resharding interrupted.".to_string()));
@@ -0,0 +1,282 @@
+//! Consistency check of the State mapping of the shards.
+//!
+//! The children of a split read their State with the prefix of an ancestor, according to
+//! `DBCol::StateShardUIdMapping`. A resharding interrupted at the wrong time, or a garbage
+//! collection that removed the State of a shard but not the mappings to it, can leave mappings
+//! behind which point nowhere or to a shard unrelated to the child. [check_shard_uid_mappings]
+//! cross-checks every mapping against the history of the shard layouts known to the epoch
+//! manager, and [repair_shard_uid_mappings] fixes the issues found, where the fix is known.
+//!
+//! Only the current mapping is checked. `DBCol::StateShardUIdMappingHistory` is left as is, as
+//! archival nodes rely on it to read the State of past heights.
+
+use std::collections::{HashMap, HashSet};
+use std::io;
+
+use borsh::BorshDeserialize;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::ProtocolVersion;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
+use near_store::flat::FlatStorageStatus;
+use near_store::{DBCol, ShardUId, Store};
+
+use crate::Error;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShardUIdMappingIssueKind {
+    /// The shard is not part of any known shard layout. The mapping is deleted on repair.
+    UnknownShard,
+    /// The mapped shard is not an ancestor of the shard. On repair, the mapping is replaced by
+    /// the one of the parent, if the shard has a single parent, like when it was set by the
+    /// split.
+    NotAncestor { expected_shard_uid: Option<ShardUId> },
+    /// No State is stored with the prefix of the mapped shard, although this node tracks the
+    /// shard, see [is_tracked_shard]. Only reported, as the mapping may still be valid, e.g.
+    /// while the State of the shard is synced, so it is left as is on repair.
+    MissingState,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShardUIdMappingIssue {
+    pub shard_uid: ShardUId,
+    pub mapped_shard_uid: ShardUId,
+    pub kind: ShardUIdMappingIssueKind,
+}
+
+/// Returns the distinct shard layouts of the protocol versions from the genesis one up to the
+/// one of this binary, in order.
+pub fn get_shard_layout_history(
+    epoch_manager: &dyn EpochManagerAdapter,
+    genesis_protocol_version: ProtocolVersion,
+) -> Vec<ShardLayout> {
+    let mut shard_layouts: Vec<ShardLayout> = vec![];
+    for protocol_version in genesis_protocol_version..=PROTOCOL_VERSION {
+        let shard_layout = epoch_manager.get_shard_layout_from_protocol_version(protocol_version);
+        if shard_layouts.last() != Some(&shard_layout) {
+            shard_layouts.push(shard_layout);
+        }
+    }
+    shard_layouts
+}
+
+/// Checks the State mapping of all the shards against the history of the shard layouts known
+/// to the epoch manager.
+pub fn check_shard_uid_mappings(
+    store: &Store,
+    epoch_manager: &dyn EpochManagerAdapter,
+    genesis_protocol_version: ProtocolVersion,
+) -> Result<Vec<ShardUIdMappingIssue>, Error> {
+    let shard_layouts = get_shard_layout_history(epoch_manager, genesis_protocol_version);
+    Ok(check_shard_uid_mappings_against_layouts(store, &shard_layouts)?)
+}
+
+/// Checks the State mapping of all the shards against the given history of shard layouts,
+/// ordered from the oldest to the newest.
+pub fn check_shard_uid_mappings_against_layouts(
+    store: &Store,
+    shard_layouts: &[ShardLayout],
+) -> io::Result<Vec<ShardUIdMappingIssue>> {
+    let mut known_shards = HashSet::new();
+    let mut parents: HashMap<ShardUId, Vec<ShardUId>> = HashMap::new();
+    for (prev_shard_layout, shard_layout) in shard_layouts.iter().zip(shard_layouts.iter().skip(1))
+    {
+        for shard_id in shard_layout.shard_ids() {
+            let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, shard_layout);
+            // Layouts which don't know their parent layout don't link the shards.
+            let Ok(parent_shard_id) = shard_layout.get_parent_shard_id(shard_id) else {
+                continue;
+            };
+            let parent_shard_uid =
+                ShardUId::from_shard_id_and_layout(parent_shard_id, prev_shard_layout);
+            if parent_shard_uid != shard_uid {
+                parents.entry(shard_uid).or_default().push(parent_shard_uid);
+            }
+        }
+    }
+    for shard_layout in shard_layouts {
+        known_shards.extend(shard_layout.shard_uids());
+    }
+
+    let mut mappings = HashMap::new();
+    for item in store.iter(DBCol::StateShardUIdMapping) {
+        let (key, value) = item?;
+        mappings.insert(ShardUId::try_from_slice(&key)?, ShardUId::try_from_slice(&value)?);
+    }
+    let is_ancestor = |shard_uid: ShardUId, ancestor: ShardUId| {
+        let mut stack = vec![shard_uid];
+        while let Some(shard_uid) = stack.pop() {
+            let shard_parents = parents.get(&shard_uid).map(Vec::as_slice).unwrap_or_default();
+            if shard_parents.contains(&ancestor) {
+                return true;
+            }
+            stack.extend(shard_parents);
+        }
+        false
+    };
+
+    let trie_store = store.trie_store();
+    let mut issues = vec![];
+    for (&shard_uid, &mapped_shard_uid) in &mappings {
+        let kind = if !known_shards.contains(&shard_uid) {
+            ShardUIdMappingIssueKind::UnknownShard
+        } else if !is_ancestor(shard_uid, mapped_shard_uid) {
+            let expected_shard_uid = match parents.get(&shard_uid).map(Vec::as_slice) {
+                Some(&[parent_shard_uid]) => match mappings.get(&parent_shard_uid) {
+                    Some(&parent_mapping) if is_ancestor(parent_shard_uid, parent_mapping) => {
+                        Some(parent_mapping)
+                    }
+                    _ => Some(parent_shard_uid),
+                },
+                _ => None,
+            };
+            ShardUIdMappingIssueKind::NotAncestor { expected_shard_uid }
+        } else if is_tracked_shard(store, shard_uid)?
+            && !trie_store.has_shard_uid_prefixed_state(mapped_shard_uid)?
+        {
+            ShardUIdMappingIssueKind::MissingState
+        } else {
+            continue;
+        };
+        issues.push(ShardUIdMappingIssue { shard_uid, mapped_shard_uid, kind });
+    }
+    issues.sort_by_key(|issue| issue.shard_uid);
+    Ok(issues)
+}
+
+/// Whether this node tracks the shard, i.e. keeps its flat storage. The nodes which never
+/// tracked a shard still have the mappings of its children set by the splits, without any State
+/// behind them.
+fn is_tracked_shard(store: &Store, shard_uid: ShardUId) -> io::Result<bool> {
+    let status = store.flat_store().get_flat_storage_status(shard_uid).map_err(io::Error::other)?;
+    Ok(!matches!(status, FlatStorageStatus::Empty | FlatStorageStatus::Disabled))
+}
+
+/// Fixes the given issues where possible. Returns the number of mappings changed.
+pub fn repair_shard_uid_mappings(
+    store: &Store,
+    issues: &[ShardUIdMappingIssue],
+) -> io::Result<usize> {
+    let mut store_update = store.store_update();
+    let mut num_repaired = 0;
+    for issue in issues {
+        let mut trie_store_update = store_update.trie_store_update();
+        match issue.kind {
+            ShardUIdMappingIssueKind::UnknownShard => {
+                trie_store_update.delete_shard_uid_mapping(issue.shard_uid);
+            }
+            ShardUIdMappingIssueKind::NotAncestor { expected_shard_uid: Some(expected) } => {
+                trie_store_update.set_shard_uid_mapping(issue.shard_uid, expected);
+            }
+            ShardUIdMappingIssueKind::NotAncestor { expected_shard_uid: None }
+            | ShardUIdMappingIssueKind::MissingState => {
+                tracing::warn!(target: "resharding", ?issue, "cannot repair the State mapping");
+                continue;
+            }
+        }
+        tracing::info!(target: "resharding", ?issue, "repaired the State mapping");
+        num_repaired += 1;
+    }
+    store_update.commit()?;
+    Ok(num_repaired)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZero;
+
+    use near_primitives::hash::CryptoHash;
+    use near_store::adapter::trie_store::get_shard_uid_mapping;
+    use near_store::flat::{BlockInfo, FlatStorageReadyStatus};
+    use near_store::test_utils::create_test_store;
+
+    use super::*;
+
+    #[test]
+    fn check_and_repair_shard_uid_mappings() {
+        let store = create_test_store();
+        let genesis_layout = ShardLayout::single_shard();
+        let first_split_layout =
+            ShardLayout::derive_shard_layout(&genesis_layout, "mm".parse().unwrap());
+        let second_split_layout =
+            ShardLayout::derive_shard_layout(&first_split_layout, "ss".parse().unwrap());
+        let shard_layouts =
+            [genesis_layout.clone(), first_split_layout.clone(), second_split_layout.clone()];
+        let shard_uid = |account: &str| {
+            let layout = if account == "genesis" { &genesis_layout } else { &second_split_layout };
+            layout.account_id_to_shard_uid(&account.parse().unwrap())
+        };
+        let root = shard_uid("genesis");
+        let left = shard_uid("aa");
+        let middle = shard_uid("pp");
+        let right = shard_uid("zz");
+        let right_parent = first_split_layout.account_id_to_shard_uid(&"zz".parse().unwrap());
+        let unknown = ShardUId { version: 42, shard_id: 7 };
+
+        let mut store_update = store.store_update();
+        let mut trie_store_update = store_update.trie_store_update();
+        trie_store_update.increment_refcount_by(
+            root,
+            &CryptoHash::default(),
+            &[0],
+            NonZero::new(1).unwrap(),
+        );
+        // Set by the splits, valid.
+        trie_store_update.set_shard_uid_mapping(left, root);
+        trie_store_update.set_shard_uid_mapping(right_parent, root);
+        trie_store_update.set_shard_uid_mapping(middle, root);
+        // Siblings are not ancestors.
+        trie_store_update.set_shard_uid_mapping(right, middle);
+        trie_store_update.set_shard_uid_mapping(unknown, root);
+        store_update.commit().unwrap();
+
+        let issues = check_shard_uid_mappings_against_layouts(&store, &shard_layouts).unwrap();
+        let mut expected_issues = vec![
+            ShardUIdMappingIssue {
+                shard_uid: right,
+                mapped_shard_uid: middle,
+                kind: ShardUIdMappingIssueKind::NotAncestor { expected_shard_uid: Some(root) },
+            },
+            ShardUIdMappingIssue {
+                shard_uid: unknown,
+                mapped_shard_uid: root,
+                kind: ShardUIdMappingIssueKind::UnknownShard,
+            },
+        ];
+        expected_issues.sort_by_key(|issue| issue.shard_uid);
+        assert_eq!(issues, expected_issues);
+
+        assert_eq!(repair_shard_uid_mappings(&store, &issues).unwrap(), 2);
+        assert_eq!(check_shard_uid_mappings_against_layouts(&store, &shard_layouts).unwrap(), []);
+        let trie_store = store.trie_store();
+        assert_eq!(get_shard_uid_mapping(&store, right), root);
+        assert_eq!(get_shard_uid_mapping(&store, unknown), unknown);
+
+        // Once the State of the root is gone, the mappings of the tracked shards to it are
+        // reported, but kept on repair.
+        let mut store_update = trie_store.store_update();
+        store_update.decrement_refcount(root, &CryptoHash::default());
+        store_update.commit().unwrap();
+        assert_eq!(check_shard_uid_mappings_against_layouts(&store, &shard_layouts).unwrap(), []);
+        let mut store_update = store.flat_store().store_update();
+        store_update.set_flat_storage_status(
+            left,
+            FlatStorageStatus::Ready(FlatStorageReadyStatus {
+                flat_head: BlockInfo::genesis(CryptoHash::default(), 0),
+            }),
+        );
+        store_update.commit().unwrap();
+        let issues = check_shard_uid_mappings_against_layouts(&store, &shard_layouts).unwrap();
+        assert_eq!(
+            issues,
+            [ShardUIdMappingIssue {
+                shard_uid: left,
+                mapped_shard_uid: root,
+                kind: ShardUIdMappingIssueKind::MissingState,
+            }]
+        );
+        assert_eq!(repair_shard_uid_mappings(&store, &issues).unwrap(), 0);
+        assert_eq!(get_shard_uid_mapping(&store, left), root);
+    }
+}
@@ -0,0 +1,250 @@
+//! Progress of the latest resharding.
+//!
+//! The status is persisted in the database so that operators can tell whether resharding is
+//! progressing or stuck, including after a restart. It is updated by [ReshardingManager] when
+//! the resharding block is processed and by [FlatStorageResharder] while the background tasks
+//! run, and is exposed through Prometheus metrics and the `/debug/api/resharding_status` page.
+//!
+//! Every resharding event has its own status, keyed by its first parent shard, so that the
+//! shards split by the same shard layout change are tracked independently.
+//!
+//! [ReshardingManager]: super::manager::ReshardingManager
+//! [FlatStorageResharder]: crate::flat_storage_resharder::FlatStorageResharder
+
+use std::io;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_async::time::Clock;
+use near_schema_checker_lib::ProtocolSchema;
+use near_store::db::RESHARDING_STATUS_KEY;
+use near_store::flat::BlockInfo;
+use near_store::{DBCol, ShardUId, Store};
+
+use crate::metrics;
+
+/// Serializes the read-modify-write cycles of [update_resharding_status], which happen on both
+/// the chain thread and the resharding actor.
+static STATUS_UPDATE_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    serde::Serialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::IntoStaticStr,
+    strum::EnumIter,
+    ProtocolSchema,
+)]
+pub enum ReshardingPhase {
+    /// The children state was created at the resharding block. The flat storage resharding
+    /// waits for the resharding block to become final.
+    Scheduled,
+    /// The key-values are copied to the flat storage of the children.
+    CopyingKeyValues,
+    /// The flat storages of the children apply the deltas of the blocks processed since the
+    /// resharding block.
+    CatchingUp,
+    /// All children flat storages are ready.
+    Done,
+    Failed,
+    Cancelled,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, serde::Serialize, Debug, Clone, ProtocolSchema)]
+pub struct ReshardingStatus {
+    /// The last block of the old shard layout. Competing resharding blocks are replaced by the
+    /// one which became final once the copy of key-values starts.
+    pub resharding_block: BlockInfo,
+    pub parent_shards: Vec<ShardUId>,
+    /// The children shards tracked by the node, the resharding is done once they all are.
+    pub children_shards: Vec<ShardUId>,
+    pub phase: ReshardingPhase,
+    /// Children shards whose flat storage is ready.
+    pub shards_done: Vec<ShardUId>,
+    /// Number of key-values copied to the children flat storages.
+    pub keys_copied: u64,
+    /// Size of the key-values copied to the children flat storages.
+    pub bytes_copied: u64,
+    /// Rough estimate of the size of the key-values to copy, based on the memory usage recorded
+    /// in the root of the source trie. Used to compute the ETA.
+    pub bytes_to_copy: Option<u64>,
+    /// Unix timestamp in milliseconds at which the copy of key-values started.
+    pub copy_started_at_ms: Option<u64>,
+}
+
+impl ReshardingStatus {
+    pub fn new(
+        resharding_block: BlockInfo,
+        parent_shards: Vec<ShardUId>,
+        children_shards: Vec<ShardUId>,
+    ) -> Self {
+        Self {
+            resharding_block,
+            parent_shards,
+            children_shards,
+            phase: ReshardingPhase::Scheduled,
+            shards_done: vec![],
+            keys_copied: 0,
+            bytes_copied: 0,
+            bytes_to_copy: None,
+            copy_started_at_ms: None,
+        }
+    }
+
+    /// Whether `shard_uid` is one of the parents or children of the resharding event.
+    pub fn contains_shard(&self, shard_uid: ShardUId) -> bool {
+        self.parent_shards.contains(&shard_uid) || self.children_shards.contains(&shard_uid)
+    }
+
+    fn key(&self) -> Vec<u8> {
+        [RESHARDING_STATUS_KEY, &self.parent_shards[0].to_bytes()].concat()
+    }
+
+    /// Marks the start of the key-values copy.
+    pub(crate) fn start_copy(&mut self, resharding_block: BlockInfo, bytes_to_copy: Option<u64>) {
+        self.resharding_block = resharding_block;
+        self.phase = ReshardingPhase::CopyingKeyValues;
+        self.keys_copied = 0;
+        self.bytes_copied = 0;
+        self.bytes_to_copy = bytes_to_copy;
+        self.copy_started_at_ms = Some(now_ms());
+    }
+
+    /// Accounts for a batch of key-values copied to the children.
+    pub(crate) fn add_copied(&mut self, num_keys: u64, num_bytes: usize) {
+        self.keys_copied += num_keys;
+        self.bytes_copied += num_bytes as u64;
+    }
+
+    /// Marks the flat storage of `shard_uid` as ready, completing the resharding once all
+    /// children are done.
+    pub(crate) fn mark_shard_done(&mut self, shard_uid: ShardUId) {
+        if !self.shards_done.contains(&shard_uid) {
+            self.shards_done.push(shard_uid);
+        }
+        if self.children_shards.iter().all(|child| self.shards_done.contains(child)) {
+            self.phase = ReshardingPhase::Done;
+        }
+    }
+
+    /// Estimated time left to copy the key-values, extrapolated from the throughput so far.
+    /// Only available while copying.
+    pub fn eta(&self) -> Option<Duration> {
+        if self.phase != ReshardingPhase::CopyingKeyValues || self.bytes_copied == 0 {
+            return None;
+        }
+        let elapsed_ms = now_ms().saturating_sub(self.copy_started_at_ms?);
+        let bytes_left = self.bytes_to_copy?.saturating_sub(self.bytes_copied);
+        let eta_ms = elapsed_ms as u128 * bytes_left as u128 / self.bytes_copied as u128;
+        Some(Duration::from_millis(eta_ms.try_into().unwrap_or(u64::MAX)))
+    }
+
+    pub(crate) fn update_metrics(&self) {
+        let parent_shard = self.parent_shards[0].to_string();
+        let phase: &'static str = self.phase.into();
+        for other in <ReshardingPhase as strum::IntoEnumIterator>::iter() {
+            let other: &'static str = other.into();
+            metrics::RESHARDING_PHASE
+                .with_label_values(&[&parent_shard, other])
+                .set((other == phase) as i64);
+        }
+        metrics::RESHARDING_SHARDS_DONE
+            .with_label_values(&[&parent_shard])
+            .set(self.shards_done.len() as i64);
+        metrics::RESHARDING_KEYS_COPIED
+            .with_label_values(&[&parent_shard])
+            .set(self.keys_copied as i64);
+        metrics::RESHARDING_BYTES_COPIED
+            .with_label_values(&[&parent_shard])
+            .set(self.bytes_copied as i64);
+        metrics::RESHARDING_ETA_SECONDS
+            .with_label_values(&[&parent_shard])
+            .set(self.eta().map_or(-1, |eta| eta.as_secs() as i64));
+    }
+
+    fn remove_metrics(&self) {
+        let parent_shard = self.parent_shards[0].to_string();
+        for phase in <ReshardingPhase as strum::IntoEnumIterator>::iter() {
+            let phase: &'static str = phase.into();
+            let _ = metrics::RESHARDING_PHASE.remove_label_values(&[&parent_shard, phase]);
+        }
+        for metric in [
+            &metrics::RESHARDING_SHARDS_DONE,
+            &metrics::RESHARDING_KEYS_COPIED,
+            &metrics::RESHARDING_BYTES_COPIED,
+            &metrics::RESHARDING_ETA_SECONDS,
+        ] {
+            let _ = metric.remove_label_values(&[&parent_shard]);
+        }
+    }
+}
+
+/// Returns the statuses of the events of the latest resharding, ordered by parent shards.
+pub fn get_resharding_statuses(store: &Store) -> io::Result<Vec<ReshardingStatus>> {
+    let mut statuses = store
+        .iter_prefix_ser(DBCol::Misc, RESHARDING_STATUS_KEY)
+        .map(|item| item.map(|(_, status)| status))
+        .collect::<io::Result<Vec<ReshardingStatus>>>()?;
+    statuses.sort_by(|a, b| a.parent_shards.cmp(&b.parent_shards));
+    Ok(statuses)
+}
+
+/// Persists the status of a new resharding event. The statuses of the events of previous
+/// reshardings, which happened at a lower resharding block, are removed.
+pub(crate) fn set_resharding_status(store: &Store, status: &ReshardingStatus) -> io::Result<()> {
+    let _lock = STATUS_UPDATE_LOCK.lock().unwrap();
+    let mut store_update = store.store_update();
+    let mut removed_statuses = vec![];
+    for old_status in get_resharding_statuses(store)? {
+        if old_status.resharding_block.height < status.resharding_block.height {
+            store_update.delete(DBCol::Misc, &old_status.key());
+            removed_statuses.push(old_status);
+        }
+    }
+    store_update.set_ser(DBCol::Misc, &status.key(), status)?;
+    store_update.commit()?;
+    for old_status in removed_statuses {
+        old_status.remove_metrics();
+    }
+    status.update_metrics();
+    Ok(())
+}
+
+/// Applies `update` to the persisted status of the resharding event `shard_uid` is a parent or
+/// a child of. Does nothing if there is none, e.g. when resharding was resumed on a database
+/// created before the status was introduced.
+///
+/// The status is informational only, so failures are logged rather than propagated.
+pub(crate) fn update_resharding_status(
+    store: &Store,
+    shard_uid: ShardUId,
+    update: impl FnOnce(&mut ReshardingStatus),
+) {
+    let _lock = STATUS_UPDATE_LOCK.lock().unwrap();
+    let result = get_resharding_statuses(store).and_then(|statuses| {
+        let Some(mut status) = statuses.into_iter().find(|status| status.contains_shard(shard_uid))
+        else {
+            return Ok(());
+        };
+        update(&mut status);
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::Misc, &status.key(), &status)?;
+        store_update.commit()?;
+        status.update_metrics();
+        Ok(())
+    });
+    if let Err(err) = result {
+        tracing::warn!(target: "resharding", ?err, ?shard_uid, "failed to update the resharding status");
+    }
+}
+
+fn now_ms() -> u64 {
+    let now = Clock::real().now_utc();
+    (now.unix_timestamp_nanos() / 1_000_000).try_into().unwrap_or_default()
+}
@@ -1,3 +1,4 @@
+use super::event_type::ReshardingSplitShardParams;
 use crate::flat_storage_resharder::FlatStorageResharder;
 use near_async::messaging::Sender;
 use near_store::ShardUId;
@@ -18,6 +19,15 @@ pub struct FlatStorageShardCatchupRequest {
     pub shard_uid: ShardUId,
 }
 
+/// Represents a request to validate the children state of a split shard, once the flat storage
+/// resharding is done.
+#[derive(actix::Message, Clone, Debug)]
+#[rtype(result = "()")]
+pub struct SplitShardValidationRequest {
+    pub resharder: FlatStorageResharder,
+    pub split_params: ReshardingSplitShardParams,
+}
+
 /// Represents a request to reload a Mem Trie for a shard after its Flat Storage resharding is
 /// finished.
 #[derive(actix::Message, Clone, Debug)]
@@ -34,5 +44,6 @@ pub struct MemtrieReloadRequest {
 pub struct ReshardingSender {
     pub flat_storage_split_shard_sender: Sender<FlatStorageSplitShardRequest>,
     pub flat_storage_shard_catchup_sender: Sender<FlatStorageShardCatchupRequest>,
+    pub split_shard_validation_sender: Sender<SplitShardValidationRequest>,
     pub memtrie_reload_sender: Sender<MemtrieReloadRequest>,
 }
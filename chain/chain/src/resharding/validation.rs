@@ -0,0 +1,383 @@
+//! Validation of the children state of a shard split.
+//!
+//! Once the flat storage resharding of a split is done, [validate_split_shard] samples keys of
+//! the parent state at the resharding block and checks that each of them is present with the
+//! same value in the child on its side of the boundary account, and absent from the other child.
+//! It also samples keys of the children flat storages and checks them against the children
+//! tries. The result is persisted and exposed through Prometheus metrics and the
+//! `/debug/api/resharding_status` page, to gain confidence in the split before the parent state
+//! is garbage collected.
+
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+use near_primitives::state::ValueRef;
+use near_primitives::trie_key::col;
+use near_primitives::types::StateRoot;
+use near_schema_checker_lib::ProtocolSchema;
+use near_store::db::RESHARDING_SPLIT_VALIDATION_KEY;
+use near_store::flat::BlockInfo;
+use near_store::trie::ops::resharding::{is_key_retained_after_split, RetainMode};
+use near_store::{DBCol, ShardTries, ShardUId, Store};
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use super::event_type::ReshardingSplitShardParams;
+use crate::{metrics, ChainStore, ChainStoreAccess, Error};
+
+/// Number of keys sampled from the parent state and from each child flat storage.
+pub const NUM_SAMPLED_KEYS: usize = 1000;
+
+/// Maximum number of mismatches kept in the result. The others are only counted.
+const MAX_REPORTED_MISMATCHES: usize = 100;
+
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    serde::Serialize,
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    strum::IntoStaticStr,
+    ProtocolSchema,
+)]
+pub enum SplitShardMismatchKind {
+    /// A key of the parent is missing from the child on its side of the boundary account.
+    MissingKey,
+    /// A key of the parent has a different value in the child.
+    WrongValue,
+    /// A key of the parent is present in the child on the other side of the boundary account.
+    UnexpectedKey,
+    /// The flat storage of the child and its trie disagree on the value of a key.
+    FlatStorageMismatch,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, serde::Serialize, Debug, Clone, ProtocolSchema)]
+pub struct SplitShardMismatch {
+    pub shard_uid: ShardUId,
+    pub key: Vec<u8>,
+    pub kind: SplitShardMismatchKind,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, serde::Serialize, Debug, Clone, ProtocolSchema)]
+pub struct SplitShardValidationResult {
+    /// The final resharding block of the split.
+    pub resharding_block: BlockInfo,
+    pub parent_shard: ShardUId,
+    /// Children validated, i.e. the ones tracked by the node.
+    pub children_shards: Vec<ShardUId>,
+    /// Number of keys of the parent checked against the children tries.
+    pub trie_keys_checked: u64,
+    /// Number of keys of the children flat storages checked against their tries.
+    pub flat_storage_keys_checked: u64,
+    pub num_mismatches: u64,
+    /// The first mismatches found, at most MAX_REPORTED_MISMATCHES.
+    pub mismatches: Vec<SplitShardMismatch>,
+}
+
+impl SplitShardValidationResult {
+    fn new(resharding_block: BlockInfo, parent_shard: ShardUId) -> Self {
+        Self {
+            resharding_block,
+            parent_shard,
+            children_shards: vec![],
+            trie_keys_checked: 0,
+            flat_storage_keys_checked: 0,
+            num_mismatches: 0,
+            mismatches: vec![],
+        }
+    }
+
+    fn add_mismatch(&mut self, shard_uid: ShardUId, key: Vec<u8>, kind: SplitShardMismatchKind) {
+        tracing::error!(target: "resharding", ?shard_uid, ?key, ?kind, "split shard validation mismatch");
+        self.num_mismatches += 1;
+        if self.mismatches.len() < MAX_REPORTED_MISMATCHES {
+            self.mismatches.push(SplitShardMismatch { shard_uid, key, kind });
+        }
+    }
+
+    pub(crate) fn update_metrics(&self) {
+        let parent_shard = self.parent_shard.to_string();
+        metrics::RESHARDING_VALIDATION_KEYS_CHECKED
+            .with_label_values(&[&parent_shard, "trie"])
+            .set(self.trie_keys_checked as i64);
+        metrics::RESHARDING_VALIDATION_KEYS_CHECKED
+            .with_label_values(&[&parent_shard, "flat_storage"])
+            .set(self.flat_storage_keys_checked as i64);
+        metrics::RESHARDING_VALIDATION_MISMATCHES
+            .with_label_values(&[&parent_shard])
+            .set(self.num_mismatches as i64);
+    }
+}
+
+fn split_shard_validation_key(parent_shard: ShardUId) -> Vec<u8> {
+    [RESHARDING_SPLIT_VALIDATION_KEY, &parent_shard.to_bytes()].concat()
+}
+
+/// Returns the result of the validation of the latest split of `parent_shard`, if there was any.
+pub fn get_split_shard_validation(
+    store: &Store,
+    parent_shard: ShardUId,
+) -> io::Result<Option<SplitShardValidationResult>> {
+    store.get_ser(DBCol::Misc, &split_shard_validation_key(parent_shard))
+}
+
+/// Persists the result of the validation of a shard split, replacing the previous one of the
+/// same parent shard.
+pub(crate) fn save_split_shard_validation(
+    store: &Store,
+    result: &SplitShardValidationResult,
+) -> io::Result<()> {
+    let mut store_update = store.store_update();
+    store_update.set_ser(DBCol::Misc, &split_shard_validation_key(result.parent_shard), result)?;
+    store_update.commit()?;
+    result.update_metrics();
+    Ok(())
+}
+
+/// Validates the children state of the split described by `split_params`, whose final
+/// resharding block is `resharding_block`. Must be called once the flat storage resharding is
+/// done, and before the parent state is garbage collected.
+pub(crate) fn validate_split_shard(
+    chain_store: &ChainStore,
+    tries: &ShardTries,
+    split_params: &ReshardingSplitShardParams,
+    resharding_block: BlockInfo,
+    rng: &mut impl Rng,
+) -> Result<SplitShardValidationResult, Error> {
+    let ReshardingSplitShardParams {
+        parent_shard,
+        left_child_shard,
+        right_child_shard,
+        boundary_account,
+        ..
+    } = split_params;
+    let _span = tracing::debug_span!(
+        target: "resharding", "validate_split_shard", ?parent_shard, ?resharding_block)
+    .entered();
+    let mut result = SplitShardValidationResult::new(resharding_block, *parent_shard);
+
+    // The state is read from disk, memtries don't keep the state of past blocks.
+    let state_root =
+        |block_hash: &CryptoHash, shard_uid: ShardUId| -> Result<Option<StateRoot>, Error> {
+            match chain_store.get_chunk_extra(block_hash, &shard_uid) {
+                Ok(chunk_extra) => Ok(Some(*chunk_extra.state_root())),
+                Err(Error::DBNotFoundErr(_)) => Ok(None),
+                Err(err) => Err(err),
+            }
+        };
+    let Some(parent_state_root) = state_root(&resharding_block.hash, *parent_shard)? else {
+        return Err(Error::Other(format!("no state for parent shard {parent_shard}")));
+    };
+    let parent_trie = tries.get_view_trie_for_shard(*parent_shard, parent_state_root);
+    // Only the children tracked by the node have a state.
+    let mut children = vec![];
+    for (child_shard, retain_mode) in
+        [(*left_child_shard, RetainMode::Left), (*right_child_shard, RetainMode::Right)]
+    {
+        if let Some(child_state_root) = state_root(&resharding_block.hash, child_shard)? {
+            let child_trie = tries.get_view_trie_for_shard(child_shard, child_state_root);
+            children.push((child_shard, retain_mode, child_trie));
+            result.children_shards.push(child_shard);
+        }
+    }
+
+    let mut parent_iter = parent_trie.disk_iter()?;
+    for _ in 0..NUM_SAMPLED_KEYS {
+        parent_iter.seek_prefix(random_key(rng))?;
+        let Some(item) = parent_iter.next() else {
+            continue;
+        };
+        let (key, value) = item?;
+        result.trie_keys_checked += 1;
+        for (child_shard, retain_mode, child_trie) in &children {
+            let is_retained = is_key_retained_after_split(&key, boundary_account, *retain_mode);
+            let mismatch = match child_trie.get(&key)? {
+                Some(_) if !is_retained => Some(SplitShardMismatchKind::UnexpectedKey),
+                Some(child_value) if child_value != value => {
+                    Some(SplitShardMismatchKind::WrongValue)
+                }
+                None if is_retained => Some(SplitShardMismatchKind::MissingKey),
+                _ => None,
+            };
+            if let Some(kind) = mismatch {
+                result.add_mismatch(*child_shard, key.clone(), kind);
+            }
+        }
+    }
+
+    // The flat storages of the children have moved past the resharding block, so they are
+    // checked against the children tries at the chain head instead.
+    let flat_storage_manager = tries.get_flat_storage_manager();
+    for (child_shard, _, _) in &children {
+        if flat_storage_manager.get_flat_storage_for_shard(*child_shard).is_none() {
+            continue;
+        }
+        let head_hash = chain_store.head()?.last_block_hash;
+        let (Some(chunk_view), Some(child_state_root)) = (
+            flat_storage_manager.chunk_view(*child_shard, head_hash),
+            state_root(&head_hash, *child_shard)?,
+        ) else {
+            continue;
+        };
+        let child_trie = tries.get_view_trie_for_shard(*child_shard, child_state_root);
+        for _ in 0..NUM_SAMPLED_KEYS {
+            let from = random_key(rng);
+            let Some(item) = chunk_view.iter_range(Some(&from), None).next() else {
+                continue;
+            };
+            let (key, _) = item.map_err(|err| Error::StorageError(err.into()))?;
+            // The iterator reads the flat head, the value at the chain head includes the deltas.
+            let flat_value = chunk_view.get_value(&key)?.map(|value| value.to_value_ref());
+            let trie_value = child_trie.get(&key)?.map(|value| ValueRef::new(&value));
+            result.flat_storage_keys_checked += 1;
+            if flat_value != trie_value {
+                result.add_mismatch(*child_shard, key, SplitShardMismatchKind::FlatStorageMismatch);
+            }
+        }
+    }
+
+    tracing::info!(
+        target: "resharding",
+        trie_keys_checked = result.trie_keys_checked,
+        flat_storage_keys_checked = result.flat_storage_keys_checked,
+        num_mismatches = result.num_mismatches,
+        "split shard validation finished"
+    );
+    Ok(result)
+}
+
+/// Returns a key in a random trie column followed by a random account-like string, to seek
+/// to a random position of the state.
+fn random_key(rng: &mut impl Rng) -> Vec<u8> {
+    const ACCOUNT_ID_CHARS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let (column, _) = col::ALL_COLUMNS_WITH_NAMES.choose(rng).unwrap();
+    let mut key = vec![*column];
+    key.extend((0..8).map(|_| *ACCOUNT_ID_CHARS.choose(rng).unwrap()));
+    key
+}
+
+#[cfg(test)]
+mod tests {
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::trie_key::{col, TrieKey};
+    use near_primitives::types::chunk_extra::ChunkExtra;
+    use near_primitives::types::AccountId;
+    use near_store::test_utils::{create_test_store, test_populate_trie, TestTriesBuilder};
+    use near_store::trie::ops::resharding::{is_key_retained_after_split, RetainMode};
+    use near_store::{ShardTries, ShardUId, Trie};
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha8Rng;
+
+    use super::{validate_split_shard, SplitShardMismatchKind};
+    use crate::resharding::event_type::ReshardingSplitShardParams;
+    use crate::ChainStore;
+    use near_store::flat::BlockInfo;
+
+    fn account_key(account_id: &str) -> Vec<u8> {
+        let account_id: AccountId = account_id.parse().unwrap();
+        TrieKey::Account { account_id }.to_vec()
+    }
+
+    fn populate(
+        tries: &ShardTries,
+        chain_store: &mut ChainStore,
+        block_hash: &CryptoHash,
+        shard_uid: ShardUId,
+        keys: Vec<Vec<u8>>,
+    ) {
+        let changes = keys.into_iter().map(|key| (key.clone(), Some(key))).collect();
+        let state_root = test_populate_trie(tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+        let mut store_update = chain_store.store_update();
+        store_update.save_chunk_extra(
+            block_hash,
+            &shard_uid,
+            ChunkExtra::new_with_only_state_root(&state_root),
+        );
+        store_update.commit().unwrap();
+    }
+
+    #[test]
+    fn validate_split_shard_detects_misplaced_keys() {
+        let store = create_test_store();
+        let tries = TestTriesBuilder::new().with_store(store.clone()).build();
+        let mut chain_store = ChainStore::new(store, 0, false);
+        let resharding_block = BlockInfo {
+            hash: CryptoHash::hash_bytes(b"block"),
+            height: 10,
+            prev_hash: CryptoHash::default(),
+        };
+        let split_params = ReshardingSplitShardParams {
+            parent_shard: ShardUId { version: 3, shard_id: 1 },
+            left_child_shard: ShardUId { version: 3, shard_id: 2 },
+            right_child_shard: ShardUId { version: 3, shard_id: 3 },
+            boundary_account: "mm".parse().unwrap(),
+            resharding_block,
+        };
+        let parent_keys: Vec<_> = ["aa", "bb", "cc", "xx", "yy", "zz"]
+            .into_iter()
+            .map(account_key)
+            .chain([vec![col::DELAYED_RECEIPT_OR_INDICES]])
+            .collect();
+        let child_keys = |retain_mode| {
+            parent_keys
+                .iter()
+                .filter(|key| {
+                    is_key_retained_after_split(key, &split_params.boundary_account, retain_mode)
+                })
+                .cloned()
+                .collect::<Vec<_>>()
+        };
+        let mut right_child_keys = child_keys(RetainMode::Right);
+        populate(
+            &tries,
+            &mut chain_store,
+            &resharding_block.hash,
+            split_params.parent_shard,
+            parent_keys.clone(),
+        );
+        populate(
+            &tries,
+            &mut chain_store,
+            &resharding_block.hash,
+            split_params.left_child_shard,
+            child_keys(RetainMode::Left),
+        );
+        populate(
+            &tries,
+            &mut chain_store,
+            &resharding_block.hash,
+            split_params.right_child_shard,
+            right_child_keys.clone(),
+        );
+
+        let mut rng = ChaCha8Rng::seed_from_u64(42);
+        let result =
+            validate_split_shard(&chain_store, &tries, &split_params, resharding_block, &mut rng)
+                .unwrap();
+        assert_eq!(result.children_shards, split_params.children_shards());
+        assert!(result.trie_keys_checked > 0);
+        assert_eq!(result.num_mismatches, 0);
+
+        // A key of the left child which is also kept by the right child must be reported.
+        right_child_keys.extend(["aa", "bb", "cc"].into_iter().map(account_key));
+        populate(
+            &tries,
+            &mut chain_store,
+            &resharding_block.hash,
+            split_params.right_child_shard,
+            right_child_keys,
+        );
+        let result =
+            validate_split_shard(&chain_store, &tries, &split_params, resharding_block, &mut rng)
+                .unwrap();
+        assert!(result.num_mismatches > 0);
+        for mismatch in result.mismatches {
+            assert_eq!(mismatch.shard_uid, split_params.right_child_shard);
+            assert_eq!(mismatch.kind, SplitShardMismatchKind::UnexpectedKey);
+        }
+    }
+}
@@ -4,9 +4,9 @@ use crate::types::{
     PreparedTransactions, RuntimeAdapter, RuntimeStorageConfig, StorageDataSource, Tip,
 };
 use crate::Error;
-use borsh::BorshDeserialize;
 use errors::FromStateViewerErrors;
 use near_async::time::{Duration, Instant};
+use near_cache::SyncLruCache;
 use near_chain_configs::{GenesisConfig, ProtocolConfig, MIN_GC_NUM_EPOCHS_TO_KEEP};
 use near_crypto::PublicKey;
 use near_epoch_manager::{EpochManagerAdapter, EpochManagerHandle};
@@ -15,7 +15,7 @@ use near_pool::types::TransactionGroupIterator;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::apply::ApplyChunkReason;
 use near_primitives::congestion_info::{
-    CongestionControl, ExtendedCongestionInfo, RejectTransactionReason, ShardAcceptsTransactions,
+    CongestionControl, ExtendedCongestionInfo, ShardAcceptsTransactions,
 };
 use near_primitives::errors::{InvalidTxError, RuntimeError, StorageError};
 use near_primitives::hash::{hash, CryptoHash};
@@ -24,16 +24,18 @@ use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_part::PartId;
+use near_primitives::state_sync::{decode_state_part, CompressedStatePart};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::trie_key::TrieKey;
 use near_primitives::types::{
     AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, MerkleHash,
     ShardId, StateChangeCause, StateRoot, StateRootNode,
 };
+use near_primitives::utils::compression::CompressedData;
 use near_primitives::version::{ProtocolFeature, ProtocolVersion};
 use near_primitives::views::{
-    AccessKeyInfoView, CallResult, ContractCodeView, QueryRequest, QueryResponse,
-    QueryResponseKind, ViewStateResult,
+    AccessKeyInfoView, AccountBalanceView, CallResult, ContractCodeView, QueryRequest,
+    QueryResponse, QueryResponseKind, StateOverride, ViewStateResult,
 };
 use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
 use near_store::flat::FlatStorageManager;
@@ -45,7 +47,8 @@ use near_store::{
 use near_vm_runner::ContractCode;
 use near_vm_runner::{precompile_contract, ContractRuntimeCache};
 use node_runtime::adapter::ViewRuntimeAdapter;
-use node_runtime::state_viewer::{TrieViewer, ViewApplyState};
+use node_runtime::receiver_queue_stats::{ReceiverQueueChanges, ReceiverQueueStats};
+use node_runtime::state_viewer::{SimulationLimits, TrieViewer, ViewApplyState};
 use node_runtime::{
     validate_transaction, verify_and_charge_transaction, ApplyState, Runtime,
     ValidatorAccountsUpdate,
@@ -61,6 +64,10 @@ pub mod test_utils;
 #[cfg(test)]
 mod tests;
 
+/// Number of state roots to keep the receipt queue stats for. Covers a few blocks of all the
+/// shards, including forks.
+const RECEIVER_QUEUE_STATS_CACHE_SIZE: usize = 128;
+
 /// Defines Nightshade state transition and validator rotation.
 /// TODO: this possibly should be merged with the runtime cargo or at least reconciled on the interfaces.
 pub struct NightshadeRuntime {
@@ -75,6 +82,9 @@ pub struct NightshadeRuntime {
     epoch_manager: Arc<EpochManagerHandle>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    /// Usage of the receipt queues by receiver account, by the shard and its state root. Only
+    /// populated once requested, then kept up to date while applying the chunks.
+    receiver_queue_stats: SyncLruCache<(ShardUId, StateRoot), Arc<ReceiverQueueStats>>,
 }
 
 impl NightshadeRuntime {
@@ -85,6 +95,7 @@ impl NightshadeRuntime {
         epoch_manager: Arc<EpochManagerHandle>,
         trie_viewer_state_size_limit: Option<u64>,
         max_gas_burnt_view: Option<Gas>,
+        simulation_limits: Option<SimulationLimits>,
         runtime_config_store: Option<RuntimeConfigStore>,
         gc_num_epochs_to_keep: u64,
         trie_config: TrieConfig,
@@ -96,7 +107,8 @@ impl NightshadeRuntime {
         };
 
         let runtime = Runtime::new();
-        let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view);
+        let trie_viewer = TrieViewer::new(trie_viewer_state_size_limit, max_gas_burnt_view)
+            .with_simulation_limits(simulation_limits);
         let flat_storage_manager = FlatStorageManager::new(store.flat_store());
         let epoch_config = epoch_manager.read().get_epoch_config(genesis_config.protocol_version);
         let shard_uids: Vec<_> = epoch_config.shard_layout.shard_uids().collect();
@@ -128,6 +140,7 @@ impl NightshadeRuntime {
             epoch_manager,
             migration_data,
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            receiver_queue_stats: SyncLruCache::new(RECEIVER_QUEUE_STATS_CACHE_SIZE),
         })
     }
 
@@ -284,6 +297,11 @@ impl NightshadeRuntime {
             is_first_block_of_version
         );
 
+        let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_block_hash)?;
+        let prev_state_root = *trie.get_root();
+        // The changes are only collected once the stats of the shard were requested.
+        let record_receiver_queue_changes =
+            self.receiver_queue_stats.get(&(shard_uid, prev_state_root)).is_some();
         let apply_state = ApplyState {
             apply_reason,
             block_height,
@@ -307,6 +325,7 @@ impl NightshadeRuntime {
             },
             congestion_info,
             bandwidth_requests,
+            record_receiver_queue_changes,
         };
 
         let instant = Instant::now();
@@ -351,6 +370,12 @@ impl NightshadeRuntime {
         if let Some(mut metrics) = apply_result.metrics {
             metrics.report(&shard_label);
         }
+        self.update_receiver_queue_stats(
+            shard_uid,
+            prev_state_root,
+            apply_result.state_root,
+            &apply_result.receiver_queue_changes,
+        );
 
         let total_balance_burnt = apply_result
             .stats
@@ -361,8 +386,6 @@ impl NightshadeRuntime {
                 Error::Other("Integer overflow during burnt balance summation".to_string())
             })?;
 
-        let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_block_hash)?;
-
         let result = ApplyChunkResult {
             trie_changes: WrappedTrieChanges::new(
                 self.get_tries(),
@@ -386,11 +409,32 @@ impl NightshadeRuntime {
             bandwidth_requests: apply_result.bandwidth_requests,
             bandwidth_scheduler_state_hash: apply_result.bandwidth_scheduler_state_hash,
             contract_updates: apply_result.contract_updates,
+            delayed_receipts_indices: apply_result.delayed_receipts_indices,
         };
 
         Ok(result)
     }
 
+    /// Derives the stats at the post state root of a chunk from the ones at its prev state
+    /// root, if they're known.
+    fn update_receiver_queue_stats(
+        &self,
+        shard_uid: ShardUId,
+        prev_state_root: StateRoot,
+        state_root: StateRoot,
+        changes: &ReceiverQueueChanges,
+    ) {
+        if prev_state_root == state_root {
+            return;
+        }
+        let Some(prev_stats) = self.receiver_queue_stats.get(&(shard_uid, prev_state_root)) else {
+            return;
+        };
+        let mut stats = prev_stats.as_ref().clone();
+        stats.apply_changes(changes);
+        self.receiver_queue_stats.put((shard_uid, state_root), Arc::new(stats));
+    }
+
     fn get_gc_stop_height_impl(&self, block_hash: &CryptoHash) -> Result<BlockHeight, Error> {
         let epoch_manager = self.epoch_manager.read();
         // an epoch must have a first block.
@@ -521,7 +565,10 @@ impl RuntimeAdapter for NightshadeRuntime {
         state_root: StateRoot,
     ) -> Result<Trie, Error> {
         let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_hash)?;
-        Ok(self.tries.get_view_trie_for_shard(shard_uid, state_root))
+        // The State on top of `prev_hash` is read with the `ShardUId` mapping of the next block,
+        // so that the past State of a resharded shard is still found on archival nodes.
+        let block_height = self.epoch_manager.get_block_info(prev_hash)?.height() + 1;
+        Ok(self.tries.get_view_trie_for_shard_at_height(shard_uid, state_root, block_height))
     }
 
     fn get_flat_storage_manager(&self) -> FlatStorageManager {
@@ -551,18 +598,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             {
                 let receiver_shard =
                     self.account_id_to_shard_uid(transaction.transaction.receiver_id(), epoch_id)?;
-                let shard_id = receiver_shard.shard_id;
-                let err = match reason {
-                    RejectTransactionReason::IncomingCongestion { congestion_level }
-                    | RejectTransactionReason::OutgoingCongestion { congestion_level }
-                    | RejectTransactionReason::MemoryCongestion { congestion_level } => {
-                        InvalidTxError::ShardCongested { shard_id, congestion_level }
-                    }
-                    RejectTransactionReason::MissedChunks { missed_chunks } => {
-                        InvalidTxError::ShardStuck { shard_id, missed_chunks }
-                    }
-                };
-                return Ok(Some(err));
+                return Ok(Some(reason.into_invalid_tx_error(receiver_shard.shard_id)));
             }
         }
 
@@ -816,7 +852,7 @@ impl RuntimeAdapter for NightshadeRuntime {
         metrics::CONGESTION_PREPARE_TX_GAS_LIMIT
             .with_label_values(&[&shard_label])
             .set(i64::try_from(transactions_gas_limit).unwrap_or(i64::MAX));
-        result.storage_proof = state_update.trie.recorded_storage().map(|s| s.nodes);
+        result.storage_proof = state_update.trie.recorded_storage()?.map(|s| s.nodes);
         Ok(result)
     }
 
@@ -888,7 +924,11 @@ impl RuntimeAdapter for NightshadeRuntime {
             let protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
             let config = self.runtime_config_store.get_config(protocol_version);
             let proof_limit = config.witness_config.main_storage_proof_size_soft_limit;
-            trie = trie.recording_reads_with_proof_size_limit(proof_limit);
+            trie = if storage_config.keep_storage_proof {
+                trie.recording_reads_with_proof_size_limit(proof_limit)
+            } else {
+                trie.recording_read_sizes_with_proof_size_limit(proof_limit)
+            };
         }
 
         match self.process_state_update(
@@ -939,6 +979,39 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewAccountBalance { account_id } => {
+                let current_protocol_version = self
+                    .epoch_manager
+                    .read()
+                    .get_epoch_info(epoch_id)
+                    .map_err(|err| {
+                        crate::near_chain_primitives::error::QueryError::from_epoch_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?
+                    .protocol_version();
+                let account_balance = self
+                    .view_account_balance(
+                        &shard_uid,
+                        *state_root,
+                        account_id,
+                        current_protocol_version,
+                    )
+                    .map_err(|err| {
+                        crate::near_chain_primitives::error::QueryError::from_view_account_error(
+                            err,
+                            block_height,
+                            *block_hash,
+                        )
+                    })?;
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewAccountBalance(account_balance),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::ViewCode { account_id } => {
                 let contract_code = self
                     .view_contract_code(&shard_uid,  *state_root, account_id)
@@ -951,7 +1024,14 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
-            QueryRequest::CallFunction { account_id, method_name, args } => {
+            QueryRequest::CallFunction { account_id, method_name, args }
+            | QueryRequest::SimulateFunctionCall { account_id, method_name, args, .. } => {
+                let state_override = match request {
+                    QueryRequest::SimulateFunctionCall { state_override, .. } => {
+                        Some(state_override)
+                    }
+                    _ => None,
+                };
                 let mut logs = vec![];
                 let (epoch_height, current_protocol_version) = {
                     let epoch_manager = self.epoch_manager.read();
@@ -978,6 +1058,7 @@ impl RuntimeAdapter for NightshadeRuntime {
                         account_id,
                         method_name,
                         args.as_ref(),
+                        state_override,
                         &mut logs,
                         self.epoch_manager.as_ref(),
                         current_protocol_version,
@@ -1090,7 +1171,7 @@ impl RuntimeAdapter for NightshadeRuntime {
     }
 
     fn validate_state_part(&self, state_root: &StateRoot, part_id: PartId, data: &[u8]) -> bool {
-        match BorshDeserialize::try_from_slice(data) {
+        match decode_state_part(data) {
             Ok(trie_nodes) => {
                 match Trie::validate_state_part(state_root, part_id, trie_nodes) {
                     Ok(_) => true,
@@ -1103,6 +1184,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             }
             // Deserialization error means we've got the data from malicious peer
             Err(err) => {
+                crate::metrics::record_decode_failure(CompressedStatePart::NAME, &err);
                 tracing::error!(target: "state-parts", ?err, "State part deserialization error");
                 false
             }
@@ -1121,7 +1203,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             .with_label_values(&[&shard_id.to_string()])
             .start_timer();
 
-        let part = BorshDeserialize::try_from_slice(data)
+        let part = decode_state_part(data)
             .expect("Part was already validated earlier, so could never fail here");
         let ApplyStatePartResult { trie_changes, flat_state_delta, contract_codes } =
             Trie::apply_state_part(state_root, part_id, part);
@@ -1208,8 +1290,11 @@ impl RuntimeAdapter for NightshadeRuntime {
         genesis_config.num_chunk_only_producer_seats = epoch_config.num_chunk_only_producer_seats;
         genesis_config.minimum_validators_per_shard = epoch_config.minimum_validators_per_shard;
         genesis_config.minimum_stake_ratio = epoch_config.minimum_stake_ratio;
+        genesis_config.chunk_validator_minimum_stake_ratio =
+            epoch_config.chunk_validator_minimum_stake_ratio;
         genesis_config.shuffle_shard_assignment_for_chunk_producers =
             epoch_config.shuffle_shard_assignment_for_chunk_producers;
+        genesis_config.validator_blocklist = epoch_config.validator_blocklist;
 
         let runtime_config =
             self.runtime_config_store.get_config(protocol_version).as_ref().clone();
@@ -1267,6 +1352,25 @@ impl RuntimeAdapter for NightshadeRuntime {
         });
         Ok(())
     }
+
+    fn get_receiver_queue_stats(
+        &self,
+        shard_id: ShardId,
+        block_hash: &CryptoHash,
+        state_root: StateRoot,
+    ) -> Result<Arc<ReceiverQueueStats>, Error> {
+        let epoch_id = self.epoch_manager.get_epoch_id(block_hash)?;
+        let shard_uid = self.get_shard_uid_from_epoch_id(shard_id, &epoch_id)?;
+        if let Some(stats) = self.receiver_queue_stats.get(&(shard_uid, state_root)) {
+            return Ok(stats);
+        }
+        let protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
+        let runtime_config = self.runtime_config_store.get_config(protocol_version);
+        let trie = self.get_view_trie_for_shard(shard_id, block_hash, state_root)?;
+        let stats = Arc::new(ReceiverQueueStats::bootstrap(&trie, runtime_config)?);
+        self.receiver_queue_stats.put((shard_uid, state_root), stats.clone());
+        Ok(stats)
+    }
 }
 
 /// Get the limit on the number of new receipts imposed by the local congestion control.
@@ -1398,6 +1502,23 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         self.trie_viewer.view_account(&state_update, account_id)
     }
 
+    fn view_account_balance(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        current_protocol_version: ProtocolVersion,
+    ) -> Result<AccountBalanceView, node_runtime::state_viewer::errors::ViewAccountError> {
+        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        let runtime_config = self.runtime_config_store.get_config(current_protocol_version);
+        self.trie_viewer.view_account_balance(
+            &state_update,
+            account_id,
+            runtime_config,
+            current_protocol_version,
+        )
+    }
+
     fn view_contract_code(
         &self,
         shard_uid: &ShardUId,
@@ -1421,11 +1542,20 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        state_override: Option<&StateOverride>,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
     ) -> Result<Vec<u8>, node_runtime::state_viewer::errors::CallFunctionError> {
-        let state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        let mut state_update = self.tries.new_trie_update_view(*shard_uid, state_root);
+        let mut cache = None;
+        if let Some(state_override) = state_override {
+            cache = self.trie_viewer.apply_state_override(
+                &mut state_update,
+                contract_id,
+                state_override,
+            )?;
+        }
         let view_state = ViewApplyState {
             shard_id: shard_uid.shard_id(),
             block_height: height,
@@ -1435,7 +1565,8 @@ impl node_runtime::adapter::ViewRuntimeAdapter for NightshadeRuntime {
             epoch_height,
             block_timestamp,
             current_protocol_version,
-            cache: Some(self.compiled_contract_cache.handle()),
+            // Overridden code must never end up in the persistent cache.
+            cache: Some(cache.unwrap_or_else(|| self.compiled_contract_cache.handle())),
         };
         self.trie_viewer.call_function(
             state_update,
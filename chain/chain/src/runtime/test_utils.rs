@@ -27,6 +27,7 @@ impl NightshadeRuntime {
             epoch_manager,
             None,
             None,
+            None,
             Some(runtime_config_store),
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
@@ -57,6 +58,7 @@ impl NightshadeRuntime {
             epoch_manager,
             None,
             None,
+            None,
             runtime_config_store,
             gc_num_epochs_to_keep,
             trie_config,
@@ -138,6 +138,7 @@ impl TestEnv {
             epoch_manager.clone(),
             None,
             None,
+            None,
             Some(runtime_config_store),
             DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             Default::default(),
@@ -1681,6 +1682,7 @@ fn test_prepare_transactions_storage_proof() {
         use_flat_storage: true,
         source: StorageDataSource::Db,
         state_patch: Default::default(),
+        keep_storage_proof: true,
     };
 
     let proposed_transactions = prepare_transactions(
@@ -1701,6 +1703,7 @@ fn test_prepare_transactions_storage_proof() {
             nodes: proposed_transactions.storage_proof.unwrap(),
         }),
         state_patch: Default::default(),
+        keep_storage_proof: true,
     };
 
     let validated_transactions = prepare_transactions(
@@ -1725,6 +1728,7 @@ fn test_prepare_transactions_empty_storage_proof() {
         use_flat_storage: true,
         source: StorageDataSource::Db,
         state_patch: Default::default(),
+        keep_storage_proof: true,
     };
 
     let proposed_transactions = prepare_transactions(
@@ -1745,6 +1749,7 @@ fn test_prepare_transactions_empty_storage_proof() {
             nodes: PartialState::default(), // We use empty storage proof here.
         }),
         state_patch: Default::default(),
+        keep_storage_proof: true,
     };
 
     let validation_result = prepare_transactions(
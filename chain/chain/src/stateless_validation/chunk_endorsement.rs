@@ -2,9 +2,15 @@ use std::collections::HashMap;
 
 use itertools::Itertools;
 use near_chain_primitives::Error;
+use near_crypto::Signature;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::{Block, BlockHeader};
+use near_primitives::chunk_suppression::{is_chunk_suppressed, ChunkSuppression};
+use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
+use near_primitives::stateless_validation::ChunkProductionKey;
+use near_primitives::types::EpochId;
+use near_primitives::version::{ProtocolFeature, ProtocolVersion};
 
 /// This function validates the chunk_endorsements present in the block body. Validation does the following:
 ///    - Match number of chunks/shards with number of chunk endorsements vector.
@@ -13,6 +19,8 @@ use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 ///       - Verify that the signature are valid for given chunk_validator index.
 ///         Essentially, signature[i] should correspond with ordered_chunk_validators[i].
 ///       - Verify that the chunk has enough stake from chunk_validators to be endorsed.
+///    - For each old chunk, the only signature allowed is the signed suppression of the chunk by
+///      its chunk producer, see `near_primitives::chunk_suppression`.
 /// Note that while getting the chunk_validator_assignments, we use the chunk_header.height_created field.
 /// This is because chunk producer sends state witness to chunk validators assignment for `height_created`.
 /// We expect the endorsements to come from these chunk validators only.
@@ -44,28 +52,59 @@ pub fn validate_chunk_endorsements_in_block(
 
     let epoch_id = epoch_manager.get_epoch_id_from_prev_block(block.header().prev_hash())?;
     let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+    let protocol_version = epoch_manager.get_epoch_protocol_version(&epoch_id)?;
     for (chunk_header, signatures) in
         block.chunks().iter_deprecated().zip(block.chunk_endorsements())
     {
-        // For old chunks, we optimize the block by not including the chunk endorsements.
+        let shard_id = chunk_header.shard_id();
+        let shard_index = shard_layout.get_shard_index(shard_id)?;
+
+        // For old chunks, we optimize the block by not including the chunk endorsements. The
+        // only signature allowed is the signed suppression of the chunk by its chunk producer.
         if chunk_header.height_included() != block.header().height() {
-            if !signatures.is_empty() {
-                tracing::error!(
-                    target: "chain",
-                    chunk_header_height_included = chunk_header.height_included(),
-                    block_header_height = block.header().height(),
-                    "Expected chunk endorsements to be empty for old chunks in current block",
-                );
-                return Err(Error::InvalidChunkEndorsement);
+            let is_suppressed = match signatures.as_slice() {
+                [] => false,
+                [Some(signature)] => {
+                    validate_chunk_suppression(
+                        epoch_manager,
+                        block,
+                        &epoch_id,
+                        protocol_version,
+                        chunk_header,
+                        signature,
+                    )?;
+                    true
+                }
+                _ => {
+                    tracing::error!(
+                        target: "chain",
+                        chunk_header_height_included = chunk_header.height_included(),
+                        block_header_height = block.header().height(),
+                        "Expected chunk endorsements to be empty for old chunks in current block",
+                    );
+                    return Err(Error::InvalidChunkEndorsement);
+                }
+            };
+            // The chunk must be marked as suppressed exactly when the block holds the signed
+            // suppression.
+            if let Some(endorsements_bitmap) = endorsements_bitmap {
+                if endorsements_bitmap.is_chunk_suppressed(shard_index) != is_suppressed {
+                    return Err(Error::InvalidChunkEndorsementBitmap(format!(
+                        "Suppressed chunk mark in bitmap does not match the signatures of shard {}, expected suppressed={}",
+                        shard_id, is_suppressed,
+                    )));
+                }
+            } else if is_suppressed {
+                return Err(Error::InvalidChunkEndorsementBitmap(format!(
+                    "Chunk of shard {} is suppressed, but the block has no endorsements bitmap",
+                    shard_id,
+                )));
             }
             continue;
         }
 
         // Validation for chunks in each shard
         // The signatures from chunk validators for each shard must match the ordered_chunk_validators
-        let shard_id = chunk_header.shard_id();
-        let shard_index = shard_layout.get_shard_index(shard_id)?;
-
         let chunk_validator_assignments = epoch_manager.get_chunk_validator_assignments(
             &epoch_id,
             shard_id,
@@ -150,6 +189,49 @@ pub fn validate_chunk_endorsements_in_block(
     Ok(())
 }
 
+/// Validates the signed suppression of the chunk of a shard with no new chunk in the block: the
+/// protocol allows suppressing the chunk after the last chunk of the shard, and the signature is
+/// from the chunk producer of the chunk that would have been built on the previous block.
+fn validate_chunk_suppression(
+    epoch_manager: &dyn EpochManagerAdapter,
+    block: &Block,
+    epoch_id: &EpochId,
+    protocol_version: ProtocolVersion,
+    last_chunk_header: &ShardChunkHeader,
+    signature: &Signature,
+) -> Result<(), Error> {
+    let shard_id = last_chunk_header.shard_id();
+    if !is_chunk_suppressed(protocol_version, last_chunk_header, block.header().height()) {
+        tracing::error!(
+            target: "chain",
+            ?shard_id,
+            last_chunk_height_included = last_chunk_header.height_included(),
+            block_header_height = block.header().height(),
+            "Chunk suppression is not allowed after the last chunk of the shard",
+        );
+        return Err(Error::InvalidChunkEndorsement);
+    }
+    let prev_block_hash = *block.header().prev_hash();
+    let prev_height = epoch_manager.get_block_info(&prev_block_hash)?.height();
+    let key = ChunkProductionKey { epoch_id: *epoch_id, height_created: prev_height + 1, shard_id };
+    let chunk_producer = epoch_manager.get_chunk_producer_info(&key)?;
+    if !ChunkSuppression::validate_signature(
+        &key,
+        prev_block_hash,
+        signature,
+        chunk_producer.public_key(),
+    ) {
+        tracing::error!(
+            target: "chain",
+            ?shard_id,
+            chunk_producer = ?chunk_producer.account_id(),
+            "Invalid chunk suppression signature",
+        );
+        return Err(Error::InvalidChunkEndorsement);
+    }
+    Ok(())
+}
+
 /// Validates the [`ChunkEndorsementBitmap`] in the [`BlockHeader`] if it is present, otherwise returns an error.
 /// This function must be called only if ChunkEndorsementInBlockHeader feature is enabled.
 pub fn validate_chunk_endorsements_in_header(
@@ -170,9 +252,22 @@ pub fn validate_chunk_endorsements_in_header(
             format!("Number of shards in bitmap and in epoch do not match: shards in bitmap={}, shards in epoch={}",
                 chunk_endorsements.num_shards(), shard_ids.len())));
     }
+    let chunk_suppression_enabled = ProtocolFeature::EmptyChunkSuppression
+        .enabled(epoch_manager.get_epoch_protocol_version(&epoch_id)?);
     let chunk_mask = header.chunk_mask();
     for shard_id in shard_ids.into_iter() {
         let shard_index = shard_layout.get_shard_index(shard_id)?;
+        // The bitmap of a shard with no new chunk may only mark the chunk as suppressed, which
+        // `validate_chunk_endorsements_in_block` checks against the chunks in the block.
+        if !chunk_mask[shard_index] && chunk_endorsements.is_chunk_suppressed(shard_index) {
+            if !chunk_suppression_enabled {
+                return Err(Error::InvalidChunkEndorsementBitmap(format!(
+                    "Chunk of shard {} is marked as suppressed, but chunk suppression is disabled",
+                    shard_id,
+                )));
+            }
+            continue;
+        }
         // For old chunks, we optimize the block and its header by not including the chunk endorsements and
         // corresponding bitmaps. Thus, we expect that the bitmap is empty for shard with no new chunk.
         if chunk_mask[shard_index] != (chunk_endorsements.len(shard_index).unwrap() > 0) {
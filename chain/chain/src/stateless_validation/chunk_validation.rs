@@ -27,6 +27,7 @@ use near_primitives::merkle::merklize;
 use near_primitives::receipt::Receipt;
 use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::sharding::{ChunkHash, ReceiptProof, ShardChunkHeader};
+use near_primitives::stateless_validation::partial_witness::MAX_COMPRESSED_STATE_WITNESS_SIZE;
 use near_primitives::stateless_validation::state_witness::{
     ChunkStateWitness, EncodedChunkStateWitness,
 };
@@ -299,26 +300,25 @@ fn get_resharding_transition(
         height: prev_header.height(),
         prev_hash: *prev_header.prev_hash(),
     };
-    let params = match ReshardingEventType::from_shard_layout(&shard_layout, block_info)? {
-        Some(ReshardingEventType::SplitShard(params)) => params,
-        None => return Ok(None),
-    };
-
-    if params.left_child_shard == shard_uid {
-        Ok(Some(ImplicitTransitionParams::Resharding(
-            params.boundary_account,
-            RetainMode::Left,
-            shard_uid,
-        )))
-    } else if params.right_child_shard == shard_uid {
-        Ok(Some(ImplicitTransitionParams::Resharding(
-            params.boundary_account,
-            RetainMode::Right,
-            shard_uid,
-        )))
-    } else {
-        Ok(None)
+    // Only the event which created `shard_uid` matters, the other parents may be split in the
+    // same shard layout change.
+    for event in ReshardingEventType::from_shard_layout(&shard_layout, block_info)? {
+        let ReshardingEventType::SplitShard(params) = event;
+        if params.left_child_shard == shard_uid {
+            return Ok(Some(ImplicitTransitionParams::Resharding(
+                params.boundary_account,
+                RetainMode::Left,
+                shard_uid,
+            )));
+        } else if params.right_child_shard == shard_uid {
+            return Ok(Some(ImplicitTransitionParams::Resharding(
+                params.boundary_account,
+                RetainMode::Right,
+                shard_uid,
+            )));
+        }
     }
+    Ok(None)
 }
 
 /// Pre-validates the chunk's receipts and transactions against the chain.
@@ -399,6 +399,7 @@ pub fn pre_validate_chunk_state_witness(
                     nodes: state_witness.new_transactions_validation_state.clone(),
                 }),
                 state_patch: Default::default(),
+                keep_storage_proof: true,
             };
 
             match validate_prepared_transactions(
@@ -465,6 +466,7 @@ pub fn pre_validate_chunk_state_witness(
                     nodes: state_witness.main_state_transition.base_state.clone(),
                 }),
                 state_patch: Default::default(),
+                keep_storage_proof: true,
             },
         })
     };
@@ -702,6 +704,7 @@ pub fn validate_chunk_state_witness(
                             nodes: transition.base_state,
                         }),
                         state_patch: Default::default(),
+                        keep_storage_proof: true,
                     },
                 };
                 let OldChunkResult { apply_result, .. } = apply_old_chunk(
@@ -743,7 +746,8 @@ pub fn validate_chunk_state_witness(
                     retain_mode,
                 )?;
 
-                let new_root = parent_trie.retain_split_shard(&boundary_account, retain_mode)?;
+                let new_root =
+                    parent_trie.retain_split_shard(&boundary_account, retain_mode)?.new_root;
 
                 (child_shard_uid, new_root, child_congestion_info)
             }
@@ -794,6 +798,42 @@ pub fn apply_result_to_chunk_extra(
 }
 
 impl Chain {
+    /// Runs the same checks on a freshly produced witness that chunk validators run on a witness
+    /// received from the network: the compressed size limit, the encoding roundtrip,
+    /// pre-validation and the main state transition.
+    ///
+    /// Unlike `shadow_validate_state_witness`, the validation is done synchronously, so that the
+    /// chunk producer can refuse to distribute a witness which would be rejected downstream.
+    pub fn validate_produced_state_witness(
+        &self,
+        witness: &ChunkStateWitness,
+        epoch_manager: &dyn EpochManagerAdapter,
+        runtime_adapter: &dyn RuntimeAdapter,
+    ) -> Result<(), Error> {
+        let (encoded_witness, _raw_witness_size) = EncodedChunkStateWitness::encode(witness)?;
+        let witness_size = encoded_witness.size_bytes() as u64;
+        if witness_size > MAX_COMPRESSED_STATE_WITNESS_SIZE.as_u64() {
+            return Err(Error::InvalidChunkStateWitness(format!(
+                "Compressed witness size {} exceeds the limit of {}",
+                witness_size, MAX_COMPRESSED_STATE_WITNESS_SIZE
+            )));
+        }
+        let (decoded_witness, _raw_witness_size) = encoded_witness.decode()?;
+        let pre_validation_output = pre_validate_chunk_state_witness(
+            &decoded_witness,
+            self,
+            epoch_manager,
+            runtime_adapter,
+        )?;
+        validate_chunk_state_witness(
+            decoded_witness,
+            pre_validation_output,
+            epoch_manager,
+            runtime_adapter,
+            &MainStateTransitionCache::default(),
+        )
+    }
+
     pub fn shadow_validate_state_witness(
         &self,
         witness: ChunkStateWitness,
@@ -3,7 +3,10 @@ use near_o11y::metrics::{
     try_create_int_counter_vec, try_create_int_gauge, HistogramVec, IntCounter, IntCounterVec,
     IntGauge,
 };
-use near_primitives::stateless_validation::state_witness::ChunkStateWitness;
+use near_primitives::stateless_validation::state_witness::{
+    ChunkStateWitness, EncodedChunkStateWitness,
+};
+use near_primitives::utils::compression::CompressedData;
 use std::sync::LazyLock;
 
 pub static SAVE_LATEST_WITNESS_GENERATE_UPDATE_TIME: LazyLock<HistogramVec> = LazyLock::new(|| {
@@ -79,6 +82,16 @@ pub static CHUNK_WITNESS_VALIDATION_FAILED_TOTAL: LazyLock<IntCounterVec> = Lazy
     .unwrap()
 });
 
+pub static PRODUCED_CHUNK_WITNESS_VALIDATION_FAILED_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_produced_chunk_witness_validation_failed_total",
+            "Number of produced witnesses which failed local validation and weren't distributed",
+            &["shard_id", "error"],
+        )
+        .unwrap()
+    });
+
 pub(crate) static CHUNK_STATE_WITNESS_VALIDATION_TIME: LazyLock<HistogramVec> =
     LazyLock::new(|| {
         try_create_histogram_vec(
@@ -179,6 +192,11 @@ fn record_witness_size_metrics_fallible(
     encoded_size: usize,
     witness: &ChunkStateWitness,
 ) -> Result<(), std::io::Error> {
+    crate::metrics::record_compression_ratio(
+        EncodedChunkStateWitness::NAME,
+        decoded_size,
+        encoded_size,
+    );
     let shard_id = witness.chunk_header.shard_id().to_string();
     CHUNK_STATE_WITNESS_RAW_SIZE
         .with_label_values(&[shard_id.as_str()])
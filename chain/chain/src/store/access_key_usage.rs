@@ -0,0 +1,236 @@
+//! This module maintains `DBCol::AccessKeyUsage`, the height of the last block in which
+//! every access key signed a transaction. It allows users auditing their accounts to
+//! identify the stale keys to remove.
+//! The column is optional, it's only maintained if `save_access_key_usage` is enabled.
+//! It only keeps the latest use of every key, so it's not garbage collected.
+
+use std::collections::HashSet;
+
+use near_chain_primitives::Error;
+use near_crypto::PublicKey;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::ExecutionOutcomeWithId;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_store::DBCol;
+
+use super::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
+
+/// Separates the account id from the public key in the keys of `DBCol::AccessKeyUsage`.
+const ACCESS_KEY_USAGE_SEPARATOR: u8 = b',';
+
+pub(crate) fn get_access_key_usage_key(account_id: &AccountId, public_key: &PublicKey) -> Vec<u8> {
+    let mut res = Vec::with_capacity(account_id.len() + 1 + public_key.len());
+    res.extend_from_slice(account_id.as_bytes());
+    res.push(ACCESS_KEY_USAGE_SEPARATOR);
+    borsh::to_writer(&mut res, public_key).expect("serializing a public key can't fail");
+    res
+}
+
+impl ChainStore {
+    pub fn save_access_key_usage_enabled(&self) -> bool {
+        self.save_access_key_usage
+    }
+
+    pub fn set_save_access_key_usage(&mut self, save_access_key_usage: bool) {
+        self.save_access_key_usage = save_access_key_usage;
+    }
+
+    /// Returns the height of the last block in which the access key signed a transaction,
+    /// if it did since the usage is recorded.
+    pub fn get_access_key_last_used_height(
+        &self,
+        account_id: &AccountId,
+        public_key: &PublicKey,
+    ) -> Result<Option<BlockHeight>, Error> {
+        Ok(self
+            .store()
+            .get_ser(DBCol::AccessKeyUsage, &get_access_key_usage_key(account_id, public_key))?)
+    }
+}
+
+impl<'a> ChainStoreUpdate<'a> {
+    /// Records the use of the access keys which signed the transactions of a chunk in
+    /// `DBCol::AccessKeyUsage`, if enabled. Only the transactions converted to receipts, i.e.
+    /// having an outcome, are accounted for.
+    pub fn save_access_key_usage(
+        &mut self,
+        height: BlockHeight,
+        chunk_hash: &ChunkHash,
+        outcomes: &[ExecutionOutcomeWithId],
+    ) -> Result<(), Error> {
+        if !self.chain_store.save_access_key_usage {
+            return Ok(());
+        }
+        let chunk = self.get_chunk(chunk_hash)?;
+        let outcome_ids: HashSet<_> = outcomes.iter().map(|outcome| outcome.id).collect();
+
+        let mut store_update = self.store().store_update();
+        for transaction in chunk.transactions() {
+            if !outcome_ids.contains(&transaction.get_hash()) {
+                continue;
+            }
+            let key = get_access_key_usage_key(
+                transaction.transaction.signer_id(),
+                transaction.transaction.public_key(),
+            );
+            // Blocks on forks and chunks applied during catchup may be older than the recorded
+            // use, which must not go backwards.
+            let last_used_height: Option<BlockHeight> =
+                self.store().get_ser(DBCol::AccessKeyUsage, &key)?;
+            if last_used_height < Some(height) {
+                store_update.set_ser(DBCol::AccessKeyUsage, &key, &height)?;
+            }
+        }
+        self.merge(store_update);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_async::time::Clock;
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_primitives::bandwidth_scheduler::BandwidthRequests;
+    use near_primitives::congestion_info::CongestionInfo;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::sharding::{
+        ShardChunk, ShardChunkHeader, ShardChunkHeaderV3, ShardChunkV2,
+    };
+    use near_primitives::transaction::{
+        ExecutionOutcome, ExecutionOutcomeWithId, SignedTransaction,
+    };
+    use near_primitives::types::{AccountId, BlockHeight, ShardId};
+    use near_primitives::validator_signer::EmptyValidatorSigner;
+    use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
+
+    use crate::test_utils::get_chain;
+    use crate::Chain;
+
+    fn create_transaction(account_id: &str, nonce: u64) -> SignedTransaction {
+        let account_id: AccountId = account_id.parse().unwrap();
+        let signer = InMemorySigner::from_seed(account_id.clone(), KeyType::ED25519, "seed");
+        SignedTransaction::send_money(
+            nonce,
+            account_id,
+            "bob".parse().unwrap(),
+            &signer,
+            10,
+            CryptoHash::default(),
+        )
+    }
+
+    fn create_chunk(height: BlockHeight, transactions: Vec<SignedTransaction>) -> ShardChunk {
+        let congestion_info = ProtocolFeature::CongestionControl
+            .enabled(PROTOCOL_VERSION)
+            .then_some(CongestionInfo::default());
+        let header = ShardChunkHeader::V3(ShardChunkHeaderV3::new(
+            PROTOCOL_VERSION,
+            CryptoHash::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            height,
+            ShardId::new(0),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            congestion_info,
+            BandwidthRequests::default_for_protocol_version(PROTOCOL_VERSION),
+            &EmptyValidatorSigner::default().into(),
+        ));
+        ShardChunk::V2(ShardChunkV2 {
+            chunk_hash: header.chunk_hash(),
+            header,
+            transactions,
+            prev_outgoing_receipts: vec![],
+        })
+    }
+
+    /// Applies a chunk with `transactions` at `height`, of which only the ones in `converted`
+    /// have an outcome.
+    fn apply_chunk(
+        chain: &mut Chain,
+        height: BlockHeight,
+        transactions: &[SignedTransaction],
+        converted: &[SignedTransaction],
+    ) {
+        let chunk = create_chunk(height, transactions.to_vec());
+        let chunk_hash = chunk.chunk_hash();
+        let outcomes: Vec<_> = converted
+            .iter()
+            .map(|transaction| ExecutionOutcomeWithId {
+                id: transaction.get_hash(),
+                outcome: ExecutionOutcome::default(),
+            })
+            .collect();
+        let mut store_update = chain.mut_chain_store().store_update();
+        store_update.save_chunk(chunk);
+        store_update.save_access_key_usage(height, &chunk_hash, &outcomes).unwrap();
+        store_update.commit().unwrap();
+    }
+
+    fn last_used_height(chain: &Chain, transaction: &SignedTransaction) -> Option<BlockHeight> {
+        let transaction = &transaction.transaction;
+        chain
+            .chain_store()
+            .get_access_key_last_used_height(transaction.signer_id(), transaction.public_key())
+            .unwrap()
+    }
+
+    #[test]
+    fn test_access_key_usage_disabled() {
+        let mut chain = get_chain(Clock::real());
+        let transaction = create_transaction("alice", 1);
+        apply_chunk(&mut chain, 1, &[transaction.clone()], &[transaction.clone()]);
+        assert_eq!(last_used_height(&chain, &transaction), None);
+    }
+
+    /// Only the transactions converted to receipts count as a use of their access key.
+    #[test]
+    fn test_access_key_usage_requires_outcome() {
+        let mut chain = get_chain(Clock::real());
+        chain.mut_chain_store().set_save_access_key_usage(true);
+        let converted = create_transaction("alice", 1);
+        let dropped = create_transaction("carol", 1);
+        apply_chunk(&mut chain, 1, &[converted.clone(), dropped.clone()], &[converted.clone()]);
+        assert_eq!(last_used_height(&chain, &converted), Some(1));
+        assert_eq!(last_used_height(&chain, &dropped), None);
+    }
+
+    /// The recorded use only moves forward, e.g. when a fork is applied after the canonical
+    /// chain.
+    #[test]
+    fn test_access_key_usage_keeps_latest_height() {
+        let mut chain = get_chain(Clock::real());
+        chain.mut_chain_store().set_save_access_key_usage(true);
+        let transaction = create_transaction("alice", 1);
+        apply_chunk(&mut chain, 5, &[transaction.clone()], &[transaction.clone()]);
+        assert_eq!(last_used_height(&chain, &transaction), Some(5));
+
+        let older_transaction = create_transaction("alice", 2);
+        apply_chunk(&mut chain, 3, &[older_transaction.clone()], &[older_transaction]);
+        assert_eq!(last_used_height(&chain, &transaction), Some(5));
+
+        let newer_transaction = create_transaction("alice", 3);
+        apply_chunk(&mut chain, 7, &[newer_transaction.clone()], &[newer_transaction]);
+        assert_eq!(last_used_height(&chain, &transaction), Some(7));
+    }
+
+    /// The usage is recorded per account and key, so the same key added to another account
+    /// is tracked separately.
+    #[test]
+    fn test_access_key_usage_key_per_account() {
+        let alice: AccountId = "alice".parse().unwrap();
+        let carol: AccountId = "carol".parse().unwrap();
+        let public_key =
+            InMemorySigner::from_seed(alice.clone(), KeyType::ED25519, "seed").public_key();
+        assert_ne!(
+            super::get_access_key_usage_key(&alice, &public_key),
+            super::get_access_key_usage_key(&carol, &public_key)
+        );
+    }
+}
@@ -0,0 +1,114 @@
+//! This module maintains `DBCol::AccountReceipts`, an index of the receipts executed
+//! by (incoming) or created by (outgoing) every account. It allows listing the recent
+//! activity of an account without scanning all the blocks.
+//! The index is optional, it's only maintained if `save_account_receipts` is enabled,
+//! and it's garbage collected together with the outcomes of the indexed blocks.
+
+use std::collections::{HashMap, HashSet};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_chain_primitives::Error;
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::ExecutionOutcomeWithId;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+use near_schema_checker_lib::ProtocolSchema;
+use near_store::DBCol;
+
+use super::secondary_indexes::{get_account_index_key, iter_account_index, AccountIndexScan};
+use super::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
+
+/// Value of `DBCol::AccountReceipts`.
+#[derive(
+    Debug, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default, ProtocolSchema,
+)]
+pub struct AccountReceiptIndexEntry {
+    /// Block in which the receipt was executed or created.
+    pub block_hash: CryptoHash,
+    pub shard_id: ShardId,
+    /// The receipt was executed by the account, i.e. the account is its receiver.
+    pub incoming: bool,
+    /// The receipt was created while executing a transaction or a receipt of the account.
+    pub outgoing: bool,
+}
+
+impl ChainStore {
+    pub fn save_account_receipts_enabled(&self) -> bool {
+        self.save_account_receipts
+    }
+
+    pub fn set_save_account_receipts(&mut self, save_account_receipts: bool) {
+        self.save_account_receipts = save_account_receipts;
+    }
+
+    /// Returns the indexed receipts of the account, ordered by height and receipt id, starting
+    /// from `from_height`. If `after` is given, only the receipts following it are returned,
+    /// which allows paginating over the results. Entries not accepted by `filter` are skipped
+    /// and don't count towards the `limit`; the scan stops early if too many are skipped,
+    /// see `iter_account_index`.
+    pub fn get_account_receipts(
+        &self,
+        account_id: &AccountId,
+        from_height: BlockHeight,
+        after: Option<(BlockHeight, CryptoHash)>,
+        limit: usize,
+        filter: impl Fn(&AccountReceiptIndexEntry) -> bool,
+    ) -> Result<AccountIndexScan<AccountReceiptIndexEntry>, Error> {
+        iter_account_index(
+            self.store(),
+            DBCol::AccountReceipts,
+            account_id,
+            from_height,
+            after,
+            limit,
+            filter,
+        )
+    }
+}
+
+impl<'a> ChainStoreUpdate<'a> {
+    /// Indexes the receipts of the outcomes of applying a chunk in `DBCol::AccountReceipts`,
+    /// if the index is enabled. The outcomes of the transactions of the chunk only index
+    /// the receipts they were converted to.
+    pub fn save_account_receipts(
+        &mut self,
+        height: BlockHeight,
+        block_hash: &CryptoHash,
+        shard_id: ShardId,
+        chunk_hash: &ChunkHash,
+        outcomes: &[ExecutionOutcomeWithId],
+    ) -> Result<(), Error> {
+        if !self.chain_store.save_account_receipts {
+            return Ok(());
+        }
+        let chunk = self.get_chunk(chunk_hash)?;
+        let transaction_hashes: HashSet<CryptoHash> =
+            chunk.transactions().iter().map(|transaction| transaction.get_hash()).collect();
+
+        let new_entry =
+            || AccountReceiptIndexEntry { block_hash: *block_hash, shard_id, ..Default::default() };
+        let mut entries: HashMap<Vec<u8>, AccountReceiptIndexEntry> = HashMap::new();
+        for outcome_with_id in outcomes {
+            let executor_id = &outcome_with_id.outcome.executor_id;
+            if !transaction_hashes.contains(&outcome_with_id.id) {
+                entries
+                    .entry(get_account_index_key(executor_id, height, &outcome_with_id.id))
+                    .or_insert_with(new_entry)
+                    .incoming = true;
+            }
+            for receipt_id in &outcome_with_id.outcome.receipt_ids {
+                entries
+                    .entry(get_account_index_key(executor_id, height, receipt_id))
+                    .or_insert_with(new_entry)
+                    .outgoing = true;
+            }
+        }
+
+        let mut store_update = self.store().store_update();
+        for (key, entry) in entries {
+            store_update.set_ser(DBCol::AccountReceipts, &key, &entry)?;
+        }
+        self.merge(store_update);
+        Ok(())
+    }
+}
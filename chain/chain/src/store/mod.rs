@@ -6,6 +6,7 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use chrono::Utc;
 use near_cache::CellLruCache;
 
+use near_chain_configs::SecondaryIndexesConfig;
 use near_chain_primitives::error::Error;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::Tip;
@@ -54,10 +55,20 @@ use crate::types::{Block, BlockHeader, LatestKnown};
 use near_store::db::{StoreStatistics, STATE_SYNC_DUMP_KEY};
 use std::sync::Arc;
 
+mod access_key_usage;
+mod account_receipts;
 mod latest_witnesses;
 mod merkle_proof;
+mod secondary_indexes;
+mod timestamp_index;
+pub use account_receipts::AccountReceiptIndexEntry;
 pub use latest_witnesses::LatestWitnessesInfo;
 pub use merkle_proof::MerkleProofAccess;
+pub(crate) use secondary_indexes::{get_account_index_key, get_block_timestamp_key};
+pub use secondary_indexes::{
+    AccountIndexScan, SecondaryIndexEntry, MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES,
+};
+pub use timestamp_index::BlocksByTimestampBackfill;
 
 // TODO: Get rid of caches in chain store
 const CACHE_SIZE: usize = 1;
@@ -457,6 +468,12 @@ pub struct ChainStore {
     /// - archive is true, cold_store is configured and migration to split_storage is finished - node
     /// working in split storage mode needs trie changes in order to do garbage collection on hot.
     save_trie_changes: bool,
+    /// Whether to maintain the `DBCol::AccountReceipts` index, see `save_account_receipts`.
+    save_account_receipts: bool,
+    /// Secondary indexes to maintain, see `SecondaryIndexesConfig`.
+    secondary_indexes: SecondaryIndexesConfig,
+    /// Whether to maintain the `DBCol::AccessKeyUsage` column, see `save_access_key_usage`.
+    save_access_key_usage: bool,
 }
 
 fn option_to_not_found<T, F>(res: io::Result<Option<T>>, field_name: F) -> Result<T, Error>
@@ -498,6 +515,9 @@ impl ChainStore {
             block_ordinal_to_hash: CellLruCache::new(CACHE_SIZE),
             processed_block_heights: CellLruCache::new(CACHE_SIZE),
             save_trie_changes,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
         }
     }
 
@@ -2725,16 +2745,28 @@ impl<'a> ChainStoreUpdate<'a> {
 
 #[cfg(test)]
 mod tests {
-    use near_async::time::Clock;
+    use assert_matches::assert_matches;
+    use near_async::time::{Clock, Duration, FakeClock};
     use std::sync::Arc;
 
+    use super::{
+        get_account_index_key, get_block_timestamp_key, AccountReceiptIndexEntry,
+        BlocksByTimestampBackfill, ChainStoreAccess, MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES,
+    };
     use crate::test_utils::get_chain;
+    use crate::types::{Block, Tip};
+    use crate::Chain;
+    use near_chain_configs::SecondaryIndexesConfig;
     use near_primitives::errors::InvalidTxError;
     use near_primitives::hash::hash;
     use near_primitives::test_utils::create_test_signer;
     use near_primitives::test_utils::TestBlockBuilder;
-    use near_primitives::types::EpochId;
+    use near_primitives::types::{AccountId, BlockHeight, EpochId};
     use near_primitives::utils::index_to_bytes;
+    use near_store::background_migration::{
+        run_background_migration_batch, BackgroundMigrationStatus,
+    };
+    use near_store::DBCol;
 
     #[test]
     fn test_tx_validity_long_fork() {
@@ -2928,4 +2960,127 @@ mod tests {
         assert_ne!(block_hash, block_hash1);
         assert_ne!(epoch_id_to_hash, epoch_id_to_hash1);
     }
+
+    /// The lookups by timestamp give the same results with the index of the blocks by timestamp,
+    /// which also holds the blocks of the forks, with an index missing some blocks and with the
+    /// binary search used without the index.
+    #[test]
+    fn test_block_height_by_timestamp() {
+        let mut chain = get_chain(Clock::real());
+        chain.mut_chain_store().set_secondary_indexes(SecondaryIndexesConfig {
+            blocks_by_timestamp: true,
+            ..SecondaryIndexesConfig::default()
+        });
+        let genesis = chain.get_block_by_height(0).unwrap();
+        let clock = FakeClock::new(genesis.header().timestamp());
+        let signer = Arc::new(create_test_signer("test1"));
+        let save_head = |chain: &mut Chain, prev: &Block, height: BlockHeight| {
+            clock.advance(Duration::seconds(1));
+            let block =
+                TestBlockBuilder::new(clock.clock(), prev, signer.clone()).height(height).build();
+            let mut store_update = chain.mut_chain_store().store_update();
+            store_update.save_block(block.clone());
+            store_update.save_block_header(block.header().clone()).unwrap();
+            store_update.save_head(&Tip::from_header(block.header())).unwrap();
+            store_update.save_secondary_indexes(&block).unwrap();
+            store_update.commit().unwrap();
+            block
+        };
+        let block1 = save_head(&mut chain, &genesis, 1);
+        let block2 = save_head(&mut chain, &block1, 2);
+        save_head(&mut chain, &block2, 3);
+
+        const SECOND: u64 = 1_000_000_000;
+        let start = genesis.header().raw_timestamp();
+        let lookup = |chain: &Chain, timestamp| {
+            chain.chain_store().get_block_height_by_timestamp(timestamp).unwrap()
+        };
+        let check = |chain: &Chain| {
+            assert_eq!(lookup(chain, start - 1), None);
+            assert_eq!(lookup(chain, start), Some(0));
+            assert_eq!(lookup(chain, start + SECOND * 3 / 2), Some(1));
+            assert_eq!(lookup(chain, start + 2 * SECOND), Some(2));
+            assert_eq!(lookup(chain, start + 10 * SECOND), Some(3));
+        };
+        check(&chain);
+
+        // A fork replaces the block at height 3, produced a second later. The replaced block
+        // stays in the index.
+        save_head(&mut chain, &block2, 3);
+        let store = chain.chain_store().store().clone();
+        assert_eq!(store.iter(DBCol::BlocksByTimestamp).count(), 4);
+        check(&chain);
+        assert_eq!(lookup(&chain, start + 3 * SECOND), Some(2));
+        assert_eq!(lookup(&chain, start + 4 * SECOND), Some(3));
+
+        let mut store_update = store.store_update();
+        store_update.delete(
+            DBCol::BlocksByTimestamp,
+            &get_block_timestamp_key(block2.header().raw_timestamp(), block2.hash()),
+        );
+        store_update.commit().unwrap();
+        check(&chain);
+
+        let mut store_update = store.store_update();
+        store_update.delete_all(DBCol::BlocksByTimestamp);
+        store_update.commit().unwrap();
+        check(&chain);
+        assert_eq!(lookup(&chain, start + 3 * SECOND), Some(2));
+
+        // The backfill indexes all the blocks again, the genesis block included.
+        let backfill = BlocksByTimestampBackfill::new(store.clone());
+        assert_matches!(
+            run_background_migration_batch(&store, &backfill, 100).unwrap(),
+            BackgroundMigrationStatus::InProgress { num_migrated: 5, .. }
+        );
+        assert_matches!(
+            run_background_migration_batch(&store, &backfill, 100).unwrap(),
+            BackgroundMigrationStatus::Done { num_migrated: 5 }
+        );
+        assert_eq!(store.iter(DBCol::BlocksByTimestamp).count(), 5);
+        check(&chain);
+    }
+
+    /// A filtered lookup of the account receipts stops after skipping too many entries and
+    /// returns where to continue from.
+    #[test]
+    fn test_account_receipts_skipped_entries_are_capped() {
+        let chain = get_chain(Clock::real());
+        let store = chain.chain_store().store();
+        let account_id: AccountId = "test1".parse().unwrap();
+        let num_outgoing = MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES as u64 + 5;
+        let mut store_update = store.store_update();
+        for height in 1..=num_outgoing + 1 {
+            let entry = AccountReceiptIndexEntry {
+                incoming: height > num_outgoing,
+                outgoing: height <= num_outgoing,
+                ..Default::default()
+            };
+            let key = get_account_index_key(&account_id, height, &hash(&height.to_le_bytes()));
+            store_update.set_ser(DBCol::AccountReceipts, &key, &entry).unwrap();
+        }
+        store_update.commit().unwrap();
+
+        let incoming = |after| {
+            chain
+                .chain_store()
+                .get_account_receipts(&account_id, 0, after, 10, |entry| entry.incoming)
+                .unwrap()
+        };
+        let scan = incoming(None);
+        assert!(scan.entries.is_empty());
+        let skipped_until = scan.skipped_until.unwrap();
+        assert_eq!(skipped_until.0, MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES as u64);
+
+        let scan = incoming(Some(skipped_until));
+        assert_eq!(scan.skipped_until, None);
+        let heights: Vec<_> = scan.entries.iter().map(|(height, _, _)| *height).collect();
+        assert_eq!(heights, vec![num_outgoing + 1]);
+
+        // Lookups without a filter are not affected.
+        let all =
+            chain.chain_store().get_account_receipts(&account_id, 0, None, 20, |_| true).unwrap();
+        assert_eq!(all.entries.len(), 20);
+        assert_eq!(all.skipped_until, None);
+    }
 }
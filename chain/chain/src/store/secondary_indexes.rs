@@ -0,0 +1,263 @@
+//! This module maintains the optional secondary indexes configured by
+//! `SecondaryIndexesConfig`: the transactions by signer (`DBCol::TransactionsByAccount`) and
+//! the blocks by timestamp (`DBCol::BlocksByTimestamp`). The indexes are updated when a block
+//! is postprocessed and garbage collected together with the block, so they cover the same
+//! blocks as the rest of the hot store. Only the outcomes of the tracked shards are indexed.
+//! The receipts by receiver are the incoming entries of `DBCol::AccountReceipts`, see
+//! `ChainStore::get_account_receipts`.
+
+use std::collections::HashSet;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_chain_configs::SecondaryIndexesConfig;
+use near_chain_primitives::Error;
+use near_primitives::block::Block;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+use near_schema_checker_lib::ProtocolSchema;
+use near_store::{DBCol, Store};
+
+use super::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
+
+/// Separates the account id from the rest of the key. Account ids never contain
+/// a comma, so all the keys of an account share the same prefix.
+const ACCOUNT_INDEX_SEPARATOR: u8 = b',';
+
+/// Length of the height and hash suffix of the keys of the indexes by account.
+const ACCOUNT_INDEX_SUFFIX_LEN: usize = 8 + 32;
+
+/// Value of `DBCol::TransactionsByAccount`.
+#[derive(
+    Debug, Clone, Copy, BorshSerialize, BorshDeserialize, PartialEq, Eq, Default, ProtocolSchema,
+)]
+pub struct SecondaryIndexEntry {
+    /// Block in which the transaction or receipt was executed.
+    pub block_hash: CryptoHash,
+    pub shard_id: ShardId,
+}
+
+/// Key of the indexes by account. The height is stored big-endian so that the keys of
+/// an account are ordered by height.
+pub(crate) fn get_account_index_key(
+    account_id: &AccountId,
+    height: BlockHeight,
+    hash: &CryptoHash,
+) -> Vec<u8> {
+    let mut res = Vec::with_capacity(account_id.len() + 1 + ACCOUNT_INDEX_SUFFIX_LEN);
+    res.extend_from_slice(account_id.as_bytes());
+    res.push(ACCOUNT_INDEX_SEPARATOR);
+    res.extend_from_slice(&height.to_be_bytes());
+    res.extend_from_slice(hash.as_ref());
+    res
+}
+
+fn get_account_index_key_rev(key: &[u8]) -> std::io::Result<(BlockHeight, CryptoHash)> {
+    if key.len() < ACCOUNT_INDEX_SUFFIX_LEN {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid key length"));
+    }
+    let (height_bytes, hash_bytes) = key[key.len() - ACCOUNT_INDEX_SUFFIX_LEN..].split_at(8);
+    let height = BlockHeight::from_be_bytes(height_bytes.try_into().unwrap());
+    let hash = CryptoHash::try_from(hash_bytes).unwrap();
+    Ok((height, hash))
+}
+
+/// Key of `DBCol::BlocksByTimestamp`, ordered by timestamp.
+pub(crate) fn get_block_timestamp_key(timestamp: u64, block_hash: &CryptoHash) -> Vec<u8> {
+    let mut res = Vec::with_capacity(8 + 32);
+    res.extend_from_slice(&timestamp.to_be_bytes());
+    res.extend_from_slice(block_hash.as_ref());
+    res
+}
+
+pub(super) fn get_block_timestamp_key_rev(key: &[u8]) -> std::io::Result<(u64, CryptoHash)> {
+    if key.len() != 8 + 32 {
+        return Err(std::io::Error::new(std::io::ErrorKind::InvalidData, "Invalid key length"));
+    }
+    let (timestamp_bytes, hash_bytes) = key.split_at(8);
+    let timestamp = u64::from_be_bytes(timestamp_bytes.try_into().unwrap());
+    let block_hash = CryptoHash::try_from(hash_bytes).unwrap();
+    Ok((timestamp, block_hash))
+}
+
+/// Maximum number of entries rejected by the filter in a single scan of an index by account.
+/// Bounds the work done for a filter which matches few of the entries of a busy account.
+pub const MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES: usize = 10_000;
+
+/// Result of `iter_account_index`.
+#[derive(Debug)]
+pub struct AccountIndexScan<V> {
+    pub entries: Vec<(BlockHeight, CryptoHash, V)>,
+    /// Set if the scan was stopped after skipping `MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES`
+    /// entries. It's the last skipped entry, pass it as `after` to continue the scan.
+    pub skipped_until: Option<(BlockHeight, CryptoHash)>,
+}
+
+/// Returns the entries of an index keyed by `get_account_index_key`, ordered by height and
+/// hash, starting from `from_height`. If `after` is given, only the entries following it are
+/// returned, which allows paginating over the results. Entries not accepted by `filter` are
+/// skipped and don't count towards the `limit`, but the scan stops after skipping
+/// `MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES` entries.
+pub(crate) fn iter_account_index<V: BorshDeserialize>(
+    store: &Store,
+    col: DBCol,
+    account_id: &AccountId,
+    from_height: BlockHeight,
+    after: Option<(BlockHeight, CryptoHash)>,
+    limit: usize,
+    filter: impl Fn(&V) -> bool,
+) -> Result<AccountIndexScan<V>, Error> {
+    let lower_bound = match after {
+        Some((height, hash)) if height >= from_height => {
+            // Appending a byte makes the bound exclusive for the `after` key.
+            let mut key = get_account_index_key(account_id, height, &hash);
+            key.push(0);
+            key
+        }
+        _ => get_account_index_key(account_id, from_height, &CryptoHash::default()),
+    };
+    let mut upper_bound = account_id.as_bytes().to_vec();
+    upper_bound.push(ACCOUNT_INDEX_SEPARATOR + 1);
+
+    let mut entries = vec![];
+    let mut skipped = 0;
+    for item in store.iter_range(col, Some(&lower_bound), Some(&upper_bound)) {
+        if entries.len() >= limit {
+            break;
+        }
+        let (key, value) = item?;
+        let value = V::try_from_slice(&value)?;
+        let (height, hash) = get_account_index_key_rev(&key)?;
+        if !filter(&value) {
+            skipped += 1;
+            if skipped >= MAX_SKIPPED_ACCOUNT_INDEX_ENTRIES {
+                return Ok(AccountIndexScan { entries, skipped_until: Some((height, hash)) });
+            }
+            continue;
+        }
+        entries.push((height, hash, value));
+    }
+    Ok(AccountIndexScan { entries, skipped_until: None })
+}
+
+impl ChainStore {
+    pub fn secondary_indexes(&self) -> SecondaryIndexesConfig {
+        self.secondary_indexes
+    }
+
+    pub fn set_secondary_indexes(&mut self, secondary_indexes: SecondaryIndexesConfig) {
+        self.secondary_indexes = secondary_indexes;
+    }
+
+    /// Returns the transactions signed by the account since `from_height`, see
+    /// `iter_account_index`.
+    pub fn get_transactions_by_account(
+        &self,
+        account_id: &AccountId,
+        from_height: BlockHeight,
+        after: Option<(BlockHeight, CryptoHash)>,
+        limit: usize,
+    ) -> Result<Vec<(BlockHeight, CryptoHash, SecondaryIndexEntry)>, Error> {
+        iter_account_index(
+            self.store(),
+            DBCol::TransactionsByAccount,
+            account_id,
+            from_height,
+            after,
+            limit,
+            |_: &SecondaryIndexEntry| true,
+        )
+        .map(|scan| scan.entries)
+    }
+
+    /// Returns the (timestamp, block hash, height) of the indexed blocks, ordered by timestamp,
+    /// starting from `from_timestamp` or following `after`. Blocks not accepted by `filter`
+    /// are skipped and don't count towards the `limit`.
+    pub fn get_blocks_by_timestamp(
+        &self,
+        from_timestamp: u64,
+        after: Option<(u64, CryptoHash)>,
+        limit: usize,
+        filter: impl Fn(&CryptoHash, BlockHeight) -> bool,
+    ) -> Result<Vec<(u64, CryptoHash, BlockHeight)>, Error> {
+        let lower_bound = match after {
+            Some((timestamp, block_hash)) if timestamp >= from_timestamp => {
+                let mut key = get_block_timestamp_key(timestamp, &block_hash);
+                key.push(0);
+                key
+            }
+            _ => get_block_timestamp_key(from_timestamp, &CryptoHash::default()),
+        };
+
+        let mut result = vec![];
+        for item in self.store().iter_range(DBCol::BlocksByTimestamp, Some(&lower_bound), None) {
+            if result.len() >= limit {
+                break;
+            }
+            let (key, value) = item?;
+            let (timestamp, block_hash) = get_block_timestamp_key_rev(&key)?;
+            let height = BlockHeight::try_from_slice(&value)?;
+            if !filter(&block_hash, height) {
+                continue;
+            }
+            result.push((timestamp, block_hash, height));
+        }
+        Ok(result)
+    }
+}
+
+impl<'a> ChainStoreUpdate<'a> {
+    /// Updates the enabled secondary indexes with the block and the outcomes of its new chunks.
+    /// Must be called after the outcomes of the block are saved in this update.
+    pub fn save_secondary_indexes(&mut self, block: &Block) -> Result<(), Error> {
+        let config = self.chain_store.secondary_indexes;
+        if !config.is_enabled() {
+            return Ok(());
+        }
+        let block_hash = block.hash();
+        let height = block.header().height();
+        let mut store_update = self.store().store_update();
+        if config.blocks_by_timestamp {
+            store_update.set_ser(
+                DBCol::BlocksByTimestamp,
+                &get_block_timestamp_key(block.header().raw_timestamp(), block_hash),
+                &height,
+            )?;
+        }
+        if config.transactions_by_account {
+            for chunk_header in
+                block.chunks().iter_deprecated().filter(|h| h.height_included() == height)
+            {
+                let shard_id = chunk_header.shard_id();
+                // Outcomes are only saved for the shards which were applied.
+                let Some(outcome_ids) =
+                    self.chain_store_cache_update.outcome_ids.get(&(*block_hash, shard_id))
+                else {
+                    continue;
+                };
+                let chunk = self.get_chunk(&chunk_header.chunk_hash())?;
+                let transaction_hashes: HashSet<CryptoHash> =
+                    chunk.transactions().iter().map(|transaction| transaction.get_hash()).collect();
+                let entry = SecondaryIndexEntry { block_hash: *block_hash, shard_id };
+                for outcome_id in outcome_ids {
+                    if !transaction_hashes.contains(outcome_id) {
+                        continue;
+                    }
+                    let Some(outcome_with_proof) =
+                        self.chain_store_cache_update.outcomes.get(&(*outcome_id, *block_hash))
+                    else {
+                        continue;
+                    };
+                    // The executor of a transaction is its signer.
+                    let key = get_account_index_key(
+                        &outcome_with_proof.outcome.executor_id,
+                        height,
+                        outcome_id,
+                    );
+                    store_update.set_ser(DBCol::TransactionsByAccount, &key, &entry)?;
+                }
+            }
+        }
+        self.merge(store_update);
+        Ok(())
+    }
+}
@@ -0,0 +1,184 @@
+//! This module finds the last block of the canonical chain at or before a given time.
+//!
+//! The canonical timestamps increase with the height, so the lookup is a binary search over the
+//! headers of the canonical chain above the GC tail. When the blocks are indexed by timestamp in
+//! `DBCol::BlocksByTimestamp`, see `SecondaryIndexesConfig::blocks_by_timestamp`, which is
+//! enabled by default, the first canonical block after the time gives the answer directly: it's
+//! the canonical block right below it. The index covers all the processed blocks, including
+//! forks, up to the GC tail, and the blocks processed before it was enabled once
+//! [BlocksByTimestampBackfill] is done, so its result is checked and the lookup falls back to
+//! the binary search below the block found in the index.
+
+use std::io;
+
+use borsh::BorshDeserialize;
+use near_chain_primitives::Error;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use near_store::background_migration::BackgroundMigration;
+use near_store::{DBCol, Store, StoreUpdate, TAIL_KEY};
+
+use super::secondary_indexes::{get_block_timestamp_key, get_block_timestamp_key_rev};
+use super::{ChainStore, ChainStoreAccess};
+
+/// Maximum number of heights walked down from a height without a canonical block, e.g. because
+/// the BlockHeight rows of an epoch synced node are missing, to find the one below it.
+const MAX_HEIGHTS_WITHOUT_CANONICAL_BLOCK: BlockHeight = 1000;
+
+impl ChainStore {
+    /// Returns the height of the last block of the canonical chain, up to the head, whose
+    /// timestamp is at or before `timestamp`, or `None` if the genesis block is after it.
+    pub fn get_block_height_by_timestamp(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<BlockHeight>, Error> {
+        let head = self.head()?;
+        if self.get_block_header(&head.last_block_hash)?.raw_timestamp() <= timestamp {
+            return Ok(Some(head.height));
+        }
+        let max_height = match self.get_first_indexed_canonical_height_after(timestamp)? {
+            Some(height) if height <= self.get_genesis_height() => return Ok(None),
+            Some(height) => {
+                let (below_height, below_timestamp) =
+                    self.get_canonical_block_at_or_below(height - 1)?;
+                if below_timestamp <= timestamp {
+                    return Ok(Some(below_height));
+                }
+                // The blocks between the time and the indexed block aren't in the index.
+                below_height
+            }
+            None => head.height,
+        };
+        self.search_block_height_by_timestamp(timestamp, max_height)
+    }
+
+    /// Height of the first block of the canonical chain in `DBCol::BlocksByTimestamp` whose
+    /// timestamp is after `timestamp`, skipping the blocks of the forks.
+    fn get_first_indexed_canonical_height_after(
+        &self,
+        timestamp: u64,
+    ) -> Result<Option<BlockHeight>, Error> {
+        let lower_bound =
+            get_block_timestamp_key(timestamp.saturating_add(1), &CryptoHash::default());
+        for item in self.store().iter_range(DBCol::BlocksByTimestamp, Some(&lower_bound), None) {
+            let (key, value) = item?;
+            let (_, block_hash) = get_block_timestamp_key_rev(&key)?;
+            let height = BlockHeight::try_from_slice(&value)?;
+            match self.get_block_hash_by_height(height) {
+                Ok(canonical_hash) if canonical_hash == block_hash => return Ok(Some(height)),
+                Ok(_) | Err(Error::DBNotFoundErr(_)) => {}
+                Err(err) => return Err(err),
+            }
+        }
+        Ok(None)
+    }
+
+    /// Timestamp of the block at `height` on the canonical chain, if any.
+    fn get_canonical_block_timestamp(&self, height: BlockHeight) -> Result<Option<u64>, Error> {
+        match self.get_block_hash_by_height(height) {
+            Ok(hash) => Ok(Some(self.get_block_header(&hash)?.raw_timestamp())),
+            Err(Error::DBNotFoundErr(_)) => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Lowest height of the canonical chain kept in the store: the GC tail, or the genesis
+    /// height on archival nodes.
+    fn get_lowest_canonical_height(&self) -> Result<BlockHeight, Error> {
+        Ok(self.tail()?.max(self.get_genesis_height()))
+    }
+
+    /// Returns the height and the timestamp of the last block of the canonical chain at or
+    /// below `height`, looking at most [MAX_HEIGHTS_WITHOUT_CANONICAL_BLOCK] heights down and
+    /// not below the GC tail.
+    fn get_canonical_block_at_or_below(
+        &self,
+        height: BlockHeight,
+    ) -> Result<(BlockHeight, u64), Error> {
+        let min_height = height
+            .saturating_sub(MAX_HEIGHTS_WITHOUT_CANONICAL_BLOCK)
+            .max(self.get_lowest_canonical_height()?);
+        for height in (min_height..=height).rev() {
+            if let Some(timestamp) = self.get_canonical_block_timestamp(height)? {
+                return Ok((height, timestamp));
+            }
+        }
+        Err(Error::DBNotFoundErr(format!("BLOCK HEIGHT: {} down to {}", height, min_height)))
+    }
+
+    /// Binary search over the canonical chain from the GC tail up to `max_height`. The
+    /// timestamps of the canonical blocks increase with their height. Fails if the block at the
+    /// tail, other than the genesis block, is after `timestamp`, as the blocks below it are
+    /// garbage collected.
+    fn search_block_height_by_timestamp(
+        &self,
+        timestamp: u64,
+        max_height: BlockHeight,
+    ) -> Result<Option<BlockHeight>, Error> {
+        let lowest_height = self.get_lowest_canonical_height()?;
+        let (mut low, mut high) = (lowest_height, max_height);
+        while low < high {
+            let mid = low + (high - low + 1) / 2;
+            let (_, mid_timestamp) = self.get_canonical_block_at_or_below(mid)?;
+            if mid_timestamp <= timestamp {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        let (height, block_timestamp) = self.get_canonical_block_at_or_below(low)?;
+        if block_timestamp <= timestamp {
+            return Ok(Some(height));
+        }
+        if lowest_height > self.get_genesis_height() {
+            return Err(Error::DBNotFoundErr(format!(
+                "BLOCK TIMESTAMP: {} is before the block at the tail height {}",
+                timestamp, lowest_height
+            )));
+        }
+        Ok(None)
+    }
+}
+
+/// Backfills `DBCol::BlocksByTimestamp` with the blocks processed before the index was enabled,
+/// from their headers. Like the blocks indexed when they are processed, only the blocks kept
+/// above the GC tail are indexed.
+pub struct BlocksByTimestampBackfill {
+    store: Store,
+}
+
+impl BlocksByTimestampBackfill {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+}
+
+impl BackgroundMigration for BlocksByTimestampBackfill {
+    fn name(&self) -> &'static str {
+        "blocks_by_timestamp_backfill"
+    }
+
+    fn source_col(&self) -> DBCol {
+        DBCol::BlockHeader
+    }
+
+    fn migrate_entry(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        store_update: &mut StoreUpdate,
+    ) -> io::Result<()> {
+        let header = BlockHeader::try_from_slice(value)?;
+        let tail = self.store.get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)?.unwrap_or(0);
+        // The headers are kept forever, unlike the blocks.
+        if header.height() < tail || !self.store.exists(DBCol::Block, key)? {
+            return Ok(());
+        }
+        store_update.set_ser(
+            DBCol::BlocksByTimestamp,
+            &get_block_timestamp_key(header.raw_timestamp(), header.hash()),
+            &header.height(),
+        )
+    }
+}
@@ -45,8 +45,8 @@ use near_primitives::types::{
 };
 use near_primitives::version::{ProtocolFeature, ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
-    AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    AccessKeyInfoView, AccessKeyList, AccountBalanceView, CallResult, ContractCodeView,
+    EpochValidatorInfo, QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
 };
 use near_store::test_utils::TestTriesBuilder;
 use near_store::{
@@ -54,6 +54,7 @@ use near_store::{
     TrieChanges, WrappedTrieChanges,
 };
 use near_vm_runner::{ContractCode, ContractRuntimeCache, NoContractRuntimeCache};
+use node_runtime::receiver_queue_stats::ReceiverQueueStats;
 use std::cmp::Ordering;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::{Arc, RwLock};
@@ -1254,6 +1255,7 @@ impl RuntimeAdapter for KeyValueRuntime {
             bandwidth_requests: BandwidthRequests::default_for_protocol_version(PROTOCOL_VERSION),
             bandwidth_scheduler_state_hash: CryptoHash::default(),
             contract_updates: Default::default(),
+            delayed_receipts_indices: Default::default(),
         })
     }
 
@@ -1287,6 +1289,26 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_height,
                 block_hash: *block_hash,
             }),
+            QueryRequest::ViewAccountBalance { account_id } => {
+                let amount = self
+                    .state
+                    .read()
+                    .unwrap()
+                    .get(state_root)
+                    .map_or_else(|| 0, |state| *state.amounts.get(account_id).unwrap_or(&0));
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::ViewAccountBalance(AccountBalanceView {
+                        amount,
+                        locked: 0,
+                        storage_reserve: 0,
+                        liquid: amount,
+                        function_call_allowance: 0,
+                        unpledged_liquid: amount,
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
             QueryRequest::ViewCode { .. } => Ok(QueryResponse {
                 kind: QueryResponseKind::ViewCode(ContractCodeView {
                     code: vec![],
@@ -1318,14 +1340,16 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_height,
                 block_hash: *block_hash,
             }),
-            QueryRequest::CallFunction { .. } => Ok(QueryResponse {
-                kind: QueryResponseKind::CallResult(CallResult {
-                    result: Default::default(),
-                    logs: Default::default(),
-                }),
-                block_height,
-                block_hash: *block_hash,
-            }),
+            QueryRequest::CallFunction { .. } | QueryRequest::SimulateFunctionCall { .. } => {
+                Ok(QueryResponse {
+                    kind: QueryResponseKind::CallResult(CallResult {
+                        result: Default::default(),
+                        logs: Default::default(),
+                    }),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
         }
     }
 
@@ -1454,4 +1478,14 @@ impl RuntimeAdapter for KeyValueRuntime {
         // Note that KeyValueRuntime does not use compiled contract cache, so this is no-op.
         Ok(())
     }
+
+    fn get_receiver_queue_stats(
+        &self,
+        _shard_id: ShardId,
+        _block_hash: &CryptoHash,
+        _state_root: StateRoot,
+    ) -> Result<Arc<ReceiverQueueStats>, Error> {
+        // KeyValueRuntime doesn't delay nor buffer receipts.
+        Ok(Arc::new(ReceiverQueueStats::default()))
+    }
 }
@@ -12,14 +12,17 @@ use crate::types::Tip;
 use crate::{ChainStoreAccess, StoreValidator};
 
 use near_chain_configs::{GCConfig, GenesisConfig, DEFAULT_GC_NUM_EPOCHS_TO_KEEP};
+use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::Block;
 use near_primitives::epoch_block_info::BlockInfo;
 use near_primitives::merkle::PartialMerkleTree;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::test_utils::{create_test_signer, TestBlockBuilder};
-use near_primitives::types::{BlockHeight, NumBlocks, StateRoot};
+use near_primitives::types::{BlockHeight, EpochId, NumBlocks, StateRoot};
 use near_primitives::validator_signer::ValidatorSigner;
+use near_store::adapter::{StoreAdapter, StoreUpdateAdapter};
+use near_store::flat::{BlockInfo as FlatBlockInfo, FlatStorageReadyStatus, FlatStorageStatus};
 use near_store::test_utils::gen_changes;
 use near_store::{DBCol, ShardTries, Trie, WrappedTrieChanges};
 
@@ -720,6 +723,209 @@ fn test_clear_old_data() {
     }
 }
 
+#[test]
+fn test_clear_epoch_data() {
+    let max_height = 14usize;
+    let mut chain = get_chain_with_epoch_length(Clock::real(), 1);
+    let epoch_manager = chain.epoch_manager.clone();
+    let genesis = chain.get_block_by_height(0).unwrap();
+    let signer = Arc::new(create_test_signer("test1"));
+    let mut prev_block = genesis;
+    let mut blocks = vec![prev_block.clone()];
+    for i in 1..=max_height {
+        add_block(
+            &mut chain,
+            epoch_manager.as_ref(),
+            &mut prev_block,
+            &mut blocks,
+            signer.clone(),
+            i as BlockHeight,
+        );
+    }
+    let epoch_heights = blocks
+        .iter()
+        .map(|block| {
+            let epoch_id = *block.header().epoch_id();
+            (epoch_id, epoch_manager.get_epoch_info(&epoch_id).unwrap().epoch_height())
+        })
+        .collect::<Vec<_>>();
+
+    let gc_config = GCConfig {
+        gc_blocks_limit: 100,
+        epoch_data_num_epochs_to_keep: Some(3),
+        ..GCConfig::default()
+    };
+    chain.clear_data(&gc_config).unwrap();
+    let tail_epoch_id =
+        *chain.get_block_by_height(chain.tail().unwrap()).unwrap().header().epoch_id();
+    let tail_epoch_height = epoch_manager.get_epoch_info(&tail_epoch_id).unwrap().epoch_height();
+    let (_, head_epoch_height) = *epoch_heights.last().unwrap();
+    // The configured value is below the number of epochs kept by the block GC, so the latter wins.
+    let floor_epoch_height =
+        (head_epoch_height - DEFAULT_GC_NUM_EPOCHS_TO_KEEP).min(tail_epoch_height);
+
+    chain.mut_chain_store().clear_epoch_data(&gc_config, epoch_manager.as_ref(), u64::MAX).unwrap();
+
+    let store = chain.chain_store().store();
+    let mut num_removed = 0;
+    for (epoch_id, epoch_height) in epoch_heights {
+        let expected_removed = epoch_id != EpochId::default() && epoch_height < floor_epoch_height;
+        let exists = store.exists(DBCol::EpochInfo, epoch_id.as_ref()).unwrap();
+        assert_eq!(exists, !expected_removed, "epoch height {}", epoch_height);
+        num_removed += expected_removed as usize;
+    }
+    assert!(num_removed > 0);
+}
+
+#[test]
+fn test_clear_resharding_data() {
+    let mut chain = get_chain_with_epoch_length(Clock::real(), 1);
+    let epoch_manager = chain.epoch_manager.clone();
+    let tries = chain.runtime_adapter.get_tries();
+    let head = chain.head().unwrap();
+    let shard_layout = epoch_manager.get_shard_layout(&head.epoch_id).unwrap();
+    let tracked_shard_uid = shard_layout.shard_uids().next().unwrap();
+    // Parent of the tracked shard, which still reads the parent's State.
+    let used_parent_shard_uid = ShardUId { version: shard_layout.version(), shard_id: 100 };
+    // Parent whose children are not tracked anymore.
+    let unused_parent_shard_uid = ShardUId { version: shard_layout.version(), shard_id: 101 };
+    let ready_status = FlatStorageStatus::Ready(FlatStorageReadyStatus {
+        flat_head: FlatBlockInfo::genesis(head.last_block_hash, head.height),
+    });
+
+    let state_key = |shard_uid: ShardUId| [&shard_uid.to_bytes()[..], &[1; 32]].concat();
+    let store = chain.chain_store().store();
+    let mut store_update = store.store_update();
+    for shard_uid in [used_parent_shard_uid, unused_parent_shard_uid] {
+        store_update.increment_refcount(DBCol::State, &state_key(shard_uid), &[1, 2, 3]);
+        store_update.flat_store_update().set_flat_storage_status(shard_uid, ready_status.clone());
+    }
+    store_update.flat_store_update().set_flat_storage_status(tracked_shard_uid, ready_status);
+    store_update
+        .trie_store_update()
+        .set_shard_uid_mapping(tracked_shard_uid, used_parent_shard_uid);
+    store_update.commit().unwrap();
+
+    let shard_tracker = chain.shard_tracker.clone();
+    assert!(chain
+        .mut_chain_store()
+        .clear_resharding_data(&tries, epoch_manager.as_ref(), &shard_tracker, None)
+        .unwrap());
+
+    let store = chain.chain_store().store();
+    let flat_store = store.flat_store();
+    assert!(store.exists(DBCol::State, &state_key(used_parent_shard_uid)).unwrap());
+    assert!(store.exists(DBCol::StateShardUIdMapping, &tracked_shard_uid.to_bytes()).unwrap());
+    assert!(!store.exists(DBCol::State, &state_key(unused_parent_shard_uid)).unwrap());
+    assert_eq!(
+        flat_store.get_flat_storage_status(unused_parent_shard_uid).unwrap(),
+        FlatStorageStatus::Empty
+    );
+    assert_ne!(
+        flat_store.get_flat_storage_status(tracked_shard_uid).unwrap(),
+        FlatStorageStatus::Empty
+    );
+}
+
+/// Sets up a removed parent shard with State, whose child is the current shard and has no flat
+/// storage yet, as while it is being caught up or state synced.
+fn setup_parent_of_child_without_flat_storage(chain: &Chain) -> (ShardUId, ShardUId, Vec<u8>) {
+    let head = chain.head().unwrap();
+    let shard_layout = chain.epoch_manager.get_shard_layout(&head.epoch_id).unwrap();
+    let child_shard_uid = shard_layout.shard_uids().next().unwrap();
+    let parent_shard_uid = ShardUId { version: shard_layout.version(), shard_id: 100 };
+    let state_key = [&parent_shard_uid.to_bytes()[..], &[1; 32]].concat();
+
+    let mut store_update = chain.chain_store().store().store_update();
+    store_update.increment_refcount(DBCol::State, &state_key, &[1, 2, 3]);
+    store_update.flat_store_update().set_flat_storage_status(
+        parent_shard_uid,
+        FlatStorageStatus::Ready(FlatStorageReadyStatus {
+            flat_head: FlatBlockInfo::genesis(head.last_block_hash, head.height),
+        }),
+    );
+    store_update
+        .flat_store_update()
+        .set_flat_storage_status(child_shard_uid, FlatStorageStatus::Empty);
+    store_update.trie_store_update().set_shard_uid_mapping(child_shard_uid, parent_shard_uid);
+    store_update.commit().unwrap();
+    (parent_shard_uid, child_shard_uid, state_key)
+}
+
+/// A tracked child without flat storage still uses the State of its parent, an untracked one
+/// doesn't.
+#[test]
+fn test_clear_resharding_data_tracked_child_without_flat_storage() {
+    let mut chain = get_chain_with_epoch_length(Clock::real(), 1);
+    let epoch_manager = chain.epoch_manager.clone();
+    let tries = chain.runtime_adapter.get_tries();
+    let (parent_shard_uid, _, state_key) = setup_parent_of_child_without_flat_storage(&chain);
+
+    let tracking_shard_tracker = ShardTracker::new(TrackedConfig::AllShards, epoch_manager.clone());
+    assert!(chain
+        .mut_chain_store()
+        .clear_resharding_data(&tries, epoch_manager.as_ref(), &tracking_shard_tracker, None)
+        .unwrap());
+    let store = chain.chain_store().store();
+    assert!(store.exists(DBCol::State, &state_key).unwrap());
+    assert_ne!(
+        store.flat_store().get_flat_storage_status(parent_shard_uid).unwrap(),
+        FlatStorageStatus::Empty
+    );
+
+    let shard_tracker = ShardTracker::new_empty(epoch_manager.clone());
+    assert!(chain
+        .mut_chain_store()
+        .clear_resharding_data(&tries, epoch_manager.as_ref(), &shard_tracker, None)
+        .unwrap());
+    let store = chain.chain_store().store();
+    assert!(!store.exists(DBCol::State, &state_key).unwrap());
+    assert_eq!(
+        store.flat_store().get_flat_storage_status(parent_shard_uid).unwrap(),
+        FlatStorageStatus::Empty
+    );
+}
+
+/// Nothing is cleared while a shard is being caught up, as the State of the child is
+/// incomplete and may be written through the mapping of its parent.
+#[test]
+fn test_clear_resharding_data_during_catchup() {
+    let mut chain = get_chain_with_epoch_length(Clock::real(), 1);
+    let epoch_manager = chain.epoch_manager.clone();
+    let tries = chain.runtime_adapter.get_tries();
+    let shard_tracker = ShardTracker::new_empty(epoch_manager.clone());
+    let (parent_shard_uid, child_shard_uid, state_key) =
+        setup_parent_of_child_without_flat_storage(&chain);
+    let head = chain.head().unwrap();
+
+    let store = chain.chain_store().store();
+    let mut store_update = store.store_update();
+    store_update
+        .set_ser(DBCol::BlocksToCatchup, head.prev_block_hash.as_ref(), &vec![head.last_block_hash])
+        .unwrap();
+    store_update.commit().unwrap();
+    assert!(!chain
+        .mut_chain_store()
+        .clear_resharding_data(&tries, epoch_manager.as_ref(), &shard_tracker, None)
+        .unwrap());
+    assert!(store.exists(DBCol::State, &state_key).unwrap());
+    assert!(store.exists(DBCol::StateShardUIdMapping, &child_shard_uid.to_bytes()).unwrap());
+
+    // Once the catchup is done, the parent is cleared.
+    let mut store_update = store.store_update();
+    store_update.delete(DBCol::BlocksToCatchup, head.prev_block_hash.as_ref());
+    store_update.commit().unwrap();
+    assert!(chain
+        .mut_chain_store()
+        .clear_resharding_data(&tries, epoch_manager.as_ref(), &shard_tracker, None)
+        .unwrap());
+    assert!(!store.exists(DBCol::State, &state_key).unwrap());
+    assert_eq!(
+        store.flat_store().get_flat_storage_status(parent_shard_uid).unwrap(),
+        FlatStorageStatus::Empty
+    );
+}
+
 // Adds block to the chain at given height after prev_block.
 fn add_block(
     chain: &mut Chain,
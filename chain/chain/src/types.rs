@@ -4,6 +4,7 @@ use near_chain_configs::GenesisConfig;
 use near_chain_configs::MutableConfigValue;
 use near_chain_configs::ProtocolConfig;
 use near_chain_configs::ReshardingConfig;
+use near_chain_configs::SecondaryIndexesConfig;
 use near_chain_primitives::Error;
 pub use near_epoch_manager::EpochManagerAdapter;
 use near_parameters::RuntimeConfig;
@@ -20,7 +21,7 @@ use near_primitives::congestion_info::ExtendedCongestionInfo;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, MerklePath};
-use near_primitives::receipt::{PromiseYieldTimeout, Receipt};
+use near_primitives::receipt::{PromiseYieldTimeout, Receipt, TrieQueueIndices};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_part::PartId;
@@ -42,7 +43,9 @@ use near_store::flat::FlatStorageManager;
 use near_store::{PartialStorage, ShardTries, Store, Trie, WrappedTrieChanges};
 use near_vm_runner::ContractCode;
 use near_vm_runner::ContractRuntimeCache;
+use node_runtime::receiver_queue_stats::ReceiverQueueStats;
 use num_rational::Rational32;
+use std::sync::Arc;
 use tracing::instrument;
 
 #[derive(Eq, PartialEq, Debug, Clone)]
@@ -113,6 +116,8 @@ pub struct ApplyChunkResult {
     pub bandwidth_scheduler_state_hash: CryptoHash,
     /// Contracts accessed and deployed while applying the chunk.
     pub contract_updates: ContractUpdates,
+    /// Indices of the delayed receipts queue of the shard after applying the chunk.
+    pub delayed_receipts_indices: TrieQueueIndices,
 }
 
 impl ApplyChunkResult {
@@ -215,22 +220,37 @@ pub struct ChainGenesis {
 pub struct ChainConfig {
     /// Whether to save `TrieChanges` on disk or not.
     pub save_trie_changes: bool,
+    /// Whether to maintain the index of receipts by account in `DBCol::AccountReceipts`.
+    pub save_account_receipts: bool,
+    /// Secondary indexes to maintain when postprocessing blocks.
+    pub secondary_indexes: SecondaryIndexesConfig,
+    /// Whether to record the last use of every access key in `DBCol::AccessKeyUsage`.
+    pub save_access_key_usage: bool,
     /// Number of threads to execute background migration work.
     /// Currently used for flat storage background creation.
     pub background_migration_threads: usize,
     /// The resharding configuration.
     pub resharding_config: MutableConfigValue<ReshardingConfig>,
+    /// Shards whose state transition data isn't generated nor saved, unless the node produces
+    /// their chunks in the current or the next epoch and thus needs the data for the state
+    /// witnesses. Only matters on chunk producers tracking other shards too: the nodes which
+    /// don't produce chunks in the current or the next epoch never save the data.
+    pub skip_state_transition_data_for_shards: Vec<ShardId>,
 }
 
 impl ChainConfig {
     pub fn test() -> Self {
         Self {
             save_trie_changes: true,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
             background_migration_threads: 1,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            skip_state_transition_data_for_shards: vec![],
         }
     }
 }
@@ -271,6 +291,10 @@ pub struct RuntimeStorageConfig {
     pub use_flat_storage: bool,
     pub source: StorageDataSource,
     pub state_patch: SandboxStatePatch,
+    /// Whether to keep the trie nodes read while applying the chunk, returned as the storage
+    /// proof of the result for the state transition data. The size of the reads is accounted
+    /// for anyway, for the storage proof size limit.
+    pub keep_storage_proof: bool,
 }
 
 impl RuntimeStorageConfig {
@@ -280,6 +304,7 @@ impl RuntimeStorageConfig {
             use_flat_storage,
             source: StorageDataSource::Db,
             state_patch: Default::default(),
+            keep_storage_proof: true,
         }
     }
 
@@ -293,6 +318,7 @@ impl RuntimeStorageConfig {
             use_flat_storage: false,
             source: StorageDataSource::DbTrieOnly,
             state_patch: Default::default(),
+            keep_storage_proof: true,
         }
     }
 }
@@ -544,6 +570,17 @@ pub trait RuntimeAdapter: Send + Sync {
         epoch_id: &EpochId,
         contract_codes: Vec<ContractCode>,
     ) -> Result<(), Error>;
+
+    /// Returns the usage of the delayed receipts queue and of the outgoing buffers of the shard
+    /// by receiver account at the given state root, the post state root of the shard at
+    /// `block_hash`. The stats are bootstrapped from the trie the first time, then kept up to
+    /// date while applying the chunks of the shard.
+    fn get_receiver_queue_stats(
+        &self,
+        shard_id: ShardId,
+        block_hash: &CryptoHash,
+        state_root: StateRoot,
+    ) -> Result<Arc<ReceiverQueueStats>, Error>;
 }
 
 /// The last known / checked height and time when we have processed it.
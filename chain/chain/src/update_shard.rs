@@ -79,6 +79,8 @@ pub struct StorageContext {
     /// Data source used for processing shard update.
     pub storage_data_source: StorageDataSource,
     pub state_patch: SandboxStatePatch,
+    /// Whether to keep the storage proof, see [RuntimeStorageConfig::keep_storage_proof].
+    pub keep_storage_proof: bool,
 }
 
 /// Processes shard update with given block and shard.
@@ -140,6 +142,7 @@ pub fn apply_new_chunk(
         use_flat_storage: true,
         source: storage_context.storage_data_source,
         state_patch: storage_context.state_patch,
+        keep_storage_proof: storage_context.keep_storage_proof,
     };
     match runtime.apply_chunk(
         storage_config,
@@ -187,6 +190,7 @@ pub fn apply_old_chunk(
         use_flat_storage: true,
         source: storage_context.storage_data_source,
         state_patch: storage_context.state_patch,
+        keep_storage_proof: storage_context.keep_storage_proof,
     };
     match runtime.apply_chunk(
         storage_config,
@@ -128,6 +128,19 @@ impl EncodedChunksCache {
         }
     }
 
+    /// Reverts `mark_entry_complete`, e.g. when reconstructing the chunk from its parts failed
+    pub fn mark_entry_incomplete(&mut self, chunk_hash: &ChunkHash) {
+        if let Some(entry) = self.encoded_chunks.get_mut(chunk_hash) {
+            entry.complete = false;
+            self.incomplete_chunks
+                .entry(*entry.header.prev_block_hash())
+                .or_default()
+                .insert(chunk_hash.clone());
+        } else {
+            warn!(target:"chunks", "cannot mark non-existent entry as incomplete {:?}", chunk_hash);
+        }
+    }
+
     pub fn mark_entry_validated(&mut self, chunk_hash: &ChunkHash) {
         if let Some(entry) = self.encoded_chunks.get_mut(chunk_hash) {
             entry.header_fully_validated = true;
@@ -65,3 +65,11 @@ pub static PARTIAL_ENCODED_CHUNK_FORWARD_CACHED_WITHOUT_PREV_BLOCK: LazyLock<Cou
     )
     .unwrap()
     });
+
+pub(crate) static CHUNK_RECONSTRUCTION_TIME: LazyLock<Histogram> = LazyLock::new(|| {
+    try_create_histogram(
+        "near_chunk_reconstruction_time",
+        "Time taken to reconstruct and decode a chunk from its parts in the background",
+    )
+    .unwrap()
+});
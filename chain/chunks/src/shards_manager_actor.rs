@@ -63,6 +63,9 @@
 //!    of this is that the previous block is accepted. If missing the previous block is the only
 //!    blocker, there's another chance to trigger this processing again in check_incomplete_chunks,
 //!    which is triggered by sending the CheckIncompleteChunks message from the client.
+//!    Reconstruction runs on `chunk_reconstruction_spawner`, and the chunk is marked complete
+//!    while it runs. If it fails for any reason other than an invalid chunk, the chunk is marked
+//!    incomplete again and added back to the request pool.
 //!
 //! ** Validating chunks
 //! Before `process_partial_encoded_chunk` returns HaveAllPartsAndReceipts, it will perform
@@ -90,12 +93,16 @@ use crate::metrics;
 use ::time::ext::InstantExt as _;
 use actix::Actor;
 use near_async::actix_wrapper::ActixWrapper;
-use near_async::futures::{DelayedActionRunner, DelayedActionRunnerExt};
+use near_async::futures::{
+    AsyncComputationSpawner, AsyncComputationSpawnerExt, DelayedActionRunner,
+    DelayedActionRunnerExt,
+};
 use near_async::messaging::{self, Handler, Sender};
 use near_async::time::Duration;
 use near_async::time::{self, Clock};
 use near_chain::byzantine_assert;
 use near_chain::near_chain_primitives::error::Error::DBNotFoundErr;
+use near_chain::rayon_spawner::RayonAsyncComputationSpawner;
 use near_chain::signature_verification::{
     verify_chunk_header_signature_with_epoch_manager,
     verify_chunk_header_signature_with_epoch_manager_and_parts,
@@ -142,6 +149,7 @@ use rand::Rng;
 use reed_solomon_erasure::galois_8::ReedSolomon;
 use std::collections::{HashMap, HashSet};
 use std::num::NonZeroUsize;
+use std::sync::mpsc;
 use std::sync::Arc;
 use tracing::{debug, debug_span, error, warn};
 
@@ -166,6 +174,9 @@ pub enum ProcessPartialEncodedChunkResult {
     Known,
     /// All parts and receipts in the chunk are received and the chunk has been processed
     HaveAllPartsAndReceipts,
+    /// Enough parts are received and the chunk is being reconstructed in the background. The
+    /// client is notified once the reconstruction finishes.
+    Reconstructing,
     /// More parts and receipts are needed for processing the full chunk
     NeedMorePartsOrReceipts,
     /// PartialEncodedChunkMessage is received earlier than Block for the same height.
@@ -267,7 +278,14 @@ pub struct ShardsManagerActor {
     shard_tracker: ShardTracker,
     peer_manager_adapter: Sender<PeerManagerMessageRequest>,
     client_adapter: Sender<ShardsManagerResponse>,
-    rs: ReedSolomon,
+    rs: Arc<ReedSolomon>,
+    /// Used to reconstruct chunks from their parts off the actor thread, so that decoding large
+    /// chunks does not delay processing of other chunk parts and requests.
+    chunk_reconstruction_spawner: Arc<dyn AsyncComputationSpawner>,
+    /// Chunks whose background reconstruction failed are sent back to the actor through this
+    /// channel, so that they can be marked incomplete and requested again.
+    failed_reconstructions_sender: mpsc::Sender<ChunkHash>,
+    failed_reconstructions_receiver: mpsc::Receiver<ChunkHash>,
 
     encoded_chunks: EncodedChunksCache,
     requested_partial_encoded_chunks: RequestPool,
@@ -305,6 +323,83 @@ impl Handler<ShardsManagerRequestFromNetwork> for ShardsManagerActor {
     }
 }
 
+/// Reports the chunk back to the actor as failed when dropped, unless disarmed. Dropping covers
+/// both reconstruction errors and a spawner that drops the task without running it.
+struct FailedReconstructionGuard {
+    chunk_hash: Option<ChunkHash>,
+    sender: mpsc::Sender<ChunkHash>,
+}
+
+impl FailedReconstructionGuard {
+    fn disarm(mut self) {
+        self.chunk_hash = None;
+    }
+}
+
+impl Drop for FailedReconstructionGuard {
+    fn drop(&mut self) {
+        if let Some(chunk_hash) = self.chunk_hash.take() {
+            // The actor may already be gone, in which case there is nothing to retry.
+            let _ = self.sender.send(chunk_hash);
+        }
+    }
+}
+
+/// Reconstructs the full chunk from the parts in `encoded_chunk`, checks it against the merkle
+/// root committed to in the header and decodes it. Runs on the chunk reconstruction spawner.
+fn reconstruct_chunk(
+    rs: &ReedSolomon,
+    encoded_chunk: &mut EncodedShardChunk,
+    me: Option<&AccountId>,
+    epoch_manager: &dyn EpochManagerAdapter,
+    shard_tracker: &ShardTracker,
+) -> Result<(ShardChunk, PartialEncodedChunk), Error> {
+    match check_chunk_complete(rs, epoch_manager.num_data_parts(), encoded_chunk) {
+        ChunkStatus::Complete(merkle_paths) => {
+            decode_encoded_chunk(encoded_chunk, merkle_paths, me, epoch_manager, shard_tracker)
+        }
+        ChunkStatus::Incomplete | ChunkStatus::Invalid => Err(Error::InvalidChunk),
+    }
+}
+
+fn check_chunk_complete(
+    rs: &ReedSolomon,
+    data_parts: usize,
+    chunk: &mut EncodedShardChunk,
+) -> ChunkStatus {
+    let _span = debug_span!(
+        target: "chunks",
+        "check_chunk_complete",
+        height_included = chunk.cloned_header().height_included(),
+        shard_id = ?chunk.cloned_header().shard_id(),
+        chunk_hash = ?chunk.chunk_hash())
+    .entered();
+
+    if chunk.content().num_fetched_parts() < data_parts {
+        debug!(target: "chunks", num_fetched_parts = chunk.content().num_fetched_parts(), data_parts, "Incomplete");
+        return ChunkStatus::Incomplete;
+    }
+
+    let encoded_length = chunk.encoded_length();
+    if let Err(err) = reed_solomon_decode::<TransactionReceipt>(
+        rs,
+        chunk.content_mut().parts.as_mut_slice(),
+        encoded_length as usize,
+    ) {
+        debug!(target: "chunks", ?err, "Invalid: Failed to decode");
+        return ChunkStatus::Invalid;
+    }
+
+    let (merkle_root, merkle_paths) = chunk.content().get_merkle_hash_and_paths();
+    if merkle_root != chunk.encoded_merkle_root() {
+        debug!(target: "chunks", ?merkle_root, chunk_encoded_merkle_root = ?chunk.encoded_merkle_root(), "Invalid: Wrong merkle root");
+        return ChunkStatus::Invalid;
+    }
+
+    debug!(target: "chunks", "Complete");
+    ChunkStatus::Complete(merkle_paths)
+}
+
 pub fn start_shards_manager(
     epoch_manager: Arc<dyn EpochManagerAdapter>,
     view_epoch_manager: Arc<dyn EpochManagerAdapter>,
@@ -337,6 +432,7 @@ pub fn start_shards_manager(
         chain_head,
         chain_header_head,
         chunk_request_retry_period,
+        Arc::new(RayonAsyncComputationSpawner),
     );
 
     let shards_manager_addr =
@@ -359,7 +455,9 @@ impl ShardsManagerActor {
         initial_chain_head: Tip,
         initial_chain_header_head: Tip,
         chunk_request_retry_period: Duration,
+        chunk_reconstruction_spawner: Arc<dyn AsyncComputationSpawner>,
     ) -> Self {
+        let (failed_reconstructions_sender, failed_reconstructions_receiver) = mpsc::channel();
         Self {
             clock,
             validator_signer,
@@ -369,11 +467,16 @@ impl ShardsManagerActor {
             shard_tracker,
             peer_manager_adapter: network_adapter,
             client_adapter,
-            rs: ReedSolomon::new(
-                epoch_manager.num_data_parts(),
-                epoch_manager.num_total_parts() - epoch_manager.num_data_parts(),
-            )
-            .unwrap(),
+            rs: Arc::new(
+                ReedSolomon::new(
+                    epoch_manager.num_data_parts(),
+                    epoch_manager.num_total_parts() - epoch_manager.num_data_parts(),
+                )
+                .unwrap(),
+            ),
+            chunk_reconstruction_spawner,
+            failed_reconstructions_sender,
+            failed_reconstructions_receiver,
             encoded_chunks: EncodedChunksCache::new(),
             requested_partial_encoded_chunks: RequestPool::new(
                 CHUNK_REQUEST_RETRY,
@@ -554,6 +657,7 @@ impl ShardsManagerActor {
                     shard_id,
                     only_archival: request_from_archival,
                     min_height: height.saturating_sub(CHUNK_REQUEST_PEER_HORIZON),
+                    height,
                 };
 
                 self.peer_manager_adapter.send(PeerManagerMessageRequest::NetworkRequests(
@@ -845,6 +949,7 @@ impl ShardsManagerActor {
             header_head_height = self.chain_header_head.height,
             pool_size = self.requested_partial_encoded_chunks.len())
         .entered();
+        self.process_failed_reconstructions();
         let me = self.validator_signer.get().map(|signer| signer.validator_id().clone());
         // Process chunk one part requests.
         let requests = self.requested_partial_encoded_chunks.fetch(self.clock.now().into());
@@ -1121,41 +1226,6 @@ impl ShardsManagerActor {
         }
     }
 
-    fn check_chunk_complete(&mut self, chunk: &mut EncodedShardChunk) -> ChunkStatus {
-        let _span = debug_span!(
-            target: "chunks",
-            "check_chunk_complete",
-            height_included = chunk.cloned_header().height_included(),
-            shard_id = ?chunk.cloned_header().shard_id(),
-            chunk_hash = ?chunk.chunk_hash())
-        .entered();
-
-        let data_parts = self.epoch_manager.num_data_parts();
-        if chunk.content().num_fetched_parts() < data_parts {
-            debug!(target: "chunks", num_fetched_parts = chunk.content().num_fetched_parts(), data_parts, "Incomplete");
-            return ChunkStatus::Incomplete;
-        }
-
-        let encoded_length = chunk.encoded_length();
-        if let Err(err) = reed_solomon_decode::<TransactionReceipt>(
-            &self.rs,
-            chunk.content_mut().parts.as_mut_slice(),
-            encoded_length as usize,
-        ) {
-            debug!(target: "chunks", ?err, "Invalid: Failed to decode");
-            return ChunkStatus::Invalid;
-        }
-
-        let (merkle_root, merkle_paths) = chunk.content().get_merkle_hash_and_paths();
-        if merkle_root != chunk.encoded_merkle_root() {
-            debug!(target: "chunks", ?merkle_root, chunk_encoded_merkle_root = ?chunk.encoded_merkle_root(), "Invalid: Wrong merkle root");
-            return ChunkStatus::Invalid;
-        }
-
-        debug!(target: "chunks", "Complete");
-        ChunkStatus::Complete(merkle_paths)
-    }
-
     /// Add a part to current encoded chunk stored in memory. It's present only if One Part was present and signed correctly.
     fn validate_part(
         &mut self,
@@ -1174,37 +1244,6 @@ impl ShardsManagerActor {
         }
     }
 
-    fn decode_encoded_chunk_if_complete(
-        &mut self,
-        mut encoded_chunk: EncodedShardChunk,
-        me: Option<&AccountId>,
-    ) -> Result<Option<(ShardChunk, PartialEncodedChunk)>, Error> {
-        match self.check_chunk_complete(&mut encoded_chunk) {
-            ChunkStatus::Complete(merkle_paths) => {
-                self.requested_partial_encoded_chunks.remove(&encoded_chunk.chunk_hash());
-                match decode_encoded_chunk(
-                    &encoded_chunk,
-                    merkle_paths,
-                    me,
-                    self.epoch_manager.as_ref(),
-                    &self.shard_tracker,
-                ) {
-                    Ok(chunk) => Ok(Some(chunk)),
-                    Err(err) => {
-                        self.encoded_chunks.remove(&encoded_chunk.chunk_hash());
-                        Err(err)
-                    }
-                }
-            }
-            ChunkStatus::Incomplete => Ok(None),
-            ChunkStatus::Invalid => {
-                let chunk_hash = encoded_chunk.chunk_hash();
-                self.encoded_chunks.remove(&chunk_hash);
-                Err(Error::InvalidChunk)
-            }
-        }
-    }
-
     fn validate_partial_encoded_chunk_forward(
         &mut self,
         forward: &PartialEncodedChunkForwardMsg,
@@ -1482,6 +1521,8 @@ impl ShardsManagerActor {
     ///    are needed for processing the full chunk
     ///  ProcessPartialEncodedChunkResult::HaveAllPartsAndReceipts: if all parts and
     ///    receipts in the chunk are received and the chunk has been processed.
+    ///  ProcessPartialEncodedChunkResult::Reconstructing: if enough parts are received and
+    ///    the full chunk is being reconstructed in the background.
     fn process_partial_encoded_chunk(
         &mut self,
         partial_encoded_chunk: MaybeValidated<PartialEncodedChunk>,
@@ -1779,19 +1820,51 @@ impl ShardsManagerActor {
                     Some(part_entry.part.clone());
             }
 
-            let (shard_chunk, partial_chunk) = self
-                .decode_encoded_chunk_if_complete(encoded_chunk, me)?
-                .expect("decoding shouldn't fail");
-
-            // For consistency, only persist shard_chunk if we actually care about the shard.
-            // Don't persist if we don't care about the shard, even if we accidentally got enough
-            // parts to reconstruct the full shard.
-            if cares_about_shard {
-                self.complete_chunk(partial_chunk, Some(shard_chunk));
-            } else {
-                self.complete_chunk(partial_chunk, None);
-            }
-            return Ok(ProcessPartialEncodedChunkResult::HaveAllPartsAndReceipts);
+            // The chunk is marked as complete right away so that parts arriving while it is being
+            // reconstructed don't trigger another reconstruction. The decoded chunk is handed over
+            // to the client, which persists it, once the background reconstruction finishes.
+            self.mark_chunk_complete(&chunk_hash);
+
+            let rs = self.rs.clone();
+            let me = me.cloned();
+            let epoch_manager = self.epoch_manager.clone();
+            let shard_tracker = self.shard_tracker.clone();
+            let client_adapter = self.client_adapter.clone();
+            let failure_guard = FailedReconstructionGuard {
+                chunk_hash: Some(chunk_hash.clone()),
+                sender: self.failed_reconstructions_sender.clone(),
+            };
+            self.chunk_reconstruction_spawner.spawn("reconstruct_chunk", move || {
+                let _timer = metrics::CHUNK_RECONSTRUCTION_TIME.start_timer();
+                match reconstruct_chunk(
+                    &rs,
+                    &mut encoded_chunk,
+                    me.as_ref(),
+                    epoch_manager.as_ref(),
+                    &shard_tracker,
+                ) {
+                    Ok((shard_chunk, partial_chunk)) => {
+                        // For consistency, only persist shard_chunk if we actually care about the
+                        // shard. Don't persist if we don't care about the shard, even if we
+                        // accidentally got enough parts to reconstruct the full shard.
+                        let shard_chunk = cares_about_shard.then_some(shard_chunk);
+                        failure_guard.disarm();
+                        client_adapter.send(ShardsManagerResponse::ChunkCompleted {
+                            partial_chunk,
+                            shard_chunk,
+                        });
+                    }
+                    Err(Error::InvalidChunk) => {
+                        failure_guard.disarm();
+                        client_adapter.send(ShardsManagerResponse::InvalidChunk(encoded_chunk));
+                    }
+                    Err(err) => {
+                        // Dropping the guard marks the chunk incomplete again on the actor.
+                        error!(target: "chunks", ?chunk_hash, ?err, "Failed to reconstruct chunk");
+                    }
+                }
+            });
+            return Ok(ProcessPartialEncodedChunkResult::Reconstructing);
         }
         Ok(ProcessPartialEncodedChunkResult::NeedMorePartsOrReceipts)
     }
@@ -1804,14 +1877,35 @@ impl ShardsManagerActor {
     ) {
         let _span = debug_span!(target: "chunks", "complete_chunk").entered();
         let chunk_hash = partial_chunk.chunk_hash();
-        self.encoded_chunks.mark_entry_complete(&chunk_hash);
-        self.encoded_chunks.remove_from_cache_if_outside_horizon(&chunk_hash);
-        self.requested_partial_encoded_chunks.remove(&chunk_hash);
+        self.mark_chunk_complete(&chunk_hash);
         debug!(target: "chunks", "Completed chunk {:?}", chunk_hash);
         self.client_adapter
             .send(ShardsManagerResponse::ChunkCompleted { partial_chunk, shard_chunk });
     }
 
+    /// Marks the chunk as complete in the cache and stops requesting its parts.
+    fn mark_chunk_complete(&mut self, chunk_hash: &ChunkHash) {
+        self.encoded_chunks.mark_entry_complete(chunk_hash);
+        self.encoded_chunks.remove_from_cache_if_outside_horizon(chunk_hash);
+        self.requested_partial_encoded_chunks.remove(chunk_hash);
+    }
+
+    /// Marks chunks whose background reconstruction failed as incomplete again and adds them back
+    /// to the request pool, so that they are reconstructed once more parts or the next block arrive.
+    fn process_failed_reconstructions(&mut self) {
+        let me = self.validator_signer.get().map(|signer| signer.validator_id().clone());
+        while let Ok(chunk_hash) = self.failed_reconstructions_receiver.try_recv() {
+            let Some(header) = self.encoded_chunks.get(&chunk_hash).map(|e| e.header.clone())
+            else {
+                debug!(target: "chunks", ?chunk_hash, "Chunk with failed reconstruction is no longer cached");
+                continue;
+            };
+            debug!(target: "chunks", ?chunk_hash, "Retrying chunk with failed reconstruction");
+            self.encoded_chunks.mark_entry_incomplete(&chunk_hash);
+            self.request_chunk_single_mark_only(&header, me.as_ref());
+        }
+    }
+
     /// Try to process chunks in the chunk cache whose previous block hash is `prev_block_hash` and
     /// who are not marked as complete yet
     /// This function is needed because chunks in chunk cache will only be marked as complete after
@@ -2141,6 +2235,7 @@ impl ShardsManagerActor {
             "type" = <&'static str>::from(&request)
         )
         .entered();
+        self.process_failed_reconstructions();
         let me = self.validator_signer.get().map(|signer| signer.validator_id().clone());
         let me = me.as_ref();
         match request {
@@ -2215,6 +2310,7 @@ impl ShardsManagerActor {
             "type" = <&'static str>::from(&request)
         )
         .entered();
+        self.process_failed_reconstructions();
         let me = self.validator_signer.get().map(|signer| signer.validator_id().clone());
         let me = me.as_ref();
         match request {
@@ -2289,7 +2385,9 @@ impl PartialEncodedChunkResponseSource {
 #[cfg(test)]
 mod test {
     use assert_matches::assert_matches;
+    use near_async::futures::InlineAsyncComputationSpawnerForTest;
     use near_async::messaging::IntoSender;
+    use near_async::test_utils::FakeDelayedActionRunner;
     use near_async::time::FakeClock;
     use near_chain_configs::MutableConfigValue;
     use near_epoch_manager::shard_tracker::TrackedConfig;
@@ -2301,7 +2399,7 @@ mod test {
     use near_primitives::types::EpochId;
     use near_primitives::validator_signer::EmptyValidatorSigner;
     use near_store::test_utils::create_test_store;
-    use std::sync::Arc;
+    use std::sync::{Arc, Mutex};
 
     use super::*;
     use crate::logic::persist_chunk;
@@ -2353,6 +2451,7 @@ mod test {
             mock_tip.clone(),
             mock_tip,
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let added = clock.now().into();
         shards_manager.requested_partial_encoded_chunks.insert(
@@ -2381,6 +2480,72 @@ mod test {
         };
     }
 
+    /// Chunk requests should be resent periodically, every retry period.
+    #[test]
+    fn test_periodically_resend_chunk_requests() {
+        let epoch_id = EpochId::default();
+        let mock_tip = Tip {
+            height: 0,
+            last_block_hash: CryptoHash::default(),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id,
+            next_epoch_id: EpochId::default(),
+        };
+        let store = create_test_store();
+        let epoch_manager = setup_epoch_manager_with_block_and_chunk_producers(
+            store.clone(),
+            vec!["test".parse().unwrap()],
+            vec![],
+            1,
+            2,
+        );
+        let epoch_manager = Arc::new(epoch_manager.into_handle());
+        let shard_layout = epoch_manager.get_shard_layout(&epoch_id).unwrap();
+        let shard_id = shard_layout.shard_ids().next().unwrap();
+        let shard_tracker = ShardTracker::new(TrackedConfig::AllShards, epoch_manager.clone());
+        let network_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let client_adapter = Arc::new(MockClientAdapterForShardsManager::default());
+        let clock = FakeClock::default();
+        let mut shards_manager = ShardsManagerActor::new(
+            clock.clock(),
+            mutable_validator_signer(&"test".parse().unwrap()),
+            epoch_manager.clone(),
+            epoch_manager,
+            shard_tracker,
+            network_adapter.as_sender(),
+            client_adapter.as_sender(),
+            store.chunk_store(),
+            mock_tip.clone(),
+            mock_tip,
+            CHUNK_REQUEST_RETRY,
+            Arc::new(InlineAsyncComputationSpawnerForTest),
+        );
+        let added = clock.now().into();
+        shards_manager.requested_partial_encoded_chunks.insert(
+            ChunkHash(hash(&[1])),
+            ChunkRequestInfo {
+                height: 0,
+                ancestor_hash: Default::default(),
+                prev_block_hash: Default::default(),
+                shard_id,
+                added,
+                last_requested: added,
+            },
+        );
+
+        let mut runner = FakeDelayedActionRunner::new(clock);
+        shards_manager.periodically_resend_chunk_requests(&mut runner);
+        assert_eq!(runner.advance(&mut shards_manager, CHUNK_REQUEST_RETRY / 2), 0);
+        assert!(network_adapter.requests.read().unwrap().is_empty());
+
+        assert_eq!(runner.advance(&mut shards_manager, CHUNK_REQUEST_RETRY / 2), 1);
+        assert_eq!(network_adapter.requests.read().unwrap().len(), 1);
+
+        assert_eq!(runner.advance(&mut shards_manager, CHUNK_REQUEST_RETRY * 2), 2);
+        assert_eq!(network_adapter.requests.read().unwrap().len(), 3);
+        assert_eq!(runner.pending_actions(), vec!["resend_chunk_requests"]);
+    }
+
     #[test]
     fn test_resend_chunk_requests() {
         // Test that resending chunk requests won't request for parts the node already received
@@ -2398,6 +2563,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         // process chunk part 0
         let partial_encoded_chunk = fixture.make_partial_encoded_chunk(&[0]);
@@ -2479,6 +2645,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
 
         // part id > num parts
@@ -2511,6 +2678,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let count_num_forward_msgs = |fixture: &ChunkTestFixture| {
             fixture
@@ -2594,6 +2762,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         shards_manager.insert_header_if_not_exists_and_process_cached_chunk_forwards(
             &fixture.mock_chunk_header,
@@ -2685,6 +2854,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let (most_parts, other_parts) = {
             let mut most_parts = fixture.mock_chunk_parts.clone();
@@ -2743,6 +2913,88 @@ mod test {
             .is_none());
     }
 
+    /// Holds spawned computations until the test runs or drops them.
+    #[derive(Default)]
+    struct DeferredAsyncComputationSpawner {
+        tasks: Mutex<Vec<Box<dyn FnOnce() + Send>>>,
+    }
+
+    impl AsyncComputationSpawner for DeferredAsyncComputationSpawner {
+        fn spawn_boxed(&self, _name: &str, f: Box<dyn FnOnce() + Send>) {
+            self.tasks.lock().unwrap().push(f);
+        }
+    }
+
+    #[test]
+    // Test that a chunk whose background reconstruction never completes is marked incomplete
+    // again and requested, and that it can be completed on a later attempt.
+    fn test_failed_chunk_reconstruction_is_retried() {
+        let fixture = ChunkTestFixture::default();
+        let clock = FakeClock::default();
+        let spawner = Arc::new(DeferredAsyncComputationSpawner::default());
+        let mut shards_manager = ShardsManagerActor::new(
+            clock.clock(),
+            mutable_validator_signer(&fixture.mock_shard_tracker),
+            Arc::new(fixture.epoch_manager.clone()),
+            Arc::new(fixture.epoch_manager.clone()),
+            fixture.shard_tracker.clone(),
+            fixture.mock_network.as_sender(),
+            fixture.mock_client_adapter.as_sender(),
+            fixture.store.clone(),
+            fixture.mock_chain_head.clone(),
+            fixture.mock_chain_head.clone(),
+            Duration::hours(1),
+            spawner.clone(),
+        );
+        let chunk_hash = fixture.mock_chunk_header.chunk_hash();
+        let forward = PartialEncodedChunkForwardMsg::from_header_and_parts(
+            &fixture.mock_chunk_header,
+            fixture.mock_chunk_parts.clone(),
+        );
+        shards_manager
+            .process_partial_encoded_chunk_forward(forward, Some(&fixture.mock_shard_tracker))
+            .unwrap();
+        shards_manager.insert_header_if_not_exists_and_process_cached_chunk_forwards(
+            &fixture.mock_chunk_header,
+        );
+        let process_result = shards_manager
+            .try_process_chunk_parts_and_receipts(
+                &fixture.mock_chunk_header,
+                Some(&fixture.mock_shard_tracker),
+            )
+            .unwrap();
+        assert_matches!(process_result, ProcessPartialEncodedChunkResult::Reconstructing);
+        assert_eq!(fixture.count_chunk_completion_messages(), 0);
+        assert!(shards_manager.encoded_chunks.get(&chunk_hash).unwrap().complete);
+
+        // The reconstruction is dropped without running, as if it failed.
+        spawner.tasks.lock().unwrap().clear();
+        shards_manager.resend_chunk_requests();
+        assert!(!shards_manager.encoded_chunks.get(&chunk_hash).unwrap().complete);
+        assert!(shards_manager
+            .encoded_chunks
+            .get_incomplete_chunks(fixture.mock_chunk_header.prev_block_hash())
+            .unwrap()
+            .contains(&chunk_hash));
+        assert!(shards_manager.requested_partial_encoded_chunks.contains_key(&chunk_hash));
+
+        // The next attempt reconstructs the chunk from the cached parts.
+        let process_result = shards_manager
+            .try_process_chunk_parts_and_receipts(
+                &fixture.mock_chunk_header,
+                Some(&fixture.mock_shard_tracker),
+            )
+            .unwrap();
+        assert_matches!(process_result, ProcessPartialEncodedChunkResult::Reconstructing);
+        for task in std::mem::take(&mut *spawner.tasks.lock().unwrap()) {
+            task();
+        }
+        assert_eq!(fixture.count_chunk_completion_messages(), 1);
+        shards_manager.resend_chunk_requests();
+        assert!(shards_manager.encoded_chunks.get(&chunk_hash).unwrap().complete);
+        assert!(!shards_manager.requested_partial_encoded_chunks.contains_key(&chunk_hash));
+    }
+
     #[test]
     // Test that when a validator receives a chunk forward before the chunk header, and that the
     // chunk header first arrives as part of a block, it should store the forward and use it
@@ -2762,6 +3014,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let forward = PartialEncodedChunkForwardMsg::from_header_and_parts(
             &fixture.mock_chunk_header,
@@ -2784,7 +3037,7 @@ mod test {
             )
             .unwrap();
         match process_result {
-            ProcessPartialEncodedChunkResult::HaveAllPartsAndReceipts => {}
+            ProcessPartialEncodedChunkResult::Reconstructing => {}
             _ => {
                 panic!("Unexpected process_result: {:?}", process_result);
             }
@@ -2831,6 +3084,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
 
         shards_manager
@@ -2868,6 +3122,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
 
         shards_manager
@@ -2902,6 +3157,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
 
         persist_chunk(
@@ -2936,6 +3192,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
 
         let mut update = fixture.chain_store.store_update();
@@ -2971,6 +3228,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         // Split the part ords into two groups.
         assert!(fixture.all_part_ords.len() >= 2);
@@ -3015,6 +3273,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         // Only add half of the parts to the cache.
         assert!(fixture.all_part_ords.len() >= 2);
@@ -3063,6 +3322,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         // Split the part ords into three groups; put one in cache, the second in partial
         // and the third is missing. We should return the first two groups.
@@ -3109,6 +3369,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let (source, response) =
             shards_manager.prepare_partial_encoded_chunk_response(PartialEncodedChunkRequestMsg {
@@ -3135,6 +3396,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let (source, response) =
             shards_manager.prepare_partial_encoded_chunk_response(PartialEncodedChunkRequestMsg {
@@ -3161,6 +3423,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let mut update = fixture.chain_store.store_update();
         let shard_chunk = fixture
@@ -3197,6 +3460,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let mut update = fixture.chain_store.store_update();
         let shard_chunk = fixture
@@ -3231,6 +3495,7 @@ mod test {
             fixture.mock_chain_head.clone(),
             fixture.mock_chain_head.clone(),
             Duration::hours(1),
+            Arc::new(InlineAsyncComputationSpawnerForTest),
         );
         let part = fixture.make_partial_encoded_chunk(&fixture.mock_part_ords);
         shards_manager
@@ -1,7 +1,9 @@
 //! Structs in this module are used for debug purposes, and might change at any time
 //! without backwards compatibility of JSON encoding.
 use crate::types::StatusError;
+use near_chain_configs::{ReshardingHandleState, ThreadPoolsConfig};
 use near_primitives::congestion_info::CongestionInfo;
+use near_primitives::serialize::dec_format;
 use near_primitives::types::{EpochId, ShardId};
 use near_primitives::views::{
     CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, RequestedStatePartsView,
@@ -174,6 +176,168 @@ pub struct ValidatorStatus {
     pub banned_chunk_producers: Vec<(EpochId, Vec<AccountId>)>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct DelayedReceiptsReceiverView {
+    pub receiver_id: AccountId,
+    pub num_receipts: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ShardDelayedReceiptsView {
+    pub shard_id: ShardId,
+    // Number of receipts in the delayed receipts queue at the head.
+    pub queue_len: u64,
+    // Number of receipts from the front of the queue that were inspected to
+    // find the top receivers. Less than `queue_len` for very long queues.
+    pub num_inspected_receipts: u64,
+    // Receivers with the most receipts among the inspected ones, in descending order.
+    pub top_receivers: Vec<DelayedReceiptsReceiverView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct DelayedReceiptsView {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    // Only the shards tracked by this node.
+    pub shards: Vec<ShardDelayedReceiptsView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReceiverQueueUsageView {
+    pub receiver_id: AccountId,
+    pub num_receipts: u64,
+    // Congestion gas of the receipts.
+    #[serde(with = "dec_format")]
+    pub gas: u128,
+    pub bytes: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ShardReceiverBackpressureView {
+    pub shard_id: ShardId,
+    // Number of receivers with receipts in the delayed receipts queue.
+    pub num_delayed_receivers: usize,
+    // Receivers with the most gas and bytes in the delayed receipts queue, in descending order.
+    pub delayed_by_gas: Vec<ReceiverQueueUsageView>,
+    pub delayed_by_bytes: Vec<ReceiverQueueUsageView>,
+    // Number of receivers with receipts in the outgoing buffers.
+    pub num_buffered_receivers: usize,
+    // Receivers with the most gas and bytes in the outgoing buffers to all the shards, in
+    // descending order.
+    pub buffered_by_gas: Vec<ReceiverQueueUsageView>,
+    pub buffered_by_bytes: Vec<ReceiverQueueUsageView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReceiverBackpressureView {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    // Only the shards tracked by this node.
+    pub shards: Vec<ShardReceiverBackpressureView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ThreadAssignmentView {
+    pub pool: String,
+    pub thread: Option<String>,
+    pub cpu_set: Vec<usize>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ThreadPoolsView {
+    pub config: ThreadPoolsConfig,
+    // The threads pinned so far.
+    pub pinned_threads: Vec<ThreadAssignmentView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReshardingStatusView {
+    pub resharding_block_hash: CryptoHash,
+    pub resharding_block_height: BlockHeight,
+    pub parent_shards: Vec<ShardId>,
+    pub children_shards: Vec<ShardId>,
+    pub phase: String,
+    // Children shards whose flat storage is ready.
+    pub shards_done: Vec<ShardId>,
+    pub keys_copied: u64,
+    pub bytes_copied: u64,
+    // Rough estimate of the total size of the key-values to copy.
+    pub bytes_to_copy: Option<u64>,
+    // Estimated time left to copy the key-values, only known while copying.
+    pub eta_secs: Option<u64>,
+    // Whether the background work is running, paused or stopped, see `ReshardingControl`.
+    pub control_state: ReshardingHandleState,
+    // Result of the validation of the children state, once a split is done.
+    pub validation: Option<ReshardingValidationView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReshardingValidationView {
+    // Children shards tracked by the node, whose state was validated.
+    pub children_shards: Vec<ShardId>,
+    pub trie_keys_checked: u64,
+    pub flat_storage_keys_checked: u64,
+    pub num_mismatches: u64,
+    // The first mismatches found.
+    pub mismatches: Vec<ReshardingMismatchView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReshardingMismatchView {
+    pub shard_id: ShardId,
+    // Trie key, encoded in base64.
+    pub key: String,
+    pub kind: String,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct TargetShardBufferedReceiptsView {
+    pub target_shard: ShardId,
+    // Number of receipts in the outgoing buffer, according to its indices.
+    pub buffer_len: Option<u64>,
+    // Number, gas and size of the receipts according to the receipt groups queue.
+    pub num_receipts: u64,
+    #[serde(with = "dec_format")]
+    pub gas: u128,
+    pub bytes: u64,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ShardBufferedReceiptsView {
+    pub shard_id: ShardId,
+    pub state_root: CryptoHash,
+    #[serde(with = "dec_format")]
+    pub congestion_info_buffered_gas: Option<u128>,
+    // Size of both the delayed and the buffered receipts.
+    pub congestion_info_receipt_bytes: Option<u64>,
+    pub target_shards: Vec<TargetShardBufferedReceiptsView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct ReshardingBufferedReceiptsView {
+    pub resharding_block_hash: CryptoHash,
+    pub resharding_block_height: BlockHeight,
+    // Buffered receipts of the parent shard before the split, if its state is still available.
+    pub parent: Option<ShardBufferedReceiptsView>,
+    // Buffered receipts of the children shards tracked by the node, right after the split.
+    pub children: Vec<ShardBufferedReceiptsView>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone)]
+pub struct ReshardingEventView {
+    // When the node received the event, up to the period of the log summary.
+    pub received_at: Utc,
+    // Name of the event, e.g. `FlatStorageCopied`.
+    pub kind: String,
+    pub block_hash: CryptoHash,
+    // Unknown for the creation of a child memtrie, which happens for every resharding block.
+    pub block_height: Option<BlockHeight>,
+    pub parent_shards: Vec<ShardId>,
+    pub children_shards: Vec<ShardId>,
+    // State root of the created child memtrie.
+    pub state_root: Option<CryptoHash>,
+}
+
 // Different debug requests that can be sent by HTML pages, via GET.
 #[derive(Debug)]
 pub enum DebugStatus {
@@ -193,6 +357,16 @@ pub enum DebugStatus {
     ChainProcessingStatus,
     // The state parts already requested.
     RequestedStateParts,
+    // Delayed receipts queues of the tracked shards and their top receivers.
+    DelayedReceipts,
+    // Receivers with the most gas and bytes in the receipt queues of the tracked shards.
+    ReceiverBackpressure,
+    // Progress of the latest resharding event.
+    ReshardingStatus,
+    // Buffered receipts of the parent and children shards of the latest splits.
+    ReshardingBufferedReceipts,
+    // The latest resharding events received by the node since it started.
+    ReshardingEvents,
 }
 
 impl actix::Message for DebugStatus {
@@ -214,4 +388,14 @@ pub enum DebugStatusResponse {
     ChainProcessingStatus(ChainProcessingInfo),
     // The state parts already requested.
     RequestedStateParts(Vec<RequestedStatePartsView>),
+    // Delayed receipts queues of the tracked shards and their top receivers.
+    DelayedReceipts(DelayedReceiptsView),
+    // Receivers with the most gas and bytes in the receipt queues of the tracked shards.
+    ReceiverBackpressure(ReceiverBackpressureView),
+    // Progress of the events of the latest resharding, one per split shard or merge.
+    ReshardingStatus(Vec<ReshardingStatusView>),
+    // Buffered receipts before and after the splits of the latest resharding, one per split.
+    ReshardingBufferedReceipts(Vec<ReshardingBufferedReceiptsView>),
+    // The latest resharding events received by the node since it started, oldest first.
+    ReshardingEvents(Vec<ReshardingEventView>),
 }
@@ -1,19 +1,22 @@
 use actix::Message;
-use near_chain_configs::{ClientConfig, ProtocolConfigView};
+use near_chain_configs::{ClientConfig, ProtocolConfigView, ReshardingHandleState};
+use near_parameters::ParameterChange;
+use near_primitives::epoch_manager::EpochConfig;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::{ChunkHash, ShardChunk};
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockHeightDelta, BlockReference, EpochId, EpochReference,
+    MaybeBlockId, ProtocolVersion, ShardId, TransactionOrReceiptId,
 };
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, GasPriceView,
-    LightClientBlockLiteView, LightClientBlockView, MaintenanceWindowsView, QueryRequest,
-    QueryResponse, ReceiptView, SplitStorageInfoView, StateChangesKindsView,
-    StateChangesRequestView, StateChangesView, StateSyncStatusView, SyncStatusView, TxStatusView,
+    AccountReceiptDirection, AccountReceiptView, BlockView, ChunkView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView, SecondaryIndexEntryView,
+    SecondaryIndexQuery, SplitStorageInfoView, StateChangesKindsView, StateChangesRequestView,
+    StateChangesView, StateSyncStatusView, SyncStatusView, TxStatusView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use near_time::Duration;
@@ -274,6 +277,17 @@ impl Message for GetBlock {
     type Result = Result<BlockView, GetBlockError>;
 }
 
+/// Actor message requesting the last block of the canonical chain whose timestamp is at or
+/// before the given one.
+#[derive(Clone, Debug)]
+pub struct GetBlockByTimestamp {
+    pub timestamp_nanosec: u64,
+}
+
+impl Message for GetBlockByTimestamp {
+    type Result = Result<BlockView, GetBlockError>;
+}
+
 /// Get block with the block merkle tree. Used for testing
 #[derive(Debug)]
 pub struct GetBlockWithMerkleTree(pub BlockReference);
@@ -403,6 +417,12 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("Invalid state override: {error_message}")]
+    InvalidStateOverride {
+        error_message: String,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("Access key for public key {public_key} has never been observed on the node at block #{block_height}")]
     UnknownAccessKey {
         public_key: near_crypto::PublicKey,
@@ -883,6 +903,101 @@ impl Message for GetReceipt {
     type Result = Result<Option<ReceiptView>, GetReceiptError>;
 }
 
+/// Lists the receipts executed or created by an account in the most recent blocks, using the
+/// index maintained by nodes with `save_account_receipts` enabled.
+#[derive(Debug)]
+pub struct GetAccountReceipts {
+    pub account_id: AccountId,
+    /// Number of the most recent blocks to look at, counting back from the head.
+    pub block_window: BlockHeightDelta,
+    /// Only return receipts with the given relation to the account.
+    pub direction: Option<AccountReceiptDirection>,
+    /// Only return receipts following the given (height, receipt id) pair, used for pagination.
+    pub after: Option<(BlockHeight, CryptoHash)>,
+    pub limit: usize,
+    pub include_outcomes: bool,
+}
+
+#[derive(Debug)]
+pub struct AccountReceiptsResponse {
+    pub receipts: Vec<AccountReceiptView>,
+    /// Set if there may be more receipts, pass it as `after` to get the next page.
+    pub next: Option<(BlockHeight, CryptoHash)>,
+}
+
+impl Message for GetAccountReceipts {
+    type Result = Result<AccountReceiptsResponse, GetAccountReceiptsError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetAccountReceiptsError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("The index of receipts by account is not enabled on this node")]
+    IndexDisabled,
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetAccountReceiptsError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+/// Lists the entries of one of the secondary indexes maintained by the node, see
+/// `SecondaryIndexesConfig`.
+#[derive(Debug)]
+pub struct GetSecondaryIndex {
+    pub query: SecondaryIndexQuery,
+    /// Only return entries following the given position, used for pagination. The position
+    /// is a (height, hash) pair for the indexes by account and a (timestamp, block hash) pair
+    /// for the index of blocks.
+    pub after: Option<(u64, CryptoHash)>,
+    pub limit: usize,
+}
+
+#[derive(Debug)]
+pub struct SecondaryIndexResponse {
+    pub entries: Vec<SecondaryIndexEntryView>,
+    /// Set if there may be more entries, pass it as `after` to get the next page.
+    pub next: Option<(u64, CryptoHash)>,
+}
+
+impl Message for GetSecondaryIndex {
+    type Result = Result<SecondaryIndexResponse, GetSecondaryIndexError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetSecondaryIndexError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("The {0} index is not enabled on this node")]
+    IndexDisabled(String),
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetSecondaryIndexError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct GetProtocolConfig(pub BlockReference);
 
@@ -914,6 +1029,67 @@ impl From<near_chain_primitives::Error> for GetProtocolConfigError {
     }
 }
 
+/// Requests the fully resolved `EpochConfig` used for the given epoch.
+#[derive(Debug)]
+pub struct GetEpochConfig {
+    pub epoch_reference: EpochReference,
+}
+
+/// `EpochConfig` in effect for a particular epoch, after applying the overrides stored for the
+/// chain and mapping the epoch to its protocol version.
+#[derive(Debug)]
+pub struct EffectiveEpochConfig {
+    pub epoch_id: EpochId,
+    pub protocol_version: ProtocolVersion,
+    pub epoch_config: EpochConfig,
+}
+
+impl Message for GetEpochConfig {
+    type Result = Result<EffectiveEpochConfig, GetEpochConfigError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetEpochConfigError {
+    #[error("IO Error: {0}")]
+    IOError(String),
+    #[error("Unknown epoch")]
+    UnknownEpoch,
+    // NOTE: Currently, the underlying errors are too broad, and while we tried to handle
+    // expected cases, we cannot statically guarantee that no other errors will be returned
+    // in the future.
+    // TODO #3851: Remove this variant once we can exhaustively match all the underlying errors
+    #[error("It is a bug if you receive this error type, please, report this incident: https://github.com/near/nearcore/issues/new/choose. Details: {0}")]
+    Unreachable(String),
+}
+
+impl From<near_chain_primitives::Error> for GetEpochConfigError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::DBNotFoundErr(_)
+            | near_chain_primitives::Error::EpochOutOfBounds(_) => Self::UnknownEpoch,
+            near_chain_primitives::Error::IOErr(error) => Self::IOError(error.to_string()),
+            _ => Self::Unreachable(error.to_string()),
+        }
+    }
+}
+
+/// Requests the changes of the runtime parameters from the protocol version of the epoch of the
+/// given block to the latest protocol version supported by the node, i.e. the version of the
+/// next protocol upgrade the node votes for.
+#[derive(Debug)]
+pub struct GetRuntimeConfigChanges(pub BlockReference);
+
+#[derive(Debug)]
+pub struct RuntimeConfigChanges {
+    pub current_protocol_version: ProtocolVersion,
+    pub next_protocol_version: ProtocolVersion,
+    pub changes: Vec<ParameterChange>,
+}
+
+impl Message for GetRuntimeConfigChanges {
+    type Result = Result<RuntimeConfigChanges, GetProtocolConfigError>;
+}
+
 #[derive(Debug)]
 pub struct GetMaintenanceWindows {
     pub account_id: AccountId,
@@ -968,6 +1144,21 @@ impl From<near_chain_primitives::Error> for GetClientConfigError {
     }
 }
 
+/// Operator request to control the resharding running in the background, e.g. to pause its
+/// I/O during peak load. Returns the resulting state.
+#[derive(Debug, Clone, Copy)]
+pub enum ReshardingControl {
+    Pause,
+    Resume,
+    /// Stops resharding until the node restarts, which starts the aborted split again from the
+    /// copy of key-values. Meant as a last resort, e.g. to free resources for a while.
+    Abort,
+}
+
+impl Message for ReshardingControl {
+    type Result = Result<ReshardingHandleState, StatusError>;
+}
+
 #[derive(Debug)]
 pub struct GetSplitStorageInfo {}
 
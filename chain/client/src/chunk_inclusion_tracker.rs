@@ -4,6 +4,7 @@ use near_async::time::Utc;
 use near_chain_primitives::Error;
 use near_o11y::log_assert_fail;
 use near_primitives::block_body::ChunkEndorsementSignatures;
+use near_primitives::chunk_suppression::ChunkSuppression;
 use near_primitives::hash::CryptoHash;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::stateless_validation::validator_assignment::ChunkEndorsementsState;
@@ -40,6 +41,10 @@ pub struct ChunkInclusionTracker {
 
     // Track banned chunk producers for a given epoch. We filter out chunks produced by them.
     banned_chunk_producers: LruCache<(EpochId, AccountId), ()>,
+
+    // Verified chunk suppressions by previous block hash and shard, see
+    // `near_primitives::chunk_suppression`.
+    prev_block_to_chunk_suppressions: LruCache<CryptoHash, HashMap<ShardId, ChunkSuppression>>,
 }
 
 enum ChunkExclusionReason {
@@ -66,9 +71,30 @@ impl ChunkInclusionTracker {
             banned_chunk_producers: LruCache::new(
                 NonZeroUsize::new(NUM_EPOCH_CHUNK_PRODUCERS_TO_KEEP_IN_BLOCKLIST).unwrap(),
             ),
+            prev_block_to_chunk_suppressions: LruCache::new(
+                NonZeroUsize::new(CHUNK_HEADERS_FOR_INCLUSION_CACHE_SIZE).unwrap(),
+            ),
         }
     }
 
+    /// Records a chunk suppression whose signature was verified, so that the block built on its
+    /// previous block can mark the chunk as suppressed instead of missed.
+    pub fn add_chunk_suppression(&mut self, suppression: ChunkSuppression) {
+        let shard_id = suppression.chunk_production_key().shard_id;
+        self.prev_block_to_chunk_suppressions
+            .get_or_insert_mut(*suppression.prev_block_hash(), HashMap::new)
+            .insert(shard_id, suppression);
+    }
+
+    /// The chunk suppression of the chunk of the shard built on `prev_block_hash`, if any.
+    pub fn get_chunk_suppression(
+        &self,
+        prev_block_hash: &CryptoHash,
+        shard_id: ShardId,
+    ) -> Option<&ChunkSuppression> {
+        self.prev_block_to_chunk_suppressions.peek(prev_block_hash)?.get(&shard_id)
+    }
+
     /// Call this function once we've collected all encoded chunk body and we are ready to include the chunk in block.
     pub fn mark_chunk_header_ready_for_inclusion(
         &mut self,
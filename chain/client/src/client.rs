@@ -31,9 +31,11 @@ use near_chain::types::{
 };
 use near_chain::{
     BlockProcessingArtifact, BlockStatus, Chain, ChainGenesis, ChainStoreAccess, Doomslug,
-    DoomslugThresholdMode, Provenance,
+    DoomslugThresholdMode, Provenance, ReceiptFilter,
+};
+use near_chain_configs::{
+    ClientConfig, MutableValidatorSigner, TrackedShardsConfig, UpdateableClientConfig,
 };
-use near_chain_configs::{ClientConfig, MutableValidatorSigner, UpdateableClientConfig};
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardedTransactionPool;
 use near_chunks::logic::{
@@ -42,6 +44,7 @@ use near_chunks::logic::{
 use near_chunks::shards_manager_actor::ShardsManagerActor;
 use near_client_primitives::debug::ChunkProduction;
 use near_client_primitives::types::{Error, StateSyncStatus};
+use near_crypto::Signature;
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::client::ProcessTxResponse;
@@ -54,6 +57,10 @@ use near_pool::InsertTransactionResult;
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::block_header::ApprovalType;
 use near_primitives::challenge::{Challenge, ChallengeBody, PartialState};
+use near_primitives::chunk_suppression::{is_chunk_suppressed, ChunkSuppression};
+use near_primitives::congestion_info::{
+    CongestionControl, RejectTransactionReason, ShardAcceptsTransactions,
+};
 use near_primitives::epoch_info::RngSeed;
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
@@ -61,9 +68,10 @@ use near_primitives::merkle::{merklize, MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::receipt::Receipt;
 use near_primitives::sharding::{
-    EncodedShardChunk, PartialEncodedChunk, ShardChunk, ShardChunkHeader, StateSyncInfo,
-    StateSyncInfoV1,
+    EncodedShardChunk, PartialEncodedChunk, ReceiptProof, ShardChunk, ShardChunkHeader,
+    StateSyncInfo, StateSyncInfoV1,
 };
+use near_primitives::state_sync::ReceiptProofResponse;
 use near_primitives::stateless_validation::ChunkProductionKey;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::chunk_extra::ChunkExtra;
@@ -72,7 +80,7 @@ use near_primitives::unwrap_or_return;
 use near_primitives::upgrade_schedule::ProtocolUpgradeVotingSchedule;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::validator_signer::ValidatorSigner;
-use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
+use near_primitives::version::{ProtocolFeature, ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{CatchupStatusView, DroppedReason};
 use near_store::ShardUId;
 use reed_solomon_erasure::galois_8::ReedSolomon;
@@ -97,6 +105,10 @@ const BLOCK_HORIZON: u64 = 500;
 /// number of blocks at the epoch start for which we will log more detailed info
 pub const EPOCH_START_INFO_BLOCKS: u64 = 500;
 
+/// Number of blocks after which the transactions rejected because of the congestion of the shard
+/// of their signer are suggested to be retried.
+const TX_ADMISSION_RETRY_AFTER_BLOCKS: i32 = 10;
+
 /// Defines whether in case of adversarial block production invalid blocks can
 /// be produced.
 #[cfg(feature = "test_features")]
@@ -200,6 +212,9 @@ pub struct Client {
     chunk_distribution_network: Option<ChunkDistributionNetwork>,
     /// Upgrade schedule which determines when the client starts voting for new protocol versions.
     upgrade_schedule: ProtocolUpgradeVotingSchedule,
+    /// Last epoch in which a chunk validator only node warned that it skips the chunks it was
+    /// assigned to produce, so that it warns once per epoch rather than at every height.
+    last_skipped_chunks_warning_epoch: Option<EpochId>,
 }
 
 impl AsRef<Client> for Client {
@@ -220,9 +235,33 @@ impl Client {
             .config
             .produce_chunk_add_transactions_time_limit
             .update(update_client_config.produce_chunk_add_transactions_time_limit);
+        if let Some(tracked_shards_config) = update_client_config.tracked_shards_config {
+            is_updated |= self.update_tracked_shards_config(tracked_shards_config);
+        }
         is_updated
     }
 
+    /// Replaces the config of the tracked shards from the epoch after the next one.
+    fn update_tracked_shards_config(&self, tracked_shards_config: TrackedShardsConfig) -> bool {
+        if self.config.chunk_validator_only {
+            tracing::warn!(target: "client", "the node runs as a chunk validator only, ignoring the tracked shards config update");
+            return false;
+        }
+        let head_epoch_height = self
+            .chain
+            .head()
+            .and_then(|head| Ok(self.epoch_manager.get_epoch_info(&head.epoch_id)?.epoch_height()));
+        match head_epoch_height {
+            Ok(head_epoch_height) => self
+                .shard_tracker
+                .update_tracked_config(tracked_shards_config.into(), head_epoch_height),
+            Err(err) => {
+                tracing::warn!(target: "client", ?err, "failed to update the tracked shards config");
+                false
+            }
+        }
+    }
+
     /// Updates client's mutable validator signer.
     /// It will update all validator signers that synchronize with it.
     pub(crate) fn update_validator_signer(&self, signer: Option<Arc<ValidatorSigner>>) -> bool {
@@ -252,6 +291,7 @@ impl Client {
         rng_seed: RngSeed,
         snapshot_callbacks: Option<SnapshotCallbacks>,
         async_computation_spawner: Arc<dyn AsyncComputationSpawner>,
+        chunk_validation_spawner: Arc<dyn AsyncComputationSpawner>,
         partial_witness_adapter: PartialWitnessSenderForClient,
         resharding_sender: ReshardingSender,
         state_sync_future_spawner: Arc<dyn FutureSpawner>,
@@ -265,10 +305,16 @@ impl Client {
         };
         let chain_config = ChainConfig {
             save_trie_changes: config.save_trie_changes,
+            save_account_receipts: config.save_account_receipts,
+            secondary_indexes: config.secondary_indexes,
+            save_access_key_usage: config.save_access_key_usage,
             background_migration_threads: config.client_background_migration_threads,
             resharding_config: config.resharding_config.clone(),
+            skip_state_transition_data_for_shards: config
+                .skip_state_transition_data_for_shards
+                .clone(),
         };
-        let chain = Chain::new(
+        let mut chain = Chain::new(
             clock.clone(),
             epoch_manager.clone(),
             shard_tracker.clone(),
@@ -282,6 +328,8 @@ impl Client {
             resharding_sender.clone(),
         )?;
         chain.init_flat_storage()?;
+        chain.resume_interrupted_resharding()?;
+        chain.check_shard_uid_mappings()?;
         let sharded_tx_pool =
             ShardedTransactionPool::new(rng_seed, config.transaction_pool_size_limit);
         let sync_status = SyncStatus::AwaitingPeers;
@@ -289,7 +337,7 @@ impl Client {
             clock.clone(),
             network_adapter.clone(),
             chain.genesis().clone(),
-            async_computation_spawner.clone(),
+            async_computation_spawner,
             config.epoch_sync.clone(),
             chain.chain_store.store(),
         );
@@ -348,7 +396,8 @@ impl Client {
             network_adapter.clone().into_sender(),
             runtime_adapter.clone(),
             config.orphan_state_witness_pool_size,
-            async_computation_spawner,
+            chunk_validation_spawner,
+            config.witness_validation_limits,
         );
         let chunk_distribution_network = ChunkDistributionNetwork::from_config(&config);
         Ok(Self {
@@ -403,6 +452,7 @@ impl Client {
             partial_witness_adapter,
             chunk_distribution_network,
             upgrade_schedule,
+            last_skipped_chunks_warning_epoch: None,
         })
     }
 
@@ -752,6 +802,26 @@ impl Client {
                 .get_mut(shard_index)
                 .ok_or(near_chain_primitives::Error::InvalidShardId(shard_id))? = chunk_endorsement;
         }
+        // The signed suppressions of the chunk producers stand for the endorsements of the
+        // suppressed chunks, see `near_primitives::chunk_suppression`.
+        for (shard_index, (chunk_header, endorsements)) in
+            chunk_headers.iter().zip(chunk_endorsements.iter_mut()).enumerate()
+        {
+            if chunk_header.is_new_chunk(height) {
+                continue;
+            }
+            let shard_id =
+                shard_layout.get_shard_id(shard_index).map_err(Into::<EpochError>::into)?;
+            if let Some(signature) = self.get_chunk_suppression_signature(
+                protocol_version,
+                &prev_hash,
+                shard_id,
+                chunk_header,
+                height,
+            ) {
+                *endorsements = vec![Some(Box::new(signature))];
+            }
+        }
 
         let prev_header = &prev_block.header();
 
@@ -931,8 +1001,37 @@ impl Client {
             last_header.height_included(),
         )?;
 
-        let outgoing_receipts_root = self.calculate_receipts_root(epoch_id, &outgoing_receipts)?;
         let protocol_version = self.epoch_manager.get_epoch_protocol_version(epoch_id)?;
+        if self.config.suppress_empty_chunks
+            && num_filtered_transactions == 0
+            && outgoing_receipts.is_empty()
+            && chunk_extra.gas_used() == 0
+            && is_chunk_suppressed(protocol_version, &last_chunk_header, next_height)
+            && !self.epoch_manager.is_next_block_epoch_start(&prev_block_hash)?
+            && !self.has_incoming_receipts(
+                epoch_id,
+                shard_id,
+                &prev_block_hash,
+                &last_chunk_header,
+            )?
+        {
+            debug!(target: "client", next_height, ?shard_id, "Not producing chunk. Suppressing chunk of quiet shard.");
+            metrics::CHUNK_SUPPRESSED_TOTAL.with_label_values(&[&shard_id.to_string()]).inc();
+            let block_producer = self.epoch_manager.get_block_producer(epoch_id, next_height)?;
+            let suppression = ChunkSuppression::new(
+                *epoch_id,
+                prev_block_hash,
+                next_height,
+                shard_id,
+                validator_signer,
+            );
+            self.network_adapter.send(PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::ChunkSuppression(block_producer, suppression),
+            ));
+            return Ok(None);
+        }
+
+        let outgoing_receipts_root = self.calculate_receipts_root(epoch_id, &outgoing_receipts)?;
         let gas_used = chunk_extra.gas_used();
         #[cfg(feature = "test_features")]
         let gas_used = if self.produce_invalid_chunks { gas_used + 1 } else { gas_used };
@@ -994,6 +1093,110 @@ impl Client {
         }))
     }
 
+    /// Verifies the signature of a chunk suppression sent by the chunk producer and keeps it for
+    /// the production of the block on top of its previous block.
+    pub fn process_chunk_suppression(
+        &mut self,
+        suppression: ChunkSuppression,
+    ) -> Result<(), near_chain::Error> {
+        let key = suppression.chunk_production_key();
+        let epoch_id =
+            self.epoch_manager.get_epoch_id_from_prev_block(suppression.prev_block_hash())?;
+        if epoch_id != key.epoch_id {
+            return Err(near_chain::Error::Other(format!(
+                "Chunk suppression for epoch {:?} on top of a block of epoch {:?}",
+                key.epoch_id, epoch_id
+            )));
+        }
+        let chunk_producer = self.epoch_manager.get_chunk_producer_info(&key)?;
+        if !suppression.verify(chunk_producer.public_key()) {
+            return Err(near_chain::Error::InvalidChunkEndorsement);
+        }
+        self.chunk_inclusion_tracker.add_chunk_suppression(suppression);
+        Ok(())
+    }
+
+    /// The signed suppression of the chunk of the shard to include in the block at `height` on
+    /// top of `prev_block`, if the chunk producer sent one and the last chunk of the shard allows
+    /// it.
+    fn get_chunk_suppression_signature(
+        &self,
+        protocol_version: ProtocolVersion,
+        prev_block_hash: &CryptoHash,
+        shard_id: ShardId,
+        last_chunk: &ShardChunkHeader,
+        height: BlockHeight,
+    ) -> Option<Signature> {
+        if !is_chunk_suppressed(protocol_version, last_chunk, height) {
+            return None;
+        }
+        self.chunk_inclusion_tracker
+            .get_chunk_suppression(prev_block_hash, shard_id)
+            .map(|suppression| suppression.signature().clone())
+    }
+
+    /// Whether the chunk producers of all the chunks not ready for inclusion in the block at
+    /// `height` on top of `prev_block_hash` signalled their suppression, in which case the block
+    /// producer doesn't wait for them. Chunks which are only late are waited for as usual.
+    pub(crate) fn missing_chunks_are_suppressed(
+        &self,
+        epoch_id: &EpochId,
+        prev_block_hash: &CryptoHash,
+        height: BlockHeight,
+    ) -> Result<bool, Error> {
+        let protocol_version = self.epoch_manager.get_epoch_protocol_version(epoch_id)?;
+        if !ProtocolFeature::EmptyChunkSuppression.enabled(protocol_version) {
+            return Ok(false);
+        }
+        let ready_chunks = self
+            .chunk_inclusion_tracker
+            .get_chunk_headers_ready_for_inclusion(epoch_id, prev_block_hash);
+        let prev_block = self.chain.get_block(prev_block_hash)?;
+        for shard_id in self.epoch_manager.shard_ids(epoch_id)? {
+            if ready_chunks.contains_key(&shard_id) {
+                continue;
+            }
+            let last_chunk =
+                Chain::get_prev_chunk_header(self.epoch_manager.as_ref(), &prev_block, shard_id)?;
+            if self
+                .get_chunk_suppression_signature(
+                    protocol_version,
+                    prev_block_hash,
+                    shard_id,
+                    &last_chunk,
+                    height,
+                )
+                .is_none()
+            {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Whether the shard got receipts in the blocks since its last chunk, which the next chunk
+    /// of the shard would apply.
+    fn has_incoming_receipts(
+        &self,
+        epoch_id: &EpochId,
+        shard_id: ShardId,
+        prev_block_hash: &CryptoHash,
+        last_chunk_header: &ShardChunkHeader,
+    ) -> Result<bool, Error> {
+        let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
+        let receipt_responses = self.chain.chain_store().get_incoming_receipts_for_shard(
+            self.epoch_manager.as_ref(),
+            shard_id,
+            &shard_layout,
+            *prev_block_hash,
+            last_chunk_header.height_included(),
+            ReceiptFilter::TargetShard,
+        )?;
+        Ok(receipt_responses.iter().any(|ReceiptProofResponse(_, receipt_proofs)| {
+            receipt_proofs.iter().any(|ReceiptProof(receipts, _)| !receipts.is_empty())
+        }))
+    }
+
     /// Calculates the root of receipt proofs.
     /// All receipts are groupped by receiver_id and hash is calculated
     /// for each such group. Then we merkalize these hashes to calculate
@@ -1041,6 +1244,27 @@ impl Client {
         txs
     }
 
+    /// The time limit of adding transactions to the chunk of the shard, adapted to the recent
+    /// apply durations of the shard if `produce_chunk_adaptive_time_limit` is set.
+    fn produce_chunk_add_transactions_time_limit(&self, shard_id: ShardId) -> Option<Duration> {
+        let fixed_time_limit = self.config.produce_chunk_add_transactions_time_limit.get();
+        let time_limit = match &self.config.produce_chunk_adaptive_time_limit {
+            Some(adaptive_config) => self
+                .chain
+                .apply_chunk_durations
+                .percentile(shard_id, adaptive_config.apply_duration_percentile)
+                .map(|apply_duration| adaptive_config.time_limit(apply_duration, fixed_time_limit))
+                .or(fixed_time_limit),
+            None => fixed_time_limit,
+        };
+        if let Some(time_limit) = time_limit {
+            metrics::PRODUCE_CHUNK_ADD_TRANSACTIONS_TIME_LIMIT
+                .with_label_values(&[&shard_id.to_string()])
+                .set(time_limit.as_seconds_f64());
+        }
+        time_limit
+    }
+
     /// Prepares an ordered list of valid transactions from the pool up the limits.
     fn prepare_transactions(
         &mut self,
@@ -1049,6 +1273,7 @@ impl Client {
         last_chunk: &ShardChunk,
         chunk_extra: &ChunkExtra,
     ) -> Result<PreparedTransactions, Error> {
+        let time_limit = self.produce_chunk_add_transactions_time_limit(shard_uid.shard_id());
         let Self { chain, sharded_tx_pool, runtime_adapter: runtime, .. } = self;
         let shard_id = shard_uid.shard_id();
         let prepared_transactions = if let Some(mut iter) =
@@ -1059,6 +1284,7 @@ impl Client {
                 use_flat_storage: true,
                 source: StorageDataSource::Db,
                 state_patch: Default::default(),
+                keep_storage_proof: true,
             };
             let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(&prev_block.hash())?;
             let protocol_version = self.epoch_manager.get_epoch_protocol_version(&epoch_id)?;
@@ -1082,7 +1308,7 @@ impl Client {
                 prev_block.into(),
                 &mut iter,
                 &mut chain.transaction_validity_check(prev_block.header().clone()),
-                self.config.produce_chunk_add_transactions_time_limit.get(),
+                time_limit,
             )?
         } else {
             PreparedTransactions { transactions: Vec::new(), limited_by: None, storage_proof: None }
@@ -1846,6 +2072,18 @@ impl Client {
             if &chunk_proposer != &validator_id {
                 continue;
             }
+            if self.config.chunk_validator_only {
+                // The node doesn't track the shard, so it doesn't have the state to produce the
+                // chunk.
+                metrics::CHUNK_SKIPPED_BY_CHUNK_VALIDATOR_ONLY_TOTAL
+                    .with_label_values(&[&shard_id.to_string()])
+                    .inc();
+                if self.last_skipped_chunks_warning_epoch != Some(epoch_id) {
+                    self.last_skipped_chunks_warning_epoch = Some(epoch_id);
+                    warn!(target: "client", ?epoch_id, ?shard_id, next_height, "Not producing the chunks assigned to this node in the epoch, the node runs as a chunk validator only and should not stake as a chunk producer");
+                }
+                continue;
+            }
 
             let _span = debug_span!(
                 target: "client",
@@ -2373,6 +2611,17 @@ impl Client {
 
         let shard_id =
             self.epoch_manager.account_id_to_shard_id(tx.transaction.signer_id(), &epoch_id)?;
+        if !is_forwarded && !check_only {
+            if let Some(response) = self.check_signer_shard_congestion(
+                &cur_block,
+                shard_id,
+                &epoch_id,
+                protocol_version,
+            )? {
+                debug!(target: "client", tx_hash = ?tx.get_hash(), ?shard_id, ?response, "Signer shard is congested, rejecting tx");
+                return Ok(response);
+            }
+        }
         let care_about_shard =
             self.shard_tracker.care_about_shard(me, &head.last_block_hash, shard_id, true);
         let will_care_about_shard =
@@ -2467,6 +2716,47 @@ impl Client {
         }
     }
 
+    /// Rejects the transactions submitted to this node whose signer shard is at or above the
+    /// `tx_admission_congestion_threshold` congestion level, as they are unlikely to be included
+    /// before they time out. Returns `None` if the transaction is admitted.
+    fn check_signer_shard_congestion(
+        &self,
+        block: &Block,
+        signer_shard_id: ShardId,
+        epoch_id: &EpochId,
+        protocol_version: ProtocolVersion,
+    ) -> Result<Option<ProcessTxResponse>, Error> {
+        let Some(threshold) = self.config.tx_admission_congestion_threshold else {
+            return Ok(None);
+        };
+        let Some(congestion_info) = block.block_congestion_info().get(&signer_shard_id).copied()
+        else {
+            return Ok(None);
+        };
+        let runtime_config = self.runtime_adapter.get_runtime_config(protocol_version)?;
+        let congestion_control = CongestionControl::new(
+            runtime_config.congestion_control_config,
+            congestion_info.congestion_info,
+            congestion_info.missed_chunks_count,
+        );
+        let ShardAcceptsTransactions::No(reason) =
+            congestion_control.shard_accepts_transactions_below(threshold)
+        else {
+            return Ok(None);
+        };
+        // A stuck shard recovers once it produces chunks again, which can't be anticipated.
+        let retry_after = match reason {
+            RejectTransactionReason::MissedChunks { .. } => None,
+            _ => Some(self.config.min_block_production_delay * TX_ADMISSION_RETRY_AFTER_BLOCKS),
+        };
+        let shard_uid = self.epoch_manager.shard_id_to_uid(signer_shard_id, epoch_id)?;
+        metrics::TRANSACTION_REJECTED_SIGNER_SHARD_CONGESTED.inc();
+        Ok(Some(ProcessTxResponse::SignerShardCongested {
+            error: reason.into_invalid_tx_error(shard_uid.shard_id),
+            retry_after,
+        }))
+    }
+
     /// Determine if I am a validator in next few blocks for specified shard, assuming epoch doesn't change.
     fn active_validator(
         &self,
@@ -2780,8 +3070,16 @@ impl Client {
         };
         let tier1_accounts = self.get_tier1_accounts(&tip)?;
         let block = self.chain.get_block(&tip.last_block_hash)?;
+        // Archival nodes serve all the blocks from the cold store with split storage, even though
+        // the tail of their hot store moves with the garbage collection.
+        let earliest_block_height = if self.config.archive {
+            self.chain.genesis().height()
+        } else {
+            self.chain.chain_store().tail()?
+        };
         self.network_adapter.send(SetChainInfo(ChainInfo {
             block,
+            earliest_block_height,
             tracked_shards,
             tier1_accounts,
         }));
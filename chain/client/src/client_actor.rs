@@ -10,6 +10,7 @@ use crate::client::AdvProduceBlocksMode;
 use crate::client::{CatchupState, Client, EPOCH_START_INFO_BLOCKS};
 use crate::config_updater::ConfigUpdater;
 use crate::debug::new_network_info_view;
+use crate::debug::ReshardingEventsHistory;
 use crate::info::{display_sync_status, InfoHelper};
 use crate::stateless_validation::partial_witness::partial_witness_actor::PartialWitnessSenderForClient;
 use crate::sync::state::chain_requests::{
@@ -29,7 +30,7 @@ use near_async::{MultiSend, MultiSenderFrom};
 use near_chain::chain::{
     ApplyChunksDoneMessage, BlockCatchUpRequest, BlockCatchUpResponse, ChunkStateWitnessMessage,
 };
-use near_chain::rayon_spawner::RayonAsyncComputationSpawner;
+use near_chain::rayon_spawner::RayonThreadPoolAsyncComputationSpawner;
 use near_chain::resharding::types::ReshardingSender;
 use near_chain::state_snapshot_actor::SnapshotCallbacks;
 use near_chain::test_utils::format_hash;
@@ -40,20 +41,23 @@ use near_chain::{
     byzantine_assert, near_chain_primitives, Block, BlockHeader, BlockProcessingArtifact,
     ChainGenesis, Provenance,
 };
-use near_chain_configs::{ClientConfig, MutableValidatorSigner, ReshardingHandle};
+use near_chain_configs::{
+    ClientConfig, MutableValidatorSigner, ReshardingHandle, ReshardingHandleState,
+};
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::get_shards_cares_about_this_or_next_epoch;
 use near_client_primitives::types::{
     Error, GetClientConfig, GetClientConfigError, GetNetworkInfo, NetworkInfoResponse,
-    StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
+    ReshardingControl, StateSyncStatus, Status, StatusError, StatusSyncInfo, SyncStatus,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::{EpochManagerAdapter, RngSeed};
 use near_network::client::{
-    BlockApproval, BlockHeadersResponse, BlockResponse, ChunkEndorsementMessage, ProcessTxRequest,
-    ProcessTxResponse, RecvChallenge, SetNetworkInfo, StateResponseReceived,
+    BlockApproval, BlockHeadersResponse, BlockResponse, ChunkEndorsementMessage,
+    ChunkSuppressionMessage, ProcessTxRequest, ProcessTxResponse, RecvChallenge, SetNetworkInfo,
+    StateResponseReceived,
 };
 use near_network::types::ReasonForBan;
 use near_network::types::{
@@ -147,6 +151,16 @@ pub fn start_client(
 
     wait_until_genesis(&chain_genesis.time);
 
+    let apply_chunks_spawner = RayonThreadPoolAsyncComputationSpawner::from_config(
+        "apply_chunks",
+        &client_config.thread_pools.chunk_application,
+    )
+    .expect("failed to create the chunk application thread pool");
+    let chunk_validation_spawner = RayonThreadPoolAsyncComputationSpawner::from_config(
+        "chunk_validation",
+        &client_config.thread_pools.witness_validation,
+    )
+    .expect("failed to create the witness validation thread pool");
     let chain_sender_for_state_sync = LateBoundSender::<ChainSenderForStateSync>::new();
     let client = Client::new(
         clock.clone(),
@@ -161,7 +175,8 @@ pub fn start_client(
         enable_doomslug,
         seed.unwrap_or_else(random_seed_from_thread),
         snapshot_callbacks,
-        Arc::new(RayonAsyncComputationSpawner),
+        apply_chunks_spawner,
+        chunk_validation_spawner,
         partial_witness_adapter,
         resharding_sender,
         state_sync_future_spawner,
@@ -267,6 +282,9 @@ pub struct ClientActorInner {
 
     /// Manages updating the config.
     config_updater: Option<ConfigUpdater>,
+
+    /// The latest resharding events, shown on the debug page.
+    pub(crate) resharding_events: ReshardingEventsHistory,
 }
 
 impl messaging::Actor for ClientActorInner {
@@ -355,6 +373,8 @@ impl ClientActorInner {
             check_validator_tracked_shards(&client, vs.validator_id())?;
         }
         let info_helper = InfoHelper::new(clock.clone(), telemetry_sender, &client.config);
+        let resharding_events =
+            ReshardingEventsHistory::new(client.chain.resharding_manager.subscribe_events());
 
         let now = clock.now_utc();
         Ok(ClientActorInner {
@@ -388,6 +408,7 @@ impl ClientActorInner {
             shutdown_signal,
             config_updater,
             sync_jobs_sender,
+            resharding_events,
         })
     }
 }
@@ -1133,7 +1154,13 @@ impl ClientActorInner {
                     .chunk_inclusion_tracker
                     .num_chunk_headers_ready_for_inclusion(&epoch_id, &head.last_block_hash);
                 let shard_ids = self.client.epoch_manager.shard_ids(&epoch_id).unwrap();
-                let have_all_chunks = head.height == 0 || num_chunks == shard_ids.len();
+                let have_all_chunks = head.height == 0
+                    || num_chunks == shard_ids.len()
+                    || self.client.missing_chunks_are_suppressed(
+                        &epoch_id,
+                        &head.last_block_hash,
+                        height,
+                    )?;
 
                 if self.client.doomslug.ready_to_produce_block(
                     height,
@@ -1977,6 +2004,7 @@ impl ClientActorInner {
     /// Print current summary.
     fn log_summary(&mut self) {
         let _span = tracing::debug_span!(target: "client", "log_summary").entered();
+        self.resharding_events.update(&self.clock);
         let signer = self.client.validator_signer.get();
         self.info_helper.log_summary(
             &self.client,
@@ -2200,6 +2228,22 @@ impl Handler<GetClientConfig> for ClientActorInner {
     }
 }
 
+impl Handler<ReshardingControl> for ClientActorInner {
+    fn handle(&mut self, msg: ReshardingControl) -> Result<ReshardingHandleState, StatusError> {
+        tracing::info!(target: "resharding", ?msg, "resharding control request");
+        let handle = &self.client.chain.resharding_manager.resharding_handle;
+        let state = match msg {
+            ReshardingControl::Pause => handle.pause(),
+            ReshardingControl::Resume => handle.resume(),
+            ReshardingControl::Abort => {
+                handle.stop();
+                handle.state()
+            }
+        };
+        Ok(state)
+    }
+}
+
 impl Handler<ChunkStateWitnessMessage> for ClientActorInner {
     #[perf]
     fn handle(&mut self, msg: ChunkStateWitnessMessage) {
@@ -2222,6 +2266,15 @@ impl Handler<ChunkEndorsementMessage> for ClientActorInner {
     }
 }
 
+impl Handler<ChunkSuppressionMessage> for ClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: ChunkSuppressionMessage) {
+        if let Err(err) = self.client.process_chunk_suppression(msg.0) {
+            tracing::error!(target: "client", ?err, "Error processing chunk suppression");
+        }
+    }
+}
+
 impl Handler<StateHeaderValidationRequest> for ClientActorInner {
     #[perf]
     fn handle(&mut self, msg: StateHeaderValidationRequest) -> Result<(), near_chain::Error> {
@@ -5,10 +5,19 @@ use crate::client_actor::ClientActorInner;
 use near_async::messaging::Handler;
 use near_async::time::{Clock, Instant};
 use near_chain::crypto_hash_timer::CryptoHashTimer;
+use near_chain::resharding::buffered_receipts::ShardBufferedReceipts;
+use near_chain::resharding::events::{ReshardingEvent, RESHARDING_EVENTS_CAPACITY};
+use near_chain::resharding::status::get_resharding_statuses;
+use near_chain::resharding::validation::get_split_shard_validation;
 use near_chain::{near_chain_primitives, Block, Chain, ChainStoreAccess};
 use near_client_primitives::debug::{
     ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    DebugStatusResponse, DelayedReceiptsReceiverView, DelayedReceiptsView, MissedHeightInfo,
+    ProductionAtHeight, ReceiverBackpressureView, ReceiverQueueUsageView,
+    ReshardingBufferedReceiptsView, ReshardingEventView, ReshardingMismatchView,
+    ReshardingStatusView, ReshardingValidationView, ShardBufferedReceiptsView,
+    ShardDelayedReceiptsView, ShardReceiverBackpressureView, TargetShardBufferedReceiptsView,
+    ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -20,6 +29,8 @@ use near_o11y::log_assert;
 use near_performance_metrics_macros::perf;
 use near_primitives::congestion_info::CongestionControl;
 use near_primitives::errors::EpochError;
+use near_primitives::serialize::to_base64;
+use near_primitives::shard_layout::ShardUId;
 use near_primitives::state_sync::get_num_state_parts;
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 use near_primitives::stateless_validation::ChunkProductionKey;
@@ -32,11 +43,14 @@ use near_primitives::{
     types::EpochId,
     views::ValidatorInfo,
 };
+use near_store::trie::receipts_column_helper::{DelayedReceiptQueue, TrieQueue};
 use near_store::DBCol;
+use node_runtime::receiver_queue_stats::{ReceiverQueueStats, ReceiverQueueUsage};
 use std::cmp::{max, min};
-use std::collections::{HashMap, HashSet};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroUsize;
 use time::ext::InstantExt as _;
+use tokio::sync::broadcast;
 
 use near_client_primitives::debug::{DebugBlockStatus, DebugChunkStatus};
 use near_network::types::{ConnectedPeerInfo, NetworkInfo, PeerType};
@@ -48,6 +62,12 @@ use near_primitives::views::{
 // Constants for debug requests.
 const DEBUG_BLOCKS_TO_FETCH: u32 = 50;
 const DEBUG_EPOCHS_TO_FETCH: u32 = 5;
+// Maximum number of delayed receipts to inspect per shard when looking for the top receivers.
+const DEBUG_DELAYED_RECEIPTS_TO_INSPECT: usize = 100_000;
+// Number of receivers with the most delayed receipts to show per shard.
+const DEBUG_DELAYED_RECEIPTS_TOP_RECEIVERS: usize = 10;
+// Number of receivers with the most gas and bytes in the receipt queues to show per shard.
+const DEBUG_RECEIVER_BACKPRESSURE_TOP_RECEIVERS: usize = 10;
 
 // How many old blocks (before HEAD) should be shown in debug page.
 const DEBUG_PRODUCTION_OLD_BLOCKS_TO_SHOW: u64 = 50;
@@ -163,6 +183,82 @@ impl BlockProductionTracker {
     }
 }
 
+/// The latest resharding events, received from the subscription to the resharding manager.
+pub(crate) struct ReshardingEventsHistory {
+    receiver: broadcast::Receiver<ReshardingEvent>,
+    events: VecDeque<ReshardingEventView>,
+}
+
+impl ReshardingEventsHistory {
+    pub(crate) fn new(receiver: broadcast::Receiver<ReshardingEvent>) -> Self {
+        Self { receiver, events: VecDeque::new() }
+    }
+
+    /// Moves the events received since the last call to the history, which keeps the latest
+    /// [RESHARDING_EVENTS_CAPACITY] ones. Needs to be called more often than that many events
+    /// are emitted, or the oldest ones are missed.
+    pub(crate) fn update(&mut self, clock: &Clock) {
+        loop {
+            let event = match self.receiver.try_recv() {
+                Ok(event) => event,
+                Err(broadcast::error::TryRecvError::Lagged(num_missed)) => {
+                    tracing::warn!(target: "client", num_missed, "missed resharding events");
+                    continue;
+                }
+                Err(broadcast::error::TryRecvError::Empty)
+                | Err(broadcast::error::TryRecvError::Closed) => break,
+            };
+            if self.events.len() == RESHARDING_EVENTS_CAPACITY {
+                self.events.pop_front();
+            }
+            self.events.push_back(resharding_event_view(clock, event));
+        }
+    }
+}
+
+fn resharding_event_view(clock: &Clock, event: ReshardingEvent) -> ReshardingEventView {
+    let kind: &'static str = (&event).into();
+    let to_shard_ids = |shard_uids: Vec<ShardUId>| {
+        shard_uids.into_iter().map(|shard_uid| shard_uid.shard_id()).collect::<Vec<_>>()
+    };
+    let (block_hash, block_height, parent_shards, children_shards, state_root) = match event {
+        ReshardingEvent::Started { resharding_block, parent_shards, children_shards } => (
+            resharding_block.hash,
+            Some(resharding_block.height),
+            to_shard_ids(parent_shards),
+            to_shard_ids(children_shards),
+            None,
+        ),
+        ReshardingEvent::ChildMemtrieCreated { block_hash, child_shard, state_root } => {
+            (block_hash, None, vec![], to_shard_ids(vec![child_shard]), Some(state_root))
+        }
+        ReshardingEvent::FlatStorageCopied { resharding_block, children_shards }
+        | ReshardingEvent::Finished { resharding_block, children_shards } => (
+            resharding_block.hash,
+            Some(resharding_block.height),
+            vec![],
+            to_shard_ids(children_shards),
+            None,
+        ),
+        ReshardingEvent::Failed { resharding_block, parent_shards } => (
+            resharding_block.hash,
+            Some(resharding_block.height),
+            to_shard_ids(parent_shards),
+            vec![],
+            None,
+        ),
+    };
+    ReshardingEventView {
+        received_at: clock.now_utc(),
+        kind: kind.to_string(),
+        block_hash,
+        block_height,
+        parent_shards,
+        children_shards,
+        state_root,
+    }
+}
+
 impl Handler<DebugStatus> for ClientActorInner {
     #[perf]
     fn handle(&mut self, msg: DebugStatus) -> Result<DebugStatusResponse, StatusError> {
@@ -191,6 +287,26 @@ impl Handler<DebugStatus> for ClientActorInner {
             DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
                 self.client.chain.get_chain_processing_info(),
             )),
+            DebugStatus::DelayedReceipts => {
+                Ok(DebugStatusResponse::DelayedReceipts(self.get_delayed_receipts_view()?))
+            }
+            DebugStatus::ReceiverBackpressure => Ok(DebugStatusResponse::ReceiverBackpressure(
+                self.get_receiver_backpressure_view()?,
+            )),
+            DebugStatus::ReshardingStatus => {
+                Ok(DebugStatusResponse::ReshardingStatus(self.get_resharding_status_view()?))
+            }
+            DebugStatus::ReshardingBufferedReceipts => {
+                Ok(DebugStatusResponse::ReshardingBufferedReceipts(
+                    self.get_resharding_buffered_receipts_view()?,
+                ))
+            }
+            DebugStatus::ReshardingEvents => {
+                self.resharding_events.update(&self.client.clock);
+                Ok(DebugStatusResponse::ReshardingEvents(
+                    self.resharding_events.events.iter().cloned().collect(),
+                ))
+            }
         }
     }
 }
@@ -386,6 +502,218 @@ impl ClientActorInner {
         Ok(TrackedShardsView { shards_tracked_this_epoch, shards_tracked_next_epoch })
     }
 
+    fn get_delayed_receipts_view(
+        &self,
+    ) -> Result<DelayedReceiptsView, near_chain_primitives::Error> {
+        let head = self.client.chain.head()?;
+        let me = self.client.validator_signer.get().map(|x| x.validator_id().clone());
+        let mut shards = vec![];
+        for shard_id in self.client.epoch_manager.shard_ids(&head.epoch_id)? {
+            if !self.client.shard_tracker.care_about_shard(
+                me.as_ref(),
+                &head.prev_block_hash,
+                shard_id,
+                true,
+            ) {
+                continue;
+            }
+            let shard_uid = self.client.epoch_manager.shard_id_to_uid(shard_id, &head.epoch_id)?;
+            let chunk_extra =
+                self.client.chain.get_chunk_extra(&head.last_block_hash, &shard_uid)?;
+            let trie = self.client.runtime_adapter.get_view_trie_for_shard(
+                shard_id,
+                &head.last_block_hash,
+                *chunk_extra.state_root(),
+            )?;
+            let queue = DelayedReceiptQueue::load(&trie)?;
+
+            let mut num_receipts_by_receiver: HashMap<AccountId, u64> = HashMap::new();
+            let mut num_inspected_receipts = 0;
+            for receipt in queue.iter(&trie, false).take(DEBUG_DELAYED_RECEIPTS_TO_INSPECT) {
+                let receiver_id = receipt?.get_receipt().receiver_id().clone();
+                *num_receipts_by_receiver.entry(receiver_id).or_default() += 1;
+                num_inspected_receipts += 1;
+            }
+            let mut top_receivers = num_receipts_by_receiver
+                .into_iter()
+                .map(|(receiver_id, num_receipts)| DelayedReceiptsReceiverView {
+                    receiver_id,
+                    num_receipts,
+                })
+                .collect::<Vec<_>>();
+            top_receivers.sort_by(|a, b| {
+                b.num_receipts.cmp(&a.num_receipts).then_with(|| a.receiver_id.cmp(&b.receiver_id))
+            });
+            top_receivers.truncate(DEBUG_DELAYED_RECEIPTS_TOP_RECEIVERS);
+
+            shards.push(ShardDelayedReceiptsView {
+                shard_id,
+                queue_len: queue.len(),
+                num_inspected_receipts,
+                top_receivers,
+            });
+        }
+        Ok(DelayedReceiptsView {
+            block_hash: head.last_block_hash,
+            block_height: head.height,
+            shards,
+        })
+    }
+
+    fn get_resharding_status_view(
+        &self,
+    ) -> Result<Vec<ReshardingStatusView>, near_chain_primitives::Error> {
+        let store = self.client.chain.chain_store().store();
+        let to_shard_ids = |shard_uids: &[ShardUId]| {
+            shard_uids.iter().map(|shard_uid| shard_uid.shard_id()).collect::<Vec<_>>()
+        };
+        let control_state = self.client.chain.resharding_manager.resharding_handle.state();
+        let mut views = vec![];
+        for status in get_resharding_statuses(store)? {
+            let phase: &'static str = status.phase.into();
+            // Only the validation of the split of the latest resharding is relevant.
+            let validation = match status.parent_shards.as_slice() {
+                [parent_shard] => get_split_shard_validation(store, *parent_shard)?,
+                _ => None,
+            };
+            let validation = validation
+                .filter(|validation| {
+                    validation.resharding_block.hash == status.resharding_block.hash
+                })
+                .map(|validation| ReshardingValidationView {
+                    children_shards: to_shard_ids(&validation.children_shards),
+                    trie_keys_checked: validation.trie_keys_checked,
+                    flat_storage_keys_checked: validation.flat_storage_keys_checked,
+                    num_mismatches: validation.num_mismatches,
+                    mismatches: validation
+                        .mismatches
+                        .iter()
+                        .map(|mismatch| {
+                            let kind: &'static str = mismatch.kind.into();
+                            ReshardingMismatchView {
+                                shard_id: mismatch.shard_uid.shard_id(),
+                                key: to_base64(&mismatch.key),
+                                kind: kind.to_string(),
+                            }
+                        })
+                        .collect(),
+                });
+            views.push(ReshardingStatusView {
+                resharding_block_hash: status.resharding_block.hash,
+                resharding_block_height: status.resharding_block.height,
+                parent_shards: to_shard_ids(&status.parent_shards),
+                children_shards: to_shard_ids(&status.children_shards),
+                phase: phase.to_string(),
+                shards_done: to_shard_ids(&status.shards_done),
+                keys_copied: status.keys_copied,
+                bytes_copied: status.bytes_copied,
+                bytes_to_copy: status.bytes_to_copy,
+                eta_secs: status.eta().map(|eta| eta.as_secs()),
+                control_state,
+                validation,
+            });
+        }
+        Ok(views)
+    }
+
+    fn get_receiver_backpressure_view(
+        &self,
+    ) -> Result<ReceiverBackpressureView, near_chain_primitives::Error> {
+        let head = self.client.chain.head()?;
+        let me = self.client.validator_signer.get().map(|x| x.validator_id().clone());
+        let top_receivers = |usage: &HashMap<AccountId, ReceiverQueueUsage>,
+                             key: fn(&ReceiverQueueUsage) -> u128| {
+            ReceiverQueueStats::top_receivers(usage, DEBUG_RECEIVER_BACKPRESSURE_TOP_RECEIVERS, key)
+                .into_iter()
+                .map(|(receiver_id, usage)| ReceiverQueueUsageView {
+                    receiver_id,
+                    num_receipts: usage.num_receipts,
+                    gas: usage.gas,
+                    bytes: usage.bytes,
+                })
+                .collect::<Vec<_>>()
+        };
+        let by_gas = |usage: &ReceiverQueueUsage| usage.gas;
+        let by_bytes = |usage: &ReceiverQueueUsage| u128::from(usage.bytes);
+
+        let mut shards = vec![];
+        for shard_id in self.client.epoch_manager.shard_ids(&head.epoch_id)? {
+            if !self.client.shard_tracker.care_about_shard(
+                me.as_ref(),
+                &head.prev_block_hash,
+                shard_id,
+                true,
+            ) {
+                continue;
+            }
+            let shard_uid = self.client.epoch_manager.shard_id_to_uid(shard_id, &head.epoch_id)?;
+            let chunk_extra =
+                self.client.chain.get_chunk_extra(&head.last_block_hash, &shard_uid)?;
+            let stats = self.client.runtime_adapter.get_receiver_queue_stats(
+                shard_id,
+                &head.last_block_hash,
+                *chunk_extra.state_root(),
+            )?;
+            shards.push(ShardReceiverBackpressureView {
+                shard_id,
+                num_delayed_receivers: stats.delayed.len(),
+                delayed_by_gas: top_receivers(&stats.delayed, by_gas),
+                delayed_by_bytes: top_receivers(&stats.delayed, by_bytes),
+                num_buffered_receivers: stats.buffered.len(),
+                buffered_by_gas: top_receivers(&stats.buffered, by_gas),
+                buffered_by_bytes: top_receivers(&stats.buffered, by_bytes),
+            });
+        }
+        Ok(ReceiverBackpressureView {
+            block_hash: head.last_block_hash,
+            block_height: head.height,
+            shards,
+        })
+    }
+
+    fn get_resharding_buffered_receipts_view(
+        &self,
+    ) -> Result<Vec<ReshardingBufferedReceiptsView>, near_chain_primitives::Error> {
+        let store = self.client.chain.chain_store().store();
+        let resharding_manager = &self.client.chain.resharding_manager;
+        let to_view = |shard: ShardBufferedReceipts| ShardBufferedReceiptsView {
+            shard_id: shard.shard_uid.shard_id(),
+            state_root: shard.state_root,
+            congestion_info_buffered_gas: shard.congestion_info_buffered_gas,
+            congestion_info_receipt_bytes: shard.congestion_info_receipt_bytes,
+            target_shards: shard
+                .target_shards
+                .into_iter()
+                .map(|target| TargetShardBufferedReceiptsView {
+                    target_shard: target.target_shard,
+                    buffer_len: target.buffer_len,
+                    num_receipts: target.num_receipts,
+                    gas: target.gas,
+                    bytes: target.bytes,
+                })
+                .collect(),
+        };
+        let mut views = vec![];
+        for status in get_resharding_statuses(store)? {
+            // Merges don't split the buffered receipts.
+            let [parent_shard] = status.parent_shards.as_slice() else {
+                continue;
+            };
+            let buffered_receipts = resharding_manager.get_split_buffered_receipts(
+                &status.resharding_block.hash,
+                *parent_shard,
+                &status.children_shards,
+            )?;
+            views.push(ReshardingBufferedReceiptsView {
+                resharding_block_hash: status.resharding_block.hash,
+                resharding_block_height: status.resharding_block.height,
+                parent: buffered_receipts.parent.map(to_view),
+                children: buffered_receipts.children.into_iter().map(to_view).collect(),
+            });
+        }
+        Ok(views)
+    }
+
     fn get_recent_epoch_info(
         &mut self,
     ) -> Result<Vec<EpochInfoView>, near_chain_primitives::Error> {
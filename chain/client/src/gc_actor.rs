@@ -1,15 +1,17 @@
 use crate::metrics;
+use crate::sync::epoch::EpochSync;
 use near_async::futures::{DelayedActionRunner, DelayedActionRunnerExt};
 use near_async::messaging::Actor;
 #[cfg(feature = "test_features")]
 use near_async::messaging::Handler;
 use near_chain::{types::RuntimeAdapter, ChainStore, ChainStoreAccess};
-use near_chain_configs::GCConfig;
+use near_chain_configs::{GCConfig, MutableValidatorSigner};
+use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
-use near_primitives::types::BlockHeight;
+use near_primitives::types::{BlockHeight, BlockHeightDelta, EpochId};
 use near_store::{metadata::DbKind, Store};
 use std::sync::Arc;
-use tracing::warn;
+use tracing::{debug, warn};
 
 /// An actor for garbage collection that runs in its own thread
 /// The actor runs periodically, as determined by `gc_step_period`,
@@ -18,8 +20,19 @@ pub struct GCActor {
     store: ChainStore,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
+    /// Needed to tell whether the children of a split still use the State of their parent.
+    shard_tracker: ShardTracker,
+    validator_signer: MutableValidatorSigner,
     gc_config: GCConfig,
     is_archive: bool,
+    /// Needed to derive the epoch sync proof checkpoint before pruning epoch data.
+    transaction_validity_period: BlockHeightDelta,
+    /// Epoch of the head when the epoch data was last pruned. Epoch data only needs to
+    /// be pruned once per epoch.
+    last_epoch_data_gc_epoch: Option<EpochId>,
+    /// Epoch of the head when the data of the shards removed by resharding was last
+    /// cleared. The shard layout can only change at epoch boundaries.
+    last_resharding_data_gc_epoch: Option<EpochId>,
     /// In some tests we may want to temporarily disable GC
     no_gc: bool,
 }
@@ -28,8 +41,11 @@ impl GCActor {
     pub fn new(
         store: Store,
         genesis_height: BlockHeight,
+        transaction_validity_period: BlockHeightDelta,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         epoch_manager: Arc<dyn EpochManagerAdapter>,
+        shard_tracker: ShardTracker,
+        validator_signer: MutableValidatorSigner,
         gc_config: GCConfig,
         is_archive: bool,
     ) -> Self {
@@ -38,7 +54,12 @@ impl GCActor {
             runtime_adapter,
             gc_config,
             epoch_manager,
+            shard_tracker,
+            validator_signer,
             is_archive,
+            transaction_validity_period,
+            last_epoch_data_gc_epoch: None,
+            last_resharding_data_gc_epoch: None,
             no_gc: false,
         }
     }
@@ -46,11 +67,13 @@ impl GCActor {
     fn clear_data(&mut self) -> Result<(), near_chain::Error> {
         // A RPC node should do regular garbage collection.
         if !self.is_archive {
-            return self.store.clear_data(
+            self.store.clear_data(
                 &self.gc_config,
                 self.runtime_adapter.clone(),
                 self.epoch_manager.clone(),
-            );
+            )?;
+            self.clear_resharding_data()?;
+            return self.clear_epoch_data();
         }
 
         // An archival node with split storage should perform garbage collection
@@ -60,11 +83,12 @@ impl GCActor {
         let store = self.store.store();
         let kind = store.get_db_kind()?;
         if kind == Some(DbKind::Hot) {
-            return self.store.clear_data(
+            self.store.clear_data(
                 &self.gc_config,
                 self.runtime_adapter.clone(),
                 self.epoch_manager.clone(),
-            );
+            )?;
+            return self.clear_resharding_data();
         }
 
         // An archival node with legacy storage or in the midst of migration to split
@@ -72,6 +96,54 @@ impl GCActor {
         self.store.clear_archive_data(self.gc_config.gc_blocks_limit, self.runtime_adapter.clone())
     }
 
+    /// Clears the data of the parent shards of past reshardings, once per epoch.
+    fn clear_resharding_data(&mut self) -> Result<(), near_chain::Error> {
+        let head = self.store.head()?;
+        if self.last_resharding_data_gc_epoch == Some(head.epoch_id) {
+            return Ok(());
+        }
+        let validator_signer = self.validator_signer.get();
+        if self.store.clear_resharding_data(
+            &self.runtime_adapter.get_tries(),
+            self.epoch_manager.as_ref(),
+            &self.shard_tracker,
+            validator_signer.as_ref().map(|signer| signer.validator_id()),
+        )? {
+            self.last_resharding_data_gc_epoch = Some(head.epoch_id);
+        }
+        Ok(())
+    }
+
+    /// Prunes the data of old epochs, if configured. Before doing so, persists an epoch sync
+    /// proof checkpoint so that the node can still derive epoch sync proofs without it.
+    fn clear_epoch_data(&mut self) -> Result<(), near_chain::Error> {
+        if self.gc_config.epoch_data_num_epochs_to_keep.is_none() {
+            return Ok(());
+        }
+        let head = self.store.head()?;
+        if self.last_epoch_data_gc_epoch == Some(head.epoch_id) {
+            return Ok(());
+        }
+        self.last_epoch_data_gc_epoch = Some(head.epoch_id);
+
+        let min_epoch_height_to_keep = match EpochSync::checkpoint_epoch_sync_proof(
+            self.store.store().clone(),
+            self.transaction_validity_period,
+        ) {
+            Ok(epoch_height) => epoch_height,
+            Err(err) => {
+                // E.g. there are not enough epochs since genesis for an epoch sync proof yet.
+                debug!(target: "garbage collection", ?err, "Not pruning epoch data, failed to checkpoint epoch sync proof");
+                return Ok(());
+            }
+        };
+        self.store.clear_epoch_data(
+            &self.gc_config,
+            self.epoch_manager.as_ref(),
+            min_epoch_height_to_keep,
+        )
+    }
+
     fn gc(&mut self, ctx: &mut dyn DelayedActionRunner<Self>) {
         if !self.no_gc {
             let timer = metrics::GC_TIME.start_timer();
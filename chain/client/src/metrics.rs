@@ -1,8 +1,9 @@
 use near_o11y::metrics::{
     exponential_buckets, linear_buckets, try_create_counter, try_create_counter_vec,
-    try_create_gauge, try_create_histogram, try_create_histogram_vec, try_create_int_counter,
-    try_create_int_counter_vec, try_create_int_gauge, try_create_int_gauge_vec, Counter,
-    CounterVec, Gauge, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    try_create_gauge, try_create_gauge_vec, try_create_histogram, try_create_histogram_vec,
+    try_create_int_counter, try_create_int_counter_vec, try_create_int_gauge,
+    try_create_int_gauge_vec, Counter, CounterVec, Gauge, GaugeVec, Histogram, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
 };
 use std::sync::LazyLock;
 
@@ -22,6 +23,25 @@ pub(crate) static CHUNK_PRODUCED_TOTAL: LazyLock<IntCounter> = LazyLock::new(||
     .unwrap()
 });
 
+pub(crate) static CHUNK_SUPPRESSED_TOTAL: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_chunk_suppressed_total",
+        "Number of chunks of quiet shards which this node skipped producing",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static CHUNK_SKIPPED_BY_CHUNK_VALIDATOR_ONLY_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_chunk_skipped_by_chunk_validator_only_total",
+            "Number of chunks which this node was assigned to produce but skipped because it runs as a chunk validator only",
+            &["shard_id"],
+        )
+        .unwrap()
+    });
+
 pub(crate) static PRODUCED_CHUNKS_SOME_POOL_TRANSACTIONS_DIDNT_FIT: LazyLock<IntCounterVec> =
     LazyLock::new(|| {
         try_create_int_counter_vec(
@@ -374,6 +394,27 @@ pub(crate) static TRANSACTION_RECEIVED_NON_VALIDATOR_FORWARDED: LazyLock<IntGaug
         .unwrap()
     });
 
+pub(crate) static TRANSACTION_REJECTED_SIGNER_SHARD_CONGESTED: LazyLock<IntCounter> = LazyLock::new(
+    || {
+        try_create_int_counter(
+            "near_transaction_rejected_signer_shard_congested_total",
+            "Transactions submitted to the node rejected because the shard of their signer is congested",
+        )
+        .unwrap()
+    },
+);
+
+pub(crate) static PRODUCE_CHUNK_ADD_TRANSACTIONS_TIME_LIMIT: LazyLock<GaugeVec> = LazyLock::new(
+    || {
+        try_create_gauge_vec(
+            "near_produce_chunk_add_transactions_time_limit_seconds",
+            "Time limit of adding transactions to the last chunk produced for the shard, adapted to the recent apply durations if enabled",
+            &["shard_id"],
+        )
+        .unwrap()
+    },
+);
+
 pub(crate) static NODE_PROTOCOL_VERSION: LazyLock<IntGauge> = LazyLock::new(|| {
     try_create_int_gauge("near_node_protocol_version", "Max protocol version supported by the node")
         .unwrap()
@@ -481,6 +522,15 @@ pub(crate) static STATE_SYNC_DOWNLOAD_RESULT: LazyLock<IntCounterVec> = LazyLock
     .unwrap()
 });
 
+pub(crate) static STATE_SYNC_PROVIDER_HEALTHY: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_state_sync_provider_healthy",
+        "Whether the state sync provider is healthy (1) or skipped after repeated failures (0)",
+        &["provider"],
+    )
+    .unwrap()
+});
+
 pub(crate) static STATE_SYNC_PARTS_TOTAL: LazyLock<IntGaugeVec> = LazyLock::new(|| {
     try_create_int_gauge_vec(
         "near_state_sync_parts_per_shard",
@@ -490,6 +540,25 @@ pub(crate) static STATE_SYNC_PARTS_TOTAL: LazyLock<IntGaugeVec> = LazyLock::new(
     .unwrap()
 });
 
+pub(crate) static STATE_SYNC_PARTS_APPLIED: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_state_sync_parts_applied",
+        "Number of parts of the shard applied so far",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static STATE_SYNC_APPLY_PART_DELAY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_state_sync_apply_part_delay_sec",
+        "Time spent applying a state part, excluding the wait for a computation slot",
+        &["shard_id"],
+        Some(exponential_buckets(0.001, 2.0, 20).unwrap()),
+    )
+    .unwrap()
+});
+
 pub(crate) static STATE_SYNC_P2P_REQUEST_DELAY: LazyLock<HistogramVec> = LazyLock::new(|| {
     try_create_histogram_vec(
         "near_state_sync_p2p_request_delay_sec",
@@ -582,6 +651,24 @@ pub(crate) static CHUNK_STATE_WITNESS_NETWORK_ROUNDTRIP_TIME: LazyLock<Histogram
         .unwrap()
     });
 
+pub(crate) static CHUNK_VALIDATION_QUEUE_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_chunk_validation_queue_depth",
+        "Number of chunk state witnesses waiting for their validation to start (by shard_id)",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
+pub(crate) static CHUNK_VALIDATION_RUNNING: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_chunk_validation_running",
+        "Number of chunk state witnesses being validated (by shard_id)",
+        &["shard_id"],
+    )
+    .unwrap()
+});
+
 pub(crate) static ORPHAN_CHUNK_STATE_WITNESS_POOL_SIZE: LazyLock<IntGaugeVec> =
     LazyLock::new(|| {
         try_create_int_gauge_vec(
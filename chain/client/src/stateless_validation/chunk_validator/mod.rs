@@ -1,15 +1,17 @@
 pub mod orphan_witness_handling;
 pub mod orphan_witness_pool;
+pub mod validation_scheduler;
 
 use crate::Client;
 use itertools::Itertools;
-use near_async::futures::{AsyncComputationSpawner, AsyncComputationSpawnerExt};
+use near_async::futures::AsyncComputationSpawner;
 use near_async::messaging::{CanSend, Sender};
 use near_chain::stateless_validation::chunk_validation;
 use near_chain::stateless_validation::processing_tracker::ProcessingDoneTracker;
 use near_chain::types::RuntimeAdapter;
 use near_chain::validate::validate_chunk_with_chunk_extra;
 use near_chain::{Block, Chain};
+use near_chain_configs::WitnessValidationLimitsConfig;
 use near_chain_primitives::Error;
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
@@ -22,6 +24,7 @@ use near_primitives::stateless_validation::state_witness::{
 use near_primitives::validator_signer::ValidatorSigner;
 use orphan_witness_pool::OrphanStateWitnessPool;
 use std::sync::Arc;
+use validation_scheduler::ValidationScheduler;
 
 // After validating a chunk state witness, we ideally need to send the chunk endorsement
 // to just the next block producer at height h. However, it's possible that blocks at height
@@ -40,7 +43,7 @@ pub struct ChunkValidator {
     network_sender: Sender<PeerManagerMessageRequest>,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
     orphan_witness_pool: OrphanStateWitnessPool,
-    validation_spawner: Arc<dyn AsyncComputationSpawner>,
+    validation_scheduler: ValidationScheduler,
     main_state_transition_result_cache: chunk_validation::MainStateTransitionCache,
 }
 
@@ -51,13 +54,14 @@ impl ChunkValidator {
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         orphan_witness_pool_size: usize,
         validation_spawner: Arc<dyn AsyncComputationSpawner>,
+        validation_limits: WitnessValidationLimitsConfig,
     ) -> Self {
         Self {
             epoch_manager,
             network_sender,
             runtime_adapter,
             orphan_witness_pool: OrphanStateWitnessPool::new(orphan_witness_pool_size),
-            validation_spawner,
+            validation_scheduler: ValidationScheduler::new(validation_spawner, validation_limits),
             main_state_transition_result_cache: chunk_validation::MainStateTransitionCache::default(
             ),
         }
@@ -143,7 +147,7 @@ impl ChunkValidator {
         let runtime_adapter = self.runtime_adapter.clone();
         let cache = self.main_state_transition_result_cache.clone();
         let signer = signer.clone();
-        self.validation_spawner.spawn("stateless_validation", move || {
+        self.validation_scheduler.schedule(shard_id, move || {
             // processing_done_tracker must survive until the processing is finished.
             let _processing_done_tracker_capture: Option<ProcessingDoneTracker> =
                 processing_done_tracker;
@@ -0,0 +1,240 @@
+use crate::metrics;
+use near_async::futures::AsyncComputationSpawner;
+use near_chain_configs::WitnessValidationLimitsConfig;
+use near_primitives::types::ShardId;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+type ValidationTask = Box<dyn FnOnce() + Send>;
+
+/// Schedules the validations of the chunk state witnesses on the validation spawner, with at
+/// most `max_concurrent_per_shard` validations of each shard and `max_concurrent` validations
+/// in total running at the same time. The validations over the limits wait in per-shard
+/// queues, and the shards take turns to start their next validation, so that a shard with many
+/// or big witnesses can't delay the validations of the other shards.
+#[derive(Clone)]
+pub struct ValidationScheduler {
+    inner: Arc<ValidationSchedulerInner>,
+}
+
+struct ValidationSchedulerInner {
+    spawner: Arc<dyn AsyncComputationSpawner>,
+    limits: WitnessValidationLimitsConfig,
+    state: Mutex<ValidationSchedulerState>,
+}
+
+#[derive(Default)]
+struct ValidationSchedulerState {
+    num_running: usize,
+    num_running_by_shard: HashMap<ShardId, usize>,
+    queues: HashMap<ShardId, VecDeque<ValidationTask>>,
+    /// Shards with queued validations, in the order they get to start their next one.
+    turns: VecDeque<ShardId>,
+}
+
+impl ValidationSchedulerState {
+    /// Takes the queued validations which can start within the limits, round-robin over the
+    /// shards, and counts them as running.
+    fn take_ready_tasks(
+        &mut self,
+        limits: &WitnessValidationLimitsConfig,
+    ) -> Vec<(ShardId, ValidationTask)> {
+        let mut ready_tasks = vec![];
+        // Shards at their own limit keep their turn for when one of their validations is done.
+        let mut num_skipped = 0;
+        while self.num_running < limits.max_concurrent && num_skipped < self.turns.len() {
+            let shard_id = self.turns.pop_front().unwrap();
+            let num_running_in_shard = self.num_running_by_shard.entry(shard_id).or_default();
+            if *num_running_in_shard >= limits.max_concurrent_per_shard {
+                self.turns.push_back(shard_id);
+                num_skipped += 1;
+                continue;
+            }
+            let queue = self.queues.get_mut(&shard_id).unwrap();
+            let task = queue.pop_front().unwrap();
+            if !queue.is_empty() {
+                self.turns.push_back(shard_id);
+            }
+            *num_running_in_shard += 1;
+            self.num_running += 1;
+            self.update_metrics(shard_id);
+            ready_tasks.push((shard_id, task));
+            num_skipped = 0;
+        }
+        ready_tasks
+    }
+
+    fn update_metrics(&self, shard_id: ShardId) {
+        let shard_label = shard_id.to_string();
+        let queue_depth = self.queues.get(&shard_id).map_or(0, |queue| queue.len());
+        metrics::CHUNK_VALIDATION_QUEUE_DEPTH
+            .with_label_values(&[&shard_label])
+            .set(queue_depth as i64);
+        let num_running = self.num_running_by_shard.get(&shard_id).copied().unwrap_or_default();
+        metrics::CHUNK_VALIDATION_RUNNING
+            .with_label_values(&[&shard_label])
+            .set(num_running as i64);
+    }
+}
+
+impl ValidationScheduler {
+    pub fn new(
+        spawner: Arc<dyn AsyncComputationSpawner>,
+        limits: WitnessValidationLimitsConfig,
+    ) -> Self {
+        Self {
+            inner: Arc::new(ValidationSchedulerInner {
+                spawner,
+                limits,
+                state: Mutex::new(ValidationSchedulerState::default()),
+            }),
+        }
+    }
+
+    /// Queues the validation of a witness of `shard_id`, which starts as soon as the limits
+    /// allow it.
+    pub fn schedule(&self, shard_id: ShardId, task: impl FnOnce() + Send + 'static) {
+        let ready_tasks = {
+            let mut state = self.inner.state.lock().unwrap();
+            let queue = state.queues.entry(shard_id).or_default();
+            queue.push_back(Box::new(task));
+            if queue.len() == 1 {
+                state.turns.push_back(shard_id);
+            }
+            state.update_metrics(shard_id);
+            state.take_ready_tasks(&self.inner.limits)
+        };
+        self.spawn(ready_tasks);
+    }
+
+    /// Spawns the tasks outside of the lock of the state, as the spawner may run them right
+    /// away on the calling thread.
+    fn spawn(&self, ready_tasks: Vec<(ShardId, ValidationTask)>) {
+        for (shard_id, task) in ready_tasks {
+            let scheduler = self.clone();
+            self.inner.spawner.spawn_boxed(
+                "stateless_validation",
+                Box::new(move || {
+                    task();
+                    scheduler.on_task_done(shard_id);
+                }),
+            );
+        }
+    }
+
+    fn on_task_done(&self, shard_id: ShardId) {
+        let ready_tasks = {
+            let mut state = self.inner.state.lock().unwrap();
+            state.num_running -= 1;
+            *state.num_running_by_shard.get_mut(&shard_id).unwrap() -= 1;
+            state.update_metrics(shard_id);
+            state.take_ready_tasks(&self.inner.limits)
+        };
+        self.spawn(ready_tasks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Spawner keeping the spawned tasks until the test runs them.
+    #[derive(Default)]
+    struct ManualSpawner {
+        tasks: Mutex<VecDeque<ValidationTask>>,
+    }
+
+    impl AsyncComputationSpawner for ManualSpawner {
+        fn spawn_boxed(&self, _name: &str, f: Box<dyn FnOnce() + Send>) {
+            self.tasks.lock().unwrap().push_back(f);
+        }
+    }
+
+    impl ManualSpawner {
+        fn num_spawned(&self) -> usize {
+            self.tasks.lock().unwrap().len()
+        }
+
+        fn run_next(&self) {
+            let task = self.tasks.lock().unwrap().pop_front().unwrap();
+            task();
+        }
+    }
+
+    fn setup(
+        max_concurrent_per_shard: usize,
+        max_concurrent: usize,
+    ) -> (Arc<ManualSpawner>, ValidationScheduler) {
+        let spawner = Arc::new(ManualSpawner::default());
+        let limits = WitnessValidationLimitsConfig { max_concurrent_per_shard, max_concurrent };
+        let scheduler = ValidationScheduler::new(spawner.clone(), limits);
+        (spawner, scheduler)
+    }
+
+    /// Schedules a task pushing its name to `log` when it runs.
+    fn schedule_logged(
+        scheduler: &ValidationScheduler,
+        log: &Arc<Mutex<Vec<&'static str>>>,
+        shard_id: u64,
+        name: &'static str,
+    ) {
+        let log = log.clone();
+        scheduler.schedule(ShardId::new(shard_id), move || log.lock().unwrap().push(name));
+    }
+
+    #[test]
+    fn test_per_shard_limit() {
+        let (spawner, scheduler) = setup(2, 8);
+        let log = Arc::new(Mutex::new(vec![]));
+        for name in ["a", "b", "c", "d"] {
+            schedule_logged(&scheduler, &log, 0, name);
+        }
+        assert_eq!(spawner.num_spawned(), 2);
+
+        // Each validation done starts the next queued one of the shard.
+        spawner.run_next();
+        assert_eq!(spawner.num_spawned(), 2);
+        for _ in 0..3 {
+            spawner.run_next();
+        }
+        assert_eq!(spawner.num_spawned(), 0);
+        assert_eq!(*log.lock().unwrap(), ["a", "b", "c", "d"]);
+    }
+
+    #[test]
+    fn test_round_robin_across_shards() {
+        let (spawner, scheduler) = setup(1, 1);
+        let log = Arc::new(Mutex::new(vec![]));
+        schedule_logged(&scheduler, &log, 3, "3a");
+        // Shard 0 queues many witnesses before the others queue one each.
+        for name in ["0a", "0b", "0c"] {
+            schedule_logged(&scheduler, &log, 0, name);
+        }
+        schedule_logged(&scheduler, &log, 1, "1a");
+        schedule_logged(&scheduler, &log, 2, "2a");
+        assert_eq!(spawner.num_spawned(), 1);
+
+        while spawner.num_spawned() > 0 {
+            spawner.run_next();
+        }
+        assert_eq!(*log.lock().unwrap(), ["3a", "0a", "1a", "2a", "0b", "0c"]);
+    }
+
+    #[test]
+    fn test_total_limit_with_shard_at_limit() {
+        let (spawner, scheduler) = setup(1, 2);
+        let log = Arc::new(Mutex::new(vec![]));
+        schedule_logged(&scheduler, &log, 0, "0a");
+        schedule_logged(&scheduler, &log, 0, "0b");
+        // Shard 0 is at its own limit, so shard 1 takes the remaining slot.
+        schedule_logged(&scheduler, &log, 1, "1a");
+        assert_eq!(spawner.num_spawned(), 2);
+        schedule_logged(&scheduler, &log, 1, "1b");
+        assert_eq!(spawner.num_spawned(), 2);
+
+        while spawner.num_spawned() > 0 {
+            spawner.run_next();
+        }
+        assert_eq!(*log.lock().unwrap(), ["0a", "1a", "0b", "1b"]);
+    }
+}
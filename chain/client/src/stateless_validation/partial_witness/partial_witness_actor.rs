@@ -23,9 +23,9 @@ use near_performance_metrics_macros::perf;
 use near_primitives::reed_solomon::{ReedSolomonEncoder, ReedSolomonEncoderCache};
 use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::stateless_validation::contract_distribution::{
-    ChunkContractAccesses, ChunkContractDeploys, CodeBytes, CodeHash, ContractCodeRequest,
-    ContractCodeResponse, ContractUpdates, MainTransitionKey, PartialEncodedContractDeploys,
-    PartialEncodedContractDeploysPart,
+    ChunkContractAccesses, ChunkContractDeploys, CodeBytes, CodeHash, CompressedContractCode,
+    ContractCodeRequest, ContractCodeResponse, ContractUpdates, MainTransitionKey,
+    PartialEncodedContractDeploys, PartialEncodedContractDeploysPart,
 };
 use near_primitives::stateless_validation::partial_witness::PartialEncodedStateWitness;
 use near_primitives::stateless_validation::state_witness::{
@@ -506,6 +506,7 @@ impl PartialWitnessActor {
             let contracts = match deploys.decompress_contracts() {
                 Ok(contracts) => contracts,
                 Err(err) => {
+                    near_chain::metrics::record_decode_failure(CompressedContractCode::NAME, &err);
                     tracing::warn!(
                         target: "client",
                         ?err,
@@ -742,7 +743,9 @@ impl PartialWitnessActor {
         response: ContractCodeResponse,
     ) -> Result<(), Error> {
         let key = response.chunk_production_key().clone();
-        let contracts = response.decompress_contracts()?;
+        let contracts = response.decompress_contracts().inspect_err(|err| {
+            near_chain::metrics::record_decode_failure(CompressedContractCode::NAME, err)
+        })?;
         self.partial_witness_tracker.store_accessed_contract_codes(key, contracts)
     }
 
@@ -470,7 +470,9 @@ impl PartialEncodedStateWitnessTracker {
         encoded_witness: &EncodedChunkStateWitness,
     ) -> Result<(ChunkStateWitness, ChunkStateWitnessSize), Error> {
         let decode_start = std::time::Instant::now();
-        let (witness, raw_witness_size) = encoded_witness.decode()?;
+        let (witness, raw_witness_size) = encoded_witness.decode().inspect_err(|err| {
+            near_chain::metrics::record_decode_failure(EncodedChunkStateWitness::NAME, err)
+        })?;
         let decode_elapsed_seconds = decode_start.elapsed().as_secs_f64();
         let witness_shard = witness.chunk_header.shard_id();
 
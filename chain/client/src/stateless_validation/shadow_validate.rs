@@ -58,6 +58,7 @@ impl Client {
             use_flat_storage: true,
             source: StorageDataSource::Db,
             state_patch: Default::default(),
+            keep_storage_proof: true,
         };
 
         // We call `validate_prepared_transactions()` here because we need storage proof for transactions validation.
@@ -83,6 +83,26 @@ impl Client {
             self.chain.chain_store.save_latest_chunk_state_witness(&state_witness)?;
         }
 
+        if self.config.validate_produced_witnesses {
+            if let Err(err) = self.chain.validate_produced_state_witness(
+                &state_witness,
+                self.epoch_manager.as_ref(),
+                self.runtime_adapter.as_ref(),
+            ) {
+                near_chain::stateless_validation::metrics::PRODUCED_CHUNK_WITNESS_VALIDATION_FAILED_TOTAL
+                    .with_label_values(&[&shard_id.to_string(), err.prometheus_label_value()])
+                    .inc();
+                tracing::error!(
+                    target: "client",
+                    ?err,
+                    chunk_hash = ?chunk_header.chunk_hash(),
+                    ?shard_id,
+                    "produced state witness failed local validation, not distributing it"
+                );
+                return Err(err);
+            }
+        }
+
         let height = chunk_header.height_created();
         if self
             .epoch_manager
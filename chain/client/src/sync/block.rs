@@ -214,15 +214,13 @@ impl BlockSync {
         for (height, hash) in requests {
             let request_from_archival = self.archive && height < gc_stop_height;
             // Assume that heads of `highest_height_peers` are ahead of the blocks we're requesting.
-            let peer = if request_from_archival {
-                // Normal peers are unlikely to have old blocks, request from an archival node.
-                let archival_peer_iter = highest_height_peers.iter().filter(|p| p.archival);
-                archival_peer_iter.choose(&mut rand::thread_rng())
-            } else {
-                // All peers are likely to have this block.
-                let peer_iter = highest_height_peers.iter();
-                peer_iter.choose(&mut rand::thread_rng())
-            };
+            // Skip the peers which advertised that they garbage collected the block. Normal peers
+            // that didn't advertise it are unlikely to have old blocks, request those from an
+            // archival node.
+            let peer = highest_height_peers
+                .iter()
+                .filter(|p| p.may_have_block(height, request_from_archival))
+                .choose(&mut rand::thread_rng());
 
             if let Some(peer) = peer {
                 debug!(
@@ -361,6 +359,7 @@ mod test {
                 highest_block_hash: Default::default(),
                 tracked_shards: vec![],
                 archival: false,
+                earliest_block_height: None,
             })
             .collect()
     }
@@ -496,5 +495,34 @@ mod test {
             requested_block_hashes,
             blocks.iter().take(max_block_requests).map(|b| *b.hash()).collect::<HashSet<_>>()
         );
+
+        // Regular peers advertising their earliest available block are asked for the blocks
+        // they still have.
+        let mut peer_infos = create_highest_height_peer_infos(2);
+        for peer in peer_infos.iter_mut() {
+            peer.earliest_block_height = Some(blocks[5].header().height());
+        }
+
+        block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+        let requested_block_hashes = collect_hashes_from_network_adapter(&network_adapter);
+        assert_eq!(
+            requested_block_hashes,
+            blocks[5..max_block_requests].iter().map(|b| *b.hash()).collect::<HashSet<_>>()
+        );
+
+        // Archival peers with split storage advertise the tail of their hot store, but serve
+        // the older blocks from their cold store.
+        let mut peer_infos = create_highest_height_peer_infos(2);
+        for peer in peer_infos.iter_mut() {
+            peer.archival = true;
+            peer.earliest_block_height = Some(blocks[30].header().height());
+        }
+
+        block_sync.block_sync(&env.clients[1].chain, &peer_infos, max_block_requests).unwrap();
+        let requested_block_hashes = collect_hashes_from_network_adapter(&network_adapter);
+        assert_eq!(
+            requested_block_hashes,
+            blocks.iter().take(max_block_requests).map(|b| *b.hash()).collect::<HashSet<_>>()
+        );
     }
 }
@@ -28,7 +28,7 @@ use near_primitives::merkle::PartialMerkleTree;
 use near_primitives::network::PeerId;
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
-    AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta, EpochId,
+    AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta, EpochHeight, EpochId,
 };
 use near_primitives::utils::{compression::CompressedData, index_to_bytes};
 use near_primitives::version::ProtocolFeature;
@@ -39,6 +39,9 @@ use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, Mutex};
 use tracing::instrument;
 
+/// Key in `DBCol::Misc` of the epoch sync proof persisted by `checkpoint_epoch_sync_proof`.
+const EPOCH_SYNC_PROOF_CHECKPOINT_KEY: &[u8] = b"EPOCH_SYNC_PROOF_CHECKPOINT";
+
 pub struct EpochSync {
     clock: Clock,
     network_adapter: PeerManagerAdapter,
@@ -97,20 +100,8 @@ impl EpochSync {
         // Epoch sync initializes a new node with the first block of some epoch; we call that
         // epoch the "target epoch". In the context of talking about the proof or the newly
         // bootstrapped node, it is also called the "current epoch".
-        let target_epoch_last_block_hash =
-            Self::find_target_epoch_to_produce_proof_for(&store, transaction_validity_period)?;
-
-        let target_epoch_last_block_header = store
-            .get_ser::<BlockHeader>(DBCol::BlockHeader, target_epoch_last_block_hash.as_bytes())?
-            .ok_or_else(|| Error::Other("Could not find last block of target epoch".to_string()))?;
-        let target_epoch_second_last_block_header = store
-            .get_ser::<BlockHeader>(
-                DBCol::BlockHeader,
-                target_epoch_last_block_header.prev_hash().as_bytes(),
-            )?
-            .ok_or_else(|| {
-                Error::Other("Could not find second last block of target epoch".to_string())
-            })?;
+        let (target_epoch_last_block_header, target_epoch_second_last_block_header) =
+            Self::find_target_epoch_last_block_headers(&store, transaction_validity_period)?;
 
         let mut guard = cache.lock().unwrap();
         if let Some((epoch_id, proof)) = &*guard {
@@ -124,17 +115,65 @@ impl EpochSync {
             store,
             target_epoch_second_last_block_header,
         );
-        let (proof, _) = match CompressedEpochSyncProof::encode(&proof?) {
+        let (proof, raw_size) = match CompressedEpochSyncProof::encode(&proof?) {
             Ok(proof) => proof,
             Err(err) => {
                 return Err(Error::Other(format!("Failed to compress epoch sync proof: {:?}", err)))
             }
         };
+        near_chain::metrics::record_compression_ratio(
+            CompressedEpochSyncProof::NAME,
+            raw_size,
+            proof.size_bytes(),
+        );
         metrics::EPOCH_SYNC_LAST_GENERATED_COMPRESSED_PROOF_SIZE.set(proof.size_bytes() as i64);
         *guard = Some((*target_epoch_last_block_header.epoch_id(), proof.clone()));
         Ok(proof)
     }
 
+    /// Derives an epoch sync proof like `derive_epoch_sync_proof` and persists it as a
+    /// checkpoint, so that later proofs can be derived from it instead of from the data of all
+    /// the epochs since genesis. Returns the lowest epoch height whose epoch data is still
+    /// needed to derive later proofs; data of older epochs can be garbage collected.
+    pub fn checkpoint_epoch_sync_proof(
+        store: Store,
+        transaction_validity_period: BlockHeightDelta,
+    ) -> Result<EpochHeight, Error> {
+        let (_, target_epoch_second_last_block_header) =
+            Self::find_target_epoch_last_block_headers(&store, transaction_validity_period)?;
+        let proof = Self::derive_epoch_sync_proof_from_last_final_block(
+            store.clone(),
+            target_epoch_second_last_block_header,
+        )?;
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::Misc, EPOCH_SYNC_PROOF_CHECKPOINT_KEY, &proof)?;
+        store_update.commit()?;
+        Ok(proof.into_v1().last_epoch.epoch_info.epoch_height())
+    }
+
+    /// Returns the headers of the last and the second last block of the target epoch, see
+    /// `find_target_epoch_to_produce_proof_for`.
+    fn find_target_epoch_last_block_headers(
+        store: &Store,
+        transaction_validity_period: BlockHeightDelta,
+    ) -> Result<(BlockHeader, BlockHeader), Error> {
+        let target_epoch_last_block_hash =
+            Self::find_target_epoch_to_produce_proof_for(store, transaction_validity_period)?;
+
+        let target_epoch_last_block_header = store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, target_epoch_last_block_hash.as_bytes())?
+            .ok_or_else(|| Error::Other("Could not find last block of target epoch".to_string()))?;
+        let target_epoch_second_last_block_header = store
+            .get_ser::<BlockHeader>(
+                DBCol::BlockHeader,
+                target_epoch_last_block_header.prev_hash().as_bytes(),
+            )?
+            .ok_or_else(|| {
+                Error::Other("Could not find second last block of target epoch".to_string())
+            })?;
+        Ok((target_epoch_last_block_header, target_epoch_second_last_block_header))
+    }
+
     /// Figures out which target epoch we should produce a proof for, based on the current
     /// state of the blockchain.
     ///
@@ -224,9 +263,16 @@ impl EpochSync {
         // If we have an existing (possibly and likely outdated) EpochSyncProof stored on disk,
         // the last epoch we have a proof for is the "previous epoch" included in that EpochSyncProof.
         // Otherwise, the last epoch we have a "proof" for is the genesis epoch.
-        let existing_epoch_sync_proof = store
-            .get_ser::<EpochSyncProof>(DBCol::EpochSyncProof, &[])?
-            .map(|proof| proof.into_v1());
+        // The stored proof is either the one this node was bootstrapped with, or the checkpoint
+        // persisted before garbage collecting old epoch data, whichever is more recent.
+        let bootstrap_proof = store.get_ser::<EpochSyncProof>(DBCol::EpochSyncProof, &[])?;
+        let checkpoint_proof =
+            store.get_ser::<EpochSyncProof>(DBCol::Misc, EPOCH_SYNC_PROOF_CHECKPOINT_KEY)?;
+        let existing_epoch_sync_proof = bootstrap_proof
+            .into_iter()
+            .chain(checkpoint_proof)
+            .map(|proof| proof.into_v1())
+            .max_by_key(|proof| proof.last_epoch.next_epoch_info.epoch_height());
         let last_epoch_we_have_proof_for = existing_epoch_sync_proof
             .as_ref()
             .and_then(|existing_proof| {
@@ -1023,6 +1069,7 @@ impl Handler<EpochSyncResponseMessage> for ClientActorInner {
         let (proof, _) = match msg.proof.decode() {
             Ok(proof) => proof,
             Err(err) => {
+                near_chain::metrics::record_decode_failure(CompressedEpochSyncProof::NAME, &err);
                 tracing::error!(?err, "Failed to uncompress epoch sync proof");
                 return;
             }
@@ -58,6 +58,11 @@ pub enum ExternalConnection {
         reqwest_client: Arc<reqwest::Client>,
         bucket: String,
     },
+    /// Read-only mirror serving the files over HTTP under `url`.
+    Http {
+        reqwest_client: Arc<reqwest::Client>,
+        url: String,
+    },
 }
 
 const GCS_ENCODE_SET: &percent_encoding::AsciiSet =
@@ -113,6 +118,24 @@ impl ExternalConnection {
                     }
                 }
             }
+            ExternalConnection::Http { reqwest_client, url } => {
+                let url = format!("{}/{}", url.trim_end_matches('/'), location);
+                let response = reqwest_client.get(&url).send().await?.error_for_status();
+                match response {
+                    Err(e) => {
+                        tracing::debug!(target: "sync", %shard_id, url, error = ?e, "HTTP mirror request failed");
+                        Err(e.into())
+                    }
+                    Ok(r) => {
+                        let bytes = r.bytes().await?.to_vec();
+                        tracing::debug!(target: "sync", %shard_id, url, num_bytes = bytes.len(), "HTTP mirror request finished");
+                        metrics::STATE_SYNC_EXTERNAL_PARTS_SIZE_DOWNLOADED
+                            .with_label_values(&[&shard_id.to_string(), &file_type.to_string()])
+                            .inc_by(bytes.len() as u64);
+                        Ok(bytes)
+                    }
+                }
+            }
         }
     }
 
@@ -171,6 +194,9 @@ impl ExternalConnection {
                 tracing::debug!(target: "state_sync_dump", ?shard_id, part_length = data.len(), ?location, ?file_type, "Wrote a state part to GCS");
                 Ok(())
             }
+            ExternalConnection::Http { url, .. } => {
+                anyhow::bail!("Can't write {location} to the read-only HTTP mirror {url}")
+            }
         }
     }
 
@@ -241,6 +267,9 @@ impl ExternalConnection {
                     .flatten()
                     .collect())
             }
+            ExternalConnection::Http { url, .. } => {
+                anyhow::bail!("Can't list {directory_path} on the HTTP mirror {url}")
+            }
         }
     }
 
@@ -509,6 +509,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                earliest_block_height: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -611,6 +612,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                earliest_block_height: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -678,6 +680,7 @@ mod test {
                 highest_block_hash: Default::default(),
                 tracked_shards: vec![],
                 archival: false,
+                earliest_block_height: None,
             });
             header_sync.syncing_peer.as_mut().unwrap().highest_block_height = highest_height;
         };
@@ -837,6 +840,7 @@ mod test {
                 },
                 tracked_shards: vec![],
                 archival: false,
+                earliest_block_height: None,
                 last_block: Some(BlockInfo {
                     height: chain2.head().unwrap().height,
                     hash: chain2.head().unwrap().last_block_hash,
@@ -1,7 +1,7 @@
 use super::chain_requests::StateHeaderValidationRequest;
+use super::providers::StateSyncProviders;
 use super::task_tracker::TaskTracker;
 use super::util::get_state_header_if_exists_in_storage;
-use super::StateSyncDownloadSource;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use near_async::messaging::AsyncSender;
@@ -17,19 +17,17 @@ use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
 use tracing::Instrument;
 
-/// The downloader works on top of a StateSyncDownloadSource, by adding:
+/// The downloader works on top of the StateSyncDownloadSources of the providers, by adding:
 ///  - caching of the header / part in rocksdb.
 ///  - validation of the header / part before persisting into rocksdb.
-///  - retrying, if the download fails, or validation fails.
+///  - retrying, if the download fails, or validation fails, falling back to the next providers.
 ///
 /// As a result, the user of this API only needs to request the header or ensure the
 /// part exists on disk, and the downloader will take care of the rest.
 pub(super) struct StateSyncDownloader {
     pub clock: Clock,
     pub store: Store,
-    pub preferred_source: Arc<dyn StateSyncDownloadSource>,
-    pub fallback_source: Option<Arc<dyn StateSyncDownloadSource>>,
-    pub num_attempts_before_fallback: usize,
+    pub providers: Arc<StateSyncProviders>,
     pub header_validation_sender:
         AsyncSender<StateHeaderValidationRequest, Result<(), near_chain::Error>>,
     pub runtime: Arc<dyn RuntimeAdapter>,
@@ -51,9 +49,7 @@ impl StateSyncDownloader {
     ) -> BoxFuture<Result<ShardStateSyncResponseHeader, near_chain::Error>> {
         let store = self.store.clone();
         let validation_sender = self.header_validation_sender.clone();
-        let preferred_source = self.preferred_source.clone();
-        let fallback_source = self.fallback_source.clone();
-        let num_attempts_before_fallback = self.num_attempts_before_fallback;
+        let providers = self.providers.clone();
         let task_tracker = self.task_tracker.clone();
         let clock = self.clock.clone();
         let retry_backoff = self.retry_backoff;
@@ -69,32 +65,40 @@ impl StateSyncDownloader {
             let i = AtomicUsize::new(0); // for easier Rust async capture
             let attempt = || {
                 async {
-                    let source = if fallback_source.is_some()
-                        && i.load(Ordering::Relaxed) >= num_attempts_before_fallback
-                    {
-                        fallback_source.as_ref().unwrap().as_ref()
-                    } else {
-                        preferred_source.as_ref()
-                    };
-                    let header = source
-                        .download_shard_header(shard_id, sync_hash, handle.clone(), cancel.clone())
-                        .await?;
-                    // We cannot validate the header with just a Store. We need the Chain, so we queue it up
-                    // so the chain can pick it up later, and we await until the chain gives us a response.
-                    handle.set_status("Waiting for validation");
-                    validation_sender
-                        .send_async(StateHeaderValidationRequest {
-                            shard_id,
-                            sync_hash,
-                            header: header.clone(),
-                        })
-                        .await
-                        .map_err(|_| {
-                            near_chain::Error::Other(
-                                "Validation request could not be handled".to_owned(),
+                    let provider = providers.select(i.load(Ordering::Relaxed));
+                    let result = async {
+                        let header = providers
+                            .get(provider)
+                            .source
+                            .download_shard_header(
+                                shard_id,
+                                sync_hash,
+                                handle.clone(),
+                                cancel.clone(),
                             )
-                        })??;
-                    Ok::<ShardStateSyncResponseHeader, near_chain::Error>(header)
+                            .await?;
+                        // We cannot validate the header with just a Store. We need the Chain, so we queue it up
+                        // so the chain can pick it up later, and we await until the chain gives us a response.
+                        handle.set_status("Waiting for validation");
+                        validation_sender
+                            .send_async(StateHeaderValidationRequest {
+                                shard_id,
+                                sync_hash,
+                                header: header.clone(),
+                            })
+                            .await
+                            .map_err(|_| {
+                                near_chain::Error::Other(
+                                    "Validation request could not be handled".to_owned(),
+                                )
+                            })??;
+                        Ok::<ShardStateSyncResponseHeader, near_chain::Error>(header)
+                    }
+                    .await;
+                    if !cancel.is_cancelled() {
+                        providers.record_result(provider, result.is_ok());
+                    }
+                    result
                 }
             };
 
@@ -139,9 +143,7 @@ impl StateSyncDownloader {
     ) -> BoxFuture<'static, Result<(), near_chain::Error>> {
         let store = self.store.clone();
         let runtime_adapter = self.runtime.clone();
-        let preferred_source = self.preferred_source.clone();
-        let fallback_source = self.fallback_source.clone();
-        let num_attempts_before_fallback = self.num_attempts_before_fallback;
+        let providers = self.providers.clone();
         let clock = self.clock.clone();
         let task_tracker = self.task_tracker.clone();
         let retry_backoff = self.retry_backoff;
@@ -156,16 +158,11 @@ impl StateSyncDownloader {
                 return Ok(());
             }
 
+            let provider = providers.select(num_prior_attempts);
             let attempt = || async {
-                let source = if fallback_source.is_some()
-                    && num_prior_attempts >= num_attempts_before_fallback
-                {
-                    fallback_source.as_ref().unwrap().as_ref()
-                } else {
-                    preferred_source.as_ref()
-                };
-
-                let part = source
+                let part = providers
+                    .get(provider)
+                    .source
                     .download_shard_part(
                         shard_id,
                         sync_hash,
@@ -192,6 +189,9 @@ impl StateSyncDownloader {
             };
 
             let res = attempt().await;
+            if !cancel.is_cancelled() {
+                providers.record_result(provider, res.is_ok());
+            }
             if let Err(ref err) = res {
                 handle.set_status(&format!("Error: {}, will retry in {}", err, retry_backoff));
                 let deadline = clock.now() + retry_backoff;
@@ -3,13 +3,15 @@ use super::util::{get_state_header_if_exists_in_storage, query_epoch_id_and_heig
 use super::StateSyncDownloadSource;
 use crate::sync::external::{external_storage_location, ExternalConnection, StateFileType};
 use crate::sync::state::util::increment_download_count;
-use borsh::BorshDeserialize;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use near_async::time::{Clock, Duration};
 use near_primitives::hash::CryptoHash;
-use near_primitives::state_sync::ShardStateSyncResponseHeader;
+use near_primitives::state_sync::{
+    decode_state_header, CompressedStateHeader, ShardStateSyncResponseHeader,
+};
 use near_primitives::types::ShardId;
+use near_primitives::utils::compression::CompressedData;
 use near_store::Store;
 use std::sync::Arc;
 use tokio_util::sync::CancellationToken;
@@ -108,7 +110,8 @@ impl StateSyncDownloadSource for StateSyncDownloadSourceExternal {
                 StateFileType::StateHeader,
             )
             .await?;
-            let header = ShardStateSyncResponseHeader::try_from_slice(&data).map_err(|e| {
+            let header = decode_state_header(&data).map_err(|e| {
+                near_chain::metrics::record_decode_failure(CompressedStateHeader::NAME, &e);
                 increment_download_count(shard_id, "header", "external", "parse_error");
                 near_chain::Error::Other(format!("Failed to parse header: {}", e))
             })?;
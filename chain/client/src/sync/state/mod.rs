@@ -2,6 +2,7 @@ pub mod chain_requests;
 mod downloader;
 mod external;
 mod network;
+mod providers;
 mod shard;
 mod task_tracker;
 mod util;
@@ -12,12 +13,15 @@ use chain_requests::ChainSenderForStateSync;
 use downloader::StateSyncDownloader;
 use external::StateSyncDownloadSourceExternal;
 use futures::future::BoxFuture;
+use itertools::Itertools;
 use near_async::futures::{FutureSpawner, FutureSpawnerExt};
 use near_async::messaging::{AsyncSender, IntoSender};
 use near_async::time::{Clock, Duration};
 use near_chain::types::RuntimeAdapter;
 use near_chain::Chain;
-use near_chain_configs::{ExternalStorageConfig, ExternalStorageLocation, SyncConfig};
+use near_chain_configs::{
+    ExternalStorageConfig, ExternalStorageLocation, StateSyncProvider as ProviderConfig, SyncConfig,
+};
 use near_client_primitives::types::{ShardSyncStatus, StateSyncStatus};
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::types::{
@@ -29,6 +33,7 @@ use near_primitives::state_sync::{ShardStateSyncResponse, ShardStateSyncResponse
 use near_primitives::types::ShardId;
 use near_store::Store;
 use network::{StateSyncDownloadSourcePeer, StateSyncDownloadSourcePeerSharedState};
+use providers::{StateSyncProvider, StateSyncProviders};
 use shard::{run_state_sync_for_shard, StateSyncShardHandle};
 use std::collections::hash_map::Entry;
 use std::collections::HashMap;
@@ -108,64 +113,97 @@ impl StateSync {
             request_timeout: p2p_timeout,
             state: peer_source_state.clone(),
         }) as Arc<dyn StateSyncDownloadSource>;
-        let (fallback_source, num_attempts_before_fallback, num_concurrent_requests) =
-            if let SyncConfig::ExternalStorage(ExternalStorageConfig {
+        let external_providers = |location: &ExternalStorageLocation, num_attempts: usize| {
+            create_external_connections(location, external_timeout)
+                .into_iter()
+                .map(|(name, conn)| StateSyncProvider {
+                    name,
+                    source: Arc::new(StateSyncDownloadSourceExternal {
+                        clock: clock.clone(),
+                        store: store.clone(),
+                        chain_id: chain_id.to_string(),
+                        conn,
+                        timeout: external_timeout,
+                        backoff: external_backoff,
+                    }),
+                    num_attempts,
+                })
+                .collect_vec()
+        };
+        let peers_provider = |num_attempts: usize| StateSyncProvider {
+            name: "peers".to_string(),
+            source: peer_source.clone(),
+            num_attempts,
+        };
+        let num_concurrent_requests =
+            |num_concurrent_requests: u32, num_concurrent_requests_during_catchup: u32| {
+                let num_concurrent_requests = if catchup {
+                    num_concurrent_requests_during_catchup
+                } else {
+                    num_concurrent_requests
+                } as usize;
+                num_concurrent_requests.min(NUM_CONCURRENT_REQUESTS_FOR_PEERS)
+            };
+        // The peers and external storage configs don't skip unhealthy providers, falling back
+        // from the peers to the external storage for all the remaining attempts.
+        let (providers, num_concurrent_requests) = match sync_config {
+            SyncConfig::Peers => (
+                StateSyncProviders::new(
+                    clock.clone(),
+                    vec![peers_provider(1)],
+                    usize::MAX,
+                    Duration::ZERO,
+                ),
+                NUM_CONCURRENT_REQUESTS_FOR_PEERS,
+            ),
+            SyncConfig::ExternalStorage(ExternalStorageConfig {
                 location,
-                num_concurrent_requests,
+                num_concurrent_requests: num_requests,
                 num_concurrent_requests_during_catchup,
                 external_storage_fallback_threshold,
-            }) = sync_config
-            {
-                let external = match location {
-                    ExternalStorageLocation::S3 { bucket, region, .. } => {
-                        let bucket = create_bucket_readonly(
-                            &bucket,
-                            &region,
-                            external_timeout.max(Duration::ZERO).unsigned_abs(),
-                        );
-                        if let Err(err) = bucket {
-                            panic!("Failed to create an S3 bucket: {}", err);
+            }) => {
+                let mut providers =
+                    vec![peers_provider(*external_storage_fallback_threshold as usize)];
+                providers.extend(external_providers(location, 1));
+                (
+                    StateSyncProviders::new(clock.clone(), providers, usize::MAX, Duration::ZERO),
+                    num_concurrent_requests(*num_requests, *num_concurrent_requests_during_catchup),
+                )
+            }
+            SyncConfig::Providers(config) => {
+                let providers = config
+                    .providers
+                    .iter()
+                    .flat_map(|provider_config| {
+                        let num_attempts = provider_config.num_attempts as usize;
+                        match &provider_config.provider {
+                            ProviderConfig::Peers => vec![peers_provider(num_attempts)],
+                            ProviderConfig::External(location) => {
+                                external_providers(location, num_attempts)
+                            }
                         }
-                        ExternalConnection::S3 { bucket: Arc::new(bucket.unwrap()) }
-                    }
-                    ExternalStorageLocation::Filesystem { root_dir } => {
-                        ExternalConnection::Filesystem { root_dir: root_dir.clone() }
-                    }
-                    ExternalStorageLocation::GCS { bucket, .. } => ExternalConnection::GCS {
-                        gcs_client: Arc::new(cloud_storage::Client::default()),
-                        reqwest_client: Arc::new(reqwest::Client::default()),
-                        bucket: bucket.clone(),
-                    },
-                };
-                let num_concurrent_requests = if catchup {
-                    *num_concurrent_requests_during_catchup
-                } else {
-                    *num_concurrent_requests
-                } as usize;
-                let fallback_source = Arc::new(StateSyncDownloadSourceExternal {
-                    clock: clock.clone(),
-                    store: store.clone(),
-                    chain_id: chain_id.to_string(),
-                    conn: external,
-                    timeout: external_timeout,
-                    backoff: external_backoff,
-                }) as Arc<dyn StateSyncDownloadSource>;
+                    })
+                    .collect_vec();
                 (
-                    Some(fallback_source),
-                    *external_storage_fallback_threshold as usize,
-                    num_concurrent_requests.min(NUM_CONCURRENT_REQUESTS_FOR_PEERS),
+                    StateSyncProviders::new(
+                        clock.clone(),
+                        providers,
+                        config.max_consecutive_failures as usize,
+                        config.unhealthy_cooldown,
+                    ),
+                    num_concurrent_requests(
+                        config.num_concurrent_requests,
+                        config.num_concurrent_requests_during_catchup,
+                    ),
                 )
-            } else {
-                (None, 0, NUM_CONCURRENT_REQUESTS_FOR_PEERS)
-            };
+            }
+        };
 
         let downloading_task_tracker = TaskTracker::new(num_concurrent_requests);
         let downloader = Arc::new(StateSyncDownloader {
             clock,
             store: store.clone(),
-            preferred_source: peer_source,
-            fallback_source,
-            num_attempts_before_fallback,
+            providers: Arc::new(providers),
             header_validation_sender: chain_requests_sender.clone().into_sender(),
             runtime: runtime.clone(),
             retry_backoff,
@@ -225,7 +263,7 @@ impl StateSync {
         );
 
         let mut all_done = true;
-        for shard_id in tracking_shards {
+        for (shard_index, shard_id) in tracking_shards.iter().enumerate() {
             let key = (sync_hash, *shard_id);
             let status = match self.shard_syncs.entry(key) {
                 Entry::Occupied(mut entry) => match entry.get_mut().result.try_recv() {
@@ -257,6 +295,7 @@ impl StateSync {
                     let shard_sync = run_state_sync_for_shard(
                         self.store.clone(),
                         *shard_id,
+                        shard_index,
                         sync_hash,
                         self.downloader.clone(),
                         self.runtime.clone(),
@@ -327,6 +366,54 @@ pub(self) trait StateSyncDownloadSource: Send + Sync + 'static {
     ) -> BoxFuture<Result<Vec<u8>, near_chain::Error>>;
 }
 
+/// Connects to the external storage at `location` for reading, along with the name of each
+/// connection. Mirror lists are expanded to one connection per mirror.
+fn create_external_connections(
+    location: &ExternalStorageLocation,
+    timeout: Duration,
+) -> Vec<(String, ExternalConnection)> {
+    match location {
+        ExternalStorageLocation::S3 { bucket, region, .. } => {
+            let conn = create_bucket_readonly(
+                &bucket,
+                &region,
+                timeout.max(Duration::ZERO).unsigned_abs(),
+            );
+            if let Err(err) = conn {
+                panic!("Failed to create an S3 bucket: {}", err);
+            }
+            vec![(
+                format!("s3:{}", bucket),
+                ExternalConnection::S3 { bucket: Arc::new(conn.unwrap()) },
+            )]
+        }
+        ExternalStorageLocation::Filesystem { root_dir } => vec![(
+            format!("filesystem:{}", root_dir.display()),
+            ExternalConnection::Filesystem { root_dir: root_dir.clone() },
+        )],
+        ExternalStorageLocation::GCS { bucket, .. } => vec![(
+            format!("gcs:{}", bucket),
+            ExternalConnection::GCS {
+                gcs_client: Arc::new(cloud_storage::Client::default()),
+                reqwest_client: Arc::new(reqwest::Client::default()),
+                bucket: bucket.clone(),
+            },
+        )],
+        ExternalStorageLocation::HttpMirrors { urls } => {
+            let reqwest_client = Arc::new(reqwest::Client::default());
+            urls.iter()
+                .map(|url| {
+                    let conn = ExternalConnection::Http {
+                        reqwest_client: reqwest_client.clone(),
+                        url: url.clone(),
+                    };
+                    (url.clone(), conn)
+                })
+                .collect()
+        }
+    }
+}
+
 /// Find the hash of the first block on the same epoch (and chain) of block with hash `sync_hash`.
 pub fn get_epoch_start_sync_hash(
     chain: &Chain,
@@ -0,0 +1,179 @@
+use super::StateSyncDownloadSource;
+use crate::metrics;
+use near_async::time::{Clock, Duration, Instant};
+use std::sync::{Arc, Mutex};
+
+/// A source of state headers and parts along with its position in the fallback order.
+pub(super) struct StateSyncProvider {
+    /// Name used in logs and metrics.
+    pub name: String,
+    pub source: Arc<dyn StateSyncDownloadSource>,
+    /// Number of attempts made with this provider before falling back to the next one.
+    pub num_attempts: usize,
+}
+
+#[derive(Default)]
+struct ProviderHealth {
+    consecutive_failures: usize,
+    /// Set when the provider failed too many times in a row; it is skipped until then.
+    unhealthy_until: Option<Instant>,
+}
+
+/// Ordered list of the providers of state headers and parts, tracking the health of each, so
+/// that the downloads fall back from the preferred providers to the next ones, skipping the
+/// providers which keep failing.
+pub(super) struct StateSyncProviders {
+    clock: Clock,
+    providers: Vec<StateSyncProvider>,
+    health: Mutex<Vec<ProviderHealth>>,
+    max_consecutive_failures: usize,
+    unhealthy_cooldown: Duration,
+}
+
+impl StateSyncProviders {
+    pub fn new(
+        clock: Clock,
+        providers: Vec<StateSyncProvider>,
+        max_consecutive_failures: usize,
+        unhealthy_cooldown: Duration,
+    ) -> Self {
+        assert!(!providers.is_empty(), "state sync needs at least one provider");
+        for provider in &providers {
+            metrics::STATE_SYNC_PROVIDER_HEALTHY.with_label_values(&[&provider.name]).set(1);
+        }
+        let health = Mutex::new(providers.iter().map(|_| ProviderHealth::default()).collect());
+        Self { clock, providers, health, max_consecutive_failures, unhealthy_cooldown }
+    }
+
+    /// Index of the provider scheduled for the given attempt: each provider is used for its
+    /// number of attempts in order, and the last one for all the remaining attempts.
+    fn scheduled(&self, attempt: usize) -> usize {
+        let mut remaining = attempt;
+        for (index, provider) in self.providers.iter().enumerate() {
+            if remaining < provider.num_attempts {
+                return index;
+            }
+            remaining -= provider.num_attempts;
+        }
+        self.providers.len() - 1
+    }
+
+    /// Selects the provider for the given attempt to download a header or part. If the
+    /// scheduled provider is unhealthy, the next healthy one in order is used instead, wrapping
+    /// around to the preferred ones. If all of them are unhealthy, the scheduled one is used.
+    pub fn select(&self, attempt: usize) -> usize {
+        let scheduled = self.scheduled(attempt);
+        let now = self.clock.now();
+        let health = self.health.lock().unwrap();
+        (0..self.providers.len())
+            .map(|offset| (scheduled + offset) % self.providers.len())
+            .find(|&index| health[index].unhealthy_until.map_or(true, |until| until <= now))
+            .unwrap_or(scheduled)
+    }
+
+    pub fn get(&self, index: usize) -> &StateSyncProvider {
+        &self.providers[index]
+    }
+
+    /// Records the result of a download from the provider, which includes the validation of
+    /// the downloaded data. Cancelled downloads should not be recorded.
+    pub fn record_result(&self, index: usize, success: bool) {
+        let provider = &self.providers[index];
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        if success {
+            health.consecutive_failures = 0;
+            health.unhealthy_until = None;
+            metrics::STATE_SYNC_PROVIDER_HEALTHY.with_label_values(&[&provider.name]).set(1);
+            return;
+        }
+        health.consecutive_failures += 1;
+        if health.consecutive_failures >= self.max_consecutive_failures {
+            tracing::warn!(
+                target: "sync",
+                provider = %provider.name,
+                consecutive_failures = health.consecutive_failures,
+                cooldown = %self.unhealthy_cooldown,
+                "State sync provider is unhealthy, skipping it",
+            );
+            health.consecutive_failures = 0;
+            health.unhealthy_until = Some(self.clock.now() + self.unhealthy_cooldown);
+            metrics::STATE_SYNC_PROVIDER_HEALTHY.with_label_values(&[&provider.name]).set(0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StateSyncProvider, StateSyncProviders};
+    use crate::sync::state::task_tracker::TaskHandle;
+    use crate::sync::state::StateSyncDownloadSource;
+    use futures::future::BoxFuture;
+    use near_async::time::{Duration, FakeClock, Utc};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::state_sync::ShardStateSyncResponseHeader;
+    use near_primitives::types::ShardId;
+    use std::sync::Arc;
+    use tokio_util::sync::CancellationToken;
+
+    struct UnusedSource;
+
+    impl StateSyncDownloadSource for UnusedSource {
+        fn download_shard_header(
+            &self,
+            _shard_id: ShardId,
+            _sync_hash: CryptoHash,
+            _handle: Arc<TaskHandle>,
+            _cancel: CancellationToken,
+        ) -> BoxFuture<Result<ShardStateSyncResponseHeader, near_chain::Error>> {
+            unreachable!()
+        }
+
+        fn download_shard_part(
+            &self,
+            _shard_id: ShardId,
+            _sync_hash: CryptoHash,
+            _part_id: u64,
+            _handle: Arc<TaskHandle>,
+            _cancel: CancellationToken,
+        ) -> BoxFuture<Result<Vec<u8>, near_chain::Error>> {
+            unreachable!()
+        }
+    }
+
+    #[test]
+    fn test_state_sync_providers_fallback_and_health() {
+        let clock = FakeClock::new(Utc::UNIX_EPOCH);
+        let providers = ["peers", "mirror1", "mirror2"]
+            .into_iter()
+            .map(|name| StateSyncProvider {
+                name: name.to_string(),
+                source: Arc::new(UnusedSource),
+                num_attempts: 2,
+            })
+            .collect();
+        let providers = StateSyncProviders::new(clock.clock(), providers, 2, Duration::minutes(1));
+
+        let selected = (0..8).map(|attempt| providers.select(attempt)).collect::<Vec<_>>();
+        assert_eq!(selected, [0, 0, 1, 1, 2, 2, 2, 2]);
+
+        // A success resets the count of consecutive failures.
+        providers.record_result(1, false);
+        providers.record_result(1, true);
+        providers.record_result(1, false);
+        assert_eq!(providers.select(2), 1);
+
+        // Unhealthy providers are skipped until the cooldown passes.
+        providers.record_result(1, false);
+        assert_eq!(providers.select(2), 2);
+        providers.record_result(2, false);
+        providers.record_result(2, false);
+        assert_eq!(providers.select(2), 0);
+        assert_eq!(providers.select(7), 0);
+        providers.record_result(0, false);
+        providers.record_result(0, false);
+        assert_eq!(providers.select(7), 2);
+        clock.advance(Duration::minutes(1));
+        assert_eq!(providers.select(2), 1);
+    }
+}
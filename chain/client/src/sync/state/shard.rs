@@ -1,5 +1,5 @@
 use super::downloader::StateSyncDownloader;
-use super::task_tracker::TaskTracker;
+use super::task_tracker::{TaskPriority, TaskTracker};
 use crate::metrics;
 use crate::sync::state::chain_requests::ChainFinalizationRequest;
 use futures::{StreamExt, TryStreamExt};
@@ -54,9 +54,12 @@ macro_rules! return_if_cancelled {
         }
     };
 }
+/// `shard_index` is the position of the shard among the shards being synced. The computations
+/// of the shards are prioritized by the height of the sync block and then by this index.
 pub(super) async fn run_state_sync_for_shard(
     store: Store,
     shard_id: ShardId,
+    shard_index: usize,
     sync_hash: CryptoHash,
     downloader: Arc<StateSyncDownloader>,
     runtime: Arc<dyn RuntimeAdapter>,
@@ -78,9 +81,11 @@ pub(super) async fn run_state_sync_for_shard(
         )?;
     let epoch_id = *block_header.epoch_id();
     let shard_uid = epoch_manager.shard_id_to_uid(shard_id, &epoch_id)?;
+    let priority = TaskPriority { sync_height: block_header.height(), shard_index };
     metrics::STATE_SYNC_PARTS_TOTAL
         .with_label_values(&[&shard_id.to_string()])
         .set(num_parts as i64);
+    metrics::STATE_SYNC_PARTS_APPLIED.with_label_values(&[&shard_id.to_string()]).set(0);
 
     return_if_cancelled!(cancel);
     *status.lock().unwrap() = ShardSyncStatus::StateDownloadParts;
@@ -145,6 +150,7 @@ pub(super) async fn run_state_sync_for_shard(
                 store,
                 runtime,
                 computation_task_tracker,
+                priority,
                 cancel,
                 sync_hash,
                 shard_id,
@@ -177,7 +183,9 @@ pub(super) async fn run_state_sync_for_shard(
     return_if_cancelled!(cancel);
     // Load memtrie.
     {
-        let handle = computation_task_tracker.get_handle(&format!("shard {}", shard_id)).await;
+        let handle = computation_task_tracker
+            .get_handle_with_priority(&format!("shard {}", shard_id), priority)
+            .await;
         let head_protocol_version = epoch_manager.get_epoch_protocol_version(&epoch_id)?;
         let shard_uids_pending_resharding = epoch_manager
             .get_shard_uids_pending_resharding(head_protocol_version, PROTOCOL_VERSION)?;
@@ -245,6 +253,7 @@ async fn apply_state_part(
     store: Store,
     runtime: Arc<dyn RuntimeAdapter>,
     computation_task_tracker: TaskTracker,
+    priority: TaskPriority,
     cancel: CancellationToken,
     sync_hash: CryptoHash,
     shard_id: ShardId,
@@ -254,9 +263,13 @@ async fn apply_state_part(
     epoch_id: EpochId,
 ) -> anyhow::Result<(), near_chain::Error> {
     return_if_cancelled!(cancel);
-    let handle =
-        computation_task_tracker.get_handle(&format!("shard {} part {}", shard_id, part_id)).await;
+    let handle = computation_task_tracker
+        .get_handle_with_priority(&format!("shard {} part {}", shard_id, part_id), priority)
+        .await;
     return_if_cancelled!(cancel);
+    let _timer = metrics::STATE_SYNC_APPLY_PART_DELAY
+        .with_label_values(&[&shard_id.to_string()])
+        .start_timer();
     handle.set_status("Loading part data from store");
     let data = store
         .get(
@@ -278,5 +291,6 @@ async fn apply_state_part(
         &data,
         &epoch_id,
     )?;
+    metrics::STATE_SYNC_PARTS_APPLIED.with_label_values(&[&shard_id.to_string()]).inc();
     Ok(())
 }
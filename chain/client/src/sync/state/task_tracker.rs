@@ -1,24 +1,70 @@
+use near_primitives::types::BlockHeight;
 use std::collections::BTreeMap;
 use std::sync::{Arc, Mutex};
-use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+use tokio::sync::oneshot;
+
+/// Priority of a task. When slots are scarce, they go to the waiting task with the lowest
+/// priority value, and to the earliest one among tasks with the same priority.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub(super) struct TaskPriority {
+    /// Height of the sync block. The state of an earlier epoch is needed sooner.
+    pub sync_height: BlockHeight,
+    /// Position of the shard among the shards being synced, as requested by the caller.
+    pub shard_index: usize,
+}
 
 /// Performs two functions:
 ///  - Limits the parallelism of tasks that call `get_handle`. Only up to `limit` handles can
-///    be obtained at the same time. Dropping a TaskHandle releases the slot.
+///    be obtained at the same time. Dropping a TaskHandle releases the slot, which goes to the
+///    waiting task with the highest priority.
 ///  - Keeps track of the status (a string) for each task handle that is active, for status
 ///    reporting.
 #[derive(Clone)]
 pub(super) struct TaskTracker {
-    semaphore: Arc<Semaphore>,
+    slots: Arc<Mutex<Slots>>,
     statuses: Arc<Mutex<BTreeMap<usize, String>>>,
     id_counter: Arc<std::sync::atomic::AtomicUsize>,
 }
 
+struct Slots {
+    available: usize,
+    /// Tasks waiting for a slot, ordered by priority and then by arrival.
+    waiting: BTreeMap<(TaskPriority, usize), oneshot::Sender<SlotPermit>>,
+}
+
+/// Occupies a slot until dropped.
+struct SlotPermit {
+    /// `None` once the slot was handed over or released.
+    slots: Option<Arc<Mutex<Slots>>>,
+}
+
+impl Drop for SlotPermit {
+    fn drop(&mut self) {
+        let Some(slots_arc) = self.slots.take() else {
+            return;
+        };
+        let mut slots = slots_arc.lock().unwrap();
+        while let Some((_, sender)) = slots.waiting.pop_first() {
+            match sender.send(SlotPermit { slots: Some(slots_arc.clone()) }) {
+                Ok(()) => return,
+                // The waiting task was dropped, try the next one.
+                Err(mut permit) => permit.slots = None,
+            }
+        }
+        slots.available += 1;
+    }
+}
+
+enum SlotAcquisition {
+    Acquired(SlotPermit),
+    Waiting(oneshot::Receiver<SlotPermit>),
+}
+
 impl TaskTracker {
     /// Creates a new TaskTracker with a specified concurrency limit.
     pub fn new(limit: usize) -> Self {
         TaskTracker {
-            semaphore: Arc::new(Semaphore::new(limit)),
+            slots: Arc::new(Mutex::new(Slots { available: limit, waiting: BTreeMap::new() })),
             statuses: Arc::new(Mutex::new(BTreeMap::new())),
             id_counter: Arc::new(std::sync::atomic::AtomicUsize::new(0)),
         }
@@ -27,13 +73,25 @@ impl TaskTracker {
     /// Asynchronously obtains a handle, waiting if necessary until a slot is available.
     /// "Asynchronously" means that when a handle is not available, the function does NOT block.
     /// The description will become part of the status string.
-    #[tracing::instrument(skip(self))]
     pub async fn get_handle(&self, description: &str) -> Arc<TaskHandle> {
-        // Acquire a permit from the semaphore.
-        let permit = self.semaphore.clone().acquire_owned().await.unwrap();
-        let description = description.to_string();
+        self.get_handle_with_priority(description, TaskPriority::default()).await
+    }
+
+    /// Same as [Self::get_handle], serving the tasks waiting for a slot by priority.
+    #[tracing::instrument(skip(self))]
+    pub async fn get_handle_with_priority(
+        &self,
+        description: &str,
+        priority: TaskPriority,
+    ) -> Arc<TaskHandle> {
         // Generate a unique ID for the handle.
         let id = self.id_counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        let permit = match self.acquire_slot(id, priority) {
+            SlotAcquisition::Acquired(permit) => permit,
+            // The sender is only dropped after sending a permit.
+            SlotAcquisition::Waiting(receiver) => receiver.await.unwrap(),
+        };
+        let description = description.to_string();
         {
             // Initialize the status for this handle.
             let mut statuses = self.statuses.lock().unwrap();
@@ -48,6 +106,18 @@ impl TaskTracker {
         .into()
     }
 
+    /// Takes a free slot, unless other tasks are already waiting for one.
+    fn acquire_slot(&self, id: usize, priority: TaskPriority) -> SlotAcquisition {
+        let mut slots = self.slots.lock().unwrap();
+        if slots.available > 0 && slots.waiting.is_empty() {
+            slots.available -= 1;
+            return SlotAcquisition::Acquired(SlotPermit { slots: Some(self.slots.clone()) });
+        }
+        let (sender, receiver) = oneshot::channel();
+        slots.waiting.insert((priority, id), sender);
+        SlotAcquisition::Waiting(receiver)
+    }
+
     /// Returns the statuses of all active tasks.
     pub fn statuses(&self) -> Vec<String> {
         self.statuses.lock().unwrap().values().cloned().collect()
@@ -60,7 +130,7 @@ pub(super) struct TaskHandle {
     id: usize,
     task_description: String,
     statuses: Arc<Mutex<BTreeMap<usize, String>>>,
-    _permit: OwnedSemaphorePermit, // Keeps the slot occupied in the semaphore.
+    _permit: SlotPermit, // Keeps the slot occupied.
 }
 
 impl TaskHandle {
@@ -79,3 +149,40 @@ impl Drop for TaskHandle {
         statuses.remove(&self.id);
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SlotAcquisition, TaskPriority, TaskTracker};
+
+    fn priority(sync_height: u64, shard_index: usize) -> TaskPriority {
+        TaskPriority { sync_height, shard_index }
+    }
+
+    #[test]
+    fn test_task_tracker_serves_by_priority() {
+        let tracker = TaskTracker::new(1);
+        let SlotAcquisition::Acquired(first) = tracker.acquire_slot(0, priority(10, 0)) else {
+            panic!("a slot must be available");
+        };
+        let acquisitions = [(1, priority(10, 2)), (2, priority(10, 1)), (3, priority(5, 3))].map(
+            |(id, priority)| match tracker.acquire_slot(id, priority) {
+                SlotAcquisition::Acquired(_) => panic!("no slot must be available"),
+                SlotAcquisition::Waiting(receiver) => receiver,
+            },
+        );
+        let [mut shard_2, mut shard_1, mut earlier_sync] = acquisitions;
+
+        // The waiting task of the earlier sync goes first, then shards in order.
+        drop(first);
+        let permit = earlier_sync.try_recv().unwrap();
+        assert!(shard_1.try_recv().is_err());
+        drop(permit);
+        let permit = shard_1.try_recv().unwrap();
+        assert!(shard_2.try_recv().is_err());
+
+        // Tasks which stopped waiting are skipped, and the slot is freed.
+        drop(shard_2);
+        drop(permit);
+        assert!(matches!(tracker.acquire_slot(4, priority(20, 0)), SlotAcquisition::Acquired(_)));
+    }
+}
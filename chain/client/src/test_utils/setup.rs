@@ -16,7 +16,7 @@ use actix::{Actor, Addr, Context};
 use futures::{future, FutureExt};
 use near_async::actix::AddrWithAutoSpanContextExt;
 use near_async::actix_wrapper::{spawn_actix_actor, ActixWrapper};
-use near_async::futures::ActixFutureSpawner;
+use near_async::futures::{ActixFutureSpawner, InlineAsyncComputationSpawnerForTest};
 use near_async::messaging::{
     noop, CanSend, IntoMultiSender, IntoSender, LateBoundSender, SendAsync, Sender,
 };
@@ -30,6 +30,7 @@ use near_chain::types::{ChainConfig, RuntimeAdapter};
 use near_chain::{Chain, ChainGenesis, DoomslugThresholdMode};
 use near_chain_configs::{
     ChunkDistributionNetworkConfig, ClientConfig, MutableConfigValue, ReshardingConfig,
+    SecondaryIndexesConfig,
 };
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::client::ShardsManagerResponse;
@@ -40,8 +41,8 @@ use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
 use near_epoch_manager::EpochManagerAdapter;
 use near_network::client::{
     AnnounceAccountRequest, BlockApproval, BlockHeadersRequest, BlockHeadersResponse, BlockRequest,
-    BlockResponse, ChunkEndorsementMessage, SetNetworkInfo, StateRequestHeader, StateRequestPart,
-    StateResponseReceived,
+    BlockResponse, ChunkEndorsementMessage, ChunkSuppressionMessage, SetNetworkInfo,
+    StateRequestHeader, StateRequestPart, StateResponseReceived,
 };
 use near_network::shards_manager::ShardsManagerRequestFromNetwork;
 use near_network::state_witness::{
@@ -264,11 +265,15 @@ pub fn setup_only_view(
         doomslug_threshold_mode,
         ChainConfig {
             save_trie_changes: true,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
             background_migration_threads: 1,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            skip_state_transition_data_for_shards: vec![],
         },
         None,
         Arc::new(RayonAsyncComputationSpawner),
@@ -456,6 +461,7 @@ fn process_peer_manager_message_default(
                         }),
                         tracked_shards: vec![0, 1, 2, 3].into_iter().map(ShardId::new).collect(),
                         archival: true,
+                        earliest_block_height: None,
                     },
                 },
                 received_bytes_per_sec: 0,
@@ -622,8 +628,12 @@ fn process_peer_manager_message_default(
                     connectors[i]
                         .view_client_actor
                         .send(
-                            StateRequestHeader { shard_id: *shard_id, sync_hash: *sync_hash }
-                                .with_span_context(),
+                            StateRequestHeader {
+                                shard_id: *shard_id,
+                                sync_hash: *sync_hash,
+                                batched_proofs: true,
+                            }
+                            .with_span_context(),
                         )
                         .then(move |response| {
                             let response = response.unwrap();
@@ -756,6 +766,15 @@ fn process_peer_manager_message_default(
                 }
             }
         }
+        NetworkRequests::ChunkSuppression(account, suppression) => {
+            for (i, name) in validators.iter().enumerate() {
+                if name == account {
+                    connectors[i]
+                        .client_actor
+                        .do_send(ChunkSuppressionMessage(suppression.clone()).with_span_context());
+                }
+            }
+        }
         NetworkRequests::PartialEncodedStateWitness(partial_witnesses) => {
             for (account, partial_witness) in partial_witnesses {
                 for (i, name) in validators.iter().enumerate() {
@@ -1038,6 +1057,17 @@ pub fn setup_no_network_with_validity_period(
                         actix::spawn(future);
                     }
                 }
+                PeerManagerMessageRequest::NetworkRequests(NetworkRequests::ChunkSuppression(
+                    account_id,
+                    suppression,
+                )) => {
+                    if account_id == &my_account_id {
+                        let future = client.send_async(
+                            ChunkSuppressionMessage(suppression.clone()).with_span_context(),
+                        );
+                        actix::spawn(future);
+                    }
+                }
                 _ => {}
             };
             PeerManagerMessageResponse::NetworkResponses(NetworkResponses::NoResponse)
@@ -1081,6 +1111,7 @@ pub fn setup_client_with_runtime(
         rng_seed,
         snapshot_callbacks,
         Arc::new(RayonAsyncComputationSpawner),
+        Arc::new(RayonAsyncComputationSpawner),
         partial_witness_adapter,
         resharding_sender,
         Arc::new(ActixFutureSpawner),
@@ -1117,11 +1148,15 @@ pub fn setup_synchronous_shards_manager(
         DoomslugThresholdMode::TwoThirds, // irrelevant
         ChainConfig {
             save_trie_changes: true,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
             background_migration_threads: 1,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            skip_state_transition_data_for_shards: vec![],
         }, // irrelevant
         None,
         Arc::new(RayonAsyncComputationSpawner),
@@ -1144,6 +1179,7 @@ pub fn setup_synchronous_shards_manager(
         chain_head,
         chain_header_head,
         Duration::hours(1),
+        Arc::new(InlineAsyncComputationSpawnerForTest),
     );
     SynchronousShardsManagerAdapter::new(shards_manager)
 }
@@ -801,7 +801,8 @@ impl TestEnv {
             ProcessTxResponse::NoResponse
             | ProcessTxResponse::RequestRouted
             | ProcessTxResponse::ValidTx => (),
-            ProcessTxResponse::InvalidTx(e) => return Err(e),
+            ProcessTxResponse::InvalidTx(e)
+            | ProcessTxResponse::SignerShardCongested { error: e, .. } => return Err(e),
             ProcessTxResponse::DoesNotTrackShard => panic!("test setup is buggy"),
         }
         let max_iters = 100;
@@ -11,21 +11,24 @@ use near_async::messaging::{Actor, CanSend, Handler};
 use near_async::time::{Clock, Duration, Instant};
 use near_chain::types::{RuntimeAdapter, Tip};
 use near_chain::{
-    get_epoch_block_producers_view, Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode,
-    MerkleProofAccess,
+    get_epoch_block_producers_view, AccountIndexScan, Chain, ChainGenesis, ChainStoreAccess,
+    DoomslugThresholdMode, MerkleProofAccess,
 };
 
 use near_chain_configs::{ClientConfig, MutableValidatorSigner, ProtocolConfigView};
 use near_chain_primitives::error::EpochErrorResultToChainError;
 use near_client_primitives::types::{
-    Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
+    AccountReceiptsResponse, EffectiveEpochConfig, Error, GetAccountReceipts,
+    GetAccountReceiptsError, GetBlock, GetBlockByTimestamp, GetBlockError, GetBlockProof,
+    GetBlockProofError, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunkError,
+    GetEpochConfig, GetEpochConfigError, GetExecutionOutcome, GetExecutionOutcomeError,
     GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetMaintenanceWindows,
     GetMaintenanceWindowsError, GetNextLightClientBlockError, GetProtocolConfig,
-    GetProtocolConfigError, GetReceipt, GetReceiptError, GetSplitStorageInfo,
-    GetSplitStorageInfoError, GetStateChangesError, GetStateChangesWithCauseInBlock,
+    GetProtocolConfigError, GetReceipt, GetReceiptError, GetRuntimeConfigChanges,
+    GetSecondaryIndex, GetSecondaryIndexError, GetSplitStorageInfo, GetSplitStorageInfoError,
+    GetStateChangesError, GetStateChangesWithCauseInBlock,
     GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfoError, Query, QueryError,
-    TxStatus, TxStatusError,
+    RuntimeConfigChanges, SecondaryIndexResponse, TxStatus, TxStatusError,
 };
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
@@ -37,6 +40,7 @@ use near_network::types::{
     NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest, ReasonForBan,
     StateResponseInfo, StateResponseInfoV2,
 };
+use near_parameters::diff_runtime_configs;
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::epoch_info::EpochInfo;
@@ -47,6 +51,7 @@ use near_primitives::receipt::Receipt;
 use near_primitives::sharding::ShardChunk;
 use near_primitives::state_sync::{
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV3,
+    ShardStateSyncResponseV4,
 };
 use near_primitives::stateless_validation::ChunkProductionKey;
 use near_primitives::transaction::SignedTransaction;
@@ -55,13 +60,15 @@ use near_primitives::types::{
     ShardId, SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
 };
 use near_primitives::validator_signer::ValidatorSigner;
+use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView,
-    LightClientBlockView, MaintenanceWindowsView, QueryRequest, QueryResponse, ReceiptView,
-    SignedTransactionView, SplitStorageInfoView, StateChangesKindsView, StateChangesView,
-    TxExecutionStatus, TxStatusView,
+    AccountReceiptDirection, AccountReceiptView, BlockView, ChunkView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, ExecutionStatusView, FinalExecutionOutcomeView,
+    FinalExecutionOutcomeViewEnum, FinalExecutionStatus, GasPriceView, LightClientBlockView,
+    MaintenanceWindowsView, QueryRequest, QueryResponse, QueryResponseKind, ReceiptView,
+    SecondaryIndexEntryView, SecondaryIndexQuery, SignedTransactionView, SplitStorageInfoView,
+    StateChangesKindsView, StateChangesView, TxExecutionStatus, TxStatusView,
 };
 use near_store::flat::{FlatStorageReadyStatus, FlatStorageStatus};
 use near_store::{DBCol, COLD_HEAD_KEY, FINAL_HEAD_KEY, HEAD_KEY};
@@ -134,7 +141,13 @@ impl ViewClientActorInner {
         config: ClientConfig,
         adv: crate::adversarial::Controls,
     ) -> Addr<ViewClientActor> {
-        SyncArbiter::start(config.view_client_threads, move || {
+        let rpc_thread_pool = config.thread_pools.rpc.clone();
+        let num_threads = rpc_thread_pool.num_threads.unwrap_or(config.view_client_threads);
+        SyncArbiter::start(num_threads, move || {
+            // The factory is called once on each of the threads.
+            if let Some(cpu_set) = &rpc_thread_pool.cpu_set {
+                near_performance_metrics::affinity::pin_current_thread("view_client", cpu_set);
+            }
             let view_client_actor = ViewClientActorInner::new(
                 clock.clone(),
                 validator.clone(),
@@ -348,6 +361,35 @@ impl ViewClientActorInner {
         Ok(windows)
     }
 
+    /// Fills in the last use of the access keys returned by a query.
+    fn add_access_key_usage(
+        &self,
+        request: &QueryRequest,
+        kind: &mut QueryResponseKind,
+    ) -> Result<(), near_chain::Error> {
+        let chain_store = self.chain.chain_store();
+        match (request, kind) {
+            (
+                QueryRequest::ViewAccessKey { account_id, public_key },
+                QueryResponseKind::AccessKey(access_key),
+            ) => {
+                access_key.last_used_block_height =
+                    chain_store.get_access_key_last_used_height(account_id, public_key)?;
+            }
+            (
+                QueryRequest::ViewAccessKeyList { account_id },
+                QueryResponseKind::AccessKeyList(access_keys),
+            ) => {
+                for key in &mut access_keys.keys {
+                    key.access_key.last_used_block_height =
+                        chain_store.get_access_key_last_used_height(account_id, &key.public_key)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
     fn handle_query(&mut self, msg: Query) -> Result<QueryResponse, QueryError> {
         let header = self.get_block_header_by_reference(&msg.block_reference);
         let header = match header {
@@ -364,10 +406,12 @@ impl ViewClientActorInner {
 
         let account_id = match &msg.request {
             QueryRequest::ViewAccount { account_id, .. } => account_id,
+            QueryRequest::ViewAccountBalance { account_id } => account_id,
             QueryRequest::ViewState { account_id, .. } => account_id,
             QueryRequest::ViewAccessKey { account_id, .. } => account_id,
             QueryRequest::ViewAccessKeyList { account_id, .. } => account_id,
             QueryRequest::CallFunction { account_id, .. } => account_id,
+            QueryRequest::SimulateFunctionCall { account_id, .. } => account_id,
             QueryRequest::ViewCode { account_id, .. } => account_id,
         };
         let shard_id = self
@@ -413,7 +457,14 @@ impl ViewClientActorInner {
             header.epoch_id(),
             &msg.request,
         ) {
-            Ok(query_response) => Ok(query_response),
+            Ok(mut query_response) => {
+                if self.config.save_access_key_usage {
+                    self.add_access_key_usage(&msg.request, &mut query_response.kind).map_err(
+                        |err| QueryError::InternalError { error_message: err.to_string() },
+                    )?;
+                }
+                Ok(query_response)
+            }
             Err(query_error) => Err(match query_error {
                 near_chain::near_chain_primitives::error::QueryError::InternalError {
                     error_message,
@@ -457,6 +508,11 @@ impl ViewClientActorInner {
                     block_height,
                     block_hash,
                 },
+                near_chain::near_chain_primitives::error::QueryError::InvalidStateOverride {
+                    error_message,
+                    block_height,
+                    block_hash,
+                } => QueryError::InvalidStateOverride { error_message, block_height, block_hash },
             }),
         }
     }
@@ -736,6 +792,24 @@ impl Handler<GetBlock> for ViewClientActorInner {
     }
 }
 
+impl Handler<GetBlockByTimestamp> for ViewClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: GetBlockByTimestamp) -> Result<BlockView, GetBlockError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetBlockByTimestamp"])
+            .start_timer();
+        let height = self
+            .chain
+            .chain_store()
+            .get_block_height_by_timestamp(msg.timestamp_nanosec)?
+            .ok_or_else(|| GetBlockError::UnknownBlock {
+                error_message: format!("no block at or before timestamp {}", msg.timestamp_nanosec),
+            })?;
+        self.handle(GetBlock(BlockReference::BlockId(BlockId::Height(height))))
+    }
+}
+
 impl Handler<GetBlockWithMerkleTree> for ViewClientActorInner {
     #[perf]
     fn handle(
@@ -1194,6 +1268,175 @@ impl Handler<GetReceipt> for ViewClientActorInner {
     }
 }
 
+impl Handler<GetAccountReceipts> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetAccountReceipts,
+    ) -> Result<AccountReceiptsResponse, GetAccountReceiptsError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetAccountReceipts"])
+            .start_timer();
+        if !self.config.save_account_receipts {
+            return Err(GetAccountReceiptsError::IndexDisabled);
+        }
+        let from_height = self.chain.head()?.height.saturating_sub(msg.block_window);
+        let direction = msg.direction;
+        // Fetch one more entry than requested to know whether there is a next page.
+        let AccountIndexScan { mut entries, skipped_until } =
+            self.chain.chain_store().get_account_receipts(
+                &msg.account_id,
+                from_height,
+                msg.after,
+                msg.limit + 1,
+                |entry| match direction {
+                    None => true,
+                    Some(AccountReceiptDirection::Incoming) => entry.incoming,
+                    Some(AccountReceiptDirection::Outgoing) => entry.outgoing,
+                },
+            )?;
+        let next = if entries.len() > msg.limit {
+            entries.truncate(msg.limit);
+            entries.last().map(|(height, receipt_id, _)| (*height, *receipt_id))
+        } else {
+            // The scan may have stopped before the end of the index if the filter skipped
+            // too many entries, in which case the next page continues from there.
+            skipped_until
+        };
+        let mut receipts = Vec::with_capacity(entries.len());
+        for (block_height, receipt_id, entry) in entries {
+            let outcome = if msg.include_outcomes {
+                match self.chain.get_execution_outcome(&receipt_id) {
+                    Ok(outcome) => Some(outcome.into()),
+                    Err(near_chain::Error::DBNotFoundErr(_)) => None,
+                    Err(err) => return Err(err.into()),
+                }
+            } else {
+                None
+            };
+            receipts.push(AccountReceiptView {
+                receipt_id,
+                block_hash: entry.block_hash,
+                block_height,
+                shard_id: entry.shard_id,
+                incoming: entry.incoming,
+                outgoing: entry.outgoing,
+                outcome,
+            });
+        }
+        Ok(AccountReceiptsResponse { receipts, next })
+    }
+}
+
+impl Handler<GetSecondaryIndex> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetSecondaryIndex,
+    ) -> Result<SecondaryIndexResponse, GetSecondaryIndexError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetSecondaryIndex"])
+            .start_timer();
+        let config = self.config.secondary_indexes;
+        let chain_store = self.chain.chain_store();
+        // Set if a filtered scan stopped before the end of the index.
+        let mut skipped_until = None;
+        // Fetch one more entry than requested to know whether there is a next page.
+        let mut entries = match msg.query {
+            SecondaryIndexQuery::TransactionsByAccount { account_id } => {
+                if !config.transactions_by_account {
+                    return Err(GetSecondaryIndexError::IndexDisabled(
+                        "transactions_by_account".to_string(),
+                    ));
+                }
+                chain_store
+                    .get_transactions_by_account(&account_id, 0, msg.after, msg.limit + 1)?
+                    .into_iter()
+                    .map(|(block_height, hash, entry)| {
+                        let view = SecondaryIndexEntryView::Transaction {
+                            hash,
+                            block_hash: entry.block_hash,
+                            block_height,
+                            shard_id: entry.shard_id,
+                        };
+                        ((block_height, hash), view)
+                    })
+                    .collect::<Vec<_>>()
+            }
+            SecondaryIndexQuery::ReceiptsByReceiver { account_id } => {
+                // The receipts executed by the account are the incoming entries of the index
+                // of the account receipts.
+                if !self.config.save_account_receipts {
+                    return Err(GetSecondaryIndexError::IndexDisabled(
+                        "save_account_receipts".to_string(),
+                    ));
+                }
+                let scan = chain_store.get_account_receipts(
+                    &account_id,
+                    0,
+                    msg.after,
+                    msg.limit + 1,
+                    |entry| entry.incoming,
+                )?;
+                skipped_until = scan.skipped_until;
+                scan.entries
+                    .into_iter()
+                    .map(|(block_height, receipt_id, entry)| {
+                        let view = SecondaryIndexEntryView::Receipt {
+                            receipt_id,
+                            block_hash: entry.block_hash,
+                            block_height,
+                            shard_id: entry.shard_id,
+                        };
+                        ((block_height, receipt_id), view)
+                    })
+                    .collect::<Vec<_>>()
+            }
+            SecondaryIndexQuery::BlocksByTimestamp { from_timestamp_nanosec } => {
+                if !config.blocks_by_timestamp {
+                    return Err(GetSecondaryIndexError::IndexDisabled(
+                        "blocks_by_timestamp".to_string(),
+                    ));
+                }
+                // All the processed blocks are indexed, skip the ones which are not on the
+                // canonical chain.
+                let is_canonical = |block_hash: &CryptoHash, height: BlockHeight| {
+                    chain_store
+                        .get_block_hash_by_height(height)
+                        .is_ok_and(|hash| &hash == block_hash)
+                };
+                chain_store
+                    .get_blocks_by_timestamp(
+                        from_timestamp_nanosec,
+                        msg.after,
+                        msg.limit + 1,
+                        is_canonical,
+                    )?
+                    .into_iter()
+                    .map(|(timestamp_nanosec, block_hash, block_height)| {
+                        let view = SecondaryIndexEntryView::Block {
+                            block_hash,
+                            block_height,
+                            timestamp_nanosec,
+                        };
+                        ((timestamp_nanosec, block_hash), view)
+                    })
+                    .collect::<Vec<_>>()
+            }
+        };
+        let next = if entries.len() > msg.limit {
+            entries.truncate(msg.limit);
+            entries.last().map(|(position, _)| *position)
+        } else {
+            skipped_until
+        };
+        let entries = entries.into_iter().map(|(_, view)| view).collect();
+        Ok(SecondaryIndexResponse { entries, next })
+    }
+}
+
 impl Handler<GetBlockProof> for ViewClientActorInner {
     #[perf]
     fn handle(&mut self, msg: GetBlockProof) -> Result<GetBlockProofResponse, GetBlockProofError> {
@@ -1233,6 +1476,59 @@ impl Handler<GetProtocolConfig> for ViewClientActorInner {
     }
 }
 
+impl Handler<GetRuntimeConfigChanges> for ViewClientActorInner {
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: GetRuntimeConfigChanges,
+    ) -> Result<RuntimeConfigChanges, GetProtocolConfigError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+            .with_label_values(&["GetRuntimeConfigChanges"])
+            .start_timer();
+        let header = match self.get_block_header_by_reference(&msg.0)? {
+            None => {
+                return Err(GetProtocolConfigError::UnknownBlock("EarliestAvailable".to_string()))
+            }
+            Some(header) => header,
+        };
+        let current_protocol_version =
+            self.epoch_manager.get_epoch_protocol_version(header.epoch_id()).into_chain_error()?;
+        let next_protocol_version = PROTOCOL_VERSION;
+        let current_config = self.runtime.get_runtime_config(current_protocol_version)?;
+        let next_config = self.runtime.get_runtime_config(next_protocol_version)?;
+        Ok(RuntimeConfigChanges {
+            current_protocol_version,
+            next_protocol_version,
+            changes: diff_runtime_configs(&current_config, &next_config),
+        })
+    }
+}
+
+impl Handler<GetEpochConfig> for ViewClientActorInner {
+    #[perf]
+    fn handle(&mut self, msg: GetEpochConfig) -> Result<EffectiveEpochConfig, GetEpochConfigError> {
+        tracing::debug!(target: "client", ?msg);
+        let _timer =
+            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetEpochConfig"]).start_timer();
+        let epoch_id = match msg.epoch_reference {
+            EpochReference::EpochId(epoch_id) => epoch_id,
+            EpochReference::BlockId(BlockId::Hash(hash)) => {
+                *self.chain.get_block_header(&hash)?.epoch_id()
+            }
+            EpochReference::BlockId(BlockId::Height(height)) => {
+                *self.chain.get_block_header_by_height(height)?.epoch_id()
+            }
+            // use header head because this is latest from the perspective of epoch manager
+            EpochReference::Latest => self.chain.header_head()?.epoch_id,
+        };
+        let protocol_version =
+            self.epoch_manager.get_epoch_protocol_version(&epoch_id).into_chain_error()?;
+        let epoch_config = self.epoch_manager.get_epoch_config(&epoch_id).into_chain_error()?;
+        Ok(EffectiveEpochConfig { epoch_id, protocol_version, epoch_config })
+    }
+}
+
 #[cfg(feature = "test_features")]
 use crate::NetworkAdversarialMessage;
 
@@ -1350,7 +1646,7 @@ impl Handler<StateRequestHeader> for ViewClientActorInner {
         let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
             .with_label_values(&["StateRequestHeader"])
             .start_timer();
-        let StateRequestHeader { shard_id, sync_hash } = msg;
+        let StateRequestHeader { shard_id, sync_hash, batched_proofs } = msg;
         if self.throttle_state_sync_request() {
             metrics::STATE_SYNC_REQUESTS_THROTTLED_TOTAL.inc();
             return None;
@@ -1381,21 +1677,37 @@ impl Handler<StateRequestHeader> for ViewClientActorInner {
         };
         let state_response = match header {
             Some(header) => {
-                let header = match header {
-                    ShardStateSyncResponseHeader::V2(inner) => inner,
-                    _ => {
+                let can_generate = self.has_state_snapshot(&sync_hash, shard_id).is_ok();
+                match header {
+                    // Only the peers which advertised that they can decode headers with batched
+                    // proofs get them.
+                    ShardStateSyncResponseHeader::V2(header) if batched_proofs => {
+                        let header = match self.chain.batch_state_response_header_proofs(header) {
+                            Ok(header) => header,
+                            Err(err) => {
+                                error!(target: "sync", ?err, "Cannot batch state sync header proofs");
+                                return None;
+                            }
+                        };
+                        ShardStateSyncResponse::V4(ShardStateSyncResponseV4 {
+                            header: Some(header),
+                            part: None,
+                            can_generate,
+                        })
+                    }
+                    ShardStateSyncResponseHeader::V2(header) => {
+                        ShardStateSyncResponse::V3(ShardStateSyncResponseV3 {
+                            header: Some(header),
+                            part: None,
+                            cached_parts: None,
+                            can_generate,
+                        })
+                    }
+                    ShardStateSyncResponseHeader::V1(_) | ShardStateSyncResponseHeader::V3(_) => {
                         tracing::error!(target: "sync", ?sync_hash, ?shard_id, "Invalid state sync header format");
                         return None;
                     }
-                };
-
-                let can_generate = self.has_state_snapshot(&sync_hash, shard_id).is_ok();
-                ShardStateSyncResponse::V3(ShardStateSyncResponseV3 {
-                    header: Some(header),
-                    part: None,
-                    cached_parts: None,
-                    can_generate,
-                })
+                }
             }
             None => ShardStateSyncResponse::V3(ShardStateSyncResponseV3 {
                 header: None,
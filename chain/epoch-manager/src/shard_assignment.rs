@@ -51,20 +51,35 @@ impl From<ValidatorsFirstShardAssignmentItem> for StakeFirstShardAssignmentItem
     }
 }
 
+/// `min_validators_per_shard` is indexed by shard index.
 fn assign_to_satisfy_shards_inner<T: HasStake + Eq, I: Iterator<Item = (usize, T)>>(
     shard_assignment: &mut ValidatorsFirstShardAssignment,
     result: &mut Vec<Vec<T>>,
     cp_iter: &mut I,
-    min_validators_per_shard: usize,
+    min_validators_per_shard: &[usize],
 ) {
     let mut buffer = Vec::with_capacity(shard_assignment.len());
+    // Shards which already have the minimum number of chunk producers. Taken
+    // out of `shard_assignment` so that they don't shadow the shards which
+    // still need producers but already have more of them, which is possible
+    // if the minimum differs between shards.
+    let mut satisfied = Vec::with_capacity(shard_assignment.len());
     // Stores (shard_index, cp_index) meaning that cp at cp_index has already been
     // added to shard shard_index.  Used to make sure we don’t add a cp to the same
     // shard multiple times.
-    let seen_capacity = result.len() * min_validators_per_shard;
+    let seen_capacity = min_validators_per_shard.iter().sum();
     let mut seen = HashSet::<(ShardIndex, usize)>::with_capacity(seen_capacity);
 
-    while shard_assignment.peek().unwrap().validators < min_validators_per_shard {
+    loop {
+        while let Some(top) = shard_assignment.peek_mut() {
+            if top.validators < min_validators_per_shard[top.shard_index] {
+                break;
+            }
+            satisfied.push(PeekMut::pop(top));
+        }
+        if shard_assignment.len() == 0 {
+            break;
+        }
         // cp_iter is an infinite cycle iterator so getting next value can never
         // fail.  cp_index is index of each element in the iterator but the
         // indexing is done before cycling thus the same cp always gets the same
@@ -79,12 +94,10 @@ fn assign_to_satisfy_shards_inner<T: HasStake + Eq, I: Iterator<Item = (usize, T
                     // producer.  Skip it and move to another producer.
                     break;
                 }
-                Some(top) if top.validators >= min_validators_per_shard => {
-                    // `shard_assignment` is sorted by number of chunk producers,
-                    // thus all remaining shards have min_validators_per_shard
-                    // producers already assigned to them.  Don’t assign current
-                    // one to any shard and move to next cp.
-                    break;
+                Some(top) if top.validators >= min_validators_per_shard[top.shard_index] => {
+                    // The shard has enough producers already.  Set it aside
+                    // and try assigning the producer to the next shard.
+                    satisfied.push(PeekMut::pop(top));
                 }
                 Some(mut top) if seen.insert((top.shard_index, cp_index)) => {
                     // Chunk producer is not yet assigned to the shard and the
@@ -108,6 +121,7 @@ fn assign_to_satisfy_shards_inner<T: HasStake + Eq, I: Iterator<Item = (usize, T
         // them) need to be put back into the heap.
         shard_assignment.extend(buffer.drain(..));
     }
+    shard_assignment.extend(satisfied);
 }
 
 /// Assigns validators to shards to satisfy `min_validators_per_shard`
@@ -116,7 +130,7 @@ fn assign_to_satisfy_shards_inner<T: HasStake + Eq, I: Iterator<Item = (usize, T
 fn assign_to_satisfy_shards<T: HasStake + Eq + Clone>(
     chunk_producers: Vec<T>,
     num_shards: NumShards,
-    min_validators_per_shard: usize,
+    min_validators_per_shard: &[usize],
 ) -> Vec<Vec<T>> {
     let mut result: Vec<Vec<T>> = (0..num_shards).map(|_| Vec::new()).collect();
 
@@ -195,7 +209,7 @@ struct ShardSetItem {
 fn assign_to_balance_shards(
     chunk_producers: Vec<ValidatorStake>,
     num_shards: NumShards,
-    min_validators_per_shard: usize,
+    min_validators_per_shard: &[usize],
     shard_assignment_changes_limit: usize,
     rng_seed: RngSeed,
     prev_chunk_producers_assignment: Option<Vec<Vec<ValidatorStake>>>,
@@ -231,16 +245,35 @@ fn assign_to_balance_shards(
     let rng = &mut EpochInfo::shard_assignment_rng(&rng_seed);
     let new_assignments_hard_limit = chunk_producers.len().max(shard_assignment_changes_limit);
     loop {
+        // The shard to move a chunk producer to: the one with the fewest chunk
+        // producers among the shards below their minimum, if any, otherwise
+        // the one with the fewest chunk producers overall.
+        let deficient_shard = shard_set.iter().find(|item| {
+            item.shard_chunk_producer_num < min_validators_per_shard[item.shard_index]
+        });
+        let is_minimal_num_satisfied = deficient_shard.is_none();
         let ShardSetItem {
             shard_chunk_producer_num: minimal_shard_validators_num,
             shard_index: minimal_shard,
-        } = *shard_set.first().unwrap();
-        let ShardSetItem {
+        } = *deficient_shard.unwrap_or_else(|| shard_set.first().unwrap());
+        // The shard to move a chunk producer from: the one with the most chunk
+        // producers among the shards above their minimum.
+        let Some(&ShardSetItem {
             shard_chunk_producer_num: maximal_shard_validators_num,
             shard_index: maximal_shard,
-        } = *shard_set.last().unwrap();
-        let is_minimal_num_satisfied = minimal_shard_validators_num >= min_validators_per_shard;
-        let is_balanced = maximal_shard_validators_num - minimal_shard_validators_num <= 1;
+        }) = shard_set.iter().rev().find(|item| {
+            item.shard_chunk_producer_num > min_validators_per_shard[item.shard_index]
+        })
+        else {
+            assert!(
+                is_minimal_num_satisfied,
+                "{num_chunk_producers} chunk producers are not enough to satisfy \
+                 minimal numbers {min_validators_per_shard:?} for {num_shards} shards",
+            );
+            break;
+        };
+        let is_balanced =
+            maximal_shard_validators_num.saturating_sub(minimal_shard_validators_num) <= 1;
 
         if is_minimal_num_satisfied
             && (is_balanced || new_assignments >= shard_assignment_changes_limit)
@@ -255,17 +288,19 @@ fn assign_to_balance_shards(
              new shard twice which shouldn't happen."
         );
         assert_ne!(
-            minimal_shard,
-            maximal_shard,
+            minimal_shard, maximal_shard,
             "Minimal shard and maximal shard are the same: {minimal_shard}. \
-            Either {} chunk producers are not enough to satisfy minimal number \
-            {min_validators_per_shard} for {num_shards} shards, or we try to \
-            balance the shard with itself.",
-            chunk_producers.len(),
+            We try to balance the shard with itself.",
         );
 
-        let minimal_shard = shard_set.pop_first().unwrap().shard_index;
-        let maximal_shard = shard_set.pop_last().unwrap().shard_index;
+        shard_set.remove(&ShardSetItem {
+            shard_chunk_producer_num: minimal_shard_validators_num,
+            shard_index: minimal_shard,
+        });
+        shard_set.remove(&ShardSetItem {
+            shard_chunk_producer_num: maximal_shard_validators_num,
+            shard_index: maximal_shard,
+        });
         let validator_pos = rng.gen_range(0..chunk_producer_assignment[maximal_shard].len());
         let validator_index = chunk_producer_assignment[maximal_shard].swap_remove(validator_pos);
         chunk_producer_assignment[minimal_shard].push(validator_index);
@@ -292,7 +327,8 @@ fn assign_to_balance_shards(
 /// list of chunk producers assigned to the i-th shard, sorted by stake.
 ///
 /// This function guarantees that, in order of priority:
-/// * every shard has at least `min_validators_per_shard` assigned to it;
+/// * every shard has at least `min_validators_per_shard` assigned to it,
+/// where `min_validators_per_shard` is indexed by shard index;
 /// * chunk producer repeats are completely avoided if possible;
 /// * if `prev_chunk_producers_assignment` is provided, it minimizes the need
 /// for chunk producers there to change shards;
@@ -304,23 +340,25 @@ fn assign_to_balance_shards(
 /// order by stake and `prev_chunk_producers_assignment` corresponds to the
 /// same number of shards.
 ///
-/// Returns error if `chunk_producers.len() < min_validators_per_shard`.
+/// Returns error if `chunk_producers.len()` is less than the minimum of
+/// some shard.
 pub(crate) fn assign_chunk_producers_to_shards(
     chunk_producers: Vec<ValidatorStake>,
     num_shards: NumShards,
-    min_validators_per_shard: usize,
+    min_validators_per_shard: &[usize],
     shard_assignment_changes_limit: usize,
     rng_seed: RngSeed,
     prev_chunk_producers_assignment: Option<Vec<Vec<ValidatorStake>>>,
 ) -> Result<Vec<Vec<ValidatorStake>>, NotEnoughValidators> {
+    assert_eq!(min_validators_per_shard.len(), num_shards as usize);
     // If there's not enough chunk producers to fill up a single shard there’s
     // nothing we can do. Return with an error.
     let num_chunk_producers = chunk_producers.len();
-    if num_chunk_producers < min_validators_per_shard {
+    if min_validators_per_shard.iter().any(|&min| num_chunk_producers < min) {
         return Err(NotEnoughValidators);
     }
 
-    let result = if chunk_producers.len() < min_validators_per_shard * (num_shards as usize) {
+    let result = if chunk_producers.len() < min_validators_per_shard.iter().sum() {
         // We don't have enough chunk producers to allow assignment without
         // repeats.
         // Assign validators to satisfy only `min_validators_per_shard` condition.
@@ -403,7 +441,7 @@ pub(crate) mod old_validator_selection {
             &mut shard_assignment,
             &mut result,
             &mut chunk_producers,
-            min_validators_per_shard,
+            &vec![min_validators_per_shard; num_shards as usize],
         );
 
         // Second, if there are any unassigned chunk producers left, distribute them
@@ -611,7 +649,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             1,
-            1,
+            &[1; 1],
             1,
             RngSeed::default(),
             None,
@@ -632,7 +670,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             1,
-            1,
+            &[1; 1],
             // We must assign new validator even if limit for balancing is zero.
             0,
             RngSeed::default(),
@@ -654,7 +692,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             3,
-            2,
+            &[2; 3],
             0,
             RngSeed::default(),
             Some(prev_assignment),
@@ -677,7 +715,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             4,
-            1,
+            &[1; 4],
             // Set limit to zero, to check that it is ignored.
             0,
             RngSeed::default(),
@@ -698,7 +736,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             2,
-            1,
+            &[1; 2],
             // As we don't change assignment at all, zero limit for balancing is enough.
             0,
             RngSeed::default(),
@@ -720,7 +758,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             3,
-            1,
+            &[1; 3],
             1,
             RngSeed::default(),
             Some(prev_assignment),
@@ -742,7 +780,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             3,
-            1,
+            &[1; 3],
             1,
             RngSeed::default(),
             None,
@@ -763,7 +801,7 @@ mod tests {
         let assignment = assign_chunk_producers_to_shards(
             (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
             3,
-            1,
+            &[1; 3],
             5,
             RngSeed::default(),
             Some(prev_assignment),
@@ -773,6 +811,43 @@ mod tests {
         assert_eq!(assignment, target_assignment);
     }
 
+    #[test]
+    /// Tests that minimums differing between shards are respected, both when
+    /// chunk producers can be assigned without repeats and when they can't.
+    fn test_shard_assignment_uneven_minimums() {
+        for num_chunk_producers in [3, 5, 8] {
+            let assignment = assign_chunk_producers_to_shards(
+                (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
+                2,
+                &[1, 3],
+                5,
+                RngSeed::default(),
+                None,
+            )
+            .unwrap();
+
+            assert!(assignment[0].len() >= 1);
+            assert!(assignment[1].len() >= 3);
+            for cps in &assignment {
+                let unique = cps.iter().map(|cp| cp.account_id()).collect::<HashSet<_>>();
+                assert_eq!(unique.len(), cps.len(), "Duplicate chunk producers in {cps:?}");
+            }
+            let num_assigned = assignment.iter().map(|cps| cps.len()).sum::<usize>();
+            assert_eq!(num_assigned, num_chunk_producers.max(4));
+        }
+
+        // The largest minimum can't be satisfied.
+        assert!(assign_chunk_producers_to_shards(
+            (0..2).into_iter().map(validator_stake_for_test).collect(),
+            2,
+            &[1, 3],
+            5,
+            RngSeed::default(),
+            None,
+        )
+        .is_err());
+    }
+
     fn validator_to_shard(assignment: &[Vec<ValidatorStake>]) -> HashMap<AccountId, ShardIndex> {
         assignment
             .iter()
@@ -802,7 +877,7 @@ mod tests {
             let new_assignment = assign_chunk_producers_to_shards(
                 (0..num_chunk_producers).into_iter().map(validator_stake_for_test).collect(),
                 num_shards,
-                1,
+                &[1; 3],
                 limit_per_iter,
                 RngSeed::default(),
                 Some(assignment.clone()),
@@ -1,23 +1,30 @@
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 use crate::EpochManagerAdapter;
 use itertools::Itertools;
 use near_cache::SyncLruCache;
-use near_chain_configs::ClientConfig;
+use near_chain_configs::{ClientConfig, TrackedShardsConfig};
 use near_primitives::errors::EpochError;
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::{AccountId, EpochId, ShardId};
+use near_primitives::types::{AccountId, EpochHeight, EpochId, ShardId};
 
-#[derive(Clone)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum TrackedConfig {
     /// Tracks shards that contain one of the given account.
     Accounts(Vec<AccountId>),
-    /// Tracks shards that are assigned to given validator account.
-    ShadowValidator(AccountId),
+    /// Tracks the shards with the given ids.
+    Shards(Vec<ShardId>),
+    /// Tracks shards that are assigned to given validator account, or to the validator of the
+    /// node if there is no account. With `next_epoch`, also tracks the shards the validator is
+    /// assigned in the next epoch.
+    ValidatorAssignments { account_id: Option<AccountId>, next_epoch: bool },
     /// Tracks all shards.
     AllShards,
     /// Rotates between sets of shards to track.
     Schedule(Vec<Vec<ShardId>>),
+    /// Tracks no shard, not even the ones the validator duties of the node require, see
+    /// `ClientConfig::chunk_validator_only`.
+    ChunkValidatorOnly,
 }
 
 impl TrackedConfig {
@@ -26,27 +33,54 @@ impl TrackedConfig {
     }
 
     pub fn from_config(config: &ClientConfig) -> Self {
-        if !config.tracked_shards.is_empty() {
+        if config.chunk_validator_only {
+            TrackedConfig::ChunkValidatorOnly
+        } else if let Some(tracked_shards_config) = &config.tracked_shards_config {
+            tracked_shards_config.clone().into()
+        } else if !config.tracked_shards.is_empty() {
             TrackedConfig::AllShards
         } else if !config.tracked_shard_schedule.is_empty() {
             TrackedConfig::Schedule(config.tracked_shard_schedule.clone())
         } else if let Some(account_id) = config.tracked_shadow_validator.as_ref() {
-            TrackedConfig::ShadowValidator(account_id.clone())
+            TrackedConfig::ValidatorAssignments {
+                account_id: Some(account_id.clone()),
+                next_epoch: false,
+            }
         } else {
             TrackedConfig::Accounts(config.tracked_accounts.clone())
         }
     }
 }
 
+impl From<TrackedShardsConfig> for TrackedConfig {
+    fn from(config: TrackedShardsConfig) -> Self {
+        match config {
+            TrackedShardsConfig::NoShards => TrackedConfig::new_empty(),
+            TrackedShardsConfig::AllShards => TrackedConfig::AllShards,
+            TrackedShardsConfig::Shards(shard_ids) => TrackedConfig::Shards(shard_ids),
+            TrackedShardsConfig::Accounts(account_ids) => TrackedConfig::Accounts(account_ids),
+            TrackedShardsConfig::Schedule(schedule) => TrackedConfig::Schedule(schedule),
+            TrackedShardsConfig::ValidatorAssignments { account_id, next_epoch } => {
+                TrackedConfig::ValidatorAssignments { account_id, next_epoch }
+            }
+        }
+    }
+}
+
 // bit mask for which shard to track
 type BitMask = Vec<bool>;
 
-/// Tracker that tracks shard ids and accounts. Right now, it only supports two modes
-/// TrackedConfig::Accounts(accounts): track the shards where `accounts` belong to
-/// TrackedConfig::AllShards: track all shards
+/// Tracker that tracks shard ids and accounts, according to a [TrackedConfig].
+///
+/// The config can be replaced while the node is running, see
+/// [ShardTracker::update_tracked_config]. The tracker keeps the configs by the epoch height
+/// they apply from, so that the shards tracked in the current and next epochs don't change.
 #[derive(Clone)]
 pub struct ShardTracker {
-    tracked_config: TrackedConfig,
+    /// The configs and the epoch heights they apply from, ordered by height. The first one
+    /// applies from genesis. Shared by the clones of the tracker, so that an update applies to
+    /// all of them.
+    tracked_configs: Arc<RwLock<Vec<(EpochHeight, Arc<TrackedConfig>)>>>,
     /// Stores shard tracking information by epoch, only useful if TrackedState == Accounts
     tracking_shards_cache: Arc<SyncLruCache<EpochId, BitMask>>,
     epoch_manager: Arc<dyn EpochManagerAdapter>,
@@ -55,7 +89,7 @@ pub struct ShardTracker {
 impl ShardTracker {
     pub fn new(tracked_config: TrackedConfig, epoch_manager: Arc<dyn EpochManagerAdapter>) -> Self {
         ShardTracker {
-            tracked_config,
+            tracked_configs: Arc::new(RwLock::new(vec![(0, Arc::new(tracked_config))])),
             // 1024 epochs on mainnet is about 512 days which is more than enough,
             // and this is a cache anyway. The data size is pretty small as well,
             // only one bit per shard per epoch.
@@ -68,12 +102,72 @@ impl ShardTracker {
         Self::new(TrackedConfig::new_empty(), epoch_manager)
     }
 
+    /// Replaces the config from the epoch after the next one of the epoch at
+    /// `head_epoch_height`, which leaves the node an epoch to catch up on the State of the
+    /// shards it starts tracking. Returns whether the config changed.
+    pub fn update_tracked_config(
+        &self,
+        tracked_config: TrackedConfig,
+        head_epoch_height: EpochHeight,
+    ) -> bool {
+        let mut tracked_configs = self.tracked_configs.write().unwrap();
+        let (_, latest_config) = tracked_configs.last().unwrap();
+        if latest_config.as_ref() == &tracked_config {
+            return false;
+        }
+        let from_epoch_height = head_epoch_height + 2;
+        tracing::info!(
+            target: "shard_tracker",
+            ?tracked_config,
+            from_epoch_height,
+            "updating the tracked shards config"
+        );
+        // The config applying from genesis is always kept, as `from_epoch_height` is above 0.
+        tracked_configs.retain(|(epoch_height, _)| *epoch_height < from_epoch_height);
+        tracked_configs.push((from_epoch_height, Arc::new(tracked_config)));
+        self.tracking_shards_cache.lock().clear();
+        true
+    }
+
+    /// Returns the config applying to the given epoch.
+    fn config_at_epoch(&self, epoch_id: &EpochId) -> Result<Arc<TrackedConfig>, EpochError> {
+        let tracked_configs = self.tracked_configs.read().unwrap();
+        if let [(_, tracked_config)] = tracked_configs.as_slice() {
+            // Avoid looking up the epoch height when the config never changed.
+            return Ok(tracked_config.clone());
+        }
+        let epoch_height = self.epoch_manager.get_epoch_info(epoch_id)?.epoch_height();
+        let (_, tracked_config) = tracked_configs
+            .iter()
+            .rev()
+            .find(|(from_epoch_height, _)| *from_epoch_height <= epoch_height)
+            .unwrap_or(&tracked_configs[0]);
+        Ok(tracked_config.clone())
+    }
+
+    /// Whether all the shards are tracked, whatever the epoch.
+    fn tracks_all_shards(&self) -> bool {
+        let tracked_configs = self.tracked_configs.read().unwrap();
+        matches!(tracked_configs.as_slice(), [(_, config)] if **config == TrackedConfig::AllShards)
+    }
+
+    /// Whether the node runs as a chunk validator only, whatever the epoch. The shards of its
+    /// validator duties are then not tracked either.
+    fn is_chunk_validator_only(&self) -> bool {
+        let tracked_configs = self.tracked_configs.read().unwrap();
+        matches!(
+            tracked_configs.as_slice(),
+            [(_, config)] if **config == TrackedConfig::ChunkValidatorOnly
+        )
+    }
+
     fn tracks_shard_at_epoch(
         &self,
         shard_id: ShardId,
         epoch_id: &EpochId,
+        me: Option<&AccountId>,
     ) -> Result<bool, EpochError> {
-        match &self.tracked_config {
+        match self.config_at_epoch(epoch_id)?.as_ref() {
             TrackedConfig::Accounts(tracked_accounts) => {
                 let shard_layout = self.epoch_manager.get_shard_layout(epoch_id)?;
                 let tracking_mask = self.tracking_shards_cache.get_or_try_put(
@@ -92,7 +186,9 @@ impl ShardTracker {
                 let shard_index = shard_layout.get_shard_index(shard_id)?;
                 Ok(tracking_mask.get(shard_index).copied().unwrap_or(false))
             }
+            TrackedConfig::Shards(shard_ids) => Ok(shard_ids.contains(&shard_id)),
             TrackedConfig::AllShards => Ok(true),
+            TrackedConfig::ChunkValidatorOnly => Ok(false),
             TrackedConfig::Schedule(schedule) => {
                 assert_ne!(schedule.len(), 0);
                 let epoch_info = self.epoch_manager.get_epoch_info(epoch_id)?;
@@ -101,24 +197,50 @@ impl ShardTracker {
                 let subset = &schedule[index as usize];
                 Ok(subset.contains(&shard_id))
             }
-            TrackedConfig::ShadowValidator(account_id) => {
-                self.epoch_manager.cares_about_shard_in_epoch(epoch_id, account_id, shard_id)
+            TrackedConfig::ValidatorAssignments { account_id, .. } => {
+                match account_id.as_ref().or(me) {
+                    Some(account_id) => self
+                        .epoch_manager
+                        .cares_about_shard_in_epoch(epoch_id, account_id, shard_id),
+                    None => Ok(false),
+                }
             }
         }
     }
 
-    fn tracks_shard(&self, shard_id: ShardId, prev_hash: &CryptoHash) -> Result<bool, EpochError> {
+    fn tracks_shard(
+        &self,
+        shard_id: ShardId,
+        prev_hash: &CryptoHash,
+        me: Option<&AccountId>,
+    ) -> Result<bool, EpochError> {
         let epoch_id = self.epoch_manager.get_epoch_id_from_prev_block(prev_hash)?;
-        self.tracks_shard_at_epoch(shard_id, &epoch_id)
+        if self.tracks_shard_at_epoch(shard_id, &epoch_id, me)? {
+            return Ok(true);
+        }
+        // Track the shards assigned in the next epoch already in the current one.
+        let tracked_config = self.config_at_epoch(&epoch_id)?;
+        let TrackedConfig::ValidatorAssignments { account_id, next_epoch: true } =
+            tracked_config.as_ref()
+        else {
+            return Ok(false);
+        };
+        match account_id.as_ref().or(me) {
+            Some(account_id) => self
+                .epoch_manager
+                .cares_about_shard_next_epoch_from_prev_block(prev_hash, account_id, shard_id),
+            None => Ok(false),
+        }
     }
 
     fn tracks_shard_next_epoch_from_prev_block(
         &self,
         shard_id: ShardId,
         prev_hash: &CryptoHash,
+        me: Option<&AccountId>,
     ) -> Result<bool, EpochError> {
         let epoch_id = self.epoch_manager.get_next_epoch_id_from_prev_block(prev_hash)?;
-        self.tracks_shard_at_epoch(shard_id, &epoch_id)
+        self.tracks_shard_at_epoch(shard_id, &epoch_id, me)
     }
 
     /// Whether the client cares about some shard right now.
@@ -136,6 +258,9 @@ impl ShardTracker {
     ) -> bool {
         // TODO: fix these unwrap_or here and handle error correctly. The current behavior masks potential errors and bugs
         // https://github.com/near/nearcore/issues/4936
+        if is_me && self.is_chunk_validator_only() {
+            return false;
+        }
         if let Some(account_id) = account_id {
             let account_cares_about_shard = self
                 .epoch_manager
@@ -153,13 +278,11 @@ impl ShardTracker {
                 // We have access to the node config. Use the config to find a definite answer.
             }
         }
-        match self.tracked_config {
-            TrackedConfig::AllShards => {
-                // Avoid looking up EpochId as a performance optimization.
-                true
-            }
-            _ => self.tracks_shard(shard_id, parent_hash).unwrap_or(false),
+        if self.tracks_all_shards() {
+            // Avoid looking up EpochId as a performance optimization.
+            return true;
         }
+        self.tracks_shard(shard_id, parent_hash, account_id).unwrap_or(false)
     }
 
     /// Whether the client cares about some shard in the next epoch.
@@ -178,6 +301,9 @@ impl ShardTracker {
         shard_id: ShardId,
         is_me: bool,
     ) -> bool {
+        if is_me && self.is_chunk_validator_only() {
+            return false;
+        }
         if let Some(account_id) = account_id {
             let account_cares_about_shard = {
                 self.epoch_manager
@@ -196,15 +322,12 @@ impl ShardTracker {
                 // We have access to the node config. Use the config to find a definite answer.
             }
         }
-        match self.tracked_config {
-            TrackedConfig::AllShards => {
-                // Avoid looking up EpochId as a performance optimization.
-                true
-            }
-            _ => {
-                self.tracks_shard_next_epoch_from_prev_block(shard_id, parent_hash).unwrap_or(false)
-            }
+        if self.tracks_all_shards() {
+            // Avoid looking up EpochId as a performance optimization.
+            return true;
         }
+        self.tracks_shard_next_epoch_from_prev_block(shard_id, parent_hash, account_id)
+            .unwrap_or(false)
     }
 }
 
@@ -221,7 +344,9 @@ mod tests {
     use near_primitives::hash::CryptoHash;
     use near_primitives::shard_layout::ShardLayout;
     use near_primitives::types::validator_stake::ValidatorStake;
-    use near_primitives::types::{BlockHeight, EpochId, NumShards, ProtocolVersion, ShardId};
+    use near_primitives::types::{
+        AccountId, BlockHeight, EpochId, NumShards, ProtocolVersion, ShardId,
+    };
     use near_primitives::version::ProtocolFeature::SimpleNightshade;
     use near_primitives::version::PROTOCOL_VERSION;
     use near_store::test_utils::create_test_store;
@@ -256,9 +381,12 @@ mod tests {
             num_chunk_validator_seats: 300,
             num_chunk_only_producer_seats: 300,
             minimum_validators_per_shard: 1,
+            minimum_validators_per_shard_overrides: Default::default(),
+            chunk_validator_minimum_stake_ratio: None,
             minimum_stake_ratio: Ratio::new(160i32, 1_000_000i32),
             chunk_producer_assignment_changes_limit: 5,
             shuffle_shard_assignment_for_chunk_producers: false,
+            validator_blocklist: Default::default(),
             validator_max_kickout_stake_perc: 100,
         };
         let reward_calculator = RewardCalculator {
@@ -385,6 +513,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_chunk_validator_only() {
+        let shard_ids = (0..4).map(ShardId::new).collect_vec();
+        let epoch_manager =
+            Arc::new(get_epoch_manager(PROTOCOL_VERSION, shard_ids.len() as NumShards, false));
+        let validator: AccountId = "test".parse().unwrap();
+        let parent_hash = CryptoHash::default();
+
+        // The only validator has to track all the shards for its chunk producer duties.
+        let tracker = ShardTracker::new(TrackedConfig::new_empty(), epoch_manager.clone());
+        for &shard_id in &shard_ids {
+            assert!(tracker.care_about_shard(Some(&validator), &parent_hash, shard_id, true));
+        }
+
+        // Unless the node runs as a chunk validator only. Other nodes still expect the
+        // validator to track the shards.
+        let tracker = ShardTracker::new(TrackedConfig::ChunkValidatorOnly, epoch_manager);
+        for &shard_id in &shard_ids {
+            assert!(!tracker.care_about_shard(Some(&validator), &parent_hash, shard_id, true));
+            assert!(!tracker.will_care_about_shard(Some(&validator), &parent_hash, shard_id, true));
+            assert!(tracker.care_about_shard(Some(&validator), &parent_hash, shard_id, false));
+        }
+    }
+
     #[test]
     fn test_track_schedule() {
         // Creates a ShardTracker that changes every epoch tracked shards.
@@ -433,6 +585,57 @@ mod tests {
         assert_eq!(get_all_shards_will_care_about(&tracker, &shard_ids, &h[7]), subset3);
     }
 
+    #[test]
+    fn test_update_tracked_config() {
+        let shard_ids = (0..4).map(ShardId::new).collect_vec();
+        let epoch_manager =
+            Arc::new(get_epoch_manager(PROTOCOL_VERSION, shard_ids.len() as NumShards, false));
+        let tracker =
+            ShardTracker::new(TrackedConfig::Shards(vec![ShardId::new(0)]), epoch_manager.clone());
+
+        let h = hash_range(8);
+        {
+            let mut epoch_manager = epoch_manager.write();
+            for i in 0..8 {
+                record_block(
+                    &mut epoch_manager,
+                    if i > 0 { h[i - 1] } else { CryptoHash::default() },
+                    h[i],
+                    i as u64,
+                    vec![],
+                    PROTOCOL_VERSION,
+                );
+            }
+        }
+        let epoch_height = |prev_hash: &CryptoHash| {
+            let epoch_id = epoch_manager.get_epoch_id_from_prev_block(prev_hash).unwrap();
+            epoch_manager.get_epoch_info(&epoch_id).unwrap().epoch_height()
+        };
+
+        let old_shards = HashSet::from([ShardId::new(0)]);
+        let new_shards = HashSet::from([ShardId::new(1), ShardId::new(2)]);
+        let new_config = TrackedConfig::Shards(new_shards.iter().copied().collect());
+        let head_epoch_height = epoch_height(&h[3]);
+        assert!(tracker.update_tracked_config(new_config.clone(), head_epoch_height));
+        assert!(!tracker.update_tracked_config(new_config, head_epoch_height));
+
+        // The shards tracked in the current and next epochs of the head don't change.
+        for i in 3..7 {
+            let expected =
+                if epoch_height(&h[i]) < head_epoch_height + 2 { &old_shards } else { &new_shards };
+            assert_eq!(&get_all_shards_care_about(&tracker, &shard_ids, &h[i]), expected);
+        }
+        assert_eq!(get_all_shards_will_care_about(&tracker, &shard_ids, &h[3]), old_shards);
+        assert_eq!(get_all_shards_will_care_about(&tracker, &shard_ids, &h[4]), new_shards);
+
+        // Clones share the config.
+        let tracker_clone = tracker.clone();
+        assert!(tracker.update_tracked_config(TrackedConfig::AllShards, 0));
+        let all_shards: HashSet<_> = shard_ids.iter().copied().collect();
+        assert_eq!(get_all_shards_care_about(&tracker_clone, &shard_ids, &h[1]), old_shards);
+        assert_eq!(get_all_shards_care_about(&tracker_clone, &shard_ids, &h[6]), all_shards);
+    }
+
     #[test]
     fn test_track_shards_shard_layout_change() {
         let simple_nightshade_version = SimpleNightshade.protocol_version();
@@ -155,9 +155,12 @@ pub fn epoch_config_with_production_config(
         num_chunk_validator_seats: 300,
         num_chunk_only_producer_seats: 300,
         minimum_validators_per_shard: 1,
+        minimum_validators_per_shard_overrides: Default::default(),
+        chunk_validator_minimum_stake_ratio: None,
         minimum_stake_ratio: Ratio::new(160i32, 1_000_000i32),
         chunk_producer_assignment_changes_limit: 5,
         shuffle_shard_assignment_for_chunk_producers: false,
+        validator_blocklist: Default::default(),
         shard_layout: ShardLayout::multi_shard(num_shards, 0),
         validator_max_kickout_stake_perc: 100,
     };
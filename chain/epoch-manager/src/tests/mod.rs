@@ -2340,9 +2340,12 @@ fn test_protocol_version_switch_with_many_seats() {
         num_chunk_validator_seats: 300,
         num_chunk_only_producer_seats: 300,
         minimum_validators_per_shard: 1,
+        minimum_validators_per_shard_overrides: Default::default(),
+        chunk_validator_minimum_stake_ratio: None,
         minimum_stake_ratio: Ratio::new(160i32, 1_000_000i32),
         chunk_producer_assignment_changes_limit: 5,
         shuffle_shard_assignment_for_chunk_producers: false,
+        validator_blocklist: Default::default(),
         validator_max_kickout_stake_perc: 100,
     };
     let config = AllEpochConfig::new(false, PROTOCOL_VERSION, epoch_config, "test-chain");
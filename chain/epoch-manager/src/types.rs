@@ -109,6 +109,20 @@ impl EpochInfoAggregator {
 
         for (shard_index, mask) in block_info.chunk_mask().iter().enumerate() {
             let shard_id = shard_layout.get_shard_id(shard_index).unwrap();
+            // A suppressed chunk is neither expected from the chunk producer nor endorsed by the
+            // chunk validators, see `near_primitives::chunk_suppression`.
+            if !*mask
+                && block_info
+                    .chunk_endorsements()
+                    .is_some_and(|bitmap| bitmap.is_chunk_suppressed(shard_index))
+            {
+                debug!(
+                    target: "epoch_tracker",
+                    ?shard_id,
+                    block_height = prev_block_height + 1,
+                    "Suppressed chunk");
+                continue;
+            }
             let chunk_producer_id = EpochManager::chunk_producer_from_info(
                 epoch_info,
                 shard_layout,
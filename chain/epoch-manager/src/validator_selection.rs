@@ -9,7 +9,7 @@ use near_primitives::types::{
 };
 use near_primitives::validator_mandates::{ValidatorMandates, ValidatorMandatesConfig};
 use near_primitives::version::ProtocolFeature;
-use num_rational::Ratio;
+use num_rational::{Ratio, Rational32};
 use rand::seq::SliceRandom;
 use std::cmp::{self, Ordering};
 use std::collections::hash_map;
@@ -49,10 +49,13 @@ fn select_validators_from_proposals(
     protocol_version: ProtocolVersion,
 ) -> ValidatorRoles {
     let shard_ids: Vec<_> = epoch_config.shard_layout.shard_ids().collect();
-    let min_stake_ratio = {
-        let rational = epoch_config.minimum_stake_ratio;
-        Ratio::new(*rational.numer() as u128, *rational.denom() as u128)
-    };
+    let to_ratio =
+        |rational: Rational32| Ratio::new(*rational.numer() as u128, *rational.denom() as u128);
+    let min_stake_ratio = to_ratio(epoch_config.minimum_stake_ratio);
+    // Chunk validators may have a lower entry stake than block and chunk producers, whose
+    // selection is what the security of the chain relies on.
+    let chunk_validator_min_stake_ratio =
+        epoch_config.chunk_validator_minimum_stake_ratio.map_or(min_stake_ratio, to_ratio);
 
     let chunk_producer_proposals = order_proposals(proposals.values().cloned());
     let (chunk_producers, _, cp_stake_threshold) = select_chunk_producers(
@@ -75,7 +78,7 @@ fn select_validators_from_proposals(
     let (chunk_validators, _, cv_stake_threshold) = select_validators(
         chunk_validator_proposals,
         epoch_config.num_chunk_validator_seats as usize,
-        min_stake_ratio,
+        chunk_validator_min_stake_ratio,
         protocol_version,
     );
 
@@ -135,7 +138,11 @@ fn get_chunk_producers_assignment(
 
     // Assign chunk producers to shards.
     let num_chunk_producers = chunk_producers.len();
-    let minimum_validators_per_shard = epoch_config.minimum_validators_per_shard as usize;
+    let minimum_validators_per_shard = epoch_config
+        .minimum_validators_per_shard_by_index()
+        .into_iter()
+        .map(|num_seats| num_seats as usize)
+        .collect::<Vec<_>>();
     let prev_chunk_producers_assignment = if use_stable_shard_assignment {
         let mut assignment = vec![];
         for validator_ids in prev_epoch_info.chunk_producers_settlement().iter() {
@@ -154,7 +161,7 @@ fn get_chunk_producers_assignment(
     let shard_assignment = assign_chunk_producers_to_shards(
         chunk_producers.clone(),
         shard_ids.len() as NumShards,
-        minimum_validators_per_shard,
+        &minimum_validators_per_shard,
         epoch_config.chunk_producer_assignment_changes_limit as usize,
         rng_seed,
         prev_chunk_producers_assignment,
@@ -192,7 +199,7 @@ pub fn proposals_to_epoch_info(
 
     let shard_ids: Vec<_> = epoch_config.shard_layout.shard_ids().collect();
     let mut stake_change = BTreeMap::new();
-    let proposals = apply_epoch_update_to_proposals(
+    let mut proposals = apply_epoch_update_to_proposals(
         proposals,
         prev_epoch_info,
         &validator_reward,
@@ -200,6 +207,15 @@ pub fn proposals_to_epoch_info(
         &mut stake_change,
     );
 
+    // Blocklisted accounts can't be selected whatever their stake, which is returned to them
+    // like for the other unselected proposals.
+    for account_id in &epoch_config.validator_blocklist {
+        if proposals.remove(account_id).is_some() {
+            tracing::info!(target: "epoch_manager", %account_id, "Excluding blocklisted validator");
+            *stake_change.get_mut(account_id).unwrap() = 0;
+        }
+    }
+
     // Select validators for the next epoch.
     // Returns unselected proposals, validator lists for all roles and stake
     // threshold to become a validator.
@@ -1158,6 +1174,86 @@ mod tests {
         assert_eq!(num_validators, epoch_info.validators_iter().len());
     }
 
+    #[test]
+    fn test_chunk_validator_minimum_stake_ratio() {
+        if !ProtocolFeature::StatelessValidation.enabled(PROTOCOL_VERSION) {
+            return;
+        }
+
+        let mut epoch_config =
+            create_epoch_config(1, 100, Some(300), Some(300), Some(Ratio::new(1i32, 10i32)));
+        epoch_config.chunk_validator_minimum_stake_ratio = Some(Ratio::new(1i32, 100i32));
+        let prev_epoch_info = create_prev_epoch_info(7, &["test5", "test6"], &[]);
+        let proposals = create_proposals(&[
+            ("test1", 1000),
+            ("test2", 1000),
+            ("test3", 1000),
+            ("test4", 200),
+            ("test5", 100),
+            ("test6", 20), // 20 is < 1/100 of 3300, too small even for a chunk validator
+        ]);
+        let epoch_info = proposals_to_epoch_info(
+            &epoch_config,
+            [0; 32],
+            &prev_epoch_info,
+            proposals,
+            Default::default(),
+            Default::default(),
+            0,
+            PROTOCOL_VERSION,
+            false,
+        )
+        .unwrap();
+
+        // Block and chunk producers keep the higher stake ratio.
+        let block_producers: Vec<_> = epoch_info
+            .block_producers_settlement()
+            .iter()
+            .map(|id| epoch_info.get_validator(*id).take_account_id().to_string())
+            .collect();
+        assert_eq!(block_producers, vec!["test1", "test2", "test3"]);
+        assert_eq!(epoch_info.chunk_producers_settlement(), &[vec![0, 1, 2]]);
+
+        // The lower stakes are chunk validators only.
+        let validators: Vec<_> =
+            epoch_info.validators_iter().map(|v| v.take_account_id().to_string()).collect();
+        assert_eq!(validators, vec!["test1", "test2", "test3", "test4", "test5"]);
+        let kickout = epoch_info.validator_kickout();
+        assert_eq!(kickout.len(), 1);
+        assert!(matches!(
+            kickout.get(AccountIdRef::new_or_panic("test6")).unwrap(),
+            ValidatorKickoutReason::NotEnoughStake { stake: 20, .. }
+        ));
+    }
+
+    #[test]
+    fn test_validator_blocklist() {
+        let mut epoch_config = create_epoch_config(1, 100, None, None, None);
+        epoch_config.validator_blocklist = ["test2".parse().unwrap()].into_iter().collect();
+        let prev_epoch_info =
+            create_prev_epoch_info(7, &[("test1", 1000), ("test2", 2000), ("test3", 1000)], &[]);
+        // test2 is blocklisted despite having the largest stake.
+        let proposals = create_proposals(&[("test1", 1000), ("test2", 3000), ("test3", 1000)]);
+        let epoch_info = proposals_to_epoch_info(
+            &epoch_config,
+            [0; 32],
+            &prev_epoch_info,
+            proposals,
+            Default::default(),
+            Default::default(),
+            0,
+            PROTOCOL_VERSION,
+            false,
+        )
+        .unwrap();
+
+        let validators: Vec<_> =
+            epoch_info.validators_iter().map(|v| v.take_account_id().to_string()).collect();
+        assert_eq!(validators, vec!["test1", "test3"]);
+        assert_eq!(epoch_info.stake_change().get(AccountIdRef::new_or_panic("test2")), Some(&0));
+        assert_eq!(epoch_info.stake_change().get(AccountIdRef::new_or_panic("test1")), Some(&1000));
+    }
+
     #[test]
     fn test_validator_assignment_with_kickout() {
         // kicked out validators are not selected
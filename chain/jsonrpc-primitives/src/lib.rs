@@ -2,4 +2,5 @@
 
 pub mod errors;
 pub mod message;
+pub mod methods;
 pub mod types;
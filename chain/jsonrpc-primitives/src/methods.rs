@@ -0,0 +1,144 @@
+//! Names, parameters and results of the JSON RPC methods.
+//!
+//! The server dispatches the requests by [Method] to handlers whose signatures must match the
+//! definitions below, and the typed client of `near-jsonrpc-client` calls them through the same
+//! definitions, so that both sides can't drift apart.
+
+use crate::types;
+
+/// A JSON RPC method, tying its name to the types of its parameters and result.
+pub trait RpcMethod {
+    const NAME: &'static str;
+    type Request;
+    type Response;
+    type Error;
+}
+
+macro_rules! rpc_methods {
+    ($(
+        $(#[$attr:meta])*
+        $method:ident = $name:literal: $request:ty => Result<$response:ty, $error:ty>;
+    )*) => {
+        $(
+            $(#[$attr])*
+            #[derive(Debug, Clone, Copy)]
+            pub struct $method;
+
+            impl RpcMethod for $method {
+                const NAME: &'static str = $name;
+                type Request = $request;
+                type Response = $response;
+                type Error = $error;
+            }
+        )*
+
+        /// The methods defined in this module, to dispatch the requests by name.
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub enum Method {
+            $($method,)*
+        }
+
+        impl Method {
+            pub const ALL: &'static [Method] = &[$(Method::$method),*];
+
+            pub fn name(self) -> &'static str {
+                match self {
+                    $(Method::$method => $method::NAME,)*
+                }
+            }
+
+            pub fn from_name(name: &str) -> Option<Method> {
+                match name {
+                    $($name => Some(Method::$method),)*
+                    _ => None,
+                }
+            }
+        }
+    };
+}
+
+rpc_methods! {
+    Block = "block": types::blocks::RpcBlockRequest
+        => Result<types::blocks::RpcBlockResponse, types::blocks::RpcBlockError>;
+    /// Returns the last block of the canonical chain whose timestamp is at or before the given
+    /// one.
+    BlockByTimestamp = "block_by_timestamp": types::blocks::RpcBlockByTimestampRequest
+        => Result<types::blocks::RpcBlockResponse, types::blocks::RpcBlockError>;
+    /// Returns the hash of the transaction without waiting for its execution.
+    BroadcastTxAsync = "broadcast_tx_async": types::transactions::RpcSendTransactionRequest
+        => Result<String, std::convert::Infallible>;
+    BroadcastTxCommit = "broadcast_tx_commit": types::transactions::RpcSendTransactionRequest
+        => Result<types::transactions::RpcTransactionResponse, types::transactions::RpcTransactionError>;
+    Chunk = "chunk": types::chunks::RpcChunkRequest
+        => Result<types::chunks::RpcChunkResponse, types::chunks::RpcChunkError>;
+    ClientConfig = "client_config": ()
+        => Result<types::client_config::RpcClientConfigResponse, types::client_config::RpcClientConfigError>;
+    GasPrice = "gas_price": types::gas_price::RpcGasPriceRequest
+        => Result<types::gas_price::RpcGasPriceResponse, types::gas_price::RpcGasPriceError>;
+    Health = "health": ()
+        => Result<types::status::RpcHealthResponse, types::status::RpcStatusError>;
+    LightClientProof = "light_client_proof": types::light_client::RpcLightClientExecutionProofRequest
+        => Result<types::light_client::RpcLightClientExecutionProofResponse, types::light_client::RpcLightClientProofError>;
+    NextLightClientBlock = "next_light_client_block": types::light_client::RpcLightClientNextBlockRequest
+        => Result<types::light_client::RpcLightClientNextBlockResponse, types::light_client::RpcLightClientNextBlockError>;
+    NetworkInfo = "network_info": ()
+        => Result<types::network_info::RpcNetworkInfoResponse, types::network_info::RpcNetworkInfoError>;
+    /// Handled separately by the server, to label the metrics by the type of query.
+    Query = "query": types::query::RpcQueryRequest
+        => Result<types::query::RpcQueryResponse, types::query::RpcQueryError>;
+    SendTx = "send_tx": types::transactions::RpcSendTransactionRequest
+        => Result<types::transactions::RpcTransactionResponse, types::transactions::RpcTransactionError>;
+    Status = "status": ()
+        => Result<types::status::RpcStatusResponse, types::status::RpcStatusError>;
+    Tx = "tx": types::transactions::RpcTransactionStatusRequest
+        => Result<types::transactions::RpcTransactionResponse, types::transactions::RpcTransactionError>;
+    Validators = "validators": types::validator::RpcValidatorRequest
+        => Result<types::validator::RpcValidatorResponse, types::validator::RpcValidatorError>;
+    ExperimentalAccountReceipts = "EXPERIMENTAL_account_receipts": types::receipts::RpcAccountReceiptsRequest
+        => Result<types::receipts::RpcAccountReceiptsResponse, types::receipts::RpcAccountReceiptsError>;
+    /// Note that the changes of a given type come with their values, unlike the changes of all
+    /// types returned by `EXPERIMENTAL_changes_in_block`.
+    ExperimentalChanges = "EXPERIMENTAL_changes": types::changes::RpcStateChangesInBlockByTypeRequest
+        => Result<types::changes::RpcStateChangesInBlockResponse, types::changes::RpcStateChangesError>;
+    ExperimentalChangesInBlock = "EXPERIMENTAL_changes_in_block": types::changes::RpcStateChangesInBlockRequest
+        => Result<types::changes::RpcStateChangesInBlockByTypeResponse, types::changes::RpcStateChangesError>;
+    ExperimentalCongestionLevel = "EXPERIMENTAL_congestion_level": types::congestion::RpcCongestionLevelRequest
+        => Result<types::congestion::RpcCongestionLevelResponse, types::congestion::RpcCongestionLevelError>;
+    ExperimentalEpochConfig = "EXPERIMENTAL_epoch_config": types::config::RpcEpochConfigRequest
+        => Result<types::config::RpcEpochConfigResponse, types::config::RpcEpochConfigError>;
+    ExperimentalLightClientProof = "EXPERIMENTAL_light_client_proof": types::light_client::RpcLightClientExecutionProofRequest
+        => Result<types::light_client::RpcLightClientExecutionProofResponse, types::light_client::RpcLightClientProofError>;
+    ExperimentalLightClientBlockProof = "EXPERIMENTAL_light_client_block_proof": types::light_client::RpcLightClientBlockProofRequest
+        => Result<types::light_client::RpcLightClientBlockProofResponse, types::light_client::RpcLightClientProofError>;
+    ExperimentalMaintenanceWindows = "EXPERIMENTAL_maintenance_windows": types::maintenance::RpcMaintenanceWindowsRequest
+        => Result<types::maintenance::RpcMaintenanceWindowsResponse, types::maintenance::RpcMaintenanceWindowsError>;
+    ExperimentalProtocolConfig = "EXPERIMENTAL_protocol_config": types::config::RpcProtocolConfigRequest
+        => Result<types::config::RpcProtocolConfigResponse, types::config::RpcProtocolConfigError>;
+    ExperimentalReceipt = "EXPERIMENTAL_receipt": types::receipts::RpcReceiptRequest
+        => Result<types::receipts::RpcReceiptResponse, types::receipts::RpcReceiptError>;
+    /// Changes of the runtime parameters in the next protocol upgrade supported by the node,
+    /// annotated with the subsystems they affect.
+    ExperimentalRuntimeConfigChanges = "EXPERIMENTAL_runtime_config_changes": types::config::RpcRuntimeConfigChangesRequest
+        => Result<types::config::RpcRuntimeConfigChangesResponse, types::config::RpcProtocolConfigError>;
+    ExperimentalSecondaryIndex = "EXPERIMENTAL_secondary_index": types::secondary_index::RpcSecondaryIndexRequest
+        => Result<types::secondary_index::RpcSecondaryIndexResponse, types::secondary_index::RpcSecondaryIndexError>;
+    ExperimentalSplitStorageInfo = "EXPERIMENTAL_split_storage_info": types::split_storage::RpcSplitStorageInfoRequest
+        => Result<types::split_storage::RpcSplitStorageInfoResponse, types::split_storage::RpcSplitStorageInfoError>;
+    ExperimentalTxStatus = "EXPERIMENTAL_tx_status": types::transactions::RpcTransactionStatusRequest
+        => Result<types::transactions::RpcTransactionResponse, types::transactions::RpcTransactionError>;
+    ExperimentalValidatorsOrdered = "EXPERIMENTAL_validators_ordered": types::validator::RpcValidatorsOrderedRequest
+        => Result<types::validator::RpcValidatorsOrderedResponse, types::validator::RpcValidatorError>;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Method;
+
+    #[test]
+    fn test_method_names() {
+        for &method in Method::ALL {
+            assert_eq!(Method::from_name(method.name()), Some(method));
+        }
+        assert_eq!(Method::from_name("EXPERIMENTAL_genesis_config"), None);
+    }
+}
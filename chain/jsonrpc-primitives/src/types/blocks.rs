@@ -23,6 +23,13 @@ pub struct RpcBlockRequest {
     pub block_reference: near_primitives::types::BlockReference,
 }
 
+#[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct RpcBlockByTimestampRequest {
+    /// Timestamp in nanoseconds, the last block produced at or before it is returned.
+    #[serde(with = "near_primitives::serialize::dec_format")]
+    pub timestamp_nanosec: u64,
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct RpcBlockResponse {
     #[serde(flatten)]
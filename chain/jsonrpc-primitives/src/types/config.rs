@@ -46,3 +46,63 @@ impl From<RpcProtocolConfigError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(error_data, error_data_value)
     }
 }
+
+/// Requests the changes of the runtime parameters from the protocol version of the epoch of the
+/// given block to the latest protocol version supported by the node. Errors are the ones of
+/// `EXPERIMENTAL_protocol_config`.
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcRuntimeConfigChangesRequest {
+    #[serde(flatten)]
+    pub block_reference: near_primitives::types::BlockReference,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcRuntimeConfigChangesResponse {
+    pub current_protocol_version: near_primitives::types::ProtocolVersion,
+    pub next_protocol_version: near_primitives::types::ProtocolVersion,
+    /// Changed parameters along with the subsystems they affect, ordered by name.
+    pub changes: Vec<near_parameters::ParameterChange>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct RpcEpochConfigRequest {
+    #[serde(flatten)]
+    pub epoch_reference: near_primitives::types::EpochReference,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcEpochConfigResponse {
+    pub epoch_id: near_primitives::types::EpochId,
+    pub protocol_version: near_primitives::types::ProtocolVersion,
+    pub epoch_config: near_primitives::epoch_manager::EpochConfig,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcEpochConfigError {
+    #[error("Epoch not found")]
+    UnknownEpoch,
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcEpochConfigError> for crate::errors::RpcError {
+    fn from(error: RpcEpochConfigError) -> Self {
+        let error_data = match &error {
+            RpcEpochConfigError::UnknownEpoch => Some(Value::String("Unknown Epoch".to_string())),
+            RpcEpochConfigError::InternalError { .. } => Some(Value::String(error.to_string())),
+        };
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcEpochConfigError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -12,6 +12,7 @@ pub mod network_info;
 pub mod query;
 pub mod receipts;
 pub mod sandbox;
+pub mod secondary_index;
 pub mod split_storage;
 pub mod status;
 pub mod transactions;
@@ -4,6 +4,10 @@ pub struct RpcQueryRequest {
     pub block_reference: near_primitives::types::BlockReference,
     #[serde(flatten)]
     pub request: near_primitives::views::QueryRequest,
+    /// Name of a known standard, such as `nep141`, to also return the `view_state` or
+    /// `call_function` result decoded as JSON in the `decoded` field of the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decode: Option<String>,
 }
 
 #[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -48,6 +52,12 @@ pub enum RpcQueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("Invalid state override: {error_message}")]
+    InvalidStateOverride {
+        error_message: String,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("Access key for public key {public_key} has never been observed on the node")]
     UnknownAccessKey {
         public_key: near_crypto::PublicKey,
@@ -70,6 +80,9 @@ pub struct RpcQueryResponse {
     pub kind: QueryResponseKind,
     pub block_height: near_primitives::types::BlockHeight,
     pub block_hash: near_primitives::hash::CryptoHash,
+    /// The result decoded according to the standard requested in `decode`, if it's recognized.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<serde_json::Value>,
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -81,6 +94,7 @@ pub enum QueryResponseKind {
     CallResult(near_primitives::views::CallResult),
     AccessKey(near_primitives::views::AccessKeyView),
     AccessKeyList(near_primitives::views::AccessKeyList),
+    ViewAccountBalance(near_primitives::views::AccountBalanceView),
 }
 
 impl From<RpcQueryError> for crate::errors::RpcError {
@@ -38,3 +38,61 @@ impl From<RpcReceiptError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
     }
 }
+
+/// Position in the list of receipts of an account, used to request the next page.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct AccountReceiptsCursor {
+    pub block_height: near_primitives::types::BlockHeight,
+    pub receipt_id: near_primitives::hash::CryptoHash,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct RpcAccountReceiptsRequest {
+    pub account_id: near_primitives::types::AccountId,
+    /// Number of the most recent blocks to look at. Defaults to all the blocks which are
+    /// still kept by the node.
+    #[serde(default)]
+    pub block_window: Option<near_primitives::types::BlockHeightDelta>,
+    /// Only list the receipts addressed to or originating from the account.
+    #[serde(default)]
+    pub direction: Option<near_primitives::views::AccountReceiptDirection>,
+    /// The `next_cursor` of the previous page.
+    #[serde(default)]
+    pub cursor: Option<AccountReceiptsCursor>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Include the execution outcomes of the receipts.
+    #[serde(default)]
+    pub include_outcomes: bool,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug)]
+pub struct RpcAccountReceiptsResponse {
+    pub receipts: Vec<near_primitives::views::AccountReceiptView>,
+    /// Set if there may be more receipts.
+    pub next_cursor: Option<AccountReceiptsCursor>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcAccountReceiptsError {
+    #[error("The index of receipts by account is not enabled on this node")]
+    IndexDisabled,
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcAccountReceiptsError> for crate::errors::RpcError {
+    fn from(error: RpcAccountReceiptsError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcAccountReceiptsError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
@@ -0,0 +1,55 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::dec_format;
+use near_primitives::views::{SecondaryIndexEntryView, SecondaryIndexQuery};
+use serde::{Deserialize, Serialize};
+
+/// Position in a secondary index, used to request the next page. The position is the block
+/// height for the indexes by account and the block timestamp for the index of blocks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct SecondaryIndexCursor {
+    #[serde(with = "dec_format")]
+    pub position: u64,
+    pub hash: CryptoHash,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct RpcSecondaryIndexRequest {
+    #[serde(flatten)]
+    pub query: SecondaryIndexQuery,
+    /// The `next_cursor` of the previous page.
+    #[serde(default)]
+    pub cursor: Option<SecondaryIndexCursor>,
+    #[serde(default)]
+    pub limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcSecondaryIndexResponse {
+    pub entries: Vec<SecondaryIndexEntryView>,
+    /// Set if there may be more entries.
+    pub next_cursor: Option<SecondaryIndexCursor>,
+}
+
+#[derive(thiserror::Error, Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcSecondaryIndexError {
+    #[error("The {index} index is not enabled on this node")]
+    IndexDisabled { index: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<RpcSecondaryIndexError> for crate::errors::RpcError {
+    fn from(error: RpcSecondaryIndexError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcSecondaryIndexError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
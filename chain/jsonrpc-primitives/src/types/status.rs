@@ -1,12 +1,14 @@
 #[cfg(feature = "debug_types")]
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    DebugBlockStatusData, DelayedReceiptsView, EpochInfoView, ReceiverBackpressureView,
+    ReshardingBufferedReceiptsView, ReshardingEventView, ReshardingStatusView, ThreadPoolsView,
+    TrackedShardsView, ValidatorStatus,
 };
 #[cfg(feature = "debug_types")]
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, NetworkGraphView, NetworkRoutesView, PeerStoreView,
-    RecentOutboundConnectionsView, RequestedStatePartsView, SnapshotHostsView,
-    SplitStorageInfoView, SyncStatusView,
+    CatchupStatusView, ChainProcessingInfo, ConnectionAuditLogView, NetworkGraphView,
+    NetworkRoutesView, PeerStoreView, RecentOutboundConnectionsView, RequestedStatePartsView,
+    SnapshotHostsView, SplitStorageInfoView, SyncStatusView,
 };
 
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
@@ -33,9 +35,16 @@ pub enum DebugStatusResponse {
     RequestedStateParts(Vec<RequestedStatePartsView>),
     NetworkGraph(NetworkGraphView),
     RecentOutboundConnections(RecentOutboundConnectionsView),
+    ConnectionAuditLog(ConnectionAuditLogView),
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
     SplitStoreStatus(SplitStorageInfoView),
+    DelayedReceipts(DelayedReceiptsView),
+    ReceiverBackpressure(ReceiverBackpressureView),
+    ThreadPools(ThreadPoolsView),
+    ReshardingStatus(Vec<ReshardingStatusView>),
+    ReshardingBufferedReceipts(Vec<ReshardingBufferedReceiptsView>),
+    ReshardingEvents(Vec<ReshardingEventView>),
 }
 
 #[cfg(feature = "debug_types")]
@@ -50,6 +50,13 @@ pub enum RpcTransactionError {
     InternalError { debug_info: String },
     #[error("Timeout")]
     TimeoutError,
+    #[error("The shard of the signer is too congested to accept new transactions: {context}")]
+    ShardCongested {
+        #[serde(skip_serializing)]
+        context: near_primitives::errors::InvalidTxError,
+        /// Suggested number of seconds to wait before submitting the transaction again.
+        retry_after_secs: Option<u64>,
+    },
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug)]
@@ -110,7 +117,8 @@ impl From<near_primitives::views::TxStatusView> for RpcTransactionResponse {
 impl From<RpcTransactionError> for crate::errors::RpcError {
     fn from(error: RpcTransactionError) -> Self {
         let error_data = match &error {
-            RpcTransactionError::InvalidTransaction { context } => {
+            RpcTransactionError::InvalidTransaction { context }
+            | RpcTransactionError::ShardCongested { context, .. } => {
                 if let Ok(value) =
                     serde_json::to_value(crate::errors::ServerError::TxExecutionError(
                         near_primitives::errors::TxExecutionError::InvalidTxError(context.clone()),
@@ -1,9 +1,10 @@
 use awc::{Client, Connector};
 use futures::{future, future::LocalBoxFuture, FutureExt, TryFutureExt};
-use near_jsonrpc_primitives::errors::RpcError;
+use near_jsonrpc_primitives::errors::{RpcError, RpcErrorKind};
 use near_jsonrpc_primitives::message::{from_slice, Message};
+use near_jsonrpc_primitives::methods::{self, RpcMethod};
 use near_jsonrpc_primitives::types::changes::{
-    RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockByTypeResponse,
+    RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockResponse,
 };
 use near_jsonrpc_primitives::types::transactions::{
     RpcTransactionResponse, RpcTransactionStatusRequest,
@@ -34,18 +35,96 @@ const PAYLOAD_LIMIT: usize = 100 * 1024 * 1024;
 type HttpRequest<T> = LocalBoxFuture<'static, Result<T, String>>;
 type RpcRequest<T> = LocalBoxFuture<'static, Result<T, RpcError>>;
 
-/// Prepare a `RPCRequest` with a given client, server address, method and parameters.
-fn call_method<P, R>(client: &Client, server_addr: &str, method: &str, params: P) -> RpcRequest<R>
+/// Policy for retrying the calls failing with a transient error: the connection to the server
+/// failed, the server hit an internal error or timed out waiting for the node. The delay
+/// between attempts doubles after each attempt, up to `max_backoff`.
+///
+/// Calls are not retried by default. Note that retrying `broadcast_tx_commit` and `send_tx`
+/// resubmits the same signed transaction, which the node executes at most once.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one.
+    pub max_attempts: u32,
+    /// Delay before the first retry.
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Makes a single attempt.
+    pub const NONE: Self =
+        Self { max_attempts: 1, initial_backoff: Duration::ZERO, max_backoff: Duration::ZERO };
+
+    pub fn exponential(
+        max_attempts: u32,
+        initial_backoff: Duration,
+        max_backoff: Duration,
+    ) -> Self {
+        Self { max_attempts, initial_backoff, max_backoff }
+    }
+
+    /// Delay before the retry following the given attempt, counting from zero.
+    fn backoff(&self, attempt: u32) -> Duration {
+        self.initial_backoff.saturating_mul(2u32.saturating_pow(attempt)).min(self.max_backoff)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+/// Whether the call may succeed if retried.
+fn is_transient_error(err: &RpcError) -> bool {
+    match &err.error_struct {
+        Some(RpcErrorKind::InternalError(_)) => true,
+        Some(RpcErrorKind::HandlerError(error)) => error["name"] == "TIMEOUT_ERROR",
+        Some(RpcErrorKind::RequestValidationError(_)) | None => false,
+    }
+}
+
+/// Prepare a `RPCRequest` with a given client, server address, method and parameters, retrying
+/// according to `retry_policy`.
+fn call_method<P, R>(
+    client: &Client,
+    server_addr: &str,
+    retry_policy: RetryPolicy,
+    method: &str,
+    params: P,
+) -> RpcRequest<R>
 where
     P: serde::Serialize,
     R: serde::de::DeserializeOwned + 'static,
 {
     let request = Message::request(method.to_string(), serde_json::to_value(&params).unwrap());
+    let client = client.clone();
+    let server_addr = server_addr.to_string();
+    async move {
+        let mut attempt = 0;
+        loop {
+            match send_request(&client, &server_addr, &request).await {
+                Err(err) if attempt + 1 < retry_policy.max_attempts && is_transient_error(&err) => {
+                    tokio::time::sleep(retry_policy.backoff(attempt)).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+    .boxed_local()
+}
+
+/// Sends a single JSON RPC request and parses its result.
+fn send_request<R>(client: &Client, server_addr: &str, request: &Message) -> RpcRequest<R>
+where
+    R: serde::de::DeserializeOwned + 'static,
+{
     // TODO: simplify this.
     client
         .post(server_addr)
         .insert_header(("Content-Type", "application/json"))
-        .send_json(&request)
+        .send_json(request)
         .map_err(|err| RpcError::new_internal_error(None, format!("{:?}", err)))
         .and_then(|mut response| {
             response.body().limit(PAYLOAD_LIMIT).map(|body| match body {
@@ -155,12 +234,23 @@ macro_rules! jsonrpc_client {
         pub struct $struct_name {
             pub server_addr: String,
             pub client: Client,
+            pub retry_policy: RetryPolicy,
         }
 
         impl $struct_name {
             /// Creates a new RPC client backed by the given transport implementation.
             pub fn new(server_addr: &str, client: Client) -> Self {
-                $struct_name { server_addr: server_addr.to_string(), client }
+                $struct_name {
+                    server_addr: server_addr.to_string(),
+                    client,
+                    retry_policy: RetryPolicy::NONE,
+                }
+            }
+
+            /// Retries the calls failing with a transient error according to `retry_policy`.
+            pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+                self.retry_policy = retry_policy;
+                self
             }
 
             $(
@@ -170,7 +260,13 @@ macro_rules! jsonrpc_client {
                 {
                     let method = stringify!($method);
                     let params = expand_params!($($arg_name,)*);
-                    call_method(&$selff.client, &$selff.server_addr, &method, params)
+                    call_method(
+                        &$selff.client,
+                        &$selff.server_addr,
+                        $selff.retry_policy,
+                        &method,
+                        params,
+                    )
                 }
             )*
         }
@@ -189,6 +285,17 @@ jsonrpc_client!(pub struct JsonRpcClient {
 });
 
 impl JsonRpcClient {
+    /// Calls one of the methods defined in [`methods`], with the parameters and result types
+    /// the server handles it with, e.g. `client.call::<methods::Block>(request)`.
+    pub fn call<M>(&self, request: M::Request) -> RpcRequest<M::Response>
+    where
+        M: RpcMethod,
+        M::Request: serde::Serialize,
+        M::Response: serde::de::DeserializeOwned + 'static,
+    {
+        call_method(&self.client, &self.server_addr, self.retry_policy, M::NAME, request)
+    }
+
     /// This is a soft-deprecated method to do query RPC request with a path and data positional
     /// parameters.
     pub fn query_by_path(
@@ -196,26 +303,33 @@ impl JsonRpcClient {
         path: String,
         data: String,
     ) -> RpcRequest<near_jsonrpc_primitives::types::query::RpcQueryResponse> {
-        call_method(&self.client, &self.server_addr, "query", [path, data])
+        call_method(&self.client, &self.server_addr, self.retry_policy, "query", [path, data])
     }
 
     pub fn query(
         &self,
         request: near_jsonrpc_primitives::types::query::RpcQueryRequest,
     ) -> RpcRequest<near_jsonrpc_primitives::types::query::RpcQueryResponse> {
-        call_method(&self.client, &self.server_addr, "query", request)
+        self.call::<methods::Query>(request)
     }
 
     pub fn block_by_id(&self, block_id: BlockId) -> RpcRequest<BlockView> {
-        call_method(&self.client, &self.server_addr, "block", [block_id])
+        call_method(&self.client, &self.server_addr, self.retry_policy, "block", [block_id])
     }
 
     pub fn block(&self, request: BlockReference) -> RpcRequest<BlockView> {
-        call_method(&self.client, &self.server_addr, "block", request)
+        call_method(&self.client, &self.server_addr, self.retry_policy, "block", request)
+    }
+
+    pub fn block_by_timestamp(
+        &self,
+        request: near_jsonrpc_primitives::types::blocks::RpcBlockByTimestampRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::blocks::RpcBlockResponse> {
+        self.call::<methods::BlockByTimestamp>(request)
     }
 
     pub fn tx(&self, request: RpcTransactionStatusRequest) -> RpcRequest<RpcTransactionResponse> {
-        call_method(&self.client, &self.server_addr, "tx", request)
+        self.call::<methods::Tx>(request)
     }
 
     #[allow(non_snake_case)]
@@ -223,15 +337,15 @@ impl JsonRpcClient {
         &self,
         request: RpcTransactionStatusRequest,
     ) -> RpcRequest<RpcTransactionResponse> {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_status", request)
+        self.call::<methods::ExperimentalTxStatus>(request)
     }
 
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_changes(
         &self,
         request: RpcStateChangesInBlockByTypeRequest,
-    ) -> RpcRequest<RpcStateChangesInBlockByTypeResponse> {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_changes", request)
+    ) -> RpcRequest<RpcStateChangesInBlockResponse> {
+        self.call::<methods::ExperimentalChanges>(request)
     }
 
     #[allow(non_snake_case)]
@@ -239,7 +353,7 @@ impl JsonRpcClient {
         &self,
         request: RpcValidatorsOrderedRequest,
     ) -> RpcRequest<Vec<ValidatorStakeView>> {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
+        self.call::<methods::ExperimentalValidatorsOrdered>(request)
     }
 
     #[allow(non_snake_case)]
@@ -247,7 +361,7 @@ impl JsonRpcClient {
         &self,
         request: near_jsonrpc_primitives::types::receipts::RpcReceiptRequest,
     ) -> RpcRequest<near_jsonrpc_primitives::types::receipts::RpcReceiptResponse> {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_receipt", request)
+        self.call::<methods::ExperimentalReceipt>(request)
     }
 
     #[allow(non_snake_case)]
@@ -255,7 +369,40 @@ impl JsonRpcClient {
         &self,
         request: near_jsonrpc_primitives::types::config::RpcProtocolConfigRequest,
     ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse> {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
+        self.call::<methods::ExperimentalProtocolConfig>(request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_runtime_config_changes(
+        &self,
+        request: near_jsonrpc_primitives::types::config::RpcRuntimeConfigChangesRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcRuntimeConfigChangesResponse> {
+        self.call::<methods::ExperimentalRuntimeConfigChanges>(request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_epoch_config(
+        &self,
+        request: near_jsonrpc_primitives::types::config::RpcEpochConfigRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcEpochConfigResponse> {
+        self.call::<methods::ExperimentalEpochConfig>(request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_account_receipts(
+        &self,
+        request: near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsResponse> {
+        self.call::<methods::ExperimentalAccountReceipts>(request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_secondary_index(
+        &self,
+        request: near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexResponse>
+    {
+        self.call::<methods::ExperimentalSecondaryIndex>(request)
     }
 
     #[allow(non_snake_case)]
@@ -264,7 +411,7 @@ impl JsonRpcClient {
         request: near_jsonrpc_primitives::types::split_storage::RpcSplitStorageInfoRequest,
     ) -> RpcRequest<near_jsonrpc_primitives::types::split_storage::RpcSplitStorageInfoResponse>
     {
-        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_split_storage_info", request)
+        self.call::<methods::ExperimentalSplitStorageInfo>(request)
     }
 
     pub fn validators(
@@ -275,7 +422,13 @@ impl JsonRpcClient {
             Some(epoch_reference) => epoch_reference,
             _ => EpochReference::Latest,
         };
-        call_method(&self.client, &self.server_addr, "validators", epoch_reference)
+        call_method(
+            &self.client,
+            &self.server_addr,
+            self.retry_policy,
+            "validators",
+            epoch_reference,
+        )
     }
 }
 
@@ -303,3 +456,32 @@ http_client!(pub struct HttpClient {
 pub fn new_http_client(server_addr: &str) -> HttpClient {
     HttpClient::new(server_addr, create_client())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{is_transient_error, RetryPolicy};
+    use near_jsonrpc_primitives::errors::RpcError;
+    use std::time::Duration;
+
+    #[test]
+    fn test_retry_policy_backoff() {
+        let policy =
+            RetryPolicy::exponential(10, Duration::from_millis(100), Duration::from_secs(1));
+        assert_eq!(policy.backoff(0), Duration::from_millis(100));
+        assert_eq!(policy.backoff(1), Duration::from_millis(200));
+        assert_eq!(policy.backoff(3), Duration::from_millis(800));
+        assert_eq!(policy.backoff(4), Duration::from_secs(1));
+        assert_eq!(policy.backoff(100), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_transient_errors() {
+        assert!(is_transient_error(&RpcError::new_internal_error(None, "closed".to_string())));
+        let timeout = serde_json::json!({"name": "TIMEOUT_ERROR", "info": {}});
+        assert!(is_transient_error(&RpcError::new_internal_or_handler_error(None, timeout)));
+        let unknown_block = serde_json::json!({"name": "UNKNOWN_BLOCK", "info": {}});
+        assert!(!is_transient_error(&RpcError::new_internal_or_handler_error(None, unknown_block)));
+        assert!(!is_transient_error(&RpcError::parse_error("bad".to_string())));
+        assert!(!is_transient_error(&RpcError::method_not_found("foo".to_string())));
+    }
+}
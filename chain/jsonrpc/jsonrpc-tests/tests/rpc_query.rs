@@ -9,6 +9,9 @@ use serde_json::json;
 use near_actix_test_utils::run_actix;
 use near_crypto::{KeyType, PublicKey, Signature};
 use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc_primitives::errors::{RpcErrorKind, RpcRequestValidationErrorKind};
+use near_jsonrpc_primitives::methods;
+use near_jsonrpc_primitives::types::blocks::{RpcBlockByTimestampRequest, RpcBlockRequest};
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
 use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
 use near_network::test_utils::wait_or_timeout;
@@ -51,6 +54,23 @@ fn test_block_by_id_hash() {
     });
 }
 
+/// Retrieve the last block at or before a timestamp via json rpc
+#[test]
+fn test_block_by_timestamp() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let genesis = client.block_by_id(BlockId::Height(0)).await.unwrap();
+        let timestamp_nanosec = genesis.header.timestamp_nanosec;
+        let block = client
+            .block_by_timestamp(RpcBlockByTimestampRequest { timestamp_nanosec })
+            .await
+            .unwrap();
+        assert_eq!(block.block_view.header.hash, genesis.header.hash);
+
+        let request = RpcBlockByTimestampRequest { timestamp_nanosec: timestamp_nanosec - 1 };
+        assert!(client.block_by_timestamp(request).await.is_err());
+    });
+}
+
 /// Retrieve blocks via json rpc
 #[test]
 fn test_block_query() {
@@ -164,6 +184,7 @@ fn test_query_account() {
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
                 request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                decode: None,
             })
             .await
             .unwrap();
@@ -171,6 +192,7 @@ fn test_query_account() {
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Height(0)),
                 request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                decode: None,
             })
             .await
             .unwrap();
@@ -178,6 +200,7 @@ fn test_query_account() {
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::BlockId(BlockId::Hash(block_hash)),
                 request: QueryRequest::ViewAccount { account_id: "test".parse().unwrap() },
+                decode: None,
             })
             .await
             .unwrap();
@@ -227,6 +250,7 @@ fn test_query_access_keys() {
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
                 request: QueryRequest::ViewAccessKeyList { account_id: "test".parse().unwrap() },
+                decode: None,
             })
             .await
             .unwrap();
@@ -278,6 +302,7 @@ fn test_query_access_key() {
                         .parse()
                         .unwrap(),
                 },
+                decode: None,
             })
             .await
             .unwrap();
@@ -304,6 +329,7 @@ fn test_query_state() {
                     prefix: vec![].into(),
                     include_proof: false,
                 },
+                decode: None,
             })
             .await
             .unwrap();
@@ -329,6 +355,7 @@ fn test_query_call_function() {
                     method_name: "method".to_string(),
                     args: vec![].into(),
                 },
+                decode: None,
             })
             .await
             .unwrap();
@@ -354,6 +381,7 @@ fn test_query_contract_code() {
             .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
                 block_reference: BlockReference::latest(),
                 request: QueryRequest::ViewCode { account_id: "test".parse().unwrap() },
+                decode: None,
             })
             .await
             .unwrap();
@@ -508,6 +536,30 @@ fn test_gas_price() {
     });
 }
 
+/// Every method defined for the typed client is served.
+#[test]
+fn test_all_methods_are_served() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        for method in methods::Method::ALL {
+            let method = method.name();
+            let result: Result<serde_json::Value, _> =
+                test_utils::call_method(&client.client, &client.server_addr, method, json!([]))
+                    .await;
+            let method_not_found = RpcErrorKind::RequestValidationError(
+                RpcRequestValidationErrorKind::MethodNotFound { method_name: method.to_string() },
+            );
+            if let Err(err) = result {
+                assert_ne!(err.error_struct, Some(method_not_found), "{method} is not served");
+            }
+        }
+
+        let request =
+            RpcBlockRequest { block_reference: BlockReference::BlockId(BlockId::Height(0)) };
+        let block = client.call::<methods::Block>(request).await.unwrap();
+        assert_eq!(block.block_view.header.height, 0);
+    });
+}
+
 #[test]
 fn test_invalid_methods() {
     test_with_client!(test_utils::NodeType::NonValidator, client, async move {
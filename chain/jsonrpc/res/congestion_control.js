@@ -82,8 +82,51 @@ function BlocksTable({ rows }) {
     </div>
 }
 
+function ReceiversTable({ title, receivers }) {
+    return <table>
+        <tbody>
+            <tr>
+                <th colSpan="4">{title}</th>
+            </tr>
+            <tr>
+                <th>Receiver</th>
+                <th>Receipts</th>
+                <th>Gas (Tgas)</th>
+                <th>Size (MiB)</th>
+            </tr>
+            {receivers.map(receiver =>
+                <tr key={receiver.receiver_id}>
+                    <td>{receiver.receiver_id}</td>
+                    <td>{receiver.num_receipts}</td>
+                    <td>{toTgas(receiver.gas)}</td>
+                    <td>{toMiB(receiver.bytes)}</td>
+                </tr>)}
+        </tbody>
+    </table>
+}
+
+function ReceiverBackpressure({ data }) {
+    if (data == null) {
+        return <div>Loading...</div>
+    }
+    return <div>
+        <div className="explanation">At block {data.block_height} ({data.block_hash})</div>
+        {data.shards.map(shard =>
+            <div key={shard.shard_id}>
+                <h3>Shard {shard.shard_id}: {shard.num_delayed_receivers} delayed and {shard.num_buffered_receivers} buffered receivers</h3>
+                <div className="receivers">
+                    <ReceiversTable title="Delayed by gas" receivers={shard.delayed_by_gas} />
+                    <ReceiversTable title="Delayed by size" receivers={shard.delayed_by_bytes} />
+                    <ReceiversTable title="Buffered by gas" receivers={shard.buffered_by_gas} />
+                    <ReceiversTable title="Buffered by size" receivers={shard.buffered_by_bytes} />
+                </div>
+            </div>)}
+    </div>
+}
+
 function Page() {
     const [rows, setRows] = React.useState([]);
+    const [receivers, setReceivers] = React.useState(null);
     const [error, setError] = React.useState(null);
     let blockStatusApiPath = '../api/block_status';
     const url = new URL(window.location.toString());
@@ -109,6 +152,13 @@ function Page() {
                 }
                 const { status_response: { BlockStatus: data } } = await resp.json();
                 setRows(sortBlocks(data.blocks));
+
+                resp = await fetch('../api/receiver_backpressure');
+                if (!resp.ok) {
+                    throw new Error('Could not fetch receiver backpressure: ' + resp.statusText);
+                }
+                const { status_response: { ReceiverBackpressure: receivers } } = await resp.json();
+                setReceivers(receivers);
             } catch (error) {
                 setError(error);
             }
@@ -127,6 +177,9 @@ function Page() {
         <h2>Blocks</h2>
         <BlocksTable
             rows={rows} />
+        <h2>Top receivers</h2>
+        <ReceiverBackpressure
+            data={receivers} />
     </div>;
 }
 
@@ -3,7 +3,9 @@ use serde_json::Value;
 
 use near_client_primitives::types::GetBlockError;
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::blocks::{RpcBlockError, RpcBlockRequest};
+use near_jsonrpc_primitives::types::blocks::{
+    RpcBlockByTimestampRequest, RpcBlockError, RpcBlockRequest,
+};
 use near_primitives::types::BlockReference;
 
 use super::{Params, RpcFrom, RpcRequest};
@@ -17,6 +19,12 @@ impl RpcRequest for RpcBlockRequest {
     }
 }
 
+impl RpcRequest for RpcBlockByTimestampRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
 impl RpcFrom<AsyncSendError> for RpcBlockError {
     fn rpc_from(error: AsyncSendError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -39,3 +47,20 @@ impl RpcFrom<GetBlockError> for RpcBlockError {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::api::RpcRequest;
+    use near_jsonrpc_primitives::types::blocks::RpcBlockByTimestampRequest;
+
+    #[test]
+    fn test_parse_block_by_timestamp_params() {
+        let params = serde_json::json!({"timestamp_nanosec": "1700000000000000000"});
+        assert_eq!(
+            RpcBlockByTimestampRequest::parse(params).unwrap(),
+            RpcBlockByTimestampRequest { timestamp_nanosec: 1700000000000000000 }
+        );
+        let params = serde_json::json!({"timestamp": "1700000000000000000"});
+        assert!(RpcBlockByTimestampRequest::parse(params).is_err());
+    }
+}
@@ -1,7 +1,10 @@
 use near_async::messaging::AsyncSendError;
-use near_client_primitives::types::GetProtocolConfigError;
+use near_client_primitives::types::{GetEpochConfigError, GetProtocolConfigError};
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigRequest};
+use near_jsonrpc_primitives::types::config::{
+    RpcEpochConfigError, RpcEpochConfigRequest, RpcProtocolConfigError, RpcProtocolConfigRequest,
+    RpcRuntimeConfigChangesRequest,
+};
 use serde_json::Value;
 
 use super::{Params, RpcFrom, RpcRequest};
@@ -35,3 +38,69 @@ impl RpcFrom<GetProtocolConfigError> for RpcProtocolConfigError {
         }
     }
 }
+
+impl RpcRequest for RpcRuntimeConfigChangesRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value).map(|block_reference| Self { block_reference })
+    }
+}
+
+impl RpcRequest for RpcEpochConfigRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value).map(|epoch_reference| Self { epoch_reference })
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcEpochConfigError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetEpochConfigError> for RpcEpochConfigError {
+    fn rpc_from(error: GetEpochConfigError) -> Self {
+        match error {
+            GetEpochConfigError::UnknownEpoch => Self::UnknownEpoch,
+            GetEpochConfigError::IOError(error_message) => Self::InternalError { error_message },
+            GetEpochConfigError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcEpochConfigError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::RpcRequest;
+    use near_jsonrpc_primitives::types::config::RpcEpochConfigRequest;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::{BlockId, EpochId, EpochReference};
+
+    #[test]
+    fn test_parse_epoch_config_params() {
+        let epoch_id = CryptoHash::new();
+        let params = serde_json::json!({"epoch_id": epoch_id.to_string()});
+        assert_eq!(
+            RpcEpochConfigRequest::parse(params).unwrap(),
+            RpcEpochConfigRequest { epoch_reference: EpochReference::EpochId(EpochId(epoch_id)) }
+        );
+
+        let params = serde_json::json!({"block_id": 12345});
+        assert_eq!(
+            RpcEpochConfigRequest::parse(params).unwrap(),
+            RpcEpochConfigRequest {
+                epoch_reference: EpochReference::BlockId(BlockId::Height(12345))
+            }
+        );
+
+        let params = serde_json::json!({"latest": null});
+        assert_eq!(
+            RpcEpochConfigRequest::parse(params).unwrap(),
+            RpcEpochConfigRequest { epoch_reference: EpochReference::Latest }
+        );
+    }
+}
@@ -17,6 +17,7 @@ mod network_info;
 mod query;
 mod receipts;
 mod sandbox;
+mod secondary_index;
 mod split_storage;
 mod status;
 mod transactions;
@@ -31,7 +31,13 @@ fn parse_bs58_data(max_len: usize, encoded: String) -> Result<Vec<u8>, RpcParseE
 
 impl RpcRequest for RpcQueryRequest {
     fn parse(value: Value) -> Result<Self, RpcParseError> {
-        Params::new(value).try_pair(parse_path_data).unwrap_or_parse()
+        let request: Self = Params::new(value).try_pair(parse_path_data).unwrap_or_parse()?;
+        if let Some(standard) = &request.decode {
+            if !crate::standards::is_known_standard(standard) {
+                return Err(RpcParseError(format!("Unknown standard to decode {}", standard)));
+            }
+        }
+        Ok(request)
     }
 }
 
@@ -84,7 +90,7 @@ fn parse_path_data(path: String, data: String) -> Result<RpcQueryRequest, RpcPar
         _ => return Err(RpcParseError(format!("Unknown path {}", query_command))),
     };
     // Use Finality::None here to make backward compatibility tests work
-    Ok(RpcQueryRequest { request, block_reference: BlockReference::latest() })
+    Ok(RpcQueryRequest { request, block_reference: BlockReference::latest(), decode: None })
 }
 
 impl RpcFrom<AsyncSendError> for RpcQueryError {
@@ -130,6 +136,9 @@ impl RpcFrom<QueryError> for RpcQueryError {
             QueryError::TooLargeContractState { contract_account_id, block_height, block_hash } => {
                 Self::TooLargeContractState { contract_account_id, block_height, block_hash }
             }
+            QueryError::InvalidStateOverride { error_message, block_height, block_hash } => {
+                Self::InvalidStateOverride { error_message, block_height, block_hash }
+            }
         }
     }
 }
@@ -140,6 +149,7 @@ impl RpcFrom<QueryResponse> for RpcQueryResponse {
             kind: RpcFrom::rpc_from(query_response.kind),
             block_hash: query_response.block_hash,
             block_height: query_response.block_height,
+            decoded: None,
         }
     }
 }
@@ -167,6 +177,9 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::AccessKeyList(access_key_list) => {
                 Self::AccessKeyList(access_key_list)
             }
+            near_primitives::views::QueryResponseKind::ViewAccountBalance(account_balance_view) => {
+                Self::ViewAccountBalance(account_balance_view)
+            }
         }
     }
 }
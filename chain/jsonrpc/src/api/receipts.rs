@@ -1,9 +1,10 @@
 use super::{Params, RpcFrom, RpcRequest};
 use near_async::messaging::AsyncSendError;
-use near_client_primitives::types::{GetReceipt, GetReceiptError};
+use near_client_primitives::types::{GetAccountReceiptsError, GetReceipt, GetReceiptError};
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::receipts::{
-    ReceiptReference, RpcReceiptError, RpcReceiptRequest,
+    ReceiptReference, RpcAccountReceiptsError, RpcAccountReceiptsRequest, RpcReceiptError,
+    RpcReceiptRequest,
 };
 use serde_json::Value;
 
@@ -40,3 +41,80 @@ impl RpcFrom<GetReceiptError> for RpcReceiptError {
         }
     }
 }
+
+impl RpcRequest for RpcAccountReceiptsRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcAccountReceiptsError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetAccountReceiptsError> for RpcAccountReceiptsError {
+    fn rpc_from(error: GetAccountReceiptsError) -> Self {
+        match error {
+            GetAccountReceiptsError::IOError(error_message) => {
+                Self::InternalError { error_message }
+            }
+            GetAccountReceiptsError::IndexDisabled => Self::IndexDisabled,
+            GetAccountReceiptsError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcAccountReceiptsError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::RpcRequest;
+    use near_jsonrpc_primitives::types::receipts::{
+        AccountReceiptsCursor, RpcAccountReceiptsRequest,
+    };
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::views::AccountReceiptDirection;
+
+    #[test]
+    fn test_parse_account_receipts_params() {
+        let params = serde_json::json!({"account_id": "alice.near"});
+        assert_eq!(
+            RpcAccountReceiptsRequest::parse(params).unwrap(),
+            RpcAccountReceiptsRequest {
+                account_id: "alice.near".parse().unwrap(),
+                block_window: None,
+                direction: None,
+                cursor: None,
+                limit: None,
+                include_outcomes: false,
+            }
+        );
+
+        let receipt_id = CryptoHash::hash_bytes(b"receipt");
+        let params = serde_json::json!({
+            "account_id": "alice.near",
+            "block_window": 100,
+            "direction": "incoming",
+            "cursor": {"block_height": 12345, "receipt_id": receipt_id.to_string()},
+            "limit": 10,
+            "include_outcomes": true,
+        });
+        assert_eq!(
+            RpcAccountReceiptsRequest::parse(params).unwrap(),
+            RpcAccountReceiptsRequest {
+                account_id: "alice.near".parse().unwrap(),
+                block_window: Some(100),
+                direction: Some(AccountReceiptDirection::Incoming),
+                cursor: Some(AccountReceiptsCursor { block_height: 12345, receipt_id }),
+                limit: Some(10),
+                include_outcomes: true,
+            }
+        );
+    }
+}
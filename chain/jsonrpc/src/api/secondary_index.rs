@@ -0,0 +1,88 @@
+use super::{Params, RpcFrom, RpcRequest};
+use near_async::messaging::AsyncSendError;
+use near_client_primitives::types::GetSecondaryIndexError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::secondary_index::{
+    RpcSecondaryIndexError, RpcSecondaryIndexRequest,
+};
+use serde_json::Value;
+
+impl RpcRequest for RpcSecondaryIndexRequest {
+    fn parse(value: Value) -> Result<Self, RpcParseError> {
+        Params::parse(value)
+    }
+}
+
+impl RpcFrom<AsyncSendError> for RpcSecondaryIndexError {
+    fn rpc_from(error: AsyncSendError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetSecondaryIndexError> for RpcSecondaryIndexError {
+    fn rpc_from(error: GetSecondaryIndexError) -> Self {
+        match error {
+            GetSecondaryIndexError::IOError(error_message) => Self::InternalError { error_message },
+            GetSecondaryIndexError::IndexDisabled(index) => Self::IndexDisabled { index },
+            GetSecondaryIndexError::Unreachable(ref error_message) => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcSecondaryIndexError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::api::RpcRequest;
+    use near_jsonrpc_primitives::types::secondary_index::{
+        RpcSecondaryIndexRequest, SecondaryIndexCursor,
+    };
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::views::SecondaryIndexQuery;
+
+    #[test]
+    fn test_parse_secondary_index_params() {
+        let params = serde_json::json!({
+            "index": "transactions_by_account",
+            "account_id": "alice.near",
+        });
+        assert_eq!(
+            RpcSecondaryIndexRequest::parse(params).unwrap(),
+            RpcSecondaryIndexRequest {
+                query: SecondaryIndexQuery::TransactionsByAccount {
+                    account_id: "alice.near".parse().unwrap(),
+                },
+                cursor: None,
+                limit: None,
+            }
+        );
+
+        let block_hash = CryptoHash::hash_bytes(b"block");
+        let params = serde_json::json!({
+            "index": "blocks_by_timestamp",
+            "from_timestamp_nanosec": "1700000000000000000",
+            "cursor": {"position": "1700000000000000001", "hash": block_hash.to_string()},
+            "limit": 10,
+        });
+        assert_eq!(
+            RpcSecondaryIndexRequest::parse(params).unwrap(),
+            RpcSecondaryIndexRequest {
+                query: SecondaryIndexQuery::BlocksByTimestamp {
+                    from_timestamp_nanosec: 1700000000000000000,
+                },
+                cursor: Some(SecondaryIndexCursor {
+                    position: 1700000000000000001,
+                    hash: block_hash,
+                }),
+                limit: Some(10),
+            }
+        );
+
+        let params = serde_json::json!({"index": "unknown_index", "account_id": "alice.near"});
+        assert!(RpcSecondaryIndexRequest::parse(params).is_err());
+    }
+}
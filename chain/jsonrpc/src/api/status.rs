@@ -50,6 +50,21 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::DelayedReceipts(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::DelayedReceipts(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ReceiverBackpressure(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ReceiverBackpressure(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ReshardingStatus(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ReshardingStatus(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ReshardingBufferedReceipts(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ReshardingBufferedReceipts(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ReshardingEvents(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ReshardingEvents(x)
+            }
         }
     }
 }
@@ -68,6 +83,9 @@ impl RpcFrom<near_network::debug::DebugStatus>
             near_network::debug::DebugStatus::RecentOutboundConnections(x) => {
                 near_jsonrpc_primitives::types::status::DebugStatusResponse::RecentOutboundConnections(x)
             }
+            near_network::debug::DebugStatus::ConnectionAuditLog(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ConnectionAuditLog(x)
+            }
             near_network::debug::DebugStatus::Routes(x) => {
                 near_jsonrpc_primitives::types::status::DebugStatusResponse::Routes(x)
             }
@@ -9,18 +9,22 @@ use near_async::actix::ActixResult;
 use near_async::messaging::{
     AsyncSendError, AsyncSender, CanSend, MessageWithCallback, SendAsync, Sender,
 };
-use near_chain_configs::GenesisConfig;
+use near_chain_configs::{GenesisConfig, ReshardingHandleState};
 use near_client::{
-    DebugStatus, GetBlock, GetBlockProof, GetChunk, GetClientConfig, GetExecutionOutcome,
-    GetGasPrice, GetMaintenanceWindows, GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig,
-    GetReceipt, GetStateChanges, GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
-    ProcessTxRequest, ProcessTxResponse, Query, Status, TxStatus,
+    DebugStatus, GetAccountReceipts, GetBlock, GetBlockByTimestamp, GetBlockProof, GetChunk,
+    GetClientConfig, GetEpochConfig, GetExecutionOutcome, GetGasPrice, GetMaintenanceWindows,
+    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt,
+    GetRuntimeConfigChanges, GetSecondaryIndex, GetStateChanges, GetStateChangesInBlock,
+    GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest, ProcessTxResponse, Query,
+    ReshardingControl, Status, TxStatus,
 };
+use near_client_primitives::debug::{ThreadAssignmentView, ThreadPoolsView};
 use near_client_primitives::types::GetSplitStorageInfo;
 pub use near_jsonrpc_client as client;
 pub use near_jsonrpc_primitives as primitives;
 use near_jsonrpc_primitives::errors::{RpcError, RpcErrorKind};
 use near_jsonrpc_primitives::message::{Message, Request};
+use near_jsonrpc_primitives::methods::{self, RpcMethod};
 use near_jsonrpc_primitives::types::blocks::RpcBlockRequest;
 use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigResponse};
 use near_jsonrpc_primitives::types::entity_debug::{EntityDebugHandler, EntityQueryWithParams};
@@ -36,7 +40,7 @@ use near_network::tcp::{self, ListenerAddr};
 use near_o11y::metrics::{prometheus, Encoder, TextEncoder};
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::{AccountId, BlockHeight, BlockId, BlockReference};
+use near_primitives::types::{AccountId, BlockHeight, BlockHeightDelta, BlockId, BlockReference};
 use near_primitives::views::{QueryRequest, TxExecutionStatus};
 use serde_json::{json, Value};
 use std::path::PathBuf;
@@ -47,6 +51,7 @@ use tracing::{error, info};
 
 mod api;
 mod metrics;
+mod standards;
 
 #[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -92,6 +97,11 @@ pub struct RpcConfig {
     // We disable it by default, as some of those endpoints might be quite CPU heavy.
     #[serde(default = "default_enable_debug_rpc")]
     pub enable_debug_rpc: bool,
+    // If true (together with `enable_debug_rpc`), enable the debug endpoints that pause, resume
+    // or abort the resharding running on the node. They change the node state and are not
+    // authenticated, so they need a separate opt-in.
+    #[serde(default)]
+    pub enable_resharding_control: bool,
     // For node developers only: if specified, the HTML files used to serve the debug pages will
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
@@ -107,6 +117,7 @@ impl Default for RpcConfig {
             polling_config: Default::default(),
             limits_config: Default::default(),
             enable_debug_rpc: false,
+            enable_resharding_control: false,
             experimental_debug_pages_src_path: None,
         }
     }
@@ -145,6 +156,26 @@ where
     serialize_response(callback(R::parse(request.params)?).await?)
 }
 
+/// Processes a call to one of the methods defined in [`methods`], like [`process_method_call`].
+///
+/// The `callback` must take the parameters and return the result of the method definition,
+/// which the typed client relies upon.
+async fn process_rpc_method_call<M, F>(
+    _method: M,
+    request: Request,
+    callback: impl FnOnce(M::Request) -> F,
+) -> Result<Value, RpcError>
+where
+    M: RpcMethod,
+    M::Request: RpcRequest,
+    M::Response: serde::ser::Serialize,
+    RpcError: std::convert::From<M::Error>,
+    F: std::future::Future<Output = Result<M::Response, M::Error>>,
+{
+    debug_assert_eq!(request.method, M::NAME);
+    process_method_call(request, callback).await
+}
+
 #[easy_ext::ext(FromNetworkClientResponses)]
 impl near_jsonrpc_primitives::types::transactions::RpcTransactionError {
     pub fn from_network_client_responses(resp: ProcessTxResponse) -> Self {
@@ -154,6 +185,13 @@ impl near_jsonrpc_primitives::types::transactions::RpcTransactionError {
             ProcessTxResponse::DoesNotTrackShard | ProcessTxResponse::RequestRouted => {
                 Self::DoesNotTrackShard
             }
+            ProcessTxResponse::SignerShardCongested { error, retry_after } => {
+                Self::ShardCongested {
+                    context: error,
+                    retry_after_secs: retry_after
+                        .map(|retry_after| retry_after.whole_seconds().max(1) as u64),
+                }
+            }
             internal_error => Self::InternalError { debug_info: format!("{:?}", internal_error) },
         }
     }
@@ -227,6 +265,7 @@ pub struct ClientSenderForRpc(
     AsyncSender<GetClientConfig, ActixResult<GetClientConfig>>,
     AsyncSender<GetNetworkInfo, ActixResult<GetNetworkInfo>>,
     AsyncSender<ProcessTxRequest, ActixResult<ProcessTxRequest>>,
+    AsyncSender<ReshardingControl, ActixResult<ReshardingControl>>,
     AsyncSender<Status, ActixResult<Status>>,
     Sender<ProcessTxRequest>,
     #[cfg(feature = "test_features")] Sender<near_client::NetworkAdversarialMessage>,
@@ -244,15 +283,20 @@ pub struct ClientSenderForRpc(
 
 #[derive(Clone, near_async::MultiSend, near_async::MultiSenderFrom)]
 pub struct ViewClientSenderForRpc(
+    AsyncSender<GetAccountReceipts, ActixResult<GetAccountReceipts>>,
     AsyncSender<GetBlock, ActixResult<GetBlock>>,
+    AsyncSender<GetBlockByTimestamp, ActixResult<GetBlockByTimestamp>>,
     AsyncSender<GetBlockProof, ActixResult<GetBlockProof>>,
     AsyncSender<GetChunk, ActixResult<GetChunk>>,
+    AsyncSender<GetEpochConfig, ActixResult<GetEpochConfig>>,
     AsyncSender<GetExecutionOutcome, ActixResult<GetExecutionOutcome>>,
     AsyncSender<GetGasPrice, ActixResult<GetGasPrice>>,
     AsyncSender<GetMaintenanceWindows, ActixResult<GetMaintenanceWindows>>,
     AsyncSender<GetNextLightClientBlock, ActixResult<GetNextLightClientBlock>>,
     AsyncSender<GetProtocolConfig, ActixResult<GetProtocolConfig>>,
     AsyncSender<GetReceipt, ActixResult<GetReceipt>>,
+    AsyncSender<GetRuntimeConfigChanges, ActixResult<GetRuntimeConfigChanges>>,
+    AsyncSender<GetSecondaryIndex, ActixResult<GetSecondaryIndex>>,
     AsyncSender<GetSplitStorageInfo, ActixResult<GetSplitStorageInfo>>,
     AsyncSender<GetStateChanges, ActixResult<GetStateChanges>>,
     AsyncSender<GetStateChangesInBlock, ActixResult<GetStateChangesInBlock>>,
@@ -284,6 +328,7 @@ struct JsonRpcHandler {
     polling_config: RpcPollingConfig,
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
+    enable_resharding_control: bool,
     debug_pages_src_path: Option<PathBuf>,
     entity_debug_handler: Arc<dyn EntityDebugHandler>,
 }
@@ -337,14 +382,15 @@ impl JsonRpcHandler {
             Err(request) => request,
         };
 
-        match request.method.as_ref() {
-            "query" => {
+        match methods::Method::from_name(&request.method) {
+            Some(methods::Method::Query) => {
                 let params: RpcQueryRequest = match RpcRequest::parse(request.params) {
                     Ok(params) => params,
                     Err(err) => return (method_name, Err(RpcError::from(err))),
                 };
                 let metrics_name = match params.request {
                     QueryRequest::ViewAccount { .. } => "query_view_account",
+                    QueryRequest::ViewAccountBalance { .. } => "query_view_account_balance",
                     QueryRequest::ViewCode { .. } => "query_view_code",
                     QueryRequest::ViewState { include_proof, .. } => {
                         if include_proof {
@@ -356,6 +402,7 @@ impl JsonRpcHandler {
                     QueryRequest::ViewAccessKey { .. } => "query_view_access_key",
                     QueryRequest::ViewAccessKeyList { .. } => "query_view_access_key_list",
                     QueryRequest::CallFunction { .. } => "query_call_function",
+                    QueryRequest::SimulateFunctionCall { .. } => "query_simulate_function_call",
                 };
                 (metrics_name.to_string(), process_query_response(self.query(params).await))
             }
@@ -369,82 +416,198 @@ impl JsonRpcHandler {
         &self,
         request: Request,
     ) -> Result<Result<Value, RpcError>, Request> {
-        Ok(match request.method.as_ref() {
+        let Some(method) = methods::Method::from_name(&request.method) else {
+            return self.process_untyped_requests_internal(request).await;
+        };
+        Ok(match method {
             // Handlers ordered alphabetically
-            "block" => process_method_call(request, |params| self.block(params)).await,
-            "broadcast_tx_async" => {
-                process_method_call(request, |params| async {
+            methods::Method::Block => {
+                process_rpc_method_call(methods::Block, request, |params| self.block(params)).await
+            }
+            methods::Method::BlockByTimestamp => {
+                process_rpc_method_call(methods::BlockByTimestamp, request, |params| {
+                    self.block_by_timestamp(params)
+                })
+                .await
+            }
+            methods::Method::BroadcastTxAsync => {
+                process_rpc_method_call(methods::BroadcastTxAsync, request, |params| async {
                     let tx = self.send_tx_async(params).await.to_string();
                     Result::<_, std::convert::Infallible>::Ok(tx)
                 })
                 .await
             }
-            "broadcast_tx_commit" => {
-                process_method_call(request, |params| self.send_tx_commit(params)).await
+            methods::Method::BroadcastTxCommit => {
+                process_rpc_method_call(methods::BroadcastTxCommit, request, |params| {
+                    self.send_tx_commit(params)
+                })
+                .await
+            }
+            methods::Method::Chunk => {
+                process_rpc_method_call(methods::Chunk, request, |params| self.chunk(params)).await
+            }
+            methods::Method::GasPrice => {
+                process_rpc_method_call(methods::GasPrice, request, |params| self.gas_price(params))
+                    .await
+            }
+            methods::Method::Health => {
+                process_rpc_method_call(methods::Health, request, |_params: ()| self.health()).await
             }
-            "chunk" => process_method_call(request, |params| self.chunk(params)).await,
-            "gas_price" => process_method_call(request, |params| self.gas_price(params)).await,
-            "health" => process_method_call(request, |_params: ()| self.health()).await,
-            "light_client_proof" => {
-                process_method_call(request, |params| {
+            methods::Method::LightClientProof => {
+                process_rpc_method_call(methods::LightClientProof, request, |params| {
                     self.light_client_execution_outcome_proof(params)
                 })
                 .await
             }
-            "next_light_client_block" => {
-                process_method_call(request, |params| self.next_light_client_block(params)).await
+            methods::Method::NextLightClientBlock => {
+                process_rpc_method_call(methods::NextLightClientBlock, request, |params| {
+                    self.next_light_client_block(params)
+                })
+                .await
+            }
+            methods::Method::NetworkInfo => {
+                process_rpc_method_call(methods::NetworkInfo, request, |_params: ()| {
+                    self.network_info()
+                })
+                .await
             }
-            "network_info" => process_method_call(request, |_params: ()| self.network_info()).await,
-            "send_tx" => process_method_call(request, |params| self.send_tx(params)).await,
-            "status" => process_method_call(request, |_params: ()| self.status()).await,
-            "tx" => {
-                process_method_call(request, |params| self.tx_status_common(params, false)).await
+            methods::Method::SendTx => {
+                process_rpc_method_call(methods::SendTx, request, |params| self.send_tx(params))
+                    .await
             }
-            "validators" => process_method_call(request, |params| self.validators(params)).await,
-            "client_config" => {
-                process_method_call(request, |_params: ()| self.client_config()).await
+            methods::Method::Status => {
+                process_rpc_method_call(methods::Status, request, |_params: ()| self.status()).await
+            }
+            methods::Method::Tx => {
+                process_rpc_method_call(methods::Tx, request, |params| {
+                    self.tx_status_common(params, false)
+                })
+                .await
             }
-            "EXPERIMENTAL_changes" => {
-                process_method_call(request, |params| self.changes_in_block_by_type(params)).await
+            methods::Method::Validators => {
+                process_rpc_method_call(methods::Validators, request, |params| {
+                    self.validators(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_changes_in_block" => {
-                process_method_call(request, |params| self.changes_in_block(params)).await
+            methods::Method::ClientConfig => {
+                process_rpc_method_call(methods::ClientConfig, request, |_params: ()| {
+                    self.client_config()
+                })
+                .await
             }
-            "EXPERIMENTAL_congestion_level" => {
-                process_method_call(request, |params| self.congestion_level(params)).await
+            methods::Method::ExperimentalAccountReceipts => {
+                process_rpc_method_call(methods::ExperimentalAccountReceipts, request, |params| {
+                    self.account_receipts(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_genesis_config" => {
-                process_method_call(request, |_params: ()| async {
-                    Result::<_, std::convert::Infallible>::Ok(&self.genesis_config)
+            methods::Method::ExperimentalChanges => {
+                process_rpc_method_call(methods::ExperimentalChanges, request, |params| {
+                    self.changes_in_block_by_type(params)
                 })
                 .await
             }
-            "EXPERIMENTAL_light_client_proof" => {
-                process_method_call(request, |params| {
+            methods::Method::ExperimentalChangesInBlock => {
+                process_rpc_method_call(methods::ExperimentalChangesInBlock, request, |params| {
+                    self.changes_in_block(params)
+                })
+                .await
+            }
+            methods::Method::ExperimentalCongestionLevel => {
+                process_rpc_method_call(methods::ExperimentalCongestionLevel, request, |params| {
+                    self.congestion_level(params)
+                })
+                .await
+            }
+            methods::Method::ExperimentalEpochConfig => {
+                process_rpc_method_call(methods::ExperimentalEpochConfig, request, |params| {
+                    self.epoch_config(params)
+                })
+                .await
+            }
+            methods::Method::ExperimentalLightClientProof => {
+                process_rpc_method_call(methods::ExperimentalLightClientProof, request, |params| {
                     self.light_client_execution_outcome_proof(params)
                 })
                 .await
             }
-            "EXPERIMENTAL_light_client_block_proof" => {
-                process_method_call(request, |params| self.light_client_block_proof(params)).await
+            methods::Method::ExperimentalLightClientBlockProof => {
+                process_rpc_method_call(
+                    methods::ExperimentalLightClientBlockProof,
+                    request,
+                    |params| self.light_client_block_proof(params),
+                )
+                .await
             }
-            "EXPERIMENTAL_protocol_config" => {
-                process_method_call(request, |params| self.protocol_config(params)).await
+            methods::Method::ExperimentalProtocolConfig => {
+                process_rpc_method_call(methods::ExperimentalProtocolConfig, request, |params| {
+                    self.protocol_config(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_receipt" => {
-                process_method_call(request, |params| self.receipt(params)).await
+            methods::Method::ExperimentalReceipt => {
+                process_rpc_method_call(methods::ExperimentalReceipt, request, |params| {
+                    self.receipt(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_tx_status" => {
-                process_method_call(request, |params| self.tx_status_common(params, true)).await
+            methods::Method::ExperimentalRuntimeConfigChanges => {
+                process_rpc_method_call(
+                    methods::ExperimentalRuntimeConfigChanges,
+                    request,
+                    |params| self.runtime_config_changes(params),
+                )
+                .await
             }
-            "EXPERIMENTAL_validators_ordered" => {
-                process_method_call(request, |params| self.validators_ordered(params)).await
+            methods::Method::ExperimentalSecondaryIndex => {
+                process_rpc_method_call(methods::ExperimentalSecondaryIndex, request, |params| {
+                    self.secondary_index(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_maintenance_windows" => {
-                process_method_call(request, |params| self.maintenance_windows(params)).await
+            methods::Method::ExperimentalTxStatus => {
+                process_rpc_method_call(methods::ExperimentalTxStatus, request, |params| {
+                    self.tx_status_common(params, true)
+                })
+                .await
+            }
+            methods::Method::ExperimentalValidatorsOrdered => {
+                process_rpc_method_call(methods::ExperimentalValidatorsOrdered, request, |params| {
+                    self.validators_ordered(params)
+                })
+                .await
+            }
+            methods::Method::ExperimentalMaintenanceWindows => {
+                process_rpc_method_call(
+                    methods::ExperimentalMaintenanceWindows,
+                    request,
+                    |params| self.maintenance_windows(params),
+                )
+                .await
+            }
+            methods::Method::ExperimentalSplitStorageInfo => {
+                process_rpc_method_call(methods::ExperimentalSplitStorageInfo, request, |params| {
+                    self.split_storage_info(params)
+                })
+                .await
             }
-            "EXPERIMENTAL_split_storage_info" => {
-                process_method_call(request, |params| self.split_storage_info(params)).await
+            // Handled by the caller, to label the metrics by the type of query.
+            methods::Method::Query => return Err(request),
+        })
+    }
+
+    /// Handles the requests of the methods which aren't defined in [`methods`].
+    async fn process_untyped_requests_internal(
+        &self,
+        request: Request,
+    ) -> Result<Result<Value, RpcError>, Request> {
+        Ok(match request.method.as_ref() {
+            "EXPERIMENTAL_genesis_config" => {
+                process_method_call(request, |_params: ()| async {
+                    Result::<_, std::convert::Infallible>::Ok(&self.genesis_config)
+                })
+                .await
             }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
@@ -800,6 +963,21 @@ impl JsonRpcHandler {
                     "/debug/api/requested_state_parts" => {
                         self.client_send(DebugStatus::RequestedStateParts).await?.rpc_into()
                     }
+                    "/debug/api/delayed_receipts" => {
+                        self.client_send(DebugStatus::DelayedReceipts).await?.rpc_into()
+                    }
+                    "/debug/api/receiver_backpressure" => {
+                        self.client_send(DebugStatus::ReceiverBackpressure).await?.rpc_into()
+                    }
+                    "/debug/api/resharding_status" => {
+                        self.client_send(DebugStatus::ReshardingStatus).await?.rpc_into()
+                    }
+                    "/debug/api/resharding_buffered_receipts" => {
+                        self.client_send(DebugStatus::ReshardingBufferedReceipts).await?.rpc_into()
+                    }
+                    "/debug/api/resharding_events" => {
+                        self.client_send(DebugStatus::ReshardingEvents).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
@@ -814,6 +992,10 @@ impl JsonRpcHandler {
                         )
                         .await?
                         .rpc_into(),
+                    "/debug/api/connection_audit_log" => self
+                        .peer_manager_send(near_network::debug::GetDebugStatus::ConnectionAuditLog)
+                        .await?
+                        .rpc_into(),
                     #[cfg(feature = "distance_vector_routing")]
                     "/debug/api/network_routes" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::Routes)
@@ -830,6 +1012,28 @@ impl JsonRpcHandler {
                             .map_err(|e| e.into_rpc_status_error())?;
                         near_jsonrpc_primitives::types::status::DebugStatusResponse::SplitStoreStatus(split_storage_info.result)
                     }
+                    "/debug/api/thread_pools" => {
+                        let client_config = self.client_config().await.map_err(|err| {
+                            near_jsonrpc_primitives::types::status::RpcStatusError::InternalError {
+                                error_message: err.to_string(),
+                            }
+                        })?;
+                        let pinned_threads =
+                            near_performance_metrics::affinity::thread_assignments()
+                                .into_iter()
+                                .map(|assignment| ThreadAssignmentView {
+                                    pool: assignment.pool,
+                                    thread: assignment.thread,
+                                    cpu_set: assignment.cpu_set,
+                                })
+                                .collect();
+                        near_jsonrpc_primitives::types::status::DebugStatusResponse::ThreadPools(
+                            ThreadPoolsView {
+                                config: client_config.client_config.thread_pools,
+                                pinned_threads,
+                            },
+                        )
+                    }
                     _ => return Ok(None),
                 };
             Ok(Some(near_jsonrpc_primitives::types::status::RpcDebugStatusResponse {
@@ -840,6 +1044,18 @@ impl JsonRpcHandler {
         }
     }
 
+    pub async fn resharding_control(
+        &self,
+        control: ReshardingControl,
+    ) -> Result<Option<ReshardingHandleState>, near_jsonrpc_primitives::types::status::RpcStatusError>
+    {
+        if self.enable_debug_rpc && self.enable_resharding_control {
+            Ok(Some(self.client_send(control).await?))
+        } else {
+            Ok(None)
+        }
+    }
+
     pub async fn debug_block_status(
         &self,
         starting_height: Option<BlockHeight>,
@@ -870,6 +1086,44 @@ impl JsonRpcHandler {
         Ok(RpcProtocolConfigResponse { config_view })
     }
 
+    pub async fn runtime_config_changes(
+        &self,
+        request_data: near_jsonrpc_primitives::types::config::RpcRuntimeConfigChangesRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::config::RpcRuntimeConfigChangesResponse,
+        near_jsonrpc_primitives::types::config::RpcProtocolConfigError,
+    > {
+        let near_client::RuntimeConfigChanges {
+            current_protocol_version,
+            next_protocol_version,
+            changes,
+        } = self.view_client_send(GetRuntimeConfigChanges(request_data.block_reference)).await?;
+        Ok(near_jsonrpc_primitives::types::config::RpcRuntimeConfigChangesResponse {
+            current_protocol_version,
+            next_protocol_version,
+            changes,
+        })
+    }
+
+    /// Returns the `EpochConfig` that is actually in effect for the given epoch, i.e. after
+    /// applying the stored overrides and resolving the epoch's protocol version.
+    pub async fn epoch_config(
+        &self,
+        request_data: near_jsonrpc_primitives::types::config::RpcEpochConfigRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::config::RpcEpochConfigResponse,
+        near_jsonrpc_primitives::types::config::RpcEpochConfigError,
+    > {
+        let near_client::EffectiveEpochConfig { epoch_id, protocol_version, epoch_config } = self
+            .view_client_send(GetEpochConfig { epoch_reference: request_data.epoch_reference })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::config::RpcEpochConfigResponse {
+            epoch_id,
+            protocol_version,
+            epoch_config,
+        })
+    }
+
     async fn query(
         &self,
         request_data: near_jsonrpc_primitives::types::query::RpcQueryRequest,
@@ -877,10 +1131,28 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::query::RpcQueryResponse,
         near_jsonrpc_primitives::types::query::RpcQueryError,
     > {
+        let method_name = match &request_data.request {
+            QueryRequest::CallFunction { method_name, .. } => Some(method_name.clone()),
+            _ => None,
+        };
         let query_response = self
             .view_client_send(Query::new(request_data.block_reference, request_data.request))
             .await?;
-        Ok(query_response.rpc_into())
+        let mut response: near_jsonrpc_primitives::types::query::RpcQueryResponse =
+            query_response.rpc_into();
+        if let Some(standard) = &request_data.decode {
+            response.decoded = match (&response.kind, method_name) {
+                (near_jsonrpc_primitives::types::query::QueryResponseKind::ViewState(state), _) => {
+                    standards::decode_view_state(standard, &state.values)
+                }
+                (
+                    near_jsonrpc_primitives::types::query::QueryResponseKind::CallResult(result),
+                    Some(method_name),
+                ) => standards::decode_call_result(standard, &method_name, &result.result),
+                _ => None,
+            };
+        }
+        Ok(response)
     }
 
     async fn tx_status_common(
@@ -908,6 +1180,22 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::blocks::RpcBlockResponse { block_view })
     }
 
+    /// Returns the last block of the canonical chain produced at or before the given time.
+    async fn block_by_timestamp(
+        &self,
+        request_data: near_jsonrpc_primitives::types::blocks::RpcBlockByTimestampRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::blocks::RpcBlockResponse,
+        near_jsonrpc_primitives::types::blocks::RpcBlockError,
+    > {
+        let block_view = self
+            .view_client_send(GetBlockByTimestamp {
+                timestamp_nanosec: request_data.timestamp_nanosec,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::blocks::RpcBlockResponse { block_view })
+    }
+
     async fn chunk(
         &self,
         request_data: near_jsonrpc_primitives::types::chunks::RpcChunkRequest,
@@ -955,6 +1243,71 @@ impl JsonRpcHandler {
         })
     }
 
+    /// Lists the receipts executed or created by an account in the most recent blocks, using
+    /// the index maintained by the node when `save_account_receipts` is enabled.
+    async fn account_receipts(
+        &self,
+        request_data: near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsResponse,
+        near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsError,
+    > {
+        const DEFAULT_LIMIT: u32 = 100;
+        const MAX_LIMIT: u32 = 1000;
+
+        let limit = request_data.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let near_client::AccountReceiptsResponse { receipts, next } = self
+            .view_client_send(GetAccountReceipts {
+                account_id: request_data.account_id,
+                block_window: request_data.block_window.unwrap_or(BlockHeightDelta::MAX),
+                direction: request_data.direction,
+                after: request_data.cursor.map(|cursor| (cursor.block_height, cursor.receipt_id)),
+                limit: limit as usize,
+                include_outcomes: request_data.include_outcomes,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::receipts::RpcAccountReceiptsResponse {
+            receipts,
+            next_cursor: next.map(|(block_height, receipt_id)| {
+                near_jsonrpc_primitives::types::receipts::AccountReceiptsCursor {
+                    block_height,
+                    receipt_id,
+                }
+            }),
+        })
+    }
+
+    /// Lists the entries of one of the secondary indexes enabled in the
+    /// `secondary_indexes` config of the node.
+    async fn secondary_index(
+        &self,
+        request_data: near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexResponse,
+        near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexError,
+    > {
+        const DEFAULT_LIMIT: u32 = 100;
+        const MAX_LIMIT: u32 = 1000;
+
+        let limit = request_data.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+        let near_client::SecondaryIndexResponse { entries, next } = self
+            .view_client_send(GetSecondaryIndex {
+                query: request_data.query,
+                after: request_data.cursor.map(|cursor| (cursor.position, cursor.hash)),
+                limit: limit as usize,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::secondary_index::RpcSecondaryIndexResponse {
+            entries,
+            next_cursor: next.map(|(position, hash)| {
+                near_jsonrpc_primitives::types::secondary_index::SecondaryIndexCursor {
+                    position,
+                    hash,
+                }
+            }),
+        })
+    }
+
     async fn receipt(
         &self,
         request_data: near_jsonrpc_primitives::types::receipts::RpcReceiptRequest,
@@ -1472,6 +1825,23 @@ async fn debug_block_status_handler(
     }
 }
 
+async fn resharding_control_handler(
+    path: web::Path<String>,
+    handler: web::Data<JsonRpcHandler>,
+) -> Result<HttpResponse, HttpError> {
+    let control = match path.as_str() {
+        "pause" => ReshardingControl::Pause,
+        "resume" => ReshardingControl::Resume,
+        "abort" => ReshardingControl::Abort,
+        _ => return Ok(HttpResponse::NotFound().finish()),
+    };
+    match handler.resharding_control(control).await {
+        Ok(Some(value)) => Ok(HttpResponse::Ok().json(&value)),
+        Ok(None) => Ok(HttpResponse::MethodNotAllowed().finish()),
+        Err(_) => Ok(HttpResponse::ServiceUnavailable().finish()),
+    }
+}
+
 async fn health_handler(handler: web::Data<JsonRpcHandler>) -> Result<HttpResponse, HttpError> {
     match handler.health().await {
         Ok(value) => Ok(HttpResponse::Ok().json(&value)),
@@ -1563,6 +1933,7 @@ async fn display_debug_html(
         "validator.css" => Some(debug_page_string!("validator.css", handler)),
         "split_store" => Some(debug_page_string!("split_store.html", handler)),
         "congestion_control" => Some(debug_page_string!("congestion_control.html", handler)),
+        "resharding" => Some(debug_page_string!("resharding.html", handler)),
         "congestion_control.css" => Some(debug_page_string!("congestion_control.css", handler)),
         "congestion_control.js" => Some(debug_page_string!("congestion_control.js", handler)),
         _ => None,
@@ -1603,6 +1974,7 @@ pub fn start_http(
         polling_config,
         limits_config,
         enable_debug_rpc,
+        enable_resharding_control,
         experimental_debug_pages_src_path: debug_pages_src_path,
     } = config;
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr.to_string());
@@ -1619,6 +1991,7 @@ pub fn start_http(
                 polling_config,
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
+                enable_resharding_control,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
                 entity_debug_handler: entity_debug_handler.clone(),
                 #[cfg(feature = "test_features")]
@@ -1640,6 +2013,10 @@ pub fn start_http(
             .service(web::resource("/network_info").route(web::get().to(network_info_handler)))
             .service(web::resource("/metrics").route(web::get().to(prometheus_handler)))
             .service(web::resource("/debug/api/entity").route(web::post().to(handle_entity_debug)))
+            .service(
+                web::resource("/debug/api/resharding/{action}")
+                    .route(web::post().to(resharding_control_handler)),
+            )
             .service(web::resource("/debug/api/{api}").route(web::get().to(debug_handler)))
             .service(
                 web::resource("/debug/api/block_status/{starting_height}")
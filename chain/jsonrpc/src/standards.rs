@@ -0,0 +1,156 @@
+//! Decoding of `view_state` and `call_function` query results of contracts implementing known
+//! standards, such as NEP-141 fungible tokens and NEP-171 non-fungible tokens. The methods and
+//! the storage layouts of the standards are described in `res/standards.json`, so supporting a
+//! new standard or layout doesn't need code changes.
+
+use near_primitives::views::StateItem;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::LazyLock;
+
+/// How the bytes of a method result, a storage key or a storage value are encoded.
+#[derive(serde::Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+enum Format {
+    Json,
+    BorshString,
+    /// Returned as a decimal string, as JSON numbers can't hold all the `u128` values.
+    BorshU128,
+}
+
+impl Format {
+    fn decode(self, bytes: &[u8]) -> Option<Value> {
+        match self {
+            Format::Json => serde_json::from_slice(bytes).ok(),
+            Format::BorshString => {
+                let (len, data) = bytes.split_first_chunk::<4>()?;
+                if u32::from_le_bytes(*len) as usize != data.len() {
+                    return None;
+                }
+                String::from_utf8(data.to_vec()).ok().map(Value::String)
+            }
+            Format::BorshU128 => {
+                let bytes: [u8; 16] = bytes.try_into().ok()?;
+                Some(Value::String(u128::from_le_bytes(bytes).to_string()))
+            }
+        }
+    }
+}
+
+/// Collection of the contract state stored under the same key prefix.
+#[derive(serde::Deserialize, Debug)]
+struct StateCollection {
+    name: String,
+    /// Hex-encoded prefix of the keys of the collection.
+    #[serde(deserialize_with = "deserialize_hex")]
+    prefix: Vec<u8>,
+    key: Format,
+    value: Format,
+}
+
+#[derive(serde::Deserialize, Debug)]
+struct Standard {
+    methods: BTreeMap<String, Format>,
+    state: Vec<StateCollection>,
+}
+
+fn deserialize_hex<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex: String = serde::Deserialize::deserialize(deserializer)?;
+    hex::decode(hex).map_err(serde::de::Error::custom)
+}
+
+static STANDARDS: LazyLock<BTreeMap<String, Standard>> = LazyLock::new(|| {
+    serde_json::from_str(include_str!("../res/standards.json")).expect("invalid standards table")
+});
+
+/// Whether `standard` can be requested in the `decode` field of a query.
+pub(crate) fn is_known_standard(standard: &str) -> bool {
+    STANDARDS.contains_key(standard)
+}
+
+/// Decodes the result of a view call of `method_name`, if the standard defines the method and
+/// the result has the expected format.
+pub(crate) fn decode_call_result(
+    standard: &str,
+    method_name: &str,
+    result: &[u8],
+) -> Option<Value> {
+    let format = STANDARDS.get(standard)?.methods.get(method_name)?;
+    format.decode(result)
+}
+
+/// Decodes the state items belonging to the collections of the standard into an object with a
+/// map from the decoded keys to the decoded values for each collection. The items which don't
+/// belong to any collection or fail to decode are left out.
+pub(crate) fn decode_view_state(standard: &str, values: &[StateItem]) -> Option<Value> {
+    let standard = STANDARDS.get(standard)?;
+    let mut decoded = Map::new();
+    for collection in &standard.state {
+        let mut entries = Map::new();
+        for item in values {
+            let Some(key) = item.key.strip_prefix(collection.prefix.as_slice()) else {
+                continue;
+            };
+            let (Some(key), Some(value)) =
+                (collection.key.decode(key), collection.value.decode(&item.value))
+            else {
+                continue;
+            };
+            let key = match key {
+                Value::String(key) => key,
+                key => key.to_string(),
+            };
+            entries.insert(key, value);
+        }
+        decoded.insert(collection.name.clone(), Value::Object(entries));
+    }
+    Some(Value::Object(decoded))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn borsh_string(s: &str) -> Vec<u8> {
+        let mut bytes = (s.len() as u32).to_le_bytes().to_vec();
+        bytes.extend(s.as_bytes());
+        bytes
+    }
+
+    #[test]
+    fn test_standards_table_is_valid() {
+        assert!(is_known_standard("nep141"));
+        assert!(is_known_standard("nep171"));
+        assert!(!is_known_standard("nep999"));
+    }
+
+    #[test]
+    fn test_decode_call_result() {
+        assert_eq!(
+            decode_call_result("nep141", "ft_balance_of", br#""1000""#),
+            Some(Value::String("1000".to_string()))
+        );
+        assert_eq!(decode_call_result("nep141", "unknown_method", br#""1000""#), None);
+        assert_eq!(decode_call_result("nep141", "ft_balance_of", b"not json"), None);
+    }
+
+    #[test]
+    fn test_decode_view_state() {
+        let mut balance_key = b"a".to_vec();
+        balance_key.extend(borsh_string("alice.near"));
+        let values = vec![
+            StateItem {
+                key: balance_key.into(),
+                value: 1_000_000_000_000_000_000_000_000u128.to_le_bytes().to_vec().into(),
+            },
+            StateItem { key: b"STATE".to_vec().into(), value: vec![1, 2, 3].into() },
+        ];
+        assert_eq!(
+            decode_view_state("nep141", &values),
+            Some(serde_json::json!({
+                "balances": { "alice.near": "1000000000000000000000000" }
+            }))
+        );
+        assert_eq!(decode_view_state("nep999", &values), None);
+    }
+}
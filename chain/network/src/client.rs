@@ -1,9 +1,11 @@
 use crate::network_protocol::StateResponseInfo;
 use crate::types::{NetworkInfo, ReasonForBan};
 use near_async::messaging::{AsyncSender, Sender};
+use near_async::time::Duration;
 use near_async::{MultiSend, MultiSendMessage, MultiSenderFrom};
 use near_primitives::block::{Approval, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
+use near_primitives::chunk_suppression::ChunkSuppression;
 use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
@@ -60,6 +62,9 @@ pub struct BlockHeadersResponse(pub Vec<BlockHeader>, pub PeerId);
 pub struct StateRequestHeader {
     pub shard_id: ShardId,
     pub sync_hash: CryptoHash,
+    /// Whether the requesting peer can decode a header with batched proofs, see
+    /// `PeerCapability::BatchedStateSyncHeaderProofs`.
+    pub batched_proofs: bool,
 }
 
 /// State request part.
@@ -112,6 +117,10 @@ pub enum ProcessTxResponse {
     /// The node being queried does not track the shard needed and therefore cannot provide userful
     /// response.
     DoesNotTrackShard,
+    /// The shard of the signer of the transaction is too congested to include it in time, see
+    /// `ClientConfig::tx_admission_congestion_threshold`. The error is either `ShardCongested`
+    /// or `ShardStuck`, and the transaction may be retried after `retry_after` if provided.
+    SignerShardCongested { error: InvalidTxError, retry_after: Option<Duration> },
 }
 
 /// Account announcements that needs to be validated before being processed.
@@ -125,6 +134,10 @@ pub struct AnnounceAccountRequest(pub Vec<(AnnounceAccount, Option<EpochId>)>);
 #[rtype(result = "()")]
 pub struct ChunkEndorsementMessage(pub ChunkEndorsement);
 
+#[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
+#[rtype(result = "()")]
+pub struct ChunkSuppressionMessage(pub ChunkSuppression);
+
 #[derive(actix::Message, Debug, Clone, PartialEq, Eq)]
 #[rtype(result = "()")]
 pub struct EpochSyncRequestMessage {
@@ -158,6 +171,7 @@ pub struct ClientSenderForNetwork {
     pub announce_account:
         AsyncSender<AnnounceAccountRequest, Result<Vec<AnnounceAccount>, ReasonForBan>>,
     pub chunk_endorsement: AsyncSender<ChunkEndorsementMessage, ()>,
+    pub chunk_suppression: AsyncSender<ChunkSuppressionMessage, ()>,
     pub epoch_sync_request: Sender<EpochSyncRequestMessage>,
     pub epoch_sync_response: Sender<EpochSyncResponseMessage>,
 }
@@ -10,12 +10,22 @@ pub(crate) struct Runtime {
 
 impl Runtime {
     pub fn new() -> Self {
+        Self::with_cpu_set(None)
+    }
+
+    /// Same as `new`, but the thread of the runtime is pinned to the given CPUs.
+    pub fn with_cpu_set(cpu_set: Option<Vec<usize>>) -> Self {
         let stop = Arc::new(tokio::sync::Notify::new());
         let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build().unwrap();
         let handle = runtime.handle().clone();
         let thread = std::thread::spawn({
             let stop = stop.clone();
-            move || runtime.block_on(stop.notified())
+            move || {
+                if let Some(cpu_set) = cpu_set {
+                    near_performance_metrics::affinity::pin_current_thread("network", &cpu_set);
+                }
+                runtime.block_on(stop.notified())
+            }
         });
         Self { handle, stop, thread: Some(thread) }
     }
@@ -1,6 +1,7 @@
 use crate::blacklist;
 use crate::concurrency::rate;
 use crate::network_protocol::PeerAddr;
+use crate::network_protocol::PeerCapabilities;
 use crate::network_protocol::PeerInfo;
 use crate::peer_manager::peer_store;
 use crate::rate_limits::messages_limits;
@@ -93,6 +94,9 @@ pub struct Tier1 {
     /// Interval between broadcasts of the list of validator's proxies.
     /// Before the broadcast, validator tries to establish all the missing connections to proxies.
     pub advertise_proxies_interval: time::Duration,
+    /// Interval between self-checks of reachability of the addresses advertised
+    /// in this node's AccountData. Results are exported as metrics.
+    pub reachability_check_interval: time::Duration,
     /// Support for gradual TIER1 feature rollout:
     /// - establishing connection to node's own proxies is always enabled (it is a part of peer
     ///   discovery mechanism). Note that unless the proxy has enable_inbound set, establishing
@@ -177,6 +181,9 @@ pub struct NetworkConfig {
     pub inbound_disabled: bool,
     /// Whether this is an archival node.
     pub archive: bool,
+    /// Capabilities advertised in the handshake. The features which the peer doesn't
+    /// advertise as well are not used on the connection.
+    pub capabilities: PeerCapabilities,
     /// Maximal rate at which SyncAccountsData can be broadcasted.
     pub accounts_data_broadcast_rate_limit: rate::Limit,
     /// Maximal rate at which SyncSnapshotHosts can be broadcasted.
@@ -195,6 +202,8 @@ pub struct NetworkConfig {
 
     /// Configuration of rate limits for incoming messages.
     pub received_messages_rate_limits: messages_limits::Config,
+    /// CPUs the threads running the peer manager are pinned to. Not pinned if not set.
+    pub cpu_set: Option<Vec<usize>>,
 
     #[cfg(test)]
     pub(crate) event_sink:
@@ -362,6 +371,13 @@ impl NetworkConfig {
             push_info_period: time::Duration::milliseconds(100),
             outbound_disabled: false,
             archive,
+            capabilities: {
+                let mut capabilities = PeerCapabilities::supported();
+                for capability in &cfg.experimental.disabled_capabilities {
+                    capabilities.remove(*capability);
+                }
+                capabilities
+            },
             accounts_data_broadcast_rate_limit: rate::Limit { qps: 0.1, burst: 1 },
             snapshot_hosts_broadcast_rate_limit: rate::Limit { qps: 0.1, burst: 1 },
             routing_table_update_rate_limit: rate::Limit { qps: 1., burst: 1 },
@@ -369,6 +385,7 @@ impl NetworkConfig {
                 connect_interval: cfg.experimental.tier1_connect_interval.try_into()?,
                 new_connections_per_attempt: cfg.experimental.tier1_new_connections_per_attempt,
                 advertise_proxies_interval: time::Duration::minutes(15),
+                reachability_check_interval: time::Duration::minutes(10),
                 enable_inbound: cfg.experimental.tier1_enable_inbound,
                 enable_outbound: cfg.experimental.tier1_enable_outbound,
             }),
@@ -380,6 +397,7 @@ impl NetworkConfig {
             },
             // Use a preset to configure rate limits and override entries with user defined values later.
             received_messages_rate_limits: messages_limits::Config::standard_preset(),
+            cpu_set: None,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -444,6 +462,7 @@ impl NetworkConfig {
             outbound_disabled: false,
             inbound_disabled: false,
             archive: false,
+            capabilities: PeerCapabilities::supported(),
             accounts_data_broadcast_rate_limit: rate::Limit { qps: 100., burst: 1000000 },
             snapshot_hosts_broadcast_rate_limit: rate::Limit { qps: 100., burst: 1000000 },
             routing_table_update_rate_limit: rate::Limit { qps: 10., burst: 1 },
@@ -453,11 +472,13 @@ impl NetworkConfig {
                 connect_interval: time::Duration::hours(1000),
                 new_connections_per_attempt: 10000,
                 advertise_proxies_interval: time::Duration::hours(1000),
+                reachability_check_interval: time::Duration::hours(1000),
                 enable_inbound: true,
                 enable_outbound: true,
             }),
             skip_tombstones: None,
             received_messages_rate_limits: messages_limits::Config::default(),
+            cpu_set: None,
             #[cfg(test)]
             event_sink: near_async::messaging::IntoSender::into_sender(
                 near_async::messaging::noop(),
@@ -552,7 +573,7 @@ mod test {
     use crate::config_json::NetworkConfigOverrides;
     use crate::network_protocol;
     use crate::network_protocol::testonly as data;
-    use crate::network_protocol::{AccountData, VersionedAccountData};
+    use crate::network_protocol::{AccountData, ReachabilityAttestation, VersionedAccountData};
     use crate::rate_limits::messages_limits::{
         RateLimitedPeerMessageKey::BlockHeaders, SingleMessageConfig,
     };
@@ -676,15 +697,24 @@ mod test {
         let clock = time::FakeClock::default();
         let signer = data::make_validator_signer(&mut rng);
 
+        let proxies: Vec<_> = (0..config::MAX_PEER_ADDRS)
+            .map(|_| {
+                // Using IPv6 gives maximal size of the resulting config.
+                let ip = data::make_ipv6(&mut rng);
+                data::make_peer_addr(&mut rng, ip)
+            })
+            .collect();
         let ad = VersionedAccountData {
             data: AccountData {
-                proxies: (0..config::MAX_PEER_ADDRS)
-                    .map(|_| {
-                        // Using IPv6 gives maximal size of the resulting config.
-                        let ip = data::make_ipv6(&mut rng);
-                        data::make_peer_addr(&mut rng, ip)
+                // All the proxies have been verified to be reachable.
+                reachability: proxies
+                    .iter()
+                    .map(|proxy| ReachabilityAttestation {
+                        proxy: proxy.clone(),
+                        verified_at: clock.now_utc(),
                     })
                     .collect(),
+                proxies,
                 peer_id: data::make_peer_id(&mut rng),
             },
             account_key: signer.public_key(),
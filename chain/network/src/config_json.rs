@@ -1,4 +1,5 @@
 use crate::network_protocol::PeerAddr;
+use crate::network_protocol::PeerCapability;
 use crate::rate_limits::messages_limits;
 use crate::stun;
 use near_async::time::Duration;
@@ -265,6 +266,12 @@ pub struct ExperimentalConfig {
     #[serde(default = "default_tier1_new_connections_per_attempt")]
     pub tier1_new_connections_per_attempt: u64,
 
+    /// Capabilities which this node doesn't advertise in the handshake, e.g. to stop using a
+    /// feature of the network protocol while it is being rolled out.
+    /// See `near_network::types::PeerCapability`.
+    #[serde(default)]
+    pub disabled_capabilities: Vec<PeerCapability>,
+
     /// See `NetworkConfig`.
     /// Fields set here will override the NetworkConfig fields.
     #[serde(default)]
@@ -299,6 +306,7 @@ impl Default for ExperimentalConfig {
             tier1_enable_outbound: default_tier1_enable_outbound(),
             tier1_connect_interval: default_tier1_connect_interval(),
             tier1_new_connections_per_attempt: default_tier1_new_connections_per_attempt(),
+            disabled_capabilities: vec![],
             network_config_overrides: Default::default(),
         }
     }
@@ -1,7 +1,8 @@
 use ::actix::Message;
 use near_primitives::views::NetworkRoutesView;
 use near_primitives::views::{
-    NetworkGraphView, PeerStoreView, RecentOutboundConnectionsView, SnapshotHostsView,
+    ConnectionAuditLogView, NetworkGraphView, PeerStoreView, RecentOutboundConnectionsView,
+    SnapshotHostsView,
 };
 
 // Different debug requests that can be sent by HTML pages, via GET.
@@ -9,6 +10,7 @@ pub enum GetDebugStatus {
     PeerStore,
     Graph,
     RecentOutboundConnections,
+    ConnectionAuditLog,
     Routes,
     SnapshotHosts,
 }
@@ -18,6 +20,7 @@ pub enum DebugStatus {
     PeerStore(PeerStoreView),
     Graph(NetworkGraphView),
     RecentOutboundConnections(RecentOutboundConnectionsView),
+    ConnectionAuditLog(ConnectionAuditLogView),
     Routes(NetworkRoutesView),
     SnapshotHosts(SnapshotHostsView),
 }
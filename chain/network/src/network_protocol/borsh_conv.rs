@@ -14,6 +14,8 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
             owned_account: None,
+            earliest_block_height: None,
+            capabilities: None,
         }
     }
 }
@@ -0,0 +1,178 @@
+/// Capabilities which the peers advertise in the handshake, see `Handshake::capabilities`.
+///
+/// A feature of the network protocol which not every peer supports (e.g. because it is being
+/// rolled out, or because the node operator disabled it) gets a capability. It is used with a
+/// peer only if both ends of the connection advertised it, instead of checking the protocol
+/// version of the peer.
+use super::RoutedMessageBody;
+
+/// A feature of the network protocol. The discriminant is the index of the bit of the feature
+/// in `PeerCapabilities`, so it must never change, and the bits of the removed features must
+/// not be reused.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::EnumIter,
+    strum::IntoStaticStr,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum PeerCapability {
+    /// Compression of the messages. Not implemented yet, so not advertised.
+    Compression = 0,
+    /// Connections over QUIC. Not implemented yet, so not advertised.
+    Quic = 1,
+    /// Receiving and relaying the partial encoded state witnesses over TIER1.
+    PartialWitness = 2,
+    /// Gossiping the hosts serving state snapshots, see `SyncSnapshotHosts`.
+    SnapshotHosts = 3,
+    /// Receiving state sync headers with the proofs of the outgoing receipts roots batched per
+    /// block, see `ShardStateSyncResponseHeaderV3`.
+    BatchedStateSyncHeaderProofs = 4,
+}
+
+impl PeerCapability {
+    fn bit(self) -> u64 {
+        1 << self as u64
+    }
+
+    /// Capability which the peer receiving the message over TIER1 needs to support.
+    pub(crate) fn required_for_tier1(body: &RoutedMessageBody) -> Option<PeerCapability> {
+        match body {
+            RoutedMessageBody::PartialEncodedStateWitness(_)
+            | RoutedMessageBody::PartialEncodedStateWitnessForward(_) => {
+                Some(PeerCapability::PartialWitness)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Set of `PeerCapability`, encoded as a bitset in the handshake. The unknown bits, i.e. the
+/// capabilities added by newer versions of the binary, are kept but never negotiated.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct PeerCapabilities(u64);
+
+impl PeerCapabilities {
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Capabilities implemented by this binary.
+    pub fn supported() -> Self {
+        [
+            PeerCapability::PartialWitness,
+            PeerCapability::SnapshotHosts,
+            PeerCapability::BatchedStateSyncHeaderProofs,
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    /// Capabilities of the peers which don't advertise any in the handshake. These peers were
+    /// released before the capabilities were introduced, so they support only the features
+    /// which existed back then.
+    pub fn legacy() -> Self {
+        [PeerCapability::PartialWitness, PeerCapability::SnapshotHosts].into_iter().collect()
+    }
+
+    pub fn from_bits(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    pub fn contains(&self, capability: PeerCapability) -> bool {
+        self.0 & capability.bit() != 0
+    }
+
+    pub fn insert(&mut self, capability: PeerCapability) {
+        self.0 |= capability.bit();
+    }
+
+    pub fn remove(&mut self, capability: PeerCapability) {
+        self.0 &= !capability.bit();
+    }
+
+    /// Capabilities which can be used on a connection: the ones advertised by both ends.
+    /// `peer` is `None` if the peer didn't advertise its capabilities, in which case it is
+    /// assumed to support the `legacy()` ones.
+    pub fn negotiate(&self, peer: Option<PeerCapabilities>) -> Self {
+        Self(self.0 & peer.unwrap_or_else(Self::legacy).0)
+    }
+}
+
+impl FromIterator<PeerCapability> for PeerCapabilities {
+    fn from_iter<I: IntoIterator<Item = PeerCapability>>(iter: I) -> Self {
+        let mut capabilities = Self::empty();
+        for capability in iter {
+            capabilities.insert(capability);
+        }
+        capabilities
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use strum::IntoEnumIterator as _;
+
+    #[test]
+    fn bits_are_stable() {
+        // The bits are part of the network protocol.
+        let bits: Vec<_> = PeerCapability::iter().map(|c| (<&str>::from(c), c.bit())).collect();
+        assert_eq!(
+            bits,
+            vec![
+                ("compression", 1),
+                ("quic", 2),
+                ("partial_witness", 4),
+                ("snapshot_hosts", 8),
+                ("batched_state_sync_header_proofs", 16)
+            ]
+        );
+    }
+
+    #[test]
+    fn negotiate() {
+        let ours: PeerCapabilities =
+            [PeerCapability::Compression, PeerCapability::SnapshotHosts].into_iter().collect();
+        let theirs: PeerCapabilities =
+            [PeerCapability::Compression, PeerCapability::PartialWitness].into_iter().collect();
+        let negotiated = ours.negotiate(Some(theirs));
+        assert!(negotiated.contains(PeerCapability::Compression));
+        assert!(!negotiated.contains(PeerCapability::SnapshotHosts));
+        assert!(!negotiated.contains(PeerCapability::PartialWitness));
+        assert_eq!(negotiated, theirs.negotiate(Some(ours)));
+
+        // Unknown capabilities of a newer peer are ignored.
+        let newer = PeerCapabilities::from_bits(theirs.bits() | 1 << 63);
+        assert_eq!(ours.negotiate(Some(newer)), negotiated);
+
+        // A peer which doesn't advertise capabilities supports the legacy ones.
+        let negotiated = ours.negotiate(None);
+        assert!(negotiated.contains(PeerCapability::SnapshotHosts));
+        assert!(!negotiated.contains(PeerCapability::Compression));
+        let negotiated = PeerCapabilities::supported().negotiate(None);
+        assert!(!negotiated.contains(PeerCapability::BatchedStateSyncHeaderProofs));
+    }
+
+    #[test]
+    fn disabling_a_capability_stops_using_it() {
+        let mut ours = PeerCapabilities::supported();
+        ours.remove(PeerCapability::SnapshotHosts);
+        for peer in [None, Some(PeerCapabilities::supported())] {
+            let negotiated = ours.negotiate(peer);
+            assert!(!negotiated.contains(PeerCapability::SnapshotHosts));
+            assert!(negotiated.contains(PeerCapability::PartialWitness));
+        }
+    }
+}
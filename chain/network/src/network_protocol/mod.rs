@@ -2,11 +2,14 @@
 #[path = "borsh.rs"]
 mod borsh_;
 mod borsh_conv;
+mod capabilities;
 mod edge;
 mod peer;
 mod proto_conv;
 mod state_sync;
+pub use capabilities::*;
 pub use edge::*;
+use near_primitives::chunk_suppression::ChunkSuppression;
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 use near_primitives::stateless_validation::contract_distribution::ChunkContractAccesses;
 use near_primitives::stateless_validation::contract_distribution::ContractCodeRequest;
@@ -127,6 +130,17 @@ pub struct AccountData {
     /// TIER1 nodes should connect to one of the proxies to sent TIER1
     /// messages to the validator.
     pub proxies: Vec<PeerAddr>,
+    /// Proxies which the validator has managed to connect to, together with the time
+    /// of the connection. Since AccountData is signed by the validator, these serve
+    /// as self-signed attestations that the advertised addresses were reachable.
+    pub reachability: Vec<ReachabilityAttestation>,
+}
+
+/// Statement of the validator that it has connected to `proxy` at `verified_at`.
+#[derive(Clone, PartialEq, Eq, Debug, Hash)]
+pub struct ReachabilityAttestation {
+    pub proxy: PeerAddr,
+    pub verified_at: time::Utc,
 }
 
 /// Wrapper of the AccountData which adds metadata to it.
@@ -365,6 +379,10 @@ pub struct Handshake {
     pub(crate) partial_edge_info: PartialEdgeInfo,
     /// Account owned by the sender.
     pub(crate) owned_account: Option<SignedOwnedAccount>,
+    /// Height of the earliest block available at the sender, at the time of the handshake.
+    pub(crate) earliest_block_height: Option<BlockHeight>,
+    /// Capabilities of the sender. Not set by the peers which predate the capabilities.
+    pub(crate) capabilities: Option<PeerCapabilities>,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -564,6 +582,7 @@ pub enum RoutedMessageBody {
     ContractCodeRequest(ContractCodeRequest),
     ContractCodeResponse(ContractCodeResponse),
     PartialEncodedContractDeploys(PartialEncodedContractDeploys),
+    ChunkSuppression(ChunkSuppression),
 }
 
 impl RoutedMessageBody {
@@ -588,7 +607,8 @@ impl RoutedMessageBody {
         match self {
             RoutedMessageBody::PartialEncodedStateWitness(_)
             | RoutedMessageBody::PartialEncodedStateWitnessForward(_)
-            | RoutedMessageBody::VersionedChunkEndorsement(_) => true,
+            | RoutedMessageBody::VersionedChunkEndorsement(_)
+            | RoutedMessageBody::ChunkSuppression(_) => true,
             _ => false,
         }
     }
@@ -667,6 +687,9 @@ impl fmt::Debug for RoutedMessageBody {
             RoutedMessageBody::PartialEncodedContractDeploys(deploys) => {
                 write!(f, "PartialEncodedContractDeploys(part={:?}", deploys.part())
             }
+            RoutedMessageBody::ChunkSuppression(suppression) => {
+                write!(f, "ChunkSuppression({:?})", suppression.chunk_production_key())
+            }
         }
     }
 }
@@ -4,11 +4,42 @@ use super::*;
 use crate::network_protocol::proto;
 use crate::network_protocol::proto::account_key_payload::Payload_type as ProtoPT;
 use crate::network_protocol::{
-    AccountData, AccountKeySignedPayload, OwnedAccount, SignedAccountData, SignedOwnedAccount,
-    VersionedAccountData,
+    AccountData, AccountKeySignedPayload, OwnedAccount, ReachabilityAttestation, SignedAccountData,
+    SignedOwnedAccount, VersionedAccountData,
 };
 use protobuf::{Message as _, MessageField as MF};
 
+#[derive(thiserror::Error, Debug)]
+pub enum ParseReachabilityAttestationError {
+    #[error("proxy: {0}")]
+    Proxy(ParseRequiredError<ParsePeerAddrError>),
+    #[error("verified_at: {0}")]
+    VerifiedAt(ParseRequiredError<ParseTimestampError>),
+}
+
+impl From<&ReachabilityAttestation> for proto::ReachabilityAttestation {
+    fn from(x: &ReachabilityAttestation) -> Self {
+        Self {
+            proxy: MF::some((&x.proxy).into()),
+            verified_at: MF::some(utc_to_proto(&x.verified_at)),
+            ..Self::default()
+        }
+    }
+}
+
+impl TryFrom<&proto::ReachabilityAttestation> for ReachabilityAttestation {
+    type Error = ParseReachabilityAttestationError;
+    fn try_from(x: &proto::ReachabilityAttestation) -> Result<Self, Self::Error> {
+        Ok(Self {
+            proxy: try_from_required(&x.proxy).map_err(Self::Error::Proxy)?,
+            verified_at: map_from_required(&x.verified_at, utc_from_proto)
+                .map_err(Self::Error::VerifiedAt)?,
+        })
+    }
+}
+
+//////////////////////////////////////////
+
 #[derive(thiserror::Error, Debug)]
 pub enum ParseAccountDataError {
     #[error("bad payload type")]
@@ -19,6 +50,8 @@ pub enum ParseAccountDataError {
     AccountKey(ParseRequiredError<ParsePublicKeyError>),
     #[error("peers: {0}")]
     Peers(ParseVecError<ParsePeerAddrError>),
+    #[error("reachability: {0}")]
+    Reachability(ParseVecError<ParseReachabilityAttestationError>),
     #[error("timestamp: {0}")]
     Timestamp(ParseRequiredError<ParseTimestampError>),
 }
@@ -31,6 +64,7 @@ impl From<&VersionedAccountData> for proto::AccountKeyPayload {
                 peer_id: MF::some((&x.peer_id).into()),
                 account_key: MF::some((&x.account_key).into()),
                 proxies: x.proxies.iter().map(Into::into).collect(),
+                reachability: x.reachability.iter().map(Into::into).collect(),
                 version: x.version,
                 timestamp: MF::some(utc_to_proto(&x.timestamp)),
                 ..Default::default()
@@ -51,6 +85,7 @@ impl TryFrom<&proto::AccountKeyPayload> for VersionedAccountData {
             data: AccountData {
                 peer_id: try_from_required(&x.peer_id).map_err(Self::Error::PeerId)?,
                 proxies: try_from_slice(&x.proxies).map_err(Self::Error::Peers)?,
+                reachability: try_from_slice(&x.reachability).map_err(Self::Error::Reachability)?,
             },
             account_key: try_from_required(&x.account_key).map_err(Self::Error::AccountKey)?,
             version: x.version,
@@ -2,7 +2,7 @@
 use super::*;
 
 use crate::network_protocol::proto;
-use crate::network_protocol::{Handshake, HandshakeFailureReason};
+use crate::network_protocol::{Handshake, HandshakeFailureReason, PeerCapabilities};
 use crate::network_protocol::{PeerChainInfoV2, PeerInfo};
 use near_primitives::block::GenesisId;
 use protobuf::MessageField as MF;
@@ -90,6 +90,8 @@ impl From<&Handshake> for proto::Handshake {
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
             owned_account: x.owned_account.as_ref().map(Into::into).into(),
+            earliest_block_height: x.earliest_block_height,
+            capabilities: x.capabilities.map(|c| c.bits()),
             ..Self::default()
         }
     }
@@ -120,6 +122,8 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::PartialEdgeInfo)?,
             owned_account: try_from_optional(&p.owned_account)
                 .map_err(Self::Error::OwnedAccount)?,
+            earliest_block_height: p.earliest_block_height,
+            capabilities: p.capabilities.map(PeerCapabilities::from_bits),
         })
     }
 }
@@ -277,6 +277,7 @@ impl Chain {
         ChainInfo {
             tracked_shards: Default::default(),
             block: self.blocks.last().unwrap().clone(),
+            earliest_block_height: self.blocks[0].header().height(),
             tier1_accounts: Arc::new(self.get_tier1_accounts()),
         }
     }
@@ -337,6 +338,8 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         sender_chain_info: chain.get_peer_chain_info(),
         partial_edge_info: make_partial_edge(rng),
         owned_account: None,
+        earliest_block_height: None,
+        capabilities: None,
     }
 }
 
@@ -372,24 +375,29 @@ pub fn make_account_data(
     account_key: PublicKey,
     peer_id: PeerId,
 ) -> VersionedAccountData {
+    let proxies = vec![
+        // Can't inline make_ipv4/ipv6 calls, because 2-phase borrow
+        // doesn't work.
+        {
+            let ip = make_ipv4(rng);
+            make_peer_addr(rng, ip)
+        },
+        {
+            let ip = make_ipv4(rng);
+            make_peer_addr(rng, ip)
+        },
+        {
+            let ip = make_ipv6(rng);
+            make_peer_addr(rng, ip)
+        },
+    ];
     VersionedAccountData {
         data: AccountData {
-            proxies: vec![
-                // Can't inline make_ipv4/ipv6 calls, because 2-phase borrow
-                // doesn't work.
-                {
-                    let ip = make_ipv4(rng);
-                    make_peer_addr(rng, ip)
-                },
-                {
-                    let ip = make_ipv4(rng);
-                    make_peer_addr(rng, ip)
-                },
-                {
-                    let ip = make_ipv6(rng);
-                    make_peer_addr(rng, ip)
-                },
-            ],
+            reachability: vec![ReachabilityAttestation {
+                proxy: proxies[0].clone(),
+                verified_at: timestamp,
+            }],
+            proxies,
             peer_id,
         },
         account_key,
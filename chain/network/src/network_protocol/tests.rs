@@ -48,6 +48,7 @@ fn bad_account_data_size() {
                 })
                 .collect(),
             peer_id: data::make_peer_id(&mut rng),
+            reachability: vec![],
         },
         account_key: signer.public_key(),
         version: rng.gen(),
@@ -63,6 +64,14 @@ fn serialize_deserialize_protobuf_only() {
     let chain = data::Chain::make(&mut clock, &mut rng, 12);
     let msgs = [
         PeerMessage::Tier1Handshake(data::make_handshake(&mut rng, &chain)),
+        PeerMessage::Tier2Handshake(Handshake {
+            earliest_block_height: Some(chain.blocks[3].header().height()),
+            ..data::make_handshake(&mut rng, &chain)
+        }),
+        PeerMessage::Tier3Handshake(Handshake {
+            capabilities: Some(PeerCapabilities::supported()),
+            ..data::make_handshake(&mut rng, &chain)
+        }),
         PeerMessage::SyncAccountsData(SyncAccountsData {
             accounts_data: (0..4)
                 .map(|_| Arc::new(data::make_signed_account_data(&mut rng, &clock.clock())))
@@ -11,9 +11,9 @@ use crate::config::PEERS_RESPONSE_MAX_PEERS;
 use crate::network_protocol::DistanceVector;
 use crate::network_protocol::{
     Edge, EdgeState, Encoding, OwnedAccount, ParsePeerMessageError, PartialEdgeInfo,
-    PeerChainInfoV2, PeerIdOrHash, PeerInfo, PeersRequest, PeersResponse, RawRoutedMessage,
-    RoutedMessageBody, RoutingTableUpdate, SnapshotHostInfoVerificationError, SyncAccountsData,
-    SyncSnapshotHosts,
+    PeerCapability, PeerChainInfoV2, PeerIdOrHash, PeerInfo, PeersRequest, PeersResponse,
+    RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, SnapshotHostInfoVerificationError,
+    SyncAccountsData, SyncSnapshotHosts,
 };
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
@@ -466,11 +466,15 @@ impl PeerActor {
     }
 
     fn send_handshake(&self, spec: HandshakeSpec) {
-        let (height, tracked_shards) =
+        let (height, tracked_shards, earliest_block_height) =
             if let Some(chain_info) = self.network_state.chain_info.load().as_ref() {
-                (chain_info.block.header().height(), chain_info.tracked_shards.clone())
+                (
+                    chain_info.block.header().height(),
+                    chain_info.tracked_shards.clone(),
+                    Some(chain_info.earliest_block_height),
+                )
             } else {
-                (0, vec![])
+                (0, vec![], None)
             };
         let handshake = Handshake {
             protocol_version: spec.protocol_version,
@@ -494,6 +498,8 @@ impl PeerActor {
                 }
                 .sign(&signer)
             }),
+            earliest_block_height,
+            capabilities: Some(self.network_state.config.capabilities),
         };
         let msg = match spec.tier {
             tcp::Tier::T1 => PeerMessage::Tier1Handshake(handshake),
@@ -674,6 +680,8 @@ impl PeerActor {
             genesis_id: handshake.sender_chain_info.genesis_id.clone(),
             tracked_shards: handshake.sender_chain_info.tracked_shards.clone(),
             archival: handshake.sender_chain_info.archival,
+            earliest_block_height: handshake.earliest_block_height,
+            capabilities: self.network_state.config.capabilities.negotiate(handshake.capabilities),
             last_block: Default::default(),
             peer_type: self.peer_type,
             stats: self.stats.clone(),
@@ -836,7 +844,9 @@ impl PeerActor {
                             // Sync the RoutingTable.
                             act.sync_routing_table();
                             // Sync snapshot hosts
-                            act.sync_snapshot_hosts();
+                            if conn.has_capability(PeerCapability::SnapshotHosts) {
+                                act.sync_snapshot_hosts();
+                            }
                         }
 
                         #[cfg(test)]
@@ -1023,6 +1033,8 @@ impl PeerActor {
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
+        let batched_state_sync_header_proofs =
+            conn.has_capability(PeerCapability::BatchedStateSyncHeaderProofs);
         let handling_future = async move {
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
@@ -1093,7 +1105,11 @@ impl PeerActor {
                 }
                 PeerMessage::StateRequestHeader(shard_id, sync_hash) => network_state
                     .client
-                    .send_async(StateRequestHeader { shard_id, sync_hash })
+                    .send_async(StateRequestHeader {
+                        shard_id,
+                        sync_hash,
+                        batched_proofs: batched_state_sync_header_proofs,
+                    })
                     .await
                     .ok()
                     .flatten()
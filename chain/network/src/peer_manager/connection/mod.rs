@@ -2,8 +2,8 @@ use crate::concurrency::arc_mutex::ArcMutex;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
 use crate::network_protocol::{
-    PeerInfo, PeerMessage, RoutedMessageBody, SignedAccountData, SignedOwnedAccount,
-    SnapshotHostInfo, SyncAccountsData, SyncSnapshotHosts,
+    PeerCapabilities, PeerCapability, PeerInfo, PeerMessage, RoutedMessageBody, SignedAccountData,
+    SignedOwnedAccount, SnapshotHostInfo, SyncAccountsData, SyncSnapshotHosts,
 };
 use crate::peer::peer_actor;
 use crate::peer::peer_actor::PeerActor;
@@ -17,7 +17,7 @@ use near_crypto::PublicKey;
 use near_o11y::WithSpanContextExt;
 use near_primitives::block::GenesisId;
 use near_primitives::network::PeerId;
-use near_primitives::types::ShardId;
+use near_primitives::types::{BlockHeight, ShardId};
 use std::collections::{hash_map::Entry, HashMap};
 use std::fmt;
 use std::future::Future;
@@ -69,6 +69,7 @@ impl tcp::Tier {
             // T1
             RoutedMessageBody::BlockApproval(..)
             | RoutedMessageBody::VersionedChunkEndorsement(..)
+            | RoutedMessageBody::ChunkSuppression(..)
             | RoutedMessageBody::PartialEncodedStateWitness(..)
             | RoutedMessageBody::PartialEncodedStateWitnessForward(..)
             | RoutedMessageBody::VersionedPartialEncodedChunk(..)
@@ -139,6 +140,10 @@ pub(crate) struct Connection {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Height of the earliest block available at the peer, as advertised in the handshake.
+    pub earliest_block_height: Option<BlockHeight>,
+    /// Capabilities advertised by both this node and the peer.
+    pub capabilities: PeerCapabilities,
     pub last_block: ArcSwap<Option<BlockInfo>>,
 
     /// Who started connection. Inbound (other) or Outbound (us).
@@ -178,10 +183,16 @@ impl Connection {
             last_block: *self.last_block.load().as_ref(),
             tracked_shards: self.tracked_shards.clone(),
             archival: self.archival,
+            earliest_block_height: self.earliest_block_height,
         };
         FullPeerInfo { peer_info: self.peer_info.clone(), chain_info }
     }
 
+    /// Whether the feature of the network protocol can be used with the peer.
+    pub fn has_capability(&self, capability: PeerCapability) -> bool {
+        self.capabilities.contains(capability)
+    }
+
     pub fn stop(&self, ban_reason: Option<ReasonForBan>) {
         self.addr.do_send(peer_actor::Stop { ban_reason }.with_span_context());
     }
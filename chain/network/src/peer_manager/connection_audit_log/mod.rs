@@ -0,0 +1,82 @@
+use crate::network_protocol::PeerInfo;
+use crate::store;
+use crate::types::{ConnectionAuditEntry, ConnectionAuditEvent};
+use near_async::time;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of most recent entries kept in the connection audit log.
+pub const CONNECTION_AUDIT_LOG_SIZE: usize = 1000;
+
+struct Inner {
+    /// Index at which the next entry is stored.
+    next_index: u64,
+    /// Entries kept in memory, the oldest first.
+    entries: VecDeque<ConnectionAuditEntry>,
+    /// Entries recorded since the last flush, with their index.
+    unsaved: Vec<(u64, ConnectionAuditEntry)>,
+}
+
+/// ConnectionAuditLog keeps the history of the connections established and closed by the node,
+/// with the reason they were closed, and of the peers it banned. The log is persisted, so that
+/// the reasons of past disconnections survive the log buffer and restarts of the node, and is
+/// rotated to keep only the CONNECTION_AUDIT_LOG_SIZE most recent entries.
+///
+/// Recording an entry doesn't touch the database, as it happens on the network path. The
+/// entries are persisted in batches by `flush`, which PeerManagerActor calls periodically.
+pub(crate) struct ConnectionAuditLog {
+    inner: Mutex<Inner>,
+    /// Also serializes the flushes, so that the batches are written in order.
+    store: Mutex<store::Store>,
+}
+
+impl ConnectionAuditLog {
+    pub fn new(store: store::Store) -> anyhow::Result<Self> {
+        let stored = store.get_connection_audit_log()?;
+        let next_index = stored.last().map_or(0, |(index, _)| index + 1);
+        let mut entries: VecDeque<_> = stored.into_iter().map(|(_, entry)| entry).collect();
+        // Only needed if the size of the log has been decreased.
+        while entries.len() > CONNECTION_AUDIT_LOG_SIZE {
+            entries.pop_front();
+        }
+        let inner = Inner { next_index, entries, unsaved: vec![] };
+        Ok(Self { inner: Mutex::new(inner), store: Mutex::new(store) })
+    }
+
+    /// Returns the recorded entries, the most recent first.
+    pub fn get_entries(&self) -> Vec<ConnectionAuditEntry> {
+        self.inner.lock().entries.iter().rev().cloned().collect()
+    }
+
+    /// Appends an entry to the log, evicting the oldest one if CONNECTION_AUDIT_LOG_SIZE is
+    /// reached. The entry is persisted by the next `flush`.
+    pub fn record(&self, clock: &time::Clock, peer_info: &PeerInfo, event: ConnectionAuditEvent) {
+        let entry =
+            ConnectionAuditEntry { time: clock.now_utc(), peer_info: peer_info.clone(), event };
+        let mut inner = self.inner.lock();
+        let index = inner.next_index;
+        inner.next_index += 1;
+        inner.unsaved.push((index, entry.clone()));
+        inner.entries.push_back(entry);
+        if inner.entries.len() > CONNECTION_AUDIT_LOG_SIZE {
+            inner.entries.pop_front();
+        }
+    }
+
+    /// Persists the entries recorded since the last flush, in a single write.
+    pub fn flush(&self) {
+        let mut store = self.store.lock();
+        let unsaved = std::mem::take(&mut self.inner.lock().unsaved);
+        if unsaved.is_empty() {
+            return;
+        }
+        if let Err(err) =
+            store.push_connection_audit_entries(&unsaved, CONNECTION_AUDIT_LOG_SIZE as u64)
+        {
+            tracing::error!(target: "network", ?err, "Failed to save connection audit log entries");
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::network_protocol::testonly::make_peer_info;
+use crate::peer_manager::connection_audit_log::ConnectionAuditLog;
+use crate::peer_manager::connection_audit_log::CONNECTION_AUDIT_LOG_SIZE;
+use crate::store;
+use crate::tcp;
+use crate::testonly::make_rng;
+use crate::types::{ConnectionAuditEvent, PeerType, ReasonForBan};
+use near_async::time;
+
+#[test]
+fn test_reload_from_storage() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(near_store::db::TestDB::new());
+
+    let peer_info = make_peer_info(rng);
+    {
+        tracing::debug!(target:"test", "write audit log entries to storage");
+        let audit_log = ConnectionAuditLog::new(store.clone()).unwrap();
+        audit_log.record(
+            &clock.clock(),
+            &peer_info,
+            ConnectionAuditEvent::Established {
+                tier: tcp::Tier::T2,
+                peer_type: PeerType::Outbound,
+            },
+        );
+        clock.advance(time::Duration::seconds(10));
+        audit_log.record(
+            &clock.clock(),
+            &peer_info,
+            ConnectionAuditEvent::Banned { reason: ReasonForBan::Abusive },
+        );
+        tracing::debug!(target:"test", "entries are only persisted once flushed");
+        assert!(store.get_connection_audit_log().unwrap().is_empty());
+        audit_log.flush();
+        assert_eq!(store.get_connection_audit_log().unwrap().len(), 2);
+    }
+    {
+        tracing::debug!(target:"test", "read audit log entries from storage");
+        let audit_log = ConnectionAuditLog::new(store).unwrap();
+        let entries = audit_log.get_entries();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[0].event,
+            ConnectionAuditEvent::Banned { reason: ReasonForBan::Abusive }
+        );
+        assert_eq!(entries[0].time, clock.now_utc());
+        assert_eq!(entries[1].peer_info, peer_info);
+    }
+}
+
+#[test]
+fn test_rotation() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(near_store::db::TestDB::new());
+    let audit_log = ConnectionAuditLog::new(store.clone()).unwrap();
+
+    let peer_infos: Vec<_> =
+        (0..CONNECTION_AUDIT_LOG_SIZE + 10).map(|_| make_peer_info(rng)).collect();
+    for peer_info in &peer_infos {
+        audit_log.record(
+            &clock.clock(),
+            peer_info,
+            ConnectionAuditEvent::Closed {
+                tier: tcp::Tier::T2,
+                peer_type: PeerType::Inbound,
+                reason: "stream error".to_string(),
+            },
+        );
+    }
+
+    tracing::debug!(target:"test", "check that only the most recent entries are kept");
+    let expected: Vec<_> = peer_infos.iter().rev().take(CONNECTION_AUDIT_LOG_SIZE).collect();
+    let entries = audit_log.get_entries();
+    assert_eq!(entries.iter().map(|e| &e.peer_info).collect::<Vec<_>>(), expected);
+    audit_log.flush();
+    assert_eq!(store.get_connection_audit_log().unwrap().len(), CONNECTION_AUDIT_LOG_SIZE);
+
+    tracing::debug!(target:"test", "check that the log is rotated the same way after reloading");
+    let audit_log = ConnectionAuditLog::new(store).unwrap();
+    assert_eq!(audit_log.get_entries().iter().map(|e| &e.peer_info).collect::<Vec<_>>(), expected);
+}
+
+#[test]
+fn test_rotation_across_flushes() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(near_store::db::TestDB::new());
+    let audit_log = ConnectionAuditLog::new(store.clone()).unwrap();
+
+    let peer_infos: Vec<_> =
+        (0..2 * CONNECTION_AUDIT_LOG_SIZE).map(|_| make_peer_info(rng)).collect();
+    for batch in peer_infos.chunks(CONNECTION_AUDIT_LOG_SIZE / 3) {
+        for peer_info in batch {
+            audit_log.record(
+                &clock.clock(),
+                peer_info,
+                ConnectionAuditEvent::Established {
+                    tier: tcp::Tier::T1,
+                    peer_type: PeerType::Inbound,
+                },
+            );
+        }
+        audit_log.flush();
+    }
+
+    tracing::debug!(target:"test", "check that the evicted entries are removed from storage");
+    let expected: Vec<_> = peer_infos.iter().rev().take(CONNECTION_AUDIT_LOG_SIZE).collect();
+    let stored = store.get_connection_audit_log().unwrap();
+    assert_eq!(stored.len(), CONNECTION_AUDIT_LOG_SIZE);
+    assert_eq!(stored.iter().rev().map(|(_, e)| &e.peer_info).collect::<Vec<_>>(), expected);
+}
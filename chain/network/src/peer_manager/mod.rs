@@ -1,4 +1,5 @@
 pub(crate) mod connection;
+pub(crate) mod connection_audit_log;
 pub(crate) mod connection_store;
 pub(crate) mod network_state;
 pub(crate) mod peer_manager_actor;
@@ -1,19 +1,20 @@
 use crate::accounts_data::{AccountDataCache, AccountDataError};
 use crate::announce_accounts::AnnounceAccountCache;
 use crate::client::{
-    BlockApproval, ChunkEndorsementMessage, ClientSenderForNetwork, ProcessTxRequest,
-    TxStatusRequest, TxStatusResponse,
+    BlockApproval, ChunkEndorsementMessage, ChunkSuppressionMessage, ClientSenderForNetwork,
+    ProcessTxRequest, TxStatusRequest, TxStatusResponse,
 };
 use crate::concurrency::demux;
 use crate::concurrency::runtime::Runtime;
 use crate::config;
 use crate::network_protocol::{
-    Edge, EdgeState, PartialEdgeInfo, PeerIdOrHash, PeerInfo, PeerMessage, RawRoutedMessage,
-    RoutedMessageBody, RoutedMessageV2, SignedAccountData, SnapshotHostInfo,
+    Edge, EdgeState, PartialEdgeInfo, PeerCapability, PeerIdOrHash, PeerInfo, PeerMessage,
+    RawRoutedMessage, RoutedMessageBody, RoutedMessageV2, SignedAccountData, SnapshotHostInfo,
 };
 use crate::peer::peer_actor::ClosingReason;
 use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
+use crate::peer_manager::connection_audit_log;
 use crate::peer_manager::connection_store;
 use crate::peer_manager::peer_store;
 use crate::private_actix::RegisterPeerError;
@@ -32,8 +33,8 @@ use crate::stats::metrics;
 use crate::store;
 use crate::tcp;
 use crate::types::{
-    ChainInfo, PeerManagerSenderForNetwork, PeerType, ReasonForBan, StatePartRequestBody,
-    Tier3Request, Tier3RequestBody,
+    ChainInfo, ConnectionAuditEvent, PeerManagerSenderForNetwork, PeerType, ReasonForBan,
+    StatePartRequestBody, Tier3Request, Tier3RequestBody,
 };
 use anyhow::Context;
 use arc_swap::ArcSwap;
@@ -132,6 +133,8 @@ pub(crate) struct NetworkState {
     pub snapshot_hosts: Arc<SnapshotHostsCache>,
     /// Connection store that provides read/write access to stored connections.
     pub connection_store: connection_store::ConnectionStore,
+    /// Persisted history of the connections and bans, for debugging purposes.
+    pub connection_audit_log: connection_audit_log::ConnectionAuditLog,
     /// List of peers to which we should re-establish a connection
     pub pending_reconnect: Mutex<Vec<PeerInfo>>,
     /// A graph of the whole NEAR network.
@@ -189,7 +192,7 @@ impl NetworkState {
         whitelist_nodes: Vec<WhitelistNode>,
     ) -> Self {
         Self {
-            runtime: Runtime::new(),
+            runtime: Runtime::with_cpu_set(config.cpu_set.clone()),
             graph: Arc::new(crate::routing::Graph::new(crate::routing::GraphConfig {
                 node_id: config.node_id(),
                 prune_unreachable_peers_after: PRUNE_UNREACHABLE_PEERS_AFTER,
@@ -214,6 +217,8 @@ impl NetworkState {
             peer_store,
             snapshot_hosts: Arc::new(SnapshotHostsCache::new(config.snapshot_hosts.clone())),
             connection_store: connection_store::ConnectionStore::new(store.clone()).unwrap(),
+            connection_audit_log: connection_audit_log::ConnectionAuditLog::new(store.clone())
+                .unwrap(),
             pending_reconnect: Mutex::new(Vec::<PeerInfo>::new()),
             accounts_data: Arc::new(AccountDataCache::new()),
             account_announcements: Arc::new(AnnounceAccountCache::new(store)),
@@ -264,6 +269,15 @@ impl NetworkState {
             if let Err(err) = self.peer_store.peer_ban(clock, peer_id, ban_reason) {
                 tracing::debug!(target: "network", ?err, "Failed to save peer data");
             }
+            let peer_info = self.peer_store.get_peer_state(peer_id).map_or_else(
+                || PeerInfo { id: peer_id.clone(), addr: None, account_id: None },
+                |state| state.peer_info,
+            );
+            self.connection_audit_log.record(
+                clock,
+                &peer_info,
+                ConnectionAuditEvent::Banned { reason: ban_reason },
+            );
         }
     }
 
@@ -337,7 +351,7 @@ impl NetworkState {
                     if !edge.verify() {
                         return Err(RegisterPeerError::InvalidEdge);
                     }
-                    this.tier1.insert_ready(conn).map_err(RegisterPeerError::PoolError)?;
+                    this.tier1.insert_ready(conn.clone()).map_err(RegisterPeerError::PoolError)?;
                 }
                 tcp::Tier::T2 => {
                     if conn.peer_type == PeerType::Inbound {
@@ -377,9 +391,14 @@ impl NetworkState {
                     if !edge.verify() {
                         return Err(RegisterPeerError::InvalidEdge);
                     }
-                    this.tier3.insert_ready(conn).map_err(RegisterPeerError::PoolError)?;
+                    this.tier3.insert_ready(conn.clone()).map_err(RegisterPeerError::PoolError)?;
                 }
             }
+            this.connection_audit_log.record(
+                &clock,
+                &conn.peer_info,
+                ConnectionAuditEvent::Established { tier: conn.tier, peer_type: conn.peer_type },
+            );
             Ok(())
         }).await.unwrap()
     }
@@ -404,6 +423,22 @@ impl NetworkState {
                 tcp::Tier::T2 => this.tier2.remove(&conn),
                 tcp::Tier::T3 => this.tier3.remove(&conn),
             }
+            this.connection_audit_log.record(
+                &clock,
+                &conn.peer_info,
+                ConnectionAuditEvent::Closed {
+                    tier: conn.tier,
+                    peer_type: conn.peer_type,
+                    reason: reason.to_string(),
+                },
+            );
+            if let ClosingReason::Ban(ban_reason) = &reason {
+                this.connection_audit_log.record(
+                    &clock,
+                    &conn.peer_info,
+                    ConnectionAuditEvent::Banned { reason: *ban_reason },
+                );
+            }
 
             // The rest of this function has to do with banning or routing,
             // which are applicable only for TIER2.
@@ -654,6 +689,12 @@ impl NetworkState {
                     Some(conn) => conn,
                     None => continue,
                 };
+                // Fall back to TIER2 if the proxy can't relay the message.
+                if let Some(capability) = PeerCapability::required_for_tier1(&msg) {
+                    if !conn.has_capability(capability) {
+                        continue;
+                    }
+                }
                 // TODO(gprusak): in case of PartialEncodedChunk, consider stripping everything
                 // but the header. This will bound the message size
                 conn.send_message(Arc::new(PeerMessage::Routed(self.sign_message(
@@ -786,6 +827,10 @@ impl NetworkState {
                 self.client.send_async(ChunkEndorsementMessage(endorsement)).await.ok();
                 None
             }
+            RoutedMessageBody::ChunkSuppression(suppression) => {
+                self.client.send_async(ChunkSuppressionMessage(suppression)).await.ok();
+                None
+            }
             RoutedMessageBody::StatePartRequest(request) => {
                 self.peer_manager_adapter.send(Tier3Request {
                     peer_info: PeerInfo {
@@ -869,6 +914,7 @@ impl NetworkState {
                 let tasks: Vec<_> = tier2
                     .ready
                     .values()
+                    .filter(|p| p.has_capability(PeerCapability::SnapshotHosts))
                     .map(|p| this.spawn(p.send_snapshot_hosts(new_data.clone())))
                     .collect();
                 for t in tasks {
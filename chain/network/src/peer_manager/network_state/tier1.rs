@@ -1,10 +1,12 @@
 use crate::accounts_data::{AccountDataCacheSnapshot, LocalAccountData};
 use crate::config::{self, FrozenValidatorConfig};
 use crate::network_protocol::{
-    AccountData, PeerAddr, PeerInfo, PeerMessage, SignedAccountData, SyncAccountsData,
+    AccountData, PeerAddr, PeerInfo, PeerMessage, ReachabilityAttestation, SignedAccountData,
+    SyncAccountsData,
 };
 use crate::peer::peer_actor::PeerActor;
 use crate::peer_manager::connection;
+use crate::stats::metrics;
 use crate::stun;
 use crate::tcp;
 use crate::types::PeerType;
@@ -194,11 +196,22 @@ impl super::NetworkState {
             }
         };
         tracing::info!(target:"network","connected to proxies {my_proxies:?}");
+        // Every proxy in `my_proxies` has just been confirmed to be reachable,
+        // so we attest to that in the signed AccountData.
+        let now = clock.now_utc();
+        let reachability = my_proxies
+            .iter()
+            .map(|proxy| ReachabilityAttestation { proxy: proxy.clone(), verified_at: now })
+            .collect();
         let new_data = self.accounts_data.set_local(
             clock,
             LocalAccountData {
                 signer,
-                data: Arc::new(AccountData { peer_id: self.config.node_id(), proxies: my_proxies }),
+                data: Arc::new(AccountData {
+                    peer_id: self.config.node_id(),
+                    proxies: my_proxies,
+                    reachability,
+                }),
             },
         );
         // Early exit in case this node is not a TIER1 node any more.
@@ -212,6 +225,50 @@ impl super::NetworkState {
         Some(new_data)
     }
 
+    /// Dials every address advertised in the local AccountData and reports
+    /// whether it is reachable via the `near_tier1_advertised_address_reachable` metric.
+    /// Misadvertised addresses prevent other validators from establishing TIER1
+    /// connections to this node, so it is better to find out about them early.
+    /// Returns the addresses which turned out to be unreachable.
+    pub async fn tier1_check_advertised_addresses(self: &Arc<Self>) -> Vec<PeerAddr> {
+        let accounts_data = self.accounts_data.load();
+        let Some(local) = &accounts_data.local else { return vec![] };
+        let mut handles = vec![];
+        for proxy in &local.data.proxies {
+            handles.push(async move {
+                // It is enough to establish a TCP connection, there is no need
+                // to perform a handshake: the connection is dropped right away.
+                let res = tcp::Stream::connect(
+                    &PeerInfo {
+                        id: proxy.peer_id.clone(),
+                        addr: Some(proxy.addr),
+                        account_id: None,
+                    },
+                    tcp::Tier::T1,
+                    &self.config.socket_options,
+                )
+                .await;
+                (proxy, res)
+            });
+        }
+        let mut unreachable = vec![];
+        for (proxy, res) in futures_util::future::join_all(handles).await {
+            let addr = proxy.addr.to_string();
+            let reachable = res.is_ok();
+            metrics::TIER1_ADVERTISED_ADDRESS_REACHABLE
+                .with_label_values(&[&addr])
+                .set(reachable as i64);
+            if let Err(err) = res {
+                tracing::warn!(target:"network", ?err, "advertised TIER1 address {:?} is unreachable", proxy);
+                metrics::TIER1_ADVERTISED_ADDRESS_UNREACHABLE_TOTAL
+                    .with_label_values(&[&addr])
+                    .inc();
+                unreachable.push(proxy.clone());
+            }
+        }
+        unreachable
+    }
+
     /// Closes TIER1 connections from nodes which are not TIER1 any more.
     /// If this node is TIER1, it additionally connects to proxies of other TIER1 nodes.
     pub async fn tier1_connect(self: &Arc<Self>, clock: &time::Clock) {
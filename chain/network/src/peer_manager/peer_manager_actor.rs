@@ -17,10 +17,10 @@ use crate::stats::metrics;
 use crate::store;
 use crate::tcp;
 use crate::types::{
-    ConnectedPeerInfo, HighestHeightPeerInfo, KnownProducer, NetworkInfo, NetworkRequests,
-    NetworkResponses, PeerInfo, PeerManagerMessageRequest, PeerManagerMessageResponse,
-    PeerManagerSenderForNetwork, PeerType, SetChainInfo, SnapshotHostInfo, StatePartRequestBody,
-    StateSyncEvent, Tier3Request, Tier3RequestBody,
+    ConnectedPeerInfo, ConnectionAuditEvent, HighestHeightPeerInfo, KnownProducer, NetworkInfo,
+    NetworkRequests, NetworkResponses, PeerInfo, PeerManagerMessageRequest,
+    PeerManagerMessageResponse, PeerManagerSenderForNetwork, PeerType, SetChainInfo,
+    SnapshotHostInfo, StatePartRequestBody, StateSyncEvent, Tier3Request, Tier3RequestBody,
 };
 use ::time::ext::InstantExt as _;
 use actix::fut::future::wrap_future;
@@ -33,8 +33,9 @@ use near_performance_metrics_macros::perf;
 use near_primitives::block::GenesisId;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::views::{
-    ConnectionInfoView, EdgeView, KnownPeerStateView, NetworkGraphView, NetworkRoutesView,
-    PeerStoreView, RecentOutboundConnectionsView, SnapshotHostInfoView, SnapshotHostsView,
+    ConnectionAuditEntryView, ConnectionAuditLogView, ConnectionInfoView, EdgeView,
+    KnownPeerStateView, NetworkGraphView, NetworkRoutesView, PeerStoreView,
+    RecentOutboundConnectionsView, SnapshotHostInfoView, SnapshotHostsView,
 };
 use network_protocol::MAX_SHARDS_PER_SNAPSHOT_HOST_INFO;
 use rand::seq::{IteratorRandom, SliceRandom};
@@ -87,6 +88,8 @@ const PREFER_PREVIOUSLY_CONNECTED_PEER: f64 = 0.6;
 pub(crate) const UPDATE_CONNECTION_STORE_INTERVAL: time::Duration = time::Duration::minutes(1);
 /// How often to poll the NetworkState for closed connections we'd like to re-establish.
 pub(crate) const POLL_CONNECTION_STORE_INTERVAL: time::Duration = time::Duration::minutes(1);
+/// How often to persist the entries recorded in the connection audit log.
+pub(crate) const FLUSH_CONNECTION_AUDIT_LOG_INTERVAL: time::Duration = time::Duration::seconds(10);
 
 /// The length of time that a Tier3 connection is allowed to idle before it is stopped
 const TIER3_IDLE_TIMEOUT: time::Duration = time::Duration::seconds(15);
@@ -187,6 +190,18 @@ impl actix::Actor for PeerManagerActor {
             }
         }));
 
+        // Periodically persist the connection audit log.
+        let clock = self.clock.clone();
+        let state = self.state.clone();
+        ctx.spawn(wrap_future(async move {
+            let mut interval =
+                time::Interval::new(clock.now(), FLUSH_CONNECTION_AUDIT_LOG_INTERVAL);
+            loop {
+                interval.tick(&clock).await;
+                state.connection_audit_log.flush();
+            }
+        }));
+
         // Periodically prints bandwidth stats for each peer.
         self.report_bandwidth_stats_trigger(ctx, REPORT_BANDWIDTH_STATS_TRIGGER_INTERVAL);
 
@@ -197,6 +212,7 @@ impl actix::Actor for PeerManagerActor {
     /// Try to gracefully disconnect from connected peers.
     fn stopping(&mut self, _ctx: &mut Self::Context) -> actix::Running {
         tracing::warn!("PeerManager: stopping");
+        self.state.connection_audit_log.flush();
         self.state.tier2.broadcast_message(Arc::new(PeerMessage::Disconnect(Disconnect {
             remove_from_connection_store: false,
         })));
@@ -238,6 +254,11 @@ impl PeerManagerActor {
         };
         let my_peer_id = config.node_id();
         let arbiter = actix::Arbiter::new().handle();
+        if let Some(cpu_set) = config.cpu_set.clone() {
+            arbiter.spawn_fn(move || {
+                near_performance_metrics::affinity::pin_current_thread("network", &cpu_set)
+            });
+        }
         let clock = clock;
         let state = Arc::new(NetworkState::new(
             &clock,
@@ -303,6 +324,18 @@ impl PeerManagerActor {
                             }
                         }
                     });
+                    // Check periodically that the advertised TIER1 addresses are reachable.
+                    arbiter.spawn({
+                        let clock = clock.clone();
+                        let state = state.clone();
+                        let mut interval = time::Interval::new(clock.now(), cfg.reachability_check_interval);
+                        async move {
+                            loop {
+                                interval.tick(&clock).await;
+                                state.tier1_check_advertised_addresses().await;
+                            }
+                        }
+                    });
                     // Update TIER1 connections periodically.
                     arbiter.spawn({
                         let clock = clock.clone();
@@ -945,7 +978,17 @@ impl PeerManagerActor {
                         let mut matching_peers = vec![];
                         for (peer_id, peer) in &self.state.tier2.load().ready {
                             let last_block = peer.last_block.load();
-                            if (peer.archival || !target.only_archival)
+                            // Archival peers serve all the chunks, whatever the tail of their hot
+                            // store they advertised. Other peers advertising their earliest
+                            // available block are trusted to have the chunk.
+                            let has_chunk = peer.archival
+                                || match peer.earliest_block_height {
+                                    Some(earliest_block_height) => {
+                                        earliest_block_height <= target.height
+                                    }
+                                    None => !target.only_archival,
+                                };
+                            if has_chunk
                                 && last_block.is_some()
                                 && last_block.as_ref().unwrap().height >= target.min_height
                                 && peer.tracked_shards.contains(&target.shard_id)
@@ -1069,6 +1112,14 @@ impl PeerManagerActor {
                 );
                 NetworkResponses::NoResponse
             }
+            NetworkRequests::ChunkSuppression(target, suppression) => {
+                self.state.send_message_to_account(
+                    &self.clock,
+                    &target,
+                    RoutedMessageBody::ChunkSuppression(suppression),
+                );
+                NetworkResponses::NoResponse
+            }
             NetworkRequests::PartialEncodedStateWitness(validator_witness_tuple) => {
                 for (chunk_validator, partial_witness) in validator_witness_tuple {
                     self.state.send_message_to_account(
@@ -1387,6 +1438,40 @@ impl actix::Handler<GetDebugStatus> for PeerManagerActor {
                         .collect::<Vec<_>>(),
                 })
             }
+            GetDebugStatus::ConnectionAuditLog => {
+                DebugStatus::ConnectionAuditLog(ConnectionAuditLogView {
+                    entries: self
+                        .state
+                        .connection_audit_log
+                        .get_entries()
+                        .into_iter()
+                        .map(|entry| {
+                            let (tier, peer_type, reason) = match &entry.event {
+                                ConnectionAuditEvent::Established { tier, peer_type } => {
+                                    (Some(*tier), Some(*peer_type), None)
+                                }
+                                ConnectionAuditEvent::Closed { tier, peer_type, reason } => {
+                                    (Some(*tier), Some(*peer_type), Some(reason.clone()))
+                                }
+                                ConnectionAuditEvent::Banned { reason } => {
+                                    (None, None, Some(format!("{:?}", reason)))
+                                }
+                            };
+                            let event: &'static str = (&entry.event).into();
+                            ConnectionAuditEntryView {
+                                time: entry.time.unix_timestamp(),
+                                peer_id: entry.peer_info.id.clone(),
+                                addr: format!("{:?}", entry.peer_info.addr),
+                                event: event.to_string(),
+                                tier: tier.map(|tier| tier.as_ref().to_string()),
+                                peer_type: peer_type
+                                    .map(|peer_type| <&'static str>::from(peer_type).to_string()),
+                                reason,
+                            }
+                        })
+                        .collect::<Vec<_>>(),
+                })
+            }
             GetDebugStatus::Routes => {
                 #[cfg(feature = "distance_vector_routing")]
                 return DebugStatus::Routes(self.state.graph_v2.get_debug_view());
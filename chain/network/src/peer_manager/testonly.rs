@@ -11,7 +11,7 @@ use crate::network_protocol::StateResponseInfo;
 use crate::network_protocol::StateResponseInfoV2;
 use crate::network_protocol::SyncSnapshotHosts;
 use crate::network_protocol::{
-    EdgeState, Encoding, PeerInfo, PeerMessage, SignedAccountData, SyncAccountsData,
+    EdgeState, Encoding, PeerAddr, PeerInfo, PeerMessage, SignedAccountData, SyncAccountsData,
 };
 use crate::peer;
 use crate::peer::peer_actor::ClosingReason;
@@ -368,6 +368,10 @@ impl ActorHandler {
         self.with_state(move |s| async move { s.tier1_advertise_proxies(&clock).await }).await
     }
 
+    pub async fn tier1_check_advertised_addresses(&self) -> Vec<PeerAddr> {
+        self.with_state(|s| async move { s.tier1_check_advertised_addresses().await }).await
+    }
+
     pub async fn disconnect(&self, peer_id: &PeerId) {
         let peer_id = peer_id.clone();
         self.with_state(move |s| async move {
@@ -100,6 +100,8 @@ async fn loop_connection() {
             target_peer_id: pm.cfg.node_id(),
             sender_listen_port: Some(port),
             sender_chain_info: chain.get_peer_chain_info(),
+            earliest_block_height: None,
+            capabilities: None,
             partial_edge_info: PartialEdgeInfo::new(
                 &pm.cfg.node_id(),
                 &pm.cfg.node_id(),
@@ -162,6 +164,8 @@ async fn owned_account_mismatch() {
             target_peer_id: pm.cfg.node_id(),
             sender_listen_port: Some(port),
             sender_chain_info: chain.get_peer_chain_info(),
+            earliest_block_height: None,
+            capabilities: None,
             partial_edge_info: PartialEdgeInfo::new(
                 &cfg.node_id(),
                 &pm.cfg.node_id(),
@@ -290,6 +294,8 @@ async fn invalid_edge() {
                 target_peer_id: pm.cfg.node_id(),
                 sender_listen_port: Some(port),
                 sender_chain_info: chain.get_peer_chain_info(),
+                earliest_block_height: None,
+                capabilities: None,
                 partial_edge_info: edge.clone(),
                 owned_account: Some(
                     OwnedAccount {
@@ -75,6 +75,8 @@ async fn test_nonces() {
             sender_chain_info: chain.get_peer_chain_info(),
             partial_edge_info: PartialEdgeInfo::new(&peer_id, &pm.cfg.node_id(), test.0, &peer_key),
             owned_account: None,
+            earliest_block_height: None,
+            capabilities: None,
         });
         stream.write(&handshake).await;
         if test.1 {
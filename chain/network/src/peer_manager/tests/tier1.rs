@@ -140,6 +140,31 @@ async fn first_proxy_advertisement() {
     );
 }
 
+// Advertised proxies should be attested as reachable and the self-check
+// should confirm that they can be dialed.
+#[tokio::test]
+async fn advertised_addresses_reachability() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let pm = start_pm(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        chain.make_config(rng),
+        chain.clone(),
+    )
+    .await;
+    let chain_info = peer_manager::testonly::make_chain_info(&chain, &[&pm.cfg]);
+    pm.set_chain_info(chain_info).await;
+    let got = pm.tier1_advertise_proxies(&clock.clock()).await.unwrap();
+    let attested: Vec<_> = got.reachability.iter().map(|a| a.proxy.clone()).collect();
+    assert_eq!(got.proxies, attested);
+    assert!(got.reachability.iter().all(|a| a.verified_at == clock.now_utc()));
+    assert_eq!(pm.tier1_check_advertised_addresses().await, vec![]);
+}
+
 #[tokio::test]
 async fn direct_connections() {
     init_test_logger();
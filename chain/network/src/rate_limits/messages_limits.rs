@@ -177,6 +177,7 @@ pub enum RateLimitedPeerMessageKey {
     VersionedPartialEncodedChunk,
     PartialEncodedChunkForward,
     ChunkEndorsement,
+    ChunkSuppression,
     ChunkStateWitnessAck,
     PartialEncodedStateWitness,
     PartialEncodedStateWitnessForward,
@@ -238,6 +239,7 @@ fn get_key_and_token_cost(message: &PeerMessage) -> Option<(RateLimitedPeerMessa
                 Some((PartialEncodedContractDeploys, 1))
             }
             RoutedMessageBody::VersionedChunkEndorsement(_) => Some((ChunkEndorsement, 1)),
+            RoutedMessageBody::ChunkSuppression(_) => Some((ChunkSuppression, 1)),
             RoutedMessageBody::_UnusedEpochSyncRequest => None,
             RoutedMessageBody::_UnusedEpochSyncResponse(_) => None,
             RoutedMessageBody::StatePartRequest(_) => None, // TODO
@@ -218,6 +218,8 @@ fn new_handshake(
         },
         partial_edge_info: PartialEdgeInfo::new(my_peer_id, target_peer_id, nonce, secret_key),
         owned_account: None,
+        earliest_block_height: None,
+        capabilities: None,
     })
 }
 
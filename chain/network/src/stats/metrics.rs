@@ -212,6 +212,23 @@ pub(crate) static SYNC_ACCOUNTS_DATA: LazyLock<IntCounterVec> = LazyLock::new(||
     )
     .unwrap()
 });
+pub(crate) static TIER1_ADVERTISED_ADDRESS_REACHABLE: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_tier1_advertised_address_reachable",
+        "Whether the advertised TIER1 address was reachable in the last self-check (1) or not (0)",
+        &["addr"],
+    )
+    .unwrap()
+});
+pub(crate) static TIER1_ADVERTISED_ADDRESS_UNREACHABLE_TOTAL: LazyLock<IntCounterVec> =
+    LazyLock::new(|| {
+        try_create_int_counter_vec(
+            "near_tier1_advertised_address_unreachable_total",
+            "Number of failed reachability self-checks of the advertised TIER1 addresses",
+            &["addr"],
+        )
+        .unwrap()
+    });
 pub(crate) static SYNC_SNAPSHOT_HOSTS: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
         "near_sync_snapshot_hosts",
@@ -1,7 +1,7 @@
 /// Store module defines atomic DB operations on top of schema module.
 /// All transactions should be implemented within this module,
 /// in particular schema::StoreUpdate is not exported.
-use crate::types::ConnectionInfo;
+use crate::types::{ConnectionAuditEntry, ConnectionInfo};
 use near_primitives::network::AnnounceAccount;
 use near_primitives::types::AccountId;
 use std::sync::Arc;
@@ -78,6 +78,37 @@ impl Store {
     }
 }
 
+// ConnectionAuditLog storage.
+impl Store {
+    /// Inserts the entries at their index of the ConnectionAuditLog column, in a single
+    /// transaction. Each entry evicts the one `log_size` indices before it, if any.
+    #[tracing::instrument(
+        target = "network::store",
+        level = "trace",
+        "Store::push_connection_audit_entries",
+        skip_all
+    )]
+    pub fn push_connection_audit_entries(
+        &mut self,
+        entries: &[(u64, ConnectionAuditEntry)],
+        log_size: u64,
+    ) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        for (index, entry) in entries {
+            update.set::<schema::ConnectionAuditLog>(index, entry);
+            if let Some(evicted) = index.checked_sub(log_size) {
+                update.delete::<schema::ConnectionAuditLog>(&evicted);
+            }
+        }
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Fetches all the rows of the ConnectionAuditLog column, ordered by index.
+    pub fn get_connection_audit_log(&self) -> Result<Vec<(u64, ConnectionAuditEntry)>, Error> {
+        self.0.iter::<schema::ConnectionAuditLog>().collect::<Result<_, _>>().map_err(Error)
+    }
+}
+
 impl From<Arc<dyn near_store::db::Database>> for Store {
     fn from(store: Arc<dyn near_store::db::Database>) -> Self {
         Self(schema::Store::from(store))
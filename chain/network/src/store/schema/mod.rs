@@ -58,6 +58,34 @@ impl BorshRepr for ConnectionInfoRepr {
     }
 }
 
+/// A Borsh representation of the primitives::ConnectionAuditEntry.
+#[derive(BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub(super) struct ConnectionAuditEntryRepr {
+    /// UNIX timestamp in nanos.
+    time: u64,
+    peer_info: primitives::PeerInfo,
+    event: primitives::ConnectionAuditEvent,
+}
+
+impl BorshRepr for ConnectionAuditEntryRepr {
+    type T = primitives::ConnectionAuditEntry;
+    fn to_repr(s: &primitives::ConnectionAuditEntry) -> Self {
+        Self {
+            time: s.time.unix_timestamp_nanos() as u64,
+            peer_info: s.peer_info.clone(),
+            event: s.event.clone(),
+        }
+    }
+
+    fn from_repr(s: Self) -> Result<primitives::ConnectionAuditEntry, Error> {
+        Ok(primitives::ConnectionAuditEntry {
+            time: time::Utc::from_unix_timestamp_nanos(s.time as i128).map_err(invalid_data)?,
+            peer_info: s.peer_info,
+            event: s.event,
+        })
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub(super) struct EdgeRepr {
     key: (PeerId, PeerId),
@@ -102,6 +130,13 @@ impl Column for RecentOutboundConnections {
     type Value = Vec<ConnectionInfoRepr>;
 }
 
+pub(super) struct ConnectionAuditLog;
+impl Column for ConnectionAuditLog {
+    const COL: DBCol = DBCol::ConnectionAuditLog;
+    type Key = U64BE;
+    type Value = ConnectionAuditEntryRepr;
+}
+
 ////////////////////////////////////////////////////
 // Storage
 
@@ -186,6 +221,18 @@ impl Format for U64LE {
     }
 }
 
+// Big endian representation for u64, which preserves the order of the keys.
+pub struct U64BE;
+impl Format for U64BE {
+    type T = u64;
+    fn encode<W: io::Write>(a: &u64, w: &mut W) -> io::Result<()> {
+        w.write_all(&a.to_be_bytes())
+    }
+    fn decode(a: &[u8]) -> Result<u64, Error> {
+        a.try_into().map(u64::from_be_bytes).map_err(invalid_data)
+    }
+}
+
 /// Column is a type-safe specification of the DB column.
 /// It defines how to encode/decode keys and values stored in the column.
 pub trait Column {
@@ -228,6 +275,18 @@ impl Store {
             None => None,
         })
     }
+
+    /// Returns all the rows of the column, ordered by the encoded key.
+    pub fn iter<C: Column>(
+        &self,
+    ) -> impl Iterator<Item = Result<(<C::Key as Format>::T, <C::Value as Format>::T), Error>> + '_
+    {
+        debug_assert!(!C::COL.is_rc());
+        self.0.iter_raw_bytes(C::COL).map(|item| {
+            let (k, v) = item?;
+            Ok((C::Key::decode(&k)?, C::Value::decode(&v)?))
+        })
+    }
 }
 
 impl From<Arc<dyn near_store::db::Database>> for Store {
@@ -240,7 +299,7 @@ impl StoreUpdate {
     pub fn set<C: Column>(&mut self, k: &<C::Key as Format>::T, v: &<C::Value as Format>::T) {
         self.0.set(C::COL, to_vec::<C::Key>(k), to_vec::<C::Value>(v))
     }
-    pub fn _delete<C: Column>(&mut self, k: &<C::Key as Format>::T) {
+    pub fn delete<C: Column>(&mut self, k: &<C::Key as Format>::T) {
         self.0.delete(C::COL, to_vec::<C::Key>(k))
     }
 }
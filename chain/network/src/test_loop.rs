@@ -3,8 +3,8 @@ use std::sync::{Arc, Mutex};
 
 use crate::client::{
     BlockApproval, BlockHeadersRequest, BlockHeadersResponse, BlockRequest, BlockResponse,
-    ChunkEndorsementMessage, EpochSyncRequestMessage, EpochSyncResponseMessage, ProcessTxRequest,
-    ProcessTxResponse,
+    ChunkEndorsementMessage, ChunkSuppressionMessage, EpochSyncRequestMessage,
+    EpochSyncResponseMessage, ProcessTxRequest, ProcessTxResponse,
 };
 use crate::shards_manager::ShardsManagerRequestFromNetwork;
 use crate::state_witness::{
@@ -35,6 +35,7 @@ pub struct ClientSenderForTestLoopNetwork {
     pub block_approval: AsyncSender<BlockApproval, ()>,
     pub transaction: AsyncSender<ProcessTxRequest, ProcessTxResponse>,
     pub chunk_endorsement: AsyncSender<ChunkEndorsementMessage, ()>,
+    pub chunk_suppression: AsyncSender<ChunkSuppressionMessage, ()>,
     pub epoch_sync_request: Sender<EpochSyncRequestMessage>,
     pub epoch_sync_response: Sender<EpochSyncResponseMessage>,
 }
@@ -111,14 +112,18 @@ impl TestLoopPeerManagerActor {
 
 /// Shared state across all the network actors. It handles the mapping between AccountId,
 /// PeerId, and the route back CryptoHash, so that individual network actors can do
-/// routing.
+/// routing. Nodes can be added while the test runs, see `add_node`, and the network can be
+/// partitioned, see `partition`.
 pub struct TestLoopNetworkSharedState {
-    account_to_peer_id: HashMap<AccountId, PeerId>,
-    senders: HashMap<PeerId, OneClientSenders>,
+    account_to_peer_id: Mutex<HashMap<AccountId, PeerId>>,
+    senders: Mutex<HashMap<PeerId, OneClientSenders>>,
     route_back: Mutex<HashMap<CryptoHash, PeerId>>,
+    /// Index of the group of each node while the network is partitioned.
+    partition: Mutex<Option<HashMap<AccountId, usize>>>,
 }
 
 /// Senders available for the networking layer, for one node in the test loop.
+#[derive(Clone)]
 struct OneClientSenders {
     client_sender: ClientSenderForTestLoopNetwork,
     view_client_sender: ViewClientSenderForTestLoopNetwork,
@@ -136,36 +141,95 @@ impl TestLoopNetworkSharedState {
         PartialWitnessSenderForNetwork: From<&'a D>,
         Sender<ShardsManagerRequestFromNetwork>: From<&'a D>,
     {
-        let mut account_to_peer_id = HashMap::new();
-        let mut senders = HashMap::new();
+        let shared_state = Self {
+            account_to_peer_id: Mutex::new(HashMap::new()),
+            senders: Mutex::new(HashMap::new()),
+            route_back: Mutex::new(HashMap::new()),
+            partition: Mutex::new(None),
+        };
         for data in datas {
-            let account_id = AccountId::from(data);
-            let peer_id = PeerId::from(data);
-            let client_sender = ClientSenderForTestLoopNetwork::from(data);
-            let view_client_sender = ViewClientSenderForTestLoopNetwork::from(data);
-            let partial_witness_sender = PartialWitnessSenderForNetwork::from(data);
-            let shards_manager_sender = Sender::<ShardsManagerRequestFromNetwork>::from(data);
-            account_to_peer_id.insert(account_id.clone(), peer_id.clone());
-            senders.insert(
-                peer_id.clone(),
-                OneClientSenders {
-                    client_sender,
-                    view_client_sender,
-                    partial_witness_sender,
-                    shards_manager_sender,
-                },
-            );
+            shared_state.add_node(data);
+        }
+        shared_state
+    }
+
+    /// Makes a node reachable by the other nodes, e.g. a node joining the network while the
+    /// test runs.
+    pub fn add_node<'a, D>(&self, data: &'a D)
+    where
+        AccountId: From<&'a D>,
+        PeerId: From<&'a D>,
+        ClientSenderForTestLoopNetwork: From<&'a D>,
+        ViewClientSenderForTestLoopNetwork: From<&'a D>,
+        PartialWitnessSenderForNetwork: From<&'a D>,
+        Sender<ShardsManagerRequestFromNetwork>: From<&'a D>,
+    {
+        let account_id = AccountId::from(data);
+        let peer_id = PeerId::from(data);
+        let client_sender = ClientSenderForTestLoopNetwork::from(data);
+        let view_client_sender = ViewClientSenderForTestLoopNetwork::from(data);
+        let partial_witness_sender = PartialWitnessSenderForNetwork::from(data);
+        let shards_manager_sender = Sender::<ShardsManagerRequestFromNetwork>::from(data);
+        self.account_to_peer_id.lock().unwrap().insert(account_id, peer_id.clone());
+        self.senders.lock().unwrap().insert(
+            peer_id,
+            OneClientSenders {
+                client_sender,
+                view_client_sender,
+                partial_witness_sender,
+                shards_manager_sender,
+            },
+        );
+    }
+
+    /// Partitions the network into groups of nodes: the messages between nodes of different
+    /// groups are dropped until `heal_partition` is called. The nodes missing from `groups`
+    /// form one more group. Messages already sent are still delivered.
+    pub fn partition(&self, groups: &[Vec<AccountId>]) {
+        let partition = groups
+            .iter()
+            .enumerate()
+            .flat_map(|(group, accounts)| {
+                accounts.iter().map(move |account_id| (account_id.clone(), group))
+            })
+            .collect();
+        *self.partition.lock().unwrap() = Some(partition);
+    }
+
+    /// Lets all the nodes reach each other again after `partition`.
+    pub fn heal_partition(&self) {
+        *self.partition.lock().unwrap() = None;
+    }
+
+    /// Whether the messages from `from` to `to` get through the network.
+    fn can_reach(&self, from: &AccountId, to: &AccountId) -> bool {
+        match &*self.partition.lock().unwrap() {
+            Some(partition) => partition.get(from) == partition.get(to),
+            None => true,
         }
+    }
 
-        Self { account_to_peer_id, senders, route_back: Mutex::new(HashMap::new()) }
+    fn can_reach_peer(&self, from: &AccountId, to: &PeerId) -> bool {
+        let to = self
+            .account_to_peer_id
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(_, peer_id)| *peer_id == to)
+            .map(|(account_id, _)| account_id.clone());
+        to.map_or(true, |to| self.can_reach(from, &to))
     }
 
-    fn senders_for_account(&self, account_id: &AccountId) -> &OneClientSenders {
-        self.senders.get(&self.account_to_peer_id[account_id]).unwrap()
+    fn peer_id_for_account(&self, account_id: &AccountId) -> PeerId {
+        self.account_to_peer_id.lock().unwrap()[account_id].clone()
     }
 
-    fn senders_for_peer(&self, peer_id: &PeerId) -> &OneClientSenders {
-        self.senders.get(peer_id).unwrap()
+    fn senders_for_account(&self, account_id: &AccountId) -> OneClientSenders {
+        self.senders_for_peer(&self.peer_id_for_account(account_id))
+    }
+
+    fn senders_for_peer(&self, peer_id: &PeerId) -> OneClientSenders {
+        self.senders.lock().unwrap().get(peer_id).unwrap().clone()
     }
 
     fn generate_route_back(&self, peer_id: &PeerId) -> CryptoHash {
@@ -175,14 +239,12 @@ impl TestLoopNetworkSharedState {
         route_id
     }
 
-    fn senders_for_route_back(&self, route_back: &CryptoHash) -> &OneClientSenders {
-        let lookup = self.route_back.lock().unwrap();
-        let peer_id = lookup.get(route_back).unwrap();
-        self.senders_for_peer(peer_id)
+    fn peer_id_for_route_back(&self, route_back: &CryptoHash) -> PeerId {
+        self.route_back.lock().unwrap().get(route_back).unwrap().clone()
     }
 
-    fn accounts(&self) -> impl Iterator<Item = &AccountId> {
-        self.account_to_peer_id.keys()
+    fn accounts(&self) -> Vec<AccountId> {
+        self.account_to_peer_id.lock().unwrap().keys().cloned().collect()
     }
 }
 
@@ -226,11 +288,13 @@ fn network_message_to_client_handler(
     let my_account_id = my_account_id.clone();
     Box::new(move |request| match request {
         NetworkRequests::Block { block } => {
-            let my_peer_id = shared_state.account_to_peer_id.get(&my_account_id).unwrap();
+            let my_peer_id = shared_state.peer_id_for_account(&my_account_id);
             for account_id in shared_state.accounts() {
-                if account_id != &my_account_id {
+                if account_id != my_account_id
+                    && shared_state.can_reach(&my_account_id, &account_id)
+                {
                     let future = shared_state
-                        .senders_for_account(account_id)
+                        .senders_for_account(&account_id)
                         .client_sender
                         .send_async(BlockResponse {
                             block: block.clone(),
@@ -247,44 +311,64 @@ fn network_message_to_client_handler(
                 approval_message.target, my_account_id,
                 "Sending message to self not supported."
             );
-            let future = shared_state
-                .senders_for_account(&approval_message.target)
-                .client_sender
-                .send_async(BlockApproval(approval_message.approval, PeerId::random()));
-            drop(future);
+            if shared_state.can_reach(&my_account_id, &approval_message.target) {
+                let future = shared_state
+                    .senders_for_account(&approval_message.target)
+                    .client_sender
+                    .send_async(BlockApproval(approval_message.approval, PeerId::random()));
+                drop(future);
+            }
             None
         }
         NetworkRequests::ForwardTx(account, transaction) => {
             assert_ne!(account, my_account_id, "Sending message to self not supported.");
-            let future = shared_state.senders_for_account(&account).client_sender.send_async(
-                ProcessTxRequest { transaction, is_forwarded: true, check_only: false },
-            );
-            drop(future);
+            if shared_state.can_reach(&my_account_id, &account) {
+                let future = shared_state.senders_for_account(&account).client_sender.send_async(
+                    ProcessTxRequest { transaction, is_forwarded: true, check_only: false },
+                );
+                drop(future);
+            }
             None
         }
         NetworkRequests::ChunkEndorsement(target, endorsement) => {
-            let future = shared_state
-                .senders_for_account(&target)
-                .client_sender
-                .send_async(ChunkEndorsementMessage(endorsement));
-            drop(future);
+            if shared_state.can_reach(&my_account_id, &target) {
+                let future = shared_state
+                    .senders_for_account(&target)
+                    .client_sender
+                    .send_async(ChunkEndorsementMessage(endorsement));
+                drop(future);
+            }
+            None
+        }
+        NetworkRequests::ChunkSuppression(target, suppression) => {
+            if shared_state.can_reach(&my_account_id, &target) {
+                let future = shared_state
+                    .senders_for_account(&target)
+                    .client_sender
+                    .send_async(ChunkSuppressionMessage(suppression));
+                drop(future);
+            }
             None
         }
         NetworkRequests::EpochSyncRequest { peer_id } => {
-            let my_peer_id = shared_state.account_to_peer_id.get(&my_account_id).unwrap();
-            assert_ne!(&peer_id, my_peer_id, "Sending message to self not supported.");
-            shared_state
-                .senders_for_peer(&peer_id)
-                .client_sender
-                .send(EpochSyncRequestMessage { from_peer: my_peer_id.clone() });
+            let my_peer_id = shared_state.peer_id_for_account(&my_account_id);
+            assert_ne!(peer_id, my_peer_id, "Sending message to self not supported.");
+            if shared_state.can_reach_peer(&my_account_id, &peer_id) {
+                shared_state
+                    .senders_for_peer(&peer_id)
+                    .client_sender
+                    .send(EpochSyncRequestMessage { from_peer: my_peer_id });
+            }
             None
         }
         NetworkRequests::EpochSyncResponse { peer_id, proof } => {
-            let my_peer_id = shared_state.account_to_peer_id.get(&my_account_id).unwrap();
-            shared_state
-                .senders_for_peer(&peer_id)
-                .client_sender
-                .send(EpochSyncResponseMessage { from_peer: my_peer_id.clone(), proof });
+            let my_peer_id = shared_state.peer_id_for_account(&my_account_id);
+            if shared_state.can_reach_peer(&my_account_id, &peer_id) {
+                shared_state
+                    .senders_for_peer(&peer_id)
+                    .client_sender
+                    .send(EpochSyncResponseMessage { from_peer: my_peer_id, proof });
+            }
             None
         }
         NetworkRequests::StateRequestPart { .. } => None,
@@ -300,26 +384,36 @@ fn network_message_to_view_client_handler(
 ) -> NetworkRequestHandler {
     Box::new(move |request| match request {
         NetworkRequests::BlockHeadersRequest { hashes, peer_id } => {
-            let responder = shared_state.senders_for_account(&my_account_id).client_sender.clone();
+            if !shared_state.can_reach_peer(&my_account_id, &peer_id) {
+                return None;
+            }
+            let responder = shared_state.senders_for_account(&my_account_id).client_sender;
             let future = shared_state
                 .senders_for_peer(&peer_id)
                 .view_client_sender
                 .send_async(BlockHeadersRequest(hashes));
             future_spawner.spawn("wait for ViewClient to handle BlockHeadersRequest", async move {
-                let response = future.await.unwrap().unwrap();
+                // The request is dropped if the peer was killed, see `TestLoopV2::kill_index`.
+                let Ok(response) = future.await else { return };
+                let response = response.unwrap();
                 let future = responder.send_async(BlockHeadersResponse(response, peer_id));
                 drop(future);
             });
             None
         }
         NetworkRequests::BlockRequest { hash, peer_id } => {
-            let responder = shared_state.senders_for_account(&my_account_id).client_sender.clone();
+            if !shared_state.can_reach_peer(&my_account_id, &peer_id) {
+                return None;
+            }
+            let responder = shared_state.senders_for_account(&my_account_id).client_sender;
             let future = shared_state
                 .senders_for_peer(&peer_id)
                 .view_client_sender
                 .send_async(BlockRequest(hash));
             future_spawner.spawn("wait for ViewClient to handle BlockRequest", async move {
-                let response = *future.await.unwrap().unwrap();
+                // The request is dropped if the peer was killed, see `TestLoopV2::kill_index`.
+                let Ok(response) = future.await else { return };
+                let response = *response.unwrap();
                 let future = responder.send_async(BlockResponse {
                     block: response,
                     peer_id,
@@ -341,60 +435,74 @@ fn network_message_to_partial_witness_handler(
     Box::new(move |request| match request {
         NetworkRequests::ChunkStateWitnessAck(target, witness_ack) => {
             assert_ne!(target, my_account_id, "Sending message to self not supported.");
-            shared_state
-                .senders_for_account(&target)
-                .partial_witness_sender
-                .send(ChunkStateWitnessAckMessage(witness_ack));
+            if shared_state.can_reach(&my_account_id, &target) {
+                shared_state
+                    .senders_for_account(&target)
+                    .partial_witness_sender
+                    .send(ChunkStateWitnessAckMessage(witness_ack));
+            }
             None
         }
 
         NetworkRequests::PartialEncodedStateWitness(validator_witness_tuple) => {
             for (target, partial_witness) in validator_witness_tuple.into_iter() {
-                shared_state
-                    .senders_for_account(&target)
-                    .partial_witness_sender
-                    .send(PartialEncodedStateWitnessMessage(partial_witness));
+                if shared_state.can_reach(&my_account_id, &target) {
+                    shared_state
+                        .senders_for_account(&target)
+                        .partial_witness_sender
+                        .send(PartialEncodedStateWitnessMessage(partial_witness));
+                }
             }
             None
         }
         NetworkRequests::PartialEncodedStateWitnessForward(chunk_validators, partial_witness) => {
             for target in chunk_validators {
-                shared_state
-                    .senders_for_account(&target)
-                    .partial_witness_sender
-                    .send(PartialEncodedStateWitnessForwardMessage(partial_witness.clone()));
+                if shared_state.can_reach(&my_account_id, &target) {
+                    shared_state
+                        .senders_for_account(&target)
+                        .partial_witness_sender
+                        .send(PartialEncodedStateWitnessForwardMessage(partial_witness.clone()));
+                }
             }
             None
         }
         NetworkRequests::ChunkContractAccesses(chunk_validators, accesses) => {
             for target in chunk_validators {
-                shared_state
-                    .senders_for_account(&target)
-                    .partial_witness_sender
-                    .send(ChunkContractAccessesMessage(accesses.clone()));
+                if shared_state.can_reach(&my_account_id, &target) {
+                    shared_state
+                        .senders_for_account(&target)
+                        .partial_witness_sender
+                        .send(ChunkContractAccessesMessage(accesses.clone()));
+                }
             }
             None
         }
         NetworkRequests::ContractCodeRequest(target, request) => {
-            shared_state
-                .senders_for_account(&target)
-                .partial_witness_sender
-                .send(ContractCodeRequestMessage(request));
+            if shared_state.can_reach(&my_account_id, &target) {
+                shared_state
+                    .senders_for_account(&target)
+                    .partial_witness_sender
+                    .send(ContractCodeRequestMessage(request));
+            }
             None
         }
         NetworkRequests::ContractCodeResponse(target, response) => {
-            shared_state
-                .senders_for_account(&target)
-                .partial_witness_sender
-                .send(ContractCodeResponseMessage(response));
+            if shared_state.can_reach(&my_account_id, &target) {
+                shared_state
+                    .senders_for_account(&target)
+                    .partial_witness_sender
+                    .send(ContractCodeResponseMessage(response));
+            }
             None
         }
         NetworkRequests::PartialEncodedContractDeploys(accounts, deploys) => {
             for account in accounts {
-                shared_state
-                    .senders_for_account(&account)
-                    .partial_witness_sender
-                    .send(PartialEncodedContractDeploysMessage(deploys.clone()));
+                if shared_state.can_reach(&my_account_id, &account) {
+                    shared_state
+                        .senders_for_account(&account)
+                        .partial_witness_sender
+                        .send(PartialEncodedContractDeploysMessage(deploys.clone()));
+                }
             }
             None
         }
@@ -417,43 +525,51 @@ fn network_message_to_shards_manager_handler(
     let my_account_id = my_account_id.clone();
     Box::new(move |request| match request {
         NetworkRequests::PartialEncodedChunkRequest { target, request, .. } => {
-            let my_peer_id = shared_state.account_to_peer_id.get(&my_account_id).unwrap();
-            let route_back = shared_state.generate_route_back(my_peer_id);
+            let my_peer_id = shared_state.peer_id_for_account(&my_account_id);
+            let route_back = shared_state.generate_route_back(&my_peer_id);
             let target = target.account_id.unwrap();
             assert!(target != my_account_id, "Sending message to self not supported.");
-            shared_state.senders_for_account(&target).shards_manager_sender.send(
-                ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkRequest {
-                    partial_encoded_chunk_request: request,
-                    route_back,
-                },
-            );
+            if shared_state.can_reach(&my_account_id, &target) {
+                shared_state.senders_for_account(&target).shards_manager_sender.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkRequest {
+                        partial_encoded_chunk_request: request,
+                        route_back,
+                    },
+                );
+            }
             None
         }
         NetworkRequests::PartialEncodedChunkResponse { route_back, response } => {
             // Use route_back information to send the response back to the correct client.
-            shared_state.senders_for_route_back(&route_back).shards_manager_sender.send(
-                ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
-                    partial_encoded_chunk_response: response,
-                    received_time: clock.now(),
-                },
-            );
+            let peer_id = shared_state.peer_id_for_route_back(&route_back);
+            if shared_state.can_reach_peer(&my_account_id, &peer_id) {
+                shared_state.senders_for_peer(&peer_id).shards_manager_sender.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkResponse {
+                        partial_encoded_chunk_response: response,
+                        received_time: clock.now(),
+                    },
+                );
+            }
             None
         }
         NetworkRequests::PartialEncodedChunkMessage { account_id, partial_encoded_chunk } => {
             assert!(account_id != my_account_id, "Sending message to self not supported.");
-            shared_state.senders_for_account(&account_id).shards_manager_sender.send(
-                ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunk(
-                    partial_encoded_chunk.into(),
-                ),
-            );
+            if shared_state.can_reach(&my_account_id, &account_id) {
+                shared_state.senders_for_account(&account_id).shards_manager_sender.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunk(
+                        partial_encoded_chunk.into(),
+                    ),
+                );
+            }
             None
         }
         NetworkRequests::PartialEncodedChunkForward { account_id, forward } => {
             assert!(account_id != my_account_id, "Sending message to self not supported.");
-            shared_state
-                .senders_for_account(&account_id)
-                .shards_manager_sender
-                .send(ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkForward(forward));
+            if shared_state.can_reach(&my_account_id, &account_id) {
+                shared_state.senders_for_account(&account_id).shards_manager_sender.send(
+                    ShardsManagerRequestFromNetwork::ProcessPartialEncodedChunkForward(forward),
+                );
+            }
             None
         }
         _ => Some(request),
@@ -6,8 +6,8 @@ pub use crate::network_protocol::{
 /// Exported types, which are part of network protocol.
 pub use crate::network_protocol::{
     Edge, PartialEdgeInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, PeerChainInfoV2, PeerInfo, SnapshotHostInfo, StateResponseInfo,
-    StateResponseInfoV1, StateResponseInfoV2,
+    PartialEncodedChunkResponseMsg, PeerCapabilities, PeerCapability, PeerChainInfoV2, PeerInfo,
+    SnapshotHostInfo, StateResponseInfo, StateResponseInfoV1, StateResponseInfoV2,
 };
 use crate::routing::routing_table_view::RoutingTableInfo;
 pub use crate::state_sync::StateSyncResponse;
@@ -16,6 +16,7 @@ use near_async::{time, MultiSend, MultiSendMessage, MultiSenderFrom};
 use near_crypto::PublicKey;
 use near_primitives::block::{ApprovalMessage, Block, GenesisId};
 use near_primitives::challenge::Challenge;
+use near_primitives::chunk_suppression::ChunkSuppression;
 use near_primitives::epoch_sync::CompressedEpochSyncProof;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
@@ -40,7 +41,18 @@ use std::sync::Arc;
 pub const ROUTED_MESSAGE_TTL: u8 = 100;
 
 /// Peer type.
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, strum::IntoStaticStr)]
+#[derive(
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    Copy,
+    Clone,
+    Debug,
+    Eq,
+    PartialEq,
+    Hash,
+    strum::IntoStaticStr,
+    ProtocolSchema,
+)]
 pub enum PeerType {
     /// Inbound session
     Inbound,
@@ -141,6 +153,34 @@ pub struct ConnectionInfo {
     pub time_connected_until: time::Utc,
 }
 
+/// Event recorded in the connection audit log.
+#[derive(
+    borsh::BorshSerialize,
+    borsh::BorshDeserialize,
+    Debug,
+    Clone,
+    Eq,
+    PartialEq,
+    strum::IntoStaticStr,
+    ProtocolSchema,
+)]
+pub enum ConnectionAuditEvent {
+    /// The connection was registered after a successful handshake.
+    Established { tier: crate::tcp::Tier, peer_type: PeerType },
+    /// The registered connection was closed.
+    Closed { tier: crate::tcp::Tier, peer_type: PeerType, reason: String },
+    /// The peer was banned.
+    Banned { reason: ReasonForBan },
+}
+
+/// Entry of the connection audit log.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ConnectionAuditEntry {
+    pub time: time::Utc,
+    pub peer_info: PeerInfo,
+    pub event: ConnectionAuditEvent,
+}
+
 impl KnownPeerStatus {
     pub fn is_banned(&self) -> bool {
         matches!(self, KnownPeerStatus::Banned(_, _))
@@ -159,6 +199,9 @@ pub struct ChainInfo {
     pub tracked_shards: Vec<ShardId>,
     // The latest block on chain.
     pub block: Block,
+    // Height of the earliest block available on this node, i.e. the tail of the chain. It is
+    // advertised to the peers in the handshake.
+    pub earliest_block_height: BlockHeight,
     // Public keys of accounts participating in the BFT consensus
     // It currently includes "block producers", "chunk producers" and "approvers".
     // They are collectively known as "validators".
@@ -287,6 +330,8 @@ pub enum NetworkRequests {
     ChunkStateWitnessAck(AccountId, ChunkStateWitnessAck),
     /// Message for a chunk endorsement, sent by a chunk validator to the block producer.
     ChunkEndorsement(AccountId, ChunkEndorsement),
+    /// Message for a chunk suppression, sent by a chunk producer to the block producer.
+    ChunkSuppression(AccountId, ChunkSuppression),
     /// Message from chunk producer to set of chunk validators to send state witness part.
     PartialEncodedStateWitness(Vec<(AccountId, PartialEncodedStateWitness)>),
     /// Message from chunk validator to all other chunk validators to forward state witness part.
@@ -337,6 +382,24 @@ pub struct HighestHeightPeerInfo {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Height of the earliest block available at the peer when it connected, if advertised.
+    pub earliest_block_height: Option<BlockHeight>,
+}
+
+impl HighestHeightPeerInfo {
+    /// Whether the peer is expected to have the block at `height`. Archival nodes serve all the
+    /// blocks, even if they advertised the tail of their hot store with split storage. Other
+    /// peers that advertised their earliest available block are trusted. For the rest, old
+    /// blocks, below the GC horizon of a regular node, aren't expected.
+    pub fn may_have_block(&self, height: BlockHeight, is_old_block: bool) -> bool {
+        if self.archival {
+            return true;
+        }
+        match self.earliest_block_height {
+            Some(earliest_block_height) => earliest_block_height <= height,
+            None => !is_old_block,
+        }
+    }
 }
 
 impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
@@ -349,6 +412,7 @@ impl From<FullPeerInfo> for Option<HighestHeightPeerInfo> {
                 highest_block_hash: p.chain_info.last_block.unwrap().hash,
                 tracked_shards: p.chain_info.tracked_shards,
                 archival: p.chain_info.archival,
+                earliest_block_height: p.chain_info.earliest_block_height,
             })
         } else {
             None
@@ -375,6 +439,9 @@ pub struct PeerChainInfo {
     pub tracked_shards: Vec<ShardId>,
     /// Denote if a node is running in archival mode or not.
     pub archival: bool,
+    /// Height of the earliest block available at the peer, as advertised in the handshake.
+    /// The peer keeps garbage collecting old blocks, so this is only a lower bound.
+    pub earliest_block_height: Option<BlockHeight>,
 }
 
 // Information about the connected peer that is shared with the rest of the system.
@@ -532,6 +599,9 @@ pub struct AccountIdOrPeerTrackingShard {
     pub only_archival: bool,
     /// Only send messages to peers whose latest chain height is no less `min_height`
     pub min_height: BlockHeight,
+    /// Height of the requested chunk. Peers that advertised a higher earliest available block
+    /// no longer have it, so messages are not sent to them.
+    pub height: BlockHeight,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, actix::Message)]
@@ -195,3 +195,13 @@ impl AsyncComputationSpawner for StdThreadAsyncComputationSpawnerForTest {
         std::thread::spawn(f);
     }
 }
+
+/// Runs the computation synchronously on the calling thread. Useful for tests
+/// which expect the effects of the computation right after it is spawned.
+pub struct InlineAsyncComputationSpawnerForTest;
+
+impl AsyncComputationSpawner for InlineAsyncComputationSpawnerForTest {
+    fn spawn_boxed(&self, _name: &str, f: Box<dyn FnOnce() + Send>) {
+        f();
+    }
+}
@@ -7,6 +7,7 @@ mod functional;
 pub mod futures;
 pub mod messaging;
 pub mod test_loop;
+pub mod test_utils;
 
 // FIXME: near_time re-export is not optimal solution, but it would require to change time in many places
 pub use near_time as time;
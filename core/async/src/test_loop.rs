@@ -63,21 +63,31 @@ pub mod data;
 pub mod futures;
 pub mod pending_events_sender;
 pub mod sender;
+pub mod trace;
 
 use data::TestLoopData;
 use futures::{TestLoopAsyncComputationSpawner, TestLoopFutureSpawner};
-use near_time::{Clock, Duration, FakeClock};
+use near_time::{Clock, Duration, FakeClock, SkewedClock};
 use pending_events_sender::{CallbackEvent, PendingEventsSender};
 use sender::TestLoopSender;
 use serde::Serialize;
-use std::collections::BinaryHeap;
+use std::collections::{BinaryHeap, HashSet, VecDeque};
+use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::sync::Mutex;
 use time::ext::InstantExt;
+use trace::{TraceEvent, TraceRecorder, TraceReplayer};
 
 use crate::messaging::{Actor, LateBoundSender};
 
+/// Number of the last handled events listed when `run_until` times out.
+const NUM_RECENT_EVENTS_IN_DUMP: usize = 20;
+/// Maximum number of pending events listed when `run_until` times out.
+const MAX_PENDING_EVENTS_IN_DUMP: usize = 50;
+/// Maximum length of the description of an event listed when `run_until` times out.
+const MAX_DESCRIPTION_LEN_IN_DUMP: usize = 200;
+
 /// Main struct for the Test Loop framework.
 /// The `TestLoopData` should contain all the business logic state that is relevant
 /// to the test. All possible `Event` that are sent to the event loop are callbacks.
@@ -107,6 +117,20 @@ pub struct TestLoopV2 {
     /// If present, a function to call to print something every time an event is
     /// handled. Intended only for debugging.
     every_event_callback: Option<Box<dyn FnMut(&TestLoopData)>>,
+    /// Indices whose events are dropped, see `kill_index`.
+    killed_indices: HashSet<usize>,
+    /// The last handled events, listed when `run_until` times out.
+    recent_events: VecDeque<String>,
+    /// ID of the event being handled, the parent of the events sent by its handler.
+    current_event: Option<usize>,
+    /// If enabled, the handled events, written to a file if the test panics, see `trace`.
+    trace: Option<TraceRecorder>,
+    /// Directory the trace is written to if the test panics.
+    trace_dir: PathBuf,
+    /// If replaying a trace, assigns the recorded IDs and due times to the events.
+    replayer: Option<TraceReplayer>,
+    /// If present, a function summarizing the state of the nodes in the trace.
+    trace_state_observer: Option<Box<dyn FnMut(&TestLoopData) -> String>>,
 }
 
 /// An event waiting to be executed, ordered by the due time and then by ID.
@@ -114,6 +138,21 @@ struct EventInHeap {
     event: CallbackEvent,
     due: Duration,
     id: usize,
+    /// ID of the event whose handler sent this event.
+    parent: Option<usize>,
+}
+
+impl EventInHeap {
+    /// Describes the event in the dump of `run_until` timeouts. The description starts with
+    /// the index and the type of the actor the event is sent to.
+    fn dump_line(&self) -> String {
+        let description = &self.event.description;
+        let description = match description.char_indices().nth(MAX_DESCRIPTION_LEN_IN_DUMP) {
+            Some((end, _)) => format!("{}...", &description[..end]),
+            None => description.clone(),
+        };
+        format!("  #{} due at {}: {}", self.id, self.due, description)
+    }
 }
 
 impl PartialEq for EventInHeap {
@@ -202,6 +241,14 @@ impl TestLoopV2 {
         let shutting_down = Arc::new(AtomicBool::new(false));
         // Needed for the log visualizer to know when the test loop starts.
         tracing::info!(target: "test_loop", "TEST_LOOP_INIT");
+        let replayer = std::env::var_os(trace::REPLAY_ENV_VAR).map(|path| {
+            let trace = trace::read_trace(path.as_ref()).unwrap_or_else(|err| {
+                panic!("failed to read the trace to replay from {:?}: {err}", path)
+            });
+            tracing::info!(target: "test_loop", ?path, "replaying trace");
+            TraceReplayer::new(trace)
+        });
+        let trace_dir = std::env::var_os(trace::TRACE_DIR_ENV_VAR).map(PathBuf::from);
         Self {
             data: TestLoopData::new(pending_events_sender.clone(), shutting_down.clone()),
             events: BinaryHeap::new(),
@@ -212,6 +259,13 @@ impl TestLoopV2 {
             clock: FakeClock::default(),
             shutting_down,
             every_event_callback: None,
+            killed_indices: HashSet::new(),
+            recent_events: VecDeque::new(),
+            current_event: None,
+            trace: trace_dir.is_some().then(TraceRecorder::default),
+            trace_dir: trace_dir.unwrap_or_else(std::env::temp_dir),
+            replayer,
+            trace_state_observer: None,
         }
     }
 
@@ -263,6 +317,13 @@ impl TestLoopV2 {
         self.clock.clock()
     }
 
+    /// Returns a clock deviating from the virtual time, see `SkewedClock`. Futures sleeping on
+    /// it wake up when the virtual time reaches their deadline on the skewed clock, but the
+    /// events of the loop are still scheduled by the virtual time.
+    pub fn skewed_clock(&self, utc_offset: Duration, drift_rate: f64) -> SkewedClock {
+        SkewedClock::new(self.clock.clone(), utc_offset, drift_rate)
+    }
+
     pub fn register_actor<A>(
         &mut self,
         actor: A,
@@ -286,18 +347,76 @@ impl TestLoopV2 {
         self.data.register_actor_for_index(index, actor, adapter)
     }
 
+    /// Kills the actors registered for `index`: the events already queued for them are dropped,
+    /// and so are the events sent to them until `revive_index` is called. This simulates a
+    /// crash of a node in multi-node tests, where each node registers its actors for its own
+    /// index. Futures and async computations are not tied to an index, so they keep running.
+    pub fn kill_index(&mut self, index: usize) {
+        self.killed_indices.insert(index);
+        self.queue_received_events();
+        self.events.retain(|event| event.event.index != Some(index));
+    }
+
+    /// Stops dropping the events of the actors registered for `index`, see `kill_index`.
+    pub fn revive_index(&mut self, index: usize) {
+        self.killed_indices.remove(&index);
+    }
+
     pub fn set_every_event_callback(&mut self, callback: impl FnMut(&TestLoopData) + 'static) {
         self.every_event_callback = Some(Box::new(callback));
     }
 
+    /// Sets a function summarizing the state of the nodes, e.g. their heads, called after every
+    /// event if the trace is enabled. The state is added to the trace whenever it changes.
+    pub fn set_trace_state_observer(
+        &mut self,
+        observer: impl FnMut(&TestLoopData) -> String + 'static,
+    ) {
+        self.trace_state_observer = Some(Box::new(observer));
+    }
+
+    /// Replays the trace of a previous run, see `trace`. Must be called before any event is
+    /// sent to the loop. Running the test with `NEAR_TEST_LOOP_REPLAY` does the same.
+    pub fn replay_trace(&mut self, trace: Vec<TraceEvent>) {
+        assert_eq!(self.next_event_index, 0, "the trace must be replayed from the start");
+        self.replayer = Some(TraceReplayer::new(trace));
+    }
+
+    /// Records the trace of the run, written to the temporary directory if the test panics. Must
+    /// be called before any event is sent to the loop. Running the test with
+    /// `NEAR_TEST_LOOP_TRACE_DIR` does the same.
+    pub fn enable_trace(&mut self) {
+        assert_eq!(self.next_event_index, 0, "the trace must be recorded from the start");
+        self.trace.get_or_insert_with(TraceRecorder::default);
+    }
+
+    /// The events handled so far, empty unless the trace is enabled.
+    pub fn trace(&self) -> &[TraceEvent] {
+        self.trace.as_ref().map_or(&[], |trace| &trace.events)
+    }
+
+    /// Writes the trace to a file, and returns its path.
+    fn dump_trace(&self, trace: &TraceRecorder) -> std::io::Result<PathBuf> {
+        let path = trace::trace_path(&self.trace_dir);
+        trace::write_trace(&path, &trace.events)?;
+        Ok(path)
+    }
+
     /// Helper to push events we have just received into the heap.
     fn queue_received_events(&mut self) {
         for event in self.pending_events.lock().unwrap().events.drain(..) {
-            self.events.push(EventInHeap {
-                due: self.current_time + event.delay,
-                id: self.next_event_index,
-                event,
-            });
+            if event.index.is_some_and(|index| self.killed_indices.contains(&index)) {
+                continue;
+            }
+            let parent = self.current_event;
+            let due = self.current_time + event.delay;
+            let (id, due) = match &mut self.replayer {
+                Some(replayer) => {
+                    replayer.assign(parent, &event.description, self.current_time, due)
+                }
+                None => (self.next_event_index, due),
+            };
+            self.events.push(EventInHeap { due, id, parent, event });
             self.next_event_index += 1;
         }
     }
@@ -363,6 +482,19 @@ impl TestLoopV2 {
 
     /// Processes the given event, by logging a line first and then finding a handler to run it.
     fn process_event(&mut self, event: EventInHeap) {
+        if self.recent_events.len() == NUM_RECENT_EVENTS_IN_DUMP {
+            self.recent_events.pop_front();
+        }
+        self.recent_events.push_back(event.dump_line());
+        if let Some(trace) = &mut self.trace {
+            trace.record(
+                event.id,
+                event.parent,
+                event.event.index,
+                event.due,
+                &event.event.description,
+            );
+        }
         let start_json = serde_json::to_string(&EventStartLogOutput {
             current_index: event.id,
             total_events: self.next_event_index,
@@ -378,11 +510,16 @@ impl TestLoopV2 {
         }
 
         let callback = event.event.callback;
+        self.current_event = Some(event.id);
         callback(&mut self.data);
+        if let (Some(trace), Some(observer)) = (&mut self.trace, &mut self.trace_state_observer) {
+            trace.record_state(observer(&self.data));
+        }
 
         // Push any new events into the queue. Do this before emitting the end log line,
         // so that it contains the correct new total number of events.
         self.queue_received_events();
+        self.current_event = None;
         let end_json =
             serde_json::to_string(&EventEndLogOutput { total_events: self.next_event_index })
                 .unwrap();
@@ -417,7 +554,8 @@ impl TestLoopV2 {
         maximum_duration: Duration,
     ) {
         let deadline = self.current_time + maximum_duration;
-        let mut decider = move |next_time, data: &mut TestLoopData| {
+        let mut timed_out = false;
+        let mut decider = |next_time, data: &mut TestLoopData| {
             if condition(data) {
                 return AdvanceDecision::Stop;
             }
@@ -426,11 +564,43 @@ impl TestLoopV2 {
                     return AdvanceDecision::AdvanceToNextEvent;
                 }
             }
-            panic!("run_until did not fulfill the condition within the given deadline");
+            timed_out = true;
+            AdvanceDecision::Stop
         };
         while let Some(event) = self.advance_till_next_event(&mut decider) {
             self.process_event(event);
         }
+        if timed_out {
+            panic!(
+                "run_until did not fulfill the condition within the given deadline {}.\n{}",
+                deadline,
+                self.dump_events()
+            );
+        }
+    }
+
+    /// Lists the pending events, in the order they would be handled, and the last handled
+    /// events, to help finding out why a test hangs.
+    fn dump_events(&mut self) -> String {
+        self.queue_received_events();
+        let mut pending_events = self.events.iter().collect::<Vec<_>>();
+        pending_events.sort_by_key(|event| (event.due, event.id));
+        let mut dump =
+            format!("{} pending events at {}:\n", pending_events.len(), self.current_time);
+        for event in pending_events.iter().take(MAX_PENDING_EVENTS_IN_DUMP) {
+            dump += &event.dump_line();
+            dump += "\n";
+        }
+        if pending_events.len() > MAX_PENDING_EVENTS_IN_DUMP {
+            dump +=
+                &format!("  ... and {} more\n", pending_events.len() - MAX_PENDING_EVENTS_IN_DUMP);
+        }
+        dump += &format!("Last {} handled events:\n", self.recent_events.len());
+        for line in &self.recent_events {
+            dump += line;
+            dump += "\n";
+        }
+        dump
     }
 
     pub fn shutdown_and_drain_remaining_events(mut self, maximum_duration: Duration) {
@@ -447,6 +617,29 @@ impl TestLoopV2 {
 impl Drop for TestLoopV2 {
     fn drop(&mut self) {
         self.queue_received_events();
+        // Don't hide the panic of the test, e.g. a timeout of `run_until`, by a second one.
+        if std::thread::panicking() {
+            self.events.clear();
+            let Some(trace) = &self.trace else {
+                tracing::info!(
+                    target: "test_loop",
+                    "Run the test with {} set to record the trace of the test loop",
+                    trace::TRACE_DIR_ENV_VAR
+                );
+                return;
+            };
+            match self.dump_trace(trace) {
+                Ok(path) => tracing::error!(
+                    target: "test_loop",
+                    "The trace of the test loop is written to {}, replay it with {}={}",
+                    path.display(),
+                    trace::REPLAY_ENV_VAR,
+                    path.display()
+                ),
+                Err(err) => tracing::error!(target: "test_loop", ?err, "failed to write the trace"),
+            }
+            return;
+        }
         if let Some(event) = self.events.pop() {
             // Drop any references that may be held by the event callbacks. This can help
             // with destruction of the data.
@@ -471,6 +664,7 @@ enum AdvanceDecision {
 #[cfg(test)]
 mod tests {
     use crate::futures::FutureSpawnerExt;
+    use crate::test_loop::trace::{read_trace, write_trace, TraceEvent};
     use crate::test_loop::TestLoopV2;
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
@@ -514,4 +708,112 @@ mod tests {
         test_loop.run_for(Duration::seconds(30));
         assert_eq!(finished.load(Ordering::Relaxed), 2);
     }
+
+    // Tests that futures sleeping on a skewed clock wake up when the skewed clock, rather than
+    // the virtual time, reaches their deadline.
+    #[test]
+    fn test_skewed_clock() {
+        let mut test_loop = TestLoopV2::new();
+        let clock = test_loop.clock();
+        let skewed_clock = test_loop.skewed_clock(Duration::seconds(-3), 1.0);
+        assert_eq!(skewed_clock.now_utc(), clock.now_utc() - Duration::seconds(3));
+
+        let finished = Arc::new(AtomicUsize::new(0));
+        let finished1 = finished.clone();
+        let skewed_clock1 = skewed_clock.clock();
+        let start_time = skewed_clock1.now();
+        test_loop.future_spawner().spawn("test", async move {
+            skewed_clock1.sleep(Duration::seconds(10)).await;
+            assert!(skewed_clock1.now() >= start_time + Duration::seconds(10));
+            finished1.fetch_add(1, Ordering::Relaxed);
+        });
+
+        // The skewed clock runs twice as fast, so the future wakes up after 5 virtual seconds.
+        test_loop.run_for(Duration::seconds(4));
+        assert_eq!(finished.load(Ordering::Relaxed), 0);
+        test_loop.run_for(Duration::seconds(2));
+        assert_eq!(finished.load(Ordering::Relaxed), 1);
+        assert!(skewed_clock.now_utc() > clock.now_utc() + Duration::seconds(2));
+
+        // Changing the drift rate doesn't make the clock jump.
+        let before = skewed_clock.now();
+        skewed_clock.set_drift_rate(0.0);
+        assert_eq!(skewed_clock.now(), before);
+        test_loop.run_for(Duration::seconds(1));
+        assert_eq!(skewed_clock.now(), before + Duration::seconds(1));
+    }
+
+    // Tests that a timeout of run_until lists the pending and the last handled events.
+    #[test]
+    fn test_run_until_timeout_dumps_events() {
+        let mut test_loop = TestLoopV2::new();
+        test_loop.send_adhoc_event("handled".to_string(), |_| {});
+        test_loop.send_adhoc_event_with_delay("pending".to_string(), Duration::seconds(10), |_| {});
+
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            test_loop.run_until(|_| false, Duration::seconds(1));
+        }));
+        let message = result.unwrap_err().downcast::<String>().unwrap();
+        let (pending_events, handled_events) = message.split_once("handled events").unwrap();
+        assert!(pending_events.contains("1 pending events"), "{message}");
+        assert!(pending_events.contains("Adhoc(pending)"), "{message}");
+        assert!(handled_events.contains("Adhoc(handled)"), "{message}");
+
+        test_loop.run_for(Duration::seconds(10));
+    }
+
+    /// Sends ad-hoc events with the given names and delays, and returns the names in the order
+    /// they are handled, with the trace of the run.
+    fn run_adhoc_events(
+        events: &[(&'static str, Duration)],
+        trace: Option<Vec<TraceEvent>>,
+    ) -> (Vec<&'static str>, Vec<TraceEvent>) {
+        let mut test_loop = TestLoopV2::new();
+        test_loop.enable_trace();
+        if let Some(trace) = trace {
+            test_loop.replay_trace(trace);
+        }
+        let handle = test_loop.data.register_data(Vec::new());
+        for &(name, delay) in events {
+            let handle = handle.clone();
+            test_loop.send_adhoc_event_with_delay(name.to_string(), delay, move |data| {
+                data.get_mut(&handle).push(name)
+            });
+        }
+        test_loop.run_for(Duration::seconds(1));
+        (test_loop.data.get(&handle).clone(), test_loop.trace().to_vec())
+    }
+
+    // Tests that replaying a trace reproduces the order and the timing of the recorded events,
+    // even if they are sent in another order and with other delays.
+    #[test]
+    fn test_trace_replay() {
+        let (order, trace) = run_adhoc_events(
+            &[("a", Duration::ZERO), ("b", Duration::ZERO), ("c", Duration::milliseconds(10))],
+            None,
+        );
+        assert_eq!(order, vec!["a", "b", "c"]);
+        assert_eq!(trace.len(), 3);
+        assert_eq!(trace[2].due_ns, 10_000_000);
+
+        let path = std::env::temp_dir().join(format!("test_trace_replay.{}", std::process::id()));
+        write_trace(&path, &trace).unwrap();
+        let read = read_trace(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, trace);
+
+        let (order, replayed_trace) = run_adhoc_events(
+            &[
+                ("c", Duration::milliseconds(30)),
+                ("d", Duration::ZERO),
+                ("b", Duration::ZERO),
+                ("a", Duration::ZERO),
+            ],
+            Some(read),
+        );
+        // The event missing from the trace is handled after the recorded ones due at the
+        // same time.
+        assert_eq!(order, vec!["a", "b", "d", "c"]);
+        assert_eq!(replayed_trace[3].due_ns, 10_000_000);
+    }
 }
@@ -84,6 +84,28 @@ impl TestLoopData {
         sender
     }
 
+    /// Function to replace an actor registered in the TestLoopData with a new instance, e.g.
+    /// to restart a node. Additionally schedules the start event for the new actor.
+    /// The senders of the previous actor, including the bound adapters, send to the new one.
+    pub fn replace_actor_for_index<A>(
+        &mut self,
+        index: usize,
+        actor_handle: &TestLoopDataHandle<A>,
+        actor: A,
+    ) -> TestLoopSender<A>
+    where
+        A: Actor + 'static,
+    {
+        *self.get_mut(actor_handle) = actor;
+        let sender = TestLoopSender::new(
+            actor_handle.clone(),
+            self.pending_events_sender.clone().for_index(index),
+            self.shutting_down.clone(),
+        );
+        self.queue_start_actor_event(sender.clone());
+        sender
+    }
+
     // Helper function to queue the start actor event on the test loop while registering an actor.
     fn queue_start_actor_event<A>(&self, mut sender: TestLoopSender<A>)
     where
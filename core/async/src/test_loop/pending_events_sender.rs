@@ -9,21 +9,22 @@ type TestLoopCallback = Box<dyn FnOnce(&mut TestLoopData) + Send>;
 /// Interface to send an event with a delay (in virtual time).
 #[derive(Clone)]
 pub struct PendingEventsSender {
-    client_index: usize,
+    client_index: Option<usize>,
     sender: Arc<dyn Fn(CallbackEvent) + Send + Sync>,
 }
 
 impl PendingEventsSender {
     pub(crate) fn new(f: impl Fn(CallbackEvent) + Send + Sync + 'static) -> Self {
-        Self { client_index: 0, sender: Arc::new(f) }
+        Self { client_index: None, sender: Arc::new(f) }
     }
 
     pub(crate) fn set_index(&mut self, index: usize) {
-        self.client_index = index;
+        self.client_index = Some(index);
     }
 
     /// Set the index of the actor that is sending the event.
-    /// This is purely for debug purposes and does not affect the execution of the event.
+    /// This is mostly for debug purposes; the execution of the event is only affected if the
+    /// index is killed, see `TestLoopV2::kill_index`.
     pub fn for_index(mut self, index: usize) -> Self {
         self.set_index(index);
         self
@@ -41,8 +42,8 @@ impl PendingEventsSender {
         callback: TestLoopCallback,
         delay: Duration,
     ) {
-        let description = format!("({},{})", self.client_index, description);
-        (self.sender)(CallbackEvent { description, callback, delay });
+        let description = format!("({},{})", self.client_index.unwrap_or(0), description);
+        (self.sender)(CallbackEvent { description, callback, delay, index: self.client_index });
     }
 }
 
@@ -55,4 +56,6 @@ pub(crate) struct CallbackEvent {
     pub(crate) callback: TestLoopCallback,
     pub(crate) delay: Duration,
     pub(crate) description: String,
+    /// Index of the actor the event was sent for, if any.
+    pub(crate) index: Option<usize>,
 }
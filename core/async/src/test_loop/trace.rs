@@ -0,0 +1,192 @@
+//! Trace of the events handled by the test loop, to reproduce the interleaving of a failed run.
+//!
+//! The trace is only recorded when the `NEAR_TEST_LOOP_TRACE_DIR` environment variable is set,
+//! as it grows with every handled event. Every handled event is then recorded: its ID, the ID
+//! of the event whose handler sent it, the index of the actor it was sent to, the virtual time
+//! it was handled at and its description (i.e. the message delivered, the timer fired, ...). A
+//! state observer set by the test can add a summary of the state of the nodes whenever it
+//! changes, see `TestLoopV2::set_trace_state_observer`.
+//!
+//! When the test panics, the trace is written as JSON lines to a file in that directory. Running
+//! the test again with `NEAR_TEST_LOOP_REPLAY` set to the path of that file makes the loop
+//! handle the events in the recorded order and at the recorded times, even if e.g. random
+//! delays differ. An event is identified across runs by its parent event, its description and
+//! the number of identical events sent before it by the same parent. The events which aren't in
+//! the trace are handled after the recorded events due at the same time.
+use near_time::Duration;
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::io::{BufRead, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Directory the trace is written to when the test panics. The trace is recorded only if set.
+pub const TRACE_DIR_ENV_VAR: &str = "NEAR_TEST_LOOP_TRACE_DIR";
+/// Path of a trace to replay.
+pub const REPLAY_ENV_VAR: &str = "NEAR_TEST_LOOP_REPLAY";
+
+/// Maximum length of the description of an event kept in the trace. The events are matched
+/// by the hash of the whole description.
+const MAX_DESCRIPTION_LEN_IN_TRACE: usize = 100;
+
+/// A handled event, one line of the trace file.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct TraceEvent {
+    pub id: usize,
+    /// ID of the event whose handler sent this event. None for the events sent by the test, or
+    /// by the futures woken up by the clock.
+    pub parent: Option<usize>,
+    /// Index of the actor the event was sent to, if any.
+    pub index: Option<usize>,
+    /// Virtual time at which the event was handled.
+    pub due_ns: u64,
+    pub description_hash: u64,
+    /// Description of the event, truncated.
+    pub description: String,
+    /// State reported by the state observer after handling the event, if it changed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state: Option<String>,
+}
+
+pub(crate) fn description_hash(description: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    description.hash(&mut hasher);
+    hasher.finish()
+}
+
+pub fn write_trace(path: &Path, trace: &[TraceEvent]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(std::fs::File::create(path)?);
+    for event in trace {
+        serde_json::to_writer(&mut writer, event)?;
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
+
+pub fn read_trace(path: &Path) -> std::io::Result<Vec<TraceEvent>> {
+    let reader = std::io::BufReader::new(std::fs::File::open(path)?);
+    let mut trace = vec![];
+    for line in reader.lines() {
+        trace.push(serde_json::from_str(&line?)?);
+    }
+    Ok(trace)
+}
+
+/// Path of the trace written to `dir` when the test running on the current thread panics. The
+/// threads of the tests are named after the tests.
+pub(crate) fn trace_path(dir: &Path) -> PathBuf {
+    let name: String = std::thread::current()
+        .name()
+        .unwrap_or("test_loop")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect();
+    dir.join(format!("{name}.{}.trace.jsonl", std::process::id()))
+}
+
+/// Records the handled events.
+#[derive(Default)]
+pub(crate) struct TraceRecorder {
+    pub(crate) events: Vec<TraceEvent>,
+    last_state: Option<String>,
+}
+
+impl TraceRecorder {
+    pub(crate) fn record(
+        &mut self,
+        id: usize,
+        parent: Option<usize>,
+        index: Option<usize>,
+        due: Duration,
+        description: &str,
+    ) {
+        let truncated = match description.char_indices().nth(MAX_DESCRIPTION_LEN_IN_TRACE) {
+            Some((end, _)) => format!("{}...", &description[..end]),
+            None => description.to_string(),
+        };
+        self.events.push(TraceEvent {
+            id,
+            parent,
+            index,
+            due_ns: due.whole_nanoseconds() as u64,
+            description_hash: description_hash(description),
+            description: truncated,
+            state: None,
+        });
+    }
+
+    /// Adds the state after handling the last recorded event, if it changed.
+    pub(crate) fn record_state(&mut self, state: String) {
+        if self.last_state.as_ref() == Some(&state) {
+            return;
+        }
+        if let Some(event) = self.events.last_mut() {
+            event.state = Some(state.clone());
+        }
+        self.last_state = Some(state);
+    }
+}
+
+#[derive(Hash, PartialEq, Eq, Clone, Copy)]
+struct EventKey {
+    parent: Option<usize>,
+    description_hash: u64,
+    /// Number of identical events sent before by the same parent.
+    occurrence: usize,
+}
+
+/// Assigns the IDs and the due times of the recorded events to the events of the replay.
+pub(crate) struct TraceReplayer {
+    recorded: HashMap<EventKey, (usize, Duration)>,
+    occurrences: HashMap<(Option<usize>, u64), usize>,
+    /// ID of the next event missing from the trace, above the IDs of the recorded events.
+    next_unrecorded_id: usize,
+    diverged: bool,
+}
+
+impl TraceReplayer {
+    pub(crate) fn new(trace: Vec<TraceEvent>) -> Self {
+        let mut occurrences = HashMap::new();
+        let mut recorded = HashMap::new();
+        let mut next_unrecorded_id = 0;
+        for event in trace {
+            let occurrence = occurrences.entry((event.parent, event.description_hash)).or_default();
+            let key = EventKey {
+                parent: event.parent,
+                description_hash: event.description_hash,
+                occurrence: *occurrence,
+            };
+            *occurrence += 1;
+            recorded.insert(key, (event.id, Duration::nanoseconds(event.due_ns as i64)));
+            next_unrecorded_id = next_unrecorded_id.max(event.id + 1);
+        }
+        Self { recorded, occurrences: HashMap::new(), next_unrecorded_id, diverged: false }
+    }
+
+    /// Returns the ID and the due time of an event sent by the handler of `parent`. The events
+    /// missing from the trace keep their due time, but they are handled after the recorded
+    /// events due at the same time.
+    pub(crate) fn assign(
+        &mut self,
+        parent: Option<usize>,
+        description: &str,
+        current_time: Duration,
+        due: Duration,
+    ) -> (usize, Duration) {
+        let description_hash = description_hash(description);
+        let occurrence = self.occurrences.entry((parent, description_hash)).or_default();
+        let key = EventKey { parent, description_hash, occurrence: *occurrence };
+        *occurrence += 1;
+        if let Some((id, recorded_due)) = self.recorded.remove(&key) {
+            return (id, recorded_due.max(current_time));
+        }
+        if !self.diverged {
+            self.diverged = true;
+            tracing::warn!(target: "test_loop", %description, ?parent, "event missing from the replayed trace, the run diverged");
+        }
+        let id = self.next_unrecorded_id;
+        self.next_unrecorded_id += 1;
+        (id, due)
+    }
+}
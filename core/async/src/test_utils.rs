@@ -0,0 +1,190 @@
+//! Utilities to unit test time-dependent logic with a [FakeClock], without setting up a
+//! TestLoop.
+//!
+//! Components such as the ShardsManager schedule their periodic work through a
+//! [DelayedActionRunner]. In production it is backed by the actix context and in TestLoop by
+//! the event loop, while [FakeDelayedActionRunner] lets a unit test own both the component and
+//! its delayed actions, and run them by moving the fake clock forward:
+//!
+//! ```ignore
+//! let clock = FakeClock::default();
+//! let mut runner = FakeDelayedActionRunner::new(clock.clone());
+//! let mut actor = MyActor::new(clock.clock());
+//! actor.start_timers(&mut runner);
+//! // Runs every action due in the next 5 seconds, in deadline order, with the clock set to the
+//! // deadline of each of them.
+//! runner.advance(&mut actor, Duration::seconds(5));
+//! ```
+//!
+//! Futures sleeping on the same clock are woken up as the clock moves forward, so logic mixing
+//! both kinds of timers observes a consistent time.
+
+use crate::futures::DelayedActionRunner;
+use crate::time::{Duration, FakeClock, Instant};
+
+type DelayedAction<T> = Box<dyn FnOnce(&mut T, &mut dyn DelayedActionRunner<T>) + Send + 'static>;
+
+struct PendingAction<T> {
+    deadline: Instant,
+    /// Breaks ties between actions with the same deadline, so that they run in the order in
+    /// which they were scheduled.
+    seq: u64,
+    name: String,
+    action: DelayedAction<T>,
+}
+
+/// [DelayedActionRunner] whose actions are run on demand against a [FakeClock].
+pub struct FakeDelayedActionRunner<T> {
+    clock: FakeClock,
+    next_seq: u64,
+    pending: Vec<PendingAction<T>>,
+}
+
+impl<T> FakeDelayedActionRunner<T> {
+    pub fn new(clock: FakeClock) -> Self {
+        Self { clock, next_seq: 0, pending: vec![] }
+    }
+
+    pub fn clock(&self) -> &FakeClock {
+        &self.clock
+    }
+
+    /// Names of the actions not run yet, in the order in which they are due.
+    pub fn pending_actions(&self) -> Vec<&str> {
+        let mut pending = self.pending.iter().collect::<Vec<_>>();
+        pending.sort_by_key(|action| (action.deadline, action.seq));
+        pending.into_iter().map(|action| action.name.as_str()).collect()
+    }
+
+    /// Deadline of the earliest action not run yet.
+    pub fn next_deadline(&self) -> Option<Instant> {
+        self.pending.iter().map(|action| action.deadline).min()
+    }
+
+    /// Runs the actions that are due at the current time, including the ones they schedule with
+    /// no delay. Returns the number of actions run.
+    pub fn run_due(&mut self, obj: &mut T) -> usize {
+        let now = self.clock.now();
+        let mut num_run = 0;
+        while self.run_earliest_until(obj, now) {
+            num_run += 1;
+        }
+        num_run
+    }
+
+    /// Moves the clock forward by `d`, running the actions that become due on the way. Before
+    /// running each action the clock is set to its deadline, so the action observes the time
+    /// it was scheduled for. Returns the number of actions run.
+    pub fn advance(&mut self, obj: &mut T, d: Duration) -> usize {
+        let target = self.clock.now() + d;
+        let mut num_run = 0;
+        while self.run_earliest_until(obj, target) {
+            num_run += 1;
+        }
+        self.clock.advance_until(target);
+        num_run
+    }
+
+    /// Moves the clock to the deadline of the earliest pending action and runs all actions due
+    /// at that time. Returns the number of actions run, zero if there was none pending.
+    pub fn advance_to_next_deadline(&mut self, obj: &mut T) -> usize {
+        let Some(deadline) = self.next_deadline() else {
+            return 0;
+        };
+        let d = deadline - self.clock.now();
+        self.advance(obj, d.max(Duration::ZERO))
+    }
+
+    /// Runs the earliest pending action if its deadline is not after `until`, moving the clock
+    /// to its deadline first. Returns whether an action was run.
+    fn run_earliest_until(&mut self, obj: &mut T, until: Instant) -> bool {
+        let Some((index, _)) = self
+            .pending
+            .iter()
+            .enumerate()
+            .filter(|(_, action)| action.deadline <= until)
+            .min_by_key(|(_, action)| (action.deadline, action.seq))
+        else {
+            return false;
+        };
+        let PendingAction { deadline, name, action, .. } = self.pending.swap_remove(index);
+        if deadline > self.clock.now() {
+            self.clock.advance_until(deadline);
+        }
+        tracing::debug!(target: "test", %name, "running delayed action");
+        action(obj, self);
+        true
+    }
+}
+
+impl<T> DelayedActionRunner<T> for FakeDelayedActionRunner<T> {
+    fn run_later_boxed(&mut self, name: &str, dur: Duration, f: DelayedAction<T>) {
+        let deadline = self.clock.now() + dur.max(Duration::ZERO);
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.pending.push(PendingAction { deadline, seq, name: name.to_string(), action: f });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FakeDelayedActionRunner;
+    use crate::futures::{DelayedActionRunner, DelayedActionRunnerExt};
+    use crate::time::{Clock, Duration, FakeClock, Instant};
+
+    struct Ticker {
+        clock: Clock,
+        ticks: Vec<Instant>,
+    }
+
+    impl Ticker {
+        fn tick_every(&mut self, period: Duration, runner: &mut dyn DelayedActionRunner<Self>) {
+            runner.run_later("tick", period, move |this, runner| {
+                this.ticks.push(this.clock.now());
+                this.tick_every(period, runner);
+            });
+        }
+    }
+
+    #[test]
+    fn periodic_action_runs_at_its_deadlines() {
+        let clock = FakeClock::default();
+        let start = clock.now();
+        let mut runner = FakeDelayedActionRunner::new(clock.clone());
+        let mut ticker = Ticker { clock: clock.clock(), ticks: vec![] };
+        ticker.tick_every(Duration::seconds(2), &mut runner);
+        assert_eq!(runner.pending_actions(), vec!["tick"]);
+
+        assert_eq!(runner.advance(&mut ticker, Duration::seconds(1)), 0);
+        assert_eq!(runner.advance(&mut ticker, Duration::seconds(6)), 3);
+        assert_eq!(
+            ticker.ticks,
+            vec![
+                start + Duration::seconds(2),
+                start + Duration::seconds(4),
+                start + Duration::seconds(6),
+            ]
+        );
+        assert_eq!(clock.now(), start + Duration::seconds(7));
+        assert_eq!(runner.next_deadline(), Some(start + Duration::seconds(8)));
+
+        assert_eq!(runner.advance_to_next_deadline(&mut ticker), 1);
+        assert_eq!(clock.now(), start + Duration::seconds(8));
+    }
+
+    #[test]
+    fn actions_with_same_deadline_run_in_scheduling_order() {
+        let clock = FakeClock::default();
+        let mut runner = FakeDelayedActionRunner::new(clock);
+        let mut order: Vec<u32> = vec![];
+        runner.run_later("second", Duration::seconds(1), |order: &mut Vec<u32>, _| order.push(2));
+        runner.run_later("first", Duration::ZERO, |order: &mut Vec<u32>, _| order.push(1));
+        runner.run_later("third", Duration::seconds(1), |order: &mut Vec<u32>, _| order.push(3));
+
+        assert_eq!(runner.pending_actions(), vec!["first", "second", "third"]);
+        assert_eq!(runner.run_due(&mut order), 1);
+        assert_eq!(runner.advance(&mut order, Duration::seconds(1)), 2);
+        assert_eq!(order, vec![1, 2, 3]);
+        assert!(runner.pending_actions().is_empty());
+    }
+}
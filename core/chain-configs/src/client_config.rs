@@ -1,6 +1,6 @@
 //! Chain Client Configuration
 use crate::ExternalStorageLocation::GCS;
-use crate::MutableConfigValue;
+use crate::{MutableConfigValue, TrackedShardsConfig};
 use bytesize::ByteSize;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId,
@@ -9,8 +9,7 @@ use near_primitives::version::Version;
 use near_time::Duration;
 use std::cmp::{max, min};
 use std::path::PathBuf;
-use std::sync::atomic::AtomicBool;
-use std::sync::Arc;
+use std::sync::{Arc, Condvar, Mutex};
 
 pub const TEST_STATE_SYNC_TIMEOUT: i64 = 5;
 
@@ -36,6 +35,10 @@ pub const DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL: u32 =
 /// before giving up and downloading it from external storage.
 pub const DEFAULT_EXTERNAL_STORAGE_FALLBACK_THRESHOLD: u64 = 3;
 
+/// The default number of consecutive failed downloads after which a state sync provider is
+/// considered unhealthy and skipped.
+pub const DEFAULT_STATE_SYNC_PROVIDER_MAX_CONSECUTIVE_FAILURES: u64 = 10;
+
 /// Configuration for garbage collection.
 #[derive(Clone, Debug, serde::Serialize, serde::Deserialize, PartialEq)]
 #[serde(default)]
@@ -54,6 +57,13 @@ pub struct GCConfig {
     /// How often gc should be run
     #[serde(with = "near_time::serde_duration_as_std")]
     pub gc_step_period: Duration,
+
+    /// If set, EpochInfo, EpochStart and EpochValidatorInfo of epochs older than
+    /// this many epochs are pruned. The genesis epoch, the epochs of blocks which
+    /// are still in the store and the epochs needed to derive epoch sync proofs
+    /// are always kept. Ignored on archival nodes.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epoch_data_num_epochs_to_keep: Option<u64>,
 }
 
 impl Default for GCConfig {
@@ -63,6 +73,7 @@ impl Default for GCConfig {
             gc_fork_clean_step: 100,
             gc_num_epochs_to_keep: DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
             gc_step_period: Duration::seconds(1),
+            epoch_data_num_epochs_to_keep: None,
         }
     }
 }
@@ -117,6 +128,11 @@ pub enum ExternalStorageLocation {
     GCS {
         bucket: String,
     },
+    /// HTTP servers mirroring the layout of a state dump, each serving the file at `location`
+    /// under `{url}/{location}`. Read-only, so it can't be used to dump state.
+    HttpMirrors {
+        urls: Vec<String>,
+    },
 }
 
 /// Configures how to dump state to external storage.
@@ -137,6 +153,12 @@ pub struct DumpConfig {
     /// Location of a json file with credentials allowing write access to the bucket.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub credentials_file: Option<PathBuf>,
+    /// Compress the state parts and headers before writing them. The headers carry the whole
+    /// chunk of the shard. The nodes syncing from the parts must run a version which detects
+    /// compressed parts and headers, as well as their peers, which serve the parts as they got
+    /// them.
+    #[serde(default)]
+    pub compress_parts: bool,
 }
 
 /// Configures how to fetch state parts during state sync.
@@ -146,6 +168,57 @@ pub enum SyncConfig {
     Peers,
     /// Expects parts to be available in external storage.
     ExternalStorage(ExternalStorageConfig),
+    /// Tries the given providers of state headers and parts in order.
+    Providers(StateSyncProvidersConfig),
+}
+
+fn default_provider_num_attempts() -> u64 {
+    DEFAULT_EXTERNAL_STORAGE_FALLBACK_THRESHOLD
+}
+
+fn default_provider_max_consecutive_failures() -> u64 {
+    DEFAULT_STATE_SYNC_PROVIDER_MAX_CONSECUTIVE_FAILURES
+}
+
+fn default_provider_unhealthy_cooldown() -> Duration {
+    Duration::minutes(1)
+}
+
+/// Source of state headers and parts.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub enum StateSyncProvider {
+    Peers,
+    External(ExternalStorageLocation),
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct StateSyncProviderConfig {
+    pub provider: StateSyncProvider,
+    /// The number of attempts made to obtain a header or part from this provider before
+    /// falling back to the next one. The last provider is used for all the remaining attempts.
+    #[serde(default = "default_provider_num_attempts")]
+    pub num_attempts: u64,
+}
+
+/// Configures an ordered list of providers of state headers and parts, so that the node can
+/// fall back to redundant mirrors when some of them are down.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug)]
+pub struct StateSyncProvidersConfig {
+    /// Providers in order of preference. Mirror lists are expanded to one provider per mirror.
+    pub providers: Vec<StateSyncProviderConfig>,
+    /// Throttles the fetch requests to this many concurrent requests.
+    #[serde(default = "default_num_concurrent_requests")]
+    pub num_concurrent_requests: u32,
+    /// Number of concurrent requests during catchup.
+    #[serde(default = "default_num_concurrent_requests_during_catchup")]
+    pub num_concurrent_requests_during_catchup: u32,
+    /// A provider failing this many downloads in a row is skipped in favour of the next
+    /// healthy one for `unhealthy_cooldown`.
+    #[serde(default = "default_provider_max_consecutive_failures")]
+    pub max_consecutive_failures: u64,
+    #[serde(default = "default_provider_unhealthy_cooldown")]
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub unhealthy_cooldown: Duration,
 }
 
 impl Default for SyncConfig {
@@ -226,24 +299,238 @@ impl Default for EpochSyncConfig {
     }
 }
 
+/// Optional secondary indexes maintained by the node on top of the chain data, which allow
+/// using the node as a lightweight explorer. The indexes are updated when blocks are
+/// postprocessed, stored in dedicated columns and garbage collected together with the
+/// blocks. Only the tracked shards are indexed.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SecondaryIndexesConfig {
+    /// Index the transactions by their signer in DBCol::TransactionsByAccount.
+    pub transactions_by_account: bool,
+    /// Index the blocks by their timestamp in DBCol::BlocksByTimestamp. Enabled by default, as
+    /// it serves the lookups of blocks by timestamp; the blocks processed before it was enabled
+    /// are indexed in the background.
+    pub blocks_by_timestamp: bool,
+}
+
+impl Default for SecondaryIndexesConfig {
+    fn default() -> Self {
+        Self { transactions_by_account: false, blocks_by_timestamp: true }
+    }
+}
+
+impl SecondaryIndexesConfig {
+    pub fn is_enabled(&self) -> bool {
+        self.transactions_by_account || self.blocks_by_timestamp
+    }
+}
+
+/// Configuration of the adaptive time limit of adding transactions to a produced chunk.
+///
+/// The chunk producer first applies the previous chunk of the shard, then adds transactions to
+/// the new chunk. When the application is slow, the fixed time limit of adding transactions
+/// makes the chunk miss its deadline. With this config, the time limit is instead the `budget`
+/// minus a percentile of the recent apply durations of the shard, bounded by
+/// `min_time_limit` and by `produce_chunk_add_transactions_time_limit`.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(default)]
+pub struct AdaptiveChunkTimeLimitConfig {
+    /// Time shared by the application of the previous chunk and the addition of transactions.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub budget: Duration,
+    /// Percentile, between 0 and 1, of the recent apply durations subtracted from the budget.
+    pub apply_duration_percentile: f64,
+    /// Lower bound of the time limit, so that slow shards still include transactions.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub min_time_limit: Duration,
+}
+
+impl Default for AdaptiveChunkTimeLimitConfig {
+    fn default() -> Self {
+        Self {
+            budget: Duration::milliseconds(600),
+            apply_duration_percentile: 0.9,
+            min_time_limit: Duration::milliseconds(50),
+        }
+    }
+}
+
+impl AdaptiveChunkTimeLimitConfig {
+    /// The time limit of adding transactions given the percentile of the recent apply
+    /// durations of the shard, at most `max_time_limit`.
+    pub fn time_limit(
+        &self,
+        apply_duration_percentile: Duration,
+        max_time_limit: Option<Duration>,
+    ) -> Duration {
+        let time_limit = (self.budget - apply_duration_percentile).max(self.min_time_limit);
+        match max_time_limit {
+            Some(max_time_limit) => time_limit.min(max_time_limit),
+            None => time_limit,
+        }
+    }
+}
+
+/// Limits of the concurrent validations of chunk state witnesses. The validations over the
+/// limits wait in per-shard queues, which take turns when a validation finishes, so that the big
+/// witnesses of one shard can't take the whole validation pool and delay the endorsements of
+/// the other shards.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct WitnessValidationLimitsConfig {
+    /// Maximum number of witnesses of a shard validated at the same time.
+    pub max_concurrent_per_shard: usize,
+    /// Maximum number of witnesses validated at the same time, over all the shards.
+    pub max_concurrent: usize,
+}
+
+impl Default for WitnessValidationLimitsConfig {
+    fn default() -> Self {
+        Self { max_concurrent_per_shard: 2, max_concurrent: 8 }
+    }
+}
+
+/// Enables the `simulate_function_call` query, which runs view calls against state with
+/// user-provided overrides, and limits the contract code which can be provided.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct SimulateFunctionCallConfig {
+    /// Maximum size in bytes of the contract code in a state override.
+    pub max_code_size: usize,
+    /// Maximum time spent compiling the contract code in a state override.
+    #[serde(with = "near_time::serde_duration_as_std")]
+    pub max_compile_time: Duration,
+}
+
+impl Default for SimulateFunctionCallConfig {
+    fn default() -> Self {
+        Self { max_code_size: 4 * 1024 * 1024, max_compile_time: Duration::seconds(2) }
+    }
+}
+
+/// Sizing and CPU pinning of a thread pool.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThreadPoolConfig {
+    /// Number of threads in the pool. The default size of the pool is used if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_threads: Option<usize>,
+    /// CPUs the threads of the pool are pinned to. The threads are not pinned if not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cpu_set: Option<Vec<usize>>,
+}
+
+/// Sizing and CPU pinning of the thread pools doing consensus-critical work, which allows
+/// operators of big machines to isolate that work from the rest of the node. The threads pinned
+/// so far are listed by the `/debug/api/thread_pools` endpoint.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+#[serde(default)]
+pub struct ThreadPoolsConfig {
+    /// The pool applying chunks. Uses the global rayon pool unless configured.
+    pub chunk_application: ThreadPoolConfig,
+    /// The pool validating chunk state witnesses. Uses the global rayon pool unless configured.
+    pub witness_validation: ThreadPoolConfig,
+    /// The threads running the peer manager. They are single-threaded runtimes, so only
+    /// `cpu_set` is supported.
+    pub network: ThreadPoolConfig,
+    /// The view client threads serving RPC queries. `num_threads` takes precedence over
+    /// `view_client_threads`.
+    pub rpc: ThreadPoolConfig,
+}
+
+/// State of the background resharding work, as controlled through [ReshardingHandle].
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReshardingHandleState {
+    Running,
+    /// Paused by the operator. The resharding tasks block between batches until resumed.
+    Paused,
+    /// Interrupted, either because the node is shutting down or because the operator aborted
+    /// resharding. This is final until the node restarts, which starts the aborted resharding
+    /// again.
+    Stopped,
+}
+
+#[derive(Debug)]
+struct ReshardingHandleInner {
+    state: ReshardingHandleState,
+    /// Number of tasks blocked in [ReshardingHandle::wait_while_paused].
+    num_paused_tasks: usize,
+}
+
 // A handle that allows the main process to interrupt resharding if needed.
 // This typically happens when the main process is interrupted.
+// Operators can also pause resharding, e.g. during peak load, and resume it later.
 #[derive(Clone, Debug)]
 pub struct ReshardingHandle {
-    keep_going: Arc<AtomicBool>,
+    inner: Arc<(Mutex<ReshardingHandleInner>, Condvar)>,
 }
 
 impl ReshardingHandle {
     pub fn new() -> Self {
-        Self { keep_going: Arc::new(AtomicBool::new(true)) }
+        let inner =
+            ReshardingHandleInner { state: ReshardingHandleState::Running, num_paused_tasks: 0 };
+        Self { inner: Arc::new((Mutex::new(inner), Condvar::new())) }
+    }
+
+    pub fn state(&self) -> ReshardingHandleState {
+        self.inner.0.lock().unwrap().state
     }
 
+    /// Returns whether resharding should keep going, i.e. it was not stopped. A paused
+    /// resharding keeps going once resumed.
     pub fn get(&self) -> bool {
-        self.keep_going.load(std::sync::atomic::Ordering::Relaxed)
+        self.state() != ReshardingHandleState::Stopped
     }
 
     pub fn stop(&self) -> () {
-        self.keep_going.store(false, std::sync::atomic::Ordering::Relaxed);
+        self.set_state(ReshardingHandleState::Stopped);
+    }
+
+    /// Pauses resharding at the next batch boundary. Does nothing if it was stopped.
+    pub fn pause(&self) -> ReshardingHandleState {
+        self.set_state(ReshardingHandleState::Paused)
+    }
+
+    /// Resumes a paused resharding. Does nothing if it was stopped.
+    pub fn resume(&self) -> ReshardingHandleState {
+        self.set_state(ReshardingHandleState::Running)
+    }
+
+    /// Blocks the calling thread while resharding is paused. Returns whether resharding should
+    /// keep going, like [Self::get].
+    pub fn wait_while_paused(&self) -> bool {
+        let (inner, changed) = &*self.inner;
+        let mut inner = inner.lock().unwrap();
+        if inner.state == ReshardingHandleState::Paused {
+            inner.num_paused_tasks += 1;
+            changed.notify_all();
+            inner = changed
+                .wait_while(inner, |inner| inner.state == ReshardingHandleState::Paused)
+                .unwrap();
+            inner.num_paused_tasks -= 1;
+        }
+        inner.state != ReshardingHandleState::Stopped
+    }
+
+    /// Blocks until at least `num_tasks` tasks are blocked by a pause, i.e. until the pause took
+    /// effect on them.
+    pub fn wait_for_paused_tasks(&self, num_tasks: usize) {
+        let (inner, changed) = &*self.inner;
+        let _inner = changed
+            .wait_while(inner.lock().unwrap(), |inner| inner.num_paused_tasks < num_tasks)
+            .unwrap();
+    }
+
+    /// Sets the new state unless resharding was stopped, and returns the resulting state.
+    fn set_state(&self, new_state: ReshardingHandleState) -> ReshardingHandleState {
+        let (inner, changed) = &*self.inner;
+        let mut inner = inner.lock().unwrap();
+        if inner.state != ReshardingHandleState::Stopped {
+            inner.state = new_state;
+        }
+        changed.notify_all();
+        inner.state
     }
 }
 
@@ -285,6 +572,10 @@ pub struct ReshardingConfig {
     /// This value can be decreased if resharding is consuming too many
     /// resources and interfering with regular node operation.
     pub catch_up_blocks: BlockHeightDelta,
+
+    /// The size of the trie nodes recorded for the state transition data of a child shard
+    /// above which they are moved from memory to the database while the child is created.
+    pub recorder_memory_limit: ByteSize,
 }
 
 impl Default for ReshardingConfig {
@@ -301,6 +592,7 @@ impl Default for ReshardingConfig {
             // state sync.
             max_poll_time: Duration::seconds(2 * 60 * 60), // 2 hours
             catch_up_blocks: 20,
+            recorder_memory_limit: ByteSize::mib(256),
         }
     }
 }
@@ -506,6 +798,13 @@ pub struct ClientConfig {
     /// Used to simulate the behavior of chunk only producers without staking tokens.
     /// This field is only used if `tracked_shards` is empty.
     pub tracked_shard_schedule: Vec<Vec<ShardId>>,
+    /// Expression selecting the tracked shards. When set, it's used instead of the
+    /// `tracked_*` fields above.
+    pub tracked_shards_config: Option<TrackedShardsConfig>,
+    /// Run the node as a chunk validator only: it tracks no shard, not even the ones its
+    /// validator duties would require, doesn't produce chunks, and only validates the state
+    /// witnesses it receives and sends the endorsements.
+    pub chunk_validator_only: bool,
     /// Not clear old data, set `true` for archive nodes.
     pub archive: bool,
     /// save_trie_changes should be set to true iff
@@ -523,6 +822,8 @@ pub struct ClientConfig {
     /// genesis file.  The value only affects the RPCs without influencing the
     /// protocol thus changing it per-node doesn’t affect the blockchain.
     pub max_gas_burnt_view: Option<Gas>,
+    /// Limits of the `simulate_function_call` query. The query is disabled if not set.
+    pub simulate_function_call: Option<SimulateFunctionCallConfig>,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
     /// Number of threads to execute background migration work in client.
@@ -549,6 +850,12 @@ pub struct ClientConfig {
     /// some limit is reached. This time limit ensures that adding transactions won't take
     /// longer than the specified duration, which helps to produce the chunk quickly.
     pub produce_chunk_add_transactions_time_limit: MutableConfigValue<Option<Duration>>,
+    /// If set, the time limit of adding transactions to a chunk adapts to the recent apply
+    /// durations of the shard, see `AdaptiveChunkTimeLimitConfig`.
+    pub produce_chunk_adaptive_time_limit: Option<AdaptiveChunkTimeLimitConfig>,
+    /// Skip producing the chunks of quiet shards, without transactions or receipts, when the
+    /// protocol allows marking them as suppressed instead of missed.
+    pub suppress_empty_chunks: bool,
     /// Optional config for the Chunk Distribution Network feature.
     /// If set to `None` then this node does not participate in the Chunk Distribution Network.
     /// Nodes not participating will still function fine, but possibly with higher
@@ -569,6 +876,34 @@ pub struct ClientConfig {
     /// which can cause extra load on the database. This option is not recommended for production use,
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
+    /// Before distributing a produced state witness, run the same validation on it that chunk
+    /// validators would run, and don't distribute the witness if the validation fails.
+    /// Turns silent rejections by chunk validators into errors on the chunk producer side,
+    /// at the cost of validating every produced chunk twice.
+    pub validate_produced_witnesses: bool,
+    /// Maintain an index of the receipts executed and created by every account in
+    /// DBCol::AccountReceipts, used by the `EXPERIMENTAL_account_receipts` RPC.
+    /// Only the tracked shards are indexed and the index is garbage collected together
+    /// with the blocks.
+    pub save_account_receipts: bool,
+    /// Secondary indexes to maintain, served by the `EXPERIMENTAL_secondary_index` RPC.
+    pub secondary_indexes: SecondaryIndexesConfig,
+    /// Record the height of the last block in which every access key signed a transaction in
+    /// DBCol::AccessKeyUsage, which is returned by `view_access_key` queries. Only the
+    /// tracked shards are covered.
+    pub save_access_key_usage: bool,
+    /// Shards whose state transition data isn't generated nor saved when the node doesn't
+    /// produce their chunks in the current or the next epoch, for chunk producers tracking
+    /// other shards too. Other nodes never save the data.
+    pub skip_state_transition_data_for_shards: Vec<ShardId>,
+    /// Congestion level, between 0 and 1, of the shard of the signer of a transaction from
+    /// which the transactions submitted to this node are rejected, instead of being routed to
+    /// a shard that is unlikely to include them before they time out. Disabled if not set.
+    pub tx_admission_congestion_threshold: Option<f64>,
+    /// Sizing and CPU pinning of the thread pools.
+    pub thread_pools: ThreadPoolsConfig,
+    /// Limits of the concurrent validations of chunk state witnesses, by shard and in total.
+    pub witness_validation_limits: WitnessValidationLimitsConfig,
 }
 
 impl ClientConfig {
@@ -631,6 +966,8 @@ impl ClientConfig {
             tracked_shadow_validator: None,
             tracked_shards: vec![],
             tracked_shard_schedule: vec![],
+            tracked_shards_config: None,
+            chunk_validator_only: false,
             archive,
             save_trie_changes,
             log_summary_style: LogSummaryStyle::Colored,
@@ -638,6 +975,7 @@ impl ClientConfig {
             view_client_throttle_period: Duration::seconds(1),
             trie_viewer_state_size_limit: None,
             max_gas_burnt_view: None,
+            simulate_function_call: None,
             enable_statistics_export: true,
             client_background_migration_threads: 1,
             state_sync_enabled,
@@ -658,6 +996,16 @@ impl ClientConfig {
             orphan_state_witness_pool_size: default_orphan_state_witness_pool_size(),
             orphan_state_witness_max_size: default_orphan_state_witness_max_size(),
             save_latest_witnesses: false,
+            validate_produced_witnesses: false,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
+            skip_state_transition_data_for_shards: vec![],
+            tx_admission_congestion_threshold: None,
+            produce_chunk_adaptive_time_limit: None,
+            suppress_empty_chunks: false,
+            thread_pools: ThreadPoolsConfig::default(),
+            witness_validation_limits: WitnessValidationLimitsConfig::default(),
         }
     }
 }
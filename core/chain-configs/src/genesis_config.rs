@@ -31,7 +31,7 @@ use serde::{Deserialize, Deserializer, Serialize};
 use serde_json::Serializer;
 use sha2::digest::Digest;
 use smart_default::SmartDefault;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 use std::fmt;
 use std::fs::File;
 use std::io::{BufReader, Read};
@@ -213,6 +213,11 @@ pub struct GenesisConfig {
     #[serde(default = "default_minimum_stake_ratio")]
     #[default(Rational32::new(160, 1_000_000))]
     pub minimum_stake_ratio: Rational32,
+    /// Overrides `minimum_stake_ratio` for the selection of chunk validators, allowing a lower
+    /// entry stake for the chunk-validator-only seats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[default(None)]
+    pub chunk_validator_minimum_stake_ratio: Option<Rational32>,
     /// If true, shuffle the chunk producers across shards. In other words, if
     /// the shard assignments were `[S_0, S_1, S_2, S_3]` where `S_i` represents
     /// the set of chunk producers for shard `i`, if this flag were true, the
@@ -220,6 +225,11 @@ pub struct GenesisConfig {
     #[serde(default = "default_shuffle_shard_assignment_for_chunk_producers")]
     #[default(false)]
     pub shuffle_shard_assignment_for_chunk_producers: bool,
+    /// Accounts excluded from the validator selection regardless of their stake. Only allowed
+    /// on chains other than mainnet and testnet.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    #[default(BTreeSet::new())]
+    pub validator_blocklist: BTreeSet<AccountId>,
     #[serde(default = "default_use_production_config")]
     #[default(false)]
     /// This is only for test purposes. We hard code some configs for mainnet and testnet
@@ -273,10 +283,13 @@ impl From<&GenesisConfig> for EpochConfig {
             num_chunk_validator_seats: config.num_chunk_validator_seats,
             num_chunk_only_producer_seats: config.num_chunk_only_producer_seats,
             minimum_validators_per_shard: config.minimum_validators_per_shard,
+            minimum_validators_per_shard_overrides: Default::default(),
             minimum_stake_ratio: config.minimum_stake_ratio,
+            chunk_validator_minimum_stake_ratio: config.chunk_validator_minimum_stake_ratio,
             chunk_producer_assignment_changes_limit: config.chunk_producer_assignment_changes_limit,
             shuffle_shard_assignment_for_chunk_producers: config
                 .shuffle_shard_assignment_for_chunk_producers,
+            validator_blocklist: config.validator_blocklist.clone(),
             validator_max_kickout_stake_perc: config.max_kickout_stake_perc,
         }
     }
@@ -871,11 +884,18 @@ pub struct ProtocolConfigView {
     /// The lowest ratio s/s_total any block producer can have.
     /// See <https://github.com/near/NEPs/pull/167> for details
     pub minimum_stake_ratio: Rational32,
+    /// The lowest ratio s/s_total any chunk validator can have, if different from
+    /// `minimum_stake_ratio`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_validator_minimum_stake_ratio: Option<Rational32>,
     /// If true, shuffle the chunk producers across shards. In other words, if
     /// the shard assignments were `[S_0, S_1, S_2, S_3]` where `S_i` represents
     /// the set of chunk producers for shard `i`, if this flag were true, the
     /// shard assignments might become, for example, `[S_2, S_0, S_3, S_1]`.
     pub shuffle_shard_assignment_for_chunk_producers: bool,
+    /// Accounts excluded from the validator selection regardless of their stake.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub validator_blocklist: BTreeSet<AccountId>,
     /// The minimum number of validators each shard must have
     pub minimum_validators_per_shard: NumSeats,
     /// Number of validator seats for chunk only producers.
@@ -926,8 +946,10 @@ impl From<ProtocolConfig> for ProtocolConfigView {
             minimum_stake_divisor: genesis_config.minimum_stake_divisor,
             max_kickout_stake_perc: genesis_config.max_kickout_stake_perc,
             minimum_stake_ratio: genesis_config.minimum_stake_ratio,
+            chunk_validator_minimum_stake_ratio: genesis_config.chunk_validator_minimum_stake_ratio,
             shuffle_shard_assignment_for_chunk_producers: genesis_config
                 .shuffle_shard_assignment_for_chunk_producers,
+            validator_blocklist: genesis_config.validator_blocklist,
             minimum_validators_per_shard: genesis_config.minimum_validators_per_shard,
             num_chunk_only_producer_seats: genesis_config.num_chunk_only_producer_seats,
             shard_layout: genesis_config.shard_layout,
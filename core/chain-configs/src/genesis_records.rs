@@ -0,0 +1,303 @@
+//! Canonical form of a set of genesis records.
+//!
+//! The tools producing genesis records (`amend-genesis`, `mirror prepare`, the
+//! `TestGenesisBuilder`) emit records in whatever order they happen to process them, and may
+//! repeat some of them. The canonical form is what they agree on: the records of every account
+//! are grouped, sorted by type and key, without duplicates, with the delayed receipts at the end
+//! in their original order, as it is the order of the delayed receipts queue.
+
+use crate::genesis_config::{stream_records_from_file, GenesisRecords};
+use anyhow::Context;
+use near_crypto::PublicKey;
+use near_primitives::hash::CryptoHash;
+use near_primitives::state_record::StateRecord;
+use near_primitives::types::AccountId;
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap};
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// Records which can't be both kept in the canonical form.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum CanonicalizeRecordsError {
+    #[error("conflicting account records of {0}")]
+    ConflictingAccount(AccountId),
+    #[error("conflicting contract records of account {0}")]
+    ConflictingContract(AccountId),
+    #[error("access key {public_key} of account {account_id} is given with different permissions")]
+    ConflictingAccessKey { account_id: AccountId, public_key: PublicKey },
+    #[error("conflicting data records of account {0}")]
+    ConflictingData(AccountId),
+    #[error("conflicting received data {data_id} records of account {account_id}")]
+    ConflictingReceivedData { account_id: AccountId, data_id: CryptoHash },
+    #[error("conflicting records of receipt {0}")]
+    ConflictingReceipt(CryptoHash),
+}
+
+/// What had to be changed to bring the records into the canonical form, besides ordering.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct CanonicalizeRecordsStats {
+    /// Number of records removed because they were exact copies of another record.
+    pub duplicates_removed: usize,
+    /// Number of access key records merged into another record of the same key, which only
+    /// differed in their nonce. The highest nonce is kept.
+    pub access_keys_merged: usize,
+}
+
+/// Order of the records of an account, the account itself first as the other records refer to
+/// it.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum RecordKind {
+    Account,
+    Contract,
+    AccessKey,
+    Data,
+    ReceivedData,
+    PostponedReceipt,
+}
+
+/// Identifies a record among the records of the same kind of an account.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum RecordSubKey {
+    None,
+    PublicKey(PublicKey),
+    DataKey(Vec<u8>),
+    Hash(CryptoHash),
+}
+
+/// Identifies a record in the canonical form, which is sorted by these keys.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct RecordKey {
+    account_id: AccountId,
+    kind: RecordKind,
+    sub_key: RecordSubKey,
+}
+
+impl RecordKey {
+    /// Returns `None` for the delayed receipts, which are kept in their original order.
+    fn new(record: &StateRecord) -> Option<Self> {
+        let (account_id, kind, sub_key) = match record {
+            StateRecord::Account { account_id, .. } => {
+                (account_id, RecordKind::Account, RecordSubKey::None)
+            }
+            StateRecord::Contract { account_id, .. } => {
+                (account_id, RecordKind::Contract, RecordSubKey::None)
+            }
+            StateRecord::AccessKey { account_id, public_key, .. } => {
+                (account_id, RecordKind::AccessKey, RecordSubKey::PublicKey(public_key.clone()))
+            }
+            StateRecord::Data { account_id, data_key, .. } => {
+                (account_id, RecordKind::Data, RecordSubKey::DataKey(data_key.to_vec()))
+            }
+            StateRecord::ReceivedData { account_id, data_id, .. } => {
+                (account_id, RecordKind::ReceivedData, RecordSubKey::Hash(*data_id))
+            }
+            StateRecord::PostponedReceipt(receipt) => (
+                receipt.receiver_id(),
+                RecordKind::PostponedReceipt,
+                RecordSubKey::Hash(*receipt.receipt_id()),
+            ),
+            StateRecord::DelayedReceipt(_) => return None,
+        };
+        Some(Self { account_id: account_id.clone(), kind, sub_key })
+    }
+}
+
+/// The error for a record conflicting with another record of the same key.
+fn conflict_error(record: StateRecord) -> CanonicalizeRecordsError {
+    match record {
+        StateRecord::Account { account_id, .. } => {
+            CanonicalizeRecordsError::ConflictingAccount(account_id)
+        }
+        StateRecord::Contract { account_id, .. } => {
+            CanonicalizeRecordsError::ConflictingContract(account_id)
+        }
+        StateRecord::AccessKey { account_id, public_key, .. } => {
+            CanonicalizeRecordsError::ConflictingAccessKey { account_id, public_key }
+        }
+        StateRecord::Data { account_id, .. } => {
+            CanonicalizeRecordsError::ConflictingData(account_id)
+        }
+        StateRecord::ReceivedData { account_id, data_id, .. } => {
+            CanonicalizeRecordsError::ConflictingReceivedData { account_id, data_id }
+        }
+        StateRecord::PostponedReceipt(receipt) | StateRecord::DelayedReceipt(receipt) => {
+            CanonicalizeRecordsError::ConflictingReceipt(*receipt.receipt_id())
+        }
+    }
+}
+
+/// Brings the records into the canonical form described in the module documentation.
+///
+/// Exact duplicates are removed and access keys given several times with the same permission
+/// are merged, keeping the highest nonce. Any other pair of records with the same key, such as
+/// two different accounts with the same id, is an error.
+pub fn canonicalize_records(
+    records: Vec<StateRecord>,
+) -> Result<(Vec<StateRecord>, CanonicalizeRecordsStats), CanonicalizeRecordsError> {
+    let mut stats = CanonicalizeRecordsStats::default();
+    let mut keyed_records = BTreeMap::new();
+    let mut delayed_receipts = vec![];
+    let mut delayed_receipt_indices = HashMap::new();
+    for record in records {
+        let Some(key) = RecordKey::new(&record) else {
+            let StateRecord::DelayedReceipt(receipt) = &record else { unreachable!() };
+            match delayed_receipt_indices.entry(*receipt.receipt_id()) {
+                hash_map::Entry::Occupied(entry) => {
+                    if delayed_receipts[*entry.get()] != record {
+                        return Err(conflict_error(record));
+                    }
+                    stats.duplicates_removed += 1;
+                }
+                hash_map::Entry::Vacant(entry) => {
+                    entry.insert(delayed_receipts.len());
+                    delayed_receipts.push(record);
+                }
+            }
+            continue;
+        };
+        let mut entry = match keyed_records.entry(key) {
+            btree_map::Entry::Vacant(entry) => {
+                entry.insert(record);
+                continue;
+            }
+            btree_map::Entry::Occupied(entry) => entry,
+        };
+        if entry.get() == &record {
+            stats.duplicates_removed += 1;
+            continue;
+        }
+        match (entry.get_mut(), record) {
+            (
+                StateRecord::AccessKey { access_key: existing, .. },
+                StateRecord::AccessKey { account_id, public_key, access_key },
+            ) => {
+                if existing.permission != access_key.permission {
+                    return Err(CanonicalizeRecordsError::ConflictingAccessKey {
+                        account_id,
+                        public_key,
+                    });
+                }
+                existing.nonce = existing.nonce.max(access_key.nonce);
+                stats.access_keys_merged += 1;
+            }
+            (_, record) => return Err(conflict_error(record)),
+        }
+    }
+    let mut records: Vec<_> = keyed_records.into_values().collect();
+    records.extend(delayed_receipts);
+    Ok((records, stats))
+}
+
+/// Canonicalizes the records of `records_file_in` into `records_file_out`, which may be the
+/// same file. All the records are loaded in memory.
+pub fn canonicalize_records_file(
+    records_file_in: &Path,
+    records_file_out: &Path,
+) -> anyhow::Result<CanonicalizeRecordsStats> {
+    let reader = BufReader::new(File::open(records_file_in).with_context(|| {
+        format!("Failed opening input records file {}", records_file_in.display())
+    })?);
+    let mut records = vec![];
+    stream_records_from_file(reader, |record| records.push(record)).with_context(|| {
+        format!("Failed deserializing records from {}", records_file_in.display())
+    })?;
+    let (records, stats) = canonicalize_records(records)?;
+    GenesisRecords(records).to_file(records_file_out);
+    Ok(stats)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use near_crypto::KeyType;
+    use near_primitives::account::{
+        AccessKey, AccessKeyPermission, Account, FunctionCallPermission,
+    };
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    fn account(account_id: &str, amount: u128) -> StateRecord {
+        StateRecord::Account {
+            account_id: account_id.parse().unwrap(),
+            account: Account::new(amount, 0, 0, CryptoHash::default(), 0, PROTOCOL_VERSION),
+        }
+    }
+
+    fn access_key(account_id: &str, seed: &str, nonce: u64) -> StateRecord {
+        StateRecord::AccessKey {
+            account_id: account_id.parse().unwrap(),
+            public_key: PublicKey::from_seed(KeyType::ED25519, seed),
+            access_key: AccessKey { nonce, permission: AccessKeyPermission::FullAccess },
+        }
+    }
+
+    fn contract(account_id: &str, code: &[u8]) -> StateRecord {
+        StateRecord::Contract { account_id: account_id.parse().unwrap(), code: code.to_vec() }
+    }
+
+    #[test]
+    fn test_canonicalize_records() {
+        let records = vec![
+            access_key("bob.near", "bob", 1),
+            contract("alice.near", &[1]),
+            account("bob.near", 10),
+            access_key("alice.near", "alice", 5),
+            account("alice.near", 20),
+            access_key("bob.near", "bob", 3),
+            contract("alice.near", &[1]),
+        ];
+        let (records, stats) = canonicalize_records(records).unwrap();
+        assert_eq!(
+            records,
+            vec![
+                account("alice.near", 20),
+                contract("alice.near", &[1]),
+                access_key("alice.near", "alice", 5),
+                account("bob.near", 10),
+                access_key("bob.near", "bob", 3),
+            ]
+        );
+        assert_eq!(
+            stats,
+            CanonicalizeRecordsStats { duplicates_removed: 1, access_keys_merged: 1 }
+        );
+
+        // The canonical form is a fixed point.
+        let (canonical_records, stats) = canonicalize_records(records.clone()).unwrap();
+        assert_eq!(canonical_records, records);
+        assert_eq!(stats, CanonicalizeRecordsStats::default());
+    }
+
+    #[test]
+    fn test_canonicalize_conflicting_records() {
+        let records = vec![account("alice.near", 20), account("alice.near", 30)];
+        assert_eq!(
+            canonicalize_records(records),
+            Err(CanonicalizeRecordsError::ConflictingAccount("alice.near".parse().unwrap()))
+        );
+
+        let records = vec![contract("alice.near", &[1]), contract("alice.near", &[2])];
+        assert_eq!(
+            canonicalize_records(records),
+            Err(CanonicalizeRecordsError::ConflictingContract("alice.near".parse().unwrap()))
+        );
+
+        let mut function_call_key = access_key("alice.near", "alice", 1);
+        let StateRecord::AccessKey { access_key: key, .. } = &mut function_call_key else {
+            unreachable!()
+        };
+        key.permission = AccessKeyPermission::FunctionCall(FunctionCallPermission {
+            allowance: None,
+            receiver_id: "alice.near".to_string(),
+            method_names: vec![],
+        });
+        let records = vec![access_key("alice.near", "alice", 1), function_call_key];
+        assert_eq!(
+            canonicalize_records(records),
+            Err(CanonicalizeRecordsError::ConflictingAccessKey {
+                account_id: "alice.near".parse().unwrap(),
+                public_key: PublicKey::from_seed(KeyType::ED25519, "alice"),
+            })
+        );
+    }
+}
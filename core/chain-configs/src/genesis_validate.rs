@@ -1,6 +1,7 @@
 use crate::genesis_config::{Genesis, GenesisConfig, GenesisContents};
 use near_config_utils::{ValidationError, ValidationErrors};
 use near_crypto::key_conversion::is_valid_staking_key;
+use near_primitives::chains::{MAINNET, TESTNET};
 use near_primitives::state_record::StateRecord;
 use near_primitives::types::AccountId;
 use num_rational::Rational32;
@@ -180,6 +181,14 @@ impl<'a> GenesisValidator<'a> {
             let error_message = format!("Epoch Length must be greater than 0");
             self.validation_errors.push_genesis_semantics_error(error_message)
         }
+
+        let chain_id = self.genesis_config.chain_id.as_str();
+        if (chain_id == MAINNET || chain_id == TESTNET)
+            && !self.genesis_config.validator_blocklist.is_empty()
+        {
+            let error_message = format!("validator_blocklist is not allowed on {}", chain_id);
+            self.validation_errors.push_genesis_semantics_error(error_message)
+        }
     }
 
     fn result_with_full_error(&self) -> Result<(), ValidationError> {
@@ -334,4 +343,24 @@ mod test {
         let genesis = &Genesis::new(config, records).unwrap();
         validate_genesis(genesis).unwrap();
     }
+
+    #[test]
+    #[should_panic(expected = "validator_blocklist is not allowed on mainnet")]
+    fn test_validator_blocklist_on_mainnet() {
+        let mut config = GenesisConfig::default();
+        config.chain_id = MAINNET.to_string();
+        config.validator_blocklist = ["test".parse().unwrap()].into_iter().collect();
+        config.validators = vec![AccountInfo {
+            account_id: "test".parse().unwrap(),
+            public_key: VALID_ED25519_RISTRETTO_KEY.parse().unwrap(),
+            amount: 10,
+        }];
+        config.total_supply = 110;
+        let records = GenesisRecords(vec![StateRecord::Account {
+            account_id: "test".parse().unwrap(),
+            account: create_account(),
+        }]);
+        let genesis = &Genesis::new(config, records).unwrap();
+        validate_genesis(genesis).unwrap();
+    }
 }
@@ -1,12 +1,15 @@
 mod client_config;
 mod genesis_config;
+mod genesis_records;
 pub mod genesis_validate;
 #[cfg(feature = "metrics")]
 mod metrics;
+mod shard_assignment;
 #[cfg(feature = "test_genesis")]
 pub mod test_genesis;
 #[cfg(feature = "test_utils")]
 pub mod test_utils;
+mod tracked_shards;
 mod updateable_config;
 
 pub use client_config::{
@@ -20,20 +23,30 @@ pub use client_config::{
     default_state_sync_retry_backoff, default_sync_check_period, default_sync_height_threshold,
     default_sync_max_block_requests, default_sync_step_period, default_transaction_pool_size_limit,
     default_trie_viewer_state_size_limit, default_tx_routing_height_horizon,
-    default_view_client_threads, default_view_client_throttle_period,
+    default_view_client_threads, default_view_client_throttle_period, AdaptiveChunkTimeLimitConfig,
     ChunkDistributionNetworkConfig, ChunkDistributionUris, ClientConfig, DumpConfig,
     EpochSyncConfig, ExternalStorageConfig, ExternalStorageLocation, GCConfig, LogSummaryStyle,
-    ReshardingConfig, ReshardingHandle, StateSyncConfig, SyncConfig, DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
+    ReshardingConfig, ReshardingHandle, ReshardingHandleState, SecondaryIndexesConfig,
+    SimulateFunctionCallConfig, StateSyncConfig, StateSyncProvider, StateSyncProviderConfig,
+    StateSyncProvidersConfig, SyncConfig, ThreadPoolConfig, ThreadPoolsConfig,
+    WitnessValidationLimitsConfig, DEFAULT_GC_NUM_EPOCHS_TO_KEEP,
     DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_EXTERNAL,
-    DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL, MIN_GC_NUM_EPOCHS_TO_KEEP,
+    DEFAULT_STATE_SYNC_NUM_CONCURRENT_REQUESTS_ON_CATCHUP_EXTERNAL,
+    DEFAULT_STATE_SYNC_PROVIDER_MAX_CONSECUTIVE_FAILURES, MIN_GC_NUM_EPOCHS_TO_KEEP,
     TEST_STATE_SYNC_TIMEOUT,
 };
 pub use genesis_config::{
     get_initial_supply, stream_records_from_file, Genesis, GenesisChangeConfig, GenesisConfig,
     GenesisContents, GenesisRecords, GenesisValidationMode, ProtocolConfig, ProtocolConfigView,
 };
+pub use genesis_records::{
+    canonicalize_records, canonicalize_records_file, CanonicalizeRecordsError,
+    CanonicalizeRecordsStats,
+};
 use near_primitives::types::{Balance, BlockHeightDelta, Gas, NumBlocks, NumSeats};
 use num_rational::Rational32;
+pub use shard_assignment::{ShardAssignmentPreview, ShardAssignmentSummary};
+pub use tracked_shards::{TrackedShardsConfig, TrackedShardsConfigError};
 pub use updateable_config::{
     MutableConfigValue, MutableValidatorSigner, UpdateableClientConfig, UpdateableValidatorSigner,
 };
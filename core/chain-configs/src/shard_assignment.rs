@@ -0,0 +1,106 @@
+//! Preview of how accounts are distributed across the shards of a layout, see
+//! [ShardAssignmentPreview].
+
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, Balance, ShardId};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Shows which shard each account ends up in for a given shard layout, together with the
+/// number of accounts and the total stake per shard.
+#[derive(Debug, Clone)]
+pub struct ShardAssignmentPreview {
+    /// Account id, shard and stake of every account, in the order they were given.
+    pub accounts: Vec<(AccountId, ShardId, Balance)>,
+    /// Summary for every shard of the layout, including the shards without any accounts.
+    pub shards: BTreeMap<ShardId, ShardAssignmentSummary>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ShardAssignmentSummary {
+    pub num_accounts: usize,
+    pub total_stake: Balance,
+}
+
+impl ShardAssignmentPreview {
+    pub fn new(
+        shard_layout: &ShardLayout,
+        accounts: impl IntoIterator<Item = (AccountId, Balance)>,
+    ) -> Self {
+        let mut shards: BTreeMap<ShardId, ShardAssignmentSummary> =
+            shard_layout.shard_ids().map(|shard_id| (shard_id, Default::default())).collect();
+        let accounts = accounts
+            .into_iter()
+            .map(|(account_id, stake)| {
+                let shard_id = shard_layout.account_id_to_shard_id(&account_id);
+                let summary = shards.entry(shard_id).or_default();
+                summary.num_accounts += 1;
+                summary.total_stake += stake;
+                (account_id, shard_id, stake)
+            })
+            .collect();
+        Self { accounts, shards }
+    }
+}
+
+impl fmt::Display for ShardAssignmentPreview {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (account_id, shard_id, stake) in &self.accounts {
+            writeln!(f, "{account_id} -> shard {shard_id} (stake {stake})")?;
+        }
+        for (shard_id, summary) in &self.shards {
+            writeln!(
+                f,
+                "shard {shard_id}: {} accounts, total stake {}",
+                summary.num_accounts, summary.total_stake
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ShardAssignmentPreview, ShardAssignmentSummary};
+    use near_primitives::shard_layout::ShardLayout;
+    use near_primitives::types::ShardId;
+
+    #[test]
+    fn test_shard_assignment_preview() {
+        let shard_layout = ShardLayout::v1(vec!["m".parse().unwrap()], None, 0);
+        let accounts = vec![
+            ("alice".parse().unwrap(), 10),
+            ("zoe".parse().unwrap(), 0),
+            ("bob".parse().unwrap(), 5),
+        ];
+        let preview = ShardAssignmentPreview::new(&shard_layout, accounts);
+
+        let shards: Vec<_> = preview.accounts.iter().map(|(_, shard_id, _)| *shard_id).collect();
+        assert_eq!(shards, vec![ShardId::new(0), ShardId::new(1), ShardId::new(0)]);
+        assert_eq!(
+            preview.shards[&ShardId::new(0)],
+            ShardAssignmentSummary { num_accounts: 2, total_stake: 15 }
+        );
+        assert_eq!(
+            preview.shards[&ShardId::new(1)],
+            ShardAssignmentSummary { num_accounts: 1, total_stake: 0 }
+        );
+        assert_eq!(
+            preview.to_string(),
+            "alice -> shard 0 (stake 10)\n\
+             zoe -> shard 1 (stake 0)\n\
+             bob -> shard 0 (stake 5)\n\
+             shard 0: 2 accounts, total stake 15\n\
+             shard 1: 1 accounts, total stake 0\n"
+        );
+    }
+
+    #[test]
+    fn test_shard_assignment_preview_includes_empty_shards() {
+        let shard_layout = ShardLayout::v1(vec!["m".parse().unwrap()], None, 0);
+        let preview = ShardAssignmentPreview::new(&shard_layout, vec![]);
+        assert!(preview.accounts.is_empty());
+        assert_eq!(preview.shards.len(), 2);
+        assert!(preview.shards.values().all(|summary| summary == &Default::default()));
+    }
+}
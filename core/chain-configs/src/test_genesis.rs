@@ -1,25 +1,29 @@
-use std::collections::{BTreeMap, HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 
-use near_crypto::PublicKey;
-use near_primitives::account::{AccessKey, Account};
+use near_crypto::{KeyType, PublicKey};
+use near_primitives::account::id::AccountType;
+use near_primitives::account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission};
+use near_primitives::action::{Action, FunctionCallAction};
 use near_primitives::epoch_manager::{EpochConfig, EpochConfigStore};
 use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum, ReceiptV0};
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::state_record::StateRecord;
 use near_primitives::test_utils::{create_test_signer, create_user_test_signer};
 use near_primitives::types::{
     AccountId, AccountInfo, Balance, BlockHeight, BlockHeightDelta, Gas, NumBlocks, NumSeats,
-    ProtocolVersion,
+    ProtocolVersion, ShardId,
 };
-use near_primitives::utils::from_timestamp;
+use near_primitives::utils::{derive_near_implicit_account_id, from_timestamp};
 use near_primitives::version::PROTOCOL_VERSION;
 use near_time::{Clock, FakeClock};
+use near_wallet_contract::wallet_contract_magic_bytes;
 use num_rational::Rational32;
 
 use crate::{
-    Genesis, GenesisConfig, GenesisContents, GenesisRecords, FISHERMEN_THRESHOLD,
-    PROTOCOL_UPGRADE_STAKE_THRESHOLD,
+    canonicalize_records, CanonicalizeRecordsError, Genesis, GenesisConfig, GenesisContents,
+    GenesisRecords, ShardAssignmentPreview, FISHERMEN_THRESHOLD, PROTOCOL_UPGRADE_STAKE_THRESHOLD,
 };
 
 #[derive(Debug, Clone)]
@@ -32,6 +36,7 @@ pub struct TestEpochConfigBuilder {
     target_validator_mandates_per_shard: NumSeats,
     avg_hidden_validator_seats_per_shard: Vec<NumSeats>,
     minimum_validators_per_shard: NumSeats,
+    minimum_validators_per_shard_overrides: BTreeMap<ShardId, NumSeats>,
     block_producer_kickout_threshold: u8,
     chunk_producer_kickout_threshold: u8,
     chunk_validator_only_kickout_threshold: u8,
@@ -42,11 +47,13 @@ pub struct TestEpochConfigBuilder {
     protocol_upgrade_stake_threshold: Rational32,
     minimum_stake_divisor: u64,
     minimum_stake_ratio: Rational32,
+    chunk_validator_minimum_stake_ratio: Option<Rational32>,
     chunk_producer_assignment_changes_limit: NumSeats,
     shuffle_shard_assignment_for_chunk_producers: bool,
+    validator_blocklist: BTreeSet<AccountId>,
 
-    // not used any more
-    num_block_producer_seats_per_shard: Vec<NumSeats>,
+    // not used any more, unless explicitly set
+    num_block_producer_seats_per_shard: Option<Vec<NumSeats>>,
     // TODO (#11267): deprecate after StatelessValidationV0 is in place.
     // Use 300 for older protocol versions.
     num_chunk_only_producer_seats: NumSeats,
@@ -93,14 +100,29 @@ pub struct TestGenesisBuilder {
     protocol_upgrade_stake_threshold: Rational32,
     chunk_producer_assignment_changes_limit: NumSeats,
     user_accounts: Vec<UserAccount>,
+    /// Access keys added to accounts of the genesis, on top of the ones of the user accounts.
+    extra_access_keys: Vec<(AccountId, PublicKey, AccessKeyPermission)>,
+    /// Receipts put into the delayed receipts queues of the shards of their receivers.
+    delayed_receipts: Vec<Receipt>,
+    /// Config of an existing chain the builder was seeded from. Used for the genesis config
+    /// fields which the builder doesn't control.
+    base_config: Option<GenesisConfig>,
 }
 
 #[derive(Debug, Clone)]
 pub enum ValidatorsSpec {
     DesiredRoles {
         block_and_chunk_producers: Vec<String>,
+        /// Chunk-only producers, only relevant for protocol versions before stateless
+        /// validation, where they are selected using `num_chunk_only_producer_seats`.
+        chunk_only_producers: Vec<String>,
         chunk_validators_only: Vec<String>,
     },
+    /// Same as `DesiredRoles`, but with an explicit stake for every validator.
+    DesiredRolesWithStakes {
+        block_and_chunk_producers: Vec<(String, Balance)>,
+        chunk_validators_only: Vec<(String, Balance)>,
+    },
     Raw {
         validators: Vec<AccountInfo>,
         num_block_producer_seats: NumSeats,
@@ -113,7 +135,39 @@ pub enum ValidatorsSpec {
 struct UserAccount {
     account_id: AccountId,
     balance: Balance,
-    access_keys: Vec<PublicKey>,
+    /// Locked balance of an account that is not a validator. Validator accounts get their
+    /// locked balance from the validators spec instead.
+    locked: Balance,
+    access_keys: Vec<(PublicKey, AccessKeyPermission)>,
+    /// Whether the wallet contract is deployed to the account, as for ETH-implicit accounts.
+    wallet_contract: bool,
+}
+
+/// Reasons why `TestGenesisBuilder::build` may refuse to produce a genesis.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TestGenesisBuildError {
+    #[error("duplicate user account {0}")]
+    DuplicateUserAccount(AccountId),
+    #[error("locked balance of validator {0} is determined by the validators spec")]
+    LockedBalanceOfValidator(AccountId),
+    #[error("receiver {receiver_id} of delayed receipt {receipt_id} is not a genesis account")]
+    UnknownDelayedReceiptReceiver { receipt_id: CryptoHash, receiver_id: AccountId },
+    #[error("duplicate account {0} in genesis records")]
+    DuplicateAccountRecord(AccountId),
+    #[error("duplicate access key {public_key} of account {account_id} in genesis records")]
+    DuplicateAccessKeyRecord { account_id: AccountId, public_key: PublicKey },
+    #[error("account {0} has more than one contract deployed")]
+    DuplicateContractRecord(AccountId),
+    #[error("access key account {0} does not exist")]
+    AccessKeyWithoutAccount(AccountId),
+    #[error("contract account {0} does not exist")]
+    ContractWithoutAccount(AccountId),
+    #[error("code hash of account {0} does not match its deployed contract")]
+    ContractCodeHashMismatch(AccountId),
+    #[error("records total supply {records} does not match config total supply {config}")]
+    TotalSupplyMismatch { records: Balance, config: Balance },
+    #[error(transparent)]
+    NonCanonicalRecords(#[from] CanonicalizeRecordsError),
 }
 
 impl Default for TestEpochConfigBuilder {
@@ -130,6 +184,7 @@ impl Default for TestEpochConfigBuilder {
             target_validator_mandates_per_shard: 68,
             avg_hidden_validator_seats_per_shard: vec![],
             minimum_validators_per_shard: 1,
+            minimum_validators_per_shard_overrides: BTreeMap::new(),
             block_producer_kickout_threshold: 0,
             chunk_producer_kickout_threshold: 0,
             chunk_validator_only_kickout_threshold: 0,
@@ -140,10 +195,12 @@ impl Default for TestEpochConfigBuilder {
             protocol_upgrade_stake_threshold: PROTOCOL_UPGRADE_STAKE_THRESHOLD,
             minimum_stake_divisor: 10,
             minimum_stake_ratio: Rational32::new(16i32, 1_000_000i32),
+            chunk_validator_minimum_stake_ratio: None,
             chunk_producer_assignment_changes_limit: 5,
             shuffle_shard_assignment_for_chunk_producers: false,
+            validator_blocklist: BTreeSet::new(),
             // consider them ineffective
-            num_block_producer_seats_per_shard: vec![1],
+            num_block_producer_seats_per_shard: None,
             num_chunk_only_producer_seats: 300,
         }
     }
@@ -164,16 +221,73 @@ impl TestEpochConfigBuilder {
         self
     }
 
+    /// Sets a V2 shard layout with the given boundary accounts, generated shard ids
+    /// and shard version 1. The layout is deterministic, so passing the same
+    /// boundary accounts to the genesis and epoch config builders yields the
+    /// same layout in both.
+    pub fn shard_layout_simple(self, boundary_accounts: &[&str]) -> Self {
+        self.shard_layout(ShardLayout::simple_v1(boundary_accounts))
+    }
+
     pub fn validators_spec(mut self, validators_spec: ValidatorsSpec) -> Self {
         let DerivedValidatorSetup {
             validators: _,
             num_block_producer_seats,
             num_chunk_producer_seats,
             num_chunk_validator_seats,
+            num_chunk_only_producer_seats,
         } = derive_validator_setup(validators_spec);
         self.num_block_producer_seats = num_block_producer_seats;
         self.num_chunk_producer_seats = num_chunk_producer_seats;
         self.num_chunk_validator_seats = num_chunk_validator_seats;
+        if let Some(num_chunk_only_producer_seats) = num_chunk_only_producer_seats {
+            self.num_chunk_only_producer_seats = num_chunk_only_producer_seats;
+        }
+        self
+    }
+
+    /// Number of chunk-only producer seats, only used by protocol versions before stateless
+    /// validation. Set automatically if the validators spec contains chunk-only producers.
+    pub fn num_chunk_only_producer_seats(
+        mut self,
+        num_chunk_only_producer_seats: NumSeats,
+    ) -> Self {
+        self.num_chunk_only_producer_seats = num_chunk_only_producer_seats;
+        self
+    }
+
+    /// Number of block producer seats for every shard of the shard layout. Not used
+    /// by the validator selection any more, but still a part of the epoch config.
+    pub fn num_block_producer_seats_per_shard(
+        mut self,
+        num_block_producer_seats_per_shard: Vec<NumSeats>,
+    ) -> Self {
+        self.num_block_producer_seats_per_shard = Some(num_block_producer_seats_per_shard);
+        self
+    }
+
+    /// Maximum number of chunk producer assignments which may change between
+    /// consecutive epochs, see `EpochConfig::chunk_producer_assignment_changes_limit`.
+    pub fn chunk_producer_assignment_changes_limit(
+        mut self,
+        chunk_producer_assignment_changes_limit: NumSeats,
+    ) -> Self {
+        self.chunk_producer_assignment_changes_limit = chunk_producer_assignment_changes_limit;
+        self
+    }
+
+    pub fn fishermen_threshold(mut self, fishermen_threshold: Balance) -> Self {
+        self.fishermen_threshold = fishermen_threshold;
+        self
+    }
+
+    /// Fraction of the total stake which has to vote for a new protocol version
+    /// before the chain upgrades to it.
+    pub fn protocol_upgrade_stake_threshold(
+        mut self,
+        protocol_upgrade_stake_threshold: Rational32,
+    ) -> Self {
+        self.protocol_upgrade_stake_threshold = protocol_upgrade_stake_threshold;
         self
     }
 
@@ -182,6 +296,29 @@ impl TestEpochConfigBuilder {
         self
     }
 
+    /// Overrides the minimum number of chunk producers for the given shards.
+    /// Shards not in the map use `minimum_validators_per_shard`.
+    pub fn minimum_validators_per_shard_overrides(
+        mut self,
+        minimum_validators_per_shard_overrides: impl IntoIterator<Item = (ShardId, NumSeats)>,
+    ) -> Self {
+        self.minimum_validators_per_shard_overrides =
+            minimum_validators_per_shard_overrides.into_iter().collect();
+        self
+    }
+
+    /// Lets chunk-validator-only seats have a lower minimum stake ratio than the other seats.
+    pub fn chunk_validator_minimum_stake_ratio(mut self, ratio: Rational32) -> Self {
+        self.chunk_validator_minimum_stake_ratio = Some(ratio);
+        self
+    }
+
+    /// Excludes the given accounts from the validator selection.
+    pub fn validator_blocklist(mut self, validator_blocklist: &[AccountId]) -> Self {
+        self.validator_blocklist = validator_blocklist.iter().cloned().collect();
+        self
+    }
+
     pub fn target_validator_mandates_per_shard(
         mut self,
         target_validator_mandates_per_shard: NumSeats,
@@ -217,6 +354,24 @@ impl TestEpochConfigBuilder {
     }
 
     pub fn build(self) -> EpochConfig {
+        let num_block_producer_seats_per_shard = match self.num_block_producer_seats_per_shard {
+            Some(seats) => {
+                assert_eq!(
+                    seats.len(),
+                    self.shard_layout.num_shards() as usize,
+                    "num_block_producer_seats_per_shard must have an entry for every shard"
+                );
+                seats
+            }
+            None => vec![1],
+        };
+        for shard_id in self.minimum_validators_per_shard_overrides.keys() {
+            assert!(
+                self.shard_layout.shard_ids().any(|id| id == *shard_id),
+                "minimum_validators_per_shard_overrides has unknown shard {}",
+                shard_id
+            );
+        }
         let epoch_config = EpochConfig {
             epoch_length: self.epoch_length,
             shard_layout: self.shard_layout,
@@ -226,6 +381,7 @@ impl TestEpochConfigBuilder {
             target_validator_mandates_per_shard: self.target_validator_mandates_per_shard,
             avg_hidden_validator_seats_per_shard: self.avg_hidden_validator_seats_per_shard,
             minimum_validators_per_shard: self.minimum_validators_per_shard,
+            minimum_validators_per_shard_overrides: self.minimum_validators_per_shard_overrides,
             block_producer_kickout_threshold: self.block_producer_kickout_threshold,
             chunk_producer_kickout_threshold: self.chunk_producer_kickout_threshold,
             chunk_validator_only_kickout_threshold: self.chunk_validator_only_kickout_threshold,
@@ -236,10 +392,12 @@ impl TestEpochConfigBuilder {
             protocol_upgrade_stake_threshold: self.protocol_upgrade_stake_threshold,
             minimum_stake_divisor: self.minimum_stake_divisor,
             minimum_stake_ratio: self.minimum_stake_ratio,
+            chunk_validator_minimum_stake_ratio: self.chunk_validator_minimum_stake_ratio,
             chunk_producer_assignment_changes_limit: self.chunk_producer_assignment_changes_limit,
             shuffle_shard_assignment_for_chunk_producers: self
                 .shuffle_shard_assignment_for_chunk_producers,
-            num_block_producer_seats_per_shard: self.num_block_producer_seats_per_shard,
+            validator_blocklist: self.validator_blocklist,
+            num_block_producer_seats_per_shard,
             num_chunk_only_producer_seats: self.num_chunk_only_producer_seats,
         };
         tracing::debug!("Epoch config: {:#?}", epoch_config);
@@ -259,6 +417,7 @@ impl Default for TestGenesisBuilder {
             shard_layout: ShardLayout::single_shard(),
             validators_spec: ValidatorsSpec::DesiredRoles {
                 block_and_chunk_producers: vec!["validator0".to_string()],
+                chunk_only_producers: vec![],
                 chunk_validators_only: vec![],
             },
             genesis_time: chrono::Utc::now(),
@@ -270,6 +429,8 @@ impl Default for TestGenesisBuilder {
             protocol_treasury_account: "near".to_string().parse().unwrap(),
             max_inflation_rate: Rational32::new(1, 1),
             user_accounts: vec![],
+            extra_access_keys: vec![],
+            delayed_receipts: vec![],
             dynamic_resharding: false,
             fishermen_threshold: 0,
             online_min_threshold: Rational32::new(90, 100),
@@ -282,6 +443,7 @@ impl Default for TestGenesisBuilder {
             minimum_stake_divisor: 10,
             protocol_upgrade_stake_threshold: Rational32::new(8, 10),
             chunk_producer_assignment_changes_limit: 5,
+            base_config: None,
         }
     }
 }
@@ -291,6 +453,53 @@ impl TestGenesisBuilder {
         Default::default()
     }
 
+    /// Seeds the builder with all fields of the config of an existing chain,
+    /// e.g. mainnet or testnet, so that only the fields relevant for the test
+    /// need to be overridden afterwards. Validators are taken as they are,
+    /// see `ValidatorsSpec::raw`.
+    ///
+    /// Genesis records are not a part of the config, so user accounts still
+    /// need to be added explicitly. The fields of the config which the builder
+    /// doesn't have setters for are kept as is.
+    pub fn from_existing_config(config: GenesisConfig) -> Self {
+        Self {
+            chain_id: config.chain_id.clone(),
+            protocol_version: config.protocol_version,
+            epoch_length: config.epoch_length,
+            shard_layout: config.shard_layout.clone(),
+            validators_spec: ValidatorsSpec::Raw {
+                validators: config.validators.clone(),
+                num_block_producer_seats: config.num_block_producer_seats,
+                num_chunk_producer_seats: config.num_chunk_producer_seats,
+                num_chunk_validator_seats: config.num_chunk_validator_seats,
+            },
+            genesis_time: config.genesis_time,
+            genesis_height: config.genesis_height,
+            min_gas_price: config.min_gas_price,
+            max_gas_price: config.max_gas_price,
+            gas_limit: config.gas_limit,
+            transaction_validity_period: config.transaction_validity_period,
+            protocol_treasury_account: config.protocol_treasury_account.to_string(),
+            max_inflation_rate: config.max_inflation_rate,
+            dynamic_resharding: config.dynamic_resharding,
+            fishermen_threshold: config.fishermen_threshold,
+            online_min_threshold: config.online_min_threshold,
+            online_max_threshold: config.online_max_threshold,
+            gas_price_adjustment_rate: config.gas_price_adjustment_rate,
+            num_blocks_per_year: config.num_blocks_per_year,
+            protocol_reward_rate: config.protocol_reward_rate,
+            max_kickout_stake_perc: config.max_kickout_stake_perc,
+            num_chunk_only_producer_seats: config.num_chunk_only_producer_seats,
+            minimum_stake_divisor: config.minimum_stake_divisor,
+            protocol_upgrade_stake_threshold: config.protocol_upgrade_stake_threshold,
+            chunk_producer_assignment_changes_limit: config.chunk_producer_assignment_changes_limit,
+            user_accounts: vec![],
+            extra_access_keys: vec![],
+            delayed_receipts: vec![],
+            base_config: Some(config),
+        }
+    }
+
     pub fn chain_id(mut self, chain_id: String) -> Self {
         self.chain_id = chain_id;
         self
@@ -326,6 +535,14 @@ impl TestGenesisBuilder {
         self
     }
 
+    /// Sets a V2 shard layout with the given boundary accounts, generated shard ids
+    /// and shard version 1. The layout is deterministic, so passing the same
+    /// boundary accounts to the genesis and epoch config builders yields the
+    /// same layout in both.
+    pub fn shard_layout_simple(self, boundary_accounts: &[&str]) -> Self {
+        self.shard_layout(ShardLayout::simple_v1(boundary_accounts))
+    }
+
     pub fn gas_prices(mut self, min: Balance, max: Balance) -> Self {
         self.min_gas_price = min;
         self.max_gas_price = max;
@@ -357,6 +574,31 @@ impl TestGenesisBuilder {
         self
     }
 
+    /// Number of chunk-only producer seats, only used by protocol versions before stateless
+    /// validation. Set automatically if the validators spec contains chunk-only producers.
+    pub fn num_chunk_only_producer_seats(
+        mut self,
+        num_chunk_only_producer_seats: NumSeats,
+    ) -> Self {
+        self.num_chunk_only_producer_seats = num_chunk_only_producer_seats;
+        self
+    }
+
+    pub fn fishermen_threshold(mut self, fishermen_threshold: Balance) -> Self {
+        self.fishermen_threshold = fishermen_threshold;
+        self
+    }
+
+    /// Fraction of the total stake which has to vote for a new protocol version
+    /// before the chain upgrades to it.
+    pub fn protocol_upgrade_stake_threshold(
+        mut self,
+        protocol_upgrade_stake_threshold: Rational32,
+    ) -> Self {
+        self.protocol_upgrade_stake_threshold = protocol_upgrade_stake_threshold;
+        self
+    }
+
     /// Specifies the protocol treasury account. If not specified, this will
     /// pick an arbitrary account name and ensure that it is included in the
     /// genesis records.
@@ -372,7 +614,28 @@ impl TestGenesisBuilder {
     ) -> Self {
         self.user_accounts.push(UserAccount {
             balance: initial_balance,
-            access_keys: vec![create_user_test_signer(&account_id).public_key()],
+            locked: 0,
+            access_keys: vec![full_access_key(&account_id)],
+            wallet_contract: false,
+            account_id,
+        });
+        self
+    }
+
+    /// Adds a user account with the given amount of locked (staked) balance, without making it
+    /// a validator. Useful for setting up unstaking and stake return scenarios directly in
+    /// genesis. The account must not be a part of the validators spec.
+    pub fn add_user_account_with_locked_balance(
+        mut self,
+        account_id: AccountId,
+        initial_balance: Balance,
+        locked_balance: Balance,
+    ) -> Self {
+        self.user_accounts.push(UserAccount {
+            balance: initial_balance,
+            locked: locked_balance,
+            access_keys: vec![full_access_key(&account_id)],
+            wallet_contract: false,
             account_id,
         });
         self
@@ -386,23 +649,175 @@ impl TestGenesisBuilder {
         for account_id in accounts {
             self.user_accounts.push(UserAccount {
                 balance: initial_balance,
-                access_keys: vec![create_user_test_signer(account_id).public_key()],
+                locked: 0,
+                access_keys: vec![full_access_key(account_id)],
+                wallet_contract: false,
                 account_id: account_id.clone(),
             });
         }
         self
     }
 
-    pub fn build(self) -> Genesis {
-        if self
-            .user_accounts
-            .iter()
-            .map(|account| &account.account_id)
-            .collect::<HashSet<_>>()
-            .len()
-            != self.user_accounts.len()
-        {
-            panic!("Duplicate user accounts specified.");
+    /// Adds a user account with the given access keys instead of the full access key of its
+    /// test signer, e.g. function call keys restricted to a receiver, an allowance and some
+    /// methods, see `function_call_permission`.
+    pub fn add_user_account_with_access_keys(
+        mut self,
+        account_id: AccountId,
+        initial_balance: Balance,
+        access_keys: Vec<(PublicKey, AccessKeyPermission)>,
+    ) -> Self {
+        self.user_accounts.push(UserAccount {
+            account_id,
+            balance: initial_balance,
+            locked: 0,
+            access_keys,
+            wallet_contract: false,
+        });
+        self
+    }
+
+    /// Adds an access key to an account of the genesis, which can be a user account or the
+    /// account of a validator. Useful to give restricted keys to the accounts added by the
+    /// helpers building the genesis, e.g. for permissioned keys and meta transaction flows.
+    pub fn add_access_key(
+        mut self,
+        account_id: AccountId,
+        public_key: PublicKey,
+        permission: AccessKeyPermission,
+    ) -> Self {
+        self.extra_access_keys.push((account_id, public_key, permission));
+        self
+    }
+
+    /// Adds `count` user accounts spread evenly across the shards of `shard_layout`, see
+    /// `synthetic_account_ids`. Use that function to get the ids of the added accounts.
+    pub fn add_synthetic_accounts(
+        self,
+        count: usize,
+        initial_balance: Balance,
+        shard_layout: &ShardLayout,
+    ) -> Self {
+        let accounts = synthetic_account_ids(count, shard_layout);
+        self.add_user_accounts_simple(&accounts, initial_balance)
+    }
+
+    /// Adds a NEAR-implicit account, i.e. the hex encoding of the given ED25519
+    /// public key, with a full access key for that public key.
+    pub fn add_implicit_account(mut self, public_key: PublicKey, initial_balance: Balance) -> Self {
+        assert_eq!(
+            public_key.key_type(),
+            KeyType::ED25519,
+            "NEAR-implicit accounts require an ED25519 key"
+        );
+        self.user_accounts.push(UserAccount {
+            account_id: derive_near_implicit_account_id(public_key.unwrap_as_ed25519()),
+            balance: initial_balance,
+            locked: 0,
+            access_keys: vec![(public_key, AccessKeyPermission::FullAccess)],
+            wallet_contract: false,
+        });
+        self
+    }
+
+    /// Adds an ETH-implicit account, i.e. `0x` followed by the hex encoding of
+    /// the given address, with the wallet contract deployed. The account has no
+    /// access keys, just like the ETH-implicit accounts created by transfers.
+    /// The deployed code is the wallet contract magic bytes for the chain id and
+    /// protocol version of the genesis.
+    pub fn add_eth_implicit_account(mut self, address: [u8; 20], initial_balance: Balance) -> Self {
+        let address: String = address.iter().map(|byte| format!("{:02x}", byte)).collect();
+        let account_id: AccountId = format!("0x{}", address).parse().unwrap();
+        debug_assert_eq!(account_id.get_account_type(), AccountType::EthImplicitAccount);
+        self.user_accounts.push(UserAccount {
+            account_id,
+            balance: initial_balance,
+            locked: 0,
+            access_keys: vec![],
+            wallet_contract: true,
+        });
+        self
+    }
+
+    /// Adds receipts to the delayed receipts queues of the shards of their
+    /// receivers. The congestion info of the genesis chunks is bootstrapped
+    /// from the genesis state, so this allows tests to start with a congested
+    /// shard instead of generating the load first. The receivers must be
+    /// accounts of the genesis.
+    pub fn add_delayed_receipts(mut self, receipts: impl IntoIterator<Item = Receipt>) -> Self {
+        self.delayed_receipts.extend(receipts);
+        self
+    }
+
+    /// Adds `num_receipts` function call receipts from `signer_id` to
+    /// `receiver_id`, each with `gas_per_receipt` attached, to the delayed
+    /// receipts queue of the shard of `receiver_id`. See `add_delayed_receipts`.
+    pub fn add_congesting_delayed_receipts(
+        self,
+        signer_id: AccountId,
+        receiver_id: AccountId,
+        num_receipts: usize,
+        gas_per_receipt: Gas,
+    ) -> Self {
+        let first_index = self.delayed_receipts.len();
+        let gas_price = self.min_gas_price;
+        let signer_public_key = create_user_test_signer(&signer_id).public_key();
+        let receipts = (first_index..first_index + num_receipts).map(|index| {
+            Receipt::V0(ReceiptV0 {
+                predecessor_id: signer_id.clone(),
+                receiver_id: receiver_id.clone(),
+                receipt_id: CryptoHash::hash_bytes(format!("genesis-delayed-{index}").as_bytes()),
+                receipt: ReceiptEnum::Action(ActionReceipt {
+                    signer_id: signer_id.clone(),
+                    signer_public_key: signer_public_key.clone(),
+                    gas_price,
+                    output_data_receivers: vec![],
+                    input_data_ids: vec![],
+                    actions: vec![Action::FunctionCall(Box::new(FunctionCallAction {
+                        method_name: "congest".to_string(),
+                        args: vec![],
+                        gas: gas_per_receipt,
+                        deposit: 0,
+                    }))],
+                }),
+            })
+        });
+        self.add_delayed_receipts(receipts.collect::<Vec<_>>())
+    }
+
+    /// Previews how the accounts added so far, including the validators, are distributed
+    /// across the shards of the configured shard layout.
+    pub fn shard_assignment_preview(&self) -> ShardAssignmentPreview {
+        let validators = derive_validator_setup(self.validators_spec.clone()).validators;
+        let mut stakes: HashMap<&AccountId, Balance> =
+            validators.iter().map(|validator| (&validator.account_id, validator.amount)).collect();
+        let mut accounts = vec![];
+        for user_account in &self.user_accounts {
+            let stake = stakes.remove(&user_account.account_id).unwrap_or(user_account.locked);
+            accounts.push((user_account.account_id.clone(), stake));
+        }
+        // Validators which are not user accounts still get an account in the genesis.
+        for validator in &validators {
+            if let Some(stake) = stakes.remove(&validator.account_id) {
+                accounts.push((validator.account_id.clone(), stake));
+            }
+        }
+        ShardAssignmentPreview::new(&self.shard_layout, accounts)
+    }
+
+    /// Builds the genesis and validates its records the same way `Genesis::validate` does:
+    /// no duplicate records, access keys and contracts only for existing accounts and a total
+    /// supply matching the records. Staked balances are not required to match the validators,
+    /// see `add_user_account_with_locked_balance`.
+    /// The records are returned in their canonical form, see `canonicalize_records`.
+    pub fn build(self) -> Result<Genesis, TestGenesisBuildError> {
+        let mut user_account_ids = HashSet::new();
+        for user_account in &self.user_accounts {
+            if !user_account_ids.insert(&user_account.account_id) {
+                return Err(TestGenesisBuildError::DuplicateUserAccount(
+                    user_account.account_id.clone(),
+                ));
+            }
         }
 
         let protocol_treasury_account: AccountId = self.protocol_treasury_account.parse().unwrap();
@@ -422,7 +837,9 @@ impl TestGenesisBuilder {
             user_accounts.push(UserAccount {
                 account_id: protocol_treasury_account.clone(),
                 balance: 0,
+                locked: 0,
                 access_keys: vec![],
+                wallet_contract: false,
             });
         }
 
@@ -431,7 +848,10 @@ impl TestGenesisBuilder {
             num_block_producer_seats,
             num_chunk_producer_seats,
             num_chunk_validator_seats,
+            num_chunk_only_producer_seats,
         } = derive_validator_setup(self.validators_spec);
+        let num_chunk_only_producer_seats =
+            num_chunk_only_producer_seats.unwrap_or(self.num_chunk_only_producer_seats);
 
         let mut total_supply = 0;
         let mut validator_stake: HashMap<AccountId, Balance> = HashMap::new();
@@ -441,26 +861,50 @@ impl TestGenesisBuilder {
         }
         let mut records = Vec::new();
         for user_account in &user_accounts {
+            let locked = match validator_stake.remove(&user_account.account_id) {
+                Some(stake) => {
+                    if user_account.locked != 0 {
+                        return Err(TestGenesisBuildError::LockedBalanceOfValidator(
+                            user_account.account_id.clone(),
+                        ));
+                    }
+                    stake
+                }
+                None => {
+                    total_supply += user_account.locked;
+                    user_account.locked
+                }
+            };
             total_supply += user_account.balance;
+            let code = user_account
+                .wallet_contract
+                .then(|| wallet_contract_magic_bytes(&self.chain_id, self.protocol_version));
+            let code_hash = match &code {
+                Some(code) => *code.hash(),
+                None => CryptoHash::default(),
+            };
             records.push(StateRecord::Account {
                 account_id: user_account.account_id.clone(),
                 account: Account::new(
                     user_account.balance,
-                    validator_stake.remove(&user_account.account_id).unwrap_or(0),
+                    locked,
                     0,
-                    CryptoHash::default(),
+                    code_hash,
                     0,
                     self.protocol_version,
                 ),
             });
-            for access_key in &user_account.access_keys {
+            if let Some(code) = code {
+                records.push(StateRecord::Contract {
+                    account_id: user_account.account_id.clone(),
+                    code: code.code().to_vec(),
+                });
+            }
+            for (public_key, permission) in &user_account.access_keys {
                 records.push(StateRecord::AccessKey {
                     account_id: user_account.account_id.clone(),
-                    public_key: access_key.clone(),
-                    access_key: AccessKey {
-                        nonce: 0,
-                        permission: near_primitives::account::AccessKeyPermission::FullAccess,
-                    },
+                    public_key: public_key.clone(),
+                    access_key: AccessKey { nonce: 0, permission: permission.clone() },
                 });
             }
         }
@@ -478,6 +922,31 @@ impl TestGenesisBuilder {
             });
         }
 
+        for (account_id, public_key, permission) in self.extra_access_keys {
+            records.push(StateRecord::AccessKey {
+                account_id,
+                public_key,
+                access_key: AccessKey { nonce: 0, permission },
+            });
+        }
+
+        let account_ids: HashSet<_> = records
+            .iter()
+            .filter_map(|record| match record {
+                StateRecord::Account { account_id, .. } => Some(account_id.clone()),
+                _ => None,
+            })
+            .collect();
+        for receipt in self.delayed_receipts {
+            if !account_ids.contains(receipt.receiver_id()) {
+                return Err(TestGenesisBuildError::UnknownDelayedReceiptReceiver {
+                    receipt_id: *receipt.receipt_id(),
+                    receiver_id: receipt.receiver_id().clone(),
+                });
+            }
+            records.push(StateRecord::DelayedReceipt(Box::new(receipt)));
+        }
+
         let genesis_config = GenesisConfig {
             chain_id: self.chain_id,
             genesis_time: self.genesis_time,
@@ -506,22 +975,101 @@ impl TestGenesisBuilder {
                 .shard_ids()
                 .map(|_| num_block_producer_seats)
                 .collect(),
-            num_chunk_only_producer_seats: self.num_chunk_only_producer_seats,
+            num_chunk_only_producer_seats,
             minimum_stake_divisor: self.minimum_stake_divisor,
             max_inflation_rate: self.max_inflation_rate,
             protocol_upgrade_stake_threshold: self.protocol_upgrade_stake_threshold,
             num_chunk_producer_seats,
             num_chunk_validator_seats,
             chunk_producer_assignment_changes_limit: self.chunk_producer_assignment_changes_limit,
-            ..Default::default()
+            ..self.base_config.unwrap_or_default()
         };
         tracing::debug!("Genesis config: {:#?}", genesis_config);
+        validate_records(&records, genesis_config.total_supply)?;
+        let (records, _) = canonicalize_records(records)?;
 
-        Genesis {
+        Ok(Genesis {
             config: genesis_config,
             contents: GenesisContents::Records { records: GenesisRecords(records) },
+        })
+    }
+}
+
+fn full_access_key(account_id: &AccountId) -> (PublicKey, AccessKeyPermission) {
+    (create_user_test_signer(account_id).public_key(), AccessKeyPermission::FullAccess)
+}
+
+/// Permission of a function call access key restricted to the calls of `method_names` on
+/// `receiver_id`, or of any method if it's empty, spending at most `allowance` on fees if
+/// set.
+pub fn function_call_permission(
+    receiver_id: &AccountId,
+    allowance: Option<Balance>,
+    method_names: &[&str],
+) -> AccessKeyPermission {
+    AccessKeyPermission::FunctionCall(FunctionCallPermission {
+        allowance,
+        receiver_id: receiver_id.to_string(),
+        method_names: method_names.iter().map(|method_name| method_name.to_string()).collect(),
+    })
+}
+
+/// Mirrors the per-record checks of `validate_genesis`, reporting the first problem found.
+fn validate_records(
+    records: &[StateRecord],
+    total_supply: Balance,
+) -> Result<(), TestGenesisBuildError> {
+    let mut code_hashes = HashMap::new();
+    let mut access_keys = HashSet::new();
+    let mut contracts = HashMap::new();
+    let mut records_total_supply: Balance = 0;
+    for record in records {
+        match record {
+            StateRecord::Account { account_id, account } => {
+                if code_hashes.insert(account_id, account.code_hash()).is_some() {
+                    return Err(TestGenesisBuildError::DuplicateAccountRecord(account_id.clone()));
+                }
+                records_total_supply += account.amount() + account.locked();
+            }
+            StateRecord::AccessKey { account_id, public_key, .. } => {
+                if !access_keys.insert((account_id, public_key)) {
+                    return Err(TestGenesisBuildError::DuplicateAccessKeyRecord {
+                        account_id: account_id.clone(),
+                        public_key: public_key.clone(),
+                    });
+                }
+            }
+            StateRecord::Contract { account_id, code } => {
+                if contracts.insert(account_id, CryptoHash::hash_bytes(code)).is_some() {
+                    return Err(TestGenesisBuildError::DuplicateContractRecord(account_id.clone()));
+                }
+            }
+            _ => {}
+        }
+    }
+    for (account_id, _) in &access_keys {
+        if !code_hashes.contains_key(account_id) {
+            return Err(TestGenesisBuildError::AccessKeyWithoutAccount((*account_id).clone()));
         }
     }
+    for (account_id, code_hash) in &contracts {
+        match code_hashes.get(account_id) {
+            None => {
+                return Err(TestGenesisBuildError::ContractWithoutAccount((*account_id).clone()))
+            }
+            Some(account_code_hash) if account_code_hash != code_hash => {
+                return Err(TestGenesisBuildError::ContractCodeHashMismatch((*account_id).clone()))
+            }
+            Some(_) => {}
+        }
+    }
+    if records_total_supply != total_supply {
+        return Err(TestGenesisBuildError::TotalSupplyMismatch {
+            records: records_total_supply,
+            config: total_supply,
+        });
+    }
+    Ok(())
 }
 
 impl ValidatorsSpec {
@@ -531,16 +1079,54 @@ impl ValidatorsSpec {
     pub fn desired_roles(
         block_and_chunk_producers: &[&str],
         chunk_validators_only: &[&str],
+    ) -> Self {
+        Self::desired_roles_with_chunk_only_producers(
+            block_and_chunk_producers,
+            &[],
+            chunk_validators_only,
+        )
+    }
+
+    /// Same as `desired_roles`, but additionally specifies chunk-only
+    /// producers. These only exist in protocol versions before stateless
+    /// validation, so this is meant for tests covering the legacy validator
+    /// selection. The number of chunk-only producer seats is set accordingly.
+    pub fn desired_roles_with_chunk_only_producers(
+        block_and_chunk_producers: &[&str],
+        chunk_only_producers: &[&str],
+        chunk_validators_only: &[&str],
     ) -> Self {
         ValidatorsSpec::DesiredRoles {
             block_and_chunk_producers: block_and_chunk_producers
                 .iter()
                 .map(|s| s.to_string())
                 .collect(),
+            chunk_only_producers: chunk_only_producers.iter().map(|s| s.to_string()).collect(),
             chunk_validators_only: chunk_validators_only.iter().map(|s| s.to_string()).collect(),
         }
     }
 
+    /// Same as `desired_roles`, but gives every validator the specified stake
+    /// instead of the default decreasing stakes. Useful for tests that need a
+    /// skewed stake distribution. To make sure the validators are selected as
+    /// specified, every block and chunk producer must have at least as much
+    /// stake as any of the chunk validators only.
+    pub fn desired_roles_with_stakes(
+        block_and_chunk_producers: &[(&str, Balance)],
+        chunk_validators_only: &[(&str, Balance)],
+    ) -> Self {
+        ValidatorsSpec::DesiredRolesWithStakes {
+            block_and_chunk_producers: block_and_chunk_producers
+                .iter()
+                .map(|(account_id, stake)| (account_id.to_string(), *stake))
+                .collect(),
+            chunk_validators_only: chunk_validators_only
+                .iter()
+                .map(|(account_id, stake)| (account_id.to_string(), *stake))
+                .collect(),
+        }
+    }
+
     /// Specifies the validator fields directly, relying on the validator selection
     /// algorithm to determine which validators are selected as block or chunk
     /// producers.
@@ -567,41 +1153,86 @@ struct DerivedValidatorSetup {
     num_block_producer_seats: NumSeats,
     num_chunk_producer_seats: NumSeats,
     num_chunk_validator_seats: NumSeats,
+    /// Set only if the spec explicitly lists chunk-only producers.
+    num_chunk_only_producer_seats: Option<NumSeats>,
 }
 
 const ONE_NEAR: Balance = 1_000_000_000_000_000_000_000_000;
 
 fn derive_validator_setup(specs: ValidatorsSpec) -> DerivedValidatorSetup {
     match specs {
-        ValidatorsSpec::DesiredRoles { block_and_chunk_producers, chunk_validators_only } => {
+        ValidatorsSpec::DesiredRoles {
+            block_and_chunk_producers,
+            chunk_only_producers,
+            chunk_validators_only,
+        } => {
             let num_block_and_chunk_producer_seats = block_and_chunk_producers.len() as NumSeats;
+            let num_chunk_only_producer_seats = chunk_only_producers.len() as NumSeats;
             let num_chunk_validator_only_seats = chunk_validators_only.len() as NumSeats;
-            let mut validators = Vec::new();
-            for i in 0..num_block_and_chunk_producer_seats as usize {
-                let account_id: AccountId = block_and_chunk_producers[i].parse().unwrap();
-                let account_info = AccountInfo {
-                    public_key: create_test_signer(account_id.as_str()).public_key(),
-                    account_id,
-                    amount: ONE_NEAR * (10000 - i as Balance),
-                };
-                validators.push(account_info);
+            // Validators are given decreasing stakes in the order of their roles, so that the
+            // validator selection algorithm picks them for the desired roles.
+            let validators = block_and_chunk_producers
+                .iter()
+                .chain(chunk_only_producers.iter())
+                .chain(chunk_validators_only.iter())
+                .enumerate()
+                .map(|(i, account_id)| {
+                    let account_id: AccountId = account_id.parse().unwrap();
+                    AccountInfo {
+                        public_key: create_test_signer(account_id.as_str()).public_key(),
+                        account_id,
+                        amount: ONE_NEAR * (10000 - i as Balance),
+                    }
+                })
+                .collect();
+            let num_chunk_producer_seats =
+                num_block_and_chunk_producer_seats + num_chunk_only_producer_seats;
+            DerivedValidatorSetup {
+                validators,
+                num_block_producer_seats: num_block_and_chunk_producer_seats,
+                num_chunk_producer_seats,
+                num_chunk_validator_seats: num_chunk_producer_seats
+                    + num_chunk_validator_only_seats,
+                num_chunk_only_producer_seats: (num_chunk_only_producer_seats > 0)
+                    .then_some(num_chunk_only_producer_seats),
             }
-            for i in 0..num_chunk_validator_only_seats as usize {
-                let account_id: AccountId = chunk_validators_only[i].parse().unwrap();
-                let account_info = AccountInfo {
-                    public_key: create_test_signer(account_id.as_str()).public_key(),
-                    account_id,
-                    amount: ONE_NEAR
-                        * (10000 - i as Balance - num_block_and_chunk_producer_seats as Balance),
-                };
-                validators.push(account_info);
+        }
+        ValidatorsSpec::DesiredRolesWithStakes {
+            block_and_chunk_producers,
+            chunk_validators_only,
+        } => {
+            let min_producer_stake =
+                block_and_chunk_producers.iter().map(|(_, stake)| *stake).min();
+            let max_validator_stake = chunk_validators_only.iter().map(|(_, stake)| *stake).max();
+            if let (Some(min_producer_stake), Some(max_validator_stake)) =
+                (min_producer_stake, max_validator_stake)
+            {
+                assert!(
+                    min_producer_stake >= max_validator_stake,
+                    "chunk validators only can't have more stake than block and chunk producers"
+                );
             }
+            let num_block_and_chunk_producer_seats = block_and_chunk_producers.len() as NumSeats;
+            let num_chunk_validator_only_seats = chunk_validators_only.len() as NumSeats;
+            let validators = block_and_chunk_producers
+                .iter()
+                .chain(chunk_validators_only.iter())
+                .map(|(account_id, stake)| {
+                    let account_id: AccountId = account_id.parse().unwrap();
+                    AccountInfo {
+                        public_key: create_test_signer(account_id.as_str()).public_key(),
+                        account_id,
+                        amount: *stake,
+                    }
+                })
+                .collect();
             DerivedValidatorSetup {
                 validators,
                 num_block_producer_seats: num_block_and_chunk_producer_seats,
                 num_chunk_producer_seats: num_block_and_chunk_producer_seats,
                 num_chunk_validator_seats: num_block_and_chunk_producer_seats
                     + num_chunk_validator_only_seats,
+                num_chunk_only_producer_seats: None,
             }
         }
         ValidatorsSpec::Raw {
@@ -614,10 +1245,53 @@ fn derive_validator_setup(specs: ValidatorsSpec) -> DerivedValidatorSetup {
             num_block_producer_seats,
             num_chunk_producer_seats,
             num_chunk_validator_seats,
+            num_chunk_only_producer_seats: None,
         },
     }
 }
 
+/// Generates `count` account ids spread evenly across the shards of the given
+/// layout: every shard gets `count / num_shards` accounts and the first
+/// `count % num_shards` shards one more. For boundary based layouts the names
+/// are derived from the lower boundary account of each shard, for the legacy
+/// hash based layout they are generated until every shard is filled up.
+pub fn synthetic_account_ids(count: usize, shard_layout: &ShardLayout) -> Vec<AccountId> {
+    let shard_ids: Vec<ShardId> = shard_layout.shard_ids().collect();
+    let num_shards = shard_ids.len();
+    let mut remaining: HashMap<ShardId, usize> = shard_ids
+        .iter()
+        .enumerate()
+        .map(|(index, shard_id)| {
+            (*shard_id, count / num_shards + usize::from(index < count % num_shards))
+        })
+        .collect();
+    // "0" sorts before any boundary account, so it prefixes names of the first shard.
+    let prefixes: Vec<String> = std::iter::once("0".to_string())
+        .chain(shard_layout.boundary_accounts().iter().map(|account_id| account_id.to_string()))
+        .collect();
+
+    let mut account_ids = Vec::with_capacity(count);
+    let max_index = 100 * count + 1000;
+    for index in 0.. {
+        if account_ids.len() == count {
+            break;
+        }
+        assert!(index < max_index, "failed to spread {count} synthetic accounts across shards");
+        for prefix in &prefixes {
+            let account_id: AccountId = format!("{prefix}-synthetic-{index}")
+                .parse()
+                .expect("boundary account too long to derive synthetic accounts from");
+            let shard_id = shard_layout.account_id_to_shard_id(&account_id);
+            let shard_remaining = remaining.get_mut(&shard_id).unwrap();
+            if *shard_remaining > 0 {
+                *shard_remaining -= 1;
+                account_ids.push(account_id);
+            }
+        }
+    }
+    account_ids
+}
+
 pub struct GenesisAndEpochConfigParams<'a> {
     pub epoch_length: BlockHeightDelta,
     pub protocol_version: ProtocolVersion,
@@ -692,10 +1366,238 @@ pub fn build_genesis_and_epoch_config_store<'a>(
     let genesis_builder = customize_genesis_builder(genesis_builder);
     let epoch_config_builder = customize_epoch_config_builder(epoch_config_builder);
 
-    let genesis = genesis_builder.build();
+    let genesis = genesis_builder.build().unwrap();
     let epoch_config = epoch_config_builder.build();
     let epoch_config_store =
         EpochConfigStore::test(BTreeMap::from([(protocol_version, Arc::new(epoch_config))]));
 
     (genesis, epoch_config_store)
 }
+
+#[cfg(test)]
+mod tests {
+    use near_primitives::state_record::StateRecord;
+    use near_primitives::types::{AccountId, Balance};
+
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::account::{AccessKey, Account};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::receipt::{Receipt, ReceiptPriority};
+    use near_primitives::version::PROTOCOL_VERSION;
+
+    use near_primitives::shard_layout::ShardLayout;
+
+    use super::{
+        synthetic_account_ids, validate_records, TestGenesisBuildError, TestGenesisBuilder,
+        ONE_NEAR,
+    };
+    use crate::{CanonicalizeRecordsError, Genesis, ShardAssignmentPreview};
+
+    fn account_balances(genesis: &Genesis, account_id: &AccountId) -> (Balance, Balance) {
+        let mut balances = None;
+        genesis.for_each_record(|record| {
+            if let StateRecord::Account { account_id: record_account_id, account } = record {
+                if record_account_id == account_id {
+                    balances = Some((account.amount(), account.locked()));
+                }
+            }
+        });
+        balances.unwrap()
+    }
+
+    #[test]
+    fn test_user_account_with_locked_balance() {
+        let account_id: AccountId = "account0".parse().unwrap();
+        let genesis = TestGenesisBuilder::new()
+            .add_user_account_with_locked_balance(account_id.clone(), 10 * ONE_NEAR, 3 * ONE_NEAR)
+            .build()
+            .unwrap();
+        assert_eq!(account_balances(&genesis, &account_id), (10 * ONE_NEAR, 3 * ONE_NEAR));
+        // The locked balance counts towards the total supply but doesn't make a validator.
+        let validator_stake: Balance =
+            genesis.config.validators.iter().map(|validator| validator.amount).sum();
+        assert_eq!(genesis.config.total_supply, validator_stake + 13 * ONE_NEAR);
+        assert!(genesis
+            .config
+            .validators
+            .iter()
+            .all(|validator| validator.account_id != account_id));
+
+        let validator_id: AccountId = "validator0".parse().unwrap();
+        let err = TestGenesisBuilder::new()
+            .add_user_account_with_locked_balance(validator_id.clone(), ONE_NEAR, ONE_NEAR)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, TestGenesisBuildError::LockedBalanceOfValidator(validator_id));
+    }
+
+    #[test]
+    fn test_duplicate_user_account() {
+        let account_id: AccountId = "account0".parse().unwrap();
+        let err = TestGenesisBuilder::new()
+            .add_user_account_simple(account_id.clone(), ONE_NEAR)
+            .add_user_account_simple(account_id.clone(), 2 * ONE_NEAR)
+            .build()
+            .unwrap_err();
+        assert_eq!(err, TestGenesisBuildError::DuplicateUserAccount(account_id));
+    }
+
+    #[test]
+    fn test_unknown_delayed_receipt_receiver() {
+        let receiver_id: AccountId = "unknown".parse().unwrap();
+        let receipt =
+            Receipt::new_balance_refund(&receiver_id, ONE_NEAR, ReceiptPriority::NoPriority);
+        let receipt_id = *receipt.receipt_id();
+        let err = TestGenesisBuilder::new()
+            .add_user_account_simple("account0".parse().unwrap(), ONE_NEAR)
+            .add_delayed_receipts([receipt])
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            err,
+            TestGenesisBuildError::UnknownDelayedReceiptReceiver { receipt_id, receiver_id }
+        );
+    }
+
+    fn account_record(
+        account_id: &AccountId,
+        amount: Balance,
+        code_hash: CryptoHash,
+    ) -> StateRecord {
+        StateRecord::Account {
+            account_id: account_id.clone(),
+            account: Account::new(amount, 0, 0, code_hash, 0, PROTOCOL_VERSION),
+        }
+    }
+
+    fn access_key_record(account_id: &AccountId, public_key: &PublicKey) -> StateRecord {
+        StateRecord::AccessKey {
+            account_id: account_id.clone(),
+            public_key: public_key.clone(),
+            access_key: AccessKey::full_access(),
+        }
+    }
+
+    #[test]
+    fn test_validate_records() {
+        let account_id: AccountId = "account0".parse().unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let code = vec![1, 2, 3];
+        let code_hash = CryptoHash::hash_bytes(&code);
+        let contract = StateRecord::Contract { account_id: account_id.clone(), code };
+
+        let valid = vec![
+            account_record(&account_id, ONE_NEAR, code_hash),
+            access_key_record(&account_id, &public_key),
+            contract.clone(),
+        ];
+        assert_eq!(validate_records(&valid, ONE_NEAR), Ok(()));
+
+        let mut records = valid.clone();
+        records.push(account_record(&account_id, ONE_NEAR, code_hash));
+        assert_eq!(
+            validate_records(&records, 2 * ONE_NEAR),
+            Err(TestGenesisBuildError::DuplicateAccountRecord(account_id.clone()))
+        );
+
+        let mut records = valid.clone();
+        records.push(access_key_record(&account_id, &public_key));
+        assert_eq!(
+            validate_records(&records, ONE_NEAR),
+            Err(TestGenesisBuildError::DuplicateAccessKeyRecord {
+                account_id: account_id.clone(),
+                public_key: public_key.clone(),
+            })
+        );
+
+        let mut records = valid.clone();
+        records.push(contract.clone());
+        assert_eq!(
+            validate_records(&records, ONE_NEAR),
+            Err(TestGenesisBuildError::DuplicateContractRecord(account_id.clone()))
+        );
+
+        let records = vec![access_key_record(&account_id, &public_key)];
+        assert_eq!(
+            validate_records(&records, 0),
+            Err(TestGenesisBuildError::AccessKeyWithoutAccount(account_id.clone()))
+        );
+
+        let records = vec![contract.clone()];
+        assert_eq!(
+            validate_records(&records, 0),
+            Err(TestGenesisBuildError::ContractWithoutAccount(account_id.clone()))
+        );
+
+        let records = vec![account_record(&account_id, ONE_NEAR, CryptoHash::default()), contract];
+        assert_eq!(
+            validate_records(&records, ONE_NEAR),
+            Err(TestGenesisBuildError::ContractCodeHashMismatch(account_id.clone()))
+        );
+
+        assert_eq!(
+            validate_records(&valid, 2 * ONE_NEAR),
+            Err(TestGenesisBuildError::TotalSupplyMismatch {
+                records: ONE_NEAR,
+                config: 2 * ONE_NEAR
+            })
+        );
+    }
+
+    #[test]
+    fn test_non_canonical_records_error() {
+        let account_id: AccountId = "account0".parse().unwrap();
+        let canonicalize_error = CanonicalizeRecordsError::ConflictingData(account_id);
+        let err = TestGenesisBuildError::from(canonicalize_error.clone());
+        assert_eq!(err.to_string(), canonicalize_error.to_string());
+        assert_eq!(err, TestGenesisBuildError::NonCanonicalRecords(canonicalize_error));
+    }
+
+    /// Asserts that the accounts are distinct and spread evenly across the shards of the layout.
+    fn assert_spread_evenly(account_ids: &[AccountId], count: usize, shard_layout: &ShardLayout) {
+        assert_eq!(account_ids.len(), count);
+        let distinct: std::collections::HashSet<_> = account_ids.iter().collect();
+        assert_eq!(distinct.len(), count);
+        let preview = ShardAssignmentPreview::new(
+            shard_layout,
+            account_ids.iter().map(|account_id| (account_id.clone(), 0)),
+        );
+        let num_accounts: Vec<usize> =
+            preview.shards.values().map(|summary| summary.num_accounts).collect();
+        let min = num_accounts.iter().min().unwrap();
+        let max = num_accounts.iter().max().unwrap();
+        assert!(max - min <= 1, "uneven spread {num_accounts:?} for layout {shard_layout:?}");
+        assert_eq!(num_accounts.iter().sum::<usize>(), count);
+    }
+
+    #[test]
+    fn test_synthetic_account_ids() {
+        let layouts = [
+            ShardLayout::single_shard(),
+            ShardLayout::simple_v1(&["account3", "account5", "account7"]),
+            ShardLayout::multi_shard(5, 3),
+            ShardLayout::v0(4, 0),
+        ];
+        for shard_layout in &layouts {
+            for count in [0, 1, 7, 20, 101] {
+                let account_ids = synthetic_account_ids(count, shard_layout);
+                assert_spread_evenly(&account_ids, count, shard_layout);
+            }
+        }
+    }
+
+    #[test]
+    fn test_add_synthetic_accounts() {
+        let shard_layout = ShardLayout::simple_v1(&["account3", "account5", "account7"]);
+        let genesis = TestGenesisBuilder::new()
+            .shard_layout(shard_layout.clone())
+            .add_synthetic_accounts(10, ONE_NEAR, &shard_layout)
+            .build()
+            .unwrap();
+        let account_ids = synthetic_account_ids(10, &shard_layout);
+        assert_spread_evenly(&account_ids, 10, &shard_layout);
+        for account_id in &account_ids {
+            assert_eq!(account_balances(&genesis, account_id), (ONE_NEAR, 0));
+        }
+    }
+}
@@ -111,10 +111,12 @@ impl Genesis {
             num_chunk_only_producer_seats: epoch_config.num_chunk_only_producer_seats,
             minimum_validators_per_shard: epoch_config.minimum_validators_per_shard,
             minimum_stake_ratio: epoch_config.minimum_stake_ratio,
+            chunk_validator_minimum_stake_ratio: epoch_config.chunk_validator_minimum_stake_ratio,
             chunk_producer_assignment_changes_limit: epoch_config
                 .chunk_producer_assignment_changes_limit,
             shuffle_shard_assignment_for_chunk_producers: epoch_config
                 .shuffle_shard_assignment_for_chunk_producers,
+            validator_blocklist: epoch_config.validator_blocklist,
 
             ..Default::default()
         };
@@ -0,0 +1,243 @@
+//! Expression selecting the shards tracked by a node, see [TrackedShardsConfig].
+
+use near_primitives::types::{AccountId, ShardId};
+use std::fmt;
+use std::str::FromStr;
+
+/// The shards a node tracks on top of the ones its validator duties require, configured by
+/// `tracked_shards_config` as one of the expressions:
+/// - `none`: no other shard;
+/// - `all`: all the shards;
+/// - `shards: [0, 2]`: the shards with the given ids, in every shard layout which has them;
+/// - `accounts: ["aurora", "relay.aurora"]`: the shards holding the given accounts;
+/// - `schedule: [[0], [1, 2]]`: the shards of the entry at the epoch height modulo the number
+///   of entries;
+/// - `validator-assignments(account)`: the shards assigned to another validator, like a
+///   shadow validator would. Without an account, the shards assigned to the validator of the
+///   node, i.e. the same as `none`;
+/// - any of the `validator-assignments` forms followed by `+next`: also the shards the
+///   validator is assigned in the next epoch, so that they are already tracked when the epoch
+///   starts.
+///
+/// It replaces the `tracked_shards`, `tracked_accounts`, `tracked_shadow_validator` and
+/// `tracked_shard_schedule` options, which can't be set together with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TrackedShardsConfig {
+    NoShards,
+    AllShards,
+    Shards(Vec<ShardId>),
+    Accounts(Vec<AccountId>),
+    Schedule(Vec<Vec<ShardId>>),
+    ValidatorAssignments { account_id: Option<AccountId>, next_epoch: bool },
+}
+
+#[derive(thiserror::Error, Debug, PartialEq, Eq)]
+pub enum TrackedShardsConfigError {
+    #[error("unknown tracked shards expression {0:?}, expected one of: none, all, shards: [..], accounts: [..], schedule: [[..], ..], validator-assignments[(account)][+next]")]
+    UnknownExpression(String),
+    #[error("invalid list in the tracked shards expression {expression:?}: {error}")]
+    InvalidList { expression: String, error: String },
+    #[error("the tracked shards expression {0:?} selects no shard, use `none` instead")]
+    Empty(String),
+}
+
+const VALIDATOR_ASSIGNMENTS: &str = "validator-assignments";
+const NEXT_EPOCH_SUFFIX: &str = "+next";
+
+fn parse_list<T: serde::de::DeserializeOwned>(
+    expression: &str,
+    list: &str,
+) -> Result<T, TrackedShardsConfigError> {
+    serde_json::from_str(list).map_err(|err| TrackedShardsConfigError::InvalidList {
+        expression: expression.to_string(),
+        error: err.to_string(),
+    })
+}
+
+/// Parses the part of a `validator-assignments` expression following the keyword.
+fn parse_validator_assignments(
+    expression: &str,
+    rest: &str,
+) -> Result<TrackedShardsConfig, TrackedShardsConfigError> {
+    let (rest, next_epoch) = match rest.strip_suffix(NEXT_EPOCH_SUFFIX) {
+        Some(rest) => (rest.trim_end(), true),
+        None => (rest, false),
+    };
+    if rest.is_empty() {
+        return Ok(TrackedShardsConfig::ValidatorAssignments { account_id: None, next_epoch });
+    }
+    let account = rest
+        .strip_prefix('(')
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(|| TrackedShardsConfigError::UnknownExpression(expression.to_string()))?;
+    let account_id = account.trim().parse::<AccountId>().map_err(|err| {
+        TrackedShardsConfigError::InvalidList {
+            expression: expression.to_string(),
+            error: err.to_string(),
+        }
+    })?;
+    Ok(TrackedShardsConfig::ValidatorAssignments { account_id: Some(account_id), next_epoch })
+}
+
+impl FromStr for TrackedShardsConfig {
+    type Err = TrackedShardsConfigError;
+
+    fn from_str(expression: &str) -> Result<Self, Self::Err> {
+        let trimmed = expression.trim();
+        let unknown = || TrackedShardsConfigError::UnknownExpression(expression.to_string());
+        let empty = || TrackedShardsConfigError::Empty(expression.to_string());
+        match trimmed {
+            "none" => return Ok(Self::NoShards),
+            "all" => return Ok(Self::AllShards),
+            _ => {}
+        }
+        if let Some(rest) = trimmed.strip_prefix(VALIDATOR_ASSIGNMENTS) {
+            return parse_validator_assignments(expression, rest);
+        }
+
+        let (kind, list) = trimmed.split_once(':').ok_or_else(unknown)?;
+        match kind.trim() {
+            "shards" => {
+                let shard_ids: Vec<ShardId> = parse_list(expression, list)?;
+                if shard_ids.is_empty() {
+                    return Err(empty());
+                }
+                Ok(Self::Shards(shard_ids))
+            }
+            "accounts" => {
+                let account_ids: Vec<AccountId> = parse_list(expression, list)?;
+                if account_ids.is_empty() {
+                    return Err(empty());
+                }
+                Ok(Self::Accounts(account_ids))
+            }
+            "schedule" => {
+                let schedule: Vec<Vec<ShardId>> = parse_list(expression, list)?;
+                if schedule.is_empty() {
+                    return Err(empty());
+                }
+                Ok(Self::Schedule(schedule))
+            }
+            _ => Err(unknown()),
+        }
+    }
+}
+
+impl fmt::Display for TrackedShardsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoShards => write!(f, "none"),
+            Self::AllShards => write!(f, "all"),
+            Self::Shards(shard_ids) => {
+                write!(f, "shards: {}", serde_json::to_string(shard_ids).unwrap())
+            }
+            Self::Accounts(account_ids) => {
+                write!(f, "accounts: {}", serde_json::to_string(account_ids).unwrap())
+            }
+            Self::Schedule(schedule) => {
+                write!(f, "schedule: {}", serde_json::to_string(schedule).unwrap())
+            }
+            Self::ValidatorAssignments { account_id, next_epoch } => {
+                write!(f, "{VALIDATOR_ASSIGNMENTS}")?;
+                if let Some(account_id) = account_id {
+                    write!(f, "({account_id})")?;
+                }
+                if *next_epoch {
+                    write!(f, "{NEXT_EPOCH_SUFFIX}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl serde::Serialize for TrackedShardsConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for TrackedShardsConfig {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let expression = String::deserialize(deserializer)?;
+        expression.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{TrackedShardsConfig, TrackedShardsConfigError};
+    use near_primitives::types::ShardId;
+
+    #[test]
+    fn test_parse_tracked_shards_config() {
+        let parse = |expression: &str| expression.parse::<TrackedShardsConfig>();
+        assert_eq!(parse(" all "), Ok(TrackedShardsConfig::AllShards));
+        assert_eq!(parse("none"), Ok(TrackedShardsConfig::NoShards));
+        assert_eq!(
+            parse("shards: [0,2]"),
+            Ok(TrackedShardsConfig::Shards(vec![ShardId::new(0), ShardId::new(2)]))
+        );
+        assert_eq!(
+            parse(r#"accounts: ["aurora", "relay.aurora"]"#),
+            Ok(TrackedShardsConfig::Accounts(vec![
+                "aurora".parse().unwrap(),
+                "relay.aurora".parse().unwrap()
+            ]))
+        );
+        assert_eq!(
+            parse("schedule: [[0], [1, 2]]"),
+            Ok(TrackedShardsConfig::Schedule(vec![
+                vec![ShardId::new(0)],
+                vec![ShardId::new(1), ShardId::new(2)]
+            ]))
+        );
+        assert_eq!(
+            parse("validator-assignments"),
+            Ok(TrackedShardsConfig::ValidatorAssignments { account_id: None, next_epoch: false })
+        );
+        assert_eq!(
+            parse("validator-assignments(test.near)+next"),
+            Ok(TrackedShardsConfig::ValidatorAssignments {
+                account_id: Some("test.near".parse().unwrap()),
+                next_epoch: true
+            })
+        );
+
+        assert_eq!(parse("shards: []"), Err(TrackedShardsConfigError::Empty("shards: []".into())));
+        assert_eq!(
+            parse("everything"),
+            Err(TrackedShardsConfigError::UnknownExpression("everything".into()))
+        );
+        assert_eq!(
+            parse("validator-assignments+prev"),
+            Err(TrackedShardsConfigError::UnknownExpression("validator-assignments+prev".into()))
+        );
+        assert!(matches!(parse("shards: [a]"), Err(TrackedShardsConfigError::InvalidList { .. })));
+        assert!(matches!(
+            parse(r#"accounts: ["Not An Account"]"#),
+            Err(TrackedShardsConfigError::InvalidList { .. })
+        ));
+    }
+
+    #[test]
+    fn test_tracked_shards_config_roundtrip() {
+        for expression in [
+            "none",
+            "all",
+            "shards: [0,2]",
+            r#"accounts: ["aurora"]"#,
+            "schedule: [[0],[1,2]]",
+            "validator-assignments",
+            "validator-assignments+next",
+            "validator-assignments(test.near)",
+            "validator-assignments(test.near)+next",
+        ] {
+            let config: TrackedShardsConfig = expression.parse().unwrap();
+            assert_eq!(config.to_string(), expression);
+            let json = serde_json::to_string(&config).unwrap();
+            assert_eq!(serde_json::from_str::<TrackedShardsConfig>(&json).unwrap(), config);
+        }
+        assert!(serde_json::from_str::<TrackedShardsConfig>(r#""shards: 1""#).is_err());
+    }
+}
@@ -9,7 +9,7 @@ use time::Duration;
 #[cfg(feature = "metrics")]
 use time::OffsetDateTime as Utc;
 
-use crate::ReshardingConfig;
+use crate::{ReshardingConfig, TrackedShardsConfig};
 
 /// A wrapper for a config value that can be updated while the node is running.
 /// When initializing sub-objects (e.g. `ShardsManager`), please make sure to
@@ -108,6 +108,10 @@ pub struct UpdateableClientConfig {
     #[serde(default)]
     #[serde(with = "near_time::serde_opt_duration_as_std")]
     pub produce_chunk_add_transactions_time_limit: Option<Duration>,
+
+    /// Expression selecting the tracked shards, applied from the epoch after the next one.
+    #[serde(default)]
+    pub tracked_shards_config: Option<TrackedShardsConfig>,
 }
 
 pub type UpdateableValidatorSigner = Option<Arc<ValidatorSigner>>;
@@ -20,6 +20,7 @@ mod log_counter;
 pub mod macros;
 pub mod metrics;
 mod opentelemetry;
+pub mod redaction;
 mod reload;
 mod subscriber;
 pub mod testonly;
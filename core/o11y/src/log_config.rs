@@ -1,3 +1,4 @@
+use crate::redaction::RedactionConfig;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::{fs::File, io::Write};
@@ -20,6 +21,11 @@ pub struct LogConfig {
     /// individual spans with something like `debug,store::trie=trace` to have specific targets be
     /// more verbose than the default.
     pub opentelemetry: Option<String>,
+    /// Sensitive values to be removed from the log output.
+    ///
+    /// Key material loaded by the node is always redacted, regardless of this setting.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redaction: Option<RedactionConfig>,
 }
 
 impl LogConfig {
@@ -0,0 +1,242 @@
+//! Redaction of sensitive values from the log output.
+//!
+//! Some operators are required to guarantee that particular values (key material, IP addresses of
+//! peers, names of allowlisted accounts, etc.) never end up in the logs shipped off the machine.
+//! Filtering by level or target is not enough for that, because such values can be interpolated
+//! into any message at any level. Instead, the log writers are wrapped in [`RedactingMakeWriter`],
+//! which replaces every occurrence of a sensitive value in a fully formatted log line right before
+//! it is written out.
+//!
+//! Sensitive values come from two sources:
+//! * Values registered by the node itself via [`add_sensitive_value`], e.g. the secret keys loaded
+//!   at startup. These can't be removed and are redacted regardless of the log config.
+//! * Values configured by the operator in the `redaction` section of `log_config.json`. These are
+//!   replaced every time the log config is reloaded.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io;
+use std::sync::{LazyLock, RwLock};
+use tracing_subscriber::fmt::MakeWriter;
+
+/// The string that replaces redacted values in the log output.
+pub const REDACTED: &str = "[REDACTED]";
+
+/// Matches IPv4 addresses and the most common IPv6 address notations.
+static IP_ADDRESS_REGEX: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"\b(?:\d{1,3}\.){3}\d{1,3}\b|\b(?:[0-9a-fA-F]{1,4}:){7}[0-9a-fA-F]{1,4}\b|\b(?:[0-9a-fA-F]{1,4}:){1,7}:(?:[0-9a-fA-F]{1,4}(?::[0-9a-fA-F]{1,4}){0,6})?",
+    )
+    .unwrap()
+});
+
+static REDACTOR: LazyLock<RwLock<Redactor>> = LazyLock::new(|| RwLock::new(Redactor::default()));
+
+/// Configures redaction of sensitive values from the log output.
+#[derive(Default, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(default)]
+pub struct RedactionConfig {
+    /// Values that must never appear in the logs, for example account ids from an allowlist.
+    pub values: Vec<String>,
+    /// Whether to replace all IPv4 and IPv6 addresses in the logs.
+    pub redact_ip_addresses: bool,
+}
+
+#[derive(Default)]
+struct Redactor {
+    /// Values registered by the node itself, never removed.
+    sensitive_values: Vec<String>,
+    /// Redaction configured in `log_config.json`.
+    config: RedactionConfig,
+}
+
+impl Redactor {
+    fn is_noop(&self) -> bool {
+        self.sensitive_values.is_empty()
+            && self.config.values.is_empty()
+            && !self.config.redact_ip_addresses
+    }
+
+    fn redact<'a>(&self, line: &'a str) -> Cow<'a, str> {
+        let mut line = Cow::Borrowed(line);
+        for value in self.sensitive_values.iter().chain(self.config.values.iter()) {
+            if !value.is_empty() && line.contains(value.as_str()) {
+                line = Cow::Owned(line.replace(value.as_str(), REDACTED));
+            }
+        }
+        if self.config.redact_ip_addresses {
+            if let Cow::Owned(redacted) = IP_ADDRESS_REGEX.replace_all(&line, REDACTED) {
+                line = Cow::Owned(redacted);
+            }
+        }
+        line
+    }
+}
+
+/// Registers a value that must never appear in the logs.
+///
+/// Meant for values such as secret keys, which are known only after the node has loaded its
+/// configuration. The registration is permanent and is not affected by log config reloads.
+pub fn add_sensitive_value(value: impl Into<String>) {
+    let value = value.into();
+    if value.is_empty() {
+        return;
+    }
+    let mut redactor = REDACTOR.write().unwrap();
+    if !redactor.sensitive_values.contains(&value) {
+        redactor.sensitive_values.push(value);
+    }
+}
+
+/// Replaces the redaction configured in `log_config.json`.
+///
+/// `None` resets the configuration. Values registered via [`add_sensitive_value`] are kept.
+pub fn set_redaction_config(config: Option<&RedactionConfig>) {
+    REDACTOR.write().unwrap().config = config.cloned().unwrap_or_default();
+}
+
+/// Replaces all sensitive values in the given string with [`REDACTED`].
+pub fn redact(line: &str) -> Cow<'_, str> {
+    REDACTOR.read().unwrap().redact(line)
+}
+
+/// A [`MakeWriter`] that redacts sensitive values from everything written by the wrapped writer.
+#[derive(Clone, Debug)]
+pub struct RedactingMakeWriter<M> {
+    inner: M,
+}
+
+impl<M> RedactingMakeWriter<M> {
+    pub fn new(inner: M) -> Self {
+        Self { inner }
+    }
+}
+
+impl<'a, M: MakeWriter<'a>> MakeWriter<'a> for RedactingMakeWriter<M> {
+    type Writer = RedactingWriter<M::Writer>;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        RedactingWriter { inner: self.inner.make_writer() }
+    }
+
+    fn make_writer_for(&'a self, meta: &tracing::Metadata<'_>) -> Self::Writer {
+        RedactingWriter { inner: self.inner.make_writer_for(meta) }
+    }
+}
+
+/// Writer produced by [`RedactingMakeWriter`].
+///
+/// The `fmt` layer writes every formatted event with a single call, so a sensitive value can't be
+/// split across two writes.
+pub struct RedactingWriter<W> {
+    inner: W,
+}
+
+impl<W: io::Write> io::Write for RedactingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let redactor = REDACTOR.read().unwrap();
+        if redactor.is_noop() {
+            drop(redactor);
+            return self.inner.write(buf);
+        }
+        let line = String::from_utf8_lossy(buf);
+        let redacted = redactor.redact(&line);
+        drop(redactor);
+        self.inner.write_all(redacted.as_bytes())?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{add_sensitive_value, RedactingMakeWriter, RedactionConfig, Redactor, REDACTED};
+    use std::sync::{Arc, Mutex};
+    use tracing_subscriber::fmt::MakeWriter;
+    use tracing_subscriber::layer::SubscriberExt;
+    use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+    const SECRET_KEY: &str = "ed25519:3D4YudUahN1nawWogh8pAKSj92sUNMdbZGjn7kERKzYoTy8tnFQuwoGUC51DowKqorvkr2pytJSnwuSbsNVfqygr";
+
+    #[derive(Clone, Default)]
+    struct CapturedOutput(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for CapturedOutput {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl<'a> MakeWriter<'a> for CapturedOutput {
+        type Writer = Self;
+
+        fn make_writer(&'a self) -> Self::Writer {
+            self.clone()
+        }
+    }
+
+    struct SignerWithDebug {
+        secret_key: &'static str,
+    }
+
+    impl std::fmt::Debug for SignerWithDebug {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            f.debug_struct("Signer").field("secret_key", &self.secret_key).finish()
+        }
+    }
+
+    #[test]
+    fn test_validator_key_is_never_logged() {
+        add_sensitive_value(SECRET_KEY);
+        let output = CapturedOutput::default();
+        let subscriber = tracing_subscriber::registry().with(
+            fmt::layer()
+                .with_ansi(false)
+                .with_writer(RedactingMakeWriter::new(output.clone()))
+                .with_filter(EnvFilter::new("trace")),
+        );
+        tracing::subscriber::with_default(subscriber, || {
+            let signer = SignerWithDebug { secret_key: SECRET_KEY };
+            let span = tracing::trace_span!("span", key = SECRET_KEY);
+            let _guard = span.enter();
+            tracing::trace!(target: "client", "loaded key {}", SECRET_KEY);
+            tracing::trace!(target: "client", secret_key = SECRET_KEY, "loaded key");
+            tracing::debug!(?signer, "signer");
+            tracing::error!(%SECRET_KEY, "error");
+        });
+        let output = String::from_utf8(output.0.lock().unwrap().clone()).unwrap();
+        assert_eq!(output.lines().count(), 4);
+        assert!(!output.contains(SECRET_KEY), "{output}");
+        assert!(!output.contains(&SECRET_KEY["ed25519:".len()..]), "{output}");
+        assert!(output.contains(REDACTED));
+    }
+
+    #[test]
+    fn test_redact_configured_values() {
+        let redactor = Redactor {
+            sensitive_values: vec![SECRET_KEY.to_string()],
+            config: RedactionConfig {
+                values: vec!["alice.near".to_string(), "".to_string()],
+                redact_ip_addresses: true,
+            },
+        };
+        assert_eq!(
+            redactor.redact(&format!("alice.near connected from 10.0.0.1:24567 with {SECRET_KEY}")),
+            "[REDACTED] connected from [REDACTED]:24567 with [REDACTED]"
+        );
+        assert_eq!(redactor.redact("peer at fe80::1:2"), "peer at [REDACTED]");
+        assert_eq!(
+            redactor.redact("bob.near produced block 1.2.3"),
+            "bob.near produced block 1.2.3"
+        );
+    }
+}
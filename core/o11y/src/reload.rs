@@ -1,4 +1,5 @@
 use crate::opentelemetry::get_opentelemetry_filter;
+use crate::redaction::{set_redaction_config, RedactingMakeWriter};
 use crate::{log_config, log_counter, BuildEnvFilterError, EnvFilterBuilder, OpenTelemetryLevel};
 use opentelemetry_sdk::trace::Tracer;
 use std::str::FromStr as _;
@@ -22,7 +23,12 @@ static DEFAULT_OTLP_LEVEL: OnceLock<OpenTelemetryLevel> = OnceLock::new();
 
 pub(crate) type LogLayer<Inner> = Layered<
     Filtered<
-        fmt::Layer<Inner, fmt::format::DefaultFields, fmt::format::Format, NonBlocking>,
+        fmt::Layer<
+            Inner,
+            fmt::format::DefaultFields,
+            fmt::format::Format,
+            RedactingMakeWriter<NonBlocking>,
+        >,
         reload::Layer<EnvFilter, Inner>,
         Inner,
     >,
@@ -31,7 +37,7 @@ pub(crate) type LogLayer<Inner> = Layered<
 
 pub(crate) type SimpleLogLayer<Inner, W> = Layered<
     Filtered<
-        fmt::Layer<Inner, fmt::format::DefaultFields, fmt::format::Format, W>,
+        fmt::Layer<Inner, fmt::format::DefaultFields, fmt::format::Format, RedactingMakeWriter<W>>,
         EnvFilter,
         Inner,
     >,
@@ -82,6 +88,7 @@ pub enum ReloadError {
 }
 
 pub fn reload_log_config(config: Option<&log_config::LogConfig>) {
+    set_redaction_config(config.and_then(|config| config.redaction.as_ref()));
     let result = if let Some(config) = config {
         reload(
             config.rust_log.as_deref(),
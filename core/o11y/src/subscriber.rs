@@ -1,4 +1,5 @@
 use crate::opentelemetry::add_opentelemetry_layer;
+use crate::redaction::RedactingMakeWriter;
 use crate::reload::{
     set_default_otlp_level, set_log_layer_handle, set_otlp_layer_handle, LogLayer, SimpleLogLayer,
 };
@@ -111,7 +112,7 @@ where
     let layer = fmt::layer()
         .with_ansi(ansi)
         .with_span_events(get_fmt_span(with_span_events))
-        .with_writer(writer)
+        .with_writer(RedactingMakeWriter::new(writer))
         .with_filter(filter);
 
     subscriber.with(layer)
@@ -140,7 +141,7 @@ where
     let layer = fmt::layer()
         .with_ansi(ansi)
         .with_span_events(get_fmt_span(with_span_events))
-        .with_writer(writer)
+        .with_writer(RedactingMakeWriter::new(writer))
         .with_filter(filter);
 
     (subscriber.with(layer), handle)
@@ -52,6 +52,8 @@ static CONFIG_DIFFS: &[(ProtocolVersion, &str)] = &[
     // Fix wasm_yield_resume_byte and relax congestion control.
     (73, include_config!("73.yaml")),
     (129, include_config!("129.yaml")),
+    // Add the `validate_contract_code` host function.
+    (151, include_config!("151.yaml")),
 ];
 
 /// Testnet parameters for versions <= 29, which (incorrectly) differed from mainnet parameters
@@ -151,6 +151,10 @@ impl ExtCostsConfig {
             ExtCosts::bls12381_p1_decompress_element => SAFETY_MULTIPLIER * 27_000_000_000,
             ExtCosts::bls12381_p2_decompress_base => SAFETY_MULTIPLIER * 500_000_000,
             ExtCosts::bls12381_p2_decompress_element => SAFETY_MULTIPLIER * 55_000_000_000,
+            // Same as sha256 and the deploy contract per byte fee, see 151.yaml.
+            ExtCosts::validate_contract_code_base => SAFETY_MULTIPLIER * 1513656750,
+            ExtCosts::validate_contract_code_byte => SAFETY_MULTIPLIER * 8039117,
+            ExtCosts::validate_contract_code_prepare_byte => SAFETY_MULTIPLIER * 2270999,
             // TODO(yield/resume): replicate fees here after estimation
             ExtCosts::yield_create_base => 300_000_000_000_000,
             ExtCosts::yield_create_byte => 300_000_000_000_000,
@@ -272,6 +276,9 @@ pub enum ExtCosts {
     bls12381_p2_decompress_element = 82,
     storage_large_read_overhead_base = 83,
     storage_large_read_overhead_byte = 84,
+    validate_contract_code_base = 85,
+    validate_contract_code_byte = 86,
+    validate_contract_code_prepare_byte = 87,
 }
 
 // Type of an action, used in fees logic.
@@ -409,6 +416,11 @@ impl ExtCosts {
             ExtCosts::bls12381_p1_decompress_element => Parameter::WasmBls12381P1DecompressElement,
             ExtCosts::bls12381_p2_decompress_base => Parameter::WasmBls12381P2DecompressBase,
             ExtCosts::bls12381_p2_decompress_element => Parameter::WasmBls12381P2DecompressElement,
+            ExtCosts::validate_contract_code_base => Parameter::WasmValidateContractCodeBase,
+            ExtCosts::validate_contract_code_byte => Parameter::WasmValidateContractCodeByte,
+            ExtCosts::validate_contract_code_prepare_byte => {
+                Parameter::WasmValidateContractCodePrepareByte
+            }
         }
     }
 }
@@ -0,0 +1,183 @@
+//! Differences between two runtime configs, e.g. the configs of two protocol versions, with the
+//! subsystems each changed parameter affects. Used to review and announce the parameter changes
+//! of protocol upgrades.
+
+use crate::{RuntimeConfig, RuntimeConfigView};
+use serde_json::Value;
+use std::collections::BTreeMap;
+
+/// Part of the protocol affected by a runtime parameter.
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    serde::Serialize,
+    serde::Deserialize,
+    strum::Display,
+)]
+#[serde(rename_all = "snake_case")]
+#[strum(serialize_all = "snake_case")]
+pub enum ParameterSubsystem {
+    /// Gas and storage fees charged for transactions, receipts and actions.
+    Fees,
+    /// Gas costs of the host functions and operations of contracts.
+    WasmCosts,
+    /// Limits on the size and gas of contracts, receipts, chunks and state witnesses.
+    Limits,
+    /// Features of the contract runtime.
+    ContractRuntime,
+    /// Rules for creating accounts.
+    AccountCreation,
+    /// Congestion control between shards.
+    CongestionControl,
+    /// Chunk state witnesses of stateless validation.
+    StateWitness,
+}
+
+/// Subsystems of the parameters whose name starts with the prefix. The first matching prefix
+/// applies, so more specific prefixes come first.
+const SUBSYSTEMS: &[(&str, &[ParameterSubsystem])] = {
+    use ParameterSubsystem::*;
+    &[
+        ("storage_amount_per_byte", &[Fees]),
+        ("transaction_costs.", &[Fees]),
+        ("wasm_config.ext_costs.", &[WasmCosts]),
+        ("wasm_config.grow_mem_cost", &[WasmCosts]),
+        ("wasm_config.regular_op_cost", &[WasmCosts]),
+        ("wasm_config.limit_config.", &[Limits]),
+        ("wasm_config.", &[ContractRuntime]),
+        ("account_creation_config.", &[AccountCreation]),
+        ("congestion_control_config.", &[CongestionControl, Limits]),
+        ("witness_config.", &[StateWitness, Limits]),
+    ]
+};
+
+/// Parameter which differs between two runtime configs.
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct ParameterChange {
+    /// Path of the parameter in the JSON format of `RuntimeConfigView`, e.g.
+    /// `wasm_config.limit_config.max_gas_burnt`.
+    pub name: String,
+    pub old_value: Value,
+    pub new_value: Value,
+    pub subsystems: Vec<ParameterSubsystem>,
+}
+
+/// Returns the parameters which differ between the two configs, ordered by name. Parameters are
+/// compared in the JSON format of `RuntimeConfigView`, so the parameters missing from the view
+/// aren't compared.
+pub fn diff_runtime_configs(old: &RuntimeConfig, new: &RuntimeConfig) -> Vec<ParameterChange> {
+    let old_params = flatten_runtime_config(old);
+    let mut new_params = flatten_runtime_config(new);
+    let mut changes = Vec::new();
+    for (name, old_value) in old_params {
+        let new_value = new_params.remove(&name).unwrap_or(Value::Null);
+        if old_value != new_value {
+            changes.push(ParameterChange {
+                subsystems: parameter_subsystems(&name),
+                name,
+                old_value,
+                new_value,
+            });
+        }
+    }
+    // Only parameters without a value in the old config are left.
+    for (name, new_value) in new_params {
+        changes.push(ParameterChange {
+            subsystems: parameter_subsystems(&name),
+            name,
+            old_value: Value::Null,
+            new_value,
+        });
+    }
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+fn parameter_subsystems(name: &str) -> Vec<ParameterSubsystem> {
+    SUBSYSTEMS
+        .iter()
+        .find(|(prefix, _)| name.starts_with(prefix))
+        .map_or_else(Vec::new, |(_, subsystems)| subsystems.to_vec())
+}
+
+/// Maps the path of each parameter to its value, e.g. `transaction_costs.burnt_gas_reward` to
+/// `[3, 10]`. Arrays are values of a single parameter.
+fn flatten_runtime_config(config: &RuntimeConfig) -> BTreeMap<String, Value> {
+    fn flatten(path: String, value: Value, params: &mut BTreeMap<String, Value>) {
+        match value {
+            Value::Object(fields) => {
+                for (field, value) in fields {
+                    let path = if path.is_empty() { field } else { format!("{path}.{field}") };
+                    flatten(path, value, params);
+                }
+            }
+            value => {
+                params.insert(path, value);
+            }
+        }
+    }
+
+    let view = RuntimeConfigView::from(config.clone());
+    let value = serde_json::to_value(view).expect("runtime config view serializes to JSON");
+    let mut params = BTreeMap::new();
+    flatten(String::new(), value, &mut params);
+    params
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff_runtime_configs, ParameterChange, ParameterSubsystem};
+    use crate::RuntimeConfig;
+    use serde_json::json;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_diff_runtime_configs() {
+        let old = RuntimeConfig::test();
+        assert_eq!(diff_runtime_configs(&old, &old), vec![]);
+
+        let mut new = old.clone();
+        let wasm_config = Arc::make_mut(&mut new.wasm_config);
+        wasm_config.regular_op_cost += 1;
+        wasm_config.limit_config.max_gas_burnt *= 2;
+        Arc::make_mut(&mut new.fees).storage_usage_config.num_bytes_account += 10;
+        new.congestion_control_config.max_congestion_incoming_gas += 1;
+
+        let changes = diff_runtime_configs(&old, &new);
+        let names = changes.iter().map(|change| change.name.as_str()).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "congestion_control_config.max_congestion_incoming_gas",
+                "transaction_costs.storage_usage_config.num_bytes_account",
+                "wasm_config.limit_config.max_gas_burnt",
+                "wasm_config.regular_op_cost",
+            ]
+        );
+        assert_eq!(
+            changes[2],
+            ParameterChange {
+                name: "wasm_config.limit_config.max_gas_burnt".to_string(),
+                old_value: json!(old.wasm_config.limit_config.max_gas_burnt),
+                new_value: json!(new.wasm_config.limit_config.max_gas_burnt),
+                subsystems: vec![ParameterSubsystem::Limits],
+            }
+        );
+        let subsystems = changes.iter().map(|change| change.subsystems.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            subsystems,
+            [
+                vec![ParameterSubsystem::CongestionControl, ParameterSubsystem::Limits],
+                vec![ParameterSubsystem::Fees],
+                vec![ParameterSubsystem::Limits],
+                vec![ParameterSubsystem::WasmCosts],
+            ]
+        );
+    }
+}
@@ -3,6 +3,7 @@
 pub mod config;
 pub mod config_store;
 pub mod cost;
+pub mod diff;
 pub mod parameter;
 pub mod parameter_table;
 pub mod view;
@@ -14,5 +15,6 @@ pub use cost::{
     transfer_exec_fee, transfer_send_fee, ActionCosts, ExtCosts, ExtCostsConfig, Fee,
     ParameterCost, RuntimeFeesConfig, StorageUsageConfig,
 };
+pub use diff::{diff_runtime_configs, ParameterChange, ParameterSubsystem};
 pub use parameter::Parameter;
 pub use view::{RuntimeConfigView, RuntimeFeesConfigView};
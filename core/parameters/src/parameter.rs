@@ -160,6 +160,9 @@ pub enum Parameter {
     WasmBls12381P1DecompressElement,
     WasmBls12381P2DecompressBase,
     WasmBls12381P2DecompressElement,
+    WasmValidateContractCodeBase,
+    WasmValidateContractCodeByte,
+    WasmValidateContractCodePrepareByte,
 
     // Smart contract limits
     MaxGasBurnt,
@@ -207,6 +210,7 @@ pub enum Parameter {
     EthImplicitAccounts,
     YieldResume,
     DiscardCustomSections,
+    ValidateContractCode,
 
     // Congestion Control
     MaxCongestionIncomingGas,
@@ -329,6 +329,8 @@ impl TryFrom<&ParameterTable> for RuntimeConfig {
                 function_call_weight: params.get(Parameter::FunctionCallWeight)?,
                 eth_implicit_accounts: params.get(Parameter::EthImplicitAccounts)?,
                 yield_resume_host_functions: params.get(Parameter::YieldResume)?,
+                validate_contract_code_host_function: params
+                    .get(Parameter::ValidateContractCode)?,
             }),
             account_creation_config: AccountCreationConfig {
                 min_allowed_top_level_account_length: params
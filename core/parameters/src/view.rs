@@ -235,6 +235,8 @@ pub struct VMConfigView {
     pub eth_implicit_accounts: bool,
     /// See [VMConfig::yield_resume_host_functions](`crate::vm::Config::yield_resume_host_functions).
     pub yield_resume_host_functions: bool,
+    /// See [VMConfig::validate_contract_code_host_function](crate::vm::Config::validate_contract_code_host_function).
+    pub validate_contract_code_host_function: bool,
 
     /// Describes limits for VM and Runtime.
     ///
@@ -262,6 +264,7 @@ impl From<crate::vm::Config> for VMConfigView {
             vm_kind: config.vm_kind,
             eth_implicit_accounts: config.eth_implicit_accounts,
             yield_resume_host_functions: config.yield_resume_host_functions,
+            validate_contract_code_host_function: config.validate_contract_code_host_function,
         }
     }
 }
@@ -285,6 +288,7 @@ impl From<VMConfigView> for crate::vm::Config {
             vm_kind: view.vm_kind,
             eth_implicit_accounts: view.eth_implicit_accounts,
             yield_resume_host_functions: view.yield_resume_host_functions,
+            validate_contract_code_host_function: view.validate_contract_code_host_function,
         }
     }
 }
@@ -487,6 +491,13 @@ pub struct ExtCostsConfigView {
     pub bls12381_p1_decompress_element: Gas,
     pub bls12381_p2_decompress_base: Gas,
     pub bls12381_p2_decompress_element: Gas,
+
+    /// Base cost of validating a candidate contract code.
+    pub validate_contract_code_base: Gas,
+    /// Cost per byte of hashing a candidate contract code.
+    pub validate_contract_code_byte: Gas,
+    /// Cost per byte of running the wasm validation on a candidate contract code.
+    pub validate_contract_code_prepare_byte: Gas,
 }
 
 impl From<crate::ExtCostsConfig> for ExtCostsConfigView {
@@ -586,6 +597,10 @@ impl From<crate::ExtCostsConfig> for ExtCostsConfigView {
             bls12381_p2_decompress_base: config.gas_cost(ExtCosts::bls12381_p2_decompress_base),
             bls12381_p2_decompress_element: config
                 .gas_cost(ExtCosts::bls12381_p2_decompress_element),
+            validate_contract_code_base: config.gas_cost(ExtCosts::validate_contract_code_base),
+            validate_contract_code_byte: config.gas_cost(ExtCosts::validate_contract_code_byte),
+            validate_contract_code_prepare_byte: config
+                .gas_cost(ExtCosts::validate_contract_code_prepare_byte),
             // removed parameters
             contract_compile_base: 0,
             contract_compile_bytes: 0,
@@ -681,6 +696,9 @@ impl From<ExtCostsConfigView> for crate::ExtCostsConfig {
                 ExtCosts::bls12381_p1_decompress_element => view.bls12381_p1_decompress_element,
                 ExtCosts::bls12381_p2_decompress_base => view.bls12381_p2_decompress_base,
                 ExtCosts::bls12381_p2_decompress_element => view.bls12381_p2_decompress_element,
+                ExtCosts::validate_contract_code_base => view.validate_contract_code_base,
+                ExtCosts::validate_contract_code_byte => view.validate_contract_code_byte,
+                ExtCosts::validate_contract_code_prepare_byte => view.validate_contract_code_prepare_byte,
         }
         .map(|_, value| ParameterCost { gas: value, compute: value });
         Self { costs }
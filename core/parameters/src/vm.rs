@@ -199,6 +199,9 @@ pub struct Config {
     /// Whether to discard custom sections.
     pub discard_custom_sections: bool,
 
+    /// Enable the `validate_contract_code` host function.
+    pub validate_contract_code_host_function: bool,
+
     /// Describes limits for VM and Runtime.
     pub limit_config: LimitConfig,
 }
@@ -224,6 +227,7 @@ impl Config {
     }
 
     pub fn enable_all_features(&mut self) {
+        self.validate_contract_code_host_function = true;
         self.yield_resume_host_functions = true;
         self.eth_implicit_accounts = true;
         self.function_call_weight = true;
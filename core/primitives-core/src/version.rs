@@ -196,6 +196,12 @@ pub enum ProtocolFeature {
     ExcludeExistingCodeFromWitnessForCodeLen,
     /// Use the block height instead of the block hash to calculate the receipt ID.
     BlockHeightForReceiptId,
+    /// Add the `validate_contract_code` host function, allowing contracts to check a candidate
+    /// code before deploying it to themselves.
+    ValidateContractCodeHostFunction,
+    /// Allow chunk producers to skip producing the chunks of quiet shards, which the blocks
+    /// mark as suppressed instead of missed, see `near_primitives::chunk_suppression`.
+    EmptyChunkSuppression,
 }
 
 impl ProtocolFeature {
@@ -285,6 +291,8 @@ impl ProtocolFeature {
             ProtocolFeature::RelaxedChunkValidation => 147,
             ProtocolFeature::ExcludeExistingCodeFromWitnessForCodeLen => 148,
             ProtocolFeature::BlockHeightForReceiptId => 149,
+            ProtocolFeature::ValidateContractCodeHostFunction => 151,
+            ProtocolFeature::EmptyChunkSuppression => 152,
             // Place features that are not yet in Nightly below this line.
         }
     }
@@ -298,7 +306,7 @@ impl ProtocolFeature {
 const STABLE_PROTOCOL_VERSION: ProtocolVersion = 74;
 
 // On nightly, pick big enough version to support all features.
-const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 149;
+const NIGHTLY_PROTOCOL_VERSION: ProtocolVersion = 152;
 
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
@@ -381,14 +381,24 @@ impl Block {
             );
             // Generate from the chunk endorsement signatures a bitmap with the same number of shards and validator assignments per shard,
             // where `Option<Signature>` is mapped to `true` and `None` is mapped to `false`.
-            Some(ChunkEndorsementsBitmap::from_endorsements(
+            let mut bitmap = ChunkEndorsementsBitmap::from_endorsements(
                 chunk_endorsements
                     .iter()
                     .map(|endorsements_for_shard| {
                         endorsements_for_shard.iter().map(|e| e.is_some()).collect_vec()
                     })
                     .collect_vec(),
-            ))
+            );
+            // The signatures of an old chunk are the signed suppression of its chunk producer,
+            // see `crate::chunk_suppression`.
+            for (shard_index, (chunk, signatures)) in
+                chunks.iter().zip(&chunk_endorsements).enumerate()
+            {
+                if chunk.height_included() != height && !signatures.is_empty() {
+                    bitmap.mark_chunk_suppressed(shard_index);
+                }
+            }
+            Some(bitmap)
         } else {
             None
         };
@@ -0,0 +1,274 @@
+//! Suppression of the chunks of quiet shards. Once `ProtocolFeature::EmptyChunkSuppression` is
+//! enabled, the chunk producer of a shard without transactions or receipts may skip producing
+//! its chunk, which saves the bandwidth and storage of empty chunks and of their state witnesses
+//! and endorsements. The block marks the skipped chunk as suppressed in the chunk endorsements
+//! bitmap of its header, and a suppressed chunk isn't expected from its chunk producer nor
+//! endorsed by its chunk validators.
+//!
+//! The chunk producer signals the suppression with a `ChunkSuppression` signed by its validator
+//! key and sent to the block producer, which includes the signature in the block in place of the
+//! chunk endorsements of the shard. The validators of the block check the signature against the
+//! chunk producer of the shard, so a chunk which is only missed, because the chunk producer is
+//! offline or didn't opt in with `suppress_empty_chunks`, is never marked as suppressed.
+//!
+//! A chunk may only be suppressed depending on the last chunk of the shard included in the block,
+//! so that all the nodes validate the mark the same way:
+//! - the shard is quiet: the last chunk has no transactions, the chunk before it used no gas,
+//!   and the shard has no delayed or buffered receipts;
+//! - the last chunk was included at most `MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA` heights before,
+//!   so a shard gets a new chunk at least that often and the receipts sent to it in the
+//!   meantime aren't delayed for longer. Missing chunks after that are missed as usual.
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_crypto::{PublicKey, Signature};
+use near_schema_checker_lib::ProtocolSchema;
+
+use crate::hash::CryptoHash;
+use crate::sharding::ShardChunkHeader;
+use crate::stateless_validation::ChunkProductionKey;
+use crate::types::{BlockHeight, BlockHeightDelta, EpochId, ShardId};
+use crate::validator_signer::ValidatorSigner;
+use crate::version::{ProtocolFeature, ProtocolVersion};
+
+/// Maximum number of heights since the last chunk of a shard was included for which the chunk
+/// of the shard may be suppressed.
+pub const MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA: BlockHeightDelta = 4;
+
+/// Whether the chunk of the shard at `height` counts as suppressed if the block at `height`
+/// includes `last_chunk` as the last chunk of the shard instead of a new chunk.
+pub fn is_chunk_suppressed(
+    protocol_version: ProtocolVersion,
+    last_chunk: &ShardChunkHeader,
+    height: BlockHeight,
+) -> bool {
+    if !ProtocolFeature::EmptyChunkSuppression.enabled(protocol_version)
+        || last_chunk.is_new_chunk(height)
+    {
+        return false;
+    }
+    let Some(congestion_info) = last_chunk.congestion_info() else {
+        return false;
+    };
+    height.saturating_sub(last_chunk.height_included()) <= MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA
+        && last_chunk.tx_root() == CryptoHash::default()
+        && last_chunk.prev_gas_used() == 0
+        && congestion_info.delayed_receipts_gas() == 0
+        && congestion_info.buffered_receipts_gas() == 0
+}
+
+/// Statement of the chunk producer of a quiet shard that it suppresses the chunk it would produce
+/// on top of `prev_block_hash`.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct ChunkSuppression {
+    inner: ChunkSuppressionInner,
+    /// Signature of `inner` by the chunk producer, included in the block.
+    signature: Signature,
+}
+
+/// The part of the chunk suppression that is signed.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct ChunkSuppressionInner {
+    epoch_id: EpochId,
+    prev_block_hash: CryptoHash,
+    height_created: BlockHeight,
+    shard_id: ShardId,
+    signature_differentiator: String,
+}
+
+impl ChunkSuppressionInner {
+    fn new(
+        epoch_id: EpochId,
+        prev_block_hash: CryptoHash,
+        height_created: BlockHeight,
+        shard_id: ShardId,
+    ) -> Self {
+        Self {
+            epoch_id,
+            prev_block_hash,
+            height_created,
+            shard_id,
+            signature_differentiator: "ChunkSuppression".to_owned(),
+        }
+    }
+}
+
+impl ChunkSuppression {
+    pub fn new(
+        epoch_id: EpochId,
+        prev_block_hash: CryptoHash,
+        height_created: BlockHeight,
+        shard_id: ShardId,
+        signer: &ValidatorSigner,
+    ) -> Self {
+        let inner = ChunkSuppressionInner::new(epoch_id, prev_block_hash, height_created, shard_id);
+        let signature = signer.sign_bytes(&borsh::to_vec(&inner).unwrap());
+        Self { inner, signature }
+    }
+
+    pub fn chunk_production_key(&self) -> ChunkProductionKey {
+        ChunkProductionKey {
+            epoch_id: self.inner.epoch_id,
+            height_created: self.inner.height_created,
+            shard_id: self.inner.shard_id,
+        }
+    }
+
+    pub fn prev_block_hash(&self) -> &CryptoHash {
+        &self.inner.prev_block_hash
+    }
+
+    pub fn signature(&self) -> &Signature {
+        &self.signature
+    }
+
+    pub fn verify(&self, public_key: &PublicKey) -> bool {
+        self.signature.verify(&borsh::to_vec(&self.inner).unwrap(), public_key)
+    }
+
+    /// Checks the signature of a chunk suppression included in a block.
+    pub fn validate_signature(
+        key: &ChunkProductionKey,
+        prev_block_hash: CryptoHash,
+        signature: &Signature,
+        public_key: &PublicKey,
+    ) -> bool {
+        let inner = ChunkSuppressionInner::new(
+            key.epoch_id,
+            prev_block_hash,
+            key.height_created,
+            key.shard_id,
+        );
+        signature.verify(&borsh::to_vec(&inner).unwrap(), public_key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_chunk_suppressed, ChunkSuppression, MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA};
+    use crate::bandwidth_scheduler::BandwidthRequests;
+    use crate::congestion_info::CongestionInfo;
+    use crate::hash::hash;
+    use crate::sharding::{ShardChunkHeader, ShardChunkHeaderV3};
+    use crate::test_utils::create_test_signer;
+    use crate::types::EpochId;
+    use crate::types::{BlockHeight, Gas, ShardId};
+    use crate::validator_signer::EmptyValidatorSigner;
+    use crate::version::{ProtocolFeature, PROTOCOL_VERSION};
+
+    fn chunk_header(
+        height_included: BlockHeight,
+        has_transactions: bool,
+        prev_gas_used: Gas,
+        delayed_receipts_gas: Gas,
+    ) -> ShardChunkHeader {
+        let mut congestion_info = CongestionInfo::default();
+        congestion_info.add_delayed_receipt_gas(delayed_receipts_gas).unwrap();
+        let tx_root = if has_transactions { hash(b"transactions") } else { Default::default() };
+        let mut header = ShardChunkHeader::V3(ShardChunkHeaderV3::new(
+            PROTOCOL_VERSION,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            height_included,
+            ShardId::new(0),
+            prev_gas_used,
+            Default::default(),
+            Default::default(),
+            Default::default(),
+            tx_root,
+            Default::default(),
+            Some(congestion_info),
+            BandwidthRequests::default_for_protocol_version(PROTOCOL_VERSION),
+            &EmptyValidatorSigner::default().into(),
+        ));
+        *header.height_included_mut() = height_included;
+        header
+    }
+
+    #[test]
+    fn test_is_chunk_suppressed() {
+        if !ProtocolFeature::EmptyChunkSuppression.enabled(PROTOCOL_VERSION) {
+            return;
+        }
+        let height = 100;
+        let quiet_chunk = chunk_header(height - 1, false, 0, 0);
+        assert!(is_chunk_suppressed(PROTOCOL_VERSION, &quiet_chunk, height));
+        assert!(!is_chunk_suppressed(
+            ProtocolFeature::EmptyChunkSuppression.protocol_version() - 1,
+            &quiet_chunk,
+            height
+        ));
+        // A new chunk isn't suppressed.
+        assert!(!is_chunk_suppressed(PROTOCOL_VERSION, &chunk_header(height, false, 0, 0), height));
+
+        let last_height = height - MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA;
+        assert!(is_chunk_suppressed(
+            PROTOCOL_VERSION,
+            &chunk_header(last_height, false, 0, 0),
+            height
+        ));
+        assert!(!is_chunk_suppressed(
+            PROTOCOL_VERSION,
+            &chunk_header(last_height - 1, false, 0, 0),
+            height
+        ));
+
+        assert!(!is_chunk_suppressed(
+            PROTOCOL_VERSION,
+            &chunk_header(height - 1, true, 0, 0),
+            height
+        ));
+        assert!(!is_chunk_suppressed(
+            PROTOCOL_VERSION,
+            &chunk_header(height - 1, false, 1, 0),
+            height
+        ));
+        assert!(!is_chunk_suppressed(
+            PROTOCOL_VERSION,
+            &chunk_header(height - 1, false, 0, 1),
+            height
+        ));
+    }
+
+    #[test]
+    fn test_chunk_suppression_signature() {
+        let signer = create_test_signer("test0");
+        let other_signer = create_test_signer("test1");
+        let prev_block_hash = hash(b"prev block");
+        let suppression = ChunkSuppression::new(
+            EpochId::default(),
+            prev_block_hash,
+            10,
+            ShardId::new(0),
+            &signer,
+        );
+        assert!(suppression.verify(&signer.public_key()));
+        assert!(!suppression.verify(&other_signer.public_key()));
+
+        let key = suppression.chunk_production_key();
+        let signature = suppression.signature();
+        assert!(ChunkSuppression::validate_signature(
+            &key,
+            prev_block_hash,
+            signature,
+            &signer.public_key()
+        ));
+        // The signature is bound to the block the chunk would be built on and to its shard.
+        assert!(!ChunkSuppression::validate_signature(
+            &key,
+            hash(b"other block"),
+            signature,
+            &signer.public_key()
+        ));
+        let mut other_key = key.clone();
+        other_key.shard_id = ShardId::new(1);
+        assert!(!ChunkSuppression::validate_signature(
+            &other_key,
+            prev_block_hash,
+            signature,
+            &signer.public_key()
+        ));
+    }
+}
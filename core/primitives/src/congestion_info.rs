@@ -1,6 +1,6 @@
 use std::collections::BTreeMap;
 
-use crate::errors::RuntimeError;
+use crate::errors::{InvalidTxError, RuntimeError};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_parameters::config::CongestionControlConfig;
 use near_primitives_core::types::{Gas, ShardId};
@@ -122,6 +122,16 @@ impl CongestionControl {
     /// If the shard doesn't accept new transaction, provide the reason for
     /// extra debugging information.
     pub fn shard_accepts_transactions(&self) -> ShardAcceptsTransactions {
+        self.shard_accepts_transactions_below(self.config.reject_tx_congestion_threshold)
+    }
+
+    /// Like [`Self::shard_accepts_transactions`], but with the given congestion
+    /// threshold instead of the one of the protocol. Nodes use it to turn away
+    /// transactions earlier than the chunk producers would.
+    pub fn shard_accepts_transactions_below(
+        &self,
+        reject_tx_congestion_threshold: f64,
+    ) -> ShardAcceptsTransactions {
         let incoming_congestion = self.incoming_congestion();
         let outgoing_congestion = self.outgoing_congestion();
         let memory_congestion = self.memory_congestion();
@@ -135,7 +145,7 @@ impl CongestionControl {
         // Convert to NotNan here, if not possible, the max above is already meaningless.
         let congestion_level =
             NotNan::new(congestion_level).unwrap_or_else(|_| NotNan::new(1.0).unwrap());
-        if *congestion_level < self.config.reject_tx_congestion_threshold {
+        if *congestion_level < reject_tx_congestion_threshold {
             return ShardAcceptsTransactions::Yes;
         }
 
@@ -166,6 +176,22 @@ pub enum RejectTransactionReason {
     MissedChunks { missed_chunks: u64 },
 }
 
+impl RejectTransactionReason {
+    /// The error returned for a transaction rejected by the given shard.
+    pub fn into_invalid_tx_error(self, shard_id: u32) -> InvalidTxError {
+        match self {
+            RejectTransactionReason::IncomingCongestion { congestion_level }
+            | RejectTransactionReason::OutgoingCongestion { congestion_level }
+            | RejectTransactionReason::MemoryCongestion { congestion_level } => {
+                InvalidTxError::ShardCongested { shard_id, congestion_level }
+            }
+            RejectTransactionReason::MissedChunks { missed_chunks } => {
+                InvalidTxError::ShardStuck { shard_id, missed_chunks }
+            }
+        }
+    }
+}
+
 /// Stores the congestion level of a shard.
 ///
 /// The CongestionInfo is a part of the ChunkHeader. It is versioned and each
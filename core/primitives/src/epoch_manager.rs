@@ -3,7 +3,7 @@ use crate::shard_layout::ShardLayout;
 use crate::types::validator_stake::ValidatorStake;
 use crate::types::{
     AccountId, Balance, BlockChunkValidatorStats, BlockHeightDelta, NumSeats, ProtocolVersion,
-    ValidatorKickoutReason,
+    ShardId, ValidatorKickoutReason,
 };
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives_core::checked_feature;
@@ -11,7 +11,7 @@ use near_primitives_core::hash::CryptoHash;
 use near_primitives_core::serialize::dec_format;
 use near_primitives_core::version::{ProtocolFeature, PROTOCOL_VERSION};
 use near_schema_checker_lib::ProtocolSchema;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::fs;
 use std::ops::Bound;
 use std::path::PathBuf;
@@ -66,8 +66,16 @@ pub struct EpochConfig {
     pub num_chunk_only_producer_seats: NumSeats,
     // #[default(1)]
     pub minimum_validators_per_shard: NumSeats,
+    /// Overrides `minimum_validators_per_shard` for individual shards.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub minimum_validators_per_shard_overrides: BTreeMap<ShardId, NumSeats>,
     // #[default(Rational32::new(160, 1_000_000))]
     pub minimum_stake_ratio: Rational32,
+    /// Overrides `minimum_stake_ratio` for the selection of chunk validators, so that the
+    /// chunk-validator-only seats can have a lower entry stake than the block and chunk
+    /// producer seats, which keep `minimum_stake_ratio`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_validator_minimum_stake_ratio: Option<Rational32>,
     // #[default(5)]
     /// Limits the number of shard changes in chunk producer assignments,
     /// if algorithm is able to choose assignment with better balance of
@@ -75,6 +83,12 @@ pub struct EpochConfig {
     pub chunk_producer_assignment_changes_limit: NumSeats,
     // #[default(false)]
     pub shuffle_shard_assignment_for_chunk_producers: bool,
+    /// Accounts excluded from the validator selection regardless of their stake, for the
+    /// emergency suspension of validators on private chains. Their proposals are dropped and
+    /// their stake is returned as if they weren't selected. All the nodes of the chain must use
+    /// the same list, which can only be set in the epoch configs of non-mainnet chains.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    pub validator_blocklist: BTreeSet<AccountId>,
 }
 
 impl EpochConfig {
@@ -84,6 +98,20 @@ impl EpochConfig {
             .max(self.num_chunk_producer_seats)
             .max(self.num_chunk_validator_seats)
     }
+
+    /// Minimum number of chunk producers for each shard, in the order of
+    /// shard indices.
+    pub fn minimum_validators_per_shard_by_index(&self) -> Vec<NumSeats> {
+        self.shard_layout
+            .shard_ids()
+            .map(|shard_id| {
+                self.minimum_validators_per_shard_overrides
+                    .get(&shard_id)
+                    .copied()
+                    .unwrap_or(self.minimum_validators_per_shard)
+            })
+            .collect()
+    }
 }
 
 impl EpochConfig {
@@ -122,9 +150,12 @@ impl EpochConfig {
             num_chunk_validator_seats: 300,
             num_chunk_only_producer_seats: 300,
             minimum_validators_per_shard: 1,
+            minimum_validators_per_shard_overrides: BTreeMap::new(),
             minimum_stake_ratio: Rational32::new(160i32, 1_000_000i32),
+            chunk_validator_minimum_stake_ratio: None,
             chunk_producer_assignment_changes_limit: 5,
             shuffle_shard_assignment_for_chunk_producers: false,
+            validator_blocklist: BTreeSet::new(),
         }
     }
 
@@ -150,9 +181,12 @@ impl EpochConfig {
             num_chunk_validator_seats: 300,
             num_chunk_only_producer_seats: 300,
             minimum_validators_per_shard: 1,
+            minimum_validators_per_shard_overrides: BTreeMap::new(),
             minimum_stake_ratio: Rational32::new(160i32, 1_000_000i32),
+            chunk_validator_minimum_stake_ratio: None,
             chunk_producer_assignment_changes_limit: 5,
             shuffle_shard_assignment_for_chunk_producers: false,
+            validator_blocklist: BTreeSet::new(),
         }
     }
 
@@ -177,9 +211,12 @@ impl EpochConfig {
             num_chunk_validator_seats: 300,
             num_chunk_only_producer_seats: 300,
             minimum_validators_per_shard: 1,
+            minimum_validators_per_shard_overrides: BTreeMap::new(),
             minimum_stake_ratio: Rational32::new(160i32, 1_000_000i32),
+            chunk_validator_minimum_stake_ratio: None,
             chunk_producer_assignment_changes_limit: 5,
             shuffle_shard_assignment_for_chunk_producers: false,
+            validator_blocklist: BTreeSet::new(),
         }
     }
 }
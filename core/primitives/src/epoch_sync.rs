@@ -80,6 +80,7 @@ impl
         EPOCH_SYNC_COMPRESSION_LEVEL,
     > for CompressedEpochSyncProof
 {
+    const NAME: &'static str = "epoch_sync_proof";
 }
 
 impl Debug for CompressedEpochSyncProof {
@@ -14,6 +14,7 @@ pub mod block;
 pub mod block_body;
 pub mod block_header;
 pub mod challenge;
+pub mod chunk_suppression;
 pub mod congestion_info;
 pub mod epoch_block_info;
 pub mod epoch_info;
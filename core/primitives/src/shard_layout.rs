@@ -838,6 +838,8 @@ fn validate_and_derive_shard_parent_map_v2(
     let mut shards_parent_map = ShardsParentMapV2::new();
     for (&parent_shard_id, child_shard_ids) in shards_split_map.iter() {
         for &child_shard_id in child_shard_ids {
+            // Merging shards is not supported, the chunk validators can't
+            // validate the state transition of a shard with two parents.
             let prev = shards_parent_map.insert(child_shard_id, parent_shard_id);
             assert!(prev.is_none(), "no shard should appear in the map twice");
         }
@@ -1608,6 +1610,19 @@ mod tests {
         );
     }
 
+    #[test]
+    #[should_panic]
+    fn test_shard_layout_merge_rejected() {
+        let s0 = ShardId::new(0);
+        let s1 = ShardId::new(1);
+        let s2 = ShardId::new(2);
+        let s3 = ShardId::new(3);
+
+        // [0, 1, 2] -> [0, 3] where 1 and 2 would be merged into 3.
+        let shards_split_map = BTreeMap::from([(s0, vec![s0]), (s1, vec![s3]), (s2, vec![s3])]);
+        ShardLayout::v2(vec!["ff".parse().unwrap()], vec![s0, s3], Some(shards_split_map));
+    }
+
     // Check that the ShardLayout::multi_shard method returns interesting shard
     // layouts. A shard layout is interesting if it has non-contiguous shard
     // ids.
@@ -1,9 +1,11 @@
+use crate::challenge::PartialState;
 use crate::hash::CryptoHash;
 use crate::merkle::MerklePath;
 use crate::sharding::{
     ReceiptProof, ShardChunk, ShardChunkHeader, ShardChunkHeaderV1, ShardChunkV1,
 };
 use crate::types::{BlockHeight, EpochId, ShardId, StateRoot, StateRootNode};
+use crate::utils::compression::{CompressedData, CompressionCodec};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_primitives_core::types::EpochHeight;
 use near_schema_checker_lib::ProtocolSchema;
@@ -15,6 +17,40 @@ pub struct ReceiptProofResponse(pub CryptoHash, pub Arc<Vec<ReceiptProof>>);
 #[derive(PartialEq, Eq, Clone, Debug, BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub struct RootProof(pub CryptoHash, pub MerklePath);
 
+/// Proof for the outgoing receipts roots of all the chunks of a single block.
+///
+/// Instead of a separate merkle path per chunk (see `RootProof`), it contains all the
+/// leaves of the merkle tree, i.e. `prev_outgoing_receipts_root` of every chunk of the
+/// block in the order of shard indices. The verifier recomputes the root once and compares
+/// it with the block's `prev_chunk_outgoing_receipts_root`.
+#[derive(PartialEq, Eq, Clone, Debug, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct BatchedRootProof {
+    pub outgoing_receipts_roots: Vec<CryptoHash>,
+}
+
+/// Proofs for the outgoing receipts roots contained in a state sync header.
+#[derive(Clone, Copy, Debug)]
+pub enum RootProofs<'a> {
+    /// A merkle path for every incoming receipt proof.
+    PerChunk(&'a [Vec<RootProof>]),
+    /// A single batched proof for every block.
+    Batched(&'a [BatchedRootProof]),
+}
+
+impl RootProofs<'_> {
+    /// Number of blocks covered by the proofs.
+    pub fn len(&self) -> usize {
+        match self {
+            Self::PerChunk(proofs) => proofs.len(),
+            Self::Batched(proofs) => proofs.len(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub struct StateHeaderKey(pub ShardId, pub CryptoHash);
 
@@ -74,6 +110,24 @@ pub struct ShardStateSyncResponseHeaderV2 {
     pub state_root_node: StateRootNode,
 }
 
+/// Same as `ShardStateSyncResponseHeaderV2`, except that the proofs of the outgoing
+/// receipts roots are batched per block, which makes the header smaller and cheaper
+/// to validate when there are many shards.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct ShardStateSyncResponseHeaderV3 {
+    pub chunk: ShardChunk,
+    pub chunk_proof: MerklePath,
+    pub prev_chunk_header: Option<ShardChunkHeader>,
+    pub prev_chunk_proof: Option<MerklePath>,
+    pub incoming_receipts_proofs: Vec<ReceiptProofResponse>,
+    /// The length of this field is the same as the length of Self::incoming_receipts_proofs.
+    /// root_proofs[i] contains the outgoing receipts roots of all the chunks included in the
+    /// block with hash incoming_receipts_proofs[i].0, verifiable against that block's
+    /// prev_chunk_outgoing_receipts_root field.
+    pub root_proofs: Vec<BatchedRootProof>,
+    pub state_root_node: StateRootNode,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub enum CachedParts {
     AllParts,
@@ -102,6 +156,7 @@ impl BitArray {
 pub enum ShardStateSyncResponseHeader {
     V1(ShardStateSyncResponseHeaderV1),
     V2(ShardStateSyncResponseHeaderV2),
+    V3(ShardStateSyncResponseHeaderV3),
 }
 
 impl ShardStateSyncResponseHeader {
@@ -110,6 +165,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => ShardChunk::V1(header.chunk),
             Self::V2(header) => header.chunk,
+            Self::V3(header) => header.chunk,
         }
     }
 
@@ -118,6 +174,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => ShardChunk::V1(header.chunk.clone()),
             Self::V2(header) => header.chunk.clone(),
+            Self::V3(header) => header.chunk.clone(),
         }
     }
 
@@ -126,6 +183,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => header.prev_chunk_header.clone().map(ShardChunkHeader::V1),
             Self::V2(header) => header.prev_chunk_header.clone(),
+            Self::V3(header) => header.prev_chunk_header.clone(),
         }
     }
 
@@ -134,6 +192,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => header.chunk.header.height_included,
             Self::V2(header) => header.chunk.height_included(),
+            Self::V3(header) => header.chunk.height_included(),
         }
     }
 
@@ -142,6 +201,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => header.chunk.header.inner.prev_state_root,
             Self::V2(header) => header.chunk.prev_state_root(),
+            Self::V3(header) => header.chunk.prev_state_root(),
         }
     }
 
@@ -150,6 +210,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => &header.chunk_proof,
             Self::V2(header) => &header.chunk_proof,
+            Self::V3(header) => &header.chunk_proof,
         }
     }
 
@@ -158,6 +219,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => &header.prev_chunk_proof,
             Self::V2(header) => &header.prev_chunk_proof,
+            Self::V3(header) => &header.prev_chunk_proof,
         }
     }
 
@@ -166,14 +228,16 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => &header.incoming_receipts_proofs,
             Self::V2(header) => &header.incoming_receipts_proofs,
+            Self::V3(header) => &header.incoming_receipts_proofs,
         }
     }
 
     #[inline]
-    pub fn root_proofs(&self) -> &[Vec<RootProof>] {
+    pub fn root_proofs(&self) -> RootProofs<'_> {
         match self {
-            Self::V1(header) => &header.root_proofs,
-            Self::V2(header) => &header.root_proofs,
+            Self::V1(header) => RootProofs::PerChunk(&header.root_proofs),
+            Self::V2(header) => RootProofs::PerChunk(&header.root_proofs),
+            Self::V3(header) => RootProofs::Batched(&header.root_proofs),
         }
     }
 
@@ -182,6 +246,7 @@ impl ShardStateSyncResponseHeader {
         match self {
             Self::V1(header) => &header.state_root_node,
             Self::V2(header) => &header.state_root_node,
+            Self::V3(header) => &header.state_root_node,
         }
     }
 
@@ -211,11 +276,19 @@ pub struct ShardStateSyncResponseV3 {
     pub can_generate: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
+pub struct ShardStateSyncResponseV4 {
+    pub header: Option<ShardStateSyncResponseHeaderV3>,
+    pub part: Option<(u64, Vec<u8>)>,
+    pub can_generate: bool,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
 pub enum ShardStateSyncResponse {
     V1(ShardStateSyncResponseV1),
     V2(ShardStateSyncResponseV2),
     V3(ShardStateSyncResponseV3),
+    V4(ShardStateSyncResponseV4),
 }
 
 impl ShardStateSyncResponse {
@@ -224,6 +297,7 @@ impl ShardStateSyncResponse {
             Self::V1(response) => response.part_id(),
             Self::V2(response) => response.part.as_ref().map(|(part_id, _)| *part_id),
             Self::V3(response) => response.part.as_ref().map(|(part_id, _)| *part_id),
+            Self::V4(response) => response.part.as_ref().map(|(part_id, _)| *part_id),
         }
     }
 
@@ -232,6 +306,7 @@ impl ShardStateSyncResponse {
             Self::V1(response) => response.header.map(ShardStateSyncResponseHeader::V1),
             Self::V2(response) => response.header.map(ShardStateSyncResponseHeader::V2),
             Self::V3(response) => response.header.map(ShardStateSyncResponseHeader::V2),
+            Self::V4(response) => response.header.map(ShardStateSyncResponseHeader::V3),
         }
     }
 
@@ -240,6 +315,7 @@ impl ShardStateSyncResponse {
             Self::V1(response) => &response.part,
             Self::V2(response) => &response.part,
             Self::V3(response) => &response.part,
+            Self::V4(response) => &response.part,
         }
     }
 
@@ -248,6 +324,7 @@ impl ShardStateSyncResponse {
             Self::V1(response) => response.part,
             Self::V2(response) => response.part,
             Self::V3(response) => response.part,
+            Self::V4(response) => response.part,
         }
     }
 
@@ -256,6 +333,7 @@ impl ShardStateSyncResponse {
             Self::V1(_response) => false,
             Self::V2(_response) => false,
             Self::V3(response) => response.can_generate,
+            Self::V4(response) => response.can_generate,
         }
     }
 
@@ -264,6 +342,7 @@ impl ShardStateSyncResponse {
             Self::V1(_response) => &None,
             Self::V2(_response) => &None,
             Self::V3(response) => &response.cached_parts,
+            Self::V4(_response) => &None,
         }
     }
 }
@@ -280,6 +359,63 @@ pub fn get_num_state_parts(memory_usage: u64) -> u64 {
     (memory_usage + STATE_PART_MEMORY_LIMIT.as_u64() - 1) / STATE_PART_MEMORY_LIMIT.as_u64()
 }
 
+/// Represents max allowed size of the raw (not compressed) state part, corresponds to the size
+/// of the borsh-serialized PartialState.
+pub const MAX_UNCOMPRESSED_STATE_PART_SIZE: u64 = bytesize::ByteSize::mib(256).0;
+pub const STATE_PART_COMPRESSION_LEVEL: i32 = 3;
+
+/// A compressed state part, as dumped to the external storage by the nodes configured to
+/// compress them.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::From, derive_more::AsRef)]
+pub struct CompressedStatePart(Box<[u8]>);
+
+impl CompressedData<PartialState, MAX_UNCOMPRESSED_STATE_PART_SIZE, STATE_PART_COMPRESSION_LEVEL>
+    for CompressedStatePart
+{
+    const NAME: &'static str = "state_part";
+}
+
+/// Deserializes a state part, whether it's compressed or not, see [CompressionCodec].
+pub fn decode_state_part(data: &[u8]) -> std::io::Result<PartialState> {
+    match CompressionCodec::detect(data) {
+        Some(_) => {
+            CompressedStatePart::from_boxed_slice(data.into()).decode().map(|(part, _size)| part)
+        }
+        None => PartialState::try_from_slice(data),
+    }
+}
+
+/// Represents max allowed size of the raw (not compressed) state sync header, corresponds to the
+/// size of the borsh-serialized ShardStateSyncResponseHeader.
+pub const MAX_UNCOMPRESSED_STATE_HEADER_SIZE: u64 = bytesize::ByteSize::mib(256).0;
+pub const STATE_HEADER_COMPRESSION_LEVEL: i32 = 3;
+
+/// A compressed state sync header, as dumped to the external storage by the nodes configured to
+/// compress the state parts. The header carries the whole chunk of the shard and its incoming
+/// receipts, so it's as large as the chunk.
+#[derive(Debug, Clone, PartialEq, Eq, derive_more::From, derive_more::AsRef)]
+pub struct CompressedStateHeader(Box<[u8]>);
+
+impl
+    CompressedData<
+        ShardStateSyncResponseHeader,
+        MAX_UNCOMPRESSED_STATE_HEADER_SIZE,
+        STATE_HEADER_COMPRESSION_LEVEL,
+    > for CompressedStateHeader
+{
+    const NAME: &'static str = "state_header";
+}
+
+/// Deserializes a state sync header, whether it's compressed or not, see [CompressionCodec].
+pub fn decode_state_header(data: &[u8]) -> std::io::Result<ShardStateSyncResponseHeader> {
+    match CompressionCodec::detect(data) {
+        Some(_) => CompressedStateHeader::from_boxed_slice(data.into())
+            .decode()
+            .map(|(header, _size)| header),
+        None => ShardStateSyncResponseHeader::try_from_slice(data),
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Debug, Clone, serde::Serialize, ProtocolSchema)]
 /// Represents the progress of dumps state of a shard.
 pub enum StateSyncDumpProgress {
@@ -306,7 +442,25 @@ pub enum StateSyncDumpProgress {
 
 #[cfg(test)]
 mod tests {
-    use crate::state_sync::{get_num_state_parts, STATE_PART_MEMORY_LIMIT};
+    use crate::challenge::PartialState;
+    use crate::hash::CryptoHash;
+    use crate::merkle::{merklize, verify_path};
+    use crate::state_sync::{
+        decode_state_part, get_num_state_parts, BatchedRootProof, CompressedStatePart, RootProof,
+        STATE_PART_MEMORY_LIMIT,
+    };
+    use crate::utils::compression::CompressedData;
+
+    #[test]
+    fn test_decode_state_part() {
+        let part = PartialState::TrieValues(vec![vec![1, 2, 3].into(), vec![42; 1000].into()]);
+        let raw = borsh::to_vec(&part).unwrap();
+        let (compressed, _) = CompressedStatePart::encode(&part).unwrap();
+        assert!(compressed.size_bytes() < raw.len());
+        assert_eq!(decode_state_part(&raw).unwrap(), part);
+        assert_eq!(decode_state_part(compressed.as_slice()).unwrap(), part);
+        assert!(decode_state_part(&raw[..raw.len() - 1]).is_err());
+    }
 
     #[test]
     fn test_get_num_state_parts() {
@@ -317,4 +471,20 @@ mod tests {
         assert_eq!(get_num_state_parts(STATE_PART_MEMORY_LIMIT.as_u64() * 100), 100);
         assert_eq!(get_num_state_parts(STATE_PART_MEMORY_LIMIT.as_u64() * 100 + 1), 101);
     }
+
+    #[test]
+    fn test_batched_root_proof_is_smaller() {
+        let roots: Vec<CryptoHash> = (0..16u64).map(CryptoHash::hash_borsh).collect();
+        let (block_root, paths) = merklize(&roots);
+        let per_chunk: Vec<RootProof> =
+            roots.iter().zip(paths).map(|(root, path)| RootProof(*root, path)).collect();
+        for RootProof(root, path) in &per_chunk {
+            assert!(verify_path(block_root, path, root));
+        }
+        let batched = BatchedRootProof { outgoing_receipts_roots: roots };
+        assert_eq!(merklize(&batched.outgoing_receipts_roots).0, block_root);
+        assert!(
+            borsh::object_length(&batched).unwrap() < borsh::object_length(&per_chunk).unwrap()
+        );
+    }
 }
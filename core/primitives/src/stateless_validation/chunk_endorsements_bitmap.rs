@@ -9,6 +9,10 @@ use near_schema_checker_lib::ProtocolSchema;
 /// since the seats are assigned to validators weighted by their stake. Thus, we represent the bits as a vector of bytes.
 /// The number of assignments may be less or equal to the number of total bytes. This representation allows increasing
 /// the chunk validator seats in the future (which will be represented by a vector of greater length).
+///
+/// The bitmap of a shard without a new chunk in the block is empty, unless the chunk producer
+/// suppressed the chunk of a quiet shard (see `crate::chunk_suppression`), in which case it holds
+/// the single byte `SUPPRESSED_CHUNK_MARKER`.
 #[derive(
     BorshSerialize,
     BorshDeserialize,
@@ -25,6 +29,10 @@ pub struct ChunkEndorsementsBitmap {
     inner: Vec<Vec<u8>>,
 }
 
+/// Bitmap of a shard whose chunk is suppressed. It can't be confused with endorsements, because
+/// the shard has no new chunk in the block.
+const SUPPRESSED_CHUNK_MARKER: u8 = u8::MAX;
+
 /// Type of BitVec used in the internal implementation.
 type BitVecType = BitVec<u8, Lsb0>;
 
@@ -83,6 +91,18 @@ impl ChunkEndorsementsBitmap {
     pub fn len(&self, shard_index: usize) -> Option<usize> {
         self.inner.get(shard_index).map(|v| v.len() * 8)
     }
+
+    /// Marks the chunk of the shard as suppressed. The shard must not have a new chunk in the
+    /// block, so there are no endorsements to record for it.
+    pub fn mark_chunk_suppressed(&mut self, shard_index: usize) {
+        self.inner[shard_index] = vec![SUPPRESSED_CHUNK_MARKER];
+    }
+
+    /// Whether the chunk of the shard is marked as suppressed. Only meaningful for the shards
+    /// without a new chunk in the block.
+    pub fn is_chunk_suppressed(&self, shard_index: usize) -> bool {
+        self.inner.get(shard_index).is_some_and(|bytes| bytes[..] == [SUPPRESSED_CHUNK_MARKER])
+    }
 }
 
 #[cfg(test)]
@@ -145,4 +165,18 @@ mod tests {
         run_bitmap_test(130, 50);
         run_bitmap_test(300, 100);
     }
+
+    #[test]
+    fn test_suppressed_chunk_marker() {
+        let mut bitmap = ChunkEndorsementsBitmap::new(NUM_SHARDS);
+        bitmap.add_endorsements(0, vec![true, false, true]);
+        bitmap.mark_chunk_suppressed(1);
+        let bitmap =
+            borsh::from_slice::<ChunkEndorsementsBitmap>(&borsh::to_vec(&bitmap).unwrap()).unwrap();
+        assert!(!bitmap.is_chunk_suppressed(0));
+        assert!(bitmap.is_chunk_suppressed(1));
+        assert!(!bitmap.is_chunk_suppressed(2));
+        assert!(!bitmap.is_chunk_suppressed(NUM_SHARDS));
+        assert_eq!(bitmap.len(1), Some(8));
+    }
 }
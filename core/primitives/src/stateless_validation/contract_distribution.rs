@@ -297,7 +297,7 @@ const CONTRACT_CODE_RESPONSE_COMPRESSION_LEVEL: i32 = 3;
     derive_more::From,
     derive_more::AsRef,
 )]
-struct CompressedContractCode(Box<[u8]>);
+pub struct CompressedContractCode(Box<[u8]>);
 
 impl
     CompressedData<
@@ -306,6 +306,7 @@ impl
         CONTRACT_CODE_RESPONSE_COMPRESSION_LEVEL,
     > for CompressedContractCode
 {
+    const NAME: &'static str = "contract_code";
 }
 
 /// Hash of some (uncompiled) contract code.
@@ -87,6 +87,14 @@ impl PartialEncodedStateWitness {
     pub fn into_part(self) -> Box<[u8]> {
         self.inner.part
     }
+
+    /// Replaces the part with garbage of the same size and signs the witness again with
+    /// `signer`, to test the rejection of malformed state witnesses.
+    #[cfg(feature = "test_utils")]
+    pub fn corrupt_part(&mut self, signer: &ValidatorSigner) {
+        self.inner.part.iter_mut().for_each(|byte| *byte = !*byte);
+        self.signature = signer.sign_bytes(&borsh::to_vec(&self.inner).unwrap());
+    }
 }
 
 #[derive(Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize, ProtocolSchema)]
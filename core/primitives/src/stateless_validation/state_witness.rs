@@ -47,6 +47,7 @@ impl
         STATE_WITNESS_COMPRESSION_LEVEL,
     > for EncodedChunkStateWitness
 {
+    const NAME: &'static str = "chunk_state_witness";
 }
 
 #[cfg(feature = "solomon")]
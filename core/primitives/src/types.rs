@@ -805,6 +805,44 @@ pub mod chunk_extra {
         pub bandwidth_requests: BandwidthRequests,
     }
 
+    /// A field of the `ChunkExtra` of a child shard, either inherited from the parent shard or
+    /// set to a new value.
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub enum ChildChunkExtraField<T> {
+        Inherit,
+        Set(T),
+    }
+
+    impl<T: Clone> ChildChunkExtraField<T> {
+        fn resolve(self, parent_value: &T) -> T {
+            match self {
+                Self::Inherit => parent_value.clone(),
+                Self::Set(value) => value,
+            }
+        }
+    }
+
+    /// The fields of the `ChunkExtra` of a child shard created by resharding, see
+    /// `ChunkExtra::new_child`.
+    ///
+    /// Every field has to be either set or explicitly inherited from the parent, so that a new
+    /// `ChunkExtra` field can't silently carry the value of the parent into its children. The
+    /// state root and the congestion info are specific to each child, so they can't be
+    /// inherited. The outgoing receipts root is not part of `ChunkExtra`, it's computed from
+    /// the receipts of the chunk.
+    #[derive(Debug, Clone)]
+    pub struct ChildChunkExtraFields {
+        pub state_root: StateRoot,
+        pub congestion_info: CongestionInfo,
+        pub outcome_root: ChildChunkExtraField<CryptoHash>,
+        pub validator_proposals: ChildChunkExtraField<Vec<ValidatorStake>>,
+        pub gas_used: ChildChunkExtraField<Gas>,
+        pub gas_limit: ChildChunkExtraField<Gas>,
+        pub balance_burnt: ChildChunkExtraField<Balance>,
+        /// Ignored if the parent `ChunkExtra` predates the bandwidth requests.
+        pub bandwidth_requests: ChildChunkExtraField<BandwidthRequests>,
+    }
+
     impl ChunkExtra {
         /// This method creates a slimmed down and invalid ChunkExtra. It's used
         /// for resharding where we only need the state root. This should not be
@@ -877,6 +915,48 @@ pub mod chunk_extra {
             }
         }
 
+        /// Creates the `ChunkExtra` of a child shard from the `ChunkExtra` of its parent, of
+        /// the same version. Every field of the child is listed, either taken from `fields` or
+        /// inherited as requested by `fields`.
+        ///
+        /// Panics if the parent predates the congestion info, which resharding requires.
+        pub fn new_child(parent: &ChunkExtra, fields: ChildChunkExtraFields) -> Self {
+            let ChildChunkExtraFields {
+                state_root,
+                congestion_info,
+                outcome_root,
+                validator_proposals,
+                gas_used,
+                gas_limit,
+                balance_burnt,
+                bandwidth_requests,
+            } = fields;
+            match parent {
+                Self::V1(_) | Self::V2(_) => {
+                    panic!("The congestion info must exist in the parent chunk extra!")
+                }
+                Self::V3(parent) => Self::V3(ChunkExtraV3 {
+                    state_root,
+                    outcome_root: outcome_root.resolve(&parent.outcome_root),
+                    validator_proposals: validator_proposals.resolve(&parent.validator_proposals),
+                    gas_used: gas_used.resolve(&parent.gas_used),
+                    gas_limit: gas_limit.resolve(&parent.gas_limit),
+                    balance_burnt: balance_burnt.resolve(&parent.balance_burnt),
+                    congestion_info,
+                }),
+                Self::V4(parent) => Self::V4(ChunkExtraV4 {
+                    state_root,
+                    outcome_root: outcome_root.resolve(&parent.outcome_root),
+                    validator_proposals: validator_proposals.resolve(&parent.validator_proposals),
+                    gas_used: gas_used.resolve(&parent.gas_used),
+                    gas_limit: gas_limit.resolve(&parent.gas_limit),
+                    balance_burnt: balance_burnt.resolve(&parent.balance_burnt),
+                    congestion_info,
+                    bandwidth_requests: bandwidth_requests.resolve(&parent.bandwidth_requests),
+                }),
+            }
+        }
+
         #[inline]
         pub fn outcome_root(&self) -> &StateRoot {
             match self {
@@ -1227,7 +1307,12 @@ mod tests {
     use near_crypto::{KeyType, PublicKey};
     use near_primitives_core::types::Balance;
 
+    use super::chunk_extra::{ChildChunkExtraField, ChildChunkExtraFields, ChunkExtra};
     use super::validator_stake::ValidatorStake;
+    use crate::bandwidth_scheduler::BandwidthRequests;
+    use crate::congestion_info::CongestionInfo;
+    use crate::hash::CryptoHash;
+    use crate::version::PROTOCOL_VERSION;
 
     fn new_validator_stake(stake: Balance) -> ValidatorStake {
         ValidatorStake::new(
@@ -1250,4 +1335,40 @@ mod tests {
         assert_eq!(new_validator_stake(10).partial_mandate_weight(5), 0);
         assert_eq!(new_validator_stake(12).partial_mandate_weight(5), 2);
     }
+
+    #[test]
+    fn test_new_child_chunk_extra() {
+        let parent = ChunkExtra::new(
+            PROTOCOL_VERSION,
+            &CryptoHash::hash_bytes(b"parent"),
+            CryptoHash::hash_bytes(b"outcome"),
+            vec![new_validator_stake(10)],
+            100,
+            1000,
+            10,
+            Some(CongestionInfo::default()),
+            BandwidthRequests::default_for_protocol_version(PROTOCOL_VERSION),
+        );
+        let child_state_root = CryptoHash::hash_bytes(b"child");
+        let child = ChunkExtra::new_child(
+            &parent,
+            ChildChunkExtraFields {
+                state_root: child_state_root,
+                congestion_info: CongestionInfo::default(),
+                outcome_root: ChildChunkExtraField::Inherit,
+                validator_proposals: ChildChunkExtraField::Set(vec![]),
+                gas_used: ChildChunkExtraField::Inherit,
+                gas_limit: ChildChunkExtraField::Set(500),
+                balance_burnt: ChildChunkExtraField::Inherit,
+                bandwidth_requests: ChildChunkExtraField::Inherit,
+            },
+        );
+        assert_eq!(child.state_root(), &child_state_root);
+        assert_eq!(child.outcome_root(), parent.outcome_root());
+        assert_eq!(child.validator_proposals().len(), 0);
+        assert_eq!(child.gas_used(), 100);
+        assert_eq!(child.gas_limit(), 500);
+        assert_eq!(child.balance_burnt(), 10);
+        assert_eq!(child.bandwidth_requests(), parent.bandwidth_requests());
+    }
 }
@@ -4,14 +4,70 @@ use borsh::{BorshDeserialize, BorshSerialize};
 use bytes::{Buf, BufMut};
 use bytesize::ByteSize;
 
+/// Codec used to compress the data of a [CompressedData].
+///
+/// The compressed data starts with the tag of its codec. The tags are above the variant indices
+/// of the borsh-serialized versioned types, so that the compressed data is told apart from the
+/// uncompressed encoding of the types which may be stored either way, like the state parts and
+/// headers dumped for state sync.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, strum::IntoStaticStr)]
+#[repr(u8)]
+pub enum CompressionCodec {
+    Zstd = 0x80,
+}
+
+impl CompressionCodec {
+    pub fn tag(self) -> u8 {
+        self as u8
+    }
+
+    pub fn from_tag(tag: u8) -> Option<Self> {
+        (tag == Self::Zstd.tag()).then_some(Self::Zstd)
+    }
+
+    /// Returns the codec the data was compressed with, if it starts with the tag of a known
+    /// codec.
+    pub fn detect(data: &[u8]) -> Option<Self> {
+        data.first().copied().and_then(Self::from_tag)
+    }
+}
+
+/// Failure to decode a [CompressedData]. It's wrapped in the `std::io::Error` returned by the
+/// decoding, see [Self::reason].
+#[derive(Debug, thiserror::Error, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum DecodeError {
+    #[error("Compressed data starts with the unknown codec tag {0:?}")]
+    UnknownCodec(Option<u8>),
+    #[error("Decompressed data exceeded limit of {limit}: {source}")]
+    SizeLimit { limit: ByteSize, source: std::io::Error },
+    #[error("Invalid compressed data: {0}")]
+    InvalidData(std::io::Error),
+}
+
+impl DecodeError {
+    /// Name of the reason of a decoding failure, e.g. for the metrics of the callers, or `None`
+    /// if `err` isn't the failure to decode a [CompressedData].
+    pub fn reason(err: &std::io::Error) -> Option<&'static str> {
+        err.get_ref().and_then(|err| err.downcast_ref::<Self>()).map(Into::into)
+    }
+}
+
 /// Helper trait for implementing a compressed structure for networking messages.
 /// The reason this is not a struct is because some derives do not work well on
 /// structs that have generics; e.g. ProtocolSchema.
+///
+/// Each implementation names the type of the data for the metrics of the callers, limits the
+/// size of the decompressed data to guard against decompression bombs, and chooses the
+/// compression level.
 pub trait CompressedData<T, const MAX_UNCOMPRESSED_SIZE: u64, const COMPRESSION_LEVEL: i32>
 where
     T: BorshSerialize + BorshDeserialize,
     Self: From<Box<[u8]>> + AsRef<Box<[u8]>>,
 {
+    /// Label of the type of the data in the metrics of the callers.
+    const NAME: &'static str;
+
     /// Only use this if you are sure that the data is already encoded.
     fn from_boxed_slice(data: Box<[u8]>) -> Self {
         Self::from(data)
@@ -22,13 +78,13 @@ where
     fn encode(uncompressed: &T) -> std::io::Result<(Self, usize)> {
         // Flow of data: Original --> Borsh serialization --> Counting write --> zstd compression --> Bytes.
         // CountingWrite will count the number of bytes for the Borsh-serialized data, before compression.
+        let tagged = vec![CompressionCodec::Zstd.tag()];
         let mut counting_write =
-            CountingWrite::new(zstd::stream::Encoder::new(Vec::new().writer(), COMPRESSION_LEVEL)?);
+            CountingWrite::new(zstd::stream::Encoder::new(tagged.writer(), COMPRESSION_LEVEL)?);
         borsh::to_writer(&mut counting_write, uncompressed)?;
 
         let borsh_bytes_len = counting_write.bytes_written();
         let encoded_bytes = counting_write.into_inner().finish()?.into_inner();
-
         Ok((Self::from(encoded_bytes.into()), borsh_bytes_len.as_u64() as usize))
     }
 
@@ -41,26 +97,28 @@ where
 
     /// Decompress and borsh-deserialize the compressed data.
     /// Returns decompressed and deserialized data along with the raw (uncompressed) serialized data size.
+    /// The errors wrap a [DecodeError].
     fn decode_with_limit(&self, limit: ByteSize) -> std::io::Result<(T, usize)> {
+        let data: &[u8] = self.as_ref();
+        let Some(CompressionCodec::Zstd) = CompressionCodec::detect(data) else {
+            return Err(std::io::Error::other(DecodeError::UnknownCodec(data.first().copied())));
+        };
+
         // Flow of data: Bytes --> zstd decompression --> Counting read --> Borsh deserialization --> Original.
         // CountingRead will count the number of bytes for the Borsh-deserialized data, after decompression.
-        let mut counting_read = CountingRead::new_with_limit(
-            zstd::stream::Decoder::new(self.as_ref().reader())?,
-            limit,
-        );
+        let decoder = zstd::stream::Decoder::new(data[1..].reader())
+            .map_err(|err| std::io::Error::other(DecodeError::InvalidData(err)))?;
+        let mut counting_read = CountingRead::new_with_limit(decoder, limit);
 
         match borsh::from_reader(&mut counting_read) {
             Err(err) => {
                 // If decompressed data exceeds the limit then CountingRead will return a WriteZero error.
                 // Here we convert it to a more descriptive error to make debugging easier.
-                let err = if err.kind() == std::io::ErrorKind::WriteZero {
-                    std::io::Error::other(format!(
-                        "Decompressed data exceeded limit of {limit}: {err}"
-                    ))
+                if err.kind() == std::io::ErrorKind::WriteZero {
+                    Err(std::io::Error::other(DecodeError::SizeLimit { limit, source: err }))
                 } else {
-                    err
-                };
-                Err(err)
+                    Err(std::io::Error::other(DecodeError::InvalidData(err)))
+                }
             }
             Ok(deserialized) => {
                 Ok((deserialized, counting_read.bytes_read().as_u64().try_into().unwrap()))
@@ -77,10 +135,53 @@ where
     }
 }
 
+/// Checks the properties shared by all the implementations of [CompressedData] on `data`.
+#[cfg(any(test, feature = "test_utils"))]
+pub fn check_compressed_data<C, T, const MAX_UNCOMPRESSED_SIZE: u64, const COMPRESSION_LEVEL: i32>(
+    data: &T,
+) where
+    C: CompressedData<T, MAX_UNCOMPRESSED_SIZE, COMPRESSION_LEVEL>,
+    T: BorshSerialize + BorshDeserialize + PartialEq + std::fmt::Debug,
+{
+    let (compressed, uncompressed_size) = C::encode(data).unwrap();
+    assert_eq!(CompressionCodec::detect(compressed.as_slice()), Some(CompressionCodec::Zstd));
+    assert_eq!(borsh::to_vec(data).unwrap().len(), uncompressed_size);
+    let (decompressed, decompressed_size) = compressed.decode().unwrap();
+    assert_eq!(&decompressed, data);
+    assert_eq!(decompressed_size, uncompressed_size);
+
+    // Borsh rejects data followed by trailing bytes, so the size of the decompressed data
+    // is always the one of the original data.
+    let mut with_trailing_bytes = borsh::to_vec(data).unwrap();
+    with_trailing_bytes.push(0);
+    let mut compressed_with_trailing_bytes = vec![CompressionCodec::Zstd.tag()];
+    compressed_with_trailing_bytes
+        .extend(zstd::encode_all(with_trailing_bytes.as_slice(), COMPRESSION_LEVEL).unwrap());
+    let err = C::from_boxed_slice(compressed_with_trailing_bytes.into()).decode().unwrap_err();
+    assert_eq!(DecodeError::reason(&err), Some("invalid_data"));
+
+    let truncated = &compressed.as_slice()[..compressed.size_bytes() / 2];
+    assert!(C::from_boxed_slice(truncated.into()).decode().is_err());
+    let untagged = &compressed.as_slice()[1..];
+    let err = C::from_boxed_slice(untagged.into()).decode().unwrap_err();
+    assert_eq!(DecodeError::reason(&err), Some("unknown_codec"));
+}
+
 #[cfg(test)]
 mod tests {
+    use super::{check_compressed_data, CompressionCodec, DecodeError};
+    use crate::challenge::PartialState;
+    use crate::state_sync::{
+        CompressedStatePart, MAX_UNCOMPRESSED_STATE_PART_SIZE, STATE_PART_COMPRESSION_LEVEL,
+    };
+    use crate::stateless_validation::state_witness::{
+        ChunkStateWitness, EncodedChunkStateWitness, MAX_UNCOMPRESSED_STATE_WITNESS_SIZE,
+        STATE_WITNESS_COMPRESSION_LEVEL,
+    };
+    use crate::types::ShardId;
     use crate::utils::compression::CompressedData;
     use borsh::{BorshDeserialize, BorshSerialize};
+    use near_primitives_core::hash::CryptoHash;
     use std::io::ErrorKind;
 
     #[derive(BorshSerialize, BorshDeserialize, PartialEq, Debug)]
@@ -89,7 +190,9 @@ mod tests {
     #[derive(derive_more::From, derive_more::AsRef)]
     struct CompressedMyData(Box<[u8]>);
 
-    impl super::CompressedData<MyData, 1000, 3> for CompressedMyData {}
+    impl super::CompressedData<MyData, 1000, 3> for CompressedMyData {
+        const NAME: &'static str = "my_data";
+    }
 
     #[test]
     fn encode_decode_within_limit() {
@@ -119,6 +222,7 @@ mod tests {
             error.to_string(),
             "Decompressed data exceeded limit of 1.0 KB: Exceeded the limit of 1000 bytes"
         );
+        assert_eq!(DecodeError::reason(&error), Some("size_limit"));
     }
 
     #[test]
@@ -127,5 +231,36 @@ mod tests {
         let error =
             CompressedMyData::from_boxed_slice(Box::new(invalid_data)).decode().unwrap_err();
         assert_eq!(error.kind(), ErrorKind::Other);
+        assert_eq!(DecodeError::reason(&error), Some("unknown_codec"));
+        let mut tagged_invalid_data = invalid_data;
+        tagged_invalid_data[0] = CompressionCodec::Zstd.tag();
+        let error =
+            CompressedMyData::from_boxed_slice(Box::new(tagged_invalid_data)).decode().unwrap_err();
+        assert_eq!(DecodeError::reason(&error), Some("invalid_data"));
+    }
+
+    #[test]
+    fn detect_codec() {
+        let (compressed, _) = CompressedMyData::encode(&MyData(vec![])).unwrap();
+        assert_eq!(CompressionCodec::detect(compressed.as_slice()), Some(CompressionCodec::Zstd));
+        assert_eq!(CompressionCodec::detect(&borsh::to_vec(&MyData(vec![1])).unwrap()), None);
+        assert_eq!(CompressionCodec::detect(&[]), None);
+    }
+
+    #[test]
+    fn compressed_data_implementations() {
+        check_compressed_data::<CompressedMyData, _, 1000, 3>(&MyData(vec![42; 100]));
+        check_compressed_data::<
+            EncodedChunkStateWitness,
+            _,
+            MAX_UNCOMPRESSED_STATE_WITNESS_SIZE,
+            STATE_WITNESS_COMPRESSION_LEVEL,
+        >(&ChunkStateWitness::new_dummy(42, ShardId::new(1), CryptoHash::hash_bytes(&[1])));
+        check_compressed_data::<
+            CompressedStatePart,
+            _,
+            MAX_UNCOMPRESSED_STATE_PART_SIZE,
+            STATE_PART_COMPRESSION_LEVEL,
+        >(&PartialState::TrieValues(vec![vec![1, 2, 3].into(), vec![42; 100].into()]));
     }
 }
@@ -125,6 +125,32 @@ impl From<AccountView> for Account {
     }
 }
 
+/// A view of the balance of an account, split by what the account can spend it on.
+#[derive(serde::Serialize, serde::Deserialize, Debug, Eq, PartialEq, Clone)]
+pub struct AccountBalanceView {
+    /// Balance of the account, not including the locked stake.
+    #[serde(with = "dec_format")]
+    pub amount: Balance,
+    /// Stake locked by the account.
+    #[serde(with = "dec_format")]
+    pub locked: Balance,
+    /// Part of `amount` reserved for the storage staking of the account, i.e. the cost of its
+    /// storage which isn't covered by `locked`.
+    #[serde(with = "dec_format")]
+    pub storage_reserve: Balance,
+    /// Part of `amount` the account can spend, `amount - storage_reserve`.
+    #[serde(with = "dec_format")]
+    pub liquid: Balance,
+    /// Sum of the allowances of the function call access keys of the account, which the keys
+    /// can spend on gas. Keys with an unlimited allowance aren't counted.
+    #[serde(with = "dec_format")]
+    pub function_call_allowance: Balance,
+    /// Part of `liquid` not pledged to the function call access keys,
+    /// `liquid - function_call_allowance`.
+    #[serde(with = "dec_format")]
+    pub unpledged_liquid: Balance,
+}
+
 #[derive(
     BorshSerialize,
     BorshDeserialize,
@@ -186,11 +212,22 @@ impl From<AccessKeyPermissionView> for AccessKeyPermission {
 pub struct AccessKeyView {
     pub nonce: Nonce,
     pub permission: AccessKeyPermissionView,
+    /// Height of the last block in which the key signed a transaction, regardless of the
+    /// queried block. Only returned by `view_access_key` and `view_access_key_list` queries to
+    /// nodes with `save_access_key_usage` enabled, and only known for the transactions
+    /// processed since it was enabled.
+    #[borsh(skip)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_used_block_height: Option<BlockHeight>,
 }
 
 impl From<AccessKey> for AccessKeyView {
     fn from(access_key: AccessKey) -> Self {
-        Self { nonce: access_key.nonce, permission: access_key.permission.into() }
+        Self {
+            nonce: access_key.nonce,
+            permission: access_key.permission.into(),
+            last_used_block_height: None,
+        }
     }
 }
 
@@ -283,6 +320,7 @@ pub enum QueryResponseKind {
     CallResult(CallResult),
     AccessKey(AccessKeyView),
     AccessKeyList(AccessKeyList),
+    ViewAccountBalance(AccountBalanceView),
 }
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -314,6 +352,38 @@ pub enum QueryRequest {
         #[serde(rename = "args_base64")]
         args: FunctionArgs,
     },
+    /// Same as `CallFunction`, but the state of the called contract is modified
+    /// according to `state_override` before the call. Nothing is persisted.
+    SimulateFunctionCall {
+        account_id: AccountId,
+        method_name: String,
+        #[serde(rename = "args_base64")]
+        args: FunctionArgs,
+        #[serde(default)]
+        state_override: StateOverride,
+    },
+    /// Liquid balance of the account after the storage staking, computed by
+    /// the same rules as the runtime, see `AccountBalanceView`.
+    ViewAccountBalance {
+        account_id: AccountId,
+    },
+}
+
+/// Changes to the state of an account applied before simulating a function
+/// call on it. Fields which are not set are taken from the actual state.
+#[serde_as]
+#[derive(serde::Serialize, serde::Deserialize, Debug, Default, PartialEq, Eq, Clone)]
+pub struct StateOverride {
+    /// Balance of the account.
+    #[serde(default, with = "dec_format", skip_serializing_if = "Option::is_none")]
+    pub balance: Option<Balance>,
+    /// Contract code deployed to the account.
+    #[serde_as(as = "Option<Base64>")]
+    #[serde(default, rename = "code_base64", skip_serializing_if = "Option::is_none")]
+    pub code: Option<Vec<u8>>,
+    /// Contract storage entries written on top of the existing storage.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub state: Vec<StateItem>,
 }
 
 fn is_false(v: &bool) -> bool {
@@ -454,6 +524,26 @@ pub struct RecentOutboundConnectionsView {
     pub recent_outbound_connections: Vec<ConnectionInfoView>,
 }
 
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ConnectionAuditEntryView {
+    /// UNIX timestamp in seconds.
+    pub time: i64,
+    pub peer_id: PeerId,
+    pub addr: String,
+    /// One of `Established`, `Closed` or `Banned`.
+    pub event: String,
+    pub tier: Option<String>,
+    pub peer_type: Option<String>,
+    /// Why the connection was closed or the peer was banned.
+    pub reason: Option<String>,
+}
+
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
+pub struct ConnectionAuditLogView {
+    /// Most recent entries first.
+    pub entries: Vec<ConnectionAuditEntryView>,
+}
+
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq)]
 pub struct SnapshotHostsView {
     pub hosts: Vec<SnapshotHostInfoView>,
@@ -1644,6 +1734,74 @@ impl ExecutionOutcomeWithIdView {
         self.outcome.to_hashes(self.id)
     }
 }
+
+/// Relation of an account to a receipt in the index of receipts by account.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum AccountReceiptDirection {
+    /// The receipt is addressed to the account.
+    Incoming,
+    /// The receipt was created by a transaction or receipt executed by the account.
+    Outgoing,
+}
+
+/// A receipt executed or created by an account, as stored in the index of receipts by account.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct AccountReceiptView {
+    pub receipt_id: CryptoHash,
+    /// Block in which the receipt was executed or created by the account.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub shard_id: ShardId,
+    pub incoming: bool,
+    pub outgoing: bool,
+    /// Outcome of the receipt on the canonical chain, if requested and already executed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub outcome: Option<ExecutionOutcomeWithIdView>,
+}
+
+/// Query of one of the secondary indexes maintained by the node.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "index", rename_all = "snake_case")]
+pub enum SecondaryIndexQuery {
+    /// Transactions signed by the account, ordered by height.
+    TransactionsByAccount { account_id: AccountId },
+    /// Receipts executed by the account, ordered by height. Requires `save_account_receipts`.
+    ReceiptsByReceiver { account_id: AccountId },
+    /// Blocks of the canonical chain with a timestamp not lower than the given one, ordered
+    /// by timestamp.
+    BlocksByTimestamp {
+        #[serde(with = "dec_format")]
+        from_timestamp_nanosec: u64,
+    },
+}
+
+/// An entry of a secondary index.
+#[derive(serde::Serialize, serde::Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SecondaryIndexEntryView {
+    Transaction {
+        hash: CryptoHash,
+        /// Block in which the transaction was converted to a receipt.
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        shard_id: ShardId,
+    },
+    Receipt {
+        receipt_id: CryptoHash,
+        /// Block in which the receipt was executed.
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        shard_id: ShardId,
+    },
+    Block {
+        block_hash: CryptoHash,
+        block_height: BlockHeight,
+        #[serde(with = "dec_format")]
+        timestamp_nanosec: u64,
+    },
+}
+
 #[derive(Clone, Debug)]
 pub struct TxStatusView {
     pub execution_outcome: Option<FinalExecutionOutcomeViewEnum>,
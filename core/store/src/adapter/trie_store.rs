@@ -6,7 +6,7 @@ use borsh::BorshDeserialize;
 use near_primitives::errors::{MissingTrieValueContext, StorageError};
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
-use near_primitives::types::RawStateChangesWithTrieKey;
+use near_primitives::types::{BlockHeight, RawStateChangesWithTrieKey};
 
 use crate::{DBCol, KeyForStateChanges, Store, StoreUpdate, TrieChanges, STATE_SNAPSHOT_KEY};
 
@@ -38,6 +38,24 @@ impl TrieStoreAdapter {
     /// For more details, see `get_key_from_shard_uid_and_hash()` docs.
     pub fn get(&self, shard_uid: ShardUId, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
         let key = get_key_from_shard_uid_and_hash(&self.store, shard_uid, hash);
+        self.get_by_key(&key, hash)
+    }
+
+    /// Like `get()`, but resolves the `shard_uid` prefix with the mapping in use at the block at
+    /// `block_height`, see `get_shard_uid_mapping_at_height()`. Used to read the State of past
+    /// blocks, which may be stored under a different prefix than the current State.
+    pub fn get_at_height(
+        &self,
+        shard_uid: ShardUId,
+        block_height: BlockHeight,
+        hash: &CryptoHash,
+    ) -> Result<Arc<[u8]>, StorageError> {
+        let mapped_shard_uid =
+            get_shard_uid_mapping_at_height(&self.store, shard_uid, block_height)?;
+        self.get_by_key(&get_state_key(mapped_shard_uid, hash), hash)
+    }
+
+    fn get_by_key(&self, key: &[u8; 40], hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
         let val = self
             .store
             .get(DBCol::State, key.as_ref())
@@ -64,6 +82,13 @@ impl TrieStoreAdapter {
         Ok(val)
     }
 
+    /// Whether any State is stored with the `shard_uid_prefix` database key prefix.
+    pub fn has_shard_uid_prefixed_state(&self, shard_uid_prefix: ShardUId) -> io::Result<bool> {
+        let key_prefix = shard_uid_prefix.to_bytes();
+        // `Store::iter_prefix` rejects the State column, reading a single key is fine.
+        Ok(self.store.storage.iter_prefix(DBCol::State, &key_prefix).next().transpose()?.is_some())
+    }
+
     #[cfg(test)]
     pub fn iter_raw_bytes(&self) -> crate::db::DBIterator {
         self.store.iter_raw_bytes(DBCol::State)
@@ -168,9 +193,38 @@ impl<'a> TrieStoreUpdateAdapter<'a> {
         )
     }
 
+    /// Record that the State of `shard_uid` is stored with the `mapped_shard_uid` prefix from the
+    /// block at `height` on, see `get_shard_uid_mapping_at_height()`.
+    pub fn set_shard_uid_mapping_from_height(
+        &mut self,
+        shard_uid: ShardUId,
+        height: BlockHeight,
+        mapped_shard_uid: ShardUId,
+    ) {
+        self.store_update.set(
+            DBCol::StateShardUIdMappingHistory,
+            &get_shard_uid_mapping_history_key(shard_uid, height),
+            &borsh::to_vec(&mapped_shard_uid).expect("Borsh serialize cannot fail"),
+        )
+    }
+
+    /// Remove the mapping of `child_shard_uid`, so that its State is accessed with its own
+    /// `ShardUId` prefix again.
+    pub fn delete_shard_uid_mapping(&mut self, child_shard_uid: ShardUId) {
+        self.store_update.delete(DBCol::StateShardUIdMapping, child_shard_uid.to_bytes().as_ref())
+    }
+
     pub fn delete_all_state(&mut self) {
         self.store_update.delete_all(DBCol::State)
     }
+
+    /// Remove the State stored with the `shard_uid_prefix` database key prefix, that is the
+    /// State of all the shards mapped to it. Must only be used once none of them is in use.
+    pub fn delete_shard_uid_prefixed_state(&mut self, shard_uid_prefix: ShardUId) {
+        let key_from = shard_uid_prefix.to_bytes();
+        let key_to = ShardUId::get_upper_bound_db_key(&key_from);
+        self.store_update.delete_range(DBCol::State, &key_from, &key_to);
+    }
 }
 
 /// Get the `ShardUId` mapping for child_shard_uid. If the mapping does not exist, map the shard to itself.
@@ -187,6 +241,48 @@ pub fn get_shard_uid_mapping(store: &Store, child_shard_uid: ShardUId) -> ShardU
         .unwrap_or(child_shard_uid)
 }
 
+/// Get the `ShardUId` whose prefix holds the State of `shard_uid` at the block at `height`,
+/// according to the history of the mapping kept in `DBCol::StateShardUIdMappingHistory`.
+///
+/// The current mapping is not enough for archival nodes, as it only tells where the latest State
+/// of the shard is stored. Falls back to the current mapping only if no change of the mapping was
+/// ever recorded for the shard, e.g. if the shard was never resharded. Fails for a height before
+/// the first recorded change, as the shard had no State of its own yet.
+pub fn get_shard_uid_mapping_at_height(
+    store: &Store,
+    shard_uid: ShardUId,
+    height: BlockHeight,
+) -> Result<ShardUId, StorageError> {
+    let key_prefix = shard_uid.to_bytes();
+    let mut first_height = None;
+    let mut mapped_shard_uid = None;
+    // The heights are big endian, so the rows are sorted by height.
+    for item in store.iter_prefix_ser::<ShardUId>(DBCol::StateShardUIdMappingHistory, &key_prefix) {
+        let (key, value) = item.map_err(|_| StorageError::StorageInternalError)?;
+        let from_height = BlockHeight::from_be_bytes(key[key_prefix.len()..].try_into().unwrap());
+        first_height.get_or_insert(from_height);
+        if from_height > height {
+            break;
+        }
+        mapped_shard_uid = Some(value);
+    }
+    match (mapped_shard_uid, first_height) {
+        (Some(mapped_shard_uid), _) => Ok(mapped_shard_uid),
+        (None, None) => Ok(get_shard_uid_mapping(store, shard_uid)),
+        (None, Some(first_height)) => Err(StorageError::StorageInconsistentState(format!(
+            "No State of shard {} at height {}, its ShardUId mapping starts at height {}",
+            shard_uid, height, first_height
+        ))),
+    }
+}
+
+fn get_shard_uid_mapping_history_key(shard_uid: ShardUId, height: BlockHeight) -> [u8; 16] {
+    let mut key = [0; 16];
+    key[0..8].copy_from_slice(&shard_uid.to_bytes());
+    key[8..].copy_from_slice(&height.to_be_bytes());
+    key
+}
+
 /// Constructs db key to be used to access the State column.
 /// First, it consults the `StateShardUIdMapping` column to map the `shard_uid` prefix
 /// to its ancestor in the resharding tree (according to Resharding V3)
@@ -200,7 +296,11 @@ fn get_key_from_shard_uid_and_hash(
     shard_uid: ShardUId,
     hash: &CryptoHash,
 ) -> [u8; 40] {
-    let mapped_shard_uid = get_shard_uid_mapping(store, shard_uid);
+    get_state_key(get_shard_uid_mapping(store, shard_uid), hash)
+}
+
+/// The State column key of the node `hash` stored with the `mapped_shard_uid` prefix.
+fn get_state_key(mapped_shard_uid: ShardUId, hash: &CryptoHash) -> [u8; 40] {
     let mut key = [0; 40];
     key[0..8].copy_from_slice(&mapped_shard_uid.to_bytes());
     key[8..].copy_from_slice(hash.as_ref());
@@ -214,7 +314,8 @@ mod tests {
     use near_primitives::hash::CryptoHash;
     use near_primitives::shard_layout::ShardUId;
 
-    use crate::adapter::trie_store::TrieStoreAdapter;
+    use crate::adapter::trie_store::{get_shard_uid_mapping_at_height, TrieStoreAdapter};
+    use crate::adapter::StoreAdapter;
     use crate::NodeStorage;
 
     const ONE: std::num::NonZeroU32 = match std::num::NonZeroU32::new(1) {
@@ -316,4 +417,82 @@ mod tests {
             Err(StorageError::MissingTrieValue(_, _))
         );
     }
+
+    #[test]
+    fn test_shard_uid_mapping_at_height() {
+        let (_tmp_dir, opener) = NodeStorage::test_opener();
+        let store = TrieStoreAdapter::new(opener.open().unwrap().get_hot_store());
+        let parent_shard = ShardUId { version: 0, shard_id: 0 };
+        let child_shard = ShardUId { version: 0, shard_id: 1 };
+        let mapping_at_height =
+            |height| get_shard_uid_mapping_at_height(store.store_ref(), child_shard, height);
+        // Without history, the current mapping is used.
+        assert_eq!(mapping_at_height(10).unwrap(), child_shard);
+        {
+            let mut store_update = store.store_update();
+            store_update.set_shard_uid_mapping(child_shard, parent_shard);
+            store_update.set_shard_uid_mapping_from_height(child_shard, 100, parent_shard);
+            store_update.commit().unwrap();
+        }
+        // The child had no State before the resharding.
+        assert_matches!(mapping_at_height(99), Err(StorageError::StorageInconsistentState(_)));
+        assert_eq!(mapping_at_height(100).unwrap(), parent_shard);
+        // The mapping was removed later, e.g. after the State of the child was synced again.
+        {
+            let mut store_update = store.store_update();
+            store_update.delete_shard_uid_mapping(child_shard);
+            store_update.set_shard_uid_mapping_from_height(child_shard, 200, child_shard);
+            store_update.commit().unwrap();
+        }
+        assert_eq!(mapping_at_height(150).unwrap(), parent_shard);
+        assert_eq!(mapping_at_height(200).unwrap(), child_shard);
+        assert_eq!(mapping_at_height(1000).unwrap(), child_shard);
+    }
+
+    #[test]
+    fn test_get_at_height_across_resharding() {
+        let (_tmp_dir, opener) = NodeStorage::test_opener();
+        let store = TrieStoreAdapter::new(opener.open().unwrap().get_hot_store());
+        let parent_shard = ShardUId { version: 0, shard_id: 0 };
+        let child_shard = ShardUId { version: 1, shard_id: 1 };
+        let parent_hash = CryptoHash::hash_bytes(&[0]);
+        let child_hash = CryptoHash::hash_bytes(&[1]);
+        // The State of the parent before the resharding at height 100.
+        {
+            let mut store_update = store.store_update();
+            store_update.increment_refcount_by(parent_shard, &parent_hash, &[0], ONE);
+            store_update.commit().unwrap();
+        }
+        assert_eq!(*store.get_at_height(parent_shard, 50, &parent_hash).unwrap(), [0]);
+        // The child reads the State of the parent after the resharding.
+        {
+            let mut store_update = store.store_update();
+            store_update.set_shard_uid_mapping(child_shard, parent_shard);
+            store_update.set_shard_uid_mapping_from_height(child_shard, 101, parent_shard);
+            store_update.increment_refcount_by(child_shard, &child_hash, &[1], ONE);
+            store_update.commit().unwrap();
+        }
+        assert_eq!(*store.get_at_height(child_shard, 101, &parent_hash).unwrap(), [0]);
+        assert_eq!(*store.get_at_height(child_shard, 150, &child_hash).unwrap(), [1]);
+        assert_eq!(*store.get_at_height(parent_shard, 50, &parent_hash).unwrap(), [0]);
+        // Before the resharding, the child has no State.
+        assert_matches!(
+            store.get_at_height(child_shard, 100, &parent_hash),
+            Err(StorageError::StorageInconsistentState(_))
+        );
+        // The State of the child is synced under its own prefix at height 200.
+        {
+            let mut store_update = store.store_update();
+            store_update.delete_shard_uid_mapping(child_shard);
+            store_update.set_shard_uid_mapping_from_height(child_shard, 200, child_shard);
+            store_update.increment_refcount_by(child_shard, &child_hash, &[2], ONE);
+            store_update.commit().unwrap();
+        }
+        assert_eq!(*store.get_at_height(child_shard, 150, &child_hash).unwrap(), [1]);
+        assert_eq!(*store.get_at_height(child_shard, 200, &child_hash).unwrap(), [2]);
+        assert_matches!(
+            store.get_at_height(child_shard, 200, &parent_hash),
+            Err(StorageError::MissingTrieValue(_, _))
+        );
+    }
 }
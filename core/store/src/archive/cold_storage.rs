@@ -1,4 +1,4 @@
-use crate::adapter::trie_store::get_shard_uid_mapping;
+use crate::adapter::trie_store::get_shard_uid_mapping_at_height;
 use crate::columns::DBKeyType;
 use crate::db::{ColdDB, COLD_HEAD_KEY, HEAD_KEY};
 use crate::{metrics, DBCol, DBTransaction, Database, Store, TrieChanges};
@@ -97,7 +97,9 @@ pub fn update_cold_db(
             if !col.is_cold() {
                 return false;
             }
-            if col == &DBCol::StateShardUIdMapping && !is_last_block_in_epoch {
+            if (col == &DBCol::StateShardUIdMapping || col == &DBCol::StateShardUIdMappingHistory)
+                && !is_last_block_in_epoch
+            {
                 return false;
             }
             true
@@ -115,7 +117,22 @@ pub fn update_cold_db(
                 // Copy column to cold db.
                 .map(|col: DBCol| -> io::Result<()> {
                     if col == DBCol::State {
-                        copy_state_from_store(shard_layout, block_hash_key, cold_db, &hot_store)
+                        copy_state_from_store(
+                            shard_layout,
+                            *height,
+                            block_hash_key,
+                            cold_db,
+                            &hot_store,
+                        )
+                    } else if col == DBCol::StateShardUIdMappingHistory {
+                        // The rows are keyed by the height at which the mapping changed, which
+                        // is not necessarily the height of a block. The column is tiny, so it's
+                        // copied as a whole.
+                        let keys = hot_store
+                            .iter(col)
+                            .map(|item| item.map(|(key, _)| key.to_vec()))
+                            .collect::<io::Result<Vec<_>>>()?;
+                        copy_from_store(cold_db, &hot_store, col, keys)
                     } else {
                         let keys = combine_keys(&key_type_to_keys, &col.key_type());
                         copy_from_store(cold_db, &hot_store, col, keys)
@@ -171,6 +188,7 @@ fn rc_aware_set(
 // the node belongs to.
 fn copy_state_from_store(
     shard_layout: &ShardLayout,
+    height: BlockHeight,
     block_hash_key: &[u8],
     cold_db: &ColdDB,
     hot_store: &Store,
@@ -195,7 +213,10 @@ fn copy_state_from_store(
 
         let Some(trie_changes) = trie_changes else { continue };
         total_keys += trie_changes.insertions().len();
-        let mapped_shard_uid_key = get_shard_uid_mapping(hot_store, shard_uid).to_bytes();
+        // The mapping may have changed since the block was processed.
+        let mapped_shard_uid_key = get_shard_uid_mapping_at_height(hot_store, shard_uid, height)
+            .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?
+            .to_bytes();
         for op in trie_changes.insertions() {
             // TODO(resharding) Test it properly. Currently this path is not triggered in testloop.
             let key = join_two_keys(&mapped_shard_uid_key, op.hash().as_bytes());
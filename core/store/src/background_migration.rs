@@ -0,0 +1,261 @@
+//! Migrations of a column done in the background while the node keeps running.
+//!
+//! The migrations of the DB version run before the node starts, which takes hours for the
+//! large columns. A background migration instead converts the entries of a source column into
+//! a new column or format in batches, while the node serves requests:
+//!
+//! * the node writes only the new format from the version introducing the migration on, so the
+//!   entries of the source column don't change anymore;
+//! * [run_background_migrations] iterates over the source column in key order and commits
+//!   each batch of converted entries atomically with the progress of the migration, persisted
+//!   in `DBCol::Misc`, so that a restarted node resumes the migration where it stopped;
+//! * until the migration is done, the readers fall back to the source column for the entries
+//!   not converted yet, see [read_with_fallback].
+//!
+//! Once a migration is done everywhere, a later DB version can delete its source column and
+//! remove the migration.
+
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::db::BACKGROUND_MIGRATION_STATUS_KEY;
+use crate::{metrics, DBCol, Store, StoreUpdate};
+
+/// Migration of the entries of a column, run in the background.
+pub trait BackgroundMigration: Send + Sync {
+    /// Unique name of the migration, which identifies its progress in the store.
+    fn name(&self) -> &'static str;
+
+    /// Column whose entries are migrated.
+    fn source_col(&self) -> DBCol;
+
+    /// Adds the writes converting an entry of the source column to `store_update`.
+    fn migrate_entry(
+        &self,
+        key: &[u8],
+        value: &[u8],
+        store_update: &mut StoreUpdate,
+    ) -> io::Result<()>;
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub enum BackgroundMigrationStatus {
+    /// The entries of the source column with keys lower than `next_key` are migrated.
+    InProgress {
+        next_key: Vec<u8>,
+        num_migrated: u64,
+    },
+    Done {
+        num_migrated: u64,
+    },
+}
+
+fn background_migration_status_key(name: &str) -> Vec<u8> {
+    [BACKGROUND_MIGRATION_STATUS_KEY, name.as_bytes()].concat()
+}
+
+/// Returns the status of the migration, or `None` if it didn't start yet.
+pub fn get_background_migration_status(
+    store: &Store,
+    name: &str,
+) -> io::Result<Option<BackgroundMigrationStatus>> {
+    store.get_ser(DBCol::Misc, &background_migration_status_key(name))
+}
+
+pub fn is_background_migration_done(store: &Store, name: &str) -> io::Result<bool> {
+    Ok(matches!(
+        get_background_migration_status(store, name)?,
+        Some(BackgroundMigrationStatus::Done { .. })
+    ))
+}
+
+/// Reads a value in the new format with `read_new`, falling back to the source column with
+/// `read_old` as long as the migration `name` isn't done.
+pub fn read_with_fallback<T>(
+    store: &Store,
+    name: &str,
+    read_new: impl FnOnce() -> io::Result<Option<T>>,
+    read_old: impl FnOnce() -> io::Result<Option<T>>,
+) -> io::Result<Option<T>> {
+    if let Some(value) = read_new()? {
+        return Ok(Some(value));
+    }
+    if is_background_migration_done(store, name)? {
+        return Ok(None);
+    }
+    read_old()
+}
+
+/// Migrates the next `batch_size` entries of the source column and commits them with the
+/// progress of the migration. Returns the status of the migration after the batch.
+pub fn run_background_migration_batch(
+    store: &Store,
+    migration: &dyn BackgroundMigration,
+    batch_size: usize,
+) -> io::Result<BackgroundMigrationStatus> {
+    let name = migration.name();
+    let (next_key, mut num_migrated) = match get_background_migration_status(store, name)? {
+        None => (vec![], 0),
+        Some(BackgroundMigrationStatus::InProgress { next_key, num_migrated }) => {
+            (next_key, num_migrated)
+        }
+        Some(status @ BackgroundMigrationStatus::Done { .. }) => return Ok(status),
+    };
+
+    let mut store_update = store.store_update();
+    let mut last_key = None;
+    for item in store.iter_range(migration.source_col(), Some(&next_key), None).take(batch_size) {
+        let (key, value) = item?;
+        migration.migrate_entry(&key, &value, &mut store_update)?;
+        num_migrated += 1;
+        last_key = Some(key);
+    }
+    let status = match last_key {
+        Some(last_key) => {
+            // The smallest key greater than `last_key`.
+            let next_key = [&last_key[..], &[0]].concat();
+            BackgroundMigrationStatus::InProgress { next_key, num_migrated }
+        }
+        None => BackgroundMigrationStatus::Done { num_migrated },
+    };
+    store_update.set_ser(DBCol::Misc, &background_migration_status_key(name), &status)?;
+    store_update.commit()?;
+    Ok(status)
+}
+
+/// Runs the migrations one after the other until they are all done or `keep_going` is unset.
+pub fn run_background_migrations(
+    store: &Store,
+    migrations: &[Box<dyn BackgroundMigration>],
+    batch_size: usize,
+    keep_going: &AtomicBool,
+) -> io::Result<()> {
+    for migration in migrations {
+        let name = migration.name();
+        tracing::info!(target: "migrations", name, "Running background migration");
+        loop {
+            if !keep_going.load(Ordering::Relaxed) {
+                tracing::info!(target: "migrations", name, "Background migration interrupted");
+                return Ok(());
+            }
+            let status = run_background_migration_batch(store, migration.as_ref(), batch_size)?;
+            let (BackgroundMigrationStatus::InProgress { num_migrated, .. }
+            | BackgroundMigrationStatus::Done { num_migrated }) = status;
+            metrics::BACKGROUND_MIGRATION_MIGRATED_ENTRIES
+                .with_label_values(&[name])
+                .set(num_migrated as i64);
+            if let BackgroundMigrationStatus::Done { .. } = status {
+                tracing::info!(target: "migrations", name, num_migrated, "Background migration done");
+                break;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::create_test_store;
+
+    const NAME: &str = "test_migration";
+    const SOURCE_COL: DBCol = DBCol::BlockHeight;
+    const TARGET_COL: DBCol = DBCol::BlockOrdinal;
+
+    /// Copies the entries of the source column to the target column, with their values
+    /// reversed.
+    struct TestMigration;
+
+    impl BackgroundMigration for TestMigration {
+        fn name(&self) -> &'static str {
+            NAME
+        }
+
+        fn source_col(&self) -> DBCol {
+            SOURCE_COL
+        }
+
+        fn migrate_entry(
+            &self,
+            key: &[u8],
+            value: &[u8],
+            store_update: &mut StoreUpdate,
+        ) -> io::Result<()> {
+            store_update.set(TARGET_COL, key, &value.iter().rev().copied().collect::<Vec<_>>());
+            Ok(())
+        }
+    }
+
+    fn read(store: &Store, key: &[u8]) -> Option<Vec<u8>> {
+        read_with_fallback(
+            store,
+            NAME,
+            || Ok(store.get(TARGET_COL, key)?.map(|value| value.to_vec())),
+            || Ok(store.get(SOURCE_COL, key)?.map(|value| value.iter().rev().copied().collect())),
+        )
+        .unwrap()
+    }
+
+    /// The migration resumes from its persisted progress, and the reads see the same values
+    /// before, during and after the migration.
+    #[test]
+    fn test_background_migration_batches() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        for i in 0u8..5 {
+            store_update.set(SOURCE_COL, &[i], &[i, 0]);
+        }
+        store_update.commit().unwrap();
+        let expected = |i: u8| Some(vec![0, i]);
+
+        assert_eq!(get_background_migration_status(&store, NAME).unwrap(), None);
+        assert_eq!(read(&store, &[3]), expected(3));
+
+        let status = run_background_migration_batch(&store, &TestMigration, 2).unwrap();
+        assert_eq!(
+            status,
+            BackgroundMigrationStatus::InProgress { next_key: vec![1, 0], num_migrated: 2 }
+        );
+        assert_eq!(store.get(TARGET_COL, &[1]).unwrap().as_deref(), Some(&[0, 1][..]));
+        assert_eq!(store.get(TARGET_COL, &[2]).unwrap(), None);
+        for i in 0u8..5 {
+            assert_eq!(read(&store, &[i]), expected(i));
+        }
+
+        let keep_going = AtomicBool::new(true);
+        let migrations: Vec<Box<dyn BackgroundMigration>> = vec![Box::new(TestMigration)];
+        run_background_migrations(&store, &migrations, 2, &keep_going).unwrap();
+        assert_eq!(
+            get_background_migration_status(&store, NAME).unwrap(),
+            Some(BackgroundMigrationStatus::Done { num_migrated: 5 })
+        );
+        for i in 0u8..5 {
+            assert_eq!(
+                store.get(TARGET_COL, &[i]).unwrap().map(|value| value.to_vec()),
+                expected(i)
+            );
+        }
+
+        // Once the migration is done, the source column isn't read anymore.
+        let mut store_update = store.store_update();
+        store_update.set(SOURCE_COL, &[5], &[5, 0]);
+        store_update.commit().unwrap();
+        assert_eq!(read(&store, &[5]), None);
+    }
+
+    #[test]
+    fn test_background_migration_interrupted() {
+        let store = create_test_store();
+        let mut store_update = store.store_update();
+        store_update.set(SOURCE_COL, &[0], &[0]);
+        store_update.commit().unwrap();
+
+        let keep_going = AtomicBool::new(false);
+        let migrations: Vec<Box<dyn BackgroundMigration>> = vec![Box::new(TestMigration)];
+        run_background_migrations(&store, &migrations, 2, &keep_going).unwrap();
+        assert_eq!(get_background_migration_status(&store, NAME).unwrap(), None);
+        assert!(!is_background_migration_done(&store, NAME).unwrap());
+    }
+}
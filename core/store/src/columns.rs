@@ -317,6 +317,43 @@ pub enum DBCol {
     /// - *Rows*: `CryptoHash`
     /// - *Column type*: `Vec<u8>`
     StateSyncNewChunks,
+    /// Index of the receipts executed by (incoming) or created by (outgoing) an account.
+    /// Only maintained if `save_account_receipts` is enabled in the client config.
+    /// - *Rows*: `AccountId` || `,` || height (u64, big endian) || receipt id (CryptoHash)
+    /// - *Column type*: `AccountReceiptIndexEntry`
+    AccountReceipts,
+    /// Secondary index of the transactions signed by an account.
+    /// Only maintained if `secondary_indexes.transactions_by_account` is enabled in the client config.
+    /// - *Rows*: `AccountId` || `,` || height (u64, big endian) || transaction hash (CryptoHash)
+    /// - *Column type*: `SecondaryIndexEntry`
+    TransactionsByAccount,
+    /// Secondary index of the processed blocks by their timestamp.
+    /// Only maintained if `secondary_indexes.blocks_by_timestamp` is enabled in the client config.
+    /// - *Rows*: timestamp in nanoseconds (u64, big endian) || block hash (CryptoHash)
+    /// - *Column type*: `BlockHeight`
+    BlocksByTimestamp,
+    /// Height of the last block in which an access key signed a transaction.
+    /// Only maintained if `save_access_key_usage` is enabled in the client config.
+    /// - *Rows*: `AccountId` || `,` || public key (borsh)
+    /// - *Column type*: `BlockHeight`
+    AccessKeyUsage,
+    /// Rotating log of the connections established and closed by the node and of the peers
+    /// it banned. Only the most recent entries are kept.
+    /// - *Rows*: sequence number (u64, big endian)
+    /// - *Column type*: network::schema::ConnectionAuditEntryRepr
+    ConnectionAuditLog,
+    /// History of `StateShardUIdMapping`, so that archival nodes resolve the State of a shard at
+    /// a given height with the database key prefix that was used at that height. A row holds the
+    /// mapping of the shard from the given height on, until the next row of the shard.
+    /// - *Rows*: `ShardUId` || `BlockHeight` (big endian)
+    /// - *Column type*: `ShardUId`
+    StateShardUIdMappingHistory,
+    /// Trie nodes recorded while creating the State of the children of a resharding, moved out
+    /// of memory once they take more than the configured limit. The rows are removed once the
+    /// state transition data of the child is assembled.
+    /// - *Rows*: `BlockHash` || `ShardUId` || trie node hash
+    /// - *Column type*: `Vec<u8>`
+    TrieRecorderSpill,
 }
 
 /// Defines different logical parts of a db key.
@@ -343,6 +380,8 @@ pub enum DBKeyType {
     PeerId,
     AccountId,
     TrieNodeOrValueHash,
+    /// Hash of a trie node kept outside of the State column. Used in DBCol::TrieRecorderSpill.
+    TrieNodeHash,
     TrieKey,
     ReceiptHash,
     TransactionHash,
@@ -352,6 +391,9 @@ pub enum DBKeyType {
     ColumnId,
     LatestWitnessesKey,
     LatestWitnessIndex,
+    BlockTimestamp,
+    PublicKey,
+    ConnectionAuditIndex,
 }
 
 impl DBCol {
@@ -462,7 +504,8 @@ impl DBCol {
             | DBCol::StateHeaders
             | DBCol::TransactionResultForBlock
             | DBCol::Transactions
-            | DBCol::StateShardUIdMapping => true,
+            | DBCol::StateShardUIdMapping
+            | DBCol::StateShardUIdMappingHistory => true,
 
             // TODO
             DBCol::ChallengedBlocks => false,
@@ -488,6 +531,14 @@ impl DBCol {
             // LatestChunkStateWitnesses stores the last N observed witnesses, used only for debugging.
             DBCol::LatestChunkStateWitnesses => false,
             DBCol::LatestWitnessesByIndex => false,
+            // AccountReceipts is an optional index of recent receipts.
+            DBCol::AccountReceipts => false,
+            // Secondary indexes are optional and only cover recent blocks.
+            DBCol::TransactionsByAccount | DBCol::BlocksByTimestamp => false,
+            // AccessKeyUsage is optional and only keeps the latest use of every key.
+            DBCol::AccessKeyUsage => false,
+            // TrieRecorderSpill only holds the temporary data of an ongoing resharding.
+            DBCol::TrieRecorderSpill => false,
             // Deprecated.
             DBCol::_ReceiptIdToShardId => false,
             // This can be re-constructed from the Chunks column, so no need to store in Cold DB.
@@ -499,6 +550,7 @@ impl DBCol {
             | DBCol::BlockHeight
             | DBCol::_Peers
             | DBCol::RecentOutboundConnections
+            | DBCol::ConnectionAuditLog
             | DBCol::BlockMerkleTree
             | DBCol::AccountAnnouncements
             | DBCol::EpochLightClientBlocks
@@ -598,6 +650,19 @@ impl DBCol {
             DBCol::StateShardUIdMapping => &[DBKeyType::ShardUId],
             DBCol::StateSyncHashes => &[DBKeyType::EpochId],
             DBCol::StateSyncNewChunks => &[DBKeyType::BlockHash],
+            DBCol::AccountReceipts => {
+                &[DBKeyType::AccountId, DBKeyType::BlockHeight, DBKeyType::ReceiptHash]
+            }
+            DBCol::TransactionsByAccount => {
+                &[DBKeyType::AccountId, DBKeyType::BlockHeight, DBKeyType::TransactionHash]
+            }
+            DBCol::BlocksByTimestamp => &[DBKeyType::BlockTimestamp, DBKeyType::BlockHash],
+            DBCol::AccessKeyUsage => &[DBKeyType::AccountId, DBKeyType::PublicKey],
+            DBCol::ConnectionAuditLog => &[DBKeyType::ConnectionAuditIndex],
+            DBCol::StateShardUIdMappingHistory => &[DBKeyType::ShardUId, DBKeyType::BlockHeight],
+            DBCol::TrieRecorderSpill => {
+                &[DBKeyType::BlockHash, DBKeyType::ShardUId, DBKeyType::TrieNodeHash]
+            }
         }
     }
 }
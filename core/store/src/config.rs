@@ -1,3 +1,4 @@
+use crate::encryption::StoreEncryptionConfig;
 use crate::trie::{
     DEFAULT_SHARD_CACHE_DELETIONS_QUEUE_CAPACITY, DEFAULT_SHARD_CACHE_TOTAL_SIZE_LIMIT,
 };
@@ -116,6 +117,10 @@ pub struct StoreConfig {
 
     // TODO (#9989): To be phased out in favor of state_snapshot_config
     pub state_snapshot_enabled: bool,
+
+    /// Encryption at rest of selected columns. Applies to the hot database only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<StoreEncryptionConfig>,
 }
 
 /// Config used to control state snapshot creation. This is used for state sync and resharding.
@@ -298,6 +303,8 @@ impl Default for StoreConfig {
 
             // TODO: To be phased out in favor of state_snapshot_config
             state_snapshot_enabled: false,
+
+            encryption: None,
         }
     }
 }
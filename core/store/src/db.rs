@@ -6,6 +6,7 @@ use std::io;
 pub(crate) mod rocksdb;
 
 mod colddb;
+mod encrypteddb;
 mod mixeddb;
 mod recoverydb;
 mod splitdb;
@@ -17,9 +18,10 @@ mod testdb;
 mod database_tests;
 
 pub use self::colddb::ColdDB;
+pub use self::encrypteddb::EncryptedDB;
 pub use self::mixeddb::{MixedDB, ReadOrder};
 pub use self::recoverydb::RecoveryDB;
-pub use self::rocksdb::RocksDB;
+pub use self::rocksdb::{RocksDB, SstFileInfo};
 pub use self::splitdb::SplitDB;
 
 pub use self::slice::DBSlice;
@@ -46,7 +48,15 @@ pub const STATE_SNAPSHOT_KEY: &[u8; 18] = b"STATE_SNAPSHOT_KEY";
 pub const FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS_KEY: &[u8] =
     b"FLAT_STATE_VALUES_INLINING_MIGRATION_STATUS";
 pub const STATE_TRANSITION_START_HEIGHTS: &[u8] = b"STATE_TRANSITION_START_HEIGHTS";
+// The resharding keys are prefixes, followed by the `ShardUId` of a parent shard so that the
+// parents split in the same resharding are tracked independently.
+pub const RESHARDING_STATUS_KEY: &[u8] = b"RESHARDING_STATUS";
+pub const RESHARDING_SPLIT_PROGRESS_KEY: &[u8] = b"RESHARDING_SPLIT_PROGRESS";
+pub const RESHARDING_SPLIT_VALIDATION_KEY: &[u8] = b"RESHARDING_SPLIT_VALIDATION";
+pub const RESHARDING_PROCESSED_BLOCKS_KEY: &[u8] = b"RESHARDING_PROCESSED_BLOCKS";
 pub const LATEST_WITNESSES_INFO: &[u8] = b"LATEST_WITNESSES_INFO";
+// Prefix of the status of a background migration, followed by the name of the migration.
+pub const BACKGROUND_MIGRATION_STATUS_KEY: &[u8] = b"BACKGROUND_MIGRATION_STATUS";
 
 #[derive(Default, Debug)]
 pub struct DBTransaction {
@@ -0,0 +1,182 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::db::{DBIterator, DBOp, DBSlice, DBTransaction, Database, StoreStatistics};
+use crate::encryption::StoreCipher;
+use crate::DBCol;
+
+/// A database which encrypts values of selected columns before writing them to the wrapped
+/// database and decrypts them on reads.
+///
+/// Keys are not encrypted. Reference-counted columns can't be encrypted, since their values are
+/// merged by the underlying database. Columns which are not selected are passed through as is.
+pub struct EncryptedDB {
+    inner: Arc<dyn Database>,
+    cipher: Arc<StoreCipher>,
+    columns: enum_map::EnumMap<DBCol, bool>,
+}
+
+impl EncryptedDB {
+    pub fn new(inner: Arc<dyn Database>, cipher: Arc<StoreCipher>, columns: &[DBCol]) -> Self {
+        let mut encrypted = enum_map::EnumMap::default();
+        for col in columns {
+            assert!(!col.is_rc(), "reference-counted column {col} can't be encrypted");
+            encrypted[*col] = true;
+        }
+        Self { inner, cipher, columns: encrypted }
+    }
+
+    fn is_encrypted(&self, col: DBCol) -> bool {
+        self.columns[col]
+    }
+
+    fn decrypt_iter<'a>(&'a self, col: DBCol, iter: DBIterator<'a>) -> DBIterator<'a> {
+        if !self.is_encrypted(col) {
+            return iter;
+        }
+        Box::new(iter.map(move |item| {
+            let (key, value) = item?;
+            let value = self.cipher.decrypt_value(col, &key, &value)?;
+            Ok((key, value.into_boxed_slice()))
+        }))
+    }
+
+    fn encrypt_op(&self, op: DBOp) -> io::Result<DBOp> {
+        let op = match op {
+            DBOp::Set { col, key, value } if self.is_encrypted(col) => {
+                let value = self.cipher.encrypt_value(col, &key, &value)?;
+                DBOp::Set { col, key, value }
+            }
+            DBOp::Insert { col, key, value } if self.is_encrypted(col) => {
+                // Encryption uses random nonces, so the overwrite check has to be done
+                // on the plain text values here rather than in the wrapped database.
+                if cfg!(debug_assertions) {
+                    if let Ok(Some(old_value)) = self.get_raw_bytes(col, &key) {
+                        super::assert_no_overwrite(col, &key, &value, &*old_value)
+                    }
+                }
+                let value = self.cipher.encrypt_value(col, &key, &value)?;
+                DBOp::Set { col, key, value }
+            }
+            op => op,
+        };
+        Ok(op)
+    }
+}
+
+impl Database for EncryptedDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        let value = self.inner.get_raw_bytes(col, key)?;
+        if !self.is_encrypted(col) {
+            return Ok(value);
+        }
+        value
+            .map(|value| Ok(DBSlice::from_vec(self.cipher.decrypt_value(col, key, &value)?)))
+            .transpose()
+    }
+
+    fn get_with_rc_stripped(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        // Reference-counted columns are never encrypted.
+        self.inner.get_with_rc_stripped(col, key)
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.decrypt_iter(col, self.inner.iter(col))
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        self.decrypt_iter(col, self.inner.iter_prefix(col, key_prefix))
+    }
+
+    fn iter_range<'a>(
+        &'a self,
+        col: DBCol,
+        lower_bound: Option<&[u8]>,
+        upper_bound: Option<&[u8]>,
+    ) -> DBIterator<'a> {
+        self.decrypt_iter(col, self.inner.iter_range(col, lower_bound, upper_bound))
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.decrypt_iter(col, self.inner.iter_raw_bytes(col))
+    }
+
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        let ops = transaction
+            .ops
+            .into_iter()
+            .map(|op| self.encrypt_op(op))
+            .collect::<io::Result<Vec<_>>>()?;
+        self.inner.write(DBTransaction { ops })
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.inner.flush()
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        self.inner.compact()
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        self.inner.get_store_statistics()
+    }
+
+    fn create_checkpoint(
+        &self,
+        path: &std::path::Path,
+        columns_to_keep: Option<&[DBCol]>,
+    ) -> anyhow::Result<()> {
+        self.inner.create_checkpoint(path, columns_to_keep)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EncryptedDB;
+    use crate::db::{DBTransaction, Database, TestDB};
+    use crate::encryption::StoreCipher;
+    use crate::DBCol;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_encrypted_columns() {
+        let inner = TestDB::new();
+        let db = EncryptedDB::new(
+            inner.clone(),
+            Arc::new(StoreCipher::new(&[1; 32])),
+            &[DBCol::BlockMisc],
+        );
+        let mut transaction = DBTransaction::new();
+        transaction.set(DBCol::BlockMisc, b"HEAD".to_vec(), b"secret".to_vec());
+        transaction.insert(DBCol::BlockMisc, b"TAIL".to_vec(), b"other secret".to_vec());
+        transaction.set(DBCol::Misc, b"key".to_vec(), b"public".to_vec());
+        db.write(transaction).unwrap();
+
+        // Selected columns are encrypted in the wrapped database.
+        let raw = inner.get_raw_bytes(DBCol::BlockMisc, b"HEAD").unwrap().unwrap();
+        assert_ne!(raw.as_slice(), b"secret");
+        let raw = inner.get_raw_bytes(DBCol::Misc, b"key").unwrap().unwrap();
+        assert_eq!(raw.as_slice(), b"public");
+
+        let value = db.get_raw_bytes(DBCol::BlockMisc, b"HEAD").unwrap().unwrap();
+        assert_eq!(value.as_slice(), b"secret");
+        let values: Vec<_> = db
+            .iter(DBCol::BlockMisc)
+            .map(|item| item.unwrap())
+            .map(|(key, value)| (key.to_vec(), value.to_vec()))
+            .collect();
+        assert_eq!(
+            values,
+            vec![
+                (b"HEAD".to_vec(), b"secret".to_vec()),
+                (b"TAIL".to_vec(), b"other secret".to_vec()),
+            ]
+        );
+
+        // Inserting the same value again passes the overwrite check.
+        let mut transaction = DBTransaction::new();
+        transaction.insert(DBCol::BlockMisc, b"TAIL".to_vec(), b"other secret".to_vec());
+        db.write(transaction).unwrap();
+    }
+}
@@ -298,6 +298,39 @@ impl RocksDB {
         Ok(())
     }
 
+    /// Returns the SST files currently making up the configured columns.
+    pub fn get_sst_files(&self) -> io::Result<Vec<SstFileInfo>> {
+        let columns: std::collections::HashMap<&str, DBCol> =
+            self.cf_handles().map(|(col, _)| (col_name(col), col)).collect();
+        let live_files = self.db.live_files().map_err(io::Error::other)?;
+        let sst_files = live_files
+            .into_iter()
+            .filter_map(|file| {
+                Some(SstFileInfo {
+                    col: *columns.get(file.column_family_name.as_str())?,
+                    name: file.name,
+                    level: file.level,
+                    size: file.size as u64,
+                    num_entries: file.num_entries,
+                    num_deletions: file.num_deletions,
+                    start_key: file.start_key,
+                })
+            })
+            .collect();
+        Ok(sst_files)
+    }
+
+    /// Returns RocksDB's estimate of the size of the live data of the column, i.e. without the
+    /// overwritten and deleted entries which are not compacted away yet.
+    pub fn get_estimated_live_data_size(&self, col: DBCol) -> io::Result<Option<u64>> {
+        self.db
+            .property_int_value_cf(
+                self.cf_handle(col)?,
+                ::rocksdb::properties::ESTIMATE_LIVE_DATA_SIZE,
+            )
+            .map_err(io::Error::other)
+    }
+
     #[tracing::instrument(
         target = "store::db::rocksdb",
         level = "trace",
@@ -686,6 +719,20 @@ impl RocksDB {
     }
 }
 
+/// An SST file of the database, see [RocksDB::get_sst_files].
+#[derive(Debug, Clone)]
+pub struct SstFileInfo {
+    pub col: DBCol,
+    pub name: String,
+    /// The LSM level of the file, 0 being the level of the files flushed from the memtables.
+    pub level: i32,
+    pub size: u64,
+    pub num_entries: u64,
+    pub num_deletions: u64,
+    /// The smallest key of the file.
+    pub start_key: Option<Vec<u8>>,
+}
+
 impl Drop for RocksDB {
     fn drop(&mut self) {
         if cfg!(feature = "single_thread_rocksdb") {
@@ -0,0 +1,306 @@
+//! Encryption at rest of selected database columns.
+//!
+//! Values are encrypted with XChaCha20-Poly1305 using a key read from an operator-provided key
+//! file. Every value is stored as `key_id || nonce || ciphertext || tag`. The 192-bit nonce is
+//! randomly generated on every write, which is large enough for random nonces to never repeat
+//! in practice, unlike the 96-bit nonces of AES-GCM. The column and the key of the cell are used
+//! as the additional authenticated data, so an encrypted value can't be moved to a different
+//! cell without being detected.
+//!
+//! Only the values are encrypted, the keys are stored in plain text so that iteration order and
+//! prefix lookups keep working.
+//!
+//! # Key rotation
+//!
+//! The `key_id` prefix identifies the key a value was encrypted with, so that values written
+//! with a previous key stay readable after the key is rotated:
+//!
+//! 1. Generate a new key file, set it as `key_file` and move the old one to
+//!    `previous_key_files`. New values are encrypted with the new key from now on.
+//! 2. Stop the node and run `neard database reencrypt`, which rewrites every value of the
+//!    encrypted columns and the validator key file with the new key.
+//! 3. Remove the old key from `previous_key_files`.
+
+use crate::DBCol;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use rand::RngCore;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Length of the encryption key in bytes.
+pub const ENCRYPTION_KEY_LEN: usize = 32;
+
+/// Length of the key identifier prepended to every encrypted value.
+const KEY_ID_LEN: usize = 4;
+
+/// Length of the XChaCha20-Poly1305 nonce.
+const NONCE_LEN: usize = 24;
+
+/// Additional authenticated data used for encrypting the validator key file.
+const VALIDATOR_KEY_AAD: &[u8] = b"validator_key";
+
+/// Configures encryption at rest of selected columns of the hot database.
+#[derive(Clone, Debug, Default, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct StoreEncryptionConfig {
+    /// Path to the file holding the hex-encoded 256-bit encryption key. If relative, resolved
+    /// relative to neard home directory.
+    pub key_file: PathBuf,
+    /// Key files of previously used keys. They are only used to decrypt values which haven't
+    /// been re-encrypted with the current key yet, see the module documentation.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub previous_key_files: Vec<PathBuf>,
+    /// Names of the columns to encrypt, e.g. `["BlockMisc", "Misc"]`. Reference-counted columns
+    /// can't be encrypted.
+    pub columns: Vec<String>,
+    /// Whether the validator key file is encrypted with the same key. Use
+    /// `neard database encrypt-file` to encrypt an existing key file.
+    pub encrypt_validator_key: bool,
+}
+
+impl StoreEncryptionConfig {
+    /// Resolves the configured column names.
+    pub fn columns(&self) -> io::Result<Vec<DBCol>> {
+        self.columns
+            .iter()
+            .map(|name| {
+                let col = <DBCol as strum::IntoEnumIterator>::iter()
+                    .find(|col| <&str>::from(col) == name.as_str())
+                    .ok_or_else(|| other_error(format!("unknown column {name}")))?;
+                // The version is read directly from RocksDB before the database is opened.
+                if col == DBCol::DbVersion {
+                    return Err(other_error(format!("column {col} can't be encrypted")));
+                }
+                if col.is_rc() {
+                    return Err(other_error(format!(
+                        "reference-counted column {col} can't be encrypted"
+                    )));
+                }
+                Ok(col)
+            })
+            .collect()
+    }
+
+    /// Loads the cipher from the current and previous key files.
+    pub fn load_cipher(&self, home_dir: &Path) -> io::Result<StoreCipher> {
+        let previous_keys = self
+            .previous_key_files
+            .iter()
+            .map(|path| read_key_file(&home_dir.join(path)))
+            .collect::<io::Result<Vec<_>>>()?;
+        Ok(StoreCipher::from_key_file(&home_dir.join(&self.key_file))?
+            .with_previous_keys(&previous_keys))
+    }
+}
+
+/// A single encryption key along with its identifier.
+struct StoreKey {
+    id: [u8; KEY_ID_LEN],
+    cipher: XChaCha20Poly1305,
+}
+
+impl StoreKey {
+    fn new(key: &[u8; ENCRYPTION_KEY_LEN]) -> Self {
+        let hash = near_primitives::hash::hash(key);
+        let id = hash.as_bytes()[..KEY_ID_LEN].try_into().unwrap();
+        Self { id, cipher: XChaCha20Poly1305::new(Key::from_slice(key)) }
+    }
+}
+
+/// XChaCha20-Poly1305 cipher used to encrypt database values.
+///
+/// Values are always encrypted with the current key. Previous keys are only used for decryption.
+pub struct StoreCipher {
+    current: StoreKey,
+    previous: Vec<StoreKey>,
+}
+
+impl StoreCipher {
+    pub fn new(key: &[u8; ENCRYPTION_KEY_LEN]) -> Self {
+        Self { current: StoreKey::new(key), previous: vec![] }
+    }
+
+    /// Allows decrypting values which were encrypted with any of the given keys.
+    pub fn with_previous_keys(mut self, keys: &[[u8; ENCRYPTION_KEY_LEN]]) -> Self {
+        self.previous.extend(keys.iter().map(StoreKey::new));
+        self
+    }
+
+    /// Reads the hex-encoded key from the given file.
+    pub fn from_key_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::new(&read_key_file(path)?))
+    }
+
+    /// Encrypts `value` stored in the cell identified by `col` and `key`.
+    pub fn encrypt_value(&self, col: DBCol, key: &[u8], value: &[u8]) -> io::Result<Vec<u8>> {
+        let _timer = crate::metrics::ENCRYPTION_LATENCY
+            .with_label_values(&["encrypt", col.into()])
+            .start_timer();
+        self.encrypt(&cell_aad(col, key), value)
+    }
+
+    /// Decrypts the value stored in the cell identified by `col` and `key`.
+    pub fn decrypt_value(&self, col: DBCol, key: &[u8], value: &[u8]) -> io::Result<Vec<u8>> {
+        let _timer = crate::metrics::ENCRYPTION_LATENCY
+            .with_label_values(&["decrypt", col.into()])
+            .start_timer();
+        self.decrypt(&cell_aad(col, key), value).map_err(|err| {
+            crate::metrics::DECRYPTION_FAILURES.with_label_values(&[col.into()]).inc();
+            err
+        })
+    }
+
+    /// Encrypts the contents of a validator key file.
+    pub fn encrypt_validator_key(&self, contents: &[u8]) -> io::Result<Vec<u8>> {
+        self.encrypt(VALIDATOR_KEY_AAD, contents)
+    }
+
+    /// Decrypts the contents of a validator key file.
+    pub fn decrypt_validator_key(&self, contents: &[u8]) -> io::Result<Vec<u8>> {
+        self.decrypt(VALIDATOR_KEY_AAD, contents)
+    }
+
+    fn encrypt(&self, aad: &[u8], plaintext: &[u8]) -> io::Result<Vec<u8>> {
+        let mut nonce = [0u8; NONCE_LEN];
+        rand::rngs::OsRng.fill_bytes(&mut nonce);
+        let ciphertext = self
+            .current
+            .cipher
+            .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+            .map_err(|_| other_error("encryption failed"))?;
+        let mut result = Vec::with_capacity(KEY_ID_LEN + NONCE_LEN + ciphertext.len());
+        result.extend_from_slice(&self.current.id);
+        result.extend_from_slice(&nonce);
+        result.extend_from_slice(&ciphertext);
+        Ok(result)
+    }
+
+    fn decrypt(&self, aad: &[u8], ciphertext: &[u8]) -> io::Result<Vec<u8>> {
+        if ciphertext.len() < KEY_ID_LEN + NONCE_LEN {
+            return Err(other_error("encrypted value is too short"));
+        }
+        let (key_id, ciphertext) = ciphertext.split_at(KEY_ID_LEN);
+        let (nonce, ciphertext) = ciphertext.split_at(NONCE_LEN);
+        let key = std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == key_id)
+            .ok_or_else(|| other_error("value is encrypted with an unknown key"))?;
+        key.cipher
+            .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| other_error("decryption failed"))
+    }
+}
+
+fn read_key_file(path: &Path) -> io::Result<[u8; ENCRYPTION_KEY_LEN]> {
+    let contents = std::fs::read_to_string(path)?;
+    let bytes = hex::decode(contents.trim()).map_err(|err| {
+        other_error(format!("invalid encryption key file {}: {err}", path.display()))
+    })?;
+    bytes.try_into().map_err(|_| {
+        other_error(format!(
+            "encryption key in {} must be {ENCRYPTION_KEY_LEN} bytes long",
+            path.display()
+        ))
+    })
+}
+
+fn cell_aad(col: DBCol, key: &[u8]) -> Vec<u8> {
+    let col: &str = col.into();
+    let mut aad = Vec::with_capacity(col.len() + 1 + key.len());
+    aad.extend_from_slice(col.as_bytes());
+    aad.push(0);
+    aad.extend_from_slice(key);
+    aad
+}
+
+fn other_error(msg: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::Other, msg.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StoreCipher, StoreEncryptionConfig};
+    use crate::DBCol;
+
+    #[test]
+    fn test_encrypt_decrypt_value() {
+        let cipher = StoreCipher::new(&[7; 32]);
+        let encrypted = cipher.encrypt_value(DBCol::BlockMisc, b"key", b"value").unwrap();
+        assert_ne!(&encrypted[..], b"value");
+        assert_eq!(cipher.decrypt_value(DBCol::BlockMisc, b"key", &encrypted).unwrap(), b"value");
+        // Nonces are random, so the same value is encrypted differently every time.
+        assert_ne!(encrypted, cipher.encrypt_value(DBCol::BlockMisc, b"key", b"value").unwrap());
+        // The value is bound to the cell it was written to.
+        assert!(cipher.decrypt_value(DBCol::BlockMisc, b"other", &encrypted).is_err());
+        assert!(cipher.decrypt_value(DBCol::Misc, b"key", &encrypted).is_err());
+        // A different key can't decrypt the value.
+        let other_cipher = StoreCipher::new(&[8; 32]);
+        assert!(other_cipher.decrypt_value(DBCol::BlockMisc, b"key", &encrypted).is_err());
+    }
+
+    #[test]
+    fn test_key_rotation() {
+        let old_cipher = StoreCipher::new(&[7; 32]);
+        let encrypted = old_cipher.encrypt_value(DBCol::BlockMisc, b"key", b"value").unwrap();
+
+        // Without the previous key the value can no longer be read.
+        let new_cipher = StoreCipher::new(&[8; 32]);
+        assert!(new_cipher.decrypt_value(DBCol::BlockMisc, b"key", &encrypted).is_err());
+
+        let new_cipher = new_cipher.with_previous_keys(&[[7; 32]]);
+        assert_eq!(
+            new_cipher.decrypt_value(DBCol::BlockMisc, b"key", &encrypted).unwrap(),
+            b"value"
+        );
+        // Re-encrypted values use the new key only.
+        let reencrypted = new_cipher.encrypt_value(DBCol::BlockMisc, b"key", b"value").unwrap();
+        assert!(old_cipher.decrypt_value(DBCol::BlockMisc, b"key", &reencrypted).is_err());
+        assert_eq!(
+            StoreCipher::new(&[8; 32])
+                .decrypt_value(DBCol::BlockMisc, b"key", &reencrypted)
+                .unwrap(),
+            b"value"
+        );
+    }
+
+    #[test]
+    fn test_key_file() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("store_key"), format!("{}\n", hex::encode([7; 32])))
+            .unwrap();
+        let config = StoreEncryptionConfig { key_file: "store_key".into(), ..Default::default() };
+        let cipher = config.load_cipher(dir.path()).unwrap();
+        let encrypted = cipher.encrypt_validator_key(b"{}").unwrap();
+        assert_eq!(StoreCipher::new(&[7; 32]).decrypt_validator_key(&encrypted).unwrap(), b"{}");
+
+        std::fs::write(dir.path().join("new_store_key"), hex::encode([8; 32])).unwrap();
+        let config = StoreEncryptionConfig {
+            key_file: "new_store_key".into(),
+            previous_key_files: vec!["store_key".into()],
+            ..Default::default()
+        };
+        let cipher = config.load_cipher(dir.path()).unwrap();
+        assert_eq!(cipher.decrypt_validator_key(&encrypted).unwrap(), b"{}");
+
+        std::fs::write(dir.path().join("store_key"), hex::encode([7; 16])).unwrap();
+        assert!(config.load_cipher(dir.path()).is_err());
+    }
+
+    #[test]
+    fn test_columns() {
+        let config = StoreEncryptionConfig {
+            columns: vec!["BlockMisc".to_string(), "Misc".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(config.columns().unwrap(), vec![DBCol::BlockMisc, DBCol::Misc]);
+        let config =
+            StoreEncryptionConfig { columns: vec!["State".to_string()], ..Default::default() };
+        assert!(config.columns().is_err());
+        let config = StoreEncryptionConfig {
+            columns: vec!["NoSuchColumn".to_string()],
+            ..Default::default()
+        };
+        assert!(config.columns().is_err());
+    }
+}
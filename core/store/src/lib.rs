@@ -48,10 +48,12 @@ use strum;
 
 pub mod adapter;
 pub mod archive;
+pub mod background_migration;
 mod columns;
 pub mod config;
 pub mod contract;
 pub mod db;
+pub mod encryption;
 pub mod flat;
 pub mod genesis;
 pub mod metadata;
@@ -138,7 +140,14 @@ impl NodeStorage {
         hot_storage: crate::db::RocksDB,
         cold_storage: Option<crate::db::RocksDB>,
     ) -> Self {
-        let hot_storage = Arc::new(hot_storage);
+        Self::from_hot_and_cold(Arc::new(hot_storage), cold_storage)
+    }
+
+    /// Constructs new object backed by given hot database and optional cold database.
+    fn from_hot_and_cold(
+        hot_storage: Arc<dyn Database>,
+        cold_storage: Option<crate::db::RocksDB>,
+    ) -> Self {
         let cold_storage = cold_storage.map(|storage| Arc::new(storage));
 
         let cold_db = if let Some(cold_storage) = cold_storage {
@@ -466,6 +475,14 @@ impl Store {
     }
 }
 
+/// Whether `from..to` is the range of all the keys with a `ShardUId` prefix.
+fn is_shard_uid_prefix_range(from: &[u8], to: &[u8]) -> bool {
+    let Ok(prefix) = <&[u8; 8]>::try_from(from) else {
+        return false;
+    };
+    to == ShardUId::get_upper_bound_db_key(prefix)
+}
+
 /// Keeps track of current changes to the database and can commit all of them to the database.
 pub struct StoreUpdate {
     transaction: DBTransaction,
@@ -619,8 +636,15 @@ impl StoreUpdate {
 
     /// Deletes the given key range from the database including `from`
     /// and excluding `to` keys.
+    ///
+    /// The State column is reference counted, so a range of it can only be deleted if no
+    /// reference from outside the range remains, which is the case for all the keys of a
+    /// `ShardUId` prefix once no shard maps to it anymore.
     pub fn delete_range(&mut self, column: DBCol, from: &[u8], to: &[u8]) {
-        assert!(column != DBCol::State, "can't range delete State column");
+        assert!(
+            column != DBCol::State || is_shard_uid_prefix_range(from, to),
+            "can't range delete State column, except for a whole ShardUId prefix"
+        );
         self.transaction.delete_range(column, from.to_vec(), to.to_vec());
     }
 
@@ -1174,7 +1198,7 @@ mod tests {
     use near_primitives::hash::CryptoHash;
     use near_vm_runner::CompiledContractInfo;
 
-    use super::{DBCol, NodeStorage, Store};
+    use super::{DBCol, NodeStorage, ShardUId, Store};
 
     fn test_clear_column(store: Store) {
         assert_eq!(store.get(DBCol::State, &[1; 8]).unwrap(), None);
@@ -1205,6 +1229,38 @@ mod tests {
         test_clear_column(crate::test_utils::create_test_store());
     }
 
+    /// Only whole `ShardUId` prefixes of the State column can be range deleted.
+    #[test]
+    fn delete_range_state_shard_uid_prefix() {
+        let store = crate::test_utils::create_test_store();
+        let prefix = ShardUId { version: 3, shard_id: 1 }.to_bytes();
+        let other_prefix = ShardUId { version: 3, shard_id: 2 }.to_bytes();
+        let state_key = |prefix: [u8; 8]| [&prefix[..], &[1; 32]].concat();
+        let mut store_update = store.store_update();
+        store_update.increment_refcount(DBCol::State, &state_key(prefix), &[1]);
+        store_update.increment_refcount(DBCol::State, &state_key(other_prefix), &[2]);
+        store_update.commit().unwrap();
+
+        let mut store_update = store.store_update();
+        store_update.delete_range(
+            DBCol::State,
+            &prefix,
+            &ShardUId::get_upper_bound_db_key(&prefix),
+        );
+        store_update.commit().unwrap();
+        assert_eq!(store.get(DBCol::State, &state_key(prefix)).unwrap(), None);
+        assert!(store.get(DBCol::State, &state_key(other_prefix)).unwrap().is_some());
+    }
+
+    #[test]
+    #[should_panic(expected = "can't range delete State column")]
+    fn delete_range_state_partial_prefix() {
+        let store = crate::test_utils::create_test_store();
+        let prefix = ShardUId { version: 3, shard_id: 1 }.to_bytes();
+        let mut store_update = store.store_update();
+        store_update.delete_range(DBCol::State, &prefix, &[&prefix[..], &[1; 32]].concat());
+    }
+
     /// Asserts that elements in the vector are sorted.
     #[track_caller]
     fn assert_sorted(want_count: usize, keys: Vec<Box<[u8]>>) {
@@ -20,6 +20,25 @@ pub(crate) static DATABASE_OP_LATENCY_HIST: LazyLock<HistogramVec> = LazyLock::n
     .unwrap()
 });
 
+pub(crate) static ENCRYPTION_LATENCY: LazyLock<HistogramVec> = LazyLock::new(|| {
+    try_create_histogram_vec(
+        "near_database_encryption_latency_by_op_and_column",
+        "Latency of encrypting and decrypting database values by operation and column.",
+        &["op", "column"],
+        Some(vec![0.000001, 0.000005, 0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.01]),
+    )
+    .unwrap()
+});
+
+pub(crate) static DECRYPTION_FAILURES: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    try_create_int_counter_vec(
+        "near_database_decryption_failures_total",
+        "Number of database values which failed to decrypt, by column.",
+        &["column"],
+    )
+    .unwrap()
+});
+
 // TODO(#9054): Rename the metric to be consistent with "accounting cache".
 pub static CHUNK_CACHE_HITS: LazyLock<IntCounterVec> = LazyLock::new(|| {
     try_create_int_counter_vec(
@@ -567,6 +586,15 @@ pub static STORAGE_MISSING_CONTRACTS_COUNT: LazyLock<IntCounterVec> = LazyLock::
     .unwrap()
 });
 
+pub static BACKGROUND_MIGRATION_MIGRATED_ENTRIES: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    try_create_int_gauge_vec(
+        "near_background_migration_migrated_entries",
+        "Number of entries of the source column converted by each background migration.",
+        &["migration"],
+    )
+    .unwrap()
+});
+
 fn export_store_stats(store: &Store, temperature: Temperature) {
     if let Some(stats) = store.get_store_statistics() {
         tracing::debug!(target:"metrics", "Exporting the db metrics for {temperature:?} store.");
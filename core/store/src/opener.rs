@@ -1,6 +1,7 @@
 use crate::config::ArchivalConfig;
 use crate::db::rocksdb::snapshot::{Snapshot, SnapshotError, SnapshotRemoveError};
 use crate::db::rocksdb::RocksDB;
+use crate::db::EncryptedDB;
 use crate::metadata::{DbKind, DbMetadata, DbVersion, DB_VERSION};
 use crate::{DBCol, DBTransaction, Mode, NodeStorage, Store, StoreConfig, Temperature};
 use std::sync::Arc;
@@ -175,6 +176,9 @@ pub struct StoreOpener<'a> {
 
     /// Archival config. This is set to a valid config for archival nodes.
     archival_config: Option<ArchivalConfig<'a>>,
+
+    /// Home directory used to resolve the path of the encryption key file.
+    home_dir: std::path::PathBuf,
 }
 
 /// Opener for a single RocksDB instance.
@@ -215,6 +219,7 @@ impl<'a> StoreOpener<'a> {
                 .flatten(),
             archival_config,
             migrator: None,
+            home_dir: home_dir.to_path_buf(),
         }
     }
 
@@ -291,7 +296,16 @@ impl<'a> StoreOpener<'a> {
             .transpose()?
             .map(|(db, _)| db);
 
-        let storage = NodeStorage::from_rocksdb(hot_db, cold_db);
+        let storage = match &self.hot.config.encryption {
+            Some(encryption) => {
+                let columns = encryption.columns()?;
+                let cipher = Arc::new(encryption.load_cipher(&self.home_dir)?);
+                tracing::info!(target: "db_opener", ?columns, "Encrypting columns of the hot database");
+                let hot_db = EncryptedDB::new(Arc::new(hot_db), cipher, &columns);
+                NodeStorage::from_hot_and_cold(Arc::new(hot_db), cold_db)
+            }
+            None => NodeStorage::from_rocksdb(hot_db, cold_db),
+        };
 
         hot_snapshot.remove()?;
         cold_snapshot.remove()?;
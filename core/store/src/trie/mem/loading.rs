@@ -2,7 +2,7 @@ use super::arena::single_thread::STArena;
 use super::mem_tries::MemTries;
 use super::node::MemTrieNodeId;
 use crate::adapter::StoreAdapter;
-use crate::flat::FlatStorageStatus;
+use crate::flat::{FlatStateChanges, FlatStorageStatus};
 use crate::trie::mem::arena::Arena;
 use crate::trie::mem::construction::TrieConstructor;
 use crate::trie::mem::mem_trie_update::TrackingMode;
@@ -156,18 +156,12 @@ pub fn load_trie_from_flat_state_and_delta(
             let old_state_root = get_state_root(store, prev_hash, shard_uid)?;
             let new_state_root = get_state_root(store, hash, shard_uid)?;
 
-            let mut trie_update = mem_tries.update(old_state_root, TrackingMode::None)?;
-            for (key, value) in changes.0 {
-                match value {
-                    Some(value) => {
-                        trie_update.insert_memtrie_only(&key, value)?;
-                    }
-                    None => trie_update.generic_delete(0, &key)?,
-                };
-            }
-
-            let mem_trie_changes = trie_update.to_mem_trie_changes_only();
-            let new_root_after_apply = mem_tries.apply_memtrie_changes(height, &mem_trie_changes);
+            let new_root_after_apply = apply_flat_state_changes_to_memtrie(
+                &mut mem_tries,
+                old_state_root,
+                height,
+                changes,
+            )?;
             assert_eq!(new_root_after_apply, new_state_root);
         }
         debug!(target: "memtrie", %shard_uid, "Applied memtrie changes for height {}", height);
@@ -177,6 +171,28 @@ pub fn load_trie_from_flat_state_and_delta(
     Ok(mem_tries)
 }
 
+/// Applies the flat state changes of a block on top of `old_state_root` and returns the new
+/// state root, which is added to the memtrie at `block_height`.
+pub fn apply_flat_state_changes_to_memtrie(
+    mem_tries: &mut MemTries,
+    old_state_root: StateRoot,
+    block_height: BlockHeight,
+    changes: FlatStateChanges,
+) -> Result<StateRoot, StorageError> {
+    let mut trie_update = mem_tries.update(old_state_root, TrackingMode::None)?;
+    for (key, value) in changes.0 {
+        match value {
+            Some(value) => {
+                trie_update.insert_memtrie_only(&key, value)?;
+            }
+            None => trie_update.generic_delete(0, &key)?,
+        };
+    }
+
+    let mem_trie_changes = trie_update.to_mem_trie_changes_only();
+    Ok(mem_tries.apply_memtrie_changes(block_height, &mem_trie_changes))
+}
+
 #[cfg(test)]
 mod tests {
     use super::load_trie_from_flat_state_and_delta;
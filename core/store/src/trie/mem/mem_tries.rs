@@ -138,6 +138,11 @@ impl MemTries {
         })
     }
 
+    /// Returns whether the given state root is present in the memtrie.
+    pub fn contains_root(&self, state_root: &StateRoot) -> bool {
+        self.roots.contains_key(state_root)
+    }
+
     /// Expires all trie roots corresponding to a height smaller than
     /// `block_height`. This internally manages refcounts. If a trie root
     /// is expired but is still used at a higher height, it will still be
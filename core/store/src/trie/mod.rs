@@ -43,7 +43,9 @@ use std::hash::Hash;
 use std::ops::DerefMut;
 use std::str;
 use std::sync::{Arc, RwLock, RwLockReadGuard};
-pub use trie_recording::{SubtreeSize, TrieRecorder, TrieRecorderStats};
+pub use trie_recording::{
+    delete_trie_recorder_spill, SubtreeSize, TrieRecorder, TrieRecorderStats,
+};
 use trie_storage_update::{
     TrieStorageNodeWithSize, TrieStorageUpdate, UpdatedTrieStorageNodeWithSize,
 };
@@ -61,6 +63,7 @@ mod raw_node;
 pub mod receipts_column_helper;
 pub mod resharding_v2;
 mod shard_tries;
+pub mod state_consistency;
 mod state_parts;
 mod state_snapshot;
 mod trie_recording;
@@ -590,6 +593,19 @@ impl TrieChanges {
     pub fn deletions(&self) -> &[TrieRefcountSubtraction] {
         self.deletions.as_slice()
     }
+
+    /// Returns the changes without their deletions. Used when the old nodes keep being
+    /// referenced by another trie, so that garbage collecting the changes doesn't
+    /// dereference them.
+    pub fn without_deletions(&self) -> TrieChanges {
+        TrieChanges {
+            old_root: self.old_root,
+            new_root: self.new_root,
+            insertions: self.insertions.clone(),
+            deletions: vec![],
+            mem_trie_changes: None,
+        }
+    }
 }
 
 /// Result of applying state part to Trie.
@@ -735,6 +751,14 @@ impl Trie {
         self.recording_reads_with_recorder(recorder)
     }
 
+    /// Like [Self::recording_reads_with_proof_size_limit], but the recorder only accounts for
+    /// the size of the nodes accessed, see [TrieRecorder::new_sizes_only], so that
+    /// [Self::recorded_storage] returns `None`.
+    pub fn recording_read_sizes_with_proof_size_limit(&self, proof_size_limit: usize) -> Self {
+        let recorder = RefCell::new(TrieRecorder::new_sizes_only(Some(proof_size_limit)));
+        self.recording_reads_with_recorder(recorder)
+    }
+
     pub fn recording_reads_with_recorder(&self, recorder: RefCell<TrieRecorder>) -> Self {
         let mut trie = Self::new_with_memtries(
             self.storage.clone(),
@@ -751,9 +775,14 @@ impl Trie {
         self.recorder
     }
 
-    /// Takes the recorded state proof out of the trie.
-    pub fn recorded_storage(&self) -> Option<PartialStorage> {
-        self.recorder.as_ref().map(|recorder| recorder.borrow_mut().recorded_storage())
+    /// Takes the recorded state proof out of the trie, if the recorder keeps it.
+    pub fn recorded_storage(&self) -> Result<Option<PartialStorage>, StorageError> {
+        self.recorder
+            .as_ref()
+            .filter(|recorder| recorder.borrow().keeps_nodes())
+            .map(|recorder| recorder.borrow_mut().recorded_storage())
+            .transpose()
+            .map_err(|_| StorageError::StorageInternalError)
     }
 
     /// Returns the in-memory size of the recorded state proof. Useful for checking size limit of state witness
@@ -1764,13 +1793,14 @@ impl Trie {
 
     /// Splits the trie, separating entries by the boundary account.
     /// Leaves the left or right part of the trie, depending on the retain mode.
+    /// Only the on-disk trie is used, so it works when memtries are not loaded.
     ///
-    /// Returns the new root hash of the trie.
+    /// Returns the trie changes, which include the new root hash of the trie.
     pub fn retain_split_shard(
         &self,
         boundary_account: &AccountId,
         retain_mode: RetainMode,
-    ) -> Result<StateRoot, StorageError> {
+    ) -> Result<TrieChanges, StorageError> {
         let mut trie_update = TrieStorageUpdate::new(&self);
         let root_node = self.move_node_to_mutable(&mut trie_update, &self.root)?;
         trie_update.retain_split_shard(boundary_account, retain_mode);
@@ -1778,8 +1808,7 @@ impl Trie {
         {
             self.memory_usage_verify(&trie_update, GenericNodeOrIndex::Updated(root_node.0));
         }
-        let result = trie_update.flatten_nodes(&self.root, root_node.0)?;
-        Ok(result.new_root)
+        trie_update.flatten_nodes(&self.root, root_node.0)
     }
 }
 
@@ -2257,7 +2286,7 @@ mod tests {
             tries.get_trie_for_shard(ShardUId::single_shard(), root).recording_reads_new_recorder();
         trie2.get(b"dog").unwrap();
         trie2.get(b"horse").unwrap();
-        let partial_storage = trie2.recorded_storage();
+        let partial_storage = trie2.recorded_storage().unwrap();
 
         let trie3 = Trie::from_recorded_storage(partial_storage.unwrap(), root, false);
 
@@ -2288,7 +2317,7 @@ mod tests {
                 .recording_reads_new_recorder();
             trie2.get(b"doge").unwrap();
             // record extension, branch and one leaf with value, but not the other
-            assert_eq!(trie2.recorded_storage().unwrap().nodes.len(), 4);
+            assert_eq!(trie2.recorded_storage().unwrap().unwrap().nodes.len(), 4);
         }
 
         {
@@ -2298,7 +2327,7 @@ mod tests {
             let updates = vec![(b"doge".to_vec(), None)];
             trie2.update(updates).unwrap();
             // record extension, branch and both leaves, but not the value.
-            assert_eq!(trie2.recorded_storage().unwrap().nodes.len(), 4);
+            assert_eq!(trie2.recorded_storage().unwrap().unwrap().nodes.len(), 4);
         }
 
         {
@@ -2308,7 +2337,7 @@ mod tests {
             let updates = vec![(b"dodo".to_vec(), Some(b"asdf".to_vec()))];
             trie2.update(updates).unwrap();
             // record extension and branch, but not leaves
-            assert_eq!(trie2.recorded_storage().unwrap().nodes.len(), 2);
+            assert_eq!(trie2.recorded_storage().unwrap().unwrap().nodes.len(), 2);
         }
     }
 
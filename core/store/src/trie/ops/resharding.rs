@@ -309,6 +309,18 @@ where
     }
 }
 
+/// Returns whether `key` belongs to the child selected by `retain_mode` when a shard is split
+/// at `boundary_account`. Keys which are copied to both children belong to both of them.
+pub fn is_key_retained_after_split(
+    key: &[u8],
+    boundary_account: &AccountId,
+    retain_mode: RetainMode,
+) -> bool {
+    boundary_account_to_intervals(boundary_account, retain_mode)
+        .iter()
+        .any(|interval| interval.start.as_slice() <= key && key < interval.end.as_slice())
+}
+
 // Expose function that takes custom ranges for testing.
 #[cfg(test)]
 #[allow(private_bounds)]
@@ -331,7 +343,9 @@ mod tests {
     use near_primitives::trie_key::col;
     use near_primitives::types::AccountId;
 
-    use super::{append_key, boundary_account_to_intervals, RetainMode};
+    use super::{
+        append_key, boundary_account_to_intervals, is_key_retained_after_split, RetainMode,
+    };
 
     #[test]
     fn test_boundary_account_to_intervals() {
@@ -392,4 +406,20 @@ mod tests {
             assert_eq!(actual, expected, "Mismatch in key: {:?}", column_name);
         }
     }
+
+    #[test]
+    fn test_is_key_retained_after_split() {
+        let alice_account: AccountId = "alice.near".parse().unwrap();
+        let retained = |key: &[u8]| {
+            (
+                is_key_retained_after_split(key, &alice_account, RetainMode::Left),
+                is_key_retained_after_split(key, &alice_account, RetainMode::Right),
+            )
+        };
+        assert_eq!(retained(&append_key(col::ACCOUNT, &"alice".parse().unwrap())), (true, false));
+        assert_eq!(retained(&append_key(col::ACCOUNT, &alice_account)), (false, true));
+        assert_eq!(retained(&append_key(col::ACCESS_KEY, &"bob".parse().unwrap())), (false, true));
+        assert_eq!(retained(&[col::DELAYED_RECEIPT_OR_INDICES, 1]), (true, true));
+        assert_eq!(retained(&[col::BUFFERED_RECEIPT, 1]), (true, false));
+    }
 }
@@ -111,7 +111,7 @@ fn run(initial_entries: Vec<(Vec<u8>, Vec<u8>)>, retain_multi_ranges: Vec<Range<
     let mut trie_changes = update.to_trie_changes();
     let memtrie_changes = trie_changes.mem_trie_changes.take().unwrap();
     let mem_state_root = memtries.apply_memtrie_changes(1, &memtrie_changes);
-    let proof = trie_recorder.recorded_storage();
+    let proof = trie_recorder.recorded_storage().unwrap();
 
     // Use proof to verify split
     let partial_trie = Trie::from_recorded_storage(proof, initial_state_root, false);
@@ -119,12 +119,23 @@ impl ShardTries {
         state_root: StateRoot,
         is_view: bool,
         block_hash: Option<CryptoHash>,
+        block_height: Option<BlockHeight>,
     ) -> Trie {
         let storage: Arc<dyn TrieStorage> =
-            if let Some(cache) = self.get_trie_cache_for(shard_uid, is_view) {
-                Arc::new(self.create_caching_storage(cache, shard_uid, is_view))
-            } else {
-                Arc::new(TrieDBStorage::new(self.0.store.clone(), shard_uid))
+            match (self.get_trie_cache_for(shard_uid, is_view), block_height) {
+                (Some(cache), None) => {
+                    Arc::new(self.create_caching_storage(cache, shard_uid, is_view))
+                }
+                (Some(cache), Some(block_height)) => Arc::new(
+                    self.create_caching_storage(cache, shard_uid, is_view)
+                        .with_block_height(block_height),
+                ),
+                (None, None) => Arc::new(TrieDBStorage::new(self.0.store.clone(), shard_uid)),
+                (None, Some(block_height)) => Arc::new(TrieDBStorage::new_at_height(
+                    self.0.store.clone(),
+                    shard_uid,
+                    block_height,
+                )),
             };
         let flat_storage_chunk_view = block_hash
             .and_then(|block_hash| self.0.flat_storage_manager.chunk_view(shard_uid, block_hash));
@@ -135,7 +146,7 @@ impl ShardTries {
     }
 
     pub fn get_trie_for_shard(&self, shard_uid: ShardUId, state_root: StateRoot) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, false, None)
+        self.get_trie_for_shard_internal(shard_uid, state_root, false, None, None)
     }
 
     pub fn get_trie_with_block_hash_for_shard_from_snapshot(
@@ -161,11 +172,23 @@ impl ShardTries {
         block_hash: &CryptoHash,
         is_view: bool,
     ) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, is_view, Some(*block_hash))
+        self.get_trie_for_shard_internal(shard_uid, state_root, is_view, Some(*block_hash), None)
     }
 
     pub fn get_view_trie_for_shard(&self, shard_uid: ShardUId, state_root: StateRoot) -> Trie {
-        self.get_trie_for_shard_internal(shard_uid, state_root, true, None)
+        self.get_trie_for_shard_internal(shard_uid, state_root, true, None, None)
+    }
+
+    /// View trie of the State of the shard at the block at `block_height`, read with the
+    /// `ShardUId` mapping in use at that height. Needed by archival nodes for the State of the
+    /// blocks before a change of the mapping, e.g. before a resharding.
+    pub fn get_view_trie_for_shard_at_height(
+        &self,
+        shard_uid: ShardUId,
+        state_root: StateRoot,
+        block_height: BlockHeight,
+    ) -> Trie {
+        self.get_trie_for_shard_internal(shard_uid, state_root, true, None, Some(block_height))
     }
 
     pub fn store_update(&self) -> TrieStoreUpdateAdapter<'static> {
@@ -0,0 +1,147 @@
+//! Comparison of the different views of the state of a shard. The memtrie, the flat storage and
+//! the on-disk trie must all hold the same key-value pairs for the same state, which is checked
+//! by the integration tests of resharding and state sync.
+
+use crate::adapter::flat_store::FlatStoreAdapter;
+use crate::adapter::trie_store::TrieStoreAdapter;
+use crate::trie::iterator::TrieItem;
+use crate::{ShardUId, StorageError};
+use near_primitives::state::FlatStateValue;
+use std::cmp::Ordering;
+use std::iter::Peekable;
+
+/// The first key at which two views of the state differ.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDivergence {
+    pub key: Vec<u8>,
+    /// Value of the key in the first view, `None` if the key is missing.
+    pub first: Option<Vec<u8>>,
+    /// Value of the key in the second view, `None` if the key is missing.
+    pub second: Option<Vec<u8>>,
+}
+
+/// Walks two iterators over the key-value pairs of a state, both sorted by key, and returns the
+/// first key which is missing in one of them or has different values.
+pub fn find_state_divergence<E>(
+    first: impl IntoIterator<Item = Result<TrieItem, E>>,
+    second: impl IntoIterator<Item = Result<TrieItem, E>>,
+) -> Result<Option<StateDivergence>, E> {
+    fn next_item<E>(
+        iter: &mut Peekable<impl Iterator<Item = Result<TrieItem, E>>>,
+    ) -> Result<Option<TrieItem>, E> {
+        iter.next().transpose()
+    }
+
+    let mut first = first.into_iter().peekable();
+    let mut second = second.into_iter().peekable();
+    loop {
+        let ordering = match (first.peek(), second.peek()) {
+            (None, None) => return Ok(None),
+            (Some(Err(_)), _) => return Err(next_item(&mut first).err().unwrap()),
+            (_, Some(Err(_))) => return Err(next_item(&mut second).err().unwrap()),
+            (Some(Ok(_)), None) => Ordering::Less,
+            (None, Some(Ok(_))) => Ordering::Greater,
+            (Some(Ok((first_key, _))), Some(Ok((second_key, _)))) => first_key.cmp(second_key),
+        };
+        match ordering {
+            Ordering::Less => {
+                let (key, value) = next_item(&mut first)?.unwrap();
+                return Ok(Some(StateDivergence { key, first: Some(value), second: None }));
+            }
+            Ordering::Greater => {
+                let (key, value) = next_item(&mut second)?.unwrap();
+                return Ok(Some(StateDivergence { key, first: None, second: Some(value) }));
+            }
+            Ordering::Equal => {
+                let (key, first_value) = next_item(&mut first)?.unwrap();
+                let (_, second_value) = next_item(&mut second)?.unwrap();
+                if first_value != second_value {
+                    return Ok(Some(StateDivergence {
+                        key,
+                        first: Some(first_value),
+                        second: Some(second_value),
+                    }));
+                }
+            }
+        }
+    }
+}
+
+/// Iterates over the state of the shard committed to the flat storage, which is the state at
+/// the flat storage head, reading the values stored by reference from the on-disk trie.
+pub fn iter_flat_state_values<'a>(
+    flat_store: &'a FlatStoreAdapter,
+    trie_store: &'a TrieStoreAdapter,
+    shard_uid: ShardUId,
+) -> impl Iterator<Item = Result<TrieItem, StorageError>> + 'a {
+    flat_store.iter(shard_uid).map(move |item| {
+        let (key, value) = item?;
+        let value = match value {
+            FlatStateValue::Ref(value_ref) => trie_store.get(shard_uid, &value_ref.hash)?.to_vec(),
+            FlatStateValue::Inlined(value) => value,
+        };
+        Ok((key, value))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{find_state_divergence, StateDivergence};
+    use crate::trie::iterator::TrieItem;
+
+    fn items(items: &[(&str, &str)]) -> Vec<Result<TrieItem, ()>> {
+        items
+            .iter()
+            .map(|(key, value)| Ok((key.as_bytes().to_vec(), value.as_bytes().to_vec())))
+            .collect()
+    }
+
+    #[test]
+    fn test_find_state_divergence() {
+        let state = items(&[("a", "1"), ("b", "2"), ("c", "3")]);
+        assert_eq!(find_state_divergence(state.clone(), state.clone()), Ok(None));
+        assert_eq!(find_state_divergence(items(&[]), items(&[])), Ok(None));
+
+        let different_value = items(&[("a", "1"), ("b", "x"), ("c", "3")]);
+        assert_eq!(
+            find_state_divergence(state.clone(), different_value),
+            Ok(Some(StateDivergence {
+                key: b"b".to_vec(),
+                first: Some(b"2".to_vec()),
+                second: Some(b"x".to_vec()),
+            }))
+        );
+
+        let missing_key = items(&[("a", "1"), ("c", "3")]);
+        assert_eq!(
+            find_state_divergence(state.clone(), missing_key.clone()),
+            Ok(Some(StateDivergence {
+                key: b"b".to_vec(),
+                first: Some(b"2".to_vec()),
+                second: None
+            }))
+        );
+        assert_eq!(
+            find_state_divergence(missing_key, state.clone()),
+            Ok(Some(StateDivergence {
+                key: b"b".to_vec(),
+                first: None,
+                second: Some(b"2".to_vec())
+            }))
+        );
+
+        let extra_key = items(&[("a", "1"), ("b", "2"), ("c", "3"), ("d", "4")]);
+        assert_eq!(
+            find_state_divergence(state.clone(), extra_key),
+            Ok(Some(StateDivergence {
+                key: b"d".to_vec(),
+                first: None,
+                second: Some(b"4".to_vec())
+            }))
+        );
+
+        let mut with_error = state.clone();
+        with_error[1] = Err(());
+        assert_eq!(find_state_divergence(state, with_error), Err(()));
+    }
+}
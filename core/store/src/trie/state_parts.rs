@@ -83,7 +83,7 @@ impl Trie {
     ) -> Result<PartialState, StorageError> {
         let with_recording = self.recording_reads_new_recorder();
         with_recording.visit_nodes_for_state_part(part_id)?;
-        let recorded = with_recording.recorded_storage().unwrap();
+        let recorded = with_recording.recorded_storage()?.unwrap();
         Ok(recorded.nodes)
     }
 
@@ -155,7 +155,7 @@ impl Trie {
         let nibbles_end =
             recording_trie.find_state_part_boundary(part_id.idx + 1, part_id.total)?;
         let boundaries_read_duration = boundaries_read_timer.stop_and_record();
-        let recorded_trie = recording_trie.recorded_storage().unwrap();
+        let recorded_trie = recording_trie.recorded_storage()?.unwrap();
 
         tracing::debug!(
             target: "state-parts",
@@ -834,7 +834,7 @@ mod tests {
                     assert_matches!(trie.get(&left_key_boundary), Ok(Some(_)));
                 }
                 let PartialState::TrieValues(proof_nodes) =
-                    trie_recording.recorded_storage().unwrap().nodes;
+                    trie_recording.recorded_storage().unwrap().unwrap().nodes;
                 let proof_size = proof_nodes.iter().map(|node| node.len()).sum::<usize>() as u64;
                 assert!(
                     proof_size <= max_proof_overhead,
@@ -1,10 +1,13 @@
-use crate::{NibbleSlice, PartialStorage, RawTrieNode, RawTrieNodeWithSize};
+use crate::{
+    DBCol, NibbleSlice, PartialStorage, RawTrieNode, RawTrieNodeWithSize, Store, StoreUpdate,
+};
 use borsh::BorshDeserialize;
 use near_primitives::challenge::PartialState;
 use near_primitives::hash::CryptoHash;
 use near_primitives::trie_key::col::ALL_COLUMNS_WITH_NAMES;
 use near_primitives::types::AccountId;
 use std::collections::{HashMap, HashSet, VecDeque};
+use std::io;
 use std::sync::Arc;
 
 /// A simple struct to capture a state proof as it's being accumulated.
@@ -24,6 +27,47 @@ pub struct TrieRecorder {
     code_len_counter: usize,
     /// Account IDs for which the code should be recorded.
     pub codes_to_record: HashSet<AccountId>,
+    /// Total size of the nodes in `recorded`.
+    memory_size: usize,
+    /// Set when the recorded nodes are moved to the store past a memory limit.
+    spill: Option<TrieRecorderSpill>,
+    /// Set when only the size of the recorded nodes is accounted for: holds their hashes and
+    /// `recorded` stays empty.
+    sizes_only: Option<HashSet<CryptoHash>>,
+}
+
+/// Keeps track of the nodes moved out of memory to `DBCol::TrieRecorderSpill`.
+struct TrieRecorderSpill {
+    store: Store,
+    /// Prefix of the keys of the spilled nodes, unique to the recording.
+    key_prefix: Vec<u8>,
+    /// Size of the nodes kept in memory above which they are spilled.
+    memory_limit: usize,
+    /// Hashes of the nodes written to the store.
+    spilled: HashSet<CryptoHash>,
+}
+
+impl TrieRecorderSpill {
+    fn key(&self, hash: &CryptoHash) -> Vec<u8> {
+        [&self.key_prefix[..], hash.as_ref()].concat()
+    }
+
+    fn delete_spilled(&mut self) -> io::Result<()> {
+        let mut store_update = self.store.store_update();
+        delete_trie_recorder_spill(&mut store_update, &self.key_prefix);
+        store_update.commit()?;
+        self.spilled.clear();
+        Ok(())
+    }
+}
+
+/// Removes the nodes spilled by the recorder created with `key_prefix`, e.g. by a recording
+/// interrupted by a restart of the node.
+pub fn delete_trie_recorder_spill(store_update: &mut StoreUpdate, key_prefix: &[u8]) {
+    let mut key_to = key_prefix.to_vec();
+    // The hashes following the prefix are never all 0xff bytes, so the range covers them all.
+    key_to.extend_from_slice(&[0xff; 33]);
+    store_update.delete_range(DBCol::TrieRecorderSpill, key_prefix, &key_to);
 }
 
 #[derive(Clone, Debug)]
@@ -60,9 +104,36 @@ impl TrieRecorder {
             removal_counter: 0,
             code_len_counter: 0,
             codes_to_record: Default::default(),
+            memory_size: 0,
+            spill: None,
+            sizes_only: None,
         }
     }
 
+    /// Creates a recorder which accounts for the size of the nodes, for the proof size limit,
+    /// without keeping them, for the nodes which don't need the state proof. Its
+    /// [Self::recorded_storage] is empty.
+    pub fn new_sizes_only(proof_size_limit: Option<usize>) -> Self {
+        Self { sizes_only: Some(HashSet::new()), ..Self::new(proof_size_limit) }
+    }
+
+    /// Whether the recorded nodes are kept, see [Self::new_sizes_only].
+    pub fn keeps_nodes(&self) -> bool {
+        self.sizes_only.is_none()
+    }
+
+    /// Creates a recorder which moves the recorded nodes to `DBCol::TrieRecorderSpill` whenever
+    /// they take more than `memory_limit` bytes in memory, for recordings too large to be held
+    /// in memory, like the ones of resharding. `key_prefix` must be unique to the recording.
+    /// The spilled nodes are streamed back, and removed from the store, by
+    /// [Self::write_recorded_storage].
+    ///
+    /// [Self::get_stats] only covers the nodes still in memory.
+    pub fn new_spilling(store: Store, key_prefix: Vec<u8>, memory_limit: usize) -> Self {
+        let spill = TrieRecorderSpill { store, key_prefix, memory_limit, spilled: HashSet::new() };
+        Self { spill: Some(spill), ..Self::new(None) }
+    }
+
     /// Records value without increasing the recorded size.
     /// This is used to bypass witness size checks in order to generate
     /// large witness for testing.
@@ -72,11 +143,49 @@ impl TrieRecorder {
     }
 
     pub fn record(&mut self, hash: &CryptoHash, node: Arc<[u8]>) {
+        if self.spill.as_ref().is_some_and(|spill| spill.spilled.contains(hash)) {
+            return;
+        }
         let size = node.len();
+        if let Some(hashes) = &mut self.sizes_only {
+            if hashes.insert(*hash) {
+                self.size = self.size.checked_add(size).unwrap();
+                self.upper_bound_size = self.upper_bound_size.checked_add(size).unwrap();
+            }
+            return;
+        }
         if self.recorded.insert(*hash, node).is_none() {
             self.size = self.size.checked_add(size).unwrap();
             self.upper_bound_size = self.upper_bound_size.checked_add(size).unwrap();
+            self.memory_size = self.memory_size.checked_add(size).unwrap();
+            self.spill_if_over_memory_limit();
+        }
+    }
+
+    fn spill_if_over_memory_limit(&mut self) {
+        let Some(spill) = &self.spill else {
+            return;
+        };
+        if self.memory_size <= spill.memory_limit {
+            return;
+        }
+        if let Err(err) = self.spill_recorded() {
+            // The nodes stay in memory, and the spill is retried with the next node.
+            tracing::warn!(target: "store", ?err, "failed to spill the recorded trie nodes");
+        }
+    }
+
+    /// Moves the nodes in memory to the store. Only for spilling recorders.
+    fn spill_recorded(&mut self) -> io::Result<()> {
+        let spill = self.spill.as_mut().expect("only spilling recorders spill");
+        let mut store_update = spill.store.store_update();
+        for (hash, node) in &self.recorded {
+            store_update.set(DBCol::TrieRecorderSpill, &spill.key(hash), node);
         }
+        store_update.commit()?;
+        spill.spilled.extend(self.recorded.drain().map(|(hash, _node)| hash));
+        self.memory_size = 0;
+        Ok(())
     }
 
     pub fn record_key_removal(&mut self) {
@@ -97,10 +206,47 @@ impl TrieRecorder {
         false
     }
 
-    pub fn recorded_storage(&mut self) -> PartialStorage {
+    /// Returns the recorded nodes, including the ones spilled to the store, which are removed
+    /// from it. For large spilled recordings, see [Self::write_recorded_storage], which doesn't
+    /// collect all the nodes in memory.
+    pub fn recorded_storage(&mut self) -> io::Result<PartialStorage> {
         let mut nodes: Vec<_> = self.recorded.drain().map(|(_key, value)| value).collect();
+        self.memory_size = 0;
+        if let Some(spill) = &mut self.spill {
+            for item in spill.store.iter_prefix(DBCol::TrieRecorderSpill, &spill.key_prefix) {
+                let (_key, node) = item?;
+                nodes.push(node.into());
+            }
+            spill.delete_spilled()?;
+        }
         nodes.sort();
-        PartialStorage { nodes: PartialState::TrieValues(nodes) }
+        Ok(PartialStorage { nodes: PartialState::TrieValues(nodes) })
+    }
+
+    /// Writes the recorded nodes to `writer` as a borsh serialized `PartialState`, and returns
+    /// their number. The nodes of a spilling recorder are first all moved to the store, then
+    /// streamed from it in the order of their hashes and removed from it, so that they are
+    /// never all held in memory at once.
+    pub fn write_recorded_storage(&mut self, writer: &mut impl io::Write) -> io::Result<usize> {
+        if self.spill.is_none() {
+            let PartialStorage { nodes } = self.recorded_storage()?;
+            let PartialState::TrieValues(values) = &nodes;
+            borsh::to_writer(writer, &nodes)?;
+            return Ok(values.len());
+        }
+        self.spill_recorded()?;
+        let spill = self.spill.as_mut().unwrap();
+        let num_nodes = spill.spilled.len();
+        // Same layout as the borsh serialization of `PartialState::TrieValues`: the index of the
+        // variant, then the number of nodes and the nodes.
+        borsh::to_writer(&mut *writer, &0u8)?;
+        borsh::to_writer(&mut *writer, &u32::try_from(num_nodes).map_err(io::Error::other)?)?;
+        for item in spill.store.iter_prefix(DBCol::TrieRecorderSpill, &spill.key_prefix) {
+            let (_key, node) = item?;
+            borsh::to_writer(&mut *writer, &node[..])?;
+        }
+        spill.delete_spilled()?;
+        Ok(num_nodes)
     }
 
     pub fn recorded_storage_size(&self) -> usize {
@@ -578,7 +724,7 @@ mod trie_recording_tests {
             }
             assert_eq!(trie.get_trie_nodes_count(), baseline_trie_nodes_count);
             trie.update(updates.iter().cloned()).unwrap();
-            let baseline_partial_storage = trie.recorded_storage().unwrap();
+            let baseline_partial_storage = trie.recorded_storage().unwrap().unwrap();
 
             // Now let's do this again with memtries enabled. Check that counters
             // are the same.
@@ -606,7 +752,7 @@ mod trie_recording_tests {
 
             // Now, let's check that when doing the same lookups with the captured partial storage,
             // we still get the same counters.
-            let partial_storage = trie.recorded_storage().unwrap();
+            let partial_storage = trie.recorded_storage().unwrap().unwrap();
             assert_partial_storage(&baseline_partial_storage, &partial_storage);
             println!(
                 "Partial storage has {} nodes from {} entries",
@@ -647,7 +793,10 @@ mod trie_recording_tests {
             }
             assert_eq!(trie.get_trie_nodes_count(), baseline_trie_nodes_count);
             trie.update(updates.iter().cloned()).unwrap();
-            assert_partial_storage(&baseline_partial_storage, &trie.recorded_storage().unwrap());
+            assert_partial_storage(
+                &baseline_partial_storage,
+                &trie.recorded_storage().unwrap().unwrap(),
+            );
 
             if !keys_to_get.is_empty() || !keys_to_get_ref.is_empty() {
                 // sanity check that we did indeed use in-memory tries.
@@ -695,4 +844,95 @@ mod trie_recording_tests {
     fn test_trie_recording_consistency_with_flat_storage_with_accounting_cache_and_missing_keys() {
         test_trie_recording_consistency(true, true, true);
     }
+
+    #[test]
+    fn test_spilling_trie_recorder() {
+        let store = crate::test_utils::create_test_store();
+        let nodes: Vec<std::sync::Arc<[u8]>> =
+            (0..100u8).map(|i| vec![i; 10 + i as usize].into()).collect();
+        let mut in_memory = super::TrieRecorder::new(None);
+        let mut spilling = super::TrieRecorder::new_spilling(store.clone(), vec![1, 2], 100);
+        // Another recording with a different prefix must be left alone.
+        let mut other = super::TrieRecorder::new_spilling(store.clone(), vec![1, 3], 0);
+        other.record(&hash(&nodes[0]), nodes[0].clone());
+        for _ in 0..2 {
+            for node in &nodes {
+                in_memory.record(&hash(node), node.clone());
+                spilling.record(&hash(node), node.clone());
+            }
+        }
+        assert!(spilling.recorded.len() < nodes.len());
+        assert_eq!(spilling.recorded_storage_size(), in_memory.recorded_storage_size());
+
+        let recorded = spilling.recorded_storage().unwrap();
+        assert_eq!(recorded, in_memory.recorded_storage().unwrap());
+        assert_eq!(store.iter(DBCol::TrieRecorderSpill).count(), 1);
+        let PartialState::TrieValues(other_nodes) = other.recorded_storage().unwrap().nodes;
+        assert_eq!(other_nodes, vec![nodes[0].clone()]);
+        assert_eq!(store.iter(DBCol::TrieRecorderSpill).count(), 0);
+    }
+
+    #[test]
+    fn test_sizes_only_trie_recorder() {
+        let nodes: Vec<std::sync::Arc<[u8]>> =
+            (0..100u8).map(|i| vec![i; 10 + i as usize].into()).collect();
+        let mut recorder = super::TrieRecorder::new(Some(1000));
+        let mut sizes_only = super::TrieRecorder::new_sizes_only(Some(1000));
+        for _ in 0..2 {
+            for node in &nodes {
+                recorder.record(&hash(node), node.clone());
+                sizes_only.record(&hash(node), node.clone());
+            }
+        }
+        assert!(sizes_only.recorded.is_empty());
+        assert_eq!(sizes_only.recorded_storage_size(), recorder.recorded_storage_size());
+        assert_eq!(
+            sizes_only.recorded_storage_size_upper_bound(),
+            recorder.recorded_storage_size_upper_bound()
+        );
+        assert!(sizes_only.check_proof_size_limit_exceed());
+        let PartialState::TrieValues(recorded) = sizes_only.recorded_storage().unwrap().nodes;
+        assert!(recorded.is_empty());
+    }
+
+    #[test]
+    fn test_write_spilled_recorded_storage() {
+        let store = crate::test_utils::create_test_store();
+        let nodes: Vec<std::sync::Arc<[u8]>> =
+            (0..100u8).map(|i| vec![i; 10 + i as usize].into()).collect();
+        let mut in_memory = super::TrieRecorder::new(None);
+        let mut spilling = super::TrieRecorder::new_spilling(store.clone(), vec![1, 2], 100);
+        let mut other = super::TrieRecorder::new_spilling(store.clone(), vec![1, 3], 0);
+        other.record(&hash(&nodes[0]), nodes[0].clone());
+        for node in &nodes {
+            in_memory.record(&hash(node), node.clone());
+            spilling.record(&hash(node), node.clone());
+        }
+
+        let mut streamed = vec![];
+        assert_eq!(spilling.write_recorded_storage(&mut streamed).unwrap(), nodes.len());
+        let PartialState::TrieValues(mut streamed_nodes) =
+            borsh::from_slice::<PartialState>(&streamed).unwrap();
+        streamed_nodes.sort();
+        assert_eq!(
+            PartialState::TrieValues(streamed_nodes),
+            in_memory.recorded_storage().unwrap().nodes
+        );
+        // Only the nodes of the written recording are removed from the store.
+        assert_eq!(store.iter(DBCol::TrieRecorderSpill).count(), 1);
+    }
+
+    #[test]
+    fn test_delete_trie_recorder_spill() {
+        let store = crate::test_utils::create_test_store();
+        let node: std::sync::Arc<[u8]> = vec![1; 10].into();
+        let mut interrupted = super::TrieRecorder::new_spilling(store.clone(), vec![1, 2], 0);
+        let mut other = super::TrieRecorder::new_spilling(store.clone(), vec![1, 3], 0);
+        interrupted.record(&hash(&node), node.clone());
+        other.record(&hash(&node), node.clone());
+        let mut store_update = store.store_update();
+        super::delete_trie_recorder_spill(&mut store_update, &[1, 2]);
+        store_update.commit().unwrap();
+        assert_eq!(store.iter(DBCol::TrieRecorderSpill).count(), 1);
+    }
 }
@@ -10,7 +10,7 @@ use near_o11y::metrics::prometheus::core::{GenericCounter, GenericGauge};
 use near_primitives::challenge::PartialState;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardUId;
-use near_primitives::types::ShardId;
+use near_primitives::types::{BlockHeight, ShardId};
 use std::collections::{HashMap, HashSet, VecDeque};
 use std::num::NonZeroUsize;
 use std::sync::{Arc, Mutex};
@@ -364,6 +364,9 @@ pub struct TrieCachingStorage {
     pub(crate) store: TrieStoreAdapter,
     pub(crate) shard_uid: ShardUId,
     pub(crate) is_view: bool,
+    /// Height of the block whose State is read, if it's a past State which may be stored under a
+    /// different `ShardUId` prefix than the current State, see `TrieStoreAdapter::get_at_height`.
+    pub(crate) block_height: Option<BlockHeight>,
 
     /// Caches ever requested items for the shard `shard_uid`. Used to speed up DB operations, presence of any item is
     /// not guaranteed.
@@ -421,7 +424,22 @@ impl TrieCachingStorage {
             prefetch_retry: metrics::PREFETCH_RETRY.with_label_values(&metrics_labels[..1]),
             prefetch_conflict: metrics::PREFETCH_CONFLICT.with_label_values(&metrics_labels[..1]),
         };
-        TrieCachingStorage { store, shard_uid, is_view, shard_cache, prefetch_api, metrics }
+        TrieCachingStorage {
+            store,
+            shard_uid,
+            is_view,
+            block_height: None,
+            shard_cache,
+            prefetch_api,
+            metrics,
+        }
+    }
+
+    /// Reads the State of the block at `block_height` instead of the current State. The shard
+    /// cache may still be shared, as it's keyed by the hashes of the nodes.
+    pub fn with_block_height(mut self, block_height: BlockHeight) -> Self {
+        self.block_height = Some(block_height);
+        self
     }
 
     /// Reads value if it is not in shard cache. Handles dropping the cache
@@ -546,7 +564,10 @@ impl TrieStorage for TrieCachingStorage {
 
 impl TrieCachingStorage {
     fn read_from_db(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
-        self.store.get(self.shard_uid, hash)
+        match self.block_height {
+            Some(block_height) => self.store.get_at_height(self.shard_uid, block_height, hash),
+            None => self.store.get(self.shard_uid, hash),
+        }
     }
 
     pub fn prefetch_api(&self) -> &Option<PrefetchApi> {
@@ -561,17 +582,31 @@ impl TrieCachingStorage {
 pub struct TrieDBStorage {
     pub(crate) store: TrieStoreAdapter,
     pub(crate) shard_uid: ShardUId,
+    /// See `TrieCachingStorage::block_height`.
+    pub(crate) block_height: Option<BlockHeight>,
 }
 
 impl TrieDBStorage {
     pub fn new(store: TrieStoreAdapter, shard_uid: ShardUId) -> Self {
-        Self { store, shard_uid }
+        Self { store, shard_uid, block_height: None }
+    }
+
+    /// Storage reading the State of the block at `block_height` instead of the current State.
+    pub fn new_at_height(
+        store: TrieStoreAdapter,
+        shard_uid: ShardUId,
+        block_height: BlockHeight,
+    ) -> Self {
+        Self { store, shard_uid, block_height: Some(block_height) }
     }
 }
 
 impl TrieStorage for TrieDBStorage {
     fn retrieve_raw_bytes(&self, hash: &CryptoHash) -> Result<Arc<[u8]>, StorageError> {
-        self.store.get(self.shard_uid, hash)
+        match self.block_height {
+            Some(block_height) => self.store.get_at_height(self.shard_uid, block_height, hash),
+            None => self.store.get(self.shard_uid, hash),
+        }
     }
 }
 
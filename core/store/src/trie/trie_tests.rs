@@ -66,7 +66,7 @@ where
 {
     let recording_trie = trie.recording_reads_new_recorder();
     let (recording_trie, output) = test(recording_trie).expect("should not fail");
-    (recording_trie.recorded_storage().unwrap(), recording_trie, output)
+    (recording_trie.recorded_storage().unwrap().unwrap(), recording_trie, output)
 }
 
 fn test_incomplete_storage<F, Out>(trie: Trie, mut test: F)
@@ -438,7 +438,7 @@ mod trie_storage_tests {
         let changes = trie.update(updates.clone()).unwrap();
         tracing::info!("Changes: {:?}", changes);
 
-        let recorded_normal = trie.recorded_storage();
+        let recorded_normal = trie.recorded_storage().unwrap();
 
         let tries =
             TestTriesBuilder::new().with_flat_storage(true).with_in_memory_tries(true).build();
@@ -450,7 +450,7 @@ mod trie_storage_tests {
 
         tracing::info!("Changes: {:?}", changes);
 
-        let recorded_memtrie = trie.recorded_storage();
+        let recorded_memtrie = trie.recorded_storage().unwrap();
 
         assert_eq!(recorded_normal, recorded_memtrie);
     }
@@ -509,7 +509,7 @@ mod trie_storage_tests {
             disk_iter.seek_prefix(&iter_prefix).unwrap();
             let disk_iter_results = disk_iter.collect::<Result<Vec<_>, _>>().unwrap();
             assert_eq!(disk_iter_results, expected_iter_results);
-            trie.recorded_storage().unwrap()
+            trie.recorded_storage().unwrap().unwrap()
         };
 
         let memtrie_iter_recorded = {
@@ -526,7 +526,7 @@ mod trie_storage_tests {
             memtrie_iter.seek_prefix(&iter_prefix).unwrap();
             let memtrie_iter_results = memtrie_iter.collect::<Result<Vec<_>, _>>().unwrap();
             assert_eq!(memtrie_iter_results, expected_iter_results);
-            trie.recorded_storage().unwrap()
+            trie.recorded_storage().unwrap().unwrap()
         };
 
         assert_eq!(disk_iter_recorded, memtrie_iter_recorded);
@@ -538,7 +538,7 @@ mod trie_storage_tests {
             disk_iter.seek_prefix(&iter_prefix).unwrap();
             let disk_iter_results = disk_iter.collect::<Result<Vec<_>, _>>().unwrap();
             assert_eq!(disk_iter_results, expected_iter_results);
-            trie.recorded_storage().unwrap()
+            trie.recorded_storage().unwrap().unwrap()
         };
 
         assert_eq!(disk_iter_recorded, partial_recorded);
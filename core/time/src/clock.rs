@@ -21,6 +21,7 @@ const FAKE_CLOCK_UTC_START: LazyLock<Utc> =
 enum ClockInner {
     Real,
     Fake(FakeClock),
+    Skewed(SkewedClock),
 }
 
 /// Clock encapsulates a system clock, allowing to replace it
@@ -46,6 +47,7 @@ impl Clock {
         match &self.0 {
             ClockInner::Real => Instant::now(),
             ClockInner::Fake(fake) => fake.now(),
+            ClockInner::Skewed(skewed) => skewed.now(),
         }
     }
 
@@ -54,6 +56,7 @@ impl Clock {
         match &self.0 {
             ClockInner::Real => Utc::now_utc(),
             ClockInner::Fake(fake) => fake.now_utc(),
+            ClockInner::Skewed(skewed) => skewed.now_utc(),
         }
     }
 
@@ -70,6 +73,7 @@ impl Clock {
         match &self.0 {
             ClockInner::Real => tokio::time::sleep_until(t.into()).await,
             ClockInner::Fake(fake) => fake.sleep_until(t).await,
+            ClockInner::Skewed(skewed) => skewed.sleep_until(t).await,
         }
     }
 
@@ -78,6 +82,7 @@ impl Clock {
         match &self.0 {
             ClockInner::Real => tokio::time::sleep(d.try_into().unwrap()).await,
             ClockInner::Fake(fake) => fake.sleep(d).await,
+            ClockInner::Skewed(skewed) => skewed.sleep(d).await,
         }
     }
 }
@@ -225,6 +230,97 @@ impl Default for FakeClock {
     }
 }
 
+struct SkewedClockInner {
+    base: FakeClock,
+    /// Time of `base` when the drift rate was last set.
+    anchor: Instant,
+    /// Skew accumulated by the drift until `anchor`.
+    anchor_skew: Duration,
+    utc_offset: Duration,
+    drift_rate: f64,
+}
+
+impl SkewedClockInner {
+    /// How far ahead of `base` the clock is at the given time of `base`.
+    fn skew(&self, base_now: Instant) -> Duration {
+        self.anchor_skew + base_now.signed_duration_since(self.anchor) * self.drift_rate
+    }
+}
+
+/// TEST-ONLY
+/// Clock of a node whose time deviates from the `FakeClock` it's derived from: its UTC time is
+/// off by a constant offset, and both its UTC and monotone time run faster or slower by a drift
+/// rate, e.g. 0.01 for a clock gaining 10ms every second. The skew can be changed while the
+/// clock is used, the time jumps by the change of the offset but not by the change of the rate.
+#[derive(Clone)]
+pub struct SkewedClock(Arc<Mutex<SkewedClockInner>>);
+
+impl SkewedClock {
+    pub fn new(base: FakeClock, utc_offset: Duration, drift_rate: f64) -> Self {
+        assert!(drift_rate > -1.0, "the skewed clock must move forward");
+        let anchor = base.now();
+        Self(Arc::new(Mutex::new(SkewedClockInner {
+            base,
+            anchor,
+            anchor_skew: Duration::ZERO,
+            utc_offset,
+            drift_rate,
+        })))
+    }
+
+    pub fn now(&self) -> Instant {
+        let inner = self.0.lock().unwrap();
+        let base_now = inner.base.now();
+        base_now + inner.skew(base_now)
+    }
+
+    pub fn now_utc(&self) -> Utc {
+        let inner = self.0.lock().unwrap();
+        let base_now = inner.base.now();
+        inner.base.now_utc() + inner.utc_offset + inner.skew(base_now)
+    }
+
+    pub fn clock(&self) -> Clock {
+        Clock(ClockInner::Skewed(self.clone()))
+    }
+
+    pub fn set_utc_offset(&self, utc_offset: Duration) {
+        self.0.lock().unwrap().utc_offset = utc_offset;
+    }
+
+    pub fn set_drift_rate(&self, drift_rate: f64) {
+        assert!(drift_rate > -1.0, "the skewed clock must move forward");
+        let mut inner = self.0.lock().unwrap();
+        let base_now = inner.base.now();
+        inner.anchor_skew = inner.skew(base_now);
+        inner.anchor = base_now;
+        inner.drift_rate = drift_rate;
+    }
+
+    /// Cancel-safe.
+    pub async fn sleep(&self, d: Duration) {
+        self.sleep_until(self.now() + d).await;
+    }
+
+    /// Cancel-safe.
+    pub async fn sleep_until(&self, t: Instant) {
+        loop {
+            let now = self.now();
+            if now >= t {
+                return;
+            }
+            // Sleeps on the base clock until the skewed clock reaches `t`, rounded up so that
+            // each iteration makes progress. Loops in case the drift rate changes meanwhile.
+            let (base, drift_rate) = {
+                let inner = self.0.lock().unwrap();
+                (inner.base.clone(), inner.drift_rate)
+            };
+            let remaining = t.signed_duration_since(now) / (1.0 + drift_rate);
+            base.sleep(remaining + Duration::NANOSECOND).await;
+        }
+    }
+}
+
 /// Interval equivalent to tokio::time::Interval with
 /// MissedTickBehavior::Skip.
 pub struct Interval {
@@ -1,12 +1,15 @@
 use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
 use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 
-use near_async::futures::FutureSpawner;
-use near_async::messaging::{noop, IntoMultiSender, IntoSender, LateBoundSender};
+use near_async::futures::{DelayedActionRunner, DelayedActionRunnerExt, FutureSpawner};
+use near_async::messaging::{noop, Actor, IntoMultiSender, IntoSender, LateBoundSender};
+use near_async::test_loop::data::TestLoopDataHandle;
 use near_async::test_loop::sender::TestLoopSender;
 use near_async::test_loop::TestLoopV2;
-use near_async::time::{Clock, Duration};
+use near_async::time::{Clock, Duration, SkewedClock};
 use near_chain::runtime::NightshadeRuntime;
 use near_chain::state_snapshot_actor::{
     get_delete_snapshot_callback, get_make_snapshot_callback, SnapshotCallbacks, StateSnapshotActor,
@@ -15,7 +18,7 @@ use near_chain::types::RuntimeAdapter;
 use near_chain::ChainGenesis;
 use near_chain_configs::{
     ClientConfig, DumpConfig, ExternalStorageConfig, ExternalStorageLocation, Genesis,
-    MutableConfigValue, StateSyncConfig, SyncConfig,
+    GenesisConfig, MutableConfigValue, StateSyncConfig, SyncConfig,
 };
 use near_chunks::shards_manager_actor::ShardsManagerActor;
 use near_client::client_actor::ClientActorInner;
@@ -23,30 +26,38 @@ use near_client::gc_actor::GCActor;
 use near_client::sync_jobs_actor::SyncJobsActor;
 use near_client::{Client, PartialWitnessActor, ViewClientActorInner};
 use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
-use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
 use near_network::test_loop::{TestLoopNetworkSharedState, TestLoopPeerManagerActor};
 use near_parameters::RuntimeConfigStore;
 use near_primitives::epoch_manager::EpochConfigStore;
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::stateless_validation::ChunkProductionKey;
 use near_primitives::test_utils::create_test_signer;
 use near_primitives::types::{AccountId, BlockHeight, ShardId, ShardIndex};
 use near_primitives::upgrade_schedule::ProtocolUpgradeVotingSchedule;
 use near_primitives::version::PROTOCOL_UPGRADE_SCHEDULE;
 use near_store::adapter::StoreAdapter;
-use near_store::config::StateSnapshotType;
-use near_store::db::TestDBFlags;
+use near_store::config::{SplitStorageConfig, StateSnapshotType};
+use near_store::db::{ColdDB, TestDBFlags};
 use near_store::genesis::initialize_genesis_state;
-use near_store::test_utils::{create_test_split_store, create_test_store_with_flags};
+use near_store::metadata::{DbKind, DB_VERSION};
+use near_store::test_utils::{
+    create_test_node_storage, create_test_node_storage_with_cold, create_test_store,
+    create_test_store_with_flags,
+};
 use near_store::{Store, StoreConfig, TrieConfig};
 use near_vm_runner::logic::ProtocolVersion;
 use near_vm_runner::{ContractRuntimeCache, FilesystemContractRuntimeCache};
+use nearcore::cold_storage::update_cold_store;
 use nearcore::state_sync::StateSyncDumper;
 
 use super::env::{ClientToShardsManagerSender, TestData, TestLoopChunksStorage, TestLoopEnv};
+use super::utils::byzantine::{register_byzantine_handlers, ByzantineBehavior};
 use super::utils::network::{
     block_dropper_by_height, chunk_endorsement_dropper, chunk_endorsement_dropper_by_hash,
 };
+use super::utils::network_faults::{network_fault_injector, NetworkFaults};
 use near_chain::resharding::resharding_actor::ReshardingActor;
 
 enum DropConditionKind {
@@ -64,6 +75,13 @@ enum DropConditionKind {
     /// self.0[`shard_id`][`height_created` - `epoch_start`] is true, or if
     /// `height_created` - `epoch_start` > self.0[`shard_id`].len()
     ChunksProducedByHeight(HashMap<ShardId, Vec<bool>>),
+    /// Drops the chunks of shard `shard_id` produced by `producer` at the heights for which
+    /// `heights` returns true, as if the producer missed them.
+    ChunksProducedBy {
+        producer: AccountId,
+        shard_id: ShardId,
+        heights: Rc<dyn Fn(BlockHeight) -> bool>,
+    },
     // Drops Block broadcast messages with height in `self.0`
     BlocksByHeight(HashSet<BlockHeight>),
 }
@@ -84,16 +102,28 @@ pub(crate) struct TestLoopBuilder {
     /// Accounts whose clients should be configured as an archival node.
     /// This should be a subset of the accounts in the `clients` list.
     archival_clients: HashSet<AccountId>,
+    /// Whether the archival nodes keep all their data in a single archive store rather than in
+    /// a split store.
+    legacy_archival_storage: bool,
     /// Will store all chunks produced within the test loop.
     chunks_storage: Arc<Mutex<TestLoopChunksStorage>>,
     /// Conditions under which chunks/endorsements/blocks are dropped.
     drop_condition_kinds: Vec<DropConditionKind>,
+    /// Network faults injected between the nodes while the test runs.
+    network_faults: Arc<Mutex<NetworkFaults>>,
+    /// Byzantine behaviors of the nodes, by account.
+    byzantine_behaviors: HashMap<AccountId, Vec<ByzantineBehavior>>,
+    /// Routing between the nodes, shared by their peer manager actors.
+    network_shared_state: Arc<TestLoopNetworkSharedState>,
     /// Number of latest epochs to keep before garbage collecting associated data.
     gc_num_epochs_to_keep: Option<u64>,
     /// The store of runtime configurations to be passed into runtime adapters.
     runtime_config_store: Option<RuntimeConfigStore>,
     /// Custom function to change the configs before constructing each client.
     config_modifier: Option<Box<dyn Fn(&mut ClientConfig, usize)>>,
+    /// Custom functions to change the config of the client of an account, applied after
+    /// `config_modifier`.
+    client_config_modifiers: HashMap<AccountId, Vec<Box<dyn Fn(&mut ClientConfig)>>>,
     /// Whether to do the warmup or not. See `skip_warmup` for more details.
     warmup: bool,
     /// Whether all nodes must track all shards.
@@ -102,6 +132,10 @@ pub(crate) struct TestLoopBuilder {
     load_mem_tries_for_tracked_shards: bool,
     /// Upgrade schedule which determines when the clients start voting for new protocol versions.
     upgrade_schedule: ProtocolUpgradeVotingSchedule,
+    /// Per-client upgrade schedules which take precedence over `upgrade_schedule`.
+    upgrade_schedule_overrides: HashMap<AccountId, ProtocolUpgradeVotingSchedule>,
+    /// Offsets and drift rates of the clocks of the nodes, by account.
+    clock_skews: HashMap<AccountId, (Duration, f64)>,
     /// Overrides to test database behavior.
     test_store_flags: TestDBFlags,
 }
@@ -150,6 +184,28 @@ fn should_drop_chunk_by_height(
     !*should_produce
 }
 
+/// Checks whether the chunk is produced by the given account for the given shard.
+fn is_chunk_produced_by(
+    epoch_manager_adapter: &dyn EpochManagerAdapter,
+    chunk: &ShardChunkHeader,
+    producer: &AccountId,
+    shard_id: ShardId,
+) -> bool {
+    if chunk.shard_id() != shard_id {
+        return false;
+    }
+    let epoch_id =
+        epoch_manager_adapter.get_epoch_id_from_prev_block(chunk.prev_block_hash()).unwrap();
+    let chunk_producer = epoch_manager_adapter
+        .get_chunk_producer_info(&ChunkProductionKey {
+            shard_id,
+            epoch_id,
+            height_created: chunk.height_created(),
+        })
+        .unwrap();
+    chunk_producer.account_id() == producer
+}
+
 /// Returns true if the chunk should be dropped based on the
 /// `DropCondition::ProtocolUpgradeChunkRange`.
 fn should_drop_chunk_for_protocol_upgrade(
@@ -282,6 +338,26 @@ fn register_drop_condition(
                 drop_chunks_condition,
             ));
         }
+        DropConditionKind::ChunksProducedBy { producer, shard_id, heights } => {
+            let inner_epoch_manager_adapter = epoch_manager_adapter.clone();
+            let producer = producer.clone();
+            let shard_id = *shard_id;
+            let heights = heights.clone();
+            let drop_chunks_condition = Box::new(move |chunk: ShardChunkHeader| -> bool {
+                heights(chunk.height_created())
+                    && is_chunk_produced_by(
+                        inner_epoch_manager_adapter.as_ref(),
+                        &chunk,
+                        &producer,
+                        shard_id,
+                    )
+            });
+            peer_manager_actor.register_override_handler(chunk_endorsement_dropper_by_hash(
+                chunks_storage,
+                epoch_manager_adapter.clone(),
+                drop_chunks_condition,
+            ));
+        }
         DropConditionKind::BlocksByHeight(heights) => {
             peer_manager_actor.register_override_handler(block_dropper_by_height(heights.clone()));
         }
@@ -298,15 +374,22 @@ impl TestLoopBuilder {
             stores_override: None,
             test_loop_data_dir: None,
             archival_clients: HashSet::new(),
+            legacy_archival_storage: false,
             chunks_storage: Default::default(),
             drop_condition_kinds: vec![],
+            network_faults: Default::default(),
+            byzantine_behaviors: HashMap::new(),
+            network_shared_state: Arc::new(TestLoopNetworkSharedState::new::<TestData>(&[])),
             gc_num_epochs_to_keep: None,
             runtime_config_store: None,
             config_modifier: None,
+            client_config_modifiers: HashMap::new(),
             warmup: true,
             track_all_shards: false,
             load_mem_tries_for_tracked_shards: true,
             upgrade_schedule: PROTOCOL_UPGRADE_SCHEDULE.clone(),
+            upgrade_schedule_overrides: HashMap::new(),
+            clock_skews: HashMap::new(),
             test_store_flags: Default::default(),
         }
     }
@@ -353,11 +436,20 @@ impl TestLoopBuilder {
 
     /// Set the accounts whose clients should be configured as archival nodes in the test loop.
     /// These accounts should be a subset of the accounts provided to the `clients` method.
+    /// By default, an archival node has a split store: its hot store is garbage collected like
+    /// the store of other nodes, once the finalized blocks are copied to its cold store.
     pub(crate) fn archival_clients(mut self, clients: HashSet<AccountId>) -> Self {
         self.archival_clients = clients;
         self
     }
 
+    /// Makes the archival nodes keep all their data in a single archive store which is never
+    /// garbage collected, as the archival nodes without split storage do.
+    pub(crate) fn legacy_archival_storage(mut self) -> Self {
+        self.legacy_archival_storage = true;
+        self
+    }
+
     pub(crate) fn drop_chunks_validated_by(mut self, account_id: &str) -> Self {
         self.drop_condition_kinds
             .push(DropConditionKind::ChunksValidatedBy(account_id.parse().unwrap()));
@@ -395,6 +487,24 @@ impl TestLoopBuilder {
         self
     }
 
+    /// Makes `account_id` miss its chunks of `shard_id` at the heights for which `heights`
+    /// returns true. The chunks are produced but all their endorsements are dropped, so they
+    /// are never included and count as missed in the chunk production stats of the producer.
+    /// As for the other drop conditions, the chunks of the first heights of the chain are kept.
+    pub(crate) fn drop_chunks_produced_by(
+        mut self,
+        account_id: &str,
+        shard_id: ShardId,
+        heights: impl Fn(BlockHeight) -> bool + 'static,
+    ) -> Self {
+        self.drop_condition_kinds.push(DropConditionKind::ChunksProducedBy {
+            producer: account_id.parse().unwrap(),
+            shard_id,
+            heights: Rc::new(heights),
+        });
+        self
+    }
+
     pub(crate) fn drop_blocks_by_height(mut self, heights: HashSet<BlockHeight>) -> Self {
         if !heights.is_empty() {
             self.drop_condition_kinds.push(DropConditionKind::BlocksByHeight(heights));
@@ -402,6 +512,16 @@ impl TestLoopBuilder {
         self
     }
 
+    /// Makes the node of the given account misbehave, see `ByzantineBehavior`.
+    pub(crate) fn byzantine(mut self, account_id: &str, behavior: ByzantineBehavior) -> Self {
+        assert!(
+            behavior != ByzantineBehavior::ProduceInvalidChunks || cfg!(feature = "test_features"),
+            "producing invalid chunks requires the test_features feature"
+        );
+        self.byzantine_behaviors.entry(account_id.parse().unwrap()).or_default().push(behavior);
+        self
+    }
+
     pub(crate) fn gc_num_epochs_to_keep(mut self, num_epochs: u64) -> Self {
         self.gc_num_epochs_to_keep = Some(num_epochs);
         self
@@ -417,6 +537,22 @@ impl TestLoopBuilder {
         self
     }
 
+    /// Custom function to change the config of the client of `account_id` only, e.g. to make
+    /// one of the validators archival or to change its tracked shards. Can be called several
+    /// times for the same account, the functions are applied in order after `config_modifier`.
+    /// Setting `archive` in the config makes the node archival, with a split store.
+    pub(crate) fn client_config_modifier(
+        mut self,
+        account_id: &str,
+        modifier: impl Fn(&mut ClientConfig) + 'static,
+    ) -> Self {
+        self.client_config_modifiers
+            .entry(account_id.parse().unwrap())
+            .or_default()
+            .push(Box::new(modifier));
+        self
+    }
+
     /// Do not automatically warmup the chain. Start from genesis instead.
     /// Note that this can cause unexpected issues, as the chain behaves
     /// somewhat differently (and correctly so) at genesis. So only skip
@@ -453,6 +589,62 @@ impl TestLoopBuilder {
         self
     }
 
+    /// Makes all clients vote for the protocol version of the genesis until `voting_date`
+    /// and for `protocol_version` from then on.
+    pub fn protocol_upgrade_voting_date(
+        self,
+        protocol_version: ProtocolVersion,
+        voting_date: chrono::DateTime<chrono::Utc>,
+    ) -> Self {
+        let schedule = ProtocolUpgradeVotingSchedule::new_from_env_or_schedule(
+            protocol_version,
+            vec![(voting_date, protocol_version)],
+        )
+        .unwrap();
+        self.protocol_upgrade_schedule(schedule)
+    }
+
+    /// Skews the clock of the node of the given account, see `SkewedClock`: its UTC time is off
+    /// by `utc_offset`, and its time runs faster or slower than the virtual time by
+    /// `drift_rate`. The skew can be changed while the test runs with `TestData::clock_skew`.
+    pub(crate) fn clock_skew(
+        mut self,
+        account_id: &str,
+        utc_offset: Duration,
+        drift_rate: f64,
+    ) -> Self {
+        self.clock_skews.insert(account_id.parse().unwrap(), (utc_offset, drift_rate));
+        self
+    }
+
+    /// Overrides the upgrade schedule of the given clients. Together with
+    /// `protocol_upgrade_voting_date` this allows spreading the votes of the validators
+    /// over an upgrade window, e.g. to check that the upgrade only happens once the
+    /// stake which voted for it crosses the protocol upgrade stake threshold.
+    pub fn protocol_upgrade_schedule_for_clients(
+        mut self,
+        clients: &[AccountId],
+        schedule: ProtocolUpgradeVotingSchedule,
+    ) -> Self {
+        for client in clients {
+            self.upgrade_schedule_overrides.insert(client.clone(), schedule.clone());
+        }
+        self
+    }
+
+    /// Makes the given clients vote for `protocol_version` right away, e.g. to have some nodes
+    /// of a protocol upgrade test run an older binary, see `utils::protocol_upgrade`.
+    pub fn protocol_version_votes_for_clients(
+        self,
+        clients: &[AccountId],
+        protocol_version: ProtocolVersion,
+    ) -> Self {
+        self.protocol_upgrade_schedule_for_clients(
+            clients,
+            ProtocolUpgradeVotingSchedule::new_immediate(protocol_version),
+        )
+    }
+
     /// Build the test loop environment.
     pub(crate) fn build(self) -> TestLoopEnv {
         self.ensure_genesis().ensure_clients().build_impl()
@@ -469,25 +661,44 @@ impl TestLoopBuilder {
             self.archival_clients.is_subset(&HashSet::from_iter(self.clients.iter().cloned())),
             "Archival accounts must be subset of the clients"
         );
+        for account_id in self.client_config_modifiers.keys() {
+            assert!(
+                self.clients.contains(account_id),
+                "Config modified for {account_id}, which is not one of the clients"
+            );
+        }
         self
     }
 
     fn build_impl(mut self) -> TestLoopEnv {
         let mut datas = Vec::new();
-        let mut network_adapters = Vec::new();
         let mut epoch_manager_adapters = Vec::new();
         let tempdir =
             self.test_loop_data_dir.take().unwrap_or_else(|| tempfile::tempdir().unwrap());
         for idx in 0..self.clients.len() {
             let account = &self.clients[idx];
             let is_archival = self.archival_clients.contains(account);
-            let (data, network_adapter, epoch_manager_adapter) =
-                self.setup_client(idx, &tempdir, is_archival);
+            let (data, epoch_manager_adapter) = self.setup_client(idx, &tempdir, is_archival);
             datas.push(data);
-            network_adapters.push(network_adapter);
             epoch_manager_adapters.push(epoch_manager_adapter);
         }
-        self.setup_network(&datas, &network_adapters, &epoch_manager_adapters);
+        self.setup_network(&datas, &epoch_manager_adapters);
+
+        // The heads of the nodes are the state recorded in the trace of the test loop.
+        let client_handles: Vec<_> = datas
+            .iter()
+            .map(|data| (data.account_id.clone(), data.client_sender.actor_handle()))
+            .collect();
+        self.test_loop.set_trace_state_observer(move |test_loop_data| {
+            let heads: Vec<_> = client_handles
+                .iter()
+                .map(|(account_id, handle)| {
+                    let head = test_loop_data.get(handle).client.chain.head();
+                    format!("{account_id}:{}", head.map_or(0, |head| head.height))
+                })
+                .collect();
+            heads.join(",")
+        });
 
         let env = TestLoopEnv { test_loop: self.test_loop, datas, tempdir };
         if self.warmup {
@@ -502,51 +713,13 @@ impl TestLoopBuilder {
         idx: usize,
         tempdir: &TempDir,
         is_archival: bool,
-    ) -> (
-        TestData,
-        Arc<LateBoundSender<TestLoopSender<TestLoopPeerManagerActor>>>,
-        Arc<dyn EpochManagerAdapter>,
-    ) {
-        let client_adapter = LateBoundSender::new();
-        let network_adapter = LateBoundSender::new();
-        let state_snapshot_adapter = LateBoundSender::new();
-        let partial_witness_adapter = LateBoundSender::new();
-        let sync_jobs_adapter = LateBoundSender::new();
-        let resharding_sender = LateBoundSender::new();
-
+    ) -> (TestData, Arc<dyn EpochManagerAdapter>) {
         let genesis = self.genesis.as_ref().unwrap();
         let epoch_config_store = self.epoch_config_store.as_ref().unwrap();
-        let mut client_config = ClientConfig::test(true, 600, 2000, 4, is_archival, true, false);
-        client_config.epoch_length = genesis.config.epoch_length;
-        client_config.max_block_wait_delay = Duration::seconds(6);
-        client_config.state_sync_enabled = true;
-        client_config.state_sync_external_timeout = Duration::milliseconds(100);
-        client_config.state_sync_p2p_timeout = Duration::milliseconds(100);
-        client_config.state_sync_retry_backoff = Duration::milliseconds(100);
-        client_config.state_sync_external_backoff = Duration::milliseconds(100);
+        let mut client_config = new_client_config(&genesis.config, tempdir, is_archival);
         if let Some(num_epochs) = self.gc_num_epochs_to_keep {
             client_config.gc.gc_num_epochs_to_keep = num_epochs;
         }
-        let external_storage_location =
-            ExternalStorageLocation::Filesystem { root_dir: tempdir.path().join("state_sync") };
-        client_config.state_sync = StateSyncConfig {
-            dump: Some(DumpConfig {
-                iteration_delay: Some(Duration::seconds(1)),
-                location: external_storage_location.clone(),
-                credentials_file: None,
-                restart_dump_for_shards: None,
-            }),
-            sync: SyncConfig::ExternalStorage(ExternalStorageConfig {
-                location: external_storage_location,
-                num_concurrent_requests: 1,
-                num_concurrent_requests_during_catchup: 1,
-                // We go straight to storage here because the network layer basically
-                // doesn't exist in testloop. We could mock a bunch of stuff to make
-                // the clients transfer state parts "peer to peer" but we wouldn't really
-                // gain anything over having them dump parts to a tempdir.
-                external_storage_fallback_threshold: 0,
-            }),
-        };
 
         // Configure tracked shards.
         // * single shard tracking for validators
@@ -564,6 +737,11 @@ impl TestLoopBuilder {
         if let Some(config_modifier) = &self.config_modifier {
             config_modifier(&mut client_config, idx);
         }
+        for modifier in self.client_config_modifiers.get(&self.clients[idx]).into_iter().flatten() {
+            modifier(&mut client_config);
+        }
+        // The modifiers may have turned the archival mode on or off.
+        let is_archival = client_config.archive;
 
         let homedir = tempdir.path().join(format!("{}", idx));
         std::fs::create_dir_all(&homedir).expect("Unable to create homedir");
@@ -574,117 +752,336 @@ impl TestLoopBuilder {
             ..Default::default()
         };
 
-        let (store, split_store): (Store, Option<Store>) =
-            if let Some(stores_override) = &self.stores_override {
-                stores_override[idx].clone()
-            } else if is_archival {
-                let (hot_store, split_store) = create_test_split_store();
-                (hot_store, Some(split_store))
-            } else {
-                let hot_store = create_test_store_with_flags(&self.test_store_flags);
-                (hot_store, None)
-            };
+        let (store, split_store, cold_db) = if let Some(stores_override) = &self.stores_override {
+            let (store, split_store) = stores_override[idx].clone();
+            (store, split_store, None)
+        } else if is_archival && self.legacy_archival_storage {
+            let store = create_test_node_storage(DB_VERSION, DbKind::Archive).get_hot_store();
+            (store, None, None)
+        } else if is_archival {
+            let (storage, ..) = create_test_node_storage_with_cold(DB_VERSION, DbKind::Hot);
+            (storage.get_hot_store(), storage.get_split_store(), storage.cold_db().cloned())
+        } else {
+            let hot_store = create_test_store_with_flags(&self.test_store_flags);
+            (hot_store, None, None)
+        };
         initialize_genesis_state(store.clone(), &genesis, None);
 
-        let sync_jobs_actor = SyncJobsActor::new(client_adapter.as_multi_sender());
-        let chain_genesis = ChainGenesis::new(&genesis.config);
+        let node_setup = NodeSetup {
+            index: idx,
+            account_id: self.clients[idx].clone(),
+            client_config,
+            genesis: Arc::new(genesis.clone()),
+            epoch_config_store: epoch_config_store.clone(),
+            runtime_config_store: self.runtime_config_store.clone(),
+            upgrade_schedule: self
+                .upgrade_schedule_overrides
+                .get(&self.clients[idx])
+                .unwrap_or(&self.upgrade_schedule)
+                .clone(),
+            byzantine_behaviors: self
+                .byzantine_behaviors
+                .get(&self.clients[idx])
+                .cloned()
+                .unwrap_or_default(),
+            clock_skew: self.clock_skews.get(&self.clients[idx]).map(
+                |&(utc_offset, drift_rate)| self.test_loop.skewed_clock(utc_offset, drift_rate),
+            ),
+            homedir,
+            store_config,
+            store,
+            split_store,
+            cold_db,
+            // Generate a PeerId. It doesn't matter what this is. We're just making it based on
+            // the account ID, so that it is stable across multiple runs in the same test.
+            peer_id: PeerId::new(create_test_signer(self.clients[idx].as_str()).public_key()),
+            chunks_storage: self.chunks_storage.clone(),
+            client_adapter: LateBoundSender::new(),
+            network_adapter: LateBoundSender::new(),
+            state_snapshot_adapter: LateBoundSender::new(),
+            partial_witness_adapter: LateBoundSender::new(),
+            sync_jobs_adapter: LateBoundSender::new(),
+            resharding_sender: LateBoundSender::new(),
+            shards_manager_adapter: LateBoundSender::new(),
+        };
+        register_node(
+            &mut self.test_loop,
+            node_setup,
+            self.network_faults.clone(),
+            self.network_shared_state.clone(),
+        )
+    }
+
+    // TODO: we assume that all `Vec`s have the same length, consider
+    // joining them into one structure.
+    fn setup_network(
+        &mut self,
+        datas: &Vec<TestData>,
+        epoch_manager_adapters: &Vec<Arc<dyn EpochManagerAdapter>>,
+    ) {
+        for data in datas {
+            self.network_shared_state.add_node(data);
+        }
+        for (idx, data) in datas.iter().enumerate() {
+            register_peer_manager(&mut self.test_loop, data, |peer_manager_actor| {
+                for condition in &self.drop_condition_kinds {
+                    register_drop_condition(
+                        peer_manager_actor,
+                        self.chunks_storage.clone(),
+                        epoch_manager_adapters[idx].clone(),
+                        condition,
+                    );
+                }
+            });
+        }
+    }
+}
+
+/// Client config shared by all the nodes, before the per-node changes.
+fn new_client_config(
+    genesis_config: &GenesisConfig,
+    tempdir: &TempDir,
+    is_archival: bool,
+) -> ClientConfig {
+    let mut client_config = ClientConfig::test(true, 600, 2000, 4, is_archival, true, false);
+    client_config.epoch_length = genesis_config.epoch_length;
+    client_config.max_block_wait_delay = Duration::seconds(6);
+    client_config.state_sync_enabled = true;
+    client_config.state_sync_external_timeout = Duration::milliseconds(100);
+    client_config.state_sync_p2p_timeout = Duration::milliseconds(100);
+    client_config.state_sync_retry_backoff = Duration::milliseconds(100);
+    client_config.state_sync_external_backoff = Duration::milliseconds(100);
+    let external_storage_location =
+        ExternalStorageLocation::Filesystem { root_dir: tempdir.path().join("state_sync") };
+    client_config.state_sync = StateSyncConfig {
+        dump: Some(DumpConfig {
+            iteration_delay: Some(Duration::seconds(1)),
+            location: external_storage_location.clone(),
+            credentials_file: None,
+            compress_parts: false,
+            restart_dump_for_shards: None,
+        }),
+        sync: SyncConfig::ExternalStorage(ExternalStorageConfig {
+            location: external_storage_location,
+            num_concurrent_requests: 1,
+            num_concurrent_requests_during_catchup: 1,
+            // We go straight to storage here because the network layer basically
+            // doesn't exist in testloop. We could mock a bunch of stuff to make
+            // the clients transfer state parts "peer to peer" but we wouldn't really
+            // gain anything over having them dump parts to a tempdir.
+            external_storage_fallback_threshold: 0,
+        }),
+    };
+    client_config
+}
+
+/// Everything needed to create the actors of a node, kept so that the node can be restarted
+/// from its store after it was killed, see `TestLoopEnv::restart_node`.
+pub(crate) struct NodeSetup {
+    /// Index the actors of the node are registered for.
+    pub(crate) index: usize,
+    account_id: AccountId,
+    client_config: ClientConfig,
+    pub(crate) genesis: Arc<Genesis>,
+    epoch_config_store: EpochConfigStore,
+    runtime_config_store: Option<RuntimeConfigStore>,
+    upgrade_schedule: ProtocolUpgradeVotingSchedule,
+    byzantine_behaviors: Vec<ByzantineBehavior>,
+    /// Clock of the node if it deviates from the virtual time of the test loop.
+    pub(crate) clock_skew: Option<SkewedClock>,
+    homedir: PathBuf,
+    store_config: StoreConfig,
+    store: Store,
+    split_store: Option<Store>,
+    /// Cold part of the split store of an archival node, to which the finalized blocks are
+    /// copied. `None` if the store isn't split or was provided by the test.
+    cold_db: Option<Arc<ColdDB>>,
+    peer_id: PeerId,
+    chunks_storage: Arc<Mutex<TestLoopChunksStorage>>,
+    client_adapter: Arc<LateBoundSender<TestLoopSender<ClientActorInner>>>,
+    network_adapter: Arc<LateBoundSender<TestLoopSender<TestLoopPeerManagerActor>>>,
+    state_snapshot_adapter: Arc<LateBoundSender<TestLoopSender<StateSnapshotActor>>>,
+    partial_witness_adapter: Arc<LateBoundSender<TestLoopSender<PartialWitnessActor>>>,
+    sync_jobs_adapter: Arc<LateBoundSender<TestLoopSender<SyncJobsActor>>>,
+    resharding_sender: Arc<LateBoundSender<TestLoopSender<ReshardingActor>>>,
+    shards_manager_adapter: Arc<LateBoundSender<TestLoopSender<ShardsManagerActor>>>,
+}
+
+/// State of a node kept to restart it, next to its `TestData`.
+pub(crate) struct NodeState {
+    pub(crate) setup: NodeSetup,
+    // Handles of the actors which are not in `TestData`.
+    gc_handle: TestLoopDataHandle<GCActor>,
+    sync_jobs_handle: TestLoopDataHandle<SyncJobsActor>,
+    state_snapshot_handle: TestLoopDataHandle<StateSnapshotActor>,
+    resharding_handle: TestLoopDataHandle<ReshardingActor>,
+    cold_store_handle: Option<TestLoopDataHandle<ColdStoreActor>>,
+}
+
+struct NodeActors {
+    client_actor: ClientActorInner,
+    view_client_actor: ViewClientActorInner,
+    shards_manager: ShardsManagerActor,
+    partial_witness_actor: PartialWitnessActor,
+    sync_jobs_actor: SyncJobsActor,
+    state_snapshot: StateSnapshotActor,
+    resharding_actor: ReshardingActor,
+    gc_actor: GCActor,
+    cold_store_actor: Option<ColdStoreActor>,
+    state_sync_dumper: StateSyncDumper,
+    epoch_manager: Arc<EpochManagerHandle>,
+}
+
+/// Copies the finalized blocks of an archival node to its cold store, as the cold store loop
+/// does in its own thread for the nodes with split storage. The hot store of the node is only
+/// garbage collected up to the blocks copied to the cold store.
+struct ColdStoreActor {
+    hot_store: Store,
+    cold_db: Arc<ColdDB>,
+    genesis_height: BlockHeight,
+    epoch_manager: Arc<EpochManagerHandle>,
+    split_storage_config: SplitStorageConfig,
+}
+
+impl ColdStoreActor {
+    fn update_cold_store(&mut self, ctx: &mut dyn DelayedActionRunner<Self>) {
+        if let Err(err) = update_cold_store(
+            &self.split_storage_config,
+            &self.hot_store,
+            &self.cold_db,
+            self.genesis_height,
+            &self.epoch_manager,
+        ) {
+            tracing::error!(target: "test", ?err, "failed to update cold store");
+        }
+        ctx.run_later(
+            "update cold store",
+            self.split_storage_config.cold_store_loop_sleep_duration,
+            move |act, ctx| act.update_cold_store(ctx),
+        );
+    }
+}
+
+impl Actor for ColdStoreActor {
+    fn start_actor(&mut self, ctx: &mut dyn DelayedActionRunner<Self>) {
+        self.update_cold_store(ctx);
+    }
+}
+
+impl NodeSetup {
+    /// Clock read by the actors of the node.
+    fn clock(&self, test_loop: &TestLoopV2) -> Clock {
+        match &self.clock_skew {
+            Some(clock_skew) => clock_skew.clock(),
+            None => test_loop.clock(),
+        }
+    }
+
+    /// Creates the actors of the node from its store. The genesis state must already be
+    /// initialized in the store.
+    fn create_actors(&self, test_loop: &TestLoopV2) -> NodeActors {
+        let client_config = &self.client_config;
+        let sync_jobs_actor = SyncJobsActor::new(self.client_adapter.as_multi_sender());
+        let chain_genesis = ChainGenesis::new(&self.genesis.config);
         let epoch_manager = EpochManager::new_arc_handle_from_epoch_config_store(
-            store.clone(),
-            &genesis.config,
-            epoch_config_store.clone(),
+            self.store.clone(),
+            &self.genesis.config,
+            self.epoch_config_store.clone(),
         );
         let shard_tracker =
-            ShardTracker::new(TrackedConfig::from_config(&client_config), epoch_manager.clone());
+            ShardTracker::new(TrackedConfig::from_config(client_config), epoch_manager.clone());
 
         let contract_cache =
             FilesystemContractRuntimeCache::test().expect("filesystem contract cache");
         let runtime_adapter = NightshadeRuntime::test_with_trie_config(
-            &homedir,
-            store.clone(),
+            &self.homedir,
+            self.store.clone(),
             ContractRuntimeCache::handle(&contract_cache),
-            &genesis.config,
+            &self.genesis.config,
             epoch_manager.clone(),
             self.runtime_config_store.clone(),
-            TrieConfig::from_store_config(&store_config),
+            TrieConfig::from_store_config(&self.store_config),
             StateSnapshotType::EveryEpoch,
             client_config.gc.gc_num_epochs_to_keep,
         );
 
         let state_snapshot = StateSnapshotActor::new(
             runtime_adapter.get_flat_storage_manager(),
-            network_adapter.as_multi_sender(),
+            self.network_adapter.as_multi_sender(),
             runtime_adapter.get_tries(),
-            state_snapshot_adapter.as_multi_sender(),
+            self.state_snapshot_adapter.as_multi_sender(),
         );
 
         let delete_snapshot_callback =
-            get_delete_snapshot_callback(state_snapshot_adapter.as_multi_sender());
+            get_delete_snapshot_callback(self.state_snapshot_adapter.as_multi_sender());
         let make_snapshot_callback = get_make_snapshot_callback(
-            state_snapshot_adapter.as_multi_sender(),
+            self.state_snapshot_adapter.as_multi_sender(),
             runtime_adapter.get_flat_storage_manager(),
         );
         let snapshot_callbacks =
             SnapshotCallbacks { make_snapshot_callback, delete_snapshot_callback };
 
         let validator_signer = MutableConfigValue::new(
-            Some(Arc::new(create_test_signer(self.clients[idx].as_str()))),
+            Some(Arc::new(create_test_signer(self.account_id.as_str()))),
             "validator_signer",
         );
 
-        let shards_manager_adapter = LateBoundSender::new();
         let client_to_shards_manager_sender = Arc::new(ClientToShardsManagerSender {
-            sender: shards_manager_adapter.clone(),
+            sender: self.shards_manager_adapter.clone(),
             chunks_storage: self.chunks_storage.clone(),
         });
 
-        // Generate a PeerId. It doesn't matter what this is. We're just making it based on
-        // the account ID, so that it is stable across multiple runs in the same test.
-        let peer_id = PeerId::new(create_test_signer(self.clients[idx].as_str()).public_key());
-
-        let client = Client::new(
-            self.test_loop.clock(),
+        #[allow(unused_mut)]
+        let mut client = Client::new(
+            self.clock(test_loop),
             client_config.clone(),
             chain_genesis.clone(),
             epoch_manager.clone(),
             shard_tracker.clone(),
             runtime_adapter.clone(),
-            network_adapter.as_multi_sender(),
+            self.network_adapter.as_multi_sender(),
             client_to_shards_manager_sender.as_sender(),
             validator_signer.clone(),
             true,
             [0; 32],
             Some(snapshot_callbacks),
-            Arc::new(self.test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
-            partial_witness_adapter.as_multi_sender(),
-            resharding_sender.as_multi_sender(),
-            Arc::new(self.test_loop.future_spawner()),
-            client_adapter.as_multi_sender(),
+            Arc::new(test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
+            Arc::new(test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
+            self.partial_witness_adapter.as_multi_sender(),
+            self.resharding_sender.as_multi_sender(),
+            Arc::new(test_loop.future_spawner()),
+            self.client_adapter.as_multi_sender(),
             self.upgrade_schedule.clone(),
         )
         .unwrap();
+        #[cfg(feature = "test_features")]
+        {
+            client.produce_invalid_chunks =
+                self.byzantine_behaviors.contains(&ByzantineBehavior::ProduceInvalidChunks);
+        }
 
         // If this is an archival node and split storage is initialized, then create view-specific
         // versions of EpochManager, ShardTracker and RuntimeAdapter and use them to initiaze the
         // ViewClientActorInner. Otherwise, we use the regular versions created above.
         let (view_epoch_manager, view_shard_tracker, view_runtime_adapter) =
-            if let Some(split_store) = &split_store {
+            if let Some(split_store) = &self.split_store {
                 let view_epoch_manager = EpochManager::new_arc_handle_from_epoch_config_store(
                     split_store.clone(),
-                    &genesis.config,
-                    epoch_config_store.clone(),
+                    &self.genesis.config,
+                    self.epoch_config_store.clone(),
                 );
                 let view_shard_tracker = ShardTracker::new(
-                    TrackedConfig::from_config(&client_config),
+                    TrackedConfig::from_config(client_config),
                     epoch_manager.clone(),
                 );
                 let view_runtime_adapter = NightshadeRuntime::test_with_trie_config(
-                    &homedir,
+                    &self.homedir,
                     split_store.clone(),
                     ContractRuntimeCache::handle(&contract_cache),
-                    &genesis.config,
+                    &self.genesis.config,
                     view_epoch_manager.clone(),
                     self.runtime_config_store.clone(),
-                    TrieConfig::from_store_config(&store_config),
+                    TrieConfig::from_store_config(&self.store_config),
                     StateSnapshotType::EveryEpoch,
                     client_config.gc.gc_num_epochs_to_keep,
                 );
@@ -693,74 +1090,84 @@ impl TestLoopBuilder {
                 (epoch_manager.clone(), shard_tracker.clone(), runtime_adapter.clone())
             };
         let view_client_actor = ViewClientActorInner::new(
-            self.test_loop.clock(),
+            self.clock(test_loop),
             validator_signer.clone(),
             chain_genesis.clone(),
             view_epoch_manager.clone(),
             view_shard_tracker,
             view_runtime_adapter,
-            network_adapter.as_multi_sender(),
+            self.network_adapter.as_multi_sender(),
             client_config.clone(),
             near_client::adversarial::Controls::default(),
         )
         .unwrap();
 
         let shards_manager = ShardsManagerActor::new(
-            self.test_loop.clock(),
+            self.clock(test_loop),
             validator_signer.clone(),
             epoch_manager.clone(),
             view_epoch_manager,
             shard_tracker.clone(),
-            network_adapter.as_sender(),
-            client_adapter.as_sender(),
-            store.chunk_store(),
+            self.network_adapter.as_sender(),
+            self.client_adapter.as_sender(),
+            self.store.chunk_store(),
             client.chain.head().unwrap(),
             client.chain.header_head().unwrap(),
             Duration::milliseconds(100),
+            Arc::new(test_loop.async_computation_spawner(|_| Duration::ZERO)),
         );
 
         let client_actor = ClientActorInner::new(
-            self.test_loop.clock(),
+            self.clock(test_loop),
             client,
-            client_adapter.as_multi_sender(),
-            peer_id.clone(),
-            network_adapter.as_multi_sender(),
+            self.client_adapter.as_multi_sender(),
+            self.peer_id.clone(),
+            self.network_adapter.as_multi_sender(),
             noop().into_sender(),
             None,
             Default::default(),
             None,
-            sync_jobs_adapter.as_multi_sender(),
+            self.sync_jobs_adapter.as_multi_sender(),
         )
         .unwrap();
 
         let partial_witness_actor = PartialWitnessActor::new(
-            self.test_loop.clock(),
-            network_adapter.as_multi_sender(),
-            client_adapter.as_multi_sender(),
+            self.clock(test_loop),
+            self.network_adapter.as_multi_sender(),
+            self.client_adapter.as_multi_sender(),
             validator_signer.clone(),
             epoch_manager.clone(),
             runtime_adapter.clone(),
-            Arc::new(self.test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
+            Arc::new(test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
         );
 
         let gc_actor = GCActor::new(
             runtime_adapter.store().clone(),
             chain_genesis.height,
+            chain_genesis.transaction_validity_period,
             runtime_adapter.clone(),
             epoch_manager.clone(),
+            shard_tracker.clone(),
+            validator_signer.clone(),
             client_config.gc.clone(),
             client_config.archive,
         );
-        // We don't send messages to `GCActor` so adapter is not needed.
-        self.test_loop.register_actor_for_index(idx, gc_actor, None);
 
         let resharding_actor =
             ReshardingActor::new(runtime_adapter.store().clone(), chain_genesis.height);
 
-        let future_spawner = self.test_loop.future_spawner();
+        let cold_store_actor = self.cold_db.as_ref().map(|cold_db| ColdStoreActor {
+            hot_store: self.store.clone(),
+            cold_db: cold_db.clone(),
+            genesis_height: chain_genesis.height,
+            epoch_manager: epoch_manager.clone(),
+            split_storage_config: SplitStorageConfig::default(),
+        });
+
+        let future_spawner = test_loop.future_spawner();
         let state_sync_dumper = StateSyncDumper {
-            clock: self.test_loop.clock(),
-            client_config,
+            clock: self.clock(test_loop),
+            client_config: client_config.clone(),
             chain_genesis,
             epoch_manager: epoch_manager.clone(),
             shard_tracker,
@@ -770,81 +1177,245 @@ impl TestLoopBuilder {
                 future_spawner.spawn_boxed("state_sync_dumper", future);
                 Box::new(|| {})
             }),
-            future_spawner: Arc::new(self.test_loop.future_spawner()),
+            future_spawner: Arc::new(test_loop.future_spawner()),
             handle: None,
         };
-        let state_sync_dumper_handle = self.test_loop.data.register_data(state_sync_dumper);
 
-        let client_sender =
-            self.test_loop.register_actor_for_index(idx, client_actor, Some(client_adapter));
-        let view_client_sender =
-            self.test_loop.register_actor_for_index(idx, view_client_actor, None);
-        let shards_manager_sender = self.test_loop.register_actor_for_index(
-            idx,
+        NodeActors {
+            client_actor,
+            view_client_actor,
             shards_manager,
-            Some(shards_manager_adapter),
+            partial_witness_actor,
+            sync_jobs_actor,
+            state_snapshot,
+            resharding_actor,
+            gc_actor,
+            cold_store_actor,
+            state_sync_dumper,
+            epoch_manager,
+        }
+    }
+}
+
+impl NodeState {
+    /// Recreates the actors of a killed node from its store, in place of the previous ones,
+    /// so that the senders to the node keep working.
+    pub(crate) fn restart(&self, test_loop: &mut TestLoopV2, data: &TestData) {
+        let idx = self.setup.index;
+        let actors = self.setup.create_actors(test_loop);
+        let test_loop_data = &mut test_loop.data;
+        test_loop_data.replace_actor_for_index(idx, &self.gc_handle, actors.gc_actor);
+        if let (Some(handle), Some(actor)) = (&self.cold_store_handle, actors.cold_store_actor) {
+            test_loop_data.replace_actor_for_index(idx, handle, actor);
+        }
+        *test_loop_data.get_mut(&data.state_sync_dumper_handle) = actors.state_sync_dumper;
+        test_loop_data.replace_actor_for_index(
+            idx,
+            &data.client_sender.actor_handle(),
+            actors.client_actor,
         );
-        let partial_witness_sender = self.test_loop.register_actor_for_index(
+        test_loop_data.replace_actor_for_index(
             idx,
-            partial_witness_actor,
-            Some(partial_witness_adapter),
+            &data.view_client_sender.actor_handle(),
+            actors.view_client_actor,
         );
-        self.test_loop.register_actor_for_index(idx, sync_jobs_actor, Some(sync_jobs_adapter));
-        self.test_loop.register_actor_for_index(idx, state_snapshot, Some(state_snapshot_adapter));
-        self.test_loop.register_actor_for_index(idx, resharding_actor, Some(resharding_sender));
-
-        // State sync dumper is not an Actor, handle starting separately.
-        let state_sync_dumper_handle_clone = state_sync_dumper_handle.clone();
-        self.test_loop.send_adhoc_event(
-            "start_state_sync_dumper".to_owned(),
-            move |test_loop_data| {
-                test_loop_data.get_mut(&state_sync_dumper_handle_clone).start().unwrap();
-            },
+        test_loop_data.replace_actor_for_index(
+            idx,
+            &data.shards_manager_sender.actor_handle(),
+            actors.shards_manager,
+        );
+        test_loop_data.replace_actor_for_index(
+            idx,
+            &data.partial_witness_sender.actor_handle(),
+            actors.partial_witness_actor,
         );
+        test_loop_data.replace_actor_for_index(idx, &self.sync_jobs_handle, actors.sync_jobs_actor);
+        test_loop_data.replace_actor_for_index(
+            idx,
+            &self.state_snapshot_handle,
+            actors.state_snapshot,
+        );
+        test_loop_data.replace_actor_for_index(
+            idx,
+            &self.resharding_handle,
+            actors.resharding_actor,
+        );
+        start_state_sync_dumper(test_loop, &data.state_sync_dumper_handle);
+    }
+}
 
-        let data = TestData {
-            account_id: self.clients[idx].clone(),
-            peer_id,
-            client_sender,
-            view_client_sender,
-            shards_manager_sender,
-            partial_witness_sender,
-            state_sync_dumper_handle,
-        };
-        (data, network_adapter, epoch_manager)
+impl NodeSetup {
+    /// Setup of a node joining the network of `template`'s node while the test runs. The node
+    /// tracks all shards and starts with an empty store holding only the genesis.
+    pub(crate) fn for_joining_node(
+        template: &NodeSetup,
+        index: usize,
+        account_id: AccountId,
+        tempdir: &TempDir,
+        config_modifier: impl FnOnce(&mut ClientConfig),
+    ) -> NodeSetup {
+        let mut client_config = new_client_config(&template.genesis.config, tempdir, false);
+        client_config.gc = template.client_config.gc.clone();
+        client_config.tracked_shards = vec![ShardId::new(666)];
+        config_modifier(&mut client_config);
+
+        let homedir = tempdir.path().join(format!("{}", index));
+        std::fs::create_dir_all(&homedir).expect("Unable to create homedir");
+        let store_config =
+            StoreConfig { path: Some(homedir.clone()), ..template.store_config.clone() };
+        let store = create_test_store();
+        initialize_genesis_state(store.clone(), &template.genesis, None);
+
+        NodeSetup {
+            index,
+            peer_id: PeerId::new(create_test_signer(account_id.as_str()).public_key()),
+            account_id,
+            client_config,
+            genesis: template.genesis.clone(),
+            epoch_config_store: template.epoch_config_store.clone(),
+            runtime_config_store: template.runtime_config_store.clone(),
+            upgrade_schedule: template.upgrade_schedule.clone(),
+            byzantine_behaviors: vec![],
+            clock_skew: None,
+            homedir,
+            store_config,
+            store,
+            split_store: None,
+            cold_db: None,
+            chunks_storage: template.chunks_storage.clone(),
+            client_adapter: LateBoundSender::new(),
+            network_adapter: LateBoundSender::new(),
+            state_snapshot_adapter: LateBoundSender::new(),
+            partial_witness_adapter: LateBoundSender::new(),
+            sync_jobs_adapter: LateBoundSender::new(),
+            resharding_sender: LateBoundSender::new(),
+            shards_manager_adapter: LateBoundSender::new(),
+        }
     }
+}
 
-    // TODO: we assume that all `Vec`s have the same length, consider
-    // joining them into one structure.
-    fn setup_network(
-        &mut self,
-        datas: &Vec<TestData>,
-        network_adapters: &Vec<Arc<LateBoundSender<TestLoopSender<TestLoopPeerManagerActor>>>>,
-        epoch_manager_adapters: &Vec<Arc<dyn EpochManagerAdapter>>,
-    ) {
-        let shared_state = Arc::new(TestLoopNetworkSharedState::new(&datas));
-        for (idx, data) in datas.iter().enumerate() {
-            let mut peer_manager_actor = TestLoopPeerManagerActor::new(
-                self.test_loop.clock(),
-                &data.account_id,
-                shared_state.clone(),
-                Arc::new(self.test_loop.future_spawner()),
-            );
+/// Creates the actors of a node and registers them in the test loop, except for the peer
+/// manager actor, see `register_peer_manager`.
+pub(crate) fn register_node(
+    test_loop: &mut TestLoopV2,
+    node_setup: NodeSetup,
+    network_faults: Arc<Mutex<NetworkFaults>>,
+    network_shared_state: Arc<TestLoopNetworkSharedState>,
+) -> (TestData, Arc<dyn EpochManagerAdapter>) {
+    let idx = node_setup.index;
+    let actors = node_setup.create_actors(test_loop);
+    let epoch_manager = actors.epoch_manager.clone();
+
+    // We don't send messages to `GCActor` so adapter is not needed.
+    let gc_sender = test_loop.register_actor_for_index(idx, actors.gc_actor, None);
+    let cold_store_handle = actors
+        .cold_store_actor
+        .map(|actor| test_loop.register_actor_for_index(idx, actor, None).actor_handle());
+    let state_sync_dumper_handle = test_loop.data.register_data(actors.state_sync_dumper);
+
+    let client_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.client_actor,
+        Some(node_setup.client_adapter.clone()),
+    );
+    let view_client_sender =
+        test_loop.register_actor_for_index(idx, actors.view_client_actor, None);
+    let shards_manager_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.shards_manager,
+        Some(node_setup.shards_manager_adapter.clone()),
+    );
+    let partial_witness_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.partial_witness_actor,
+        Some(node_setup.partial_witness_adapter.clone()),
+    );
+    let sync_jobs_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.sync_jobs_actor,
+        Some(node_setup.sync_jobs_adapter.clone()),
+    );
+    let state_snapshot_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.state_snapshot,
+        Some(node_setup.state_snapshot_adapter.clone()),
+    );
+    let resharding_actor_sender = test_loop.register_actor_for_index(
+        idx,
+        actors.resharding_actor,
+        Some(node_setup.resharding_sender.clone()),
+    );
+
+    // State sync dumper is not an Actor, handle starting separately.
+    start_state_sync_dumper(test_loop, &state_sync_dumper_handle);
+
+    let data = TestData {
+        account_id: node_setup.account_id.clone(),
+        peer_id: node_setup.peer_id.clone(),
+        client_sender,
+        view_client_sender,
+        shards_manager_sender,
+        partial_witness_sender,
+        state_sync_dumper_handle,
+        network_faults,
+        network_shared_state,
+        node_state: Arc::new(NodeState {
+            setup: node_setup,
+            gc_handle: gc_sender.actor_handle(),
+            sync_jobs_handle: sync_jobs_sender.actor_handle(),
+            state_snapshot_handle: state_snapshot_sender.actor_handle(),
+            resharding_handle: resharding_actor_sender.actor_handle(),
+            cold_store_handle,
+        }),
+    };
+    (data, epoch_manager)
+}
 
-            for condition in &self.drop_condition_kinds {
-                register_drop_condition(
-                    &mut peer_manager_actor,
-                    self.chunks_storage.clone(),
-                    epoch_manager_adapters[idx].clone(),
-                    condition,
-                );
-            }
+/// Creates the peer manager actor of a node and registers it in the test loop.
+/// `register_handlers` registers the override handlers of the node, before the handlers of
+/// its byzantine behaviors and the injector of the network faults.
+pub(crate) fn register_peer_manager(
+    test_loop: &mut TestLoopV2,
+    data: &TestData,
+    register_handlers: impl FnOnce(&mut TestLoopPeerManagerActor),
+) {
+    let mut peer_manager_actor = TestLoopPeerManagerActor::new(
+        data.node_state.setup.clock(test_loop),
+        &data.account_id,
+        data.network_shared_state.clone(),
+        Arc::new(test_loop.future_spawner()),
+    );
+    register_handlers(&mut peer_manager_actor);
+    register_byzantine_handlers(
+        &mut peer_manager_actor,
+        &data.account_id,
+        data.node_state.setup.network_adapter.clone(),
+        &data.node_state.setup.byzantine_behaviors,
+    );
+
+    // Registered last, so that the faults apply before the other handlers.
+    peer_manager_actor.register_override_handler(network_fault_injector(
+        data.account_id.clone(),
+        data.network_faults.clone(),
+    ));
+
+    let peer_manager_sender = test_loop.register_actor_for_index(
+        data.node_state.setup.index,
+        peer_manager_actor,
+        Some(data.node_state.setup.network_adapter.clone()),
+    );
+    data.network_faults
+        .lock()
+        .unwrap()
+        .register_sender(data.account_id.clone(), peer_manager_sender);
+}
 
-            self.test_loop.register_actor_for_index(
-                idx,
-                peer_manager_actor,
-                Some(network_adapters[idx].clone()),
-            );
-        }
-    }
+fn start_state_sync_dumper(
+    test_loop: &TestLoopV2,
+    state_sync_dumper_handle: &TestLoopDataHandle<StateSyncDumper>,
+) {
+    let state_sync_dumper_handle = state_sync_dumper_handle.clone();
+    test_loop.send_adhoc_event("start_state_sync_dumper".to_owned(), move |test_loop_data| {
+        test_loop_data.get_mut(&state_sync_dumper_handle).start().unwrap();
+    });
 }
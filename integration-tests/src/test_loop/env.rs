@@ -2,24 +2,33 @@ use near_async::messaging::{CanSend, IntoMultiSender, IntoSender, LateBoundSende
 use near_async::test_loop::data::{TestLoopData, TestLoopDataHandle};
 use near_async::test_loop::sender::TestLoopSender;
 use near_async::test_loop::TestLoopV2;
-use near_async::time::Duration;
+use near_async::time::{Duration, SkewedClock};
+use near_chain_configs::{ClientConfig, TrackedShardsConfig};
 use near_chunks::adapter::ShardsManagerRequestFromClient;
 use near_chunks::shards_manager_actor::ShardsManagerActor;
 use near_client::client_actor::ClientActorInner;
-use near_client::{PartialWitnessActor, ViewClientActorInner};
+use near_client::{PartialWitnessActor, SetNetworkInfo, ViewClientActorInner};
 use near_jsonrpc::ViewClientSenderForRpc;
 use near_network::shards_manager::ShardsManagerRequestFromNetwork;
 use near_network::state_witness::PartialWitnessSenderForNetwork;
-use near_network::test_loop::{ClientSenderForTestLoopNetwork, ViewClientSenderForTestLoopNetwork};
+use near_network::test_loop::{
+    ClientSenderForTestLoopNetwork, TestLoopNetworkSharedState, ViewClientSenderForTestLoopNetwork,
+};
+use near_network::types::{HighestHeightPeerInfo, NetworkInfo, PeerInfo};
+use near_primitives::block::GenesisId;
 use near_primitives::network::PeerId;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::types::AccountId;
-use near_primitives_core::types::BlockHeight;
+use near_primitives_core::types::{BlockHeight, BlockHeightDelta, EpochHeight};
 use nearcore::state_sync::StateSyncDumper;
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use tempfile::TempDir;
 
+use super::builder::{register_node, register_peer_manager, NodeSetup, NodeState};
+use super::utils::get_node_data;
+use super::utils::network_faults::{NetworkFault, NetworkFaultId, NetworkFaults};
+
 const NETWORK_DELAY: Duration = Duration::milliseconds(10);
 
 pub struct TestLoopEnv {
@@ -62,6 +71,191 @@ impl TestLoopEnv {
         Self { test_loop, datas, tempdir }
     }
 
+    /// Injects a network fault between the nodes until it is removed with
+    /// `remove_network_fault`.
+    pub fn add_network_fault(&self, fault: NetworkFault) -> NetworkFaultId {
+        self.datas[0].network_faults.lock().unwrap().add(fault)
+    }
+
+    /// Removes a network fault. The messages held by the fault are sent right away.
+    pub fn remove_network_fault(&self, id: NetworkFaultId) {
+        self.datas[0].network_faults.lock().unwrap().remove(id);
+    }
+
+    /// Injects a network fault while the head of the first node advances by `num_blocks`
+    /// blocks, e.g. to drop the chunk endorsements of a validator for 10 blocks.
+    pub fn run_with_network_fault(
+        &mut self,
+        fault: NetworkFault,
+        num_blocks: BlockHeightDelta,
+        maximum_duration: Duration,
+    ) {
+        let client_handle = self.datas[0].client_sender.actor_handle();
+        let head_height = |test_loop_data: &TestLoopData| {
+            test_loop_data.get(&client_handle).client.chain.head().unwrap().height
+        };
+        let target_height = head_height(&self.test_loop.data) + num_blocks;
+        let id = self.add_network_fault(fault);
+        self.test_loop.run_until(
+            |test_loop_data| head_height(test_loop_data) >= target_height,
+            maximum_duration,
+        );
+        self.remove_network_fault(id);
+    }
+
+    /// Kills the node of `account_id` mid-test, as if it crashed: its actors stop handling
+    /// events and the messages sent to it are dropped, until it is restarted with
+    /// `restart_node`. Its store is kept.
+    pub fn kill_node(&mut self, account_id: &AccountId) {
+        let data = get_node_data(&self.datas, account_id);
+        tracing::info!(target: "test", %account_id, "killing node");
+        // State sync dumper is not an Actor, handle stopping separately.
+        self.test_loop.data.get_mut(&data.state_sync_dumper_handle).stop();
+        self.test_loop.kill_index(data.node_state.setup.index);
+    }
+
+    /// Restarts a node killed with `kill_node` from its store. The actors of the node are
+    /// recreated in place of the killed ones, so its `TestData` and the senders to it stay
+    /// valid.
+    pub fn restart_node(&mut self, account_id: &AccountId) {
+        let data = get_node_data(&self.datas, account_id).clone();
+        tracing::info!(target: "test", %account_id, "restarting node");
+        self.test_loop.revive_index(data.node_state.setup.index);
+        data.node_state.restart(&mut self.test_loop, &data);
+    }
+
+    /// Restarts a node killed with `kill_node` from its store, as `restart_node` does, with a
+    /// change of the shards it tracks, e.g. to cover a node starting or stopping to track a
+    /// shard. As for a config update of a running node, the new config applies from the epoch
+    /// after the next one of the head of the node, so that the node catches up on the State of
+    /// the shards it starts tracking during the next epoch. A later `restart_node` goes back to
+    /// the config the node was built with.
+    ///
+    /// Returns the height of the epoch from which the new config applies.
+    pub fn restart_node_with_tracked_shards(
+        &mut self,
+        account_id: &AccountId,
+        tracked_shards_config: TrackedShardsConfig,
+    ) -> EpochHeight {
+        self.restart_node(account_id);
+        let data = get_node_data(&self.datas, account_id);
+        let client = &self.test_loop.data.get(&data.client_sender.actor_handle()).client;
+        let head = client.chain.head().unwrap();
+        let head_epoch_height =
+            client.epoch_manager.get_epoch_info(&head.epoch_id).unwrap().epoch_height();
+        tracing::info!(target: "test", %account_id, ?tracked_shards_config, "changing tracked shards");
+        client.shard_tracker.update_tracked_config(tracked_shards_config.into(), head_epoch_height);
+        head_epoch_height + 2
+    }
+
+    /// Adds a new node to the running network, e.g. to test that a node can sync from scratch.
+    /// The node isn't a validator, tracks all shards and starts with only the genesis in its
+    /// store, then syncs to the head of the other nodes; `config_modifier` can e.g. lower the
+    /// sync horizons. The drop conditions of the builder don't apply to the node.
+    ///
+    /// Returns the index of the node in `datas`.
+    pub fn add_node(
+        &mut self,
+        account_id: AccountId,
+        config_modifier: impl FnOnce(&mut ClientConfig),
+    ) -> usize {
+        tracing::info!(target: "test", %account_id, "adding node");
+        let index = self.datas.len();
+        let template = &self.datas[0];
+        let node_setup = NodeSetup::for_joining_node(
+            &template.node_state.setup,
+            index,
+            account_id,
+            &self.tempdir,
+            config_modifier,
+        );
+        let (data, _) = register_node(
+            &mut self.test_loop,
+            node_setup,
+            template.network_faults.clone(),
+            template.network_shared_state.clone(),
+        );
+        data.network_shared_state.add_node(&data);
+        register_peer_manager(&mut self.test_loop, &data, |_| {});
+
+        // The networking layer is mocked out, so nothing tells the new node about its peers.
+        // Tell it the heads of the other nodes, so that it starts syncing from them.
+        self.send_highest_height_peers(&data, self.datas.iter());
+        self.datas.push(data);
+        index
+    }
+
+    /// Partitions the network into groups of nodes which only reach the nodes of their own
+    /// group, until `heal_network_partition` is called. The nodes missing from `groups` form
+    /// one more group.
+    pub fn partition_network(&self, groups: &[Vec<AccountId>]) {
+        tracing::info!(target: "test", ?groups, "partitioning network");
+        self.datas[0].network_shared_state.partition(groups);
+    }
+
+    /// Heals the partition of the network. The nodes are told the heads of the others, so that
+    /// the nodes left behind by the partition sync to the head of the network.
+    pub fn heal_network_partition(&self) {
+        tracing::info!(target: "test", "healing network partition");
+        self.datas[0].network_shared_state.heal_partition();
+        for data in &self.datas {
+            let peers = self.datas.iter().filter(|peer| peer.account_id != data.account_id);
+            self.send_highest_height_peers(data, peers);
+        }
+    }
+
+    /// Partitions the network into `groups` for `duration`, then heals the partition. A
+    /// partition can stop the whole network, e.g. if no group has two thirds of the stake, so
+    /// it lasts for a duration rather than for a number of blocks.
+    pub fn run_with_network_partition(&mut self, groups: &[Vec<AccountId>], duration: Duration) {
+        self.partition_network(groups);
+        self.test_loop.run_for(duration);
+        self.heal_network_partition();
+    }
+
+    /// The networking layer is mocked out, so the nodes don't learn the heads of their peers.
+    /// Tells the node the heads of `peers`, from which it can sync.
+    fn send_highest_height_peers<'a>(
+        &self,
+        data: &TestData,
+        peers: impl Iterator<Item = &'a TestData>,
+    ) {
+        let highest_height_peers = peers.map(|peer| self.highest_height_peer_info(peer)).collect();
+        data.client_sender.send(SetNetworkInfo(NetworkInfo {
+            connected_peers: Vec::new(),
+            highest_height_peers, // only this field matters.
+            known_producers: vec![],
+            num_connected_peers: 0,
+            peer_max_count: 0,
+            received_bytes_per_sec: 0,
+            sent_bytes_per_sec: 0,
+            tier1_accounts_data: Vec::new(),
+            tier1_accounts_keys: Vec::new(),
+            tier1_connections: Vec::new(),
+        }));
+    }
+
+    fn highest_height_peer_info(&self, data: &TestData) -> HighestHeightPeerInfo {
+        let client = &self.test_loop.data.get(&data.client_sender.actor_handle()).client;
+        let head = client.chain.head().unwrap();
+        HighestHeightPeerInfo {
+            archival: false,
+            earliest_block_height: None,
+            genesis_id: GenesisId {
+                chain_id: data.node_state.setup.genesis.config.chain_id.clone(),
+                hash: *client.chain.genesis().hash(),
+            },
+            highest_block_hash: head.last_block_hash,
+            highest_block_height: head.height,
+            tracked_shards: vec![],
+            peer_info: PeerInfo {
+                account_id: Some(data.account_id.clone()),
+                addr: None,
+                id: data.peer_id.clone(),
+            },
+        }
+    }
+
     /// Used to finish off remaining events that are still in the loop. This can be necessary if the
     /// destructor of some components wait for certain condition to become true. Otherwise, the
     /// destructors may end up waiting forever. This also helps avoid a panic when destructing
@@ -146,6 +340,20 @@ pub struct TestData {
     pub shards_manager_sender: TestLoopSender<ShardsManagerActor>,
     pub partial_witness_sender: TestLoopSender<PartialWitnessActor>,
     pub state_sync_dumper_handle: TestLoopDataHandle<StateSyncDumper>,
+    /// Network faults injected between the nodes, shared by all the nodes.
+    pub network_faults: Arc<Mutex<NetworkFaults>>,
+    /// Routing between the nodes, shared by all the nodes.
+    pub network_shared_state: Arc<TestLoopNetworkSharedState>,
+    /// State needed to restart the node after it was killed.
+    pub(crate) node_state: Arc<NodeState>,
+}
+
+impl TestData {
+    /// Clock of the node if it was skewed with `TestLoopBuilder::clock_skew`, to change its
+    /// skew while the test runs.
+    pub(crate) fn clock_skew(&self) -> Option<&SkewedClock> {
+        self.node_state.setup.clock_skew.as_ref()
+    }
 }
 
 impl From<&TestData> for AccountId {
@@ -0,0 +1,87 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::{
+    execute_tx, get_next_nonce, get_shared_block_hash, view_access_key, view_access_key_list,
+};
+use crate::test_loop::utils::ONE_NEAR;
+
+/// The view client of a node saving the access key usage reports the height of the block in
+/// which an access key last signed a transaction, for both single keys and key lists.
+#[test]
+fn test_view_access_key_last_used_block_height() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..3).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let validators = accounts[..2].iter().map(|a| a.as_str()).collect_vec();
+    let rpc_id = accounts[2].clone();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::single_shard(),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts.clone())
+        .config_modifier(|config, _| config.save_access_key_usage = true)
+        .build();
+    env.test_loop.run_for(Duration::seconds(3));
+
+    let sender = &accounts[0];
+    let signer = create_user_test_signer(sender);
+    let nonce = get_next_nonce(&env.test_loop.data, &env.datas, sender);
+    let block_hash = get_shared_block_hash(&env.datas, &env.test_loop.data);
+    let tx = SignedTransaction::send_money(
+        nonce,
+        sender.clone(),
+        accounts[1].clone(),
+        &signer,
+        ONE_NEAR,
+        block_hash,
+    );
+    let outcome =
+        execute_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5)).unwrap();
+    let rpc_client = &env.test_loop.data.get(&env.datas[2].client_sender.actor_handle()).client;
+    let tx_height = rpc_client
+        .chain
+        .get_block_header(&outcome.transaction_outcome.block_hash)
+        .unwrap()
+        .height();
+
+    let access_key =
+        view_access_key(&mut env.test_loop.data, &env.datas, &rpc_id, sender, &signer.public_key())
+            .unwrap();
+    assert_eq!(access_key.last_used_block_height, Some(tx_height));
+    let access_keys =
+        view_access_key_list(&mut env.test_loop.data, &env.datas, &rpc_id, sender).unwrap();
+    let key = access_keys.keys.iter().find(|key| key.public_key == signer.public_key()).unwrap();
+    assert_eq!(key.access_key.last_used_block_height, Some(tx_height));
+
+    // The key of the receiver never signed a transaction.
+    let receiver = &accounts[1];
+    let receiver_key = create_user_test_signer(receiver).public_key();
+    let access_key =
+        view_access_key(&mut env.test_loop.data, &env.datas, &rpc_id, receiver, &receiver_key)
+            .unwrap();
+    assert_eq!(access_key.last_used_block_height, None);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
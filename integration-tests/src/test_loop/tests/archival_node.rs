@@ -0,0 +1,103 @@
+use std::collections::HashSet;
+
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::archival::{assert_history_available, get_block_by_height};
+use crate::test_loop::utils::transactions::execute_money_transfers;
+use crate::test_loop::utils::{get_final_height, get_head_height, get_node_data};
+
+const NUM_VALIDATORS: usize = 2;
+const EPOCH_LENGTH: u64 = 10;
+const GC_NUM_EPOCHS_TO_KEEP: u64 = 3;
+const GENESIS_HEIGHT: BlockHeight = 10000;
+
+fn tail_height(env: &TestLoopEnv, account_id: &AccountId) -> BlockHeight {
+    let handle = get_node_data(&env.datas, account_id).client_sender.actor_handle();
+    env.test_loop.data.get(&handle).client.chain.tail().unwrap()
+}
+
+/// Runs 2 validators and a non-validator archival node for enough epochs for the validators to
+/// garbage collect the blocks of the money transfers executed at the start, then checks that the
+/// archival node still serves all the blocks, chunks and execution outcomes since then.
+/// Returns the env and the account of the archival node.
+fn run_archival_node(legacy_archival_storage: bool) -> (TestLoopEnv, AccountId) {
+    init_test_logger();
+    let mut builder = TestLoopBuilder::new();
+
+    let accounts =
+        (0..20).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let validators = accounts.iter().take(NUM_VALIDATORS).map(|t| t.as_str()).collect_vec();
+    let validator = accounts[0].clone();
+    let archival = accounts[NUM_VALIDATORS].clone();
+    let clients = accounts.iter().take(NUM_VALIDATORS + 1).cloned().collect_vec();
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::simple_v1(&["account3", "account5", "account7"]),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| {
+            genesis_builder.genesis_height(GENESIS_HEIGHT).transaction_validity_period(1000)
+        },
+        |epoch_config_builder| epoch_config_builder,
+    );
+    if legacy_archival_storage {
+        builder = builder.legacy_archival_storage();
+    }
+    let mut env = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .archival_clients(HashSet::from([archival.clone()]))
+        .gc_num_epochs_to_keep(GC_NUM_EPOCHS_TO_KEEP)
+        .build();
+
+    let transfers_start = get_head_height(&mut env);
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts[NUM_VALIDATORS..]).unwrap();
+    let transfers_end = get_head_height(&mut env);
+
+    // Run until the validators garbage collect the blocks of the transfers.
+    let validator_handle = get_node_data(&env.datas, &validator).client_sender.actor_handle();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            test_loop_data.get(&validator_handle).client.chain.tail().unwrap() > transfers_end
+        },
+        Duration::seconds((EPOCH_LENGTH * (GC_NUM_EPOCHS_TO_KEEP + 4)) as i64),
+    );
+    assert!(get_block_by_height(&mut env, &validator, transfers_start).is_err());
+
+    let final_height = get_final_height(&env, &archival);
+    let num_outcomes = assert_history_available(&mut env, &archival, transfers_start, final_height);
+    assert!(num_outcomes > 0, "no execution outcomes since height {transfers_start}");
+    assert_history_available(&mut env, &archival, GENESIS_HEIGHT + 1, transfers_start);
+    (env, archival)
+}
+
+/// The hot store of an archival node with split storage is garbage collected like the store of
+/// the validators, and the history is served from its cold store.
+#[test]
+fn slow_test_archival_node_split_storage() {
+    let (env, archival) = run_archival_node(false);
+    assert!(tail_height(&env, &archival) > GENESIS_HEIGHT);
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// An archival node without split storage doesn't garbage collect its store.
+#[test]
+fn slow_test_archival_node_legacy_storage() {
+    let (env, archival) = run_archival_node(true);
+    assert_eq!(tail_height(&env, &archival), GENESIS_HEIGHT);
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -9,9 +9,11 @@
 //! tests are useful to show that the scheduler works on a "real" chain. For more throughout testing
 //! of the scheduler algorithm itself, check out `ChainSimulator` which tests the scheduler on
 //! various scenarios and is able to run for much longer while using less CPU time.
+//! The hot receiver scenarios guard against regressions in asymmetric traffic, checking that no
+//! sender gets starved and that the receipts get delivered within a bounded number of blocks.
 
 use std::cell::RefCell;
-use std::collections::{BTreeMap, HashMap};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::convert::Infallible;
 use std::rc::Rc;
 use std::sync::Arc;
@@ -75,7 +77,7 @@ fn slow_test_bandwidth_scheduler_three_shards_random_receipts() {
         .num_shards(3)
         .default_link_generator(|| Box::new(RandomReceiptSizeGenerator))
         .build();
-    let summary = run_bandwidth_scheduler_test(scenario, 2000);
+    let summary = run_bandwidth_scheduler_test(scenario, 2000).summary;
     assert!(summary.bandwidth_utilization > 0.55); // 55% utilization
     assert!(summary.link_imbalance_ratio < 1.8); // < 80% difference on links
     assert!(summary.worst_link_estimation_ratio > 0.4); // 40% of estimated link throughput
@@ -95,7 +97,7 @@ fn slow_test_bandwidth_scheduler_four_shards_random_receipts_missing_chunks() {
         .default_link_generator(|| Box::new(RandomReceiptSizeGenerator))
         .missing_chunk_probability(0.1)
         .build();
-    let summary = run_bandwidth_scheduler_test(scenario, 2000);
+    let summary = run_bandwidth_scheduler_test(scenario, 2000).summary;
     assert!(summary.bandwidth_utilization > 0.35); // 35% utilization
     assert!(summary.link_imbalance_ratio < 6.0); // < 500% difference on links
     assert!(summary.worst_link_estimation_ratio > 0.1); // 10% of estimated link throughput
@@ -110,7 +112,122 @@ fn slow_test_bandwidth_scheduler_four_shards_random_receipts_missing_chunks() {
     assert!(summary.max_outgoing <= summary.max_shard_bandwidth);
 }
 
-fn run_bandwidth_scheduler_test(scenario: TestScenario, tx_concurrency: usize) -> TestSummary {
+/// One hot receiver shard with 3 senders.
+#[test]
+fn slow_test_bandwidth_scheduler_hot_receiver_four_shards() {
+    if !ProtocolFeature::BandwidthScheduler.enabled(PROTOCOL_VERSION) {
+        return;
+    }
+    run_hot_receiver_test(4);
+}
+
+/// One hot receiver shard with 5 senders.
+#[test]
+fn slow_test_bandwidth_scheduler_hot_receiver_six_shards() {
+    if !ProtocolFeature::BandwidthScheduler.enabled(PROTOCOL_VERSION) {
+        return;
+    }
+    run_hot_receiver_test(6);
+}
+
+/// Number of consecutive heights a link may have receipts buffered without sending any of them.
+/// A maximum size receipt at the front of the buffer has to wait for the link to accumulate
+/// enough allowance, which takes a few heights when the receiver is shared by many senders.
+const MAX_STARVED_HEIGHTS: u64 = 15;
+/// Maximum number of heights between the submission of a workload transaction and the execution
+/// of the receipt it sends to the hot receiver, including the time spent in outgoing buffers.
+const MAX_TX_LATENCY_HEIGHTS: u64 = 40;
+
+/// All the shards except shard 0 send receipts of random sizes to shard 0, which can't receive
+/// all of them. The scheduler has to share the bandwidth of the receiver fairly between the
+/// senders, without starving any of them.
+fn run_hot_receiver_test(num_shards: u64) {
+    let hot_receiver: ShardIndex = 0;
+    let mut scenario_builder = TestScenarioBuilder::new().num_shards(num_shards);
+    for sender in 1..num_shards as ShardIndex {
+        scenario_builder =
+            scenario_builder.link_generator(sender, hot_receiver, RandomReceiptSizeGenerator);
+    }
+    let scenario = scenario_builder.build();
+    let active_links = scenario.get_active_links();
+
+    // Enough transactions to keep the receiver saturated, but not so many that the latency is
+    // dominated by the length of the outgoing buffers.
+    let result = run_bandwidth_scheduler_test(scenario, 100 * num_shards as usize);
+    let summary = &result.summary;
+    assert!(summary.bandwidth_utilization > 0.5, "{summary}"); // 50% utilization
+    assert!(summary.link_imbalance_ratio < 3.0, "{summary}"); // < 200% difference on links
+    assert!(summary.worst_link_estimation_ratio > 0.3, "{summary}"); // 30% of estimated link throughput
+    assert!(summary.max_incoming <= summary.max_shard_bandwidth, "{summary}");
+    assert!(summary.max_outgoing <= summary.max_shard_bandwidth, "{summary}");
+
+    for (link, starved_heights) in longest_starvation(&result.bandwidth_stats, &active_links) {
+        assert!(
+            starved_heights <= MAX_STARVED_HEIGHTS,
+            "link {link:?} had buffered receipts but sent nothing for {starved_heights} heights"
+        );
+    }
+    for sender in 1..num_shards as ShardIndex {
+        let txs_done = result.txs_done.get(&sender).copied().unwrap_or_default();
+        assert!(txs_done > 0, "no transaction of shard {sender} completed");
+        let max_latency = result.max_tx_latency[&sender];
+        assert!(
+            max_latency <= MAX_TX_LATENCY_HEIGHTS,
+            "a transaction of shard {sender} took {max_latency} heights"
+        );
+    }
+}
+
+/// The longest streak of consecutive heights at which the sender of each link had receipts
+/// buffered for the receiver but didn't send any receipt to it.
+fn longest_starvation(
+    bandwidth_stats: &TestBandwidthStats,
+    active_links: &BTreeSet<(ShardIndex, ShardIndex)>,
+) -> BTreeMap<(ShardIndex, ShardIndex), u64> {
+    let mut current: BTreeMap<(ShardIndex, ShardIndex), u64> = BTreeMap::new();
+    let mut longest: BTreeMap<(ShardIndex, ShardIndex), u64> =
+        active_links.iter().map(|link| (*link, 0)).collect();
+    for ((_height, sender), chunk_stats) in &bandwidth_stats.chunk_stats {
+        for &(link_sender, receiver) in active_links {
+            if link_sender != *sender {
+                continue;
+            }
+            let has_buffered = chunk_stats
+                .size_of_buffered_receipts_to_shard
+                .get(&receiver)
+                .is_some_and(|size| size.as_u64() > 0);
+            let has_sent = chunk_stats
+                .size_of_outgoing_receipts_to_shard
+                .get(&receiver)
+                .is_some_and(|size| size.as_u64() > 0);
+            let streak = current.entry((*sender, receiver)).or_default();
+            if has_buffered && !has_sent {
+                *streak += 1;
+            } else {
+                *streak = 0;
+            }
+            let longest = longest.get_mut(&(*sender, receiver)).unwrap();
+            *longest = (*longest).max(*streak);
+        }
+    }
+    longest
+}
+
+/// Results of the workload of a bandwidth scheduler test.
+struct BandwidthSchedulerTestResult {
+    summary: TestSummary,
+    bandwidth_stats: TestBandwidthStats,
+    /// Number of completed workload transactions per sender shard.
+    txs_done: BTreeMap<ShardIndex, u64>,
+    /// The longest time, in heights, that a workload transaction took to complete or has been
+    /// running for at the end of the workload, per sender shard.
+    max_tx_latency: BTreeMap<ShardIndex, BlockHeight>,
+}
+
+fn run_bandwidth_scheduler_test(
+    scenario: TestScenario,
+    tx_concurrency: usize,
+) -> BandwidthSchedulerTestResult {
     init_test_logger();
     let active_links = scenario.get_active_links();
     let mut rng = ChaCha20Rng::seed_from_u64(0);
@@ -226,6 +343,8 @@ fn run_bandwidth_scheduler_test(scenario: TestScenario, tx_concurrency: usize) -
     test_loop.run_until(testloop_func, Duration::seconds(300));
 
     tracing::info!(target: "scheduler_test", "Total transactions completed: {}", workload_generator.txs_done());
+    let txs_done = workload_generator.txs_done_per_shard();
+    let max_tx_latency = workload_generator.max_tx_latency_per_shard(last_height.unwrap());
 
     let client = &test_loop.data.get(&client_handle).client;
     let bandwidth_stats =
@@ -236,7 +355,7 @@ fn run_bandwidth_scheduler_test(scenario: TestScenario, tx_concurrency: usize) -
 
     let summary = bandwidth_stats.summarize(&active_links);
     println!("{}", summary);
-    summary
+    BandwidthSchedulerTestResult { summary, bandwidth_stats, txs_done, max_tx_latency }
 }
 
 /// Analyze blocks and chunks produced while the workload was running
@@ -638,6 +757,30 @@ impl WorkloadGenerator {
     pub fn txs_done(&self) -> u64 {
         self.workload_senders.iter().map(|s| s.txs_done).sum()
     }
+
+    pub fn txs_done_per_shard(&self) -> BTreeMap<ShardIndex, u64> {
+        let mut txs_done = BTreeMap::new();
+        for sender in &self.workload_senders {
+            *txs_done.entry(sender.my_sender_shard_index).or_default() += sender.txs_done;
+        }
+        txs_done
+    }
+
+    /// The longest latency of the transactions of the senders which sent any, per shard.
+    pub fn max_tx_latency_per_shard(
+        &self,
+        current_height: BlockHeight,
+    ) -> BTreeMap<ShardIndex, BlockHeight> {
+        let mut max_latency = BTreeMap::new();
+        for sender in &self.workload_senders {
+            let Some(latency) = sender.max_latency(current_height) else {
+                continue;
+            };
+            let shard_max_latency = max_latency.entry(sender.my_sender_shard_index).or_default();
+            *shard_max_latency = latency.max(*shard_max_latency);
+        }
+        max_latency
+    }
 }
 
 /// Runs one transaction at a time using unique pair of (account, access_key)
@@ -650,6 +793,10 @@ struct WorkloadSender {
     tx_runner: Option<TransactionRunner>,
     txs_done: u64,
     my_sender_shard_index: ShardIndex,
+    /// Height at which the running transaction was started.
+    tx_start_height: Option<BlockHeight>,
+    /// The longest time, in heights, that a completed transaction took.
+    max_completed_latency: Option<BlockHeight>,
 }
 
 impl WorkloadSender {
@@ -672,9 +819,18 @@ impl WorkloadSender {
             txs_done: 0,
             link_generators,
             my_sender_shard_index,
+            tx_start_height: None,
+            max_completed_latency: None,
         }
     }
 
+    /// The longest latency of a completed transaction, or of the running one if it is longer.
+    /// None if the sender never started a transaction.
+    pub fn max_latency(&self, current_height: BlockHeight) -> Option<BlockHeight> {
+        let running_latency = self.tx_start_height.map(|start| current_height - start);
+        running_latency.max(self.max_completed_latency)
+    }
+
     pub fn run(
         &mut self,
         client_sender: &TestLoopSender<ClientActorInner>,
@@ -688,6 +844,9 @@ impl WorkloadSender {
                     Poll::Pending => {}
                     Poll::Ready(_) => {
                         self.txs_done += 1;
+                        let latency =
+                            client.chain.head().unwrap().height - self.tx_start_height.unwrap();
+                        self.max_completed_latency = Some(latency).max(self.max_completed_latency);
                         self.start_new_transaction(client_sender, client, future_spawner, rng)
                     }
                 }
@@ -704,6 +863,7 @@ impl WorkloadSender {
         rng: &mut ChaCha20Rng,
     ) {
         self.tx_runner = None;
+        self.tx_start_height = None;
 
         // Generate a new transaction
         // The transaction will do a function call to a contract deployed on this sender's account.
@@ -732,6 +892,7 @@ impl WorkloadSender {
         );
 
         // Start the transaction
+        self.tx_start_height = Some(client.chain.head().unwrap().height);
         let mut tx_runner = TransactionRunner::new(tx, true);
         let _poll_res = tx_runner.poll_assert_success(client_sender, client, future_spawner);
         self.tx_runner = Some(tx_runner);
@@ -0,0 +1,85 @@
+use near_async::time::Duration;
+use near_o11y::testonly::init_test_logger;
+
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::byzantine::ByzantineBehavior;
+use crate::test_loop::utils::setups::single_shard_validators_setup;
+use crate::test_loop::utils::{get_head_height, has_all_chunks};
+
+/// The single shard validators setup, with the given byzantine behaviors.
+fn setup_env(byzantine_behaviors: &[(&str, ByzantineBehavior)]) -> TestLoopEnv {
+    let mut builder = single_shard_validators_setup();
+    for (account_id, behavior) in byzantine_behaviors {
+        builder = builder.byzantine(account_id, *behavior);
+    }
+    builder.build()
+}
+
+/// Runs the chain for a while, and checks that it kept going with some chunks missing.
+fn assert_chunks_missing(env: &mut TestLoopEnv) {
+    let start_height = get_head_height(env);
+    env.test_loop.run_for(Duration::seconds(5));
+    let end_height = get_head_height(env);
+    assert!(end_height > start_height + 10, "the chain stalled at height {end_height}");
+    let has_all_chunks = has_all_chunks(env, start_height + 2..=end_height);
+    assert!(has_all_chunks.contains(&false), "no chunk was rejected");
+    assert!(has_all_chunks.contains(&true), "all chunks were rejected");
+}
+
+/// The chunks whose endorsements are withheld by half of the validators don't get enough
+/// endorsements to be included.
+#[test]
+fn test_byzantine_withhold_endorsements() {
+    init_test_logger();
+    let mut env = setup_env(&[
+        ("account2", ByzantineBehavior::WithholdEndorsements),
+        ("account3", ByzantineBehavior::WithholdEndorsements),
+    ]);
+    assert_chunks_missing(&mut env);
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// The validators can't reconstruct the malformed state witnesses of a chunk producer, so its
+/// chunks aren't endorsed.
+#[test]
+fn test_byzantine_malformed_state_witnesses() {
+    init_test_logger();
+    let mut env = setup_env(&[("account3", ByzantineBehavior::MalformedStateWitnesses)]);
+    assert_chunks_missing(&mut env);
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// The chunk validators reject the invalid chunks of a chunk producer.
+#[test]
+#[cfg_attr(not(feature = "test_features"), ignore)]
+fn test_byzantine_produce_invalid_chunks() {
+    init_test_logger();
+    let mut env = setup_env(&[("account3", ByzantineBehavior::ProduceInvalidChunks)]);
+    assert_chunks_missing(&mut env);
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// The blocks double signed by a block producer reach the other nodes as forks, and the chain
+/// keeps going.
+#[test]
+fn test_byzantine_double_sign_blocks() {
+    init_test_logger();
+    let mut env = setup_env(&[("account3", ByzantineBehavior::DoubleSignBlocks)]);
+
+    let start_height = get_head_height(&mut env);
+    env.test_loop.run_for(Duration::seconds(5));
+    let end_height = get_head_height(&mut env);
+    assert!(end_height > start_height + 10, "the chain stalled at height {end_height}");
+
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let chain_store = env.test_loop.data.get(&client_handle).client.chain.chain_store();
+    let num_double_signed_heights = (start_height..end_height)
+        .filter(|&height| {
+            let block_hashes = chain_store.get_all_block_hashes_by_height(height).unwrap();
+            block_hashes.values().map(|hashes| hashes.len()).sum::<usize>() > 1
+        })
+        .count();
+    assert!(num_double_signed_heights > 0, "no double signed block reached the first node");
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,95 @@
+use std::cell::Cell;
+
+use itertools::Itertools;
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::stateless_validation::ChunkProductionKey;
+use near_primitives::types::{AccountId, ValidatorKickoutReason};
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::validators::get_epoch_all_validators;
+
+const NUM_VALIDATORS: usize = 4;
+const EPOCH_LENGTH: u64 = 10;
+
+/// One of the chunk producers misses its chunks at even heights. Checks that no other chunk is
+/// missed and that the producer is kicked out for producing only half of its chunks.
+#[test]
+fn slow_test_chunk_producer_kicked_out_for_missed_chunks() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..NUM_VALIDATORS).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let missing_producer = accounts[1].clone();
+    let shard_layout = ShardLayout::single_shard();
+    let shard_id = shard_layout.shard_ids().next().unwrap();
+    let validators = accounts.iter().map(|a| a.as_str()).collect_vec();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder.kickouts_standard_80_percent(),
+    );
+
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts)
+        .drop_chunks_produced_by(missing_producer.as_str(), shard_id, |height| height % 2 == 0)
+        .build();
+
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let last_checked_height = Cell::new(0);
+    let num_missed_chunks = Cell::new(0);
+    let success_condition = |test_loop_data: &mut TestLoopData| -> bool {
+        let client = &test_loop_data.get(&client_handle).client;
+        let tip = client.chain.head().unwrap();
+        if tip.height == last_checked_height.get() {
+            return false;
+        }
+        last_checked_height.set(tip.height);
+
+        let block = client.chain.get_block(&tip.last_block_hash).unwrap();
+        if !block.header().chunk_mask()[0] {
+            let chunk_producer = client
+                .epoch_manager
+                .get_chunk_producer_info(&ChunkProductionKey {
+                    shard_id,
+                    epoch_id: tip.epoch_id,
+                    height_created: tip.height,
+                })
+                .unwrap();
+            assert_eq!(chunk_producer.account_id(), &missing_producer, "unexpected missed chunk");
+            assert_eq!(tip.height % 2, 0, "chunk missed at an odd height");
+            num_missed_chunks.set(num_missed_chunks.get() + 1);
+        }
+
+        let epoch_height =
+            client.epoch_manager.get_epoch_height_from_prev_block(&tip.prev_block_hash).unwrap();
+        assert!(epoch_height < 5, "the chunk producer wasn't kicked out");
+        if get_epoch_all_validators(client).contains(&missing_producer.to_string()) {
+            return false;
+        }
+        let epoch_info = client.epoch_manager.get_epoch_info(&tip.epoch_id).unwrap();
+        assert!(matches!(
+            epoch_info.validator_kickout().get(&missing_producer),
+            Some(ValidatorKickoutReason::NotEnoughChunks { .. })
+        ));
+        true
+    };
+    env.test_loop.run_until(success_condition, Duration::seconds((5 * EPOCH_LENGTH) as i64));
+    assert!(num_missed_chunks.get() > 0);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
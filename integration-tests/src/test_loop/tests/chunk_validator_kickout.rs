@@ -1,6 +1,6 @@
 use crate::test_loop::builder::TestLoopBuilder;
 use crate::test_loop::env::TestLoopEnv;
-use crate::test_loop::utils::validators::get_epoch_all_validators;
+use crate::test_loop::utils::validators::{get_epoch_all_validators, EpochBoundaryHooks};
 use itertools::Itertools;
 use near_async::test_loop::data::TestLoopData;
 use near_async::time::Duration;
@@ -102,6 +102,16 @@ fn run_test_chunk_validator_kickout(accounts: Vec<AccountId>, test_case: TestCas
     let initial_validators = get_epoch_all_validators(&test_loop.data.get(&client_handle).client);
     assert_eq!(initial_validators.len(), NUM_ACCOUNTS);
     assert!(initial_validators.contains(&test_case.selected_account().to_string()));
+    let expected_kickout = account_to_kickout.cloned();
+    // No validator other than the selected one must ever leave the validator set.
+    EpochBoundaryHooks::new(client_handle.clone())
+        .on_epoch_transition(move |transition| {
+            for account_id in transition.removed_validators() {
+                assert_eq!(Some(&account_id), expected_kickout.as_ref(), "Unexpected kickout");
+            }
+            assert!(transition.added_validators().is_empty(), "No new validators are expected");
+        })
+        .install(&mut test_loop);
     let success_condition = |test_loop_data: &mut TestLoopData| -> bool {
         let client = &test_loop_data.get(&client_handle).client;
         let tip = client.chain.head().unwrap();
@@ -0,0 +1,101 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::Duration;
+use near_chain::ChainStoreAccess;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_client::Client;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::get_head_height;
+
+/// Number of blocks produced by each validator on the canonical chain above `from_height`.
+fn count_blocks_by_producer(
+    client: &Client,
+    from_height: BlockHeight,
+) -> HashMap<AccountId, usize> {
+    let mut counts = HashMap::new();
+    let mut header =
+        client.chain.get_block_header(&client.chain.head().unwrap().last_block_hash).unwrap();
+    while header.height() > from_height {
+        let producer =
+            client.epoch_manager.get_block_producer(header.epoch_id(), header.height()).unwrap();
+        *counts.entry(producer).or_default() += 1;
+        header = client.chain.get_block_header(header.prev_hash()).unwrap();
+    }
+    counts
+}
+
+/// Runs until the first node has `num_blocks` more blocks, returns the height it started from.
+fn run_blocks(env: &mut TestLoopEnv, num_blocks: BlockHeight) -> BlockHeight {
+    let start_height = get_head_height(env);
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    env.test_loop.run_until(
+        |test_loop_data: &mut TestLoopData| {
+            let client = &test_loop_data.get(&client_handle).client;
+            client.chain.head().unwrap().height >= start_height + num_blocks
+        },
+        Duration::seconds(2 * num_blocks as i64),
+    );
+    start_height
+}
+
+/// One validator's clock is 5 minutes ahead and another's is 30 seconds ahead and runs 5%
+/// faster. Checks that the blocks of the first are rejected for their timestamps in the future
+/// while the second keeps producing, until the first clock is set back.
+#[test]
+fn test_block_production_with_clock_skew() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..4).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let validators = accounts.iter().map(|a| a.as_str()).collect_vec();
+    let far_ahead = accounts[3].clone();
+    let slightly_ahead = accounts[2].clone();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::single_shard(),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts)
+        .clock_skew(far_ahead.as_str(), Duration::minutes(5), 0.0)
+        .clock_skew(slightly_ahead.as_str(), Duration::seconds(30), 0.05)
+        // The warmup expects all the blocks to be produced.
+        .skip_warmup()
+        .build();
+
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let start_height = run_blocks(&mut env, 20);
+    let counts =
+        count_blocks_by_producer(&env.test_loop.data.get(&client_handle).client, start_height);
+    assert!(!counts.contains_key(&far_ahead), "blocks from the future were accepted: {counts:?}");
+    assert!(
+        counts.contains_key(&slightly_ahead),
+        "no blocks within the skew tolerance: {counts:?}"
+    );
+
+    env.datas[3].clock_skew().unwrap().set_utc_offset(Duration::ZERO);
+    let start_height = run_blocks(&mut env, 20);
+    let counts =
+        count_blocks_by_producer(&env.test_loop.data.get(&client_handle).client, start_height);
+    assert!(counts.contains_key(&far_ahead), "no blocks after the clock was set back: {counts:?}");
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
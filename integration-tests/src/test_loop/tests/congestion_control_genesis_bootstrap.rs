@@ -11,6 +11,7 @@ use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
 
 use crate::test_loop::builder::TestLoopBuilder;
 use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::TGAS;
 
 const NUM_SHARDS: usize = 4;
 
@@ -64,6 +65,78 @@ fn test_congestion_control_genesis_bootstrap() {
         .shutdown_and_drain_remaining_events(Duration::seconds(20));
 }
 
+/// Checks that delayed receipts in the genesis records are accounted for in the
+/// genesis congestion info, so that tests can start from a congested shard.
+#[test]
+fn test_congestion_control_genesis_delayed_receipts() {
+    if !ProtocolFeature::CongestionControl.enabled(PROTOCOL_VERSION) {
+        return;
+    }
+
+    init_test_logger();
+
+    let builder = TestLoopBuilder::new();
+
+    let accounts = ["test0", "test1"];
+    let clients: Vec<AccountId> = accounts.iter().map(|account| account.parse().unwrap()).collect();
+    let receiver: AccountId = "account6".parse().unwrap();
+    let mut genesis_accounts = clients.clone();
+    genesis_accounts.push(receiver.clone());
+
+    let epoch_length = 100;
+    let shard_layout = ShardLayout::simple_v1(&["account3", "account5", "account7"]);
+    let receiver_shard_index =
+        shard_layout.get_shard_index(shard_layout.account_id_to_shard_id(&receiver)).unwrap();
+    let validators_spec = ValidatorsSpec::desired_roles(&accounts[0..1], &accounts[1..2]);
+
+    let num_receipts = 100;
+    let gas_per_receipt = 300 * TGAS;
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec,
+            accounts: &genesis_accounts,
+        },
+        |genesis_builder| {
+            genesis_builder.add_congesting_delayed_receipts(
+                clients[0].clone(),
+                receiver.clone(),
+                num_receipts,
+                gas_per_receipt,
+            )
+        },
+        |epoch_config_builder| epoch_config_builder.minimum_validators_per_shard(1),
+    );
+
+    let TestLoopEnv { mut test_loop, datas: node_datas, tempdir } =
+        builder.genesis(genesis).epoch_config_store(epoch_config_store).clients(clients).build();
+
+    test_loop.run_for(Duration::seconds(5));
+
+    let client = &test_loop.data.get(&node_datas[0].client_sender.actor_handle()).client;
+    let infos = near_store::get_genesis_congestion_infos(client.chain.chain_store().store())
+        .unwrap()
+        .unwrap();
+    assert_eq!(infos.len(), NUM_SHARDS);
+    for (shard_index, info) in infos.iter().enumerate() {
+        if shard_index == receiver_shard_index {
+            assert!(
+                info.delayed_receipts_gas() >= (num_receipts as u128) * (gas_per_receipt as u128)
+            );
+            assert!(info.receipt_bytes() > 0);
+        } else {
+            assert_eq!(info.delayed_receipts_gas(), 0);
+            assert_eq!(info.receipt_bytes(), 0);
+        }
+        assert_eq!(info.buffered_receipts_gas(), 0);
+    }
+
+    TestLoopEnv { test_loop, datas: node_datas, tempdir }
+        .shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
 fn check_genesis_congestion_info_in_store(client: &mut Client) {
     let gc_config = client.config.gc.clone();
     client.chain.clear_data(&gc_config).unwrap();
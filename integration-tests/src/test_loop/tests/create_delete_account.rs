@@ -1,3 +1,4 @@
+use assert_matches::assert_matches;
 use itertools::Itertools;
 use near_async::futures::{DelayedActionRunner, DelayedActionRunnerExt};
 use near_async::time::Duration;
@@ -5,16 +6,19 @@ use near_chain_configs::test_genesis::{
     build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
 };
 use near_client::client_actor::ClientActorInner;
+use near_client::QueryError;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::shard_layout::ShardLayout;
+use near_primitives::test_utils::create_user_test_signer;
 use near_primitives::types::AccountId;
 use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::views::AccessKeyPermissionView;
 
 use crate::test_loop::builder::TestLoopBuilder;
 use crate::test_loop::env::TestLoopEnv;
 use crate::test_loop::utils::transactions::{
     call_contract, check_txs, do_create_account, do_delete_account, do_deploy_contract,
-    get_next_nonce,
+    get_next_nonce, view_access_key, view_account, view_call,
 };
 use crate::test_loop::utils::ONE_NEAR;
 
@@ -108,13 +112,32 @@ fn test_create_delete_account() {
 
     // Create account.
     do_create_account(&mut env, &rpc_id, &accounts[0], &new_account, 100 * ONE_NEAR);
+    let account = view_account(&mut env.test_loop.data, &env.datas, &rpc_id, &new_account).unwrap();
+    assert_eq!(account.amount, 100 * ONE_NEAR);
+    let public_key = create_user_test_signer(&new_account).public_key();
+    let access_key =
+        view_access_key(&mut env.test_loop.data, &env.datas, &rpc_id, &new_account, &public_key)
+            .unwrap();
+    assert_eq!(access_key.permission, AccessKeyPermissionView::FullAccess);
     // Deploy contract.
     do_deploy_contract(&mut env, &rpc_id, &new_account, contract_code);
+    let call_result = view_call(
+        &mut env.test_loop.data,
+        &env.datas,
+        &rpc_id,
+        &new_account,
+        "log_something",
+        vec![],
+    )
+    .unwrap();
+    assert_eq!(call_result.logs, ["hello"]);
     // Write a key-value pair to the contract storage.
     do_call_contract(&mut env, &rpc_id, &new_account);
     // Delete account. Should remove everything - account, contract code and
     // storage.
     do_delete_account(&mut env, &rpc_id, &new_account, &accounts[1]);
+    let result = view_account(&mut env.test_loop.data, &env.datas, &rpc_id, &new_account);
+    assert_matches!(result, Err(QueryError::UnknownAccount { .. }));
 
     env.shutdown_and_drain_remaining_events(Duration::seconds(20));
 }
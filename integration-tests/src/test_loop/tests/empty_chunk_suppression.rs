@@ -0,0 +1,106 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_client::Client;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::chunk_suppression::MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::version::{ProtocolFeature, PROTOCOL_VERSION};
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::execute_money_transfers;
+use crate::test_loop::utils::validators::get_epoch_all_validators;
+
+const NUM_VALIDATORS: usize = 4;
+const EPOCH_LENGTH: u64 = 10;
+
+/// Checks the chunks of the blocks above `from_height` up to the head and returns the number of
+/// suppressed chunks. No chunk is missed, and every shard gets a new chunk at least every
+/// `MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA` heights.
+fn check_suppressed_chunks(client: &Client, from_height: BlockHeight) -> usize {
+    let mut num_suppressed_chunks = 0;
+    let mut block_hash = client.chain.head().unwrap().last_block_hash;
+    loop {
+        let block = client.chain.get_block(&block_hash).unwrap();
+        let height = block.header().height();
+        if height <= from_height {
+            break;
+        }
+        let chunk_endorsements = block.header().chunk_endorsements().unwrap();
+        for (shard_index, chunk) in block.chunks().iter_deprecated().enumerate() {
+            assert!(
+                height - chunk.height_included() <= MAX_SUPPRESSED_CHUNKS_HEIGHT_DELTA,
+                "no new chunk for shard index {shard_index} since height {}",
+                chunk.height_included()
+            );
+            if !chunk.is_new_chunk(height) {
+                assert!(
+                    chunk_endorsements.is_chunk_suppressed(shard_index),
+                    "chunk missed at height {height} for shard index {shard_index}"
+                );
+                num_suppressed_chunks += 1;
+            }
+        }
+        block_hash = *block.header().prev_hash();
+    }
+    num_suppressed_chunks
+}
+
+/// The chunk producers of an idle network skip most of the chunks, which the blocks mark as
+/// suppressed, while the shards still get new chunks regularly, the transactions are processed
+/// and no validator is kicked out.
+#[test]
+fn slow_test_empty_chunk_suppression() {
+    if !ProtocolFeature::EmptyChunkSuppression.enabled(PROTOCOL_VERSION) {
+        return;
+    }
+    init_test_logger();
+    let builder = TestLoopBuilder::new();
+
+    let accounts =
+        (0..20).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let clients = accounts.iter().take(NUM_VALIDATORS).cloned().collect_vec();
+    let validators = clients.iter().map(|account| account.as_str()).collect_vec();
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::simple_v1(&["account5", "account10", "account15"]),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder.genesis_height(10000).transaction_validity_period(1000),
+        |epoch_config_builder| epoch_config_builder.kickouts_standard_80_percent(),
+    );
+    let mut env = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .config_modifier(|config, _| config.suppress_empty_chunks = true)
+        .build();
+
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let genesis_height = env.test_loop.data.get(&client_handle).client.chain.genesis().height();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let client = &test_loop_data.get(&client_handle).client;
+            client.chain.head().unwrap().height > genesis_height + 3 * EPOCH_LENGTH
+        },
+        Duration::seconds(4 * EPOCH_LENGTH as i64),
+    );
+    let client = &env.test_loop.data.get(&client_handle).client;
+    assert!(check_suppressed_chunks(client, genesis_height + EPOCH_LENGTH) > 0);
+    assert_eq!(get_epoch_all_validators(client).len(), NUM_VALIDATORS);
+
+    // The shards produce chunks for the transactions and their receipts.
+    let from_height = client.chain.head().unwrap().height;
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+    let client = &env.test_loop.data.get(&client_handle).client;
+    check_suppressed_chunks(client, from_height);
+    assert_eq!(get_epoch_all_validators(client).len(), NUM_VALIDATORS);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -166,6 +166,7 @@ fn bootstrap_node_via_epoch_sync(setup: TestNetworkSetup, source_node: usize) ->
         &test_loop.data.get(&node_datas[source_node].client_sender.actor_handle()).client.chain;
     let peer_info = HighestHeightPeerInfo {
         archival: false,
+        earliest_block_height: None,
         genesis_id: GenesisId {
             chain_id: genesis.config.chain_id.clone(),
             hash: *source_chain.genesis().hash(),
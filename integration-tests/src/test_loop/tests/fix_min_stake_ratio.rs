@@ -83,7 +83,8 @@ fn slow_test_fix_min_stake_ratio() {
         // Disable validator rewards.
         .max_inflation_rate(Rational32::new(0, 1))
         .add_user_accounts_simple(&accounts, initial_balance)
-        .build();
+        .build()
+        .unwrap();
 
     let TestLoopEnv { mut test_loop, datas: node_datas, tempdir } =
         builder.genesis(genesis).epoch_config_store(epoch_config_store).clients(clients).build();
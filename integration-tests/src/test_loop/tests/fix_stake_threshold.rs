@@ -54,7 +54,8 @@ fn slow_test_fix_validator_stake_threshold() {
         .validators_spec(validators_spec)
         .max_inflation_rate(Rational32::new(0, 1))
         .add_user_accounts_simple(&accounts, initial_balance)
-        .build();
+        .build()
+        .unwrap();
 
     let TestLoopEnv { mut test_loop, datas: node_data, tempdir } = test_loop_builder
         .genesis(genesis)
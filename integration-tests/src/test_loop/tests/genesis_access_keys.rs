@@ -0,0 +1,113 @@
+use assert_matches::assert_matches;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, function_call_permission, GenesisAndEpochConfigParams,
+    ValidatorsSpec,
+};
+use near_crypto::{InMemorySigner, KeyType, Signer};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::errors::{InvalidAccessKeyError, InvalidTxError};
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::{
+    do_deploy_contract, execute_tx, get_shared_block_hash, run_tx,
+};
+use crate::test_loop::utils::{ONE_NEAR, TGAS};
+
+/// Function call access keys added with the test genesis helpers restrict the transactions of
+/// their account right away.
+#[test]
+fn test_function_call_access_keys_in_genesis() {
+    init_test_logger();
+
+    let accounts: Vec<AccountId> = vec!["account0".parse().unwrap()];
+    let rpc_id = accounts[0].clone();
+    let contract_id = accounts[0].clone();
+    let user_id: AccountId = "user0".parse().unwrap();
+    let contract_signer = InMemorySigner::from_seed(contract_id.clone(), KeyType::ED25519, "fc0");
+    let user_signer = InMemorySigner::from_seed(user_id.clone(), KeyType::ED25519, "fc1");
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::single_shard(),
+            validators_spec: ValidatorsSpec::desired_roles(&["account0"], &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| {
+            genesis_builder
+                .add_access_key(
+                    contract_id.clone(),
+                    contract_signer.public_key(),
+                    function_call_permission(&contract_id, Some(ONE_NEAR), &["log_something"]),
+                )
+                .add_user_account_with_access_keys(
+                    user_id.clone(),
+                    100 * ONE_NEAR,
+                    vec![(
+                        user_signer.public_key(),
+                        function_call_permission(&contract_id, None, &[]),
+                    )],
+                )
+        },
+        |epoch_config_builder| epoch_config_builder,
+    );
+
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts)
+        .build();
+    do_deploy_contract(&mut env, &rpc_id, &contract_id, near_test_contracts::rs_contract().into());
+
+    let call = |nonce, signer: &Signer, method_name: &str, block_hash| {
+        SignedTransaction::call(
+            nonce,
+            signer.get_account_id(),
+            contract_id.clone(),
+            signer,
+            0,
+            method_name.to_string(),
+            vec![],
+            100 * TGAS,
+            block_hash,
+        )
+    };
+
+    // The key of the contract account can only call the allowed method.
+    let block_hash = get_shared_block_hash(&env.datas, &env.test_loop.data);
+    let tx = call(1, &contract_signer, "log_something", block_hash);
+    run_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5));
+    let tx = call(2, &contract_signer, "write_random_value", block_hash);
+    let res = execute_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5));
+    assert_matches!(
+        res,
+        Err(InvalidTxError::InvalidAccessKeyError(
+            InvalidAccessKeyError::MethodNameMismatch { .. }
+        ))
+    );
+
+    // The user can call any method of the contract, but can't transfer.
+    let tx = call(1, &user_signer, "write_random_value", block_hash);
+    run_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5));
+    let tx = SignedTransaction::send_money(
+        2,
+        user_id,
+        contract_id.clone(),
+        &user_signer,
+        ONE_NEAR,
+        block_hash,
+    );
+    let res = execute_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5));
+    assert_matches!(
+        res,
+        Err(InvalidTxError::InvalidAccessKeyError(InvalidAccessKeyError::RequiresFullAccess))
+    );
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,80 @@
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_crypto::{InMemorySigner, KeyType, SecretKey};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+use near_primitives::utils::derive_near_implicit_account_id;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_wallet_contract::wallet_contract_magic_bytes;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::{get_shared_block_hash, run_tx, view_account};
+use crate::test_loop::utils::ONE_NEAR;
+
+/// Implicit accounts added with the test genesis helpers are usable right away:
+/// the NEAR-implicit account can sign transactions with its key and the
+/// ETH-implicit account has the wallet contract deployed.
+#[test]
+fn test_implicit_accounts_in_genesis() {
+    init_test_logger();
+
+    let accounts: Vec<AccountId> = vec!["account0".parse().unwrap()];
+    let rpc_id = accounts[0].clone();
+    let chain_id = "test".to_string();
+
+    let secret_key = SecretKey::from_seed(KeyType::ED25519, "near_implicit");
+    let public_key = secret_key.public_key();
+    let near_implicit_account_id = derive_near_implicit_account_id(public_key.unwrap_as_ed25519());
+    let near_implicit_signer =
+        InMemorySigner::from_secret_key(near_implicit_account_id.clone(), secret_key);
+
+    let eth_implicit_account_id: AccountId =
+        "0x2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a2a".parse().unwrap();
+    let magic_bytes = wallet_contract_magic_bytes(&chain_id, PROTOCOL_VERSION);
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::single_shard(),
+            validators_spec: ValidatorsSpec::desired_roles(&["account0"], &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| {
+            genesis_builder
+                .chain_id(chain_id.clone())
+                .add_implicit_account(public_key.clone(), 100 * ONE_NEAR)
+                .add_eth_implicit_account([0x2a; 20], 100 * ONE_NEAR)
+        },
+        |epoch_config_builder| epoch_config_builder,
+    );
+
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts)
+        .build();
+
+    let tx = SignedTransaction::send_money(
+        1,
+        near_implicit_account_id,
+        eth_implicit_account_id.clone(),
+        &near_implicit_signer,
+        ONE_NEAR,
+        get_shared_block_hash(&env.datas, &env.test_loop.data),
+    );
+    run_tx(&mut env.test_loop, &rpc_id, tx, &env.datas, Duration::seconds(5));
+
+    let account =
+        view_account(&mut env.test_loop.data, &env.datas, &rpc_id, &eth_implicit_account_id)
+            .unwrap();
+    assert_eq!(account.amount, 101 * ONE_NEAR);
+    assert_eq!(&account.code_hash, magic_bytes.hash());
+    assert_eq!(account.locked, 0);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -8,10 +8,12 @@ use near_o11y::testonly::init_test_logger;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::types::AccountId;
 use near_primitives::version::PROTOCOL_VERSION;
+use near_store::flat::FlatStorageStatus;
 
 use crate::test_loop::builder::TestLoopBuilder;
 use crate::test_loop::env::TestLoopEnv;
 use crate::test_loop::utils::transactions::execute_money_transfers;
+use crate::test_loop::utils::trie_sanity::{assert_state_consistency, StateViewsChecked};
 
 /// Runs chain with sequence of chunks with empty state changes, long enough to
 /// cover 5 epochs which is default GC period.
@@ -99,3 +101,70 @@ fn test_load_memtrie_after_empty_chunks() {
     TestLoopEnv { test_loop, datas: node_datas, tempdir }
         .shutdown_and_drain_remaining_events(Duration::seconds(20));
 }
+
+/// Checks that memtries, flat storage and on-disk tries of all shards hold the
+/// same state, both at the flat storage head and at the chain head, where flat
+/// storage has only deltas and is not compared.
+#[test]
+fn test_memtrie_flat_storage_and_disk_trie_consistency() {
+    init_test_logger();
+    let builder = TestLoopBuilder::new();
+
+    let epoch_length = 5;
+    let shard_layout = ShardLayout::simple_v1(&["account3", "account5"]);
+    let accounts =
+        (0..8).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let client_accounts = accounts.iter().take(2).cloned().collect_vec();
+    let validators_spec = ValidatorsSpec::desired_roles(
+        &client_accounts.iter().map(|t| t.as_str()).collect_vec(),
+        &[],
+    );
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: shard_layout.clone(),
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder.genesis_height(10000).transaction_validity_period(1000),
+        |epoch_config_builder| epoch_config_builder,
+    );
+
+    let TestLoopEnv { mut test_loop, datas: node_datas, tempdir } = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(client_accounts)
+        .track_all_shards()
+        .load_mem_tries_for_tracked_shards(true)
+        .build();
+
+    execute_money_transfers(&mut test_loop, &node_datas, &accounts).unwrap();
+
+    for data in &node_datas {
+        let client = &test_loop.data.get(&data.client_sender.actor_handle()).client;
+        let head = client.chain.head().unwrap();
+        let flat_storage_manager = client.runtime_adapter.get_flat_storage_manager();
+        for shard_uid in shard_layout.shard_uids() {
+            let FlatStorageStatus::Ready(status) =
+                flat_storage_manager.get_flat_storage_status(shard_uid)
+            else {
+                panic!("flat storage of shard {shard_uid} is not ready");
+            };
+            assert_eq!(
+                assert_state_consistency(client, &status.flat_head.hash, shard_uid),
+                StateViewsChecked { memtrie: true, flat_storage: true }
+            );
+            if status.flat_head.hash != head.last_block_hash {
+                assert_eq!(
+                    assert_state_consistency(client, &head.last_block_hash, shard_uid),
+                    StateViewsChecked { memtrie: true, flat_storage: false }
+                );
+            }
+        }
+    }
+
+    TestLoopEnv { test_loop, datas: node_datas, tempdir }
+        .shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,70 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::load_generator::{run_load, ActionMix, LoadGeneratorConfig, TxKind};
+use crate::test_loop::utils::transactions::{do_deploy_contract, make_accounts};
+
+const NUM_ACCOUNTS: usize = 20;
+const NUM_VALIDATORS: usize = 3;
+const TPS: u64 = 20;
+const DURATION_SECONDS: i64 = 10;
+
+/// Runs a mixed workload on 3 validators of 4 shards and checks that all its transactions
+/// finish, through an RPC node tracking all shards.
+#[test]
+fn slow_test_load_generator_mixed_workload() {
+    init_test_logger();
+    let accounts = make_accounts(NUM_ACCOUNTS);
+    let validators = accounts[..NUM_VALIDATORS].iter().map(|a| a.as_str()).collect_vec();
+    // The first client which isn't a validator tracks all shards.
+    let clients = accounts[..=NUM_VALIDATORS].to_vec();
+    let rpc_id = accounts[NUM_VALIDATORS].clone();
+    let contract_id = accounts[NUM_ACCOUNTS - 1].clone();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::simple_v1(&["account3", "account5", "account7"]),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build();
+
+    do_deploy_contract(&mut env, &rpc_id, &contract_id, near_test_contracts::rs_contract().into());
+
+    let mut config = LoadGeneratorConfig::new(
+        accounts[..NUM_ACCOUNTS - 1].to_vec(),
+        TPS,
+        Duration::seconds(DURATION_SECONDS),
+    );
+    config.action_mix = ActionMix { transfers: 6, function_calls: 3, deploys: 1 };
+    config.contract_id = Some(contract_id);
+    let stats = run_load(&mut env, &rpc_id, config);
+
+    assert_eq!(stats.num_submitted, (TPS * DURATION_SECONDS as u64) as usize);
+    for kind in [TxKind::Transfer, TxKind::FunctionCall, TxKind::Deploy] {
+        assert!(stats.num_submitted_by_kind.get(&kind).is_some_and(|&n| n > 0), "no {kind:?}");
+    }
+    assert_eq!(stats.num_unfinished, 0);
+    assert_eq!(stats.num_failed, 0);
+    assert_eq!(stats.num_succeeded, stats.num_submitted);
+    assert!(stats.throughput() > 0.0);
+    assert!(stats.latency_percentile(50.0) <= stats.latency_percentile(99.0));
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -1,23 +1,42 @@
+mod access_key_usage;
+mod archival_node;
 mod bandwidth_scheduler;
 mod bandwidth_scheduler_protocol_upgrade;
+mod byzantine;
+mod chunk_producer_kickout;
 mod chunk_validator_kickout;
+mod clock_skew;
 mod congestion_control;
 mod congestion_control_genesis_bootstrap;
 mod contract_distribution_cross_shard;
 mod contract_distribution_simple;
 mod create_delete_account;
+mod empty_chunk_suppression;
 mod epoch_sync;
 mod fix_chunk_producer_stake_threshold;
 mod fix_min_stake_ratio;
 mod fix_stake_threshold;
+mod genesis_access_keys;
+mod implicit_accounts;
 mod in_memory_tries;
+mod load_generator;
 mod max_receipt_size;
 mod multinode_stateless_validators;
 mod multinode_test_loop_example;
+mod network_faults;
+mod network_partition;
+mod node_join;
+mod node_restart;
+mod per_node_client_config;
 mod protocol_upgrade;
+mod protocol_upgrade_golden;
+mod receipt_tree;
 mod reject_outdated_blocks;
 mod resharding_v3;
 mod simple_test_loop_example;
+mod skip_state_transition_data;
 mod state_sync;
 mod syncing;
+mod total_supply_invariant;
+mod tracked_shards_change;
 mod view_requests_to_archival_node;
@@ -0,0 +1,78 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_o11y::testonly::init_test_logger;
+
+use crate::test_loop::utils::network_faults::{
+    NetworkFault, NetworkFaultAction, NetworkMessageKind,
+};
+use crate::test_loop::utils::setups::single_shard_validators_setup;
+use crate::test_loop::utils::{get_head_height, has_all_chunks};
+
+/// Dropping the chunk endorsements of half of the validators leaves the chunks without enough
+/// endorsements, until the fault is removed.
+#[test]
+fn test_network_fault_drop_chunk_endorsements() {
+    init_test_logger();
+    let mut env = single_shard_validators_setup().build();
+
+    let fault_start = get_head_height(&mut env);
+    let ids = ["account2", "account3"].map(|account_id| {
+        env.add_network_fault(
+            NetworkFault::new(NetworkMessageKind::ChunkEndorsement, NetworkFaultAction::Drop)
+                .from_account(account_id),
+        )
+    });
+    env.test_loop.run_for(Duration::seconds(5));
+    let fault_end = get_head_height(&mut env);
+    assert!(
+        has_all_chunks(&env, fault_start + 2..=fault_end).contains(&false),
+        "chunks were included without the dropped endorsements"
+    );
+
+    for id in ids {
+        env.remove_network_fault(id);
+    }
+    env.test_loop.run_for(Duration::seconds(5));
+    let head_height = get_head_height(&mut env);
+    assert!(has_all_chunks(&env, head_height - 3..=head_height).into_iter().all(|all| all));
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// The chain keeps up with delayed, duplicated and reordered messages.
+#[test]
+fn test_network_fault_delay_duplicate_reorder() {
+    init_test_logger();
+    let mut env = single_shard_validators_setup().build();
+
+    let faults = [
+        NetworkFault::new(
+            NetworkMessageKind::Approval,
+            NetworkFaultAction::Delay(Duration::milliseconds(200)),
+        )
+        .from_account("account3"),
+        NetworkFault::new(NetworkMessageKind::Block, NetworkFaultAction::Duplicate),
+        NetworkFault::new(
+            NetworkMessageKind::PartialEncodedStateWitness,
+            NetworkFaultAction::Reorder { batch_size: 2 },
+        )
+        .to_account("account0"),
+    ];
+    let ids = faults.into_iter().map(|fault| env.add_network_fault(fault)).collect_vec();
+    env.run_with_network_fault(
+        NetworkFault::new(NetworkMessageKind::ChunkEndorsement, NetworkFaultAction::Duplicate)
+            .from_account("account1")
+            .to_account("account2"),
+        10,
+        Duration::seconds(10),
+    );
+    for id in ids {
+        env.remove_network_fault(id);
+    }
+
+    env.test_loop.run_for(Duration::seconds(3));
+    let head_height = get_head_height(&mut env);
+    assert!(has_all_chunks(&env, head_height - 3..=head_height).into_iter().all(|all| all));
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,88 @@
+use near_async::time::Duration;
+use near_client::Client;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::types::{AccountId, BlockHeight};
+
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::get_final_height;
+use crate::test_loop::utils::network_partition::{
+    assert_final_head_convergence, assert_fork_resolution,
+};
+use crate::test_loop::utils::setups::{single_shard_validators_setup, NUM_SINGLE_SHARD_VALIDATORS};
+use crate::test_loop::utils::transactions::execute_money_transfers;
+
+/// The single shard validators setup and the accounts of the validators. The validators have
+/// the same stake, so that a group of nodes needs 3 of them to produce blocks.
+fn setup_env() -> (TestLoopEnv, Vec<AccountId>) {
+    let env = single_shard_validators_setup().build();
+    let accounts = env.datas.iter().map(|data| data.account_id.clone()).collect();
+    (env, accounts)
+}
+
+fn client(env: &TestLoopEnv, index: usize) -> &Client {
+    &env.test_loop.data.get(&env.datas[index].client_sender.actor_handle()).client
+}
+
+fn head_height(env: &TestLoopEnv, index: usize) -> BlockHeight {
+    client(env, index).chain.head().unwrap().height
+}
+
+/// The group with 3 of the 4 validators keeps producing and finalizing blocks while the
+/// network is partitioned, and the isolated validator syncs to its chain once the partition
+/// heals.
+#[test]
+fn test_network_partition_isolated_validator() {
+    init_test_logger();
+    let (mut env, accounts) = setup_env();
+    env.test_loop.run_for(Duration::seconds(2));
+
+    let start_height = head_height(&env, 0);
+    let start_final_height = get_final_height(&env, &accounts[0]);
+    let isolated = NUM_SINGLE_SHARD_VALIDATORS - 1;
+    env.partition_network(&[accounts[..isolated].to_vec(), vec![accounts[isolated].clone()]]);
+    env.test_loop.run_for(Duration::seconds(10));
+    assert!(
+        get_final_height(&env, &accounts[0]) > start_final_height + 3,
+        "the majority stopped finalizing"
+    );
+    assert!(
+        head_height(&env, isolated) <= start_height + 1,
+        "the isolated validator produced blocks alone"
+    );
+
+    let partition_final_height = get_final_height(&env, &accounts[0]);
+    env.heal_network_partition();
+    assert_fork_resolution(&mut env, start_height, Duration::seconds(20));
+    assert_final_head_convergence(&mut env, partition_final_height, Duration::seconds(10));
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// Neither half of the validators has enough stake to produce blocks while the network is
+/// split in two, so the chain stops, and it resumes with a single chain once the partition
+/// heals.
+#[test]
+fn test_network_partition_split_in_halves() {
+    init_test_logger();
+    let (mut env, accounts) = setup_env();
+    env.test_loop.run_for(Duration::seconds(2));
+
+    let start_height = head_height(&env, 0);
+    let start_final_height = get_final_height(&env, &accounts[0]);
+    let halves = [
+        accounts[..NUM_SINGLE_SHARD_VALIDATORS / 2].to_vec(),
+        accounts[NUM_SINGLE_SHARD_VALIDATORS / 2..].to_vec(),
+    ];
+    env.run_with_network_partition(&halves, Duration::seconds(5));
+    for index in 0..NUM_SINGLE_SHARD_VALIDATORS {
+        // Only the blocks produced with the approvals sent before the partition are accepted.
+        assert!(head_height(&env, index) <= start_height + 2, "a half kept producing blocks");
+    }
+
+    assert_fork_resolution(&mut env, start_height, Duration::seconds(20));
+    assert_final_head_convergence(&mut env, start_final_height + 3, Duration::seconds(10));
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,95 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::AccountId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::execute_money_transfers;
+
+const NUM_CLIENTS: usize = 4;
+
+// Test that a node joining a running network with only the genesis bootstraps itself with
+// epoch sync and state sync, then keeps up with the network.
+#[test]
+fn slow_test_node_join_with_epoch_sync_and_state_sync() {
+    init_test_logger();
+    let builder = TestLoopBuilder::new();
+
+    let accounts =
+        (0..20).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let clients = accounts.iter().take(NUM_CLIENTS).cloned().collect_vec();
+    let shard_layout = ShardLayout::simple_v1(&["account3", "account5", "account7"]);
+    let validators_spec =
+        ValidatorsSpec::desired_roles(&clients.iter().map(|t| t.as_str()).collect_vec(), &[]);
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder.genesis_height(10000).transaction_validity_period(20),
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env =
+        builder.genesis(genesis).epoch_config_store(epoch_config_store).clients(clients).build();
+
+    // Make the chain progress for several epochs, so that the new node is far behind.
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+    let node0 = env.datas[0].client_sender.actor_handle();
+    let genesis_height = env.test_loop.data.get(&node0).client.chain.genesis().height();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            test_loop_data.get(&node0).client.chain.head().unwrap().height > genesis_height + 50
+        },
+        Duration::seconds(30),
+    );
+
+    let new_node_index = env.add_node(accounts[NUM_CLIENTS].clone(), |config| {
+        // Make the horizons small enough to trigger epoch sync, header sync and block sync.
+        config.epoch_sync.epoch_sync_horizon = 30;
+        config.block_header_fetch_horizon = 8;
+        config.block_fetch_horizon = 3;
+    });
+    let new_node = env.datas[new_node_index].client_sender.actor_handle();
+    let sync_status_history = Rc::new(RefCell::new(Vec::<String>::new()));
+    {
+        let sync_status_history = sync_status_history.clone();
+        env.test_loop.set_every_event_callback(move |test_loop_data| {
+            let sync_status = test_loop_data.get(&new_node).client.sync_status.as_variant_name();
+            let mut history = sync_status_history.borrow_mut();
+            if history.last().map(|s| s as &str) != Some(sync_status) {
+                history.push(sync_status.to_string());
+            }
+        });
+    }
+
+    let new_node = env.datas[new_node_index].client_sender.actor_handle();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let new_node_height = test_loop_data.get(&new_node).client.chain.head().unwrap().height;
+            let node0_height = test_loop_data.get(&node0).client.chain.head().unwrap().height;
+            new_node_height == node0_height
+        },
+        Duration::seconds(20),
+    );
+    let history = sync_status_history.borrow().clone();
+    for sync_status in ["EpochSync", "HeaderSync", "StateSync", "BlockSync"] {
+        assert!(history.iter().any(|s| s == sync_status), "no {sync_status} in {history:?}");
+    }
+    assert_eq!(history.last().map(|s| s as &str), Some("NoSync"));
+
+    // The new node keeps up with the network, and its transactions go through.
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,111 @@
+use itertools::Itertools;
+use near_async::messaging::CanSend;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_client::SetNetworkInfo;
+use near_network::types::{HighestHeightPeerInfo, NetworkInfo, PeerInfo};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::block::GenesisId;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::AccountId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::transactions::execute_money_transfers;
+
+const NUM_CLIENTS: usize = 4;
+
+// Test that a validator which is killed mid-test can be restarted from its store and catch up
+// with the rest of the network.
+#[test]
+fn slow_test_kill_and_restart_node() {
+    init_test_logger();
+    let builder = TestLoopBuilder::new();
+
+    let accounts =
+        (0..20).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let clients = accounts.iter().take(NUM_CLIENTS).cloned().collect_vec();
+    let shard_layout = ShardLayout::simple_v1(&["account3", "account5", "account7"]);
+    let validators_spec =
+        ValidatorsSpec::desired_roles(&clients.iter().map(|t| t.as_str()).collect_vec(), &[]);
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 20,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder.genesis_height(10000).transaction_validity_period(1000),
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = builder
+        .genesis(genesis.clone())
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build();
+
+    let killed_account = accounts[NUM_CLIENTS - 1].clone();
+    let killed_handle = env.datas[NUM_CLIENTS - 1].client_sender.actor_handle();
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let head_height = |env: &TestLoopEnv, handle| {
+        env.test_loop.data.get(handle).client.chain.head().unwrap().height
+    };
+
+    // The rest of the network keeps producing blocks while the node is down.
+    env.kill_node(&killed_account);
+    let killed_height = head_height(&env, &killed_handle);
+    let target_height = head_height(&env, &client_handle) + 10;
+    env.test_loop.run_until(
+        |test_loop_data| {
+            test_loop_data.get(&client_handle).client.chain.head().unwrap().height >= target_height
+        },
+        Duration::seconds(20),
+    );
+    assert_eq!(head_height(&env, &killed_handle), killed_height);
+
+    env.restart_node(&killed_account);
+    // TestLoopEnv doesn't propagate the network info to the peers, so tell the restarted node
+    // that node 0 is available to sync from, see `slow_test_sync_from_genesis`.
+    let chain0 = &env.test_loop.data.get(&client_handle).client.chain;
+    let peer_info = HighestHeightPeerInfo {
+        archival: false,
+        earliest_block_height: None,
+        genesis_id: GenesisId { chain_id: genesis.config.chain_id, hash: *chain0.genesis().hash() },
+        highest_block_hash: chain0.head().unwrap().last_block_hash,
+        highest_block_height: chain0.head().unwrap().height,
+        tracked_shards: vec![],
+        peer_info: PeerInfo {
+            account_id: Some(accounts[0].clone()),
+            addr: None,
+            id: env.datas[0].peer_id.clone(),
+        },
+    };
+    env.datas[NUM_CLIENTS - 1].client_sender.send(SetNetworkInfo(NetworkInfo {
+        connected_peers: Vec::new(),
+        highest_height_peers: vec![peer_info], // only this field matters.
+        known_producers: vec![],
+        num_connected_peers: 0,
+        peer_max_count: 0,
+        received_bytes_per_sec: 0,
+        sent_bytes_per_sec: 0,
+        tier1_accounts_data: Vec::new(),
+        tier1_accounts_keys: Vec::new(),
+        tier1_connections: Vec::new(),
+    }));
+
+    // The restarted node catches up, and the network keeps working with it.
+    let target_height = head_height(&env, &client_handle) + 10;
+    env.test_loop.run_until(
+        |test_loop_data| {
+            test_loop_data.get(&killed_handle).client.chain.head().unwrap().height >= target_height
+        },
+        Duration::seconds(30),
+    );
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -0,0 +1,88 @@
+use itertools::Itertools;
+use near_async::messaging::Handler;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_client::GetBlock;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, BlockId, BlockReference, ShardId};
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::env::TestLoopEnv;
+
+const NUM_CLIENTS: usize = 4;
+const EPOCH_LENGTH: u64 = 10;
+const GC_NUM_EPOCHS_TO_KEEP: u64 = 3;
+
+/// One of the validators is made archival and tracks all shards through its own config, while
+/// the other validators keep the default config of the test loop.
+#[test]
+fn slow_test_archival_validator_among_validators() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..NUM_CLIENTS).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let clients = accounts.clone();
+    let validators_spec =
+        ValidatorsSpec::desired_roles(&clients.iter().map(|t| t.as_str()).collect_vec(), &[]);
+    let shard_layout = ShardLayout::simple_v1(&["account1", "account2"]);
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: shard_layout.clone(),
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let genesis_height = genesis.config.genesis_height;
+
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .gc_num_epochs_to_keep(GC_NUM_EPOCHS_TO_KEEP)
+        .client_config_modifier("account3", |config| {
+            config.archive = true;
+            config.tracked_shards = vec![ShardId::new(666)];
+        })
+        .build();
+
+    // Run the chain until the non-archival nodes garbage collect the first epoch.
+    let target_height = genesis_height + EPOCH_LENGTH * (GC_NUM_EPOCHS_TO_KEEP + 2);
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    env.test_loop.run_until(
+        |test_loop_data| {
+            test_loop_data.get(&client_handle).client.chain.head().unwrap().height >= target_height
+        },
+        Duration::seconds(target_height as i64),
+    );
+
+    let genesis_block_request = GetBlock(BlockReference::BlockId(BlockId::Height(genesis_height)));
+    for (idx, data) in env.datas.iter().enumerate() {
+        let view_client = env.test_loop.data.get_mut(&data.view_client_sender.actor_handle());
+        let is_archival = idx == NUM_CLIENTS - 1;
+        let genesis_block = view_client.handle(genesis_block_request.clone());
+        assert_eq!(genesis_block.is_ok(), is_archival, "unexpected genesis block of node {idx}");
+
+        let client = &env.test_loop.data.get(&data.client_sender.actor_handle()).client;
+        let head = client.chain.head().unwrap();
+        let num_tracked_shards = shard_layout
+            .shard_ids()
+            .filter(|&shard_id| {
+                client.shard_tracker.care_about_shard(None, &head.prev_block_hash, shard_id, false)
+            })
+            .count();
+        if is_archival {
+            assert_eq!(num_tracked_shards, shard_layout.num_shards() as usize);
+        } else {
+            assert_eq!(num_tracked_shards, 0);
+        }
+    }
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
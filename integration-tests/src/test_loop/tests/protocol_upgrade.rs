@@ -7,19 +7,23 @@ use near_chain_configs::test_genesis::{
 };
 use near_o11y::testonly::init_test_logger;
 use near_primitives::epoch_manager::{EpochConfig, EpochConfigStore};
+use near_primitives::num_rational::Rational32;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::types::{AccountId, BlockHeight, ShardId, ShardIndex};
 use near_primitives::upgrade_schedule::ProtocolUpgradeVotingSchedule;
+use near_primitives::utils::from_timestamp;
 use near_primitives::version::PROTOCOL_VERSION;
 use near_vm_runner::logic::ProtocolVersion;
 
 use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, HashMap};
-use std::ops::Deref;
 use std::sync::Arc;
 
 use crate::test_loop::builder::TestLoopBuilder;
 use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::protocol_upgrade::{
+    protocol_upgrade_epoch_config_store, run_until_protocol_version,
+};
 use crate::test_loop::utils::ONE_NEAR;
 
 /// Test upgrading the blockchain to another protocol version.
@@ -70,19 +74,14 @@ pub(crate) fn test_protocol_upgrade(
         .epoch_length(epoch_length)
         .validators_spec(validators_spec.clone())
         .add_user_accounts_simple(&accounts, initial_balance)
-        .build();
+        .build()
+        .unwrap();
     let genesis_epoch_info = TestEpochConfigBuilder::new()
         .epoch_length(epoch_length)
         .shard_layout(shard_layout.clone())
         .validators_spec(validators_spec)
         .build();
 
-    let mainnet_epoch_config_store = EpochConfigStore::for_chain_id("mainnet", None).unwrap();
-    let mut old_epoch_config: EpochConfig =
-        mainnet_epoch_config_store.get_config(old_protocol).deref().clone();
-    let mut new_epoch_config: EpochConfig =
-        mainnet_epoch_config_store.get_config(new_protocol).deref().clone();
-
     // Adjust the epoch configs for the test
     let adjust_epoch_config = |config: &mut EpochConfig| {
         config.epoch_length = epoch_length;
@@ -97,13 +96,8 @@ pub(crate) fn test_protocol_upgrade(
             config.chunk_validator_only_kickout_threshold = 0;
         }
     };
-    adjust_epoch_config(&mut old_epoch_config);
-    adjust_epoch_config(&mut new_epoch_config);
-
-    let epoch_config_store = EpochConfigStore::test(BTreeMap::from_iter(vec![
-        (old_protocol, Arc::new(old_epoch_config)),
-        (new_protocol, Arc::new(new_epoch_config)),
-    ]));
+    let epoch_config_store =
+        protocol_upgrade_epoch_config_store(old_protocol..=new_protocol, adjust_epoch_config);
 
     // Immediately start voting for the new protocol version
     let protocol_upgrade_schedule = ProtocolUpgradeVotingSchedule::new_immediate(new_protocol);
@@ -226,3 +220,178 @@ fn slow_test_protocol_upgrade_with_missing_chunks_two() {
 fn slow_test_protocol_upgrade_not_latest() {
     test_protocol_upgrade(PROTOCOL_VERSION - 2, PROTOCOL_VERSION - 1, HashMap::new());
 }
+
+/// Test that the protocol upgrade only happens once the stake voting for the new protocol
+/// version crosses the protocol upgrade stake threshold. Half of the stake votes for the new
+/// version right away, a quarter starts voting at the voting date and the remaining quarter
+/// never votes during the test.
+#[test]
+fn slow_test_protocol_upgrade_voting_window() {
+    init_test_logger();
+
+    let old_protocol = PROTOCOL_VERSION - 1;
+    let new_protocol = PROTOCOL_VERSION;
+    let epoch_length = 10;
+    let clients =
+        (0..4).map(|i| format!("validator{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let producers = clients.iter().map(|account| account.as_str()).collect_vec();
+    let validators_spec = ValidatorsSpec::desired_roles(&producers, &[]);
+    // Strictly more than half of the stake has to vote for the new version.
+    let threshold = Rational32::new(1, 2);
+
+    let builder = TestLoopBuilder::new();
+    let genesis_time = from_timestamp(builder.clock().now_utc().unix_timestamp_nanos() as u64);
+    let voting_date = genesis_time + chrono::Duration::seconds(10);
+    let genesis = TestGenesisBuilder::new()
+        .protocol_version(old_protocol)
+        .genesis_time(genesis_time)
+        .epoch_length(epoch_length)
+        .validators_spec(validators_spec.clone())
+        .protocol_upgrade_stake_threshold(threshold)
+        .add_user_accounts_simple(&clients, 10000 * ONE_NEAR)
+        .build()
+        .unwrap();
+    let epoch_config = Arc::new(
+        TestEpochConfigBuilder::new()
+            .epoch_length(epoch_length)
+            .validators_spec(validators_spec)
+            .protocol_upgrade_stake_threshold(threshold)
+            .build(),
+    );
+    let epoch_config_store = EpochConfigStore::test(BTreeMap::from_iter(vec![
+        (old_protocol, epoch_config.clone()),
+        (new_protocol, epoch_config),
+    ]));
+
+    let late_schedule = ProtocolUpgradeVotingSchedule::new_from_env_or_schedule(
+        new_protocol,
+        vec![(genesis_time + chrono::Duration::days(365), new_protocol)],
+    )
+    .unwrap();
+    let TestLoopEnv { mut test_loop, datas: node_datas, tempdir } = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .protocol_upgrade_voting_date(new_protocol, voting_date)
+        .protocol_upgrade_schedule_for_clients(
+            &clients[..2],
+            ProtocolUpgradeVotingSchedule::new_immediate(new_protocol),
+        )
+        .protocol_upgrade_schedule_for_clients(&clients[3..], late_schedule)
+        .clients(clients)
+        .build();
+
+    let client_handle = node_datas[0].client_sender.actor_handle();
+    let first_new_protocol_block_time = Cell::new(None);
+    test_loop.run_until(
+        |test_loop_data| {
+            let client = &test_loop_data.get(&client_handle).client;
+            let tip = client.chain.head().unwrap();
+            let protocol_version =
+                client.epoch_manager.get_epoch_protocol_version(&tip.epoch_id).unwrap();
+            if protocol_version == new_protocol && first_new_protocol_block_time.get().is_none() {
+                let header = client.chain.get_block_header(&tip.last_block_hash).unwrap();
+                first_new_protocol_block_time.set(Some(from_timestamp(header.raw_timestamp())));
+            }
+            first_new_protocol_block_time.get().is_some()
+        },
+        Duration::seconds(60),
+    );
+
+    let first_new_protocol_block_time = first_new_protocol_block_time.get().unwrap();
+    assert!(
+        first_new_protocol_block_time > voting_date,
+        "upgraded at {first_new_protocol_block_time} before the voting date {voting_date}"
+    );
+
+    TestLoopEnv { test_loop, datas: node_datas, tempdir }
+        .shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// Builds a network with a validator voting for each of `protocol_version_votes` and the genesis
+/// at `genesis_protocol`, in which more than half of the stake is enough to upgrade the protocol
+/// version.
+fn setup_protocol_upgrade_votes(
+    genesis_protocol: ProtocolVersion,
+    protocol_version_votes: &[ProtocolVersion],
+) -> TestLoopEnv {
+    let epoch_length = 10;
+    let clients = (0..protocol_version_votes.len())
+        .map(|i| format!("validator{}", i).parse().unwrap())
+        .collect::<Vec<AccountId>>();
+    let producers = clients.iter().map(|account| account.as_str()).collect_vec();
+    let validators_spec = ValidatorsSpec::desired_roles(&producers, &[]);
+    let threshold = Rational32::new(1, 2);
+
+    let mut builder = TestLoopBuilder::new();
+    let genesis = TestGenesisBuilder::new()
+        .protocol_version(genesis_protocol)
+        .genesis_time_from_clock(&builder.clock())
+        .epoch_length(epoch_length)
+        .validators_spec(validators_spec.clone())
+        .protocol_upgrade_stake_threshold(threshold)
+        .add_user_accounts_simple(&clients, 10000 * ONE_NEAR)
+        .build()
+        .unwrap();
+    let genesis_epoch_config = TestEpochConfigBuilder::new()
+        .epoch_length(epoch_length)
+        .validators_spec(validators_spec)
+        .build();
+    let epoch_config_store =
+        protocol_upgrade_epoch_config_store(genesis_protocol..=PROTOCOL_VERSION, |config| {
+            config.epoch_length = epoch_length;
+            config.shard_layout = genesis_epoch_config.shard_layout.clone();
+            config.num_block_producer_seats = genesis_epoch_config.num_block_producer_seats;
+            config.num_chunk_producer_seats = genesis_epoch_config.num_chunk_producer_seats;
+            config.num_chunk_validator_seats = genesis_epoch_config.num_chunk_validator_seats;
+            config.protocol_upgrade_stake_threshold = threshold;
+        });
+
+    for (client, &protocol_version) in clients.iter().zip(protocol_version_votes) {
+        builder = builder
+            .protocol_version_votes_for_clients(std::slice::from_ref(client), protocol_version);
+    }
+    builder.genesis(genesis).epoch_config_store(epoch_config_store).clients(clients).build()
+}
+
+/// The votes of the first epoch upgrade the protocol two epochs later, straight to the voted
+/// version even if it is several versions ahead.
+#[test]
+fn slow_test_protocol_upgrade_skipping_versions() {
+    init_test_logger();
+    let genesis_protocol = PROTOCOL_VERSION - 2;
+    let mut env = setup_protocol_upgrade_votes(genesis_protocol, &[PROTOCOL_VERSION; 4]);
+
+    let history = run_until_protocol_version(&mut env, PROTOCOL_VERSION, Duration::seconds(60));
+    let first_epoch_height = history.first_epoch_height();
+    assert_eq!(history.protocol_version(first_epoch_height + 1), Some(genesis_protocol));
+    history.assert_activated_at(PROTOCOL_VERSION, first_epoch_height + 2);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// Nodes running an older binary vote for an older protocol version, and the network upgrades
+/// to the version with the most stake.
+#[test]
+fn slow_test_protocol_upgrade_with_older_nodes() {
+    init_test_logger();
+    let genesis_protocol = PROTOCOL_VERSION - 2;
+    let mut env = setup_protocol_upgrade_votes(
+        genesis_protocol,
+        &[PROTOCOL_VERSION - 1, PROTOCOL_VERSION - 1, PROTOCOL_VERSION - 1, PROTOCOL_VERSION],
+    );
+
+    let history = run_until_protocol_version(&mut env, PROTOCOL_VERSION - 1, Duration::seconds(60));
+    history.assert_activated_at(PROTOCOL_VERSION - 1, history.first_epoch_height() + 2);
+
+    // The upgrade stops there, the node voting for the latest version doesn't have enough stake.
+    env.test_loop.run_for(Duration::seconds(30));
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let client = &env.test_loop.data.get(&client_handle).client;
+    let tip = client.chain.head().unwrap();
+    assert_eq!(
+        client.epoch_manager.get_epoch_protocol_version(&tip.epoch_id).unwrap(),
+        PROTOCOL_VERSION - 1
+    );
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
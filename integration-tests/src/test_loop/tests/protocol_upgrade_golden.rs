@@ -0,0 +1,278 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    TestEpochConfigBuilder, TestGenesisBuilder, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::epoch_manager::EpochConfigStore;
+use near_primitives::hash::CryptoHash;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::upgrade_schedule::ProtocolUpgradeVotingSchedule;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_vm_runner::logic::ProtocolVersion;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::env::TestLoopEnv;
+use crate::test_loop::utils::golden::{assert_golden, GoldenOutput};
+use crate::test_loop::utils::transactions::{
+    execute_tx, get_next_nonce, get_shared_block_hash, make_accounts,
+};
+use crate::test_loop::utils::{get_node_data, ONE_NEAR, TGAS};
+
+const EPOCH_LENGTH: u64 = 15;
+const GENESIS_HEIGHT: BlockHeight = 10000;
+/// The blocks up to this height are compared against the golden file. The scenario must be
+/// done by then.
+const LAST_RECORDED_HEIGHT: BlockHeight = GENESIS_HEIGHT + 6 * EPOCH_LENGTH;
+
+/// A step of the scenario: a transaction to execute, built from its nonce and block hash.
+struct Step {
+    name: &'static str,
+    signer_id: AccountId,
+    make_tx: Box<dyn Fn(u64, CryptoHash) -> SignedTransaction>,
+}
+
+impl Step {
+    fn send_money(name: &'static str, signer_id: &AccountId, receiver_id: &AccountId) -> Self {
+        let (signer_id, receiver_id) = (signer_id.clone(), receiver_id.clone());
+        let signer = create_user_test_signer(&signer_id);
+        Self {
+            name,
+            signer_id: signer_id.clone(),
+            make_tx: Box::new(move |nonce, block_hash| {
+                SignedTransaction::send_money(
+                    nonce,
+                    signer_id.clone(),
+                    receiver_id.clone(),
+                    &signer,
+                    ONE_NEAR,
+                    block_hash,
+                )
+            }),
+        }
+    }
+
+    fn call(
+        name: &'static str,
+        signer_id: &AccountId,
+        contract_id: &AccountId,
+        method_name: &'static str,
+        args: Vec<u8>,
+    ) -> Self {
+        let (signer_id, contract_id) = (signer_id.clone(), contract_id.clone());
+        let signer = create_user_test_signer(&signer_id);
+        Self {
+            name,
+            signer_id: signer_id.clone(),
+            make_tx: Box::new(move |nonce, block_hash| {
+                SignedTransaction::call(
+                    nonce,
+                    signer_id.clone(),
+                    contract_id.clone(),
+                    &signer,
+                    0,
+                    method_name.to_string(),
+                    args.clone(),
+                    100 * TGAS,
+                    block_hash,
+                )
+            }),
+        }
+    }
+
+    fn run(&self, env: &mut TestLoopEnv, rpc_id: &AccountId, output: &mut GoldenOutput) {
+        tracing::info!(target: "test", name = self.name, "running scenario step");
+        let nonce = get_next_nonce(&env.test_loop.data, &env.datas, &self.signer_id);
+        let block_hash = get_shared_block_hash(&env.datas, &env.test_loop.data);
+        let tx = (self.make_tx)(nonce, block_hash);
+        let result =
+            execute_tx(&mut env.test_loop, rpc_id, tx, &env.datas, Duration::seconds(20)).unwrap();
+        output.record_transaction(self.name, &result);
+    }
+}
+
+/// The canonical scenario: transfers within and across shards, creation and deletion of an
+/// account, deployment of a contract and calls to it, both before and after the upgrade.
+fn scenario_steps(accounts: &[AccountId]) -> (Vec<Step>, Vec<Step>) {
+    let (sender, receiver) = (&accounts[3], &accounts[7]);
+    let contract_id: AccountId = format!("contract.{}", sender).parse().unwrap();
+    let contract_signer = create_user_test_signer(&contract_id);
+
+    let create_contract_account = {
+        let (sender, contract_id) = (sender.clone(), contract_id.clone());
+        let sender_signer = create_user_test_signer(&sender);
+        let public_key = contract_signer.public_key();
+        Step {
+            name: "create_account",
+            signer_id: sender.clone(),
+            make_tx: Box::new(move |nonce, block_hash| {
+                SignedTransaction::create_account(
+                    nonce,
+                    sender.clone(),
+                    contract_id.clone(),
+                    10 * ONE_NEAR,
+                    public_key.clone(),
+                    &sender_signer,
+                    block_hash,
+                )
+            }),
+        }
+    };
+    let deploy_contract = {
+        let contract_id = contract_id.clone();
+        let contract_signer = contract_signer.clone();
+        Step {
+            name: "deploy_contract",
+            signer_id: contract_id.clone(),
+            make_tx: Box::new(move |nonce, block_hash| {
+                SignedTransaction::deploy_contract(
+                    nonce,
+                    &contract_id,
+                    near_test_contracts::rs_contract().to_vec(),
+                    &contract_signer,
+                    block_hash,
+                )
+            }),
+        }
+    };
+    let delete_contract_account = {
+        let (sender, contract_id) = (sender.clone(), contract_id.clone());
+        Step {
+            name: "delete_account",
+            signer_id: contract_id.clone(),
+            make_tx: Box::new(move |nonce, block_hash| {
+                SignedTransaction::delete_account(
+                    nonce,
+                    contract_id.clone(),
+                    contract_id.clone(),
+                    sender.clone(),
+                    &contract_signer,
+                    block_hash,
+                )
+            }),
+        }
+    };
+    let write_key_value = |name, key: &[u8], value: u64| {
+        let args = [key, &value.to_le_bytes()].concat();
+        Step::call(name, receiver, &contract_id, "write_key_value", args)
+    };
+
+    let before_upgrade = vec![
+        Step::send_money("transfer_same_shard", sender, &accounts[4]),
+        Step::send_money("transfer_cross_shard", sender, receiver),
+        create_contract_account,
+        deploy_contract,
+        write_key_value("write_key_value_before_upgrade", b"key", 1),
+    ];
+    let after_upgrade = vec![
+        Step::send_money("transfer_cross_shard_after_upgrade", receiver, sender),
+        write_key_value("overwrite_key_value_after_upgrade", b"key", 2),
+        Step::call("log_after_upgrade", receiver, &contract_id, "log_something", vec![]),
+        delete_contract_account,
+    ];
+    (before_upgrade, after_upgrade)
+}
+
+/// Runs the canonical scenario across the upgrade from `old_protocol` to `new_protocol` and
+/// compares the outcomes of its transactions and the state roots of the chain against the
+/// golden file `name`, see `test_loop::utils::golden`.
+fn test_protocol_upgrade_golden(
+    name: &str,
+    old_protocol: ProtocolVersion,
+    new_protocol: ProtocolVersion,
+) {
+    init_test_logger();
+
+    // 2 producers and 1 rpc node tracking all shards, 2 shards, 10 accounts with 10k NEAR each.
+    let accounts = make_accounts(10);
+    let clients = accounts[..3].to_vec();
+    let rpc_id = accounts[2].clone();
+    let validators_spec = ValidatorsSpec::desired_roles(&["account0", "account1"], &[]);
+    let shard_layout = ShardLayout::multi_shard_custom(vec!["account5".parse().unwrap()], 1);
+
+    let builder = TestLoopBuilder::new();
+    let genesis = TestGenesisBuilder::new()
+        .protocol_version(old_protocol)
+        .genesis_time_from_clock(&builder.clock())
+        .genesis_height(GENESIS_HEIGHT)
+        .shard_layout(shard_layout.clone())
+        .epoch_length(EPOCH_LENGTH)
+        .validators_spec(validators_spec.clone())
+        .add_user_accounts_simple(&accounts, 10000 * ONE_NEAR)
+        .build()
+        .unwrap();
+    let epoch_config = Arc::new(
+        TestEpochConfigBuilder::new()
+            .epoch_length(EPOCH_LENGTH)
+            .shard_layout(shard_layout)
+            .validators_spec(validators_spec)
+            .build(),
+    );
+    let epoch_config_store = EpochConfigStore::test(BTreeMap::from_iter(vec![
+        (old_protocol, epoch_config.clone()),
+        (new_protocol, epoch_config),
+    ]));
+    let mut env = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .protocol_upgrade_schedule(ProtocolUpgradeVotingSchedule::new_immediate(new_protocol))
+        .clients(clients)
+        .track_all_shards()
+        .build();
+
+    let rpc_handle = get_node_data(&env.datas, &rpc_id).client_sender.actor_handle();
+    let head_protocol_version = |env: &TestLoopEnv| {
+        let client = &env.test_loop.data.get(&rpc_handle).client;
+        let tip = client.chain.head().unwrap();
+        client.epoch_manager.get_epoch_protocol_version(&tip.epoch_id).unwrap()
+    };
+
+    let (before_upgrade, after_upgrade) = scenario_steps(&accounts);
+    let mut output = GoldenOutput::default();
+    for step in &before_upgrade {
+        assert_eq!(head_protocol_version(&env), old_protocol, "upgraded before {}", step.name);
+        step.run(&mut env, &rpc_id, &mut output);
+    }
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let client = &test_loop_data.get(&rpc_handle).client;
+            let tip = client.chain.head().unwrap();
+            client.epoch_manager.get_epoch_protocol_version(&tip.epoch_id).unwrap() == new_protocol
+        },
+        Duration::seconds((5 * EPOCH_LENGTH) as i64),
+    );
+    for step in &after_upgrade {
+        step.run(&mut env, &rpc_id, &mut output);
+    }
+
+    let head_height = env.test_loop.data.get(&rpc_handle).client.chain.head().unwrap().height;
+    assert!(
+        head_height <= LAST_RECORDED_HEIGHT,
+        "the scenario ended at height {head_height}, after the last recorded height"
+    );
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let client = &test_loop_data.get(&rpc_handle).client;
+            client.chain.head().unwrap().height >= LAST_RECORDED_HEIGHT
+        },
+        Duration::seconds((2 * EPOCH_LENGTH) as i64),
+    );
+    let client = &env.test_loop.data.get(&rpc_handle).client;
+    output.record_blocks(client, GENESIS_HEIGHT + 1..=LAST_RECORDED_HEIGHT);
+    assert_golden(name, &output);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
+
+/// Fails when a change alters the execution results or the state of the chain across the
+/// upgrade to the latest protocol version. If the change is intended, e.g. a new protocol
+/// feature, review and accept the new golden file with `cargo insta review`.
+#[test]
+fn slow_test_protocol_upgrade_golden_latest() {
+    test_protocol_upgrade_golden("protocol_upgrade_latest", PROTOCOL_VERSION - 1, PROTOCOL_VERSION);
+}
@@ -0,0 +1,96 @@
+use std::cell::RefCell;
+
+use assert_matches::assert_matches;
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::{ExecutionStatus, SignedTransaction};
+use near_primitives::types::AccountId;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::receipt_tree::{trace_receipt_tree, ReceiptTree};
+use crate::test_loop::utils::transactions::{get_next_nonce, get_shared_block_hash, submit_tx};
+use crate::test_loop::utils::ONE_NEAR;
+
+/// Traces a transfer between two shards on validators tracking only their own shards, and
+/// checks where the transaction and its receipts were executed.
+#[test]
+fn test_trace_cross_shard_receipt_tree() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..6).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let clients = accounts[..4].to_vec();
+    let validators = clients.iter().map(|a| a.as_str()).collect_vec();
+    let shard_layout = ShardLayout::simple_v1(&["account3"]);
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 10,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: shard_layout.clone(),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build();
+
+    let sender = accounts[0].clone();
+    let receiver = accounts[5].clone();
+    let tx = SignedTransaction::send_money(
+        get_next_nonce(&env.test_loop.data, &env.datas, &sender),
+        sender.clone(),
+        receiver.clone(),
+        &create_user_test_signer(&sender),
+        ONE_NEAR,
+        get_shared_block_hash(&env.datas, &env.test_loop.data),
+    );
+    let tx_hash = tx.get_hash();
+    submit_tx(&env.datas, &sender, tx);
+
+    let tree = RefCell::new(None::<ReceiptTree>);
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let receipt_tree = trace_receipt_tree(test_loop_data, &env.datas, &tx_hash);
+            let is_complete = receipt_tree.is_complete();
+            *tree.borrow_mut() = Some(receipt_tree);
+            is_complete
+        },
+        Duration::seconds(10),
+    );
+    let tree = tree.into_inner().unwrap();
+    tracing::info!(target: "test", "receipt tree:\n{tree}");
+
+    // The transaction is converted to a receipt on the shard of the sender, which executes the
+    // transfer on the shard of the receiver.
+    let tx_execution = tree.execution.as_ref().unwrap();
+    assert_eq!(tx_execution.outcome.executor_id, sender);
+    assert_matches!(tx_execution.outcome.status, ExecutionStatus::SuccessReceiptId(_));
+    assert_eq!(tree.children.len(), 1);
+    let transfer_execution = tree.children[0].execution.as_ref().unwrap();
+    assert_eq!(transfer_execution.outcome.executor_id, receiver);
+    assert_matches!(transfer_execution.outcome.status, ExecutionStatus::SuccessValue(_));
+    assert_ne!(tx_execution.shard_id, transfer_execution.shard_id);
+    assert!(tx_execution.block_height < transfer_execution.block_height);
+
+    for node in tree.flatten() {
+        let execution = node.execution.as_ref().unwrap();
+        assert_eq!(
+            execution.shard_id,
+            shard_layout.account_id_to_shard_id(&execution.outcome.executor_id)
+        );
+    }
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
@@ -68,7 +68,8 @@ fn slow_test_reject_blocks_with_outdated_protocol_version() {
         .validators_spec(ValidatorsSpec::raw(validators, 3, 3, 3))
         .max_inflation_rate(Rational32::new(0, 1))
         .add_user_accounts_simple(&accounts, initial_balance)
-        .build();
+        .build()
+        .unwrap();
 
     let TestLoopEnv { mut test_loop, datas: node_data, tempdir } = test_loop_builder
         .genesis(genesis)
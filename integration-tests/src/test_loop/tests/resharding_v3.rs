@@ -21,16 +21,17 @@ use crate::test_loop::utils::receipts::{
 #[cfg(feature = "test_features")]
 use crate::test_loop::utils::resharding::fork_before_resharding_block;
 use crate::test_loop::utils::resharding::{
-    call_burn_gas_contract, call_promise_yield, check_state_cleanup_after_resharding,
-    execute_money_transfers, execute_storage_operations, temporary_account_during_resharding,
-    TrackedShardSchedule,
+    call_burn_gas_contract, call_promise_yield, check_resharding_after_catchup,
+    check_state_cleanup_after_resharding, execute_money_transfers, execute_storage_operations,
+    temporary_account_during_resharding, TrackedShardSchedule,
 };
 use crate::test_loop::utils::sharding::print_and_assert_shard_accounts;
 use crate::test_loop::utils::transactions::{
     check_txs, create_account, deploy_contract, get_smallest_height_head,
 };
 use crate::test_loop::utils::trie_sanity::{
-    check_state_shard_uid_mapping_after_resharding, TrieSanityCheck,
+    check_children_state_on_disk_after_resharding, check_state_shard_uid_mapping_after_resharding,
+    TrieSanityCheck,
 };
 use crate::test_loop::utils::{ONE_NEAR, TGAS};
 use near_parameters::{vm, RuntimeConfig, RuntimeConfigStore};
@@ -369,7 +370,8 @@ fn test_resharding_v3_base(params: TestReshardingParameters) {
             &params.validators.iter().map(|account_id| account_id.as_str()).collect_vec(),
         ))
         .add_user_accounts_simple(&params.accounts, params.initial_balance)
-        .build();
+        .build()
+        .unwrap();
 
     if params.track_all_shards {
         builder = builder.track_all_shards();
@@ -517,6 +519,15 @@ fn test_resharding_v3_base(params: TestReshardingParameters) {
             assert!(epoch_height + GC_NUM_EPOCHS_TO_KEEP < TESTLOOP_NUM_EPOCHS_TO_WAIT);
             println!("State after resharding:");
             print_and_assert_shard_accounts(&clients, &tip);
+            // The children state roots must be readable from disk, with or without memtries.
+            let checked_clients = clients.iter().filter(|client| {
+                check_children_state_on_disk_after_resharding(
+                    client,
+                    &tip.prev_block_hash,
+                    parent_shard_uid,
+                )
+            });
+            assert!(checked_clients.count() > 0);
         }
 
         for client in clients {
@@ -710,9 +721,9 @@ fn test_resharding_v3_drop_chunks_all() {
     );
 }
 
+/// The epoch ends on two competing forks. The children memtries created on the first resharding
+/// block must be extended with the parent state of the second one, which ends up final.
 #[test]
-// TODO(resharding): fix nearcore and un-ignore this test
-#[ignore]
 #[cfg(feature = "test_features")]
 fn test_resharding_v3_resharding_block_in_fork() {
     test_resharding_v3_base(
@@ -788,6 +799,45 @@ fn test_resharding_v3_shard_shuffling_untrack_then_track() {
     test_resharding_v3_base(params);
 }
 
+/// The node tracks an unrelated shard until the resharding, and a child afterwards. It state
+/// syncs the parent during the epoch of the resharding, and the resharding waits for the catchup
+/// of the parent to apply the resharding block.
+#[test]
+fn test_resharding_v3_state_sync_then_reshard() {
+    let account_in_stable_shard: AccountId = "account0".parse().unwrap();
+    let split_boundary_account: AccountId = NEW_BOUNDARY_ACCOUNT.parse().unwrap();
+    let base_shard_layout = get_base_shard_layout(DEFAULT_SHARD_LAYOUT_VERSION);
+    let new_shard_layout =
+        ShardLayout::derive_shard_layout(&base_shard_layout, split_boundary_account.clone());
+    let parent_shard_uid = base_shard_layout.account_id_to_shard_uid(&split_boundary_account);
+    let child_shard_uid = new_shard_layout.account_id_to_shard_uid(&split_boundary_account);
+    let unrelated_shard_id = new_shard_layout.account_id_to_shard_id(&account_in_stable_shard);
+
+    let tracked_shard_sequence = vec![
+        unrelated_shard_id,
+        unrelated_shard_id,
+        child_shard_uid.shard_id(),
+        child_shard_uid.shard_id(),
+    ];
+    let num_clients = 8;
+    let client_index = (num_clients - 1) as usize;
+    let tracked_shard_schedule = TrackedShardSchedule {
+        client_index,
+        schedule: shard_sequence_to_schedule(tracked_shard_sequence),
+    };
+    test_resharding_v3_base(
+        TestReshardingParametersBuilder::default()
+            .num_clients(num_clients)
+            .tracked_shard_schedule(Some(tracked_shard_schedule))
+            .add_loop_action(check_resharding_after_catchup(
+                client_index,
+                parent_shard_uid,
+                child_shard_uid,
+            ))
+            .build(),
+    );
+}
+
 #[test]
 fn test_resharding_v3_shard_shuffling_intense() {
     let chunk_ranges_to_drop = HashMap::from([(0, -1..2), (1, -3..0), (2, -3..3), (3, 0..1)]);
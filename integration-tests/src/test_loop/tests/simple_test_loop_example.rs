@@ -14,7 +14,6 @@ use near_epoch_manager::EpochManager;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::network::PeerId;
 
-use near_primitives::shard_layout::ShardLayout;
 use near_primitives::test_utils::create_test_signer;
 use near_primitives::types::AccountId;
 use near_primitives::version::{PROTOCOL_UPGRADE_SCHEDULE, PROTOCOL_VERSION};
@@ -49,12 +48,13 @@ fn test_client_with_simple_test_loop() {
         .genesis_time_from_clock(&test_loop.clock())
         .protocol_version(PROTOCOL_VERSION)
         .genesis_height(10000)
-        .shard_layout(ShardLayout::simple_v1(&["account3", "account5", "account7"]))
+        .shard_layout_simple(&["account3", "account5", "account7"])
         .transaction_validity_period(1000)
         .epoch_length(10)
         .validators_spec(ValidatorsSpec::desired_roles(&["account0"], &[]))
         .add_user_accounts_simple(&accounts, initial_balance)
-        .build();
+        .build()
+        .unwrap();
 
     let store = create_test_store();
     initialize_genesis_state(store.clone(), &genesis, None);
@@ -93,6 +93,7 @@ fn test_client_with_simple_test_loop() {
         [0; 32],
         None,
         Arc::new(test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
+        Arc::new(test_loop.async_computation_spawner(|_| Duration::milliseconds(80))),
         noop().into_multi_sender(),
         noop().into_multi_sender(),
         Arc::new(test_loop.future_spawner()),
@@ -113,6 +114,7 @@ fn test_client_with_simple_test_loop() {
         client.chain.head().unwrap(),
         client.chain.header_head().unwrap(),
         Duration::milliseconds(100),
+        Arc::new(test_loop.async_computation_spawner(|_| Duration::ZERO)),
     );
 
     let client_actor = ClientActorInner::new(
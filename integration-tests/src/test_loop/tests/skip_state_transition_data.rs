@@ -0,0 +1,104 @@
+use std::cell::Cell;
+
+use itertools::Itertools;
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::Duration;
+use near_chain::ChainStoreAccess;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::AccountId;
+use near_primitives::utils::get_block_shard_id_rev;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_store::DBCol;
+
+use crate::test_loop::builder::TestLoopBuilder;
+
+const NUM_VALIDATORS: usize = 4;
+const EPOCH_LENGTH: u64 = 10;
+
+/// All the validators track all the shards but skip the state transition data of all of them.
+/// Checks that they keep it only for the shards they produce chunks for, which is enough for
+/// all the chunks to be produced.
+#[test]
+fn slow_test_skip_state_transition_data_for_shards() {
+    init_test_logger();
+    let accounts: Vec<AccountId> =
+        (0..NUM_VALIDATORS).map(|i| format!("account{}", i).parse().unwrap()).collect_vec();
+    let validators = accounts.iter().map(|a| a.as_str()).collect_vec();
+    let shard_layout = ShardLayout::simple_v1(&["account2"]);
+    let shard_ids = shard_layout.shard_ids().collect_vec();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let genesis_height = genesis.config.genesis_height;
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(accounts)
+        .track_all_shards()
+        .config_modifier(move |config, _| {
+            config.skip_state_transition_data_for_shards = shard_ids.clone();
+        })
+        .build();
+
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let target_height = genesis_height + 3 * EPOCH_LENGTH;
+    let last_checked_height = Cell::new(genesis_height);
+    env.test_loop.run_until(
+        |test_loop_data: &mut TestLoopData| {
+            let client = &test_loop_data.get(&client_handle).client;
+            let tip = client.chain.head().unwrap();
+            if tip.height != last_checked_height.get() {
+                last_checked_height.set(tip.height);
+                let block = client.chain.get_block(&tip.last_block_hash).unwrap();
+                assert!(
+                    block.header().chunk_mask().iter().all(|&included| included),
+                    "chunk missed at height {}",
+                    tip.height
+                );
+            }
+            tip.height >= target_height
+        },
+        Duration::seconds(target_height as i64),
+    );
+
+    for data in &env.datas {
+        let client = &env.test_loop.data.get(&data.client_sender.actor_handle()).client;
+        let epoch_manager = &client.epoch_manager;
+        let mut num_entries = 0;
+        for item in client.chain.chain_store().store().iter(DBCol::StateTransitionData) {
+            let (block_hash, shard_id) = get_block_shard_id_rev(&item.unwrap().0).unwrap();
+            let epoch_ids = [
+                epoch_manager.get_epoch_id(&block_hash).unwrap(),
+                epoch_manager.get_next_epoch_id(&block_hash).unwrap(),
+            ];
+            let produces_chunks = epoch_ids.iter().any(|epoch_id| {
+                epoch_manager
+                    .get_epoch_chunk_producers_for_shard(epoch_id, shard_id)
+                    .unwrap()
+                    .contains(&data.account_id)
+            });
+            assert!(
+                produces_chunks,
+                "{} saved the state transition data of shard {shard_id} at {block_hash}",
+                data.account_id
+            );
+            num_entries += 1;
+        }
+        assert!(num_entries > 0, "{} saved no state transition data", data.account_id);
+    }
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
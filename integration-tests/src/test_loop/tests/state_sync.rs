@@ -128,7 +128,7 @@ fn setup_initial_blockchain(
             }
         }
     }
-    let genesis = genesis_builder.build();
+    let genesis = genesis_builder.build().unwrap();
 
     let epoch_config = TestEpochConfigBuilder::new()
         .epoch_length(epoch_length)
@@ -492,13 +492,17 @@ fn spam_state_sync_header_reqs(env: &mut TestLoopEnv) {
     let view_client = env.test_loop.data.get_mut(&view_client_handle);
 
     for _ in 0..30 {
-        let res = view_client.handle(StateRequestHeader { shard_id: ShardId::new(0), sync_hash });
+        let res = view_client.handle(StateRequestHeader {
+            shard_id: ShardId::new(0),
+            sync_hash,
+            batched_proofs: false,
+        });
         assert!(res.is_some());
     }
 
     // immediately query again, should be rejected
     let shard_id = ShardId::new(0);
-    let res = view_client.handle(StateRequestHeader { shard_id, sync_hash });
+    let res = view_client.handle(StateRequestHeader { shard_id, sync_hash, batched_proofs: false });
     assert!(res.is_none());
 
     env.test_loop.run_for(Duration::seconds(40));
@@ -507,7 +511,7 @@ fn spam_state_sync_header_reqs(env: &mut TestLoopEnv) {
     let view_client_handle = env.datas[0].view_client_sender.actor_handle();
     let view_client = env.test_loop.data.get_mut(&view_client_handle);
 
-    let res = view_client.handle(StateRequestHeader { shard_id, sync_hash });
+    let res = view_client.handle(StateRequestHeader { shard_id, sync_hash, batched_proofs: false });
     assert!(res.is_some());
 }
 
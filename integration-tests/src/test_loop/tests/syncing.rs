@@ -119,6 +119,7 @@ fn slow_test_sync_from_genesis() {
     let chain0 = &test_loop.data.get(&node_datas[0].client_sender.actor_handle()).client.chain;
     let peer_info = HighestHeightPeerInfo {
         archival: false,
+        earliest_block_height: None,
         genesis_id: GenesisId { chain_id: genesis.config.chain_id, hash: *chain0.genesis().hash() },
         highest_block_hash: chain0.head().unwrap().last_block_hash,
         highest_block_height: chain0.head().unwrap().height,
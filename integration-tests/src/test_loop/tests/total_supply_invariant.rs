@@ -0,0 +1,112 @@
+use std::cell::Cell;
+
+use itertools::Itertools;
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::version::PROTOCOL_VERSION;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::balance_invariant::assert_total_supply_invariant;
+use crate::test_loop::utils::transactions::{
+    call_contract, do_deploy_contract, get_next_nonce, get_shared_block_hash, make_accounts,
+    submit_tx,
+};
+use crate::test_loop::utils::{get_head_height, ONE_NEAR};
+
+const NUM_ACCOUNTS: usize = 12;
+const NUM_VALIDATORS: usize = 3;
+const EPOCH_LENGTH: u64 = 10;
+
+/// Sends transfers and function calls between 4 shards and checks the total supply invariant
+/// at every height while their receipts are in flight, across epochs minting rewards.
+#[test]
+fn slow_test_total_supply_invariant() {
+    init_test_logger();
+    let accounts = make_accounts(NUM_ACCOUNTS);
+    let validators = accounts[..NUM_VALIDATORS].iter().map(|a| a.as_str()).collect_vec();
+    // The first client which isn't a validator tracks all shards.
+    let clients = accounts[..=NUM_VALIDATORS].to_vec();
+    let rpc_id = accounts[NUM_VALIDATORS].clone();
+    let contract_id = accounts[NUM_ACCOUNTS - 1].clone();
+
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::simple_v1(&["account3", "account5", "account7"]),
+            validators_spec: ValidatorsSpec::desired_roles(&validators, &[]),
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = TestLoopBuilder::new()
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .build();
+
+    do_deploy_contract(&mut env, &rpc_id, &contract_id, near_test_contracts::rs_contract().into());
+    let initial_supply = assert_total_supply_invariant(&env.test_loop.data, &env.datas);
+
+    let signers = &accounts[..NUM_ACCOUNTS - 1];
+    let block_hash = get_shared_block_hash(&env.datas, &env.test_loop.data);
+    for (i, sender) in signers.iter().enumerate() {
+        let receiver = &signers[(i + 5) % signers.len()];
+        let nonce = get_next_nonce(&env.test_loop.data, &env.datas, sender);
+        let tx = SignedTransaction::send_money(
+            nonce,
+            sender.clone(),
+            receiver.clone(),
+            &create_user_test_signer(sender),
+            ONE_NEAR * (i as u128 + 1),
+            block_hash,
+        );
+        submit_tx(&env.datas, &rpc_id, tx);
+        call_contract(
+            &mut env.test_loop,
+            &env.datas,
+            &rpc_id,
+            sender,
+            &contract_id,
+            "write_random_value".to_string(),
+            vec![],
+            nonce + 1,
+        );
+    }
+
+    let target_height = get_head_height(&mut env) + 2 * EPOCH_LENGTH;
+    let last_checked_height = Cell::new(0);
+    let saw_receipts_in_flight = Cell::new(false);
+    let rpc_handle = env.datas[NUM_VALIDATORS].client_sender.actor_handle();
+    env.test_loop.run_until(
+        |test_loop_data: &mut TestLoopData| {
+            let height = test_loop_data.get(&rpc_handle).client.chain.head().unwrap().height;
+            if height != last_checked_height.get() {
+                last_checked_height.set(height);
+                let breakdown = assert_total_supply_invariant(test_loop_data, &env.datas);
+                if breakdown.in_flight_receipts > 0 {
+                    saw_receipts_in_flight.set(true);
+                }
+            }
+            height >= target_height
+        },
+        Duration::seconds(3 * EPOCH_LENGTH as i64),
+    );
+    assert!(
+        saw_receipts_in_flight.get(),
+        "the invariant was never checked with receipts in flight"
+    );
+
+    let final_supply = assert_total_supply_invariant(&env.test_loop.data, &env.datas);
+    assert_ne!(final_supply.total_supply, initial_supply.total_supply);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
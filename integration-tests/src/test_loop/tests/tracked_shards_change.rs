@@ -0,0 +1,120 @@
+use itertools::Itertools;
+use near_async::time::Duration;
+use near_chain_configs::test_genesis::{
+    build_genesis_and_epoch_config_store, GenesisAndEpochConfigParams, ValidatorsSpec,
+};
+use near_chain_configs::TrackedShardsConfig;
+use near_client::Client;
+use near_o11y::testonly::init_test_logger;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{AccountId, EpochHeight};
+use near_primitives::version::PROTOCOL_VERSION;
+use near_store::ShardUId;
+
+use crate::test_loop::builder::TestLoopBuilder;
+use crate::test_loop::utils::transactions::execute_money_transfers;
+use crate::test_loop::utils::trie_sanity::assert_state_consistency;
+
+const NUM_VALIDATORS: usize = 4;
+const EPOCH_LENGTH: u64 = 10;
+
+fn head_epoch_height(client: &Client) -> EpochHeight {
+    let head = client.chain.head().unwrap();
+    client.epoch_manager.get_epoch_info(&head.epoch_id).unwrap().epoch_height()
+}
+
+/// Whether the client applied the chunk of the shard in its head block.
+fn has_state_at_head(client: &Client, shard_uid: ShardUId) -> bool {
+    let head = client.chain.head().unwrap();
+    client.chain.get_chunk_extra(&head.last_block_hash, &shard_uid).is_ok()
+}
+
+fn has_memtrie(client: &Client, shard_uid: ShardUId) -> bool {
+    client.runtime_adapter.get_tries().get_mem_tries(shard_uid).is_some()
+}
+
+// Test that a node restarted with a change of its tracked shards keeps tracking the old shards
+// until the new config applies, catches up on the State of the shards it starts tracking, then
+// stops applying the chunks of the shards it no longer tracks and unloads their memtries.
+#[test]
+fn slow_test_restart_node_with_tracked_shards_change() {
+    init_test_logger();
+    let builder = TestLoopBuilder::new();
+
+    let accounts =
+        (0..20).map(|i| format!("account{}", i).parse().unwrap()).collect::<Vec<AccountId>>();
+    let validators = accounts.iter().take(NUM_VALIDATORS).cloned().collect_vec();
+    let observer = accounts[NUM_VALIDATORS].clone();
+    let clients = accounts.iter().take(NUM_VALIDATORS + 1).cloned().collect_vec();
+    let shard_layout = ShardLayout::simple_v1(&["account3", "account5", "account7"]);
+    let shard_uids = shard_layout.shard_uids().collect_vec();
+    let (old_shard, new_shard) = (shard_uids[0], shard_uids[1]);
+    let validators_spec =
+        ValidatorsSpec::desired_roles(&validators.iter().map(|t| t.as_str()).collect_vec(), &[]);
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: EPOCH_LENGTH,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout,
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder.genesis_height(10000).transaction_validity_period(1000),
+        |epoch_config_builder| epoch_config_builder,
+    );
+    let mut env = builder
+        .genesis(genesis)
+        .epoch_config_store(epoch_config_store)
+        .clients(clients)
+        .client_config_modifier(observer.as_str(), move |config| {
+            config.tracked_shards = vec![];
+            config.tracked_shards_config =
+                Some(TrackedShardsConfig::Shards(vec![old_shard.shard_id()]));
+        })
+        .build();
+
+    let observer_handle = env.datas[NUM_VALIDATORS].client_sender.actor_handle();
+    let client = &env.test_loop.data.get(&observer_handle).client;
+    assert!(has_state_at_head(client, old_shard));
+    assert!(!has_state_at_head(client, new_shard));
+
+    env.kill_node(&observer);
+    let new_config_epoch_height = env.restart_node_with_tracked_shards(
+        &observer,
+        TrackedShardsConfig::Shards(vec![new_shard.shard_id()]),
+    );
+
+    // The old config applies until the new one does, while the node catches up on the State of
+    // the new shard.
+    env.test_loop.run_until(
+        |test_loop_data| {
+            head_epoch_height(&test_loop_data.get(&observer_handle).client)
+                == new_config_epoch_height - 1
+        },
+        Duration::seconds(EPOCH_LENGTH as i64 * 3),
+    );
+    let client = &env.test_loop.data.get(&observer_handle).client;
+    assert!(has_state_at_head(client, old_shard));
+    assert!(!has_state_at_head(client, new_shard));
+
+    env.test_loop.run_until(
+        |test_loop_data| {
+            head_epoch_height(&test_loop_data.get(&observer_handle).client)
+                == new_config_epoch_height
+        },
+        Duration::seconds(EPOCH_LENGTH as i64 * 2),
+    );
+    let client = &env.test_loop.data.get(&observer_handle).client;
+    assert!(!has_state_at_head(client, old_shard));
+    assert!(!has_memtrie(client, old_shard));
+    assert!(has_state_at_head(client, new_shard));
+    assert!(has_memtrie(client, new_shard));
+
+    // The node keeps applying the chunks of the new shard.
+    execute_money_transfers(&mut env.test_loop, &env.datas, &accounts).unwrap();
+    let client = &env.test_loop.data.get(&observer_handle).client;
+    let head = client.chain.head().unwrap();
+    assert!(assert_state_consistency(client, &head.last_block_hash, new_shard).memtrie);
+
+    env.shutdown_and_drain_remaining_events(Duration::seconds(20));
+}
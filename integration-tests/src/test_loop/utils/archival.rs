@@ -0,0 +1,118 @@
+//! Queries of the history kept by the nodes, e.g. to check that an archival node still serves
+//! the blocks, chunks and execution outcomes which the other nodes garbage collected.
+
+use std::collections::HashMap;
+
+use near_async::messaging::Handler;
+use near_client::{
+    GetBlock, GetBlockError, GetChunk, GetChunkError, GetExecutionOutcomesForBlock,
+    ViewClientActorInner,
+};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{AccountId, BlockHeight, BlockId, BlockReference, ShardId};
+use near_primitives::views::{BlockView, ChunkView, ExecutionOutcomeWithIdView};
+
+use super::get_node_data;
+use crate::test_loop::env::TestLoopEnv;
+
+/// Sends a request to the view client of the node of `account_id` and returns the response.
+pub(crate) fn view_client_request<M>(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    request: M,
+) -> M::Result
+where
+    M: actix::Message,
+    M::Result: Send,
+    ViewClientActorInner: Handler<M>,
+{
+    let handle = get_node_data(&env.datas, account_id).view_client_sender.actor_handle();
+    env.test_loop.data.get_mut(&handle).handle(request)
+}
+
+pub(crate) fn get_block_by_height(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    height: BlockHeight,
+) -> Result<BlockView, GetBlockError> {
+    view_client_request(env, account_id, GetBlock(BlockReference::BlockId(BlockId::Height(height))))
+}
+
+pub(crate) fn get_block_by_hash(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    block_hash: CryptoHash,
+) -> Result<BlockView, GetBlockError> {
+    view_client_request(
+        env,
+        account_id,
+        GetBlock(BlockReference::BlockId(BlockId::Hash(block_hash))),
+    )
+}
+
+/// Returns the chunks included for the first time in the block, i.e. not the chunks of the
+/// shards whose chunk is missing in the block.
+pub(crate) fn get_new_chunks(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    block: &BlockView,
+) -> Result<Vec<ChunkView>, GetChunkError> {
+    block
+        .chunks
+        .iter()
+        .filter(|chunk| chunk.height_included == block.header.height)
+        .map(|chunk| {
+            view_client_request(env, account_id, GetChunk::ChunkHash(ChunkHash(chunk.chunk_hash)))
+        })
+        .collect()
+}
+
+pub(crate) fn get_execution_outcomes(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    block_hash: CryptoHash,
+) -> Result<HashMap<ShardId, Vec<ExecutionOutcomeWithIdView>>, String> {
+    view_client_request(env, account_id, GetExecutionOutcomesForBlock { block_hash })
+}
+
+/// Checks that the node of `account_id` serves the blocks of its canonical chain from
+/// `to_height` down to `from_height`, with their new chunks and the execution outcomes of
+/// their transactions and receipts. Returns the number of execution outcomes, so that the
+/// caller can check that the history isn't empty.
+pub(crate) fn assert_history_available(
+    env: &mut TestLoopEnv,
+    account_id: &AccountId,
+    from_height: BlockHeight,
+    to_height: BlockHeight,
+) -> usize {
+    let mut num_outcomes = 0;
+    let mut block = get_block_by_height(env, account_id, to_height)
+        .unwrap_or_else(|err| panic!("{account_id} has no block at height {to_height}: {err}"));
+    while block.header.height >= from_height {
+        let height = block.header.height;
+        let chunks = get_new_chunks(env, account_id, &block)
+            .unwrap_or_else(|err| panic!("{account_id} has no chunk at height {height}: {err}"));
+        let num_transactions: usize = chunks.iter().map(|chunk| chunk.transactions.len()).sum();
+        let outcomes = get_execution_outcomes(env, account_id, block.header.hash)
+            .unwrap_or_else(|err| panic!("{account_id} has no outcomes at height {height}: {err}"));
+        let block_outcomes = outcomes.values().map(Vec::len).sum::<usize>();
+        assert!(
+            block_outcomes >= num_transactions,
+            "{account_id} has {block_outcomes} outcomes for {num_transactions} transactions at height {height}"
+        );
+        num_outcomes += block_outcomes;
+
+        if height == from_height {
+            break;
+        }
+        let prev_hash = block.header.prev_hash;
+        block = match get_block_by_hash(env, account_id, prev_hash) {
+            Ok(block) => block,
+            // The genesis block has no previous block.
+            Err(_) if prev_hash == CryptoHash::default() => break,
+            Err(err) => panic!("{account_id} has no block before height {height}: {err}"),
+        };
+    }
+    num_outcomes
+}
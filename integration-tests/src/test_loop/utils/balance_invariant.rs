@@ -0,0 +1,208 @@
+//! Check of the total supply invariant at any point of a test loop: the tokens held by the
+//! accounts, their stakes and the receipts in the state or in flight between the shards, plus
+//! the gas burnt by the last applied chunks, add up to the total supply in the block header.
+//! Minted rewards and burnt gas are accounted for in the total supply by the chain, so the check
+//! holds after arbitrary workloads:
+//!
+//! ```ignore
+//! run_load(&mut env, &rpc_id, config);
+//! assert_total_supply_invariant(&env.test_loop.data, &env.datas);
+//! ```
+//!
+//! It doesn't support the blocks right after a resharding, where the receipts of the parent
+//! shard are split between the children.
+
+use borsh::BorshDeserialize;
+use near_async::test_loop::data::TestLoopData;
+use near_chain::{ChainStoreAccess, ReceiptFilter};
+use near_client::Client;
+use near_parameters::{ActionCosts, RuntimeConfig};
+use near_primitives::account::Account;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::{Receipt, ReceiptEnum, ReceiptOrStateStoredReceipt};
+use near_primitives::trie_key::{col, TrieKey};
+use near_primitives::types::{Balance, BlockHeight};
+use node_runtime::config::{
+    safe_gas_to_balance, total_deposit, total_prepaid_exec_fees, total_prepaid_gas,
+    total_prepaid_send_fees,
+};
+
+use crate::test_loop::env::TestData;
+use crate::test_loop::utils::sharding::client_tracking_shard;
+
+/// Where the tokens of the total supply are at a block.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct SupplyBreakdown {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Total supply in the header of the block.
+    pub total_supply: Balance,
+    /// Liquid balances of all the accounts.
+    pub accounts_amount: Balance,
+    /// Stakes of all the accounts.
+    pub accounts_locked: Balance,
+    /// Deposits and prepaid gas of the receipts in the delayed receipts queues.
+    pub delayed_receipts: Balance,
+    /// Deposits and prepaid gas of the receipts buffered by congestion control.
+    pub buffered_receipts: Balance,
+    /// Deposits and prepaid gas of the receipts waiting for their input data, including the
+    /// yielded ones.
+    pub postponed_receipts: Balance,
+    /// Deposits and prepaid gas of the receipts sent by a shard and not yet applied by the
+    /// receiving shard.
+    pub in_flight_receipts: Balance,
+    /// Gas burnt by the last chunks applied on each shard, which is subtracted from the total
+    /// supply only once the next chunk of the shard is included.
+    pub pending_burnt: Balance,
+}
+
+impl SupplyBreakdown {
+    /// Sum of the balances, which must equal `total_supply`.
+    pub fn total(&self) -> Balance {
+        self.accounts_amount
+            + self.accounts_locked
+            + self.delayed_receipts
+            + self.buffered_receipts
+            + self.postponed_receipts
+            + self.in_flight_receipts
+            + self.pending_burnt
+    }
+}
+
+/// Deposits and prepaid gas held by a receipt, as counted by the balance checker of the runtime.
+fn receipt_cost(config: &RuntimeConfig, receipt: &Receipt) -> Balance {
+    match receipt.receipt() {
+        ReceiptEnum::Action(action_receipt) | ReceiptEnum::PromiseYield(action_receipt) => {
+            let mut cost = total_deposit(&action_receipt.actions).unwrap();
+            if !receipt.predecessor_id().is_system() {
+                let gas = config.fees.fee(ActionCosts::new_action_receipt).exec_fee()
+                    + total_prepaid_exec_fees(
+                        config,
+                        &action_receipt.actions,
+                        receipt.receiver_id(),
+                    )
+                    .unwrap()
+                    + total_prepaid_gas(&action_receipt.actions).unwrap()
+                    + total_prepaid_send_fees(config, &action_receipt.actions).unwrap();
+                cost += safe_gas_to_balance(action_receipt.gas_price, gas).unwrap();
+            }
+            cost
+        }
+        ReceiptEnum::Data(_) | ReceiptEnum::PromiseResume(_) => 0,
+    }
+}
+
+/// Computes where the tokens of the total supply are at `block_hash`, which must be on the
+/// canonical chain of `client`. The client has to track all the shards.
+pub(crate) fn get_supply_breakdown(client: &Client, block_hash: &CryptoHash) -> SupplyBreakdown {
+    let chain_store = client.chain.chain_store();
+    let epoch_manager = client.epoch_manager.as_ref();
+    let block = client.chain.get_block(block_hash).unwrap();
+    let epoch_id = block.header().epoch_id();
+    let shard_layout = epoch_manager.get_shard_layout(epoch_id).unwrap();
+    let protocol_version = epoch_manager.get_epoch_protocol_version(epoch_id).unwrap();
+    let config = client.runtime_adapter.get_runtime_config(protocol_version).unwrap();
+    let tries = client.runtime_adapter.get_tries();
+
+    let mut breakdown = SupplyBreakdown {
+        block_hash: *block_hash,
+        block_height: block.header().height(),
+        total_supply: block.header().total_supply(),
+        ..Default::default()
+    };
+    for chunk_header in block.chunks().iter_raw() {
+        let shard_id = chunk_header.shard_id();
+        let shard_uid = epoch_manager.shard_id_to_uid(shard_id, epoch_id).unwrap();
+        let chunk_extra = client.chain.get_chunk_extra(block_hash, &shard_uid).unwrap();
+        breakdown.pending_burnt += chunk_extra.balance_burnt();
+
+        let trie = tries.get_trie_for_shard(shard_uid, *chunk_extra.state_root());
+        for item in trie.lock_for_iter().iter().unwrap() {
+            let (key, value) = item.unwrap();
+            match key[0] {
+                col::ACCOUNT => {
+                    let account = Account::try_from_slice(&value).unwrap();
+                    breakdown.accounts_amount += account.amount();
+                    breakdown.accounts_locked += account.locked();
+                }
+                col::DELAYED_RECEIPT_OR_INDICES
+                    if key.len() != TrieKey::DelayedReceiptIndices.len() =>
+                {
+                    let receipt = ReceiptOrStateStoredReceipt::try_from_slice(&value).unwrap();
+                    breakdown.delayed_receipts += receipt_cost(&config, &receipt.into_receipt());
+                }
+                col::BUFFERED_RECEIPT => {
+                    let receipt = ReceiptOrStateStoredReceipt::try_from_slice(&value).unwrap();
+                    breakdown.buffered_receipts += receipt_cost(&config, &receipt.into_receipt());
+                }
+                col::POSTPONED_RECEIPT | col::PROMISE_YIELD_RECEIPT => {
+                    let receipt = Receipt::try_from_slice(&value).unwrap();
+                    breakdown.postponed_receipts += receipt_cost(&config, &receipt);
+                }
+                _ => {}
+            }
+        }
+
+        // The receipts sent to the shard since its last chunk was applied.
+        let incoming_receipts = chain_store
+            .get_incoming_receipts_for_shard(
+                epoch_manager,
+                shard_id,
+                &shard_layout,
+                *block_hash,
+                chunk_header.height_included(),
+                ReceiptFilter::TargetShard,
+            )
+            .unwrap();
+        for response in incoming_receipts {
+            for receipt_proof in response.1.iter() {
+                for receipt in &receipt_proof.0 {
+                    breakdown.in_flight_receipts += receipt_cost(&config, receipt);
+                }
+            }
+        }
+
+        // The receipts produced by the last chunk of the shard, which will be sent along with
+        // its next chunk.
+        let included_block_hash = *chain_store
+            .get_block_header_on_chain_by_height(block_hash, chunk_header.height_included())
+            .unwrap()
+            .hash();
+        let outgoing_receipts =
+            chain_store.get_outgoing_receipts(&included_block_hash, shard_id).unwrap_or_default();
+        for receipt in outgoing_receipts.iter() {
+            breakdown.in_flight_receipts += receipt_cost(&config, receipt);
+        }
+    }
+    breakdown
+}
+
+/// Asserts that the balances add up to the total supply at the head of the first node tracking
+/// all the shards.
+pub(crate) fn assert_total_supply_invariant(
+    test_loop_data: &TestLoopData,
+    node_datas: &[TestData],
+) -> SupplyBreakdown {
+    let client = node_datas
+        .iter()
+        .map(|data| &test_loop_data.get(&data.client_sender.actor_handle()).client)
+        .find(|client| {
+            let tip = client.chain.head().unwrap();
+            let shard_ids = client.epoch_manager.shard_ids(&tip.epoch_id).unwrap();
+            shard_ids
+                .iter()
+                .all(|&shard_id| client_tracking_shard(client, shard_id, &tip.prev_block_hash))
+        })
+        .expect("the total supply invariant needs a node tracking all shards");
+    let tip = client.chain.head().unwrap();
+    let breakdown = get_supply_breakdown(client, &tip.last_block_hash);
+    assert_eq!(
+        breakdown.total(),
+        breakdown.total_supply,
+        "total supply invariant violated at height {}, the balances differ by {}: {:?}",
+        breakdown.block_height,
+        breakdown.total().abs_diff(breakdown.total_supply),
+        breakdown
+    );
+    breakdown
+}
@@ -0,0 +1,109 @@
+//! Byzantine behaviors of the validators of a test loop, to exercise the paths rejecting the
+//! invalid data sent by misbehaving validators. They are set per node with
+//! `TestLoopBuilder::byzantine`, e.g. to have account3 sign two blocks at each height it
+//! produces a block at:
+//!
+//! ```ignore
+//! let env = builder.byzantine("account3", ByzantineBehavior::DoubleSignBlocks).build();
+//! ```
+
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use near_async::messaging::{CanSend, LateBoundSender};
+use near_async::test_loop::sender::TestLoopSender;
+use near_network::test_loop::TestLoopPeerManagerActor;
+use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
+use near_primitives::test_utils::create_test_signer;
+use near_primitives::types::{AccountId, BlockHeight};
+
+use super::network::chunk_endorsement_dropper;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ByzantineBehavior {
+    /// Produces chunks with a wrong gas used, which the chunk validators can't endorse.
+    /// Requires the `test_features` feature.
+    ProduceInvalidChunks,
+    /// Broadcasts a second block with a different timestamp next to each block the node
+    /// produces, both signed by the node.
+    DoubleSignBlocks,
+    /// Doesn't send the chunk endorsements of the node.
+    WithholdEndorsements,
+    /// Sends the parts of the state witnesses with garbage in place of the data, signed by the
+    /// node.
+    MalformedStateWitnesses,
+}
+
+/// Registers the handlers of the byzantine behaviors of `account_id` done over the network,
+/// i.e. all but `ProduceInvalidChunks`, which is set in the client of the node.
+pub(crate) fn register_byzantine_handlers(
+    peer_manager_actor: &mut TestLoopPeerManagerActor,
+    account_id: &AccountId,
+    network_adapter: Arc<LateBoundSender<TestLoopSender<TestLoopPeerManagerActor>>>,
+    behaviors: &[ByzantineBehavior],
+) {
+    for behavior in behaviors {
+        match behavior {
+            ByzantineBehavior::ProduceInvalidChunks => {}
+            ByzantineBehavior::DoubleSignBlocks => peer_manager_actor.register_override_handler(
+                block_double_signer(account_id.clone(), network_adapter.clone()),
+            ),
+            ByzantineBehavior::WithholdEndorsements => peer_manager_actor
+                .register_override_handler(chunk_endorsement_dropper(account_id.clone())),
+            ByzantineBehavior::MalformedStateWitnesses => peer_manager_actor
+                .register_override_handler(state_witness_corrupter(account_id.clone())),
+        }
+    }
+}
+
+/// Handler sending a second block, with the timestamp of the original one plus one
+/// nanosecond, for each block broadcast by `account_id` it produced.
+fn block_double_signer(
+    account_id: AccountId,
+    network_adapter: Arc<LateBoundSender<TestLoopSender<TestLoopPeerManagerActor>>>,
+) -> Box<dyn Fn(NetworkRequests) -> Option<NetworkRequests>> {
+    let signer = create_test_signer(account_id.as_str());
+    // Heights the second block was sent at, so that it goes through unchanged.
+    let double_signed_heights = RefCell::new(HashSet::<BlockHeight>::new());
+    Box::new(move |request| {
+        let NetworkRequests::Block { block } = &request else {
+            return Some(request);
+        };
+        let header = block.header();
+        if !header.verify_block_producer(&signer.public_key())
+            || !double_signed_heights.borrow_mut().insert(header.height())
+        {
+            return Some(request);
+        }
+        let mut second_block = block.clone();
+        let header = second_block.mut_header();
+        header.set_timestamp(header.raw_timestamp() + 1);
+        header.resign(&signer);
+        network_adapter.send(PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
+            block: second_block,
+        }));
+        Some(request)
+    })
+}
+
+/// Handler corrupting the parts of the state witnesses sent by `account_id`, including the
+/// parts it forwards.
+fn state_witness_corrupter(
+    account_id: AccountId,
+) -> Box<dyn Fn(NetworkRequests) -> Option<NetworkRequests>> {
+    let signer = create_test_signer(account_id.as_str());
+    Box::new(move |request| match request {
+        NetworkRequests::PartialEncodedStateWitness(mut parts) => {
+            for (_, part) in &mut parts {
+                part.corrupt_part(&signer);
+            }
+            Some(NetworkRequests::PartialEncodedStateWitness(parts))
+        }
+        NetworkRequests::PartialEncodedStateWitnessForward(receivers, mut part) => {
+            part.corrupt_part(&signer);
+            Some(NetworkRequests::PartialEncodedStateWitnessForward(receivers, part))
+        }
+        _ => Some(request),
+    })
+}
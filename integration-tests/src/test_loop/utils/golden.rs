@@ -0,0 +1,138 @@
+//! Golden-file checks of the consensus-affecting outputs of test loop scenarios.
+//!
+//! A scenario runs a fixed sequence of transactions, typically across a protocol upgrade, and
+//! records their outcomes and the post-state of every chunk of the canonical chain in a
+//! [GoldenOutput]. [assert_golden] compares it against the reviewed golden file in
+//! `test_loop/tests/golden/`, so that a change which unintentionally alters the execution
+//! results or the state roots fails with a readable diff of the affected transactions and
+//! heights.
+//!
+//! The golden files have to be updated when the protocol changes on purpose, e.g. when the
+//! protocol version is bumped or a protocol feature is stabilised. Run the scenario both with
+//! the default features and with the `nightly` feature, and review the diffs:
+//!
+//! ```text
+//! cargo install cargo-insta
+//! cargo insta test -p integration-tests                    -- test_loop::tests::protocol_upgrade_golden
+//! cargo insta test -p integration-tests --features nightly -- test_loop::tests::protocol_upgrade_golden
+//! cargo insta review
+//! ```
+
+use std::ops::RangeInclusive;
+
+use near_client::Client;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{AccountId, BlockHeight, Gas, ProtocolVersion, ShardId};
+use near_primitives::views::{
+    ExecutionOutcomeView, ExecutionStatusView, FinalExecutionOutcomeView, FinalExecutionStatus,
+};
+
+/// Outputs of a scenario compared against its golden file.
+#[derive(serde::Serialize, Debug, Default)]
+pub(crate) struct GoldenOutput {
+    /// Outcomes of the transactions of the scenario, in the order they were executed.
+    pub transactions: Vec<GoldenTransaction>,
+    /// Post-state of the chunks of the blocks of the canonical chain.
+    pub blocks: Vec<GoldenBlock>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub(crate) struct GoldenTransaction {
+    /// Name of the step of the scenario, to make the diffs readable.
+    pub name: String,
+    pub status: FinalExecutionStatus,
+    /// Outcome of the transaction followed by the outcomes of its receipts.
+    pub outcomes: Vec<GoldenOutcome>,
+}
+
+/// The fields of an execution outcome compared against the golden file.
+#[derive(serde::Serialize, Debug)]
+pub(crate) struct GoldenOutcome {
+    pub executor_id: AccountId,
+    pub status: ExecutionStatusView,
+    pub gas_burnt: Gas,
+    /// Serialized as a string, like in the views.
+    pub tokens_burnt: String,
+    pub logs: Vec<String>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub(crate) struct GoldenBlock {
+    pub height: BlockHeight,
+    pub protocol_version: ProtocolVersion,
+    pub chunks: Vec<GoldenChunk>,
+}
+
+#[derive(serde::Serialize, Debug)]
+pub(crate) struct GoldenChunk {
+    pub shard_id: ShardId,
+    /// Whether the chunk was produced at the height of the block, as opposed to a missing chunk
+    /// whose shard only applied the implicit state transition.
+    pub is_new_chunk: bool,
+    pub state_root: CryptoHash,
+    pub outcome_root: CryptoHash,
+    pub gas_used: Gas,
+    pub balance_burnt: String,
+}
+
+impl From<&ExecutionOutcomeView> for GoldenOutcome {
+    fn from(outcome: &ExecutionOutcomeView) -> Self {
+        Self {
+            executor_id: outcome.executor_id.clone(),
+            status: outcome.status.clone(),
+            gas_burnt: outcome.gas_burnt,
+            tokens_burnt: outcome.tokens_burnt.to_string(),
+            logs: outcome.logs.clone(),
+        }
+    }
+}
+
+impl GoldenOutput {
+    pub fn record_transaction(&mut self, name: &str, result: &FinalExecutionOutcomeView) {
+        let outcomes = std::iter::once(&result.transaction_outcome)
+            .chain(&result.receipts_outcome)
+            .map(|outcome| GoldenOutcome::from(&outcome.outcome))
+            .collect();
+        self.transactions.push(GoldenTransaction {
+            name: name.to_string(),
+            status: result.status.clone(),
+            outcomes,
+        });
+    }
+
+    /// Records the blocks of the canonical chain of `client` at the given heights. The client
+    /// must track all shards.
+    pub fn record_blocks(&mut self, client: &Client, heights: RangeInclusive<BlockHeight>) {
+        for height in heights {
+            let block = client.chain.get_block_by_height(height).unwrap();
+            let epoch_id = block.header().epoch_id();
+            let protocol_version =
+                client.epoch_manager.get_epoch_protocol_version(epoch_id).unwrap();
+            let mut chunks = vec![];
+            for chunk_header in block.chunks().iter() {
+                let shard_id = chunk_header.shard_id();
+                let shard_uid = client.epoch_manager.shard_id_to_uid(shard_id, epoch_id).unwrap();
+                let chunk_extra = client.chain.get_chunk_extra(block.hash(), &shard_uid).unwrap();
+                chunks.push(GoldenChunk {
+                    shard_id,
+                    is_new_chunk: chunk_header.is_new_chunk(height),
+                    state_root: *chunk_extra.state_root(),
+                    outcome_root: *chunk_extra.outcome_root(),
+                    gas_used: chunk_extra.gas_used(),
+                    balance_burnt: chunk_extra.balance_burnt().to_string(),
+                });
+            }
+            self.blocks.push(GoldenBlock { height, protocol_version, chunks });
+        }
+    }
+}
+
+/// Compares `output` against the golden file `name`. The golden files are kept separately for
+/// the `nightly` feature, since it changes the latest protocol version.
+pub(crate) fn assert_golden(name: &str, output: &GoldenOutput) {
+    let name = if cfg!(feature = "nightly") { format!("{name}_nightly") } else { name.to_string() };
+    let mut settings = insta::Settings::clone_current();
+    settings.set_snapshot_path("../tests/golden");
+    settings.set_prepend_module_to_snapshot(false);
+    settings.bind(|| insta::assert_yaml_snapshot!(name.as_str(), output));
+}
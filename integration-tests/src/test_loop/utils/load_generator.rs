@@ -0,0 +1,319 @@
+//! Generator of a transaction workload running inside the test loop, to benchmark changes
+//! like congestion control or the chunk capacity deterministically.
+//!
+//! The generator submits `tps` transactions per second of test loop time to an RPC node, with
+//! signers, receivers and kinds of transactions picked by a seeded RNG, then waits for their
+//! final outcomes and reports the throughput and the latencies:
+//!
+//! ```ignore
+//! let mut config = LoadGeneratorConfig::new(accounts, 50, Duration::seconds(20));
+//! config.action_mix = ActionMix { transfers: 3, function_calls: 1, deploys: 0 };
+//! config.contract_id = Some(contract_id);
+//! let stats = run_load(&mut env, &rpc_id, config);
+//! assert_eq!(stats.num_unfinished, 0);
+//! ```
+
+use std::collections::HashMap;
+
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::{Clock, Duration, Utc};
+use near_primitives::hash::CryptoHash;
+use near_primitives::test_utils::create_user_test_signer;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::{AccountId, Balance, Gas};
+use near_primitives::views::FinalExecutionStatus;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+
+use super::transactions::{get_next_nonce, get_shared_block_hash, submit_tx};
+use super::{get_node_data, ONE_NEAR, TGAS};
+use crate::test_loop::env::{TestData, TestLoopEnv};
+
+/// Amount of the transfers of the workload.
+const TRANSFER_AMOUNT: Balance = ONE_NEAR / 1000;
+
+/// Relative weights of the kinds of transactions of the workload.
+#[derive(Clone, Debug)]
+pub(crate) struct ActionMix {
+    /// Transfers between the accounts of the workload.
+    pub transfers: u32,
+    /// Calls of `LoadGeneratorConfig::method_name` on `LoadGeneratorConfig::contract_id`.
+    pub function_calls: u32,
+    /// Deployments of the trivial contract on the signer account.
+    pub deploys: u32,
+}
+
+impl Default for ActionMix {
+    fn default() -> Self {
+        Self { transfers: 1, function_calls: 0, deploys: 0 }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum TxKind {
+    Transfer,
+    FunctionCall,
+    Deploy,
+}
+
+#[derive(Clone, Debug)]
+pub(crate) struct LoadGeneratorConfig {
+    /// Signers and receivers of the transactions, with the test signer of their account id.
+    pub accounts: Vec<AccountId>,
+    /// Transactions submitted per second of test loop time.
+    pub tps: u64,
+    /// For how long the transactions are submitted.
+    pub duration: Duration,
+    /// How long to wait for the outcomes of the transactions after the last one is submitted.
+    /// The transactions without an outcome by then are reported as unfinished.
+    pub drain_timeout: Duration,
+    pub action_mix: ActionMix,
+    /// Contract receiving the function calls, which must be deployed before the run. It can't
+    /// be one of `accounts`, as their contracts are replaced by the deploys.
+    pub contract_id: Option<AccountId>,
+    pub method_name: String,
+    pub args: Vec<u8>,
+    pub function_call_gas: Gas,
+    pub seed: u64,
+}
+
+impl LoadGeneratorConfig {
+    /// Config of a workload of transfers, the other fields can be set afterwards.
+    pub fn new(accounts: Vec<AccountId>, tps: u64, duration: Duration) -> Self {
+        Self {
+            accounts,
+            tps,
+            duration,
+            drain_timeout: Duration::seconds(30),
+            action_mix: ActionMix::default(),
+            contract_id: None,
+            method_name: "log_something".to_string(),
+            args: vec![],
+            function_call_gas: 300 * TGAS,
+            seed: 0,
+        }
+    }
+}
+
+/// Throughput and latencies of a workload.
+#[derive(Debug, Default)]
+pub(crate) struct LoadStats {
+    pub num_submitted: usize,
+    pub num_submitted_by_kind: HashMap<TxKind, usize>,
+    /// Transactions whose execution succeeded.
+    pub num_succeeded: usize,
+    /// Transactions executed with a failure.
+    pub num_failed: usize,
+    /// Transactions without a final outcome at the end of the run, e.g. the ones rejected by
+    /// the RPC node or dropped from a congested shard.
+    pub num_unfinished: usize,
+    /// Time from the submission of each finished transaction to its final outcome, sorted.
+    pub latencies: Vec<Duration>,
+    /// Time from the first submission to the last outcome, or to the end of the run if some
+    /// transactions are unfinished.
+    pub elapsed: Duration,
+}
+
+impl LoadStats {
+    /// Finished transactions per second.
+    pub fn throughput(&self) -> f64 {
+        (self.num_succeeded + self.num_failed) as f64 / self.elapsed.as_seconds_f64()
+    }
+
+    /// Returns the latency below which `percentile` percent of the finished transactions are.
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let index = ((self.latencies.len() - 1) as f64 * percentile / 100.0).round() as usize;
+        Some(self.latencies[index])
+    }
+}
+
+struct LoadGenerator<'a> {
+    config: LoadGeneratorConfig,
+    node_datas: &'a [TestData],
+    rpc_id: &'a AccountId,
+    clock: Clock,
+    rng: ChaCha20Rng,
+    nonces: HashMap<AccountId, u64>,
+    start: Utc,
+    num_to_submit: u64,
+    /// Submission times of the transactions without a final outcome yet.
+    pending: HashMap<CryptoHash, Utc>,
+    /// Height of the RPC head when the pending transactions were last checked.
+    last_checked_height: u64,
+    last_outcome: Utc,
+    stats: LoadStats,
+}
+
+impl LoadGenerator<'_> {
+    /// Submits the transactions due by now and collects the outcomes of the pending ones.
+    /// Returns whether the run is over.
+    fn poll(&mut self, test_loop_data: &TestLoopData) -> bool {
+        let now = self.clock.now_utc();
+        let elapsed = now - self.start;
+        let num_due = if elapsed >= self.config.duration {
+            self.num_to_submit
+        } else {
+            (elapsed.whole_milliseconds() as u64 * self.config.tps / 1000).min(self.num_to_submit)
+        };
+        while (self.stats.num_submitted as u64) < num_due {
+            self.submit(test_loop_data, now);
+        }
+
+        let rpc_handle = get_node_data(self.node_datas, self.rpc_id).client_sender.actor_handle();
+        let rpc = &test_loop_data.get(&rpc_handle).client;
+        let head_height = rpc.chain.head().unwrap().height;
+        if head_height != self.last_checked_height {
+            self.last_checked_height = head_height;
+            let mut finished = vec![];
+            for (tx_hash, submitted) in &self.pending {
+                let Ok(outcome) = rpc.chain.get_final_transaction_result(tx_hash) else {
+                    continue;
+                };
+                match outcome.status {
+                    FinalExecutionStatus::SuccessValue(_) => self.stats.num_succeeded += 1,
+                    FinalExecutionStatus::Failure(err) => {
+                        tracing::debug!(target: "test", ?tx_hash, ?err, "transaction failed");
+                        self.stats.num_failed += 1;
+                    }
+                    FinalExecutionStatus::NotStarted | FinalExecutionStatus::Started => continue,
+                }
+                self.stats.latencies.push(now - *submitted);
+                self.last_outcome = now;
+                finished.push(*tx_hash);
+            }
+            for tx_hash in finished {
+                self.pending.remove(&tx_hash);
+            }
+        }
+
+        let all_submitted = self.stats.num_submitted as u64 == self.num_to_submit;
+        (all_submitted && self.pending.is_empty())
+            || elapsed >= self.config.duration + self.config.drain_timeout
+    }
+
+    fn submit(&mut self, test_loop_data: &TestLoopData, now: Utc) {
+        let mix = &self.config.action_mix;
+        let weight = self.rng.gen_range(0..mix.transfers + mix.function_calls + mix.deploys);
+        let kind = if weight < mix.transfers {
+            TxKind::Transfer
+        } else if weight < mix.transfers + mix.function_calls {
+            TxKind::FunctionCall
+        } else {
+            TxKind::Deploy
+        };
+        let accounts = &self.config.accounts;
+        let signer_id = accounts[self.rng.gen_range(0..accounts.len())].clone();
+        let signer = create_user_test_signer(&signer_id);
+        let nonce = self.nonces.get_mut(&signer_id).unwrap();
+        *nonce += 1;
+        let block_hash = get_shared_block_hash(self.node_datas, test_loop_data);
+
+        let tx = match kind {
+            TxKind::Transfer => {
+                let receiver_id = accounts[self.rng.gen_range(0..accounts.len())].clone();
+                SignedTransaction::send_money(
+                    *nonce,
+                    signer_id,
+                    receiver_id,
+                    &signer,
+                    TRANSFER_AMOUNT,
+                    block_hash,
+                )
+            }
+            TxKind::FunctionCall => SignedTransaction::call(
+                *nonce,
+                signer_id,
+                self.config.contract_id.clone().unwrap(),
+                &signer,
+                0,
+                self.config.method_name.clone(),
+                self.config.args.clone(),
+                self.config.function_call_gas,
+                block_hash,
+            ),
+            TxKind::Deploy => SignedTransaction::deploy_contract(
+                *nonce,
+                &signer_id,
+                near_test_contracts::trivial_contract().to_vec(),
+                &signer,
+                block_hash,
+            ),
+        };
+        self.pending.insert(tx.get_hash(), now);
+        submit_tx(self.node_datas, self.rpc_id, tx);
+        self.stats.num_submitted += 1;
+        *self.stats.num_submitted_by_kind.entry(kind).or_default() += 1;
+    }
+}
+
+/// Runs the workload described by `config`, submitting the transactions to `rpc_id`, which
+/// should track all shards to see the outcomes of all transactions. Returns once all the
+/// transactions are finished, or after `config.drain_timeout` past the submission period.
+pub(crate) fn run_load(
+    env: &mut TestLoopEnv,
+    rpc_id: &AccountId,
+    config: LoadGeneratorConfig,
+) -> LoadStats {
+    assert!(!config.accounts.is_empty(), "the workload needs accounts");
+    let mix = &config.action_mix;
+    assert!(mix.transfers + mix.function_calls + mix.deploys > 0, "empty action mix");
+    if mix.function_calls > 0 {
+        let contract_id = config.contract_id.as_ref().expect("function calls need a contract");
+        assert!(!config.accounts.contains(contract_id), "the contract can't be a signer");
+    }
+
+    let nonces = config
+        .accounts
+        .iter()
+        .map(|account_id| {
+            // `get_next_nonce` returns the next nonce, the generator increments before use.
+            let nonce = get_next_nonce(&env.test_loop.data, &env.datas, account_id) - 1;
+            (account_id.clone(), nonce)
+        })
+        .collect();
+    let clock = env.test_loop.clock();
+    let start = clock.now_utc();
+    let num_to_submit = config.tps * config.duration.whole_seconds() as u64;
+    let maximum_duration = config.duration + config.drain_timeout + Duration::seconds(1);
+    let mut generator = LoadGenerator {
+        rng: ChaCha20Rng::seed_from_u64(config.seed),
+        config,
+        node_datas: &env.datas,
+        rpc_id,
+        clock,
+        nonces,
+        start,
+        num_to_submit,
+        pending: HashMap::new(),
+        last_checked_height: 0,
+        last_outcome: start,
+        stats: LoadStats::default(),
+    };
+    env.test_loop.run_until(|test_loop_data| generator.poll(test_loop_data), maximum_duration);
+
+    let mut stats = generator.stats;
+    stats.num_unfinished = generator.pending.len();
+    stats.elapsed = if stats.num_unfinished == 0 {
+        generator.last_outcome - start
+    } else {
+        generator.clock.now_utc() - start
+    };
+    stats.latencies.sort();
+    tracing::info!(
+        target: "test",
+        num_submitted = stats.num_submitted,
+        num_succeeded = stats.num_succeeded,
+        num_failed = stats.num_failed,
+        num_unfinished = stats.num_unfinished,
+        throughput = stats.throughput(),
+        latency_p50 = ?stats.latency_percentile(50.0),
+        latency_p99 = ?stats.latency_percentile(99.0),
+        latency_max = ?stats.latencies.last(),
+        by_kind = ?stats.num_submitted_by_kind,
+        "Load generator stats"
+    );
+    stats
+}
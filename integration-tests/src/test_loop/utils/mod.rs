@@ -1,11 +1,20 @@
 use super::env::{TestData, TestLoopEnv};
 use near_async::test_loop::data::TestLoopData;
 use near_client::client_actor::ClientActorInner;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, BlockHeight};
 
+pub(crate) mod archival;
+pub(crate) mod balance_invariant;
+pub(crate) mod byzantine;
 pub(crate) mod contract_distribution;
+pub(crate) mod golden;
+pub(crate) mod load_generator;
 pub(crate) mod loop_action;
 pub(crate) mod network;
+pub(crate) mod network_faults;
+pub(crate) mod network_partition;
+pub(crate) mod protocol_upgrade;
+pub(crate) mod receipt_tree;
 pub(crate) mod receipts;
 pub(crate) mod resharding;
 pub(crate) mod setups;
@@ -24,6 +33,26 @@ pub(crate) fn get_head_height(env: &mut TestLoopEnv) -> u64 {
     client.chain.head().unwrap().height
 }
 
+/// Returns the height of the final head of the node with the given account id.
+pub(crate) fn get_final_height(env: &TestLoopEnv, account_id: &AccountId) -> BlockHeight {
+    let client_handle = get_node_data(&env.datas, account_id).client_sender.actor_handle();
+    env.test_loop.data.get(&client_handle).client.chain.final_head().unwrap().height
+}
+
+/// Returns whether the blocks of node at index 0 at the given heights have all their chunks.
+/// Skipped heights are ignored.
+pub(crate) fn has_all_chunks(
+    env: &TestLoopEnv,
+    heights: impl Iterator<Item = BlockHeight>,
+) -> Vec<bool> {
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let client = &env.test_loop.data.get(&client_handle).client;
+    heights
+        .filter_map(|height| client.chain.get_block_by_height(height).ok())
+        .map(|block| block.header().chunk_mask().iter().all(|&included| included))
+        .collect()
+}
+
 /// Returns the test data of for the node with the given account id.
 pub(crate) fn get_node_data<'a>(
     node_datas: &'a [TestData],
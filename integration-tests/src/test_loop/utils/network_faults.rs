@@ -0,0 +1,274 @@
+//! Injection of network faults between the nodes of a test loop.
+//!
+//! The faults apply to the [NetworkRequests] sent by a node to its `TestLoopPeerManagerActor`,
+//! before they are routed to their receivers. They are added and removed while the test runs,
+//! see `TestLoopEnv::add_network_fault` and `TestLoopEnv::run_with_network_fault`, e.g. to drop
+//! all chunk endorsements from account3 to account0 for 10 blocks:
+//!
+//! ```ignore
+//! let fault = NetworkFault::new(NetworkMessageKind::ChunkEndorsement, NetworkFaultAction::Drop)
+//!     .from_account("account3")
+//!     .to_account("account0");
+//! env.run_with_network_fault(fault, 10, Duration::seconds(20));
+//! ```
+
+use std::collections::{BTreeMap, HashMap};
+
+use itertools::Itertools;
+use near_async::messaging::CanSend;
+use near_async::test_loop::sender::TestLoopSender;
+use near_async::time::Duration;
+use near_network::test_loop::TestLoopPeerManagerActor;
+use near_network::types::{NetworkRequests, PeerManagerMessageRequest};
+use near_primitives::types::AccountId;
+
+/// Kinds of network messages a fault applies to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NetworkMessageKind {
+    /// Block broadcasts. A block is broadcast to all the nodes at once, so the faults on blocks
+    /// can't select the receiver.
+    Block,
+    Approval,
+    ChunkEndorsement,
+    /// Partial encoded chunks and their forwards.
+    PartialEncodedChunk,
+    /// Partial encoded state witnesses and their forwards.
+    PartialEncodedStateWitness,
+}
+
+#[derive(Clone, Debug)]
+pub enum NetworkFaultAction {
+    Drop,
+    Delay(Duration),
+    /// Holds the messages until `batch_size` of them are held, then sends them in the reverse
+    /// order. The messages still held when the fault is removed are sent then, in the reverse
+    /// order as well.
+    Reorder {
+        batch_size: usize,
+    },
+    /// Sends the messages twice.
+    Duplicate,
+}
+
+/// A fault applied to the messages of a kind between a pair of nodes.
+#[derive(Clone, Debug)]
+pub struct NetworkFault {
+    pub kind: NetworkMessageKind,
+    /// Sender of the messages, any node if `None`.
+    pub from: Option<AccountId>,
+    /// Receiver of the messages, any node if `None`.
+    pub to: Option<AccountId>,
+    pub action: NetworkFaultAction,
+}
+
+impl NetworkFault {
+    pub fn new(kind: NetworkMessageKind, action: NetworkFaultAction) -> Self {
+        Self { kind, from: None, to: None, action }
+    }
+
+    pub fn from_account(mut self, account_id: &str) -> Self {
+        self.from = Some(account_id.parse().unwrap());
+        self
+    }
+
+    pub fn to_account(mut self, account_id: &str) -> Self {
+        self.to = Some(account_id.parse().unwrap());
+        self
+    }
+}
+
+pub type NetworkFaultId = u64;
+
+struct ActiveNetworkFault {
+    fault: NetworkFault,
+    /// Messages held by a `Reorder` fault, by sender.
+    held: HashMap<AccountId, Vec<NetworkRequests>>,
+}
+
+/// Network faults injected between the nodes of a test loop, shared by all the nodes.
+#[derive(Default)]
+pub struct NetworkFaults {
+    next_id: NetworkFaultId,
+    faults: BTreeMap<NetworkFaultId, ActiveNetworkFault>,
+    /// Messages sent again by a fault, which must go through without being faulted again, with
+    /// their sender. A message is recognized by its content, so a message sent again may let
+    /// an identical message of the same sender through instead, which doesn't matter for tests.
+    released: Vec<(AccountId, NetworkRequests)>,
+    /// Senders to the peer manager actors of the nodes, to send the messages again.
+    senders: HashMap<AccountId, TestLoopSender<TestLoopPeerManagerActor>>,
+}
+
+impl NetworkFaults {
+    pub fn register_sender(
+        &mut self,
+        account_id: AccountId,
+        sender: TestLoopSender<TestLoopPeerManagerActor>,
+    ) {
+        self.senders.insert(account_id, sender);
+    }
+
+    pub fn add(&mut self, fault: NetworkFault) -> NetworkFaultId {
+        assert!(
+            fault.kind != NetworkMessageKind::Block || fault.to.is_none(),
+            "block broadcasts can't be faulted for a single receiver"
+        );
+        if let NetworkFaultAction::Reorder { batch_size } = fault.action {
+            assert!(batch_size > 1, "reordering needs batches of at least 2 messages");
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.faults.insert(id, ActiveNetworkFault { fault, held: HashMap::new() });
+        id
+    }
+
+    /// Removes the fault and sends the messages it still holds.
+    pub fn remove(&mut self, id: NetworkFaultId) {
+        let fault = self.faults.remove(&id).expect("unknown network fault");
+        for (sender_id, held) in fault.held.into_iter().sorted_by(|a, b| a.0.cmp(&b.0)) {
+            for request in held.into_iter().rev() {
+                self.send_again(&sender_id, request, Duration::ZERO);
+            }
+        }
+    }
+
+    /// Applies the faults to a request sent by `sender_id`. Returns the part of the request
+    /// to process normally, if any.
+    fn apply(
+        &mut self,
+        sender_id: &AccountId,
+        request: NetworkRequests,
+    ) -> Option<NetworkRequests> {
+        if let Some(index) = self.released.iter().position(|(released_sender_id, released)| {
+            released_sender_id == sender_id && released == &request
+        }) {
+            self.released.swap_remove(index);
+            return Some(request);
+        }
+
+        let mut request = request;
+        let ids = self.faults.keys().copied().collect_vec();
+        for id in ids {
+            let fault = &self.faults[&id].fault;
+            if fault.from.as_ref().is_some_and(|from| from != sender_id) {
+                continue;
+            }
+            let (faulted, rest) = split_request(fault.kind, fault.to.as_ref(), request);
+            if let Some(faulted) = faulted {
+                self.apply_action(id, sender_id, faulted);
+            }
+            request = rest?;
+        }
+        Some(request)
+    }
+
+    fn apply_action(
+        &mut self,
+        id: NetworkFaultId,
+        sender_id: &AccountId,
+        request: NetworkRequests,
+    ) {
+        let fault = self.faults.get_mut(&id).unwrap();
+        match fault.fault.action {
+            NetworkFaultAction::Drop => {}
+            NetworkFaultAction::Delay(delay) => self.send_again(sender_id, request, delay),
+            NetworkFaultAction::Reorder { batch_size } => {
+                let held = fault.held.entry(sender_id.clone()).or_default();
+                held.push(request);
+                if held.len() >= batch_size {
+                    let held = std::mem::take(held);
+                    for request in held.into_iter().rev() {
+                        self.send_again(sender_id, request, Duration::ZERO);
+                    }
+                }
+            }
+            NetworkFaultAction::Duplicate => {
+                self.send_again(sender_id, request.clone(), Duration::ZERO);
+                self.send_again(sender_id, request, Duration::ZERO);
+            }
+        }
+    }
+
+    fn send_again(&mut self, sender_id: &AccountId, request: NetworkRequests, delay: Duration) {
+        let sender = self.senders.get(sender_id).expect("peer manager sender not registered");
+        self.released.push((sender_id.clone(), request.clone()));
+        sender.clone().with_delay(delay).send(PeerManagerMessageRequest::NetworkRequests(request));
+    }
+}
+
+/// Splits a request into the part a fault on the messages of `kind` to `to` applies to, and
+/// the rest. Requests sent to several receivers are split by receiver.
+fn split_request(
+    kind: NetworkMessageKind,
+    to: Option<&AccountId>,
+    request: NetworkRequests,
+) -> (Option<NetworkRequests>, Option<NetworkRequests>) {
+    let is_faulted_receiver = |receiver: &AccountId| to.map_or(true, |to| to == receiver);
+    let receiver = match (kind, &request) {
+        (NetworkMessageKind::Block, NetworkRequests::Block { .. }) => None,
+        (NetworkMessageKind::Approval, NetworkRequests::Approval { approval_message }) => {
+            Some(&approval_message.target)
+        }
+        (NetworkMessageKind::ChunkEndorsement, NetworkRequests::ChunkEndorsement(target, _)) => {
+            Some(target)
+        }
+        (
+            NetworkMessageKind::PartialEncodedChunk,
+            NetworkRequests::PartialEncodedChunkMessage { account_id, .. }
+            | NetworkRequests::PartialEncodedChunkForward { account_id, .. },
+        ) => Some(account_id),
+        (
+            NetworkMessageKind::PartialEncodedStateWitness,
+            NetworkRequests::PartialEncodedStateWitness(_)
+            | NetworkRequests::PartialEncodedStateWitnessForward(..),
+        ) => return split_state_witness_request(request, is_faulted_receiver),
+        _ => return (None, Some(request)),
+    };
+    if receiver.map_or(true, is_faulted_receiver) {
+        (Some(request), None)
+    } else {
+        (None, Some(request))
+    }
+}
+
+fn split_state_witness_request(
+    request: NetworkRequests,
+    is_faulted_receiver: impl Fn(&AccountId) -> bool,
+) -> (Option<NetworkRequests>, Option<NetworkRequests>) {
+    let non_empty = |request: NetworkRequests, is_empty: bool| (!is_empty).then_some(request);
+    match request {
+        NetworkRequests::PartialEncodedStateWitness(parts) => {
+            let (faulted, rest): (Vec<_>, Vec<_>) =
+                parts.into_iter().partition(|(receiver, _)| is_faulted_receiver(receiver));
+            let (faulted_is_empty, rest_is_empty) = (faulted.is_empty(), rest.is_empty());
+            (
+                non_empty(NetworkRequests::PartialEncodedStateWitness(faulted), faulted_is_empty),
+                non_empty(NetworkRequests::PartialEncodedStateWitness(rest), rest_is_empty),
+            )
+        }
+        NetworkRequests::PartialEncodedStateWitnessForward(receivers, witness) => {
+            let (faulted, rest): (Vec<_>, Vec<_>) =
+                receivers.into_iter().partition(|receiver| is_faulted_receiver(receiver));
+            let (faulted_is_empty, rest_is_empty) = (faulted.is_empty(), rest.is_empty());
+            (
+                non_empty(
+                    NetworkRequests::PartialEncodedStateWitnessForward(faulted, witness.clone()),
+                    faulted_is_empty,
+                ),
+                non_empty(
+                    NetworkRequests::PartialEncodedStateWitnessForward(rest, witness),
+                    rest_is_empty,
+                ),
+            )
+        }
+        _ => unreachable!("not a state witness request"),
+    }
+}
+
+/// Handler applying the network faults to the requests sent by `account_id`. It must be the
+/// last registered override handler of the node, so that it runs first.
+pub fn network_fault_injector(
+    account_id: AccountId,
+    network_faults: std::sync::Arc<std::sync::Mutex<NetworkFaults>>,
+) -> Box<dyn Fn(NetworkRequests) -> Option<NetworkRequests>> {
+    Box::new(move |request| network_faults.lock().unwrap().apply(&account_id, request))
+}
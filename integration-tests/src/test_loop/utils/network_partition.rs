@@ -0,0 +1,96 @@
+//! Checks of the chains of the nodes after a partition of the network is healed, see
+//! `TestLoopEnv::run_with_network_partition`.
+
+use near_async::test_loop::data::TestLoopData;
+use near_async::time::Duration;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+use crate::test_loop::env::{TestData, TestLoopEnv};
+
+fn head_hash(test_loop_data: &TestLoopData, data: &TestData) -> CryptoHash {
+    test_loop_data
+        .get(&data.client_sender.actor_handle())
+        .client
+        .chain
+        .head()
+        .unwrap()
+        .last_block_hash
+}
+
+/// Runs the test loop until all the nodes have the same head, i.e. the forks created while the
+/// network was partitioned are resolved. Then checks that all the nodes agree on the canonical
+/// chain above `from_height`, and that the final block of every node is on it.
+pub(crate) fn assert_fork_resolution(
+    env: &mut TestLoopEnv,
+    from_height: BlockHeight,
+    maximum_duration: Duration,
+) {
+    let datas = &env.datas;
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let head_hash0 = head_hash(test_loop_data, &datas[0]);
+            datas.iter().all(|data| head_hash(test_loop_data, data) == head_hash0)
+        },
+        maximum_duration,
+    );
+
+    // The canonical chain of the first node, from its head down to `from_height`.
+    let client = &env.test_loop.data.get(&datas[0].client_sender.actor_handle()).client;
+    let mut canonical_chain = Vec::new();
+    let mut block_hash = client.chain.head().unwrap().last_block_hash;
+    loop {
+        let header = client.chain.get_block_header(&block_hash).unwrap();
+        if header.height() < from_height {
+            break;
+        }
+        canonical_chain.push((header.height(), block_hash));
+        block_hash = *header.prev_hash();
+    }
+
+    for data in datas {
+        let client = &env.test_loop.data.get(&data.client_sender.actor_handle()).client;
+        for (height, block_hash) in &canonical_chain {
+            assert_eq!(
+                client.chain.get_block_hash_by_height(*height).ok().as_ref(),
+                Some(block_hash),
+                "{} has another canonical block at height {height}",
+                data.account_id
+            );
+        }
+        let final_head = client.chain.final_head().unwrap();
+        if final_head.height >= from_height {
+            assert!(
+                canonical_chain.contains(&(final_head.height, final_head.last_block_hash)),
+                "final block of {} at height {} isn't canonical",
+                data.account_id,
+                final_head.height
+            );
+        }
+    }
+}
+
+/// Runs the test loop until all the nodes have the same final block above `min_height`, i.e.
+/// finality resumed after the partition of the network was healed. Returns the height of the
+/// final block.
+pub(crate) fn assert_final_head_convergence(
+    env: &mut TestLoopEnv,
+    min_height: BlockHeight,
+    maximum_duration: Duration,
+) -> BlockHeight {
+    let datas = &env.datas;
+    let final_head = |test_loop_data: &TestLoopData, data: &TestData| {
+        test_loop_data.get(&data.client_sender.actor_handle()).client.chain.final_head().unwrap()
+    };
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let final_head0 = final_head(test_loop_data, &datas[0]);
+            final_head0.height > min_height
+                && datas.iter().all(|data| {
+                    final_head(test_loop_data, data).last_block_hash == final_head0.last_block_hash
+                })
+        },
+        maximum_duration,
+    );
+    final_head(&env.test_loop.data, &datas[0]).height
+}
@@ -0,0 +1,130 @@
+//! Helpers for the tests upgrading the protocol version of a test loop network. The genesis is
+//! built at an older protocol version, e.g. `PROTOCOL_VERSION - 2`, the nodes vote for the
+//! versions set with `TestLoopBuilder::protocol_version_votes_for_clients`, and
+//! [run_until_protocol_version] drives the network until the upgrade, recording the protocol
+//! version of each epoch:
+//!
+//! ```ignore
+//! let epoch_config_store = protocol_upgrade_epoch_config_store(
+//!     PROTOCOL_VERSION - 2..=PROTOCOL_VERSION,
+//!     |epoch_config| epoch_config.epoch_length = epoch_length,
+//! );
+//! let mut env = builder.genesis(genesis).epoch_config_store(epoch_config_store).build();
+//! let history = run_until_protocol_version(&mut env, PROTOCOL_VERSION, Duration::seconds(60));
+//! history.assert_feature_activated_at(ProtocolFeature::SomeFeature, 3);
+//! ```
+
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::ops::{Deref, RangeInclusive};
+use std::sync::Arc;
+
+use near_async::time::Duration;
+use near_primitives::epoch_manager::{EpochConfig, EpochConfigStore};
+use near_primitives::types::EpochHeight;
+use near_primitives::version::{ProtocolFeature, ProtocolVersion};
+
+use crate::test_loop::env::TestLoopEnv;
+
+/// Returns an epoch config store with the mainnet epoch configs of the given protocol versions,
+/// adjusted for the test by `adjust_epoch_config`, e.g. to set the epoch length and the shard
+/// layout of the genesis.
+pub(crate) fn protocol_upgrade_epoch_config_store(
+    protocol_versions: RangeInclusive<ProtocolVersion>,
+    adjust_epoch_config: impl Fn(&mut EpochConfig),
+) -> EpochConfigStore {
+    let mainnet_epoch_config_store = EpochConfigStore::for_chain_id("mainnet", None).unwrap();
+    let epoch_configs = protocol_versions.map(|protocol_version| {
+        let mut epoch_config =
+            mainnet_epoch_config_store.get_config(protocol_version).deref().clone();
+        adjust_epoch_config(&mut epoch_config);
+        (protocol_version, Arc::new(epoch_config))
+    });
+    EpochConfigStore::test(BTreeMap::from_iter(epoch_configs))
+}
+
+/// Protocol versions of the epochs seen by the first node during a protocol upgrade.
+#[derive(Debug)]
+pub(crate) struct ProtocolVersionHistory {
+    epochs: BTreeMap<EpochHeight, ProtocolVersion>,
+}
+
+impl ProtocolVersionHistory {
+    /// Height of the first epoch seen during the test, usually the genesis epoch.
+    pub fn first_epoch_height(&self) -> EpochHeight {
+        *self.epochs.keys().next().unwrap()
+    }
+
+    pub fn protocol_version(&self, epoch_height: EpochHeight) -> Option<ProtocolVersion> {
+        self.epochs.get(&epoch_height).copied()
+    }
+
+    /// Returns the height of the first epoch running `protocol_version` or a later version.
+    pub fn activation_epoch_height(
+        &self,
+        protocol_version: ProtocolVersion,
+    ) -> Option<EpochHeight> {
+        self.epochs
+            .iter()
+            .find(|(_, &epoch_protocol_version)| epoch_protocol_version >= protocol_version)
+            .map(|(&epoch_height, _)| epoch_height)
+    }
+
+    /// Checks that `protocol_version` became active at the epoch `epoch_height`.
+    pub fn assert_activated_at(
+        &self,
+        protocol_version: ProtocolVersion,
+        epoch_height: EpochHeight,
+    ) {
+        assert_eq!(
+            self.activation_epoch_height(protocol_version),
+            Some(epoch_height),
+            "protocol version {protocol_version} activated at the wrong epoch, epochs: {:?}",
+            self.epochs
+        );
+    }
+
+    /// Checks that `feature` became active at the epoch `epoch_height`.
+    pub fn assert_feature_activated_at(&self, feature: ProtocolFeature, epoch_height: EpochHeight) {
+        assert_eq!(
+            self.activation_epoch_height(feature.protocol_version()),
+            Some(epoch_height),
+            "{feature:?} activated at the wrong epoch, epochs: {:?}",
+            self.epochs
+        );
+    }
+}
+
+/// Runs the test loop until the epoch of the head of the first node runs `protocol_version`,
+/// recording the protocol version of each epoch on the way. Panics if the protocol version of
+/// an epoch is lower than the one of the previous epoch.
+pub(crate) fn run_until_protocol_version(
+    env: &mut TestLoopEnv,
+    protocol_version: ProtocolVersion,
+    maximum_duration: Duration,
+) -> ProtocolVersionHistory {
+    let client_handle = env.datas[0].client_sender.actor_handle();
+    let epochs = RefCell::new(BTreeMap::<EpochHeight, ProtocolVersion>::new());
+    env.test_loop.run_until(
+        |test_loop_data| {
+            let client = &test_loop_data.get(&client_handle).client;
+            let tip = client.chain.head().unwrap();
+            let epoch_info = client.epoch_manager.get_epoch_info(&tip.epoch_id).unwrap();
+            let epoch_protocol_version = epoch_info.protocol_version();
+            let mut epochs = epochs.borrow_mut();
+            if !epochs.contains_key(&epoch_info.epoch_height()) {
+                if let Some((_, &last_protocol_version)) = epochs.last_key_value() {
+                    assert!(
+                        epoch_protocol_version >= last_protocol_version,
+                        "protocol version went down from {last_protocol_version} to {epoch_protocol_version}"
+                    );
+                }
+                tracing::info!(target: "test", epoch_height = epoch_info.epoch_height(), epoch_protocol_version, "New epoch");
+                epochs.insert(epoch_info.epoch_height(), epoch_protocol_version);
+            }
+            epoch_protocol_version == protocol_version
+        },
+        maximum_duration,
+    );
+    ProtocolVersionHistory { epochs: epochs.into_inner() }
+}
@@ -0,0 +1,123 @@
+//! Tracing of the receipts of a transaction across the shards and the nodes of a test loop,
+//! to debug cross-shard tests without digging through the store of each node:
+//!
+//! ```ignore
+//! let tree = trace_receipt_tree(&env.test_loop.data, &env.datas, &tx_hash);
+//! tracing::info!(target: "test", "receipts of the transaction:\n{tree}");
+//! ```
+
+use std::fmt;
+
+use itertools::Itertools;
+use near_async::test_loop::data::TestLoopData;
+use near_client::Client;
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::ExecutionOutcome;
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+
+use crate::test_loop::env::TestData;
+
+/// Where a transaction or a receipt was executed, with its outcome.
+#[derive(Clone, Debug)]
+pub(crate) struct Execution {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub shard_id: ShardId,
+    /// First node, in the order of the clients, with the outcome on its canonical chain.
+    pub node_account_id: AccountId,
+    pub outcome: ExecutionOutcome,
+}
+
+/// A transaction or a receipt with the tree of the receipts it produced.
+#[derive(Clone, Debug)]
+pub(crate) struct ReceiptTree {
+    /// Hash of the transaction or id of the receipt.
+    pub id: CryptoHash,
+    /// `None` if no node has executed the receipt yet.
+    pub execution: Option<Execution>,
+    pub children: Vec<ReceiptTree>,
+}
+
+impl ReceiptTree {
+    /// Whether the transaction and all the receipts it produced are executed.
+    pub fn is_complete(&self) -> bool {
+        self.execution.is_some() && self.children.iter().all(ReceiptTree::is_complete)
+    }
+
+    /// Returns the transaction and all its receipts, depth first.
+    pub fn flatten(&self) -> Vec<&ReceiptTree> {
+        let mut nodes = vec![self];
+        for child in &self.children {
+            nodes.extend(child.flatten());
+        }
+        nodes
+    }
+
+    fn fmt_with_depth(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        write!(f, "{:indent$}{}", "", self.id, indent = 2 * depth)?;
+        match &self.execution {
+            Some(execution) => writeln!(
+                f,
+                " executor={} shard={} height={} node={} status={:?}",
+                execution.outcome.executor_id,
+                execution.shard_id,
+                execution.block_height,
+                execution.node_account_id,
+                execution.outcome.status
+            )?,
+            None => writeln!(f, " not executed")?,
+        }
+        for child in &self.children {
+            child.fmt_with_depth(f, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ReceiptTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_with_depth(f, 0)
+    }
+}
+
+/// Builds the tree of the receipts of the transaction `tx_hash` from the outcomes stored by
+/// the nodes. Each outcome is looked up on all the nodes, so the tree spans all the shards even
+/// if no node tracks all of them. Receipts which aren't executed yet are leaves of the tree.
+pub(crate) fn trace_receipt_tree(
+    test_loop_data: &TestLoopData,
+    node_datas: &[TestData],
+    tx_hash: &CryptoHash,
+) -> ReceiptTree {
+    let clients = node_datas
+        .iter()
+        .map(|data| {
+            (&data.account_id, &test_loop_data.get(&data.client_sender.actor_handle()).client)
+        })
+        .collect_vec();
+    build_receipt_tree(&clients, *tx_hash)
+}
+
+fn build_receipt_tree(clients: &[(&AccountId, &Client)], id: CryptoHash) -> ReceiptTree {
+    let execution = clients.iter().find_map(|(account_id, client)| {
+        let outcome = client.chain.get_execution_outcome(&id).ok()?;
+        let header = client.chain.get_block_header(&outcome.block_hash).unwrap();
+        let outcome = outcome.outcome_with_id.outcome;
+        let shard_id = client
+            .epoch_manager
+            .account_id_to_shard_id(&outcome.executor_id, header.epoch_id())
+            .unwrap();
+        Some(Execution {
+            block_hash: *header.hash(),
+            block_height: header.height(),
+            shard_id,
+            node_account_id: (*account_id).clone(),
+            outcome,
+        })
+    });
+    let children = execution
+        .iter()
+        .flat_map(|execution| &execution.outcome.receipt_ids)
+        .map(|receipt_id| build_receipt_tree(clients, *receipt_id))
+        .collect();
+    ReceiptTree { id, execution, children }
+}
@@ -20,6 +20,7 @@ use near_primitives::views::{
 };
 use near_store::adapter::StoreAdapter;
 use near_store::db::refcount::decode_value_with_rc;
+use near_store::flat::FlatStorageStatus;
 use near_store::{DBCol, ShardUId};
 use rand::seq::SliceRandom;
 use rand::{Rng, SeedableRng};
@@ -687,3 +688,37 @@ pub(crate) fn check_state_cleanup_after_resharding(
     );
     LoopAction::new(action_fn, succeeded)
 }
+
+/// Checks that a node which catches up the parent shard during the epoch of the resharding, to
+/// track a child in the next epoch, performs the resharding once the catchup is done.
+pub(crate) fn check_resharding_after_catchup(
+    client_index: usize,
+    parent_shard_uid: ShardUId,
+    child_shard_uid: ShardUId,
+) -> LoopAction {
+    let (done, succeeded) = LoopAction::shared_success_flag();
+    let action_fn = Box::new(
+        move |node_datas: &[TestData], test_loop_data: &mut TestLoopData, _: AccountId| {
+            if done.get() {
+                return;
+            }
+
+            let client_handle = node_datas[client_index].client_sender.actor_handle();
+            let client = &test_loop_data.get(&client_handle).client;
+            let tip = client.chain.head().unwrap();
+            // The blocks of the next epoch are only processed once the catchup is done.
+            if !this_block_has_new_shard_layout(client.epoch_manager.as_ref(), &tip) {
+                return;
+            }
+
+            assert!(!client.chain.resharding_manager.is_waiting_for_catchup(parent_shard_uid));
+            let flat_storage_manager = client.chain.runtime_adapter.get_flat_storage_manager();
+            assert_matches!(
+                flat_storage_manager.get_flat_storage_status(child_shard_uid),
+                FlatStorageStatus::Resharding(_) | FlatStorageStatus::Ready(_)
+            );
+            done.set(true);
+        },
+    );
+    LoopAction::new(action_fn, succeeded)
+}
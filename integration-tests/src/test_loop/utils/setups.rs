@@ -57,3 +57,30 @@ pub fn standard_setup_1() -> TestLoopEnv {
         .clients(clients)
         .build()
 }
+
+/// Number of validators of [single_shard_validators_setup].
+pub const NUM_SINGLE_SHARD_VALIDATORS: usize = 4;
+
+/// 4 validators (account{i}) with the same stake of a single shard, with epochs long enough for
+/// nobody to be kicked out because of the faults injected by the tests. Returns the builder, so
+/// that the tests can set up the nodes further before building the env.
+pub fn single_shard_validators_setup() -> TestLoopBuilder {
+    let accounts: Vec<AccountId> = (0..NUM_SINGLE_SHARD_VALIDATORS)
+        .map(|i| format!("account{}", i).parse().unwrap())
+        .collect_vec();
+    let clients = accounts.clone();
+    let validators_spec =
+        ValidatorsSpec::desired_roles(&clients.iter().map(|t| t.as_str()).collect_vec(), &[]);
+    let (genesis, epoch_config_store) = build_genesis_and_epoch_config_store(
+        GenesisAndEpochConfigParams {
+            epoch_length: 100,
+            protocol_version: PROTOCOL_VERSION,
+            shard_layout: ShardLayout::single_shard(),
+            validators_spec,
+            accounts: &accounts,
+        },
+        |genesis_builder| genesis_builder,
+        |epoch_config_builder| epoch_config_builder,
+    );
+    TestLoopBuilder::new().genesis(genesis).epoch_config_store(epoch_config_store).clients(clients)
+}
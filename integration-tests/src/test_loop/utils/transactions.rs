@@ -1,7 +1,7 @@
 use crate::test_loop::env::{TestData, TestLoopEnv};
 use assert_matches::assert_matches;
 use itertools::Itertools;
-use near_async::messaging::{AsyncSendError, CanSend, SendAsync};
+use near_async::messaging::{AsyncSendError, CanSend, Handler, SendAsync};
 use near_async::test_loop::data::TestLoopData;
 use near_async::test_loop::futures::TestLoopFutureSpawner;
 use near_async::test_loop::sender::TestLoopSender;
@@ -9,17 +9,18 @@ use near_async::test_loop::TestLoopV2;
 use near_async::time::Duration;
 use near_client::client_actor::ClientActorInner;
 use near_client::test_utils::test_loop::ClientQueries;
-use near_client::{Client, ProcessTxResponse};
-use near_crypto::Signer;
+use near_client::{Client, ProcessTxResponse, Query, QueryError};
+use near_crypto::{PublicKey, Signer};
 use near_network::client::ProcessTxRequest;
 use near_primitives::block::Tip;
 use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::test_utils::create_user_test_signer;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::AccountId;
+use near_primitives::types::{AccountId, BlockReference};
 use near_primitives::views::{
-    FinalExecutionOutcomeView, FinalExecutionStatus, QueryRequest, QueryResponseKind,
+    AccessKeyList, AccessKeyView, AccountView, CallResult, FinalExecutionOutcomeView,
+    FinalExecutionStatus, QueryRequest, QueryResponseKind,
 };
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
@@ -482,6 +483,86 @@ pub fn execute_tx(
     res.unwrap()
 }
 
+/// Runs a query at the head of the chain of `node_id`, through its view client, without
+/// submitting any transaction.
+fn view_client_query(
+    test_loop_data: &mut TestLoopData,
+    node_datas: &[TestData],
+    node_id: &AccountId,
+    request: QueryRequest,
+) -> Result<QueryResponseKind, QueryError> {
+    let view_client_handle = get_node_data(node_datas, node_id).view_client_sender.actor_handle();
+    let view_client = test_loop_data.get_mut(&view_client_handle);
+    let response = view_client.handle(Query::new(BlockReference::latest(), request))?;
+    Ok(response.kind)
+}
+
+/// Calls the view method `method_name` of the contract of `account_id` on `node_id`.
+pub fn view_call(
+    test_loop_data: &mut TestLoopData,
+    node_datas: &[TestData],
+    node_id: &AccountId,
+    account_id: &AccountId,
+    method_name: &str,
+    args: Vec<u8>,
+) -> Result<CallResult, QueryError> {
+    let request = QueryRequest::CallFunction {
+        account_id: account_id.clone(),
+        method_name: method_name.to_string(),
+        args: args.into(),
+    };
+    match view_client_query(test_loop_data, node_datas, node_id, request)? {
+        QueryResponseKind::CallResult(call_result) => Ok(call_result),
+        kind => panic!("Expected CallResult response, got {kind:?}"),
+    }
+}
+
+/// Returns the account `account_id` as seen by `node_id`.
+pub fn view_account(
+    test_loop_data: &mut TestLoopData,
+    node_datas: &[TestData],
+    node_id: &AccountId,
+    account_id: &AccountId,
+) -> Result<AccountView, QueryError> {
+    let request = QueryRequest::ViewAccount { account_id: account_id.clone() };
+    match view_client_query(test_loop_data, node_datas, node_id, request)? {
+        QueryResponseKind::ViewAccount(account) => Ok(account),
+        kind => panic!("Expected ViewAccount response, got {kind:?}"),
+    }
+}
+
+/// Returns the access key `public_key` of `account_id` as seen by `node_id`.
+pub fn view_access_key(
+    test_loop_data: &mut TestLoopData,
+    node_datas: &[TestData],
+    node_id: &AccountId,
+    account_id: &AccountId,
+    public_key: &PublicKey,
+) -> Result<AccessKeyView, QueryError> {
+    let request = QueryRequest::ViewAccessKey {
+        account_id: account_id.clone(),
+        public_key: public_key.clone(),
+    };
+    match view_client_query(test_loop_data, node_datas, node_id, request)? {
+        QueryResponseKind::AccessKey(access_key) => Ok(access_key),
+        kind => panic!("Expected AccessKey response, got {kind:?}"),
+    }
+}
+
+/// Returns the access keys of `account_id` as seen by `node_id`.
+pub fn view_access_key_list(
+    test_loop_data: &mut TestLoopData,
+    node_datas: &[TestData],
+    node_id: &AccountId,
+    account_id: &AccountId,
+) -> Result<AccessKeyList, QueryError> {
+    let request = QueryRequest::ViewAccessKeyList { account_id: account_id.clone() };
+    match view_client_query(test_loop_data, node_datas, node_id, request)? {
+        QueryResponseKind::AccessKeyList(access_keys) => Ok(access_keys),
+        kind => panic!("Expected AccessKeyList response, got {kind:?}"),
+    }
+}
+
 /// Creates account ids for the given number of accounts.
 pub fn make_accounts(num_accounts: usize) -> Vec<AccountId> {
     let accounts = (0..num_accounts).map(|i| make_account(i)).collect_vec();
@@ -640,6 +721,9 @@ impl TransactionRunner {
                 }
                 _ => TxProcessingResult::Invalid(err),
             },
+            ProcessTxResponse::SignerShardCongested { error, .. } => {
+                TxProcessingResult::Congested(error)
+            }
             ProcessTxResponse::DoesNotTrackShard => {
                 panic!("Transaction submitted to a node that doesn't track the shard")
             }
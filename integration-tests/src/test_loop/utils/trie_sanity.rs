@@ -11,14 +11,18 @@ use near_client::Client;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::state::FlatStateValue;
+use near_primitives::trie_key::trie_key_parsers::parse_account_id_from_raw_key;
 use near_primitives::types::{AccountId, EpochId, NumShards};
 use near_primitives::version::PROTOCOL_VERSION;
 use near_store::adapter::trie_store::get_shard_uid_mapping;
 use near_store::adapter::StoreAdapter;
 use near_store::db::refcount::decode_value_with_rc;
 use near_store::flat::FlatStorageStatus;
+use near_store::trie::state_consistency::{
+    find_state_divergence, iter_flat_state_values, StateDivergence,
+};
 use near_store::{DBCol, ShardUId};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 // For each epoch, keep a map from AccountId to a map with keys equal to
 // the set of shards that account tracks in that epoch, and bool values indicating
@@ -367,6 +371,11 @@ pub fn check_state_shard_uid_mapping_after_resharding(
         if &mapped_shard_uid == child_shard_uid {
             continue;
         }
+        // The mapping is also recorded in its history, for archival nodes.
+        let history_key_prefix = child_shard_uid.to_bytes();
+        let mut history = store
+            .iter_prefix_ser::<ShardUId>(DBCol::StateShardUIdMappingHistory, &history_key_prefix);
+        assert_eq!(history.next().unwrap().unwrap().1, mapped_shard_uid);
         shard_uid_mapping.insert(child_shard_uid, mapped_shard_uid);
         if tracked_shards.contains(child_shard_uid) {
             tracked_mapped_children.push(*child_shard_uid);
@@ -436,3 +445,141 @@ pub fn check_state_shard_uid_mapping_after_resharding(
         assert!(shard_uid_mapping.is_empty());
     }
 }
+
+/// Reads back from disk the state of the children computed at the resharding block, and asserts
+/// that every child holds its side of the parent state. Returns false if the client didn't
+/// compute any child.
+pub fn check_children_state_on_disk_after_resharding(
+    client: &Client,
+    resharding_block_hash: &CryptoHash,
+    parent_shard_uid: ShardUId,
+) -> bool {
+    let epoch_manager = &client.epoch_manager;
+    let child_epoch_id = epoch_manager.get_next_epoch_id(resharding_block_hash).unwrap();
+    let child_shard_layout = epoch_manager.get_shard_layout(&child_epoch_id).unwrap();
+    let children_shard_uids =
+        child_shard_layout.get_children_shards_uids(parent_shard_uid.shard_id()).unwrap();
+    // Only the children tracked by the client have a chunk extra at the resharding block.
+    let children_state_roots = children_shard_uids
+        .into_iter()
+        .filter_map(|child_shard_uid| {
+            let chunk_extra =
+                client.chain.get_chunk_extra(resharding_block_hash, &child_shard_uid).ok()?;
+            Some((child_shard_uid, *chunk_extra.state_root()))
+        })
+        .collect_vec();
+    if children_state_roots.is_empty() {
+        return false;
+    }
+
+    let tries = client.runtime_adapter.get_tries();
+    let read_disk_state = |shard_uid: ShardUId, state_root| -> BTreeMap<Vec<u8>, Vec<u8>> {
+        let trie = tries.get_view_trie_for_shard(shard_uid, state_root);
+        assert!(!trie.has_memtries());
+        trie.disk_iter().unwrap().map(Result::unwrap).collect()
+    };
+    let parent_state_root = *client
+        .chain
+        .get_chunk_extra(resharding_block_hash, &parent_shard_uid)
+        .unwrap()
+        .state_root();
+    let parent_state = read_disk_state(parent_shard_uid, parent_state_root);
+
+    for (child_shard_uid, child_state_root) in children_state_roots {
+        let child_state = read_disk_state(child_shard_uid, child_state_root);
+        for (key, value) in &child_state {
+            assert_eq!(parent_state.get(key), Some(value), "{child_shard_uid} has foreign key");
+        }
+        // The keys of the accounts of the child are all retained, the other keys are either
+        // copied to both children or to one of them only.
+        for (key, value) in &parent_state {
+            let Some(account_id) = parse_account_id_from_raw_key(key).unwrap() else {
+                continue;
+            };
+            let owned = child_shard_layout.account_id_to_shard_uid(&account_id) == child_shard_uid;
+            assert_eq!(
+                child_state.get(key),
+                owned.then_some(value),
+                "{child_shard_uid} {account_id}"
+            );
+        }
+    }
+    true
+}
+
+/// Views of the state which were compared with the on-disk trie by `assert_state_consistency()`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateViewsChecked {
+    pub memtrie: bool,
+    pub flat_storage: bool,
+}
+
+/// Asserts that the memtrie and the flat storage of the shard hold the same state as the on-disk
+/// trie after applying the chunk of the given block, panicking with the first divergent key.
+///
+/// The memtrie is only compared if the client has it loaded for the shard, and the flat storage
+/// only if its head is at the given block, as only the data committed at the head can be read.
+pub fn assert_state_consistency(
+    client: &Client,
+    block_hash: &CryptoHash,
+    shard_uid: ShardUId,
+) -> StateViewsChecked {
+    let state_root = *client.chain.get_chunk_extra(block_hash, &shard_uid).unwrap().state_root();
+    // get_view_trie_for_shard() never uses memtries.
+    let disk_trie = client
+        .runtime_adapter
+        .get_view_trie_for_shard(shard_uid.shard_id(), block_hash, state_root)
+        .unwrap();
+    assert!(!disk_trie.has_memtries());
+    let mut checked = StateViewsChecked { memtrie: false, flat_storage: false };
+
+    let trie = client
+        .runtime_adapter
+        .get_trie_for_shard(shard_uid.shard_id(), block_hash, state_root, false)
+        .unwrap();
+    if trie.has_memtries() {
+        let divergence = find_state_divergence(
+            trie.lock_for_iter().iter().unwrap(),
+            disk_trie.disk_iter().unwrap(),
+        )
+        .unwrap();
+        assert_no_divergence(divergence, "memtrie", shard_uid, block_hash);
+        checked.memtrie = true;
+    }
+
+    let flat_storage_manager = client.runtime_adapter.get_flat_storage_manager();
+    if let FlatStorageStatus::Ready(status) =
+        flat_storage_manager.get_flat_storage_status(shard_uid)
+    {
+        if status.flat_head.hash == *block_hash {
+            let store = client.chain.chain_store().store();
+            let (flat_store, trie_store) = (store.flat_store(), store.trie_store());
+            let divergence = find_state_divergence(
+                iter_flat_state_values(&flat_store, &trie_store, shard_uid),
+                disk_trie.disk_iter().unwrap(),
+            )
+            .unwrap();
+            assert_no_divergence(divergence, "flat storage", shard_uid, block_hash);
+            checked.flat_storage = true;
+        }
+    }
+    checked
+}
+
+fn assert_no_divergence(
+    divergence: Option<StateDivergence>,
+    view: &str,
+    shard_uid: ShardUId,
+    block_hash: &CryptoHash,
+) {
+    let Some(StateDivergence { key, first, second }) = divergence else {
+        return;
+    };
+    panic!(
+        "{view} and on-disk trie of shard {shard_uid} diverge at block {block_hash}: \
+        key {}, {view} value {:?}, on-disk trie value {:?}",
+        hex::encode(&key),
+        first.map(hex::encode),
+        second.map(hex::encode),
+    );
+}
@@ -1,4 +1,13 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+
+use near_async::test_loop::data::{TestLoopData, TestLoopDataHandle};
+use near_async::test_loop::TestLoopV2;
+use near_client::client_actor::ClientActorInner;
 use near_client::Client;
+use near_primitives::epoch_manager::EpochConfig;
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{AccountId, BlockHeight, EpochHeight, EpochId};
 
 /// Get all validator account names for the latest epoch.
 pub(crate) fn get_epoch_all_validators(client: &Client) -> Vec<String> {
@@ -7,3 +16,115 @@ pub(crate) fn get_epoch_all_validators(client: &Client) -> Vec<String> {
     let all_validators = client.epoch_manager.get_epoch_all_validators(&epoch_id).unwrap();
     all_validators.into_iter().map(|vs| vs.account_id().to_string()).collect()
 }
+
+/// Describes a transition between two consecutive epochs, as observed by the head of a client.
+pub(crate) struct EpochTransition {
+    pub prev_epoch_id: EpochId,
+    pub epoch_id: EpochId,
+    /// Height of the new epoch.
+    pub epoch_height: EpochHeight,
+    /// Height of the first block of the new epoch seen by the client.
+    pub block_height: BlockHeight,
+    pub prev_validators: Vec<ValidatorStake>,
+    pub validators: Vec<ValidatorStake>,
+    pub prev_epoch_config: EpochConfig,
+    pub epoch_config: EpochConfig,
+}
+
+impl EpochTransition {
+    /// Accounts which were validators in the previous epoch but are not anymore.
+    pub fn removed_validators(&self) -> Vec<AccountId> {
+        let validators = self.validators.iter().map(|v| v.account_id()).collect::<Vec<_>>();
+        self.prev_validators
+            .iter()
+            .map(|v| v.account_id())
+            .filter(|account_id| !validators.contains(account_id))
+            .cloned()
+            .collect()
+    }
+
+    /// Accounts which became validators in the new epoch.
+    pub fn added_validators(&self) -> Vec<AccountId> {
+        let prev_validators =
+            self.prev_validators.iter().map(|v| v.account_id()).collect::<Vec<_>>();
+        self.validators
+            .iter()
+            .map(|v| v.account_id())
+            .filter(|account_id| !prev_validators.contains(account_id))
+            .cloned()
+            .collect()
+    }
+}
+
+type EpochTransitionHook = Box<dyn FnMut(&EpochTransition)>;
+
+/// Calls the registered hooks every time the head of the observed client enters a new epoch.
+///
+/// The hooks are run from the every-event callback of the test loop, so asserting inside a
+/// hook fails the test at the epoch boundary where the assertion is violated. This replaces the
+/// height arithmetic otherwise needed to find out in which epoch the chain currently is.
+pub(crate) struct EpochBoundaryHooks {
+    client_handle: TestLoopDataHandle<ClientActorInner>,
+    hooks: Vec<EpochTransitionHook>,
+}
+
+impl EpochBoundaryHooks {
+    pub fn new(client_handle: TestLoopDataHandle<ClientActorInner>) -> Self {
+        Self { client_handle, hooks: vec![] }
+    }
+
+    /// Registers a hook to call at every epoch transition.
+    pub fn on_epoch_transition(mut self, hook: impl FnMut(&EpochTransition) + 'static) -> Self {
+        self.hooks.push(Box::new(hook));
+        self
+    }
+
+    /// Installs the hooks as the every-event callback of the test loop, replacing any callback
+    /// set before. Returns the ids of the epochs observed so far, starting with the epoch of
+    /// the head at the first event, which is updated as the test loop runs.
+    pub fn install(self, test_loop: &mut TestLoopV2) -> Rc<RefCell<Vec<EpochId>>> {
+        let Self { client_handle, mut hooks } = self;
+        let epoch_ids = Rc::new(RefCell::new(vec![]));
+        let observed_epoch_ids = epoch_ids.clone();
+        test_loop.set_every_event_callback(move |test_loop_data: &TestLoopData| {
+            let client = &test_loop_data.get(&client_handle).client;
+            let tip = client.chain.head().unwrap();
+            let prev_epoch_id = {
+                let mut epoch_ids = epoch_ids.borrow_mut();
+                let Some(&prev_epoch_id) = epoch_ids.last() else {
+                    epoch_ids.push(tip.epoch_id);
+                    return;
+                };
+                if prev_epoch_id == tip.epoch_id {
+                    return;
+                }
+                epoch_ids.push(tip.epoch_id);
+                prev_epoch_id
+            };
+
+            let epoch_manager = &client.epoch_manager;
+            let transition = EpochTransition {
+                prev_epoch_id,
+                epoch_id: tip.epoch_id,
+                epoch_height: epoch_manager.get_epoch_info(&tip.epoch_id).unwrap().epoch_height(),
+                block_height: tip.height,
+                prev_validators: epoch_manager.get_epoch_all_validators(&prev_epoch_id).unwrap(),
+                validators: epoch_manager.get_epoch_all_validators(&tip.epoch_id).unwrap(),
+                prev_epoch_config: epoch_manager.get_epoch_config(&prev_epoch_id).unwrap(),
+                epoch_config: epoch_manager.get_epoch_config(&tip.epoch_id).unwrap(),
+            };
+            tracing::info!(
+                target: "test",
+                epoch_height = transition.epoch_height,
+                block_height = transition.block_height,
+                removed_validators = ?transition.removed_validators(),
+                added_validators = ?transition.added_validators(),
+                "Epoch transition"
+            );
+            for hook in &mut hooks {
+                hook(&transition);
+            }
+        });
+        observed_epoch_ids
+    }
+}
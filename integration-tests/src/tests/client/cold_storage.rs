@@ -189,11 +189,12 @@ fn test_storage_after_commit_of_cold_update() {
         let cold_store = &storage.get_cold_store().unwrap();
         let num_checks = check_iter(client_store, cold_store, col, &no_check_rules);
         // assert that this test actually checks something
-        // apart from StateChangesForSplitStates, StateHeaders, and StateShardUIdMapping, that are empty
+        // apart from StateChangesForSplitStates, StateHeaders and the StateShardUIdMapping columns, that are empty
         assert!(
             col == DBCol::StateChangesForSplitStates
                 || col == DBCol::StateHeaders
                 || col == DBCol::StateShardUIdMapping
+                || col == DBCol::StateShardUIdMappingHistory
                 || num_checks > 0
         );
     }
@@ -320,11 +321,12 @@ fn test_cold_db_copy_with_height_skips() {
             let cold_store = storage.get_cold_store().unwrap();
             let num_checks = check_iter(&client_store, &cold_store, col, &no_check_rules);
             // assert that this test actually checks something
-            // apart from StateChangesForSplitStates, StateHeaders, and StateShardUIdMapping, that are empty
+            // apart from StateChangesForSplitStates, StateHeaders and the StateShardUIdMapping columns, that are empty
             assert!(
                 col == DBCol::StateChangesForSplitStates
                     || col == DBCol::StateHeaders
                     || col == DBCol::StateShardUIdMapping
+                    || col == DBCol::StateShardUIdMappingHistory
                     || num_checks > 0
             );
         }
@@ -374,10 +376,11 @@ fn test_initial_copy_to_cold(batch_size: usize) {
             continue;
         }
         let num_checks = check_iter(&client_store, &cold_store, col, &vec![]);
-        // StateChangesForSplitStates, StateHeaders, and StateShardUIdMapping are empty
+        // StateChangesForSplitStates, StateHeaders and the StateShardUIdMapping columns are empty
         if col == DBCol::StateChangesForSplitStates
             || col == DBCol::StateHeaders
             || col == DBCol::StateShardUIdMapping
+            || col == DBCol::StateShardUIdMappingHistory
         {
             continue;
         }
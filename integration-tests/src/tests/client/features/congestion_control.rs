@@ -53,6 +53,60 @@ fn set_default_congestion_control(config_store: &RuntimeConfigStore, config: &mu
     config.congestion_control_config = cc_config.congestion_control_config;
 }
 
+/// Test that RPC clients reject the transactions whose signer shard is congested
+/// when the `tx_admission_congestion_threshold` is set.
+#[test]
+fn test_rpc_client_rejection_of_congested_signer_shard() {
+    if !ProtocolFeature::CongestionControl.enabled(PROTOCOL_VERSION) {
+        return;
+    }
+
+    let sender_id: AccountId = "test0".parse().unwrap();
+    let mut env = setup_test_runtime(sender_id.clone(), PROTOCOL_VERSION);
+
+    // prepare a contract to call
+    let mut nonce = 10;
+    setup_contract(&mut env, &mut nonce);
+
+    // Congest the shard of the contract, like in `test_rpc_client_rejection`.
+    let signer = InMemorySigner::test_signer(&sender_id);
+    submit_n_100tgas_fns(&mut env, 1_000, &mut nonce, &signer);
+    let tip = env.clients[0].chain.head().unwrap();
+    for i in 1..10 {
+        env.produce_block(0, tip.height + i);
+    }
+
+    // The contract signs transactions to a shard that is not congested, so that
+    // only the congestion of the signer shard matters. The nonce upper bound is
+    // not checked on submission.
+    let contract_id: AccountId = CONTRACT_ID.parse().unwrap();
+    let contract_signer = InMemorySigner::test_signer(&contract_id);
+    let receiver_id: AccountId = "test3".parse().unwrap();
+    let mut contract_nonce = 10u64.pow(15);
+    let block_hash = *env.clients[0].chain.head_header().unwrap().hash();
+
+    // Without the threshold, the transaction is not rejected because of its signer.
+    let fn_tx =
+        new_cheap_fn_call(&mut contract_nonce, &contract_signer, receiver_id.clone(), block_hash);
+    let response = env.clients[0].process_tx(fn_tx, false, false);
+    assert_matches!(response, ProcessTxResponse::ValidTx);
+
+    env.clients[0].config.tx_admission_congestion_threshold = Some(0.5);
+    let fn_tx = new_cheap_fn_call(&mut contract_nonce, &contract_signer, receiver_id, block_hash);
+    let response = env.clients[0].process_tx(fn_tx.clone(), false, false);
+    assert_matches!(
+        response,
+        ProcessTxResponse::SignerShardCongested {
+            error: InvalidTxError::ShardCongested { .. },
+            retry_after: Some(_),
+        }
+    );
+
+    // Forwarded transactions were already admitted by the node that received them.
+    let response = env.clients[0].process_tx(fn_tx, true, false);
+    assert_matches!(response, ProcessTxResponse::ValidTx);
+}
+
 /// Set up the test runtime with the given protocol version and runtime configs.
 /// The test version of runtime has custom gas cost.
 fn setup_test_runtime(_sender_id: AccountId, protocol_version: ProtocolVersion) -> TestEnv {
@@ -52,7 +52,8 @@ fn slow_test_in_memory_trie_node_consistency() {
         .add_user_accounts_simple(&accounts, initial_balance)
         .gas_limit_one_petagas()
         .transaction_validity_period(100)
-        .build();
+        .build()
+        .unwrap();
 
     // Create two stores, one for each node. We'll be reusing the stores later
     // to emulate node restarts.
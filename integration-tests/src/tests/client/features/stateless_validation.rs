@@ -400,6 +400,54 @@ fn test_chunk_state_witness_bad_shard_id() {
     assert_matches!(error, near_chain::Error::InvalidShardId(_));
 }
 
+/// Test that the validation of produced witnesses accepts the witnesses of the chunk producer
+/// and rejects a witness whose main state transition was tampered with.
+#[test]
+fn test_validate_produced_state_witness() {
+    init_integration_logger();
+
+    let accounts = vec!["test0".parse().unwrap()];
+    let genesis = Genesis::test(accounts.clone(), 1);
+    let mut env = TestEnv::builder(&genesis.config)
+        .validators(accounts)
+        .nightshade_runtimes(&genesis)
+        .build();
+
+    let mut witnesses = vec![];
+    for height in 1..6 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block, Provenance::PRODUCED);
+        while let Some(request) = env.partial_witness_adapters[0].pop_distribution_request() {
+            witnesses.push(request.state_witness);
+        }
+    }
+    assert!(!witnesses.is_empty(), "The chunk producer should have produced witnesses");
+
+    let client = &env.clients[0];
+    for witness in &witnesses {
+        client
+            .chain
+            .validate_produced_state_witness(
+                witness,
+                client.epoch_manager.as_ref(),
+                client.runtime_adapter.as_ref(),
+            )
+            .unwrap();
+    }
+
+    let mut invalid_witness = witnesses.pop().unwrap();
+    invalid_witness.main_state_transition.post_state_root = CryptoHash::hash_bytes(b"invalid");
+    let error = client
+        .chain
+        .validate_produced_state_witness(
+            &invalid_witness,
+            client.epoch_manager.as_ref(),
+            client.runtime_adapter.as_ref(),
+        )
+        .unwrap_err();
+    assert_matches!(error, near_chain::Error::InvalidChunkStateWitness(_));
+}
+
 /// Tests that eth-implicit accounts still work with stateless validation.
 #[test]
 fn test_eth_implicit_accounts() {
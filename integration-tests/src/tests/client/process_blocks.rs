@@ -45,7 +45,7 @@ use near_primitives::receipt::DelayedReceiptIndices;
 use near_primitives::shard_layout::{get_block_shard_uid, ShardUId};
 use near_primitives::sharding::{ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderV3};
 use near_primitives::state_part::PartId;
-use near_primitives::state_sync::StatePartKey;
+use near_primitives::state_sync::{ShardStateSyncResponseHeader, StatePartKey};
 use near_primitives::stateless_validation::chunk_endorsement::ChunkEndorsement;
 use near_primitives::stateless_validation::chunk_endorsements_bitmap::ChunkEndorsementsBitmap;
 use near_primitives::stateless_validation::ChunkProductionKey;
@@ -953,6 +953,7 @@ fn client_sync_headers() {
                             last_block: Some(BlockInfo { height: 5, hash: hash(&[5]) }),
                             tracked_shards: vec![],
                             archival: false,
+                            earliest_block_height: None,
                         },
                     },
                     received_bytes_per_sec: 0,
@@ -972,6 +973,7 @@ fn client_sync_headers() {
                     highest_block_hash: hash(&[5]),
                     tracked_shards: vec![],
                     archival: false,
+                    earliest_block_height: None,
                 }],
                 sent_bytes_per_sec: 0,
                 received_bytes_per_sec: 0,
@@ -2522,6 +2524,56 @@ fn slow_test_catchup_gas_price_change() {
     assert_eq!(chunk_extra_after_sync, expected_chunk_extra);
 }
 
+/// The batched proofs sent to peers that negotiated them must validate against the
+/// same blocks as the per-chunk proofs, and be rejected if any root is tampered with.
+#[test]
+fn test_set_state_header_with_batched_proofs() {
+    init_test_logger();
+    let epoch_length = 5;
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = epoch_length;
+
+    let mut env = TestEnv::builder(&genesis.config)
+        .clients_count(2)
+        .real_stores()
+        .nightshade_runtimes(&genesis)
+        .build();
+
+    let mut blocks = vec![];
+    for i in 1..=10 {
+        let block = env.clients[0].produce_block(i).unwrap().unwrap();
+        blocks.push(block.clone());
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        env.process_block(1, block, Provenance::NONE);
+    }
+
+    let sync_hash =
+        env.clients[0].chain.get_sync_hash(blocks.last().unwrap().hash()).unwrap().unwrap();
+    let epoch_id = *env.clients[0].chain.get_block_header(&sync_hash).unwrap().epoch_id();
+    let shard_layout = env.clients[0].epoch_manager.get_shard_layout(&epoch_id).unwrap();
+    let shard_id = shard_layout.shard_uids().next().unwrap().shard_id();
+
+    let ShardStateSyncResponseHeader::V2(header) =
+        env.clients[0].chain.get_state_response_header(shard_id, sync_hash).unwrap()
+    else {
+        panic!("expected a V2 state sync header");
+    };
+    let batched = env.clients[0].chain.batch_state_response_header_proofs(header).unwrap();
+    assert!(!batched.root_proofs.is_empty());
+
+    let mut tampered = batched.clone();
+    tampered.root_proofs[0].outgoing_receipts_roots[0] = CryptoHash::default();
+    assert!(env.clients[1]
+        .chain
+        .set_state_header(shard_id, sync_hash, ShardStateSyncResponseHeader::V3(tampered))
+        .is_err());
+
+    env.clients[1]
+        .chain
+        .set_state_header(shard_id, sync_hash, ShardStateSyncResponseHeader::V3(batched))
+        .unwrap();
+}
+
 #[test]
 fn test_block_execution_outcomes() {
     init_test_logger();
@@ -52,6 +52,7 @@ fn slow_test_state_dump() {
         restart_dump_for_shards: None,
         iteration_delay: Some(Duration::ZERO),
         credentials_file: None,
+        compress_parts: false,
     });
 
     let validator = MutableConfigValue::new(
@@ -163,6 +164,7 @@ fn run_state_sync_with_dumped_parts(
         restart_dump_for_shards: None,
         iteration_delay: Some(Duration::ZERO),
         credentials_file: None,
+        compress_parts: false,
     });
     let mut state_sync_dumper = StateSyncDumper {
         clock: Clock::real(),
@@ -339,6 +339,7 @@ fn ultra_slow_test_sync_state_dump() {
                 restart_dump_for_shards: None,
                 iteration_delay: Some(Duration::milliseconds(500)),
                 credentials_file: None,
+                compress_parts: false,
             });
             near1.config.store.state_snapshot_enabled = true;
 
@@ -748,7 +749,10 @@ fn slow_test_state_sync_headers() {
                 for shard_id in shard_ids {
                     // Make StateRequestHeader and expect that the response contains a header and `can_generate` is true.
                     let state_response_info = match view_client1
-                        .send(StateRequestHeader { shard_id, sync_hash }.with_span_context())
+                        .send(
+                            StateRequestHeader { shard_id, sync_hash, batched_proofs: false }
+                                .with_span_context(),
+                        )
                         .await
                     {
                         Ok(Some(StateResponse(state_response_info))) => Some(state_response_info),
@@ -933,7 +937,10 @@ fn slow_test_state_sync_headers_no_tracked_shards() {
                 for shard_id in shard_ids {
                     // Make StateRequestHeader and expect that the response contains a header and `can_generate` is true.
                     let state_response_info = match view_client2
-                        .send(StateRequestHeader { shard_id, sync_hash }.with_span_context())
+                        .send(
+                            StateRequestHeader { shard_id, sync_hash, batched_proofs: false }
+                                .with_span_context(),
+                        )
                         .await
                     {
                         Ok(Some(StateResponse(state_response_info))) => Some(state_response_info),
@@ -275,6 +275,7 @@ fn ultra_slow_test_query_rpc_account_view_must_succeed() {
                 request: near_primitives::views::QueryRequest::ViewAccount {
                     account_id: "near.0".parse().unwrap(),
                 },
+                decode: None,
             })
             .await
             .unwrap();
@@ -314,6 +315,7 @@ fn ultra_slow_test_query_rpc_account_view_account_doesnt_exist_must_return_error
                     request: near_primitives::views::QueryRequest::ViewAccount {
                         account_id: "accountdoesntexist.0".parse().unwrap(),
                     },
+                    decode: None,
                 })
                 .await;
 
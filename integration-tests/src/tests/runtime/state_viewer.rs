@@ -1,15 +1,18 @@
 use std::{collections::HashMap, io, sync::Arc};
 
+use assert_matches::assert_matches;
 use borsh::BorshDeserialize;
 
 use crate::runtime_utils::{get_runtime_and_trie, get_test_trie_viewer, TEST_SHARD_UID};
+use near_crypto::{KeyType, PublicKey};
+use near_parameters::RuntimeConfig;
 use near_primitives::{
-    account::Account,
+    account::{AccessKey, AccessKeyPermission, Account, FunctionCallPermission},
     hash::{hash as sha256, CryptoHash},
     serialize::to_base64,
     trie_key::trie_key_parsers,
     types::{AccountId, StateRoot},
-    views::StateItem,
+    views::{StateItem, StateOverride},
 };
 use near_primitives::{
     test_utils::MockEpochInfoProvider,
@@ -17,7 +20,9 @@ use near_primitives::{
     types::{EpochId, StateChangeCause},
     version::PROTOCOL_VERSION,
 };
-use near_store::{set_account, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, ShardUId};
+use near_store::{
+    set_access_key, set_account, NibbleSlice, RawTrieNode, RawTrieNodeWithSize, ShardUId,
+};
 use node_runtime::state_viewer::errors;
 use node_runtime::state_viewer::*;
 use testlib::runtime_utils::alice_account;
@@ -190,6 +195,120 @@ fn test_view_call_with_args() {
     assert_eq!(view_call_result.unwrap(), 3u64.to_le_bytes().to_vec());
 }
 
+fn simulation_limits() -> SimulationLimits {
+    SimulationLimits {
+        max_code_size: 1024 * 1024,
+        max_compile_time: std::time::Duration::from_secs(60),
+    }
+}
+
+#[test]
+fn test_view_call_with_state_override() {
+    let contract_id: AccountId = "test.contract".parse().unwrap();
+    let view_state = |cache| ViewApplyState {
+        block_height: 1,
+        prev_block_hash: CryptoHash::default(),
+        block_hash: CryptoHash::default(),
+        shard_id: ShardUId::single_shard().shard_id(),
+        epoch_id: EpochId::default(),
+        epoch_height: 0,
+        block_timestamp: 1,
+        current_protocol_version: PROTOCOL_VERSION,
+        cache,
+    };
+
+    // Storage and balance overrides are visible to the call.
+    let (viewer, mut root) = get_test_trie_viewer();
+    let viewer = viewer.with_simulation_limits(Some(simulation_limits()));
+    let key = 42u64.to_le_bytes();
+    let state_override = StateOverride {
+        balance: Some(1234),
+        code: None,
+        state: vec![StateItem {
+            key: key.to_vec().into(),
+            value: 7u64.to_le_bytes().to_vec().into(),
+        }],
+    };
+    let cache = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap();
+    assert!(cache.is_none());
+    let result = viewer.call_function(
+        root,
+        view_state(None),
+        &contract_id,
+        "read_value",
+        &key,
+        &mut vec![],
+        &MockEpochInfoProvider::default(),
+    );
+    assert_eq!(result.unwrap(), 7u64.to_le_bytes());
+
+    let (_, mut root) = get_test_trie_viewer();
+    viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap();
+    let result = viewer.call_function(
+        root,
+        view_state(None),
+        &contract_id,
+        "ext_account_balance",
+        &[],
+        &mut vec![],
+        &MockEpochInfoProvider::default(),
+    );
+    assert_eq!(result.unwrap(), 1234u128.to_le_bytes());
+
+    // The overridden code is compiled into a separate cache and executed instead of the
+    // deployed one.
+    let (_, mut root) = get_test_trie_viewer();
+    let state_override = StateOverride {
+        code: Some(near_test_contracts::trivial_contract().to_vec()),
+        ..Default::default()
+    };
+    let cache = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap();
+    assert!(cache.is_some());
+    let result = viewer.call_function(
+        root,
+        view_state(cache),
+        &contract_id,
+        "run_test",
+        &[],
+        &mut vec![],
+        &MockEpochInfoProvider::default(),
+    );
+    let err = result.unwrap_err();
+    assert!(err.to_string().contains("MethodNotFound"), "unexpected error: {err}");
+}
+
+#[test]
+fn test_view_call_with_state_override_limits() {
+    let contract_id: AccountId = "test.contract".parse().unwrap();
+    let code = near_test_contracts::rs_contract().to_vec();
+    let state_override = StateOverride { code: Some(code.clone()), ..Default::default() };
+
+    // Simulations are opt-in.
+    let (viewer, mut root) = get_test_trie_viewer();
+    let err = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap_err();
+    assert_matches!(err, errors::CallFunctionError::InvalidStateOverride { .. });
+
+    let viewer = get_test_trie_viewer().0.with_simulation_limits(Some(SimulationLimits {
+        max_code_size: code.len() - 1,
+        ..simulation_limits()
+    }));
+    let err = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap_err();
+    assert_matches!(err, errors::CallFunctionError::InvalidStateOverride { .. });
+
+    let viewer = get_test_trie_viewer().0.with_simulation_limits(Some(SimulationLimits {
+        max_compile_time: std::time::Duration::ZERO,
+        ..simulation_limits()
+    }));
+    let err = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap_err();
+    assert_matches!(err, errors::CallFunctionError::InvalidStateOverride { .. });
+
+    // Invalid code is rejected when compiling it.
+    let viewer = get_test_trie_viewer().0.with_simulation_limits(Some(simulation_limits()));
+    let state_override = StateOverride { code: Some(vec![1, 2, 3]), ..Default::default() };
+    let err = viewer.apply_state_override(&mut root, &contract_id, &state_override).unwrap_err();
+    assert_matches!(err, errors::CallFunctionError::VMError { .. });
+}
+
 fn assert_view_state(
     trie_viewer: &TrieViewer,
     state_update: &near_store::TrieUpdate,
@@ -385,6 +504,54 @@ fn test_view_state_with_large_contract() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_view_account_balance() {
+    let (_, tries, root) = get_runtime_and_trie();
+    let mut state_update = tries.new_trie_update(TEST_SHARD_UID, root);
+    let runtime_config = RuntimeConfig::test();
+    let storage_cost = 1_000 * runtime_config.storage_amount_per_byte();
+    let locked = storage_cost / 4;
+    let amount = 10 * storage_cost;
+    set_account(
+        &mut state_update,
+        alice_account(),
+        &Account::new(amount, locked, 0, CryptoHash::default(), 1_000, PROTOCOL_VERSION),
+    );
+    for (seed, allowance) in [("limited", Some(storage_cost)), ("unlimited", None)] {
+        let access_key = AccessKey {
+            nonce: 0,
+            permission: AccessKeyPermission::FunctionCall(FunctionCallPermission {
+                allowance,
+                receiver_id: "test.contract".to_string(),
+                method_names: vec![],
+            }),
+        };
+        let public_key = PublicKey::from_seed(KeyType::ED25519, seed);
+        set_access_key(&mut state_update, alice_account(), public_key, &access_key);
+    }
+
+    let trie_viewer = TrieViewer::default();
+    let balance = trie_viewer
+        .view_account_balance(&state_update, &alice_account(), &runtime_config, PROTOCOL_VERSION)
+        .unwrap();
+    // The locked stake covers part of the storage cost, the rest is reserved from the amount.
+    let storage_reserve = storage_cost - locked;
+    assert_eq!(balance.amount, amount);
+    assert_eq!(balance.locked, locked);
+    assert_eq!(balance.storage_reserve, storage_reserve);
+    assert_eq!(balance.liquid, amount - storage_reserve);
+    assert_eq!(balance.function_call_allowance, storage_cost);
+    assert_eq!(balance.unpledged_liquid, amount - storage_reserve - storage_cost);
+
+    let result = trie_viewer.view_account_balance(
+        &state_update,
+        &"missing.near".parse().unwrap(),
+        &runtime_config,
+        PROTOCOL_VERSION,
+    );
+    assert!(matches!(result, Err(errors::ViewAccountError::AccountDoesNotExist { .. })));
+}
+
 #[test]
 fn test_log_when_panic() {
     let (viewer, root) = get_test_trie_viewer();
@@ -54,7 +54,8 @@ impl RpcUser {
     }
 
     pub fn query(&self, request: QueryRequest) -> Result<RpcQueryResponse, String> {
-        let request = RpcQueryRequest { request, block_reference: BlockReference::latest() };
+        let request =
+            RpcQueryRequest { request, block_reference: BlockReference::latest(), decode: None };
         self.actix(move |client| client.query(request).map_err(|err| err.to_string()))
     }
 
@@ -208,6 +208,7 @@ impl RuntimeUser {
             migration_flags: MigrationFlags::default(),
             congestion_info,
             bandwidth_requests: BlockBandwidthRequests::empty(),
+            record_receiver_queue_changes: false,
         }
     }
 
@@ -0,0 +1,81 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+
+use near_chain::BlocksByTimestampBackfill;
+use near_chain_configs::ClientConfig;
+use near_store::background_migration::{
+    is_background_migration_done, run_background_migrations, BackgroundMigration,
+};
+use near_store::{NodeStorage, Store};
+
+/// Number of entries of the source column converted in each batch of a background migration.
+const BACKGROUND_MIGRATION_BATCH_SIZE: usize = 10_000;
+
+/// The migrations of the store run in the background, in order, see
+/// `near_store::background_migration`. A migration converting a column is removed from here by
+/// the DB version migration deleting its source column.
+fn background_migrations(
+    store: &Store,
+    client_config: &ClientConfig,
+) -> Vec<Box<dyn BackgroundMigration>> {
+    let mut migrations: Vec<Box<dyn BackgroundMigration>> = vec![];
+    if client_config.secondary_indexes.blocks_by_timestamp {
+        migrations.push(Box::new(BlocksByTimestampBackfill::new(store.clone())));
+    }
+    migrations
+}
+
+/// A handle that keeps the state of the background migrations thread and can be used to stop
+/// it.
+pub struct BackgroundMigrationsHandle {
+    join_handle: std::thread::JoinHandle<()>,
+    keep_going: Arc<AtomicBool>,
+}
+
+impl BackgroundMigrationsHandle {
+    pub fn stop(self) {
+        self.keep_going.store(false, std::sync::atomic::Ordering::Relaxed);
+        match self.join_handle.join() {
+            Ok(_) => {
+                tracing::debug!(target: "migrations", "Joined the background migrations thread");
+            }
+            Err(_) => {
+                tracing::error!(target: "migrations", "Failed to join the background migrations thread");
+            }
+        }
+    }
+}
+
+/// Spawns a thread running the background migrations of the hot store which are not done yet.
+/// Returns `None` if they are all done.
+pub fn spawn_background_migrations(
+    storage: &NodeStorage,
+    client_config: &ClientConfig,
+) -> anyhow::Result<Option<BackgroundMigrationsHandle>> {
+    let store = storage.get_hot_store();
+    let mut pending_migrations = vec![];
+    for migration in background_migrations(&store, client_config) {
+        if !is_background_migration_done(&store, migration.name())? {
+            pending_migrations.push(migration);
+        }
+    }
+    if pending_migrations.is_empty() {
+        return Ok(None);
+    }
+
+    let keep_going = Arc::new(AtomicBool::new(true));
+    let keep_going_clone = keep_going.clone();
+    tracing::info!(target: "migrations", num_migrations = pending_migrations.len(), "Spawning the background migrations");
+    let join_handle =
+        std::thread::Builder::new().name("background_migrations".to_string()).spawn(move || {
+            if let Err(err) = run_background_migrations(
+                &store,
+                &pending_migrations,
+                BACKGROUND_MIGRATION_BATCH_SIZE,
+                &keep_going_clone,
+            ) {
+                tracing::error!(target: "migrations", ?err, "Background migrations failed");
+            }
+        })?;
+    Ok(Some(BackgroundMigrationsHandle { join_handle, keep_going }))
+}
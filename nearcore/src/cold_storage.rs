@@ -416,6 +416,34 @@ fn cold_store_loop(
     }
 }
 
+/// Runs one iteration of the cold store loop in the calling thread: the initial migration if the
+/// cold storage isn't populated yet, then the copy of all the blocks finalized since the cold
+/// head. Used by the tests which can't run the loop in a thread, e.g. the test loop tests.
+pub fn update_cold_store(
+    split_storage_config: &SplitStorageConfig,
+    hot_store: &Store,
+    cold_db: &Arc<ColdDB>,
+    genesis_height: BlockHeight,
+    epoch_manager: &EpochManagerHandle,
+) -> anyhow::Result<()> {
+    let keep_going = Arc::new(AtomicBool::new(true));
+    cold_store_migration(
+        split_storage_config,
+        &keep_going,
+        genesis_height,
+        hot_store,
+        cold_db.clone(),
+    )?;
+    while let ColdStoreCopyResult::OtherBlockCopied = cold_store_copy(
+        hot_store,
+        cold_db.as_ref(),
+        genesis_height,
+        epoch_manager,
+        split_storage_config.num_cold_store_read_threads,
+    )? {}
+    Ok(())
+}
+
 /// Spawns the cold store loop in a background thread and returns ColdStoreLoopHandle.
 /// If cold store is not configured it does nothing and returns None.
 /// The cold store loop is spawned in a rust native thread because it's quite heavy
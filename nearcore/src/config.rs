@@ -20,13 +20,15 @@ use near_chain_configs::{
     default_sync_max_block_requests, default_sync_step_period, default_transaction_pool_size_limit,
     default_trie_viewer_state_size_limit, default_tx_routing_height_horizon,
     default_view_client_threads, default_view_client_throttle_period, get_initial_supply,
-    ChunkDistributionNetworkConfig, ClientConfig, EpochSyncConfig, GCConfig, Genesis,
-    GenesisConfig, GenesisValidationMode, LogSummaryStyle, MutableConfigValue,
-    MutableValidatorSigner, ReshardingConfig, StateSyncConfig, BLOCK_PRODUCER_KICKOUT_THRESHOLD,
-    CHUNK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_VALIDATOR_ONLY_KICKOUT_THRESHOLD,
-    EXPECTED_EPOCH_LENGTH, FAST_EPOCH_LENGTH, FISHERMEN_THRESHOLD, GAS_PRICE_ADJUSTMENT_RATE,
-    GENESIS_CONFIG_FILENAME, INITIAL_GAS_LIMIT, MAX_INFLATION_RATE, MIN_BLOCK_PRODUCTION_DELAY,
-    MIN_GAS_PRICE, NEAR_BASE, NUM_BLOCKS_PER_YEAR, NUM_BLOCK_PRODUCER_SEATS, PROTOCOL_REWARD_RATE,
+    AdaptiveChunkTimeLimitConfig, ChunkDistributionNetworkConfig, ClientConfig, EpochSyncConfig,
+    GCConfig, Genesis, GenesisConfig, GenesisValidationMode, LogSummaryStyle, MutableConfigValue,
+    MutableValidatorSigner, ReshardingConfig, SecondaryIndexesConfig, SimulateFunctionCallConfig,
+    StateSyncConfig, ThreadPoolsConfig, TrackedShardsConfig, WitnessValidationLimitsConfig,
+    BLOCK_PRODUCER_KICKOUT_THRESHOLD, CHUNK_PRODUCER_KICKOUT_THRESHOLD,
+    CHUNK_VALIDATOR_ONLY_KICKOUT_THRESHOLD, EXPECTED_EPOCH_LENGTH, FAST_EPOCH_LENGTH,
+    FISHERMEN_THRESHOLD, GAS_PRICE_ADJUSTMENT_RATE, GENESIS_CONFIG_FILENAME, INITIAL_GAS_LIMIT,
+    MAX_INFLATION_RATE, MIN_BLOCK_PRODUCTION_DELAY, MIN_GAS_PRICE, MIN_GC_NUM_EPOCHS_TO_KEEP,
+    NEAR_BASE, NUM_BLOCKS_PER_YEAR, NUM_BLOCK_PRODUCER_SEATS, PROTOCOL_REWARD_RATE,
     PROTOCOL_UPGRADE_STAKE_THRESHOLD, TRANSACTION_VALIDITY_PERIOD,
 };
 use near_config_utils::{DownloadConfigType, ValidationError, ValidationErrors};
@@ -52,9 +54,11 @@ use near_rosetta_rpc::RosettaRpcConfig;
 use near_store::config::{
     ArchivalConfig, ArchivalStoreConfig, SplitStorageConfig, StateSnapshotType,
 };
+use near_store::encryption::StoreCipher;
 use near_store::{StateSnapshotConfig, Store, TrieConfig};
 use near_telemetry::TelemetryConfig;
 use near_vm_runner::{ContractRuntimeCache, FilesystemContractRuntimeCache};
+use node_runtime::state_viewer::SimulationLimits;
 use num_rational::Rational32;
 use std::fs;
 use std::fs::File;
@@ -244,6 +248,18 @@ pub struct Config {
     pub tracked_shards: Vec<ShardId>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tracked_shard_schedule: Option<Vec<Vec<ShardId>>>,
+    /// Expression selecting the tracked shards, e.g. `"shards: [0, 2]"` or
+    /// `"validator-assignments+next"`, see [TrackedShardsConfig]. Replaces the `tracked_*`
+    /// options above, which can't be set together with it. Can be changed while the node is
+    /// running, the change applies from the epoch after the next one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tracked_shards_config: Option<TrackedShardsConfig>,
+    /// Run the node as a chunk validator only, which tracks no shard and only validates the
+    /// state witnesses it receives, see [Config::set_chunk_validator_only] for the preset of the
+    /// other options. A chunk validator only node doesn't need the hardware to hold the State
+    /// of a shard, but can't produce chunks.
+    #[serde(skip_serializing_if = "is_false")]
+    pub chunk_validator_only: bool,
     #[serde(skip_serializing_if = "is_false")]
     pub archive: bool,
     /// If save_trie_changes is not set it will get inferred from the `archive` field as follows:
@@ -269,6 +285,9 @@ pub struct Config {
     /// If set, overrides value in genesis configuration.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub max_gas_burnt_view: Option<Gas>,
+    /// Enables the `simulate_function_call` query with the given limits.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub simulate_function_call: Option<SimulateFunctionCallConfig>,
     /// Different parameters to configure underlying storage.
     pub store: near_store::StoreConfig,
     /// Different parameters to configure underlying cold storage.
@@ -339,11 +358,68 @@ pub struct Config {
     /// which can cause extra load on the database. This option is not recommended for production use,
     /// as a large number of incoming witnesses could cause denial of service.
     pub save_latest_witnesses: bool,
+    /// Before distributing a produced state witness, run the same validation on it that chunk
+    /// validators would run, and don't distribute the witness if the validation fails.
+    /// Useful for debugging chunk validation failures; doubles the cost of validating
+    /// produced chunks.
+    #[serde(skip_serializing_if = "is_false")]
+    pub validate_produced_witnesses: bool,
+    /// Maintain an index of the receipts executed and created by every account, which is
+    /// served by the `EXPERIMENTAL_account_receipts` RPC. Only the tracked shards are indexed.
+    #[serde(skip_serializing_if = "is_false")]
+    pub save_account_receipts: bool,
+    /// Secondary indexes of transactions and blocks to maintain, which are
+    /// served by the `EXPERIMENTAL_secondary_index` RPC. All disabled by default.
+    #[serde(skip_serializing_if = "is_default")]
+    pub secondary_indexes: SecondaryIndexesConfig,
+    /// Record the height of the last block in which every access key signed a transaction,
+    /// which is returned by `view_access_key` queries to help identifying stale keys. Only the
+    /// tracked shards are covered.
+    #[serde(skip_serializing_if = "is_false")]
+    pub save_access_key_usage: bool,
+    /// Shards for which the state transition data, used by chunk producers to generate the
+    /// state witnesses, isn't generated nor saved. It is meant for chunk producers tracking
+    /// more shards than the ones they produce chunks for, e.g. with `tracked_shards_config`
+    /// set to all the shards: the data is kept anyway while the node produces the chunks of
+    /// the shard in the current or the next epoch, so that the setting is safe for them. The
+    /// nodes which don't produce chunks in the current or the next epoch, like RPC nodes,
+    /// never generate nor save the data, with or without this setting.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub skip_state_transition_data_for_shards: Vec<ShardId>,
+    /// Reject the transactions submitted to this node when the shard of their signer is at or
+    /// above this congestion level, between 0 and 1, with an error suggesting when to retry.
+    /// Without it, such transactions are routed anyway and often time out. Disabled by default.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tx_admission_congestion_threshold: Option<f64>,
+    /// Adapt the time limit of adding transactions to produced chunks to the recent apply
+    /// durations of each shard, to reduce the chunks missed on shards with volatile load.
+    /// Disabled by default, in which case `produce_chunk_add_transactions_time_limit` is used.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub produce_chunk_adaptive_time_limit: Option<AdaptiveChunkTimeLimitConfig>,
+    /// Skip producing the chunks of quiet shards, without transactions or receipts, once the
+    /// protocol allows it. The node sends a signed suppression to the block producer instead,
+    /// and the blocks mark such chunks as suppressed, so they don't count as missed. Disabled by
+    /// default.
+    #[serde(skip_serializing_if = "is_false")]
+    pub suppress_empty_chunks: bool,
+    /// Sizing and CPU pinning of the thread pools used for chunk application, witness
+    /// validation, networking and RPC. The threads pinned so far are listed by the
+    /// `/debug/api/thread_pools` endpoint.
+    #[serde(skip_serializing_if = "is_default")]
+    pub thread_pools: ThreadPoolsConfig,
+    /// Limits of the concurrent validations of chunk state witnesses, by shard and in total,
+    /// which keep the shards with big witnesses from delaying the endorsements of the others.
+    #[serde(skip_serializing_if = "is_default")]
+    pub witness_validation_limits: WitnessValidationLimitsConfig,
 }
 
 fn is_false(value: &bool) -> bool {
     !*value
 }
+
+fn is_default<T: Default + PartialEq>(value: &T) -> bool {
+    *value == T::default()
+}
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -362,6 +438,8 @@ impl Default for Config {
             tracked_shadow_validator: None,
             tracked_shards: vec![],
             tracked_shard_schedule: None,
+            tracked_shards_config: None,
+            chunk_validator_only: false,
             archive: false,
             save_trie_changes: None,
             log_summary_style: LogSummaryStyle::Colored,
@@ -371,6 +449,7 @@ impl Default for Config {
             view_client_throttle_period: default_view_client_throttle_period(),
             trie_viewer_state_size_limit: default_trie_viewer_state_size_limit(),
             max_gas_burnt_view: None,
+            simulate_function_call: None,
             store: near_store::StoreConfig::default(),
             cold_store: None,
             split_storage: None,
@@ -390,6 +469,16 @@ impl Default for Config {
             orphan_state_witness_max_size: default_orphan_state_witness_max_size(),
             max_loaded_contracts: 256,
             save_latest_witnesses: false,
+            validate_produced_witnesses: false,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
+            skip_state_transition_data_for_shards: vec![],
+            tx_admission_congestion_threshold: None,
+            produce_chunk_adaptive_time_limit: None,
+            suppress_empty_chunks: false,
+            thread_pools: ThreadPoolsConfig::default(),
+            witness_validation_limits: WitnessValidationLimitsConfig::default(),
         }
     }
 }
@@ -473,6 +562,37 @@ impl Config {
             self.split_storage.as_ref(),
         )
     }
+
+    /// Presets the options of a node running as a chunk validator only: it tracks no shard,
+    /// loads no memtrie, keeps the minimum number of epochs, has no cold store, doesn't dump
+    /// the state nor create state snapshots and doesn't maintain the optional indexes, so that
+    /// the store only holds the blocks, chunks and epoch data needed to validate the state
+    /// witnesses.
+    pub fn set_chunk_validator_only(&mut self) {
+        self.chunk_validator_only = true;
+        self.tracked_accounts.clear();
+        self.tracked_shadow_validator = None;
+        self.tracked_shards.clear();
+        self.tracked_shard_schedule = None;
+        self.tracked_shards_config = Some(TrackedShardsConfig::NoShards);
+        self.archive = false;
+        self.cold_store = None;
+        self.split_storage = None;
+        self.archival_storage = None;
+        if let Some(state_sync) = &mut self.state_sync {
+            state_sync.dump = None;
+        }
+        self.gc.gc_num_epochs_to_keep = MIN_GC_NUM_EPOCHS_TO_KEEP;
+        self.store.load_mem_tries_for_shards.clear();
+        self.store.load_mem_tries_for_tracked_shards = false;
+        self.store.state_snapshot_enabled = false;
+        self.store.state_snapshot_config.state_snapshot_type = StateSnapshotType::ForReshardingOnly;
+        self.save_latest_witnesses = false;
+        self.save_account_receipts = false;
+        self.save_access_key_usage = false;
+        self.secondary_indexes =
+            SecondaryIndexesConfig { transactions_by_account: false, blocks_by_timestamp: false };
+    }
 }
 
 #[derive(Clone)]
@@ -544,6 +664,8 @@ impl NearConfig {
                 tracked_shards: config.tracked_shards,
                 tracked_shadow_validator: config.tracked_shadow_validator,
                 tracked_shard_schedule: config.tracked_shard_schedule.unwrap_or(vec![]),
+                tracked_shards_config: config.tracked_shards_config,
+                chunk_validator_only: config.chunk_validator_only,
                 archive: config.archive,
                 save_trie_changes: config.save_trie_changes.unwrap_or(!config.archive),
                 log_summary_style: config.log_summary_style,
@@ -552,6 +674,7 @@ impl NearConfig {
                 view_client_throttle_period: config.view_client_throttle_period,
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
                 max_gas_burnt_view: config.max_gas_burnt_view,
+                simulate_function_call: config.simulate_function_call,
                 enable_statistics_export: config.store.enable_statistics_export,
                 client_background_migration_threads: 8,
                 state_sync_enabled: config.state_sync_enabled,
@@ -572,13 +695,28 @@ impl NearConfig {
                 orphan_state_witness_pool_size: config.orphan_state_witness_pool_size,
                 orphan_state_witness_max_size: config.orphan_state_witness_max_size,
                 save_latest_witnesses: config.save_latest_witnesses,
+                validate_produced_witnesses: config.validate_produced_witnesses,
+                save_account_receipts: config.save_account_receipts,
+                secondary_indexes: config.secondary_indexes,
+                save_access_key_usage: config.save_access_key_usage,
+                skip_state_transition_data_for_shards: config
+                    .skip_state_transition_data_for_shards
+                    .clone(),
+                tx_admission_congestion_threshold: config.tx_admission_congestion_threshold,
+                produce_chunk_adaptive_time_limit: config.produce_chunk_adaptive_time_limit,
+                suppress_empty_chunks: config.suppress_empty_chunks,
+                thread_pools: config.thread_pools.clone(),
+                witness_validation_limits: config.witness_validation_limits,
+            },
+            network_config: NetworkConfig {
+                cpu_set: config.thread_pools.network.cpu_set,
+                ..NetworkConfig::new(
+                    config.network,
+                    network_key_pair.secret_key,
+                    validator_signer.clone(),
+                    config.archive,
+                )?
             },
-            network_config: NetworkConfig::new(
-                config.network,
-                network_key_pair.secret_key,
-                validator_signer.clone(),
-                config.archive,
-            )?,
             telemetry_config: config.telemetry,
             #[cfg(feature = "json_rpc")]
             rpc_config: config.rpc,
@@ -664,6 +802,10 @@ impl NightshadeRuntime {
             epoch_manager,
             config.client_config.trie_viewer_state_size_limit,
             config.client_config.max_gas_burnt_view,
+            config.client_config.simulate_function_call.map(|config| SimulationLimits {
+                max_code_size: config.max_code_size,
+                max_compile_time: config.max_compile_time.unsigned_abs(),
+            }),
             None,
             config.config.gc.gc_num_epochs_to_keep(),
             TrieConfig::from_store_config(&config.config.store),
@@ -790,6 +932,7 @@ pub fn init_configs(
     download_config_url: Option<&str>,
     boot_nodes: Option<&str>,
     max_gas_burnt_view: Option<Gas>,
+    chunk_validator_only: bool,
 ) -> anyhow::Result<()> {
     fs::create_dir_all(dir).with_context(|| anyhow!("Failed to create directory {:?}", dir))?;
 
@@ -843,6 +986,10 @@ pub fn init_configs(
         config.max_gas_burnt_view = Some(max_gas_burnt_view);
     }
 
+    if chunk_validator_only {
+        config.set_chunk_validator_only();
+    }
+
     // Before finalizing the Config and Genesis, make sure the node and validator keys exist.
     generate_or_load_keys(dir, &config, &chain_id, account_id, test_seed)?;
     match chain_id.as_ref() {
@@ -1361,12 +1508,43 @@ impl From<NodeKeyFile> for KeyFile {
     }
 }
 
-pub fn load_validator_key(validator_file: &Path) -> anyhow::Result<Option<Arc<ValidatorSigner>>> {
+/// Returns the cipher used to decrypt the validator key file, if the store is configured to
+/// encrypt it.
+pub fn validator_key_cipher(
+    home_dir: &Path,
+    config: &Config,
+) -> anyhow::Result<Option<StoreCipher>> {
+    match &config.store.encryption {
+        Some(encryption) if encryption.encrypt_validator_key => {
+            Ok(Some(encryption.load_cipher(home_dir).context("failed to load encryption key")?))
+        }
+        _ => Ok(None),
+    }
+}
+
+fn read_key_file(validator_file: &Path, cipher: Option<&StoreCipher>) -> anyhow::Result<KeyFile> {
+    let Some(cipher) = cipher else {
+        return Ok(KeyFile::from_file(validator_file)?);
+    };
+    let contents = cipher.decrypt_validator_key(&fs::read(validator_file)?)?;
+    let contents = near_config_utils::strip_comments_from_json_str(&String::from_utf8(contents)?)?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+pub fn load_validator_key(
+    validator_file: &Path,
+    cipher: Option<&StoreCipher>,
+) -> anyhow::Result<Option<Arc<ValidatorSigner>>> {
     if !validator_file.exists() {
         return Ok(None);
     }
-    match InMemoryValidatorSigner::from_file(&validator_file) {
-        Ok(signer) => Ok(Some(Arc::new(signer))),
+    match read_key_file(validator_file, cipher) {
+        Ok(key_file) => {
+            // Key material must never appear in the logs, whatever the log level.
+            near_o11y::redaction::add_sensitive_value(key_file.secret_key.to_string());
+            let signer = InMemorySigner::from(key_file);
+            Ok(Some(Arc::new(InMemoryValidatorSigner::from_signer(Signer::InMemory(signer)))))
+        }
         Err(_) => {
             let error_message =
                 format!("Failed initializing validator signer from {}", validator_file.display());
@@ -1389,7 +1567,9 @@ pub fn load_config(
     };
 
     let validator_file: PathBuf = dir.join(&config.validator_key_file);
-    let validator_signer = match load_validator_key(&validator_file) {
+    let validator_signer = match validator_key_cipher(dir, &config)
+        .and_then(|cipher| load_validator_key(&validator_file, cipher.as_ref()))
+    {
         Ok(validator_signer) => validator_signer,
         Err(e) => {
             validation_errors.push_validator_key_file_error(e.to_string());
@@ -1400,7 +1580,10 @@ pub fn load_config(
     let node_key_path = dir.join(&config.node_key_file);
     let network_signer_result = NodeKeyFile::from_file(&node_key_path);
     let network_signer = match network_signer_result {
-        Ok(node_key_file) => Some(node_key_file),
+        Ok(node_key_file) => {
+            near_o11y::redaction::add_sensitive_value(node_key_file.secret_key.to_string());
+            Some(node_key_file)
+        }
         Err(_) => {
             let error_message =
                 format!("Failed reading node key file from {}", node_key_path.display());
@@ -1481,7 +1664,9 @@ mod tests {
 
     use itertools::Itertools;
     use near_async::time::Duration;
-    use near_chain_configs::{GCConfig, Genesis, GenesisValidationMode};
+    use near_chain_configs::{
+        GCConfig, Genesis, GenesisValidationMode, TrackedShardsConfig, MIN_GC_NUM_EPOCHS_TO_KEEP,
+    };
     use near_crypto::InMemorySigner;
     use near_primitives::types::{AccountId, NumShards, ShardId};
     use tempfile::tempdir;
@@ -1509,6 +1694,7 @@ mod tests {
             None,
             None,
             None,
+            false,
         )
         .unwrap();
         let genesis = Genesis::from_file(
@@ -1566,6 +1752,7 @@ mod tests {
             None,
             None,
             None,
+            false,
         )
         .unwrap();
 
@@ -1599,6 +1786,7 @@ mod tests {
             None,
             None,
             None,
+            false,
         )
         .unwrap();
 
@@ -1607,6 +1795,37 @@ mod tests {
         let _validator_signer = InMemorySigner::from_file(&validator_key_file).unwrap();
     }
 
+    #[test]
+    fn test_init_config_chunk_validator_only() {
+        let temp_dir = tempdir().unwrap();
+        init_configs(
+            &temp_dir.path(),
+            Some("localnet".to_string()),
+            Some(AccountId::from_str("account.near").unwrap()),
+            Some("seed1"),
+            3,
+            false,
+            None,
+            false,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            true,
+        )
+        .unwrap();
+
+        let config = Config::from_file(&temp_dir.path().join(CONFIG_FILENAME)).unwrap();
+        assert!(config.chunk_validator_only);
+        assert!(config.tracked_shards.is_empty());
+        assert_eq!(config.tracked_shards_config, Some(TrackedShardsConfig::NoShards));
+        assert!(!config.store.load_mem_tries_for_tracked_shards);
+        assert!(!config.store.state_snapshot_enabled);
+        assert_eq!(config.gc.gc_num_epochs_to_keep, MIN_GC_NUM_EPOCHS_TO_KEEP);
+    }
+
     /// Tests that loading a config.json file works and results in values being
     /// correctly parsed and defaults being applied correctly applied.
     /// We skip config validation since we only care about Config being correctly loaded from file.
@@ -1633,6 +1852,7 @@ mod tests {
                     gc_fork_clean_step: 420,
                     gc_num_epochs_to_keep: 24,
                     gc_step_period: Duration::seconds(1),
+                    epoch_data_num_epochs_to_keep: None,
                 }
             } else {
                 GCConfig {
@@ -1640,6 +1860,7 @@ mod tests {
                     gc_fork_clean_step: 100,
                     gc_num_epochs_to_keep: 5,
                     gc_step_period: Duration::seconds(1),
+                    epoch_data_num_epochs_to_keep: None,
                 }
             };
             assert_eq!(want_gc, config.gc);
@@ -1,6 +1,7 @@
 use std::str::FromStr;
 
 use crate::config::Config;
+use near_chain_configs::TrackedShardsConfig;
 use near_jsonrpc::RpcConfig;
 use near_network::config_json::{ExperimentalConfig, NetworkConfigOverrides};
 use near_o11y::testonly::init_test_logger;
@@ -34,7 +35,9 @@ fn test_config_duration_all_std() {
         expected_shutdown: Some(Default::default()),
         genesis_records_file: Some(Default::default()),
         max_gas_burnt_view: Some(Default::default()),
+        simulate_function_call: Some(Default::default()),
         produce_chunk_add_transactions_time_limit: Some(Default::default()),
+        produce_chunk_adaptive_time_limit: Some(Default::default()),
         rpc: Some(RpcConfig {
             experimental_debug_pages_src_path: Some(Default::default()),
             prometheus_addr: Some(Default::default()),
@@ -45,6 +48,7 @@ fn test_config_duration_all_std() {
         split_storage: Some(Default::default()),
         tracked_shadow_validator: Some(AccountId::from_str("test").unwrap()),
         tracked_shard_schedule: Some(Default::default()),
+        tracked_shards_config: Some(TrackedShardsConfig::AllShards),
         transaction_pool_size_limit: Some(Default::default()),
         state_sync: Some(Default::default()),
         trie_viewer_state_size_limit: Some(Default::default()),
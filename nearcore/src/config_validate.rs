@@ -1,4 +1,6 @@
-use near_chain_configs::{ExternalStorageLocation, SyncConfig};
+use near_chain_configs::{
+    ExternalStorageLocation, StateSyncProvider, SyncConfig, TrackedShardsConfig,
+};
 use near_config_utils::{ValidationError, ValidationErrors};
 use std::collections::HashSet;
 use std::path::Path;
@@ -89,25 +91,13 @@ impl<'a> ConfigValidator<'a> {
                     }
                 }
 
-                match &dump_config.location {
-                    ExternalStorageLocation::S3 { bucket, region } => {
-                        if bucket.is_empty() || region.is_empty() {
-                            let error_message = format!("'config.state_sync.dump.location.S3.bucket' and 'config.state_sync.dump.location.S3.region' need to be specified when 'config.state_sync.dump.location.S3' is present.");
-                            self.validation_errors.push_config_semantics_error(error_message);
-                        }
-                    }
-                    ExternalStorageLocation::Filesystem { root_dir } => {
-                        if root_dir.as_path() == Path::new("") {
-                            let error_message = format!("'config.state_sync.dump.location.Filesystem.root_dir' needs to be specified when 'config.state_sync.dump.location.Filesystem' is present.");
-                            self.validation_errors.push_config_semantics_error(error_message);
-                        }
-                    }
-                    ExternalStorageLocation::GCS { bucket } => {
-                        if bucket.is_empty() {
-                            let error_message = format!("'config.state_sync.dump.location.GCS.bucket' needs to be specified when 'config.state_sync.dump.location.GCS' is present.");
-                            self.validation_errors.push_config_semantics_error(error_message);
-                        }
-                    }
+                self.validate_external_storage_location(
+                    &dump_config.location,
+                    "config.state_sync.dump.location",
+                );
+                if let ExternalStorageLocation::HttpMirrors { .. } = &dump_config.location {
+                    let error_message = format!("'config.state_sync.dump.location.HttpMirrors' is read-only and can't be used to dump state.");
+                    self.validation_errors.push_config_semantics_error(error_message);
                 }
 
                 if let Some(credentials_file) = &dump_config.credentials_file {
@@ -120,28 +110,38 @@ impl<'a> ConfigValidator<'a> {
             match &state_sync.sync {
                 SyncConfig::Peers => {}
                 SyncConfig::ExternalStorage(config) => {
-                    match &config.location {
-                        ExternalStorageLocation::S3 { bucket, region } => {
-                            if bucket.is_empty() || region.is_empty() {
-                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.S3.bucket' and 'config.state_sync.sync.ExternalStorage.location.S3.region' need to be specified when 'config.state_sync.sync.ExternalStorage.location.S3' is present.");
-                                self.validation_errors.push_config_semantics_error(error_message);
-                            }
-                        }
-                        ExternalStorageLocation::Filesystem { root_dir } => {
-                            if root_dir.as_path() == Path::new("") {
-                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.Filesystem.root_dir' needs to be specified when 'config.state_sync.sync.ExternalStorage.location.Filesystem' is present.");
-                                self.validation_errors.push_config_semantics_error(error_message);
-                            }
+                    self.validate_external_storage_location(
+                        &config.location,
+                        "config.state_sync.sync.ExternalStorage.location",
+                    );
+                    if config.num_concurrent_requests == 0 {
+                        let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
+                }
+                SyncConfig::Providers(config) => {
+                    if config.providers.is_empty() {
+                        let error_message = format!("'config.state_sync.sync.Providers.providers' needs to contain at least one provider.");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
+                    for (i, provider_config) in config.providers.iter().enumerate() {
+                        if let StateSyncProvider::External(location) = &provider_config.provider {
+                            self.validate_external_storage_location(
+                                location,
+                                &format!("config.state_sync.sync.Providers.providers[{i}].provider.External"),
+                            );
                         }
-                        ExternalStorageLocation::GCS { bucket } => {
-                            if bucket.is_empty() {
-                                let error_message = format!("'config.state_sync.sync.ExternalStorage.location.GCS.bucket' needs to be specified when 'config.state_sync.sync.ExternalStorage.location.GCS' is present.");
-                                self.validation_errors.push_config_semantics_error(error_message);
-                            }
+                        if provider_config.num_attempts == 0 {
+                            let error_message = format!("'config.state_sync.sync.Providers.providers[{i}].num_attempts' needs to be greater than 0");
+                            self.validation_errors.push_config_semantics_error(error_message);
                         }
                     }
                     if config.num_concurrent_requests == 0 {
-                        let error_message = format!("'config.state_sync.sync.ExternalStorage.num_concurrent_requests' needs to be greater than 0");
+                        let error_message = format!("'config.state_sync.sync.Providers.num_concurrent_requests' needs to be greater than 0");
+                        self.validation_errors.push_config_semantics_error(error_message);
+                    }
+                    if config.max_consecutive_failures == 0 {
+                        let error_message = format!("'config.state_sync.sync.Providers.max_consecutive_failures' needs to be greater than 0");
                         self.validation_errors.push_config_semantics_error(error_message);
                     }
                 }
@@ -157,6 +157,134 @@ impl<'a> ConfigValidator<'a> {
             let error_message = format!("'config.tx_routing_height_horizon' can't be too high to avoid spamming the network. Keep it below 100. Got {tx_routing_height_horizon}.");
             self.validation_errors.push_config_semantics_error(error_message);
         }
+
+        let thread_pools = &self.config.thread_pools;
+        for (name, thread_pool) in [
+            ("chunk_application", &thread_pools.chunk_application),
+            ("witness_validation", &thread_pools.witness_validation),
+            ("network", &thread_pools.network),
+            ("rpc", &thread_pools.rpc),
+        ] {
+            if thread_pool.num_threads == Some(0) {
+                let error_message =
+                    format!("'config.thread_pools.{name}.num_threads' should be greater than 0.");
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            if thread_pool.cpu_set.as_ref().is_some_and(|cpu_set| cpu_set.is_empty()) {
+                let error_message =
+                    format!("'config.thread_pools.{name}.cpu_set' should not be empty.");
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+        if thread_pools.network.num_threads.is_some() {
+            let error_message = "'config.thread_pools.network.num_threads' is not supported, the network runtimes are single-threaded.".to_string();
+            self.validation_errors.push_config_semantics_error(error_message);
+        }
+
+        let witness_validation_limits = &self.config.witness_validation_limits;
+        for (name, limit) in [
+            ("max_concurrent_per_shard", witness_validation_limits.max_concurrent_per_shard),
+            ("max_concurrent", witness_validation_limits.max_concurrent),
+        ] {
+            if limit == 0 {
+                let error_message =
+                    format!("'config.witness_validation_limits.{name}' should be greater than 0.");
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+        }
+
+        if self.config.tracked_shards_config.is_some() {
+            let legacy_options = [
+                ("tracked_shards", !self.config.tracked_shards.is_empty()),
+                ("tracked_accounts", !self.config.tracked_accounts.is_empty()),
+                ("tracked_shadow_validator", self.config.tracked_shadow_validator.is_some()),
+                (
+                    "tracked_shard_schedule",
+                    self.config.tracked_shard_schedule.as_ref().is_some_and(|s| !s.is_empty()),
+                ),
+            ];
+            for (name, is_set) in legacy_options {
+                if is_set {
+                    let error_message = format!("'config.{name}' can't be set together with 'config.tracked_shards_config', express it in 'config.tracked_shards_config' instead.");
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
+
+        if self.config.chunk_validator_only {
+            if self.config.archive {
+                let error_message =
+                    "'config.chunk_validator_only' can't be set together with 'config.archive'."
+                        .to_string();
+                self.validation_errors.push_config_semantics_error(error_message);
+            }
+            let tracking_options = [
+                ("tracked_shards", !self.config.tracked_shards.is_empty()),
+                ("tracked_accounts", !self.config.tracked_accounts.is_empty()),
+                ("tracked_shadow_validator", self.config.tracked_shadow_validator.is_some()),
+                (
+                    "tracked_shard_schedule",
+                    self.config.tracked_shard_schedule.as_ref().is_some_and(|s| !s.is_empty()),
+                ),
+                (
+                    "tracked_shards_config",
+                    self.config
+                        .tracked_shards_config
+                        .as_ref()
+                        .is_some_and(|c| *c != TrackedShardsConfig::NoShards),
+                ),
+                (
+                    "store.load_mem_tries_for_shards",
+                    !self.config.store.load_mem_tries_for_shards.is_empty(),
+                ),
+                ("cold_store", self.config.cold_store.is_some()),
+                (
+                    "state_sync.dump",
+                    self.config.state_sync.as_ref().is_some_and(|s| s.dump.is_some()),
+                ),
+            ];
+            for (name, is_set) in tracking_options {
+                if is_set {
+                    let error_message = format!("'config.{name}' can't be set together with 'config.chunk_validator_only', a chunk validator only node tracks no shard.");
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
+    }
+
+    fn validate_external_storage_location(
+        &mut self,
+        location: &ExternalStorageLocation,
+        path: &str,
+    ) {
+        match location {
+            ExternalStorageLocation::S3 { bucket, region } => {
+                if bucket.is_empty() || region.is_empty() {
+                    let error_message = format!("'{path}.S3.bucket' and '{path}.S3.region' need to be specified when '{path}.S3' is present.");
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+            ExternalStorageLocation::Filesystem { root_dir } => {
+                if root_dir.as_path() == Path::new("") {
+                    let error_message = format!("'{path}.Filesystem.root_dir' needs to be specified when '{path}.Filesystem' is present.");
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+            ExternalStorageLocation::GCS { bucket } => {
+                if bucket.is_empty() {
+                    let error_message = format!(
+                        "'{path}.GCS.bucket' needs to be specified when '{path}.GCS' is present."
+                    );
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+            ExternalStorageLocation::HttpMirrors { urls } => {
+                if urls.is_empty() || urls.iter().any(|url| url.is_empty()) {
+                    let error_message = format!("'{path}.HttpMirrors.urls' needs to contain at least one URL, and no empty ones, when '{path}.HttpMirrors' is present.");
+                    self.validation_errors.push_config_semantics_error(error_message);
+                }
+            }
+        }
     }
 
     fn result_with_full_error(&self) -> Result<(), ValidationError> {
@@ -252,4 +380,58 @@ mod tests {
         config.tx_routing_height_horizon = 1_000_000_000;
         validate_config(&config).unwrap();
     }
+
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.thread_pools.rpc.cpu_set' should not be empty."
+    )]
+    fn test_thread_pool_empty_cpu_set() {
+        let mut config = Config::default();
+        config.thread_pools.rpc.cpu_set = Some(vec![]);
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.tracked_shards' can't be set together with 'config.tracked_shards_config', express it in 'config.tracked_shards_config' instead."
+    )]
+    fn test_tracked_shards_config_with_legacy_option() {
+        let mut config = Config::default();
+        config.tracked_shards_config = Some("all".parse().unwrap());
+        validate_config(&config).unwrap();
+        config.tracked_shards.push(ShardId::new(0));
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.tracked_shards' can't be set together with 'config.chunk_validator_only', a chunk validator only node tracks no shard."
+    )]
+    fn test_chunk_validator_only_with_tracked_shards() {
+        let mut config = Config::default();
+        config.set_chunk_validator_only();
+        validate_config(&config).unwrap();
+        config.tracked_shards.push(ShardId::new(0));
+        validate_config(&config).unwrap();
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "\\nconfig.json semantic issue: 'config.state_sync.sync.Providers.providers[1].provider.External.HttpMirrors.urls' needs to contain at least one URL"
+    )]
+    fn test_state_sync_providers_with_empty_mirror_list() {
+        let mut config = Config::default();
+        let state_sync = serde_json::json!({
+            "sync": {
+                "Providers": {
+                    "providers": [
+                        { "provider": "Peers", "num_attempts": 2 },
+                        { "provider": { "External": { "HttpMirrors": { "urls": [] } } } },
+                    ],
+                },
+            },
+        });
+        config.state_sync = Some(serde_json::from_value(state_sync).unwrap());
+        validate_config(&config).unwrap();
+    }
 }
@@ -66,6 +66,7 @@ pub fn get_updateable_client_config(config: &Config) -> UpdateableClientConfig {
         expected_shutdown: config.expected_shutdown,
         resharding_config: config.resharding_config,
         produce_chunk_add_transactions_time_limit: config.produce_chunk_add_transactions_time_limit,
+        tracked_shards_config: config.tracked_shards_config.clone(),
     }
 }
 
@@ -110,7 +111,10 @@ fn read_validator_key(
     config: &Config,
 ) -> Result<Option<Arc<ValidatorSigner>>, UpdateableConfigLoaderError> {
     let validator_file: PathBuf = home_dir.join(&config.validator_key_file);
-    match crate::config::load_validator_key(&validator_file) {
+    let cipher = crate::config::validator_key_cipher(home_dir, config).map_err(|err| {
+        UpdateableConfigLoaderError::ValidatorKeyFileError { file: validator_file.clone(), err }
+    })?;
+    match crate::config::load_validator_key(&validator_file, cipher.as_ref()) {
         Ok(Some(validator_signer)) => {
             tracing::info!(target: "neard", "Hot loading validator key {}.", validator_file.display());
             Ok(Some(validator_signer))
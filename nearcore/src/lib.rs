@@ -4,6 +4,7 @@ pub use crate::config::{init_configs, load_config, load_test_config, NearConfig}
 use crate::entity_debug::EntityDebugHandlerImpl;
 use crate::metrics::spawn_trie_metrics_loop;
 
+use crate::background_migrations::{spawn_background_migrations, BackgroundMigrationsHandle};
 use crate::cold_storage::spawn_cold_store_loop;
 use crate::state_sync::StateSyncDumper;
 use actix::{Actor, Addr};
@@ -47,6 +48,7 @@ use std::sync::Arc;
 use tokio::sync::broadcast;
 
 pub mod append_only_map;
+pub mod background_migrations;
 pub mod cold_storage;
 pub mod config;
 #[cfg(test)]
@@ -218,6 +220,9 @@ pub struct NearNode {
     /// The cold_store_loop_handle will only be set if the cold store is configured.
     /// It's a handle to a background thread that copies data from the hot store to the cold store.
     pub cold_store_loop_handle: Option<ColdStoreLoopHandle>,
+    /// Handle to the background thread running the migrations of the store, set only while some
+    /// migrations are not done.
+    pub background_migrations_handle: Option<BackgroundMigrationsHandle>,
     /// Contains handles to background threads that may be dumping state to S3.
     pub state_sync_dumper: StateSyncDumper,
     // A handle that allows the main process to interrupt resharding if needed.
@@ -292,10 +297,8 @@ pub fn start_with_config_and_synchronization(
                 &config.genesis.config,
                 Some(home_dir),
             );
-            let view_shard_tracker = ShardTracker::new(
-                TrackedConfig::from_config(&config.client_config),
-                epoch_manager.clone(),
-            );
+            // Shares the tracked config with the client, which updates it on reload.
+            let view_shard_tracker = shard_tracker.clone();
             let view_runtime = NightshadeRuntime::from_config(
                 home_dir,
                 split_store.clone(),
@@ -309,6 +312,8 @@ pub fn start_with_config_and_synchronization(
         };
 
     let cold_store_loop_handle = spawn_cold_store_loop(&config, &storage, epoch_manager.clone())?;
+    let background_migrations_handle =
+        spawn_background_migrations(&storage, &config.client_config)?;
 
     let telemetry = ActixWrapper::new(TelemetryActor::new(config.telemetry_config.clone())).start();
     let chain_genesis = ChainGenesis::new(&config.genesis.config);
@@ -377,8 +382,11 @@ pub fn start_with_config_and_synchronization(
     let (_gc_actor, gc_arbiter) = spawn_actix_actor(GCActor::new(
         runtime.store().clone(),
         chain_genesis.height,
+        chain_genesis.transaction_validity_period,
         runtime.clone(),
         epoch_manager.clone(),
+        shard_tracker.clone(),
+        config.validator_signer.clone(),
         config.client_config.gc.clone(),
         config.client_config.archive,
     ));
@@ -512,6 +520,7 @@ pub fn start_with_config_and_synchronization(
         rpc_servers,
         arbiters,
         cold_store_loop_handle,
+        background_migrations_handle,
         state_sync_dumper,
         resharding_handle,
         state_sync_runtime,
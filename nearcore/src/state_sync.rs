@@ -2,7 +2,7 @@ use crate::metrics;
 
 use actix_rt::Arbiter;
 use anyhow::Context;
-use borsh::BorshSerialize;
+use borsh::BorshDeserialize;
 use futures::future::BoxFuture;
 use futures::{FutureExt, StreamExt};
 use near_async::futures::{respawn_for_parallelism, FutureSpawner};
@@ -20,10 +20,14 @@ use near_client::sync::external::{
 use near_epoch_manager::shard_tracker::ShardTracker;
 use near_epoch_manager::EpochManagerAdapter;
 use near_primitives::block::BlockHeader;
+use near_primitives::challenge::PartialState;
 use near_primitives::hash::CryptoHash;
 use near_primitives::state_part::PartId;
-use near_primitives::state_sync::StateSyncDumpProgress;
+use near_primitives::state_sync::{
+    CompressedStateHeader, CompressedStatePart, StateSyncDumpProgress,
+};
 use near_primitives::types::{EpochHeight, EpochId, ShardId, StateRoot};
+use near_primitives::utils::compression::CompressedData;
 use rand::seq::SliceRandom;
 use rand::thread_rng;
 use std::collections::{HashMap, HashSet};
@@ -90,6 +94,9 @@ impl StateSyncDumper {
                     bucket,
                 }
             },
+            ExternalStorageLocation::HttpMirrors { .. } => {
+                anyhow::bail!("HTTP mirrors are read-only and can't be used to dump state");
+            }
         };
 
         let chain_id = self.client_config.chain_id.clone();
@@ -121,6 +128,7 @@ impl StateSyncDumper {
                 chain_id,
                 external,
                 dump_config.iteration_delay.unwrap_or(Duration::seconds(10)),
+                dump_config.compress_parts,
                 self.validator.clone(),
                 keep_running.clone(),
                 self.future_spawner.clone(),
@@ -305,6 +313,8 @@ struct StateDumper {
     future_spawner: Arc<dyn FutureSpawner>,
     // Used to limit how many tasks can be doing the computation-heavy state part generation at a time
     obtain_parts: Arc<Semaphore>,
+    // Whether to compress the state parts before uploading them
+    compress_parts: bool,
 }
 
 // Stores needed data for use in part upload futures
@@ -326,6 +336,7 @@ struct PartUploader {
     parts_missing: Arc<RwLock<HashSet<u64>>>,
     obtain_parts: Arc<Semaphore>,
     canceled: Arc<AtomicBool>,
+    compress_parts: bool,
 }
 
 impl PartUploader {
@@ -374,6 +385,19 @@ impl PartUploader {
                     part_id,
                 )
             };
+            let state_part = state_part.and_then(|state_part| {
+                if !self.compress_parts {
+                    return Ok(state_part);
+                }
+                let part = PartialState::try_from_slice(&state_part)?;
+                let (compressed, raw_size) = CompressedStatePart::encode(&part)?;
+                near_chain::metrics::record_compression_ratio(
+                    CompressedStatePart::NAME,
+                    raw_size,
+                    compressed.size_bytes(),
+                );
+                Ok(compressed.as_slice().to_vec())
+            });
             match state_part {
                 Ok(state_part) => {
                     break state_part;
@@ -511,6 +535,7 @@ impl StateDumper {
         runtime: Arc<dyn RuntimeAdapter>,
         external: ExternalConnection,
         future_spawner: Arc<dyn FutureSpawner>,
+        compress_parts: bool,
     ) -> Self {
         Self {
             clock,
@@ -524,6 +549,7 @@ impl StateDumper {
             external,
             future_spawner,
             obtain_parts: Arc::new(Semaphore::new(4)),
+            compress_parts,
         }
     }
 
@@ -591,8 +617,17 @@ impl StateDumper {
             .with_label_values(&[&shard_id.to_string()])
             .set(num_parts.try_into().unwrap_or(i64::MAX));
 
-        let mut header_bytes: Vec<u8> = Vec::new();
-        state_header.serialize(&mut header_bytes)?;
+        let header_bytes = if self.compress_parts {
+            let (compressed, raw_size) = CompressedStateHeader::encode(&state_header)?;
+            near_chain::metrics::record_compression_ratio(
+                CompressedStateHeader::NAME,
+                raw_size,
+                compressed.size_bytes(),
+            );
+            compressed.as_slice().to_vec()
+        } else {
+            borsh::to_vec(&state_header)?
+        };
         let (sender, receiver) = oneshot::channel();
         Ok((
             ShardDump {
@@ -777,6 +812,7 @@ impl StateDumper {
                         parts_missing: shard_dump.parts_missing.clone(),
                         obtain_parts: self.obtain_parts.clone(),
                         canceled: dump.canceled.clone(),
+                        compress_parts: self.compress_parts,
                     }))
                 } else {
                     empty_shards.insert(shard_id);
@@ -979,6 +1015,7 @@ async fn state_sync_dump(
     chain_id: String,
     external: ExternalConnection,
     iteration_delay: Duration,
+    compress_parts: bool,
     validator: MutableValidatorSigner,
     keep_running: Arc<AtomicBool>,
     future_spawner: Arc<dyn FutureSpawner>,
@@ -995,6 +1032,7 @@ async fn state_sync_dump(
         runtime,
         external,
         future_spawner,
+        compress_parts,
     );
     dumper.init(iteration_delay).await?;
 
@@ -1035,6 +1073,7 @@ async fn do_state_sync_dump(
     chain_id: String,
     external: ExternalConnection,
     iteration_delay: Duration,
+    compress_parts: bool,
     validator: MutableValidatorSigner,
     keep_running: Arc<AtomicBool>,
     future_spawner: Arc<dyn FutureSpawner>,
@@ -1048,6 +1087,7 @@ async fn do_state_sync_dump(
         chain_id,
         external,
         iteration_delay,
+        compress_parts,
         validator,
         keep_running,
         future_spawner,
@@ -1,6 +1,6 @@
 #[cfg(unix)]
 use anyhow::Context;
-use near_amend_genesis::AmendGenesisCommand;
+use near_amend_genesis::{AmendGenesisCommand, CanonicalizeRecordsCommand};
 use near_chain_configs::GenesisValidationMode;
 use near_client::ConfigUpdater;
 use near_cold_store_tool::ColdStoreCommand;
@@ -17,6 +17,7 @@ use near_o11y::{
     default_subscriber, default_subscriber_with_opentelemetry, BuildEnvFilterError,
     EnvFilterBuilder,
 };
+use near_parameters::{diff_runtime_configs, RuntimeConfigStore};
 use near_ping::PingCommand;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::compute_root_from_path;
@@ -115,6 +116,9 @@ impl NeardCmd {
             NeardSubCommand::AmendGenesis(cmd) => {
                 cmd.run()?;
             }
+            NeardSubCommand::CanonicalizeGenesisRecords(cmd) => {
+                cmd.run()?;
+            }
             NeardSubCommand::ColdStore(cmd) => {
                 cmd.run(&home_dir, genesis_validation)?;
             }
@@ -147,6 +151,9 @@ impl NeardCmd {
             NeardSubCommand::ReplayArchive(cmd) => {
                 cmd.run(&home_dir, genesis_validation)?;
             }
+            NeardSubCommand::RuntimeConfigDiff(cmd) => {
+                cmd.run()?;
+            }
         };
         Ok(())
     }
@@ -227,6 +234,9 @@ pub(super) enum NeardSubCommand {
     /// Amend a genesis/records file created by `dump-state`.
     AmendGenesis(AmendGenesisCommand),
 
+    /// Rewrite a genesis records file in the canonical form shared by the genesis tools.
+    CanonicalizeGenesisRecords(CanonicalizeRecordsCommand),
+
     /// Testing tool for cold storage
     ColdStore(ColdStoreCommand),
 
@@ -253,6 +263,10 @@ pub(super) enum NeardSubCommand {
 
     /// Replays the blocks in the chain from an archival node.
     ReplayArchive(ReplayArchiveCommand),
+
+    /// Prints the runtime parameters which differ between two protocol versions, along with the
+    /// subsystems they affect.
+    RuntimeConfigDiff(RuntimeConfigDiffCommand),
 }
 
 #[allow(unused)]
@@ -321,6 +335,10 @@ pub(super) struct InitCmd {
     /// from genesis configuration will be taken.
     #[clap(long)]
     max_gas_burnt_view: Option<Gas>,
+    /// Generate the config of a node running as a chunk validator only, which tracks no shard
+    /// and keeps the minimum of data needed to validate the chunks.
+    #[clap(long)]
+    chunk_validator_only: bool,
 }
 
 /// Warns if unsupported build of the executable is used on mainnet or testnet.
@@ -393,6 +411,7 @@ impl InitCmd {
             self.download_config_url.as_deref(),
             self.boot_nodes.as_deref(),
             self.max_gas_burnt_view,
+            self.chunk_validator_only,
         )
         .context("Failed to initialize configs")
     }
@@ -551,6 +570,7 @@ impl RunCmd {
             let nearcore::NearNode {
                 rpc_servers,
                 cold_store_loop_handle,
+                background_migrations_handle,
                 mut state_sync_dumper,
                 resharding_handle,
                 ..
@@ -576,6 +596,9 @@ impl RunCmd {
             if let Some(handle) = cold_store_loop_handle {
                 handle.stop()
             }
+            if let Some(handle) = background_migrations_handle {
+                handle.stop()
+            }
             state_sync_dumper.stop();
             resharding_handle.stop();
             futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
@@ -809,6 +832,44 @@ impl ValidateConfigCommand {
     }
 }
 
+#[derive(clap::Parser)]
+pub(super) struct RuntimeConfigDiffCommand {
+    /// Protocol version to compare from.
+    #[clap(long)]
+    from: ProtocolVersion,
+    /// Protocol version to compare to, the latest one supported by this binary by default.
+    #[clap(long, default_value_t = near_primitives::version::PROTOCOL_VERSION)]
+    to: ProtocolVersion,
+    /// Chain whose runtime configs are compared, as testnet had different parameters early on.
+    #[clap(long, default_value = "mainnet")]
+    chain_id: String,
+    /// Print the changes as JSON instead of one line per parameter.
+    #[clap(long)]
+    json: bool,
+}
+
+impl RuntimeConfigDiffCommand {
+    pub(super) fn run(&self) -> anyhow::Result<()> {
+        let store = RuntimeConfigStore::for_chain_id(&self.chain_id);
+        let changes = diff_runtime_configs(store.get_config(self.from), store.get_config(self.to));
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&changes)?);
+            return Ok(());
+        }
+        for change in changes {
+            let subsystems = change.subsystems.iter().map(ToString::to_string).collect::<Vec<_>>();
+            println!(
+                "{}: {} -> {} [{}]",
+                change.name,
+                change.old_value,
+                change.new_value,
+                subsystems.join(", ")
+            );
+        }
+        Ok(())
+    }
+}
+
 #[cfg(target_os = "linux")]
 fn normalize_whitespace(s: &str) -> String {
     s.split_whitespace().collect::<Vec<_>>().join(" ")
@@ -52,11 +52,15 @@ fn vm_hash(vm_kind: VMKind) -> u64 {
 
 #[tracing::instrument(level = "trace", target = "vm", "get_key", skip_all)]
 pub fn get_contract_cache_key(code_hash: CryptoHash, config: &Config) -> CryptoHash {
+    let vm_hash = vm_hash(config.vm_kind);
+    // Contracts instrumented for the NaN audit must not be mixed up with regular ones.
+    #[cfg(feature = "test_features")]
+    let vm_hash = vm_hash ^ crate::nan_audit::cache_salt();
     let key = ContractCacheKey::Version5 {
         code_hash,
         vm_config_non_crypto_hash: config.non_crypto_hash(),
         vm_kind: config.vm_kind,
-        vm_hash: vm_hash(config.vm_kind),
+        vm_hash,
     };
     CryptoHash::hash_borsh(key)
 }
@@ -174,6 +174,13 @@ imports! {
     // #######################
     promise_batch_action_create_account<[promise_index: u64] -> []>,
     promise_batch_action_deploy_contract<[promise_index: u64, code_len: u64, code_ptr: u64] -> []>,
+    #[validate_contract_code_host_function] validate_contract_code<[
+        code_len: u64,
+        code_ptr: u64,
+        hash_len: u64,
+        hash_ptr: u64,
+        validate_wasm: u64
+    ] -> [u64]>,
     promise_batch_action_function_call<[
         promise_index: u64,
         method_name_len: u64,
@@ -302,6 +309,10 @@ imports! {
     // as it doesn't take almost any resources to execute but burns a lot of
     // gas.
     ##["test_features"] burn_gas<[gas: u64] -> []>,
+
+    // Report a non-canonical NaN observed by the checks injected in the NaN
+    // canonicalization audit mode, see `crate::nan_audit`.
+    ##["test_features"] nan_audit_report<[function_index: u32, bits: u64] -> []>,
 }
 
 pub(crate) use {call_with_name, for_each_available_import};
@@ -10,6 +10,8 @@ mod instrument;
 pub mod logic;
 #[cfg(feature = "metrics")]
 mod metrics;
+#[cfg(feature = "test_features")]
+pub mod nan_audit;
 #[cfg(all(feature = "near_vm", target_arch = "x86_64"))]
 mod near_vm_runner;
 #[cfg(feature = "prepare")]
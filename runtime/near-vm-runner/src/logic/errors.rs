@@ -265,6 +265,10 @@ pub enum HostError {
     BLS12381InvalidInput {
         msg: String,
     },
+    /// Invalid input to the `validate_contract_code` host function (e.g. hash of a wrong length).
+    ContractCodeValidationInvalidInput {
+        msg: String,
+    },
     /// Yield payload length exceeds the maximum permitted.
     YieldPayloadLength {
         length: u64,
@@ -509,6 +513,9 @@ impl std::fmt::Display for HostError {
                 write!(f, "ED25519 signature verification error: {}", msg)
             }
             BLS12381InvalidInput { msg } => write!(f, "BLS12-381 invalid input: {}", msg),
+            ContractCodeValidationInvalidInput { msg } => {
+                write!(f, "Contract code validation invalid input: {}", msg)
+            }
             YieldPayloadLength { length, limit } => write!(
                 f,
                 "Yield resume payload is {length} bytes which exceeds the {limit} byte limit"
@@ -1734,6 +1734,16 @@ bls12381_p2_decompress_base + bls12381_p2_decompress_element * num_elements`
         Ok(())
     }
 
+    /// Records a non-canonical NaN produced by the current contract.
+    ///
+    /// Only called by the checks injected in the NaN canonicalization audit mode, see
+    /// [`crate::nan_audit`].
+    #[cfg(feature = "test_features")]
+    pub fn nan_audit_report(&mut self, function_index: u32, bits: u64) -> Result<()> {
+        crate::nan_audit::report(&self.context.current_account_id, function_index, bits);
+        Ok(())
+    }
+
     // ################
     // # Promises API #
     // ################
@@ -2136,6 +2146,87 @@ bls12381_p2_decompress_base + bls12381_p2_decompress_element * num_elements`
         Ok(())
     }
 
+    /// Checks whether the given contract code is safe to be deployed, so that a contract can
+    /// verify a candidate code before deploying it to itself with
+    /// `promise_batch_action_deploy_contract` and avoid bricking itself with an invalid code.
+    ///
+    /// The code must have the sha256 hash stored at `hash_ptr` and must not exceed
+    /// `max_contract_size`. If `validate_wasm` is 1, the code additionally has to pass the same
+    /// wasm validation and preparation the runtime performs before executing a contract.
+    ///
+    /// Returns 0 if the code passed all the checks, 1 if the hash does not match, 2 if the code
+    /// exceeds `max_contract_size` and 3 if the wasm validation failed.
+    ///
+    /// # Errors
+    ///
+    /// * If `code_len + code_ptr` or `hash_len + hash_ptr` points outside the memory of the guest
+    /// or host returns `MemoryAccessViolation`.
+    /// * If `hash_len` is not 32 or `validate_wasm` is neither 0 nor 1 returns
+    /// `ContractCodeValidationInvalidInput`.
+    ///
+    /// # Cost
+    ///
+    /// `input_cost(num_bytes_hash) + input_cost(num_bytes_code) + validate_contract_code_base +
+    ///  validate_contract_code_byte * num_bytes_code +
+    ///  validate_contract_code_prepare_byte * num_bytes_code (if validate_wasm is 1)`
+    pub fn validate_contract_code(
+        &mut self,
+        code_len: u64,
+        code_ptr: u64,
+        hash_len: u64,
+        hash_ptr: u64,
+        validate_wasm: u64,
+    ) -> Result<u64> {
+        self.result_state.gas_counter.pay_base(validate_contract_code_base)?;
+        let validate_wasm = match validate_wasm {
+            0 => false,
+            1 => true,
+            _ => {
+                return Err(HostError::ContractCodeValidationInvalidInput {
+                    msg: "validate_wasm must be either 0 or 1".to_string(),
+                }
+                .into())
+            }
+        };
+        let expected_hash = {
+            let vec = get_memory_or_register!(self, hash_ptr, hash_len)?;
+            <[u8; 32]>::try_from(&vec[..]).map_err(|_| {
+                VMLogicError::HostError(HostError::ContractCodeValidationInvalidInput {
+                    msg: "invalid code hash length".to_string(),
+                })
+            })?
+        };
+        let code = get_memory_or_register!(self, code_ptr, code_len)?;
+        let code_len = code.len() as u64;
+        self.result_state.gas_counter.pay_per(validate_contract_code_byte, code_len)?;
+
+        if CryptoHash::hash_bytes(&code) != CryptoHash(expected_hash) {
+            return Ok(1);
+        }
+        if code_len > self.config.limit_config.max_contract_size {
+            return Ok(2);
+        }
+        if validate_wasm {
+            self.result_state.gas_counter.pay_per(validate_contract_code_prepare_byte, code_len)?;
+            if !self.is_preparable_contract_code(&code) {
+                return Ok(3);
+            }
+        }
+        Ok(0)
+    }
+
+    #[cfg(feature = "prepare")]
+    fn is_preparable_contract_code(&self, code: &[u8]) -> bool {
+        crate::prepare::prepare_contract(code, &self.config, self.config.vm_kind).is_ok()
+    }
+
+    #[cfg(not(feature = "prepare"))]
+    fn is_preparable_contract_code(&self, _code: &[u8]) -> bool {
+        unreachable!(
+            "contracts can only be executed with a wasm runtime, which enables preparation"
+        )
+    }
+
     /// Appends `FunctionCall` action to the batch of actions for the given promise pointed by
     /// `promise_idx`.
     ///
@@ -11,6 +11,7 @@ mod promises;
 mod registers;
 mod storage_read_write;
 mod storage_usage;
+mod validate_contract_code;
 mod view_method;
 mod vm_logic_builder;
 
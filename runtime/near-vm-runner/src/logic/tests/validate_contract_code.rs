@@ -0,0 +1,147 @@
+use crate::logic::tests::helpers::*;
+use crate::logic::tests::vm_logic_builder::VMLogicBuilder;
+use crate::logic::HostError;
+use crate::logic::VMLogicError;
+use crate::map;
+use near_parameters::ExtCosts;
+use near_primitives_core::hash::CryptoHash;
+use std::collections::HashMap;
+
+const CODE: &[u8] = b"candidate contract code";
+
+#[track_caller]
+fn check_validate_contract_code(
+    mut logic_builder: VMLogicBuilder,
+    code: &[u8],
+    hash: &[u8],
+    validate_wasm: u64,
+    want: Result<u64, HostError>,
+    want_costs: HashMap<ExtCosts, u64>,
+) {
+    let mut logic = logic_builder.build();
+    let code = logic.internal_mem_write(code);
+    let hash = logic.internal_mem_write(hash);
+
+    let result =
+        logic.validate_contract_code(code.len, code.ptr, hash.len, hash.ptr, validate_wasm);
+
+    let want = want.map_err(VMLogicError::HostError);
+    assert_eq!(want, result);
+    assert_costs(want_costs);
+}
+
+#[test]
+fn test_validate_contract_code_hash_and_size() {
+    let hash = CryptoHash::hash_bytes(CODE);
+    let code_len = CODE.len() as u64;
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        CODE,
+        hash.as_bytes(),
+        0,
+        Ok(0),
+        map! {
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 32 + code_len,
+            ExtCosts::validate_contract_code_base: 1,
+            ExtCosts::validate_contract_code_byte: code_len,
+        },
+    );
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        CODE,
+        CryptoHash::hash_bytes(b"another code").as_bytes(),
+        0,
+        Ok(1),
+        map! {
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 32 + code_len,
+            ExtCosts::validate_contract_code_base: 1,
+            ExtCosts::validate_contract_code_byte: code_len,
+        },
+    );
+
+    let mut logic_builder = VMLogicBuilder::default();
+    logic_builder.config.limit_config.max_contract_size = code_len - 1;
+    check_validate_contract_code(
+        logic_builder,
+        CODE,
+        hash.as_bytes(),
+        0,
+        Ok(2),
+        map! {
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 32 + code_len,
+            ExtCosts::validate_contract_code_base: 1,
+            ExtCosts::validate_contract_code_byte: code_len,
+        },
+    );
+}
+
+#[test]
+fn test_validate_contract_code_invalid_input() {
+    let hash = CryptoHash::hash_bytes(CODE);
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        CODE,
+        &hash.as_bytes()[..31],
+        0,
+        Err(HostError::ContractCodeValidationInvalidInput {
+            msg: "invalid code hash length".to_string(),
+        }),
+        map! {
+            ExtCosts::read_memory_base: 1,
+            ExtCosts::read_memory_byte: 31,
+            ExtCosts::validate_contract_code_base: 1,
+        },
+    );
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        CODE,
+        hash.as_bytes(),
+        2,
+        Err(HostError::ContractCodeValidationInvalidInput {
+            msg: "validate_wasm must be either 0 or 1".to_string(),
+        }),
+        map! {
+            ExtCosts::validate_contract_code_base: 1,
+        },
+    );
+}
+
+#[test]
+#[cfg(feature = "prepare")]
+fn test_validate_contract_code_wasm() {
+    let code_len = CODE.len() as u64;
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        CODE,
+        CryptoHash::hash_bytes(CODE).as_bytes(),
+        1,
+        Ok(3),
+        map! {
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 32 + code_len,
+            ExtCosts::validate_contract_code_base: 1,
+            ExtCosts::validate_contract_code_byte: code_len,
+            ExtCosts::validate_contract_code_prepare_byte: code_len,
+        },
+    );
+
+    let wasm = wat::parse_str(r#"(module (func (export "main")))"#).unwrap();
+    let wasm_len = wasm.len() as u64;
+    check_validate_contract_code(
+        VMLogicBuilder::default(),
+        &wasm,
+        CryptoHash::hash_bytes(&wasm).as_bytes(),
+        1,
+        Ok(0),
+        map! {
+            ExtCosts::read_memory_base: 2,
+            ExtCosts::read_memory_byte: 32 + wasm_len,
+            ExtCosts::validate_contract_code_base: 1,
+            ExtCosts::validate_contract_code_byte: wasm_len,
+            ExtCosts::validate_contract_code_prepare_byte: wasm_len,
+        },
+    );
+}
@@ -0,0 +1,308 @@
+//! Floating point NaN canonicalization audit mode.
+//!
+//! The WebAssembly specification allows float operations producing a NaN to return any NaN bit
+//! pattern. Our runtimes canonicalize such NaNs so that contract execution stays deterministic
+//! across machines, but this relies on every compiler backend getting the canonicalization right
+//! at every point where the bits of a float become observable.
+//!
+//! When enabled, this mode rewrites every contract before preparation so that the result of each
+//! float operation that may produce a NaN is passed through a small checker function. The checker
+//! calls the `nan_audit_report` host function whenever it observes a NaN whose bit pattern is not
+//! the canonical one, and the host function records the offending contract and wasm function.
+//!
+//! The audit is intended for nightly test runs only: it is compiled in with the `test_features`
+//! feature and is switched on for the whole process by setting the `NEAR_VM_NAN_AUDIT`
+//! environment variable. Audited contracts execute extra instructions and thus burn more gas than
+//! the same contracts would otherwise.
+
+use near_primitives_core::types::AccountId;
+use std::sync::{Mutex, OnceLock};
+
+/// Environment variable which enables the audit mode for the current process.
+pub const NAN_AUDIT_ENV_VAR: &str = "NEAR_VM_NAN_AUDIT";
+
+/// Bit pattern of the canonical `f32` NaN.
+const CANONICAL_NAN_F32: u32 = 0x7FC0_0000;
+/// Bit pattern of the canonical `f64` NaN.
+const CANONICAL_NAN_F64: u64 = 0x7FF8_0000_0000_0000;
+
+/// A non-canonical NaN observed while executing an audited contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NanAuditReport {
+    /// The account whose contract produced the NaN.
+    pub account_id: AccountId,
+    /// Index of the wasm function containing the float operation, in the function index space of
+    /// the original contract code.
+    pub function_index: u32,
+    /// Raw bits of the NaN. `f32` values are zero-extended.
+    pub bits: u64,
+}
+
+static REPORTS: Mutex<Vec<NanAuditReport>> = Mutex::new(Vec::new());
+
+/// Whether the audit mode is enabled for this process.
+pub fn is_enabled() -> bool {
+    static ENABLED: OnceLock<bool> = OnceLock::new();
+    *ENABLED.get_or_init(|| std::env::var_os(NAN_AUDIT_ENV_VAR).is_some())
+}
+
+/// Returns all non-canonical NaNs observed so far and clears the list.
+pub fn take_reports() -> Vec<NanAuditReport> {
+    std::mem::take(&mut *REPORTS.lock().unwrap())
+}
+
+pub(crate) fn report(account_id: &AccountId, function_index: u32, bits: u64) {
+    tracing::error!(
+        target: "vm",
+        %account_id,
+        function_index,
+        bits = format_args!("{bits:#x}"),
+        "contract produced a non-canonical NaN"
+    );
+    REPORTS.lock().unwrap().push(NanAuditReport {
+        account_id: account_id.clone(),
+        function_index,
+        bits,
+    });
+}
+
+/// Value mixed into the VM hash so that audited and regular compilation artifacts never share a
+/// cache entry.
+pub(crate) fn cache_salt() -> u64 {
+    if is_enabled() {
+        0x6e61_6e5f_6175_6469
+    } else {
+        0
+    }
+}
+
+/// Instruments `original_code` if the audit mode is enabled.
+///
+/// Contracts that cannot be instrumented are returned unchanged, the regular preparation will
+/// then deal with them as usual.
+#[cfg(feature = "prepare")]
+pub(crate) fn maybe_instrument(original_code: &[u8]) -> std::borrow::Cow<'_, [u8]> {
+    if !is_enabled() {
+        return original_code.into();
+    }
+    let instrumented = parity_wasm::deserialize_buffer(original_code)
+        .map_err(|err| err.to_string())
+        .and_then(|module| instrument(module).map_err(|_| "too many functions".to_string()))
+        .and_then(|module| parity_wasm::serialize(module).map_err(|err| err.to_string()));
+    match instrumented {
+        Ok(code) => code.into(),
+        Err(err) => {
+            tracing::warn!(target: "vm", %err, "could not instrument contract for the NaN audit");
+            original_code.into()
+        }
+    }
+}
+
+/// Injects a check after every float operation of `module` that may produce a NaN.
+///
+/// This imports `env.nan_audit_report` and appends two checker functions, one per float type,
+/// which take the value to check and the index of the calling function and return the value
+/// unchanged. As with gas instrumentation, the added import shifts the indices of all functions
+/// defined in the module, so all references to them are updated accordingly.
+#[cfg(feature = "prepare")]
+fn instrument(
+    module: parity_wasm::elements::Module,
+) -> Result<parity_wasm::elements::Module, parity_wasm::elements::Module> {
+    use parity_wasm::builder;
+    use parity_wasm::elements::{self, BlockType, Instruction::*, ValueType};
+
+    let mut mbuilder = builder::from_module(module);
+    let report_sig = mbuilder.push_signature(
+        builder::signature().with_params(vec![ValueType::I32, ValueType::I64]).build_sig(),
+    );
+    mbuilder.push_import(
+        builder::import()
+            .module("env")
+            .field("nan_audit_report")
+            .external()
+            .func(report_sig)
+            .build(),
+    );
+    let mut module = mbuilder.build();
+
+    let report_func = module.import_count(elements::ImportCountType::Function) as u32 - 1;
+    let Ok(check_f32) = u32::try_from(module.functions_space()) else {
+        return Err(module);
+    };
+    let check_f64 = check_f32 + 1;
+
+    for section in module.sections_mut() {
+        match section {
+            elements::Section::Code(code_section) => {
+                for (index, func_body) in code_section.bodies_mut().iter_mut().enumerate() {
+                    let function_index = report_func + index as u32;
+                    let code = func_body.code_mut().elements_mut();
+                    let mut instrumented = Vec::with_capacity(code.len());
+                    for instruction in code.drain(..) {
+                        let check = match &instruction {
+                            F32Add | F32Sub | F32Mul | F32Div | F32Min | F32Max | F32Sqrt
+                            | F32Ceil | F32Floor | F32Trunc | F32Nearest | F32DemoteF64 => {
+                                Some(check_f32)
+                            }
+                            F64Add | F64Sub | F64Mul | F64Div | F64Min | F64Max | F64Sqrt
+                            | F64Ceil | F64Floor | F64Trunc | F64Nearest | F64PromoteF32 => {
+                                Some(check_f64)
+                            }
+                            Call(call_index) if *call_index >= report_func => {
+                                instrumented.push(Call(call_index + 1));
+                                continue;
+                            }
+                            _ => None,
+                        };
+                        instrumented.push(instruction);
+                        if let Some(check) = check {
+                            instrumented.push(I32Const(function_index as i32));
+                            instrumented.push(Call(check));
+                        }
+                    }
+                    *code = instrumented;
+                }
+            }
+            elements::Section::Export(export_section) => {
+                for export in export_section.entries_mut() {
+                    if let elements::Internal::Function(func_index) = export.internal_mut() {
+                        if *func_index >= report_func {
+                            *func_index += 1
+                        }
+                    }
+                }
+            }
+            elements::Section::Element(elements_section) => {
+                for segment in elements_section.entries_mut() {
+                    for func_index in segment.members_mut() {
+                        if *func_index >= report_func {
+                            *func_index += 1
+                        }
+                    }
+                }
+            }
+            elements::Section::Start(start_idx) => {
+                if *start_idx >= report_func {
+                    *start_idx += 1
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let mut mbuilder = builder::from_module(module);
+    for (value_type, check) in [
+        (
+            ValueType::F32,
+            vec![
+                GetLocal(0),
+                I32ReinterpretF32,
+                I32Const(CANONICAL_NAN_F32 as i32),
+                I32Ne,
+                If(BlockType::NoResult),
+                GetLocal(1),
+                GetLocal(0),
+                I32ReinterpretF32,
+                I64ExtendUI32,
+                Call(report_func),
+                End,
+            ],
+        ),
+        (
+            ValueType::F64,
+            vec![
+                GetLocal(0),
+                I64ReinterpretF64,
+                I64Const(CANONICAL_NAN_F64 as i64),
+                I64Ne,
+                If(BlockType::NoResult),
+                GetLocal(1),
+                GetLocal(0),
+                I64ReinterpretF64,
+                Call(report_func),
+                End,
+            ],
+        ),
+    ] {
+        // A value is NaN if and only if it does not compare equal to itself.
+        let mut instructions = vec![GetLocal(0), GetLocal(0)];
+        instructions.push(if value_type == ValueType::F32 { F32Ne } else { F64Ne });
+        instructions.push(If(BlockType::NoResult));
+        instructions.extend(check);
+        instructions.extend([End, GetLocal(0), End]);
+        mbuilder.push_function(
+            builder::function()
+                .signature()
+                .with_params(vec![value_type, ValueType::I32])
+                .with_result(value_type)
+                .build()
+                .body()
+                .with_instructions(elements::Instructions::new(instructions))
+                .build()
+                .build(),
+        );
+    }
+    Ok(mbuilder.build())
+}
+
+#[cfg(all(test, feature = "prepare"))]
+mod tests {
+    use super::*;
+    use parity_wasm::elements::Instruction::*;
+
+    fn instrument_wat(wat: &str) -> parity_wasm::elements::Module {
+        let module = parity_wasm::deserialize_buffer(&wat::parse_str(wat).unwrap()).unwrap();
+        let module = instrument(module).unwrap();
+        let code = parity_wasm::serialize(module.clone()).unwrap();
+        wasmparser::validate(&code).unwrap();
+        module
+    }
+
+    #[test]
+    fn checks_float_operations() {
+        let module = instrument_wat(
+            r#"(module
+                (import "env" "input" (func $input (param i64)))
+                (func $f (export "f") (param f32 f64) (result f64)
+                    (call $input (i64.const 0))
+                    (f64.add (f64.promote_f32 (f32.neg (local.get 0))) (local.get 1))))"#,
+        );
+        let body = &module.code_section().unwrap().bodies()[0];
+        assert_eq!(
+            body.code().elements(),
+            &[
+                I64Const(0),
+                Call(0),
+                GetLocal(0),
+                F32Neg,
+                F64PromoteF32,
+                I32Const(1),
+                Call(4),
+                GetLocal(1),
+                F64Add,
+                I32Const(1),
+                Call(4),
+                End,
+            ]
+        );
+        let export = &module.export_section().unwrap().entries()[0];
+        assert_eq!(export.internal(), &parity_wasm::elements::Internal::Function(2));
+    }
+
+    #[test]
+    fn shifts_defined_function_indices() {
+        let module = instrument_wat(
+            r#"(module
+                (table 1 funcref)
+                (elem (i32.const 0) $g)
+                (func $f (call $g))
+                (func $g)
+                (start $f))"#,
+        );
+        let body = &module.code_section().unwrap().bodies()[0];
+        assert_eq!(body.code().elements(), &[Call(2), End]);
+        let segment = &module.elements_section().unwrap().entries()[0];
+        assert_eq!(segment.members(), &[2]);
+        assert_eq!(module.start_section(), Some(1));
+    }
+}
@@ -30,6 +30,10 @@ pub fn prepare_contract(
         (kind != VMKind::NearVm) || (prepare == crate::logic::ContractPrepareVersion::V2),
         "NearVM only works with contract prepare version V2",
     );
+    #[cfg(feature = "test_features")]
+    let audited_code = crate::nan_audit::maybe_instrument(original_code);
+    #[cfg(feature = "test_features")]
+    let original_code = &audited_code[..];
     let features = crate::features::WasmFeatures::from(prepare);
     match prepare {
         crate::logic::ContractPrepareVersion::V0 => {
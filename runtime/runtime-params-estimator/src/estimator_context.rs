@@ -192,6 +192,7 @@ impl<'c> EstimatorContext<'c> {
             migration_flags: MigrationFlags::default(),
             congestion_info,
             bandwidth_requests: BlockBandwidthRequests::empty(),
+            record_receiver_queue_changes: false,
         }
     }
 
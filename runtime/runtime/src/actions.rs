@@ -1464,6 +1464,7 @@ mod tests {
             migration_flags: MigrationFlags::default(),
             congestion_info: BlockCongestionInfo::default(),
             bandwidth_requests: BlockBandwidthRequests::empty(),
+            record_receiver_queue_changes: false,
         }
     }
 
@@ -6,7 +6,7 @@ use near_primitives::types::{
     AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, MerkleHash,
 };
 use near_primitives::version::ProtocolVersion;
-use near_primitives::views::ViewStateResult;
+use near_primitives::views::{AccountBalanceView, StateOverride, ViewStateResult};
 use near_vm_runner::ContractCode;
 
 /// Adapter for querying runtime.
@@ -18,6 +18,14 @@ pub trait ViewRuntimeAdapter {
         account_id: &AccountId,
     ) -> Result<Account, crate::state_viewer::errors::ViewAccountError>;
 
+    fn view_account_balance(
+        &self,
+        shard_uid: &ShardUId,
+        state_root: MerkleHash,
+        account_id: &AccountId,
+        current_protocol_version: ProtocolVersion,
+    ) -> Result<AccountBalanceView, crate::state_viewer::errors::ViewAccountError>;
+
     fn view_contract_code(
         &self,
         shard_uid: &ShardUId,
@@ -38,6 +46,7 @@ pub trait ViewRuntimeAdapter {
         contract_id: &AccountId,
         method_name: &str,
         args: &[u8],
+        state_override: Option<&StateOverride>,
         logs: &mut Vec<String>,
         epoch_info_provider: &dyn EpochInfoProvider,
         current_protocol_version: ProtocolVersion,
@@ -2,6 +2,7 @@ use crate::bandwidth_scheduler::BandwidthSchedulerOutput;
 use crate::config::{
     safe_add_gas, total_prepaid_exec_fees, total_prepaid_gas, total_prepaid_send_fees,
 };
+use crate::receiver_queue_stats::ReceiverQueueDiff;
 use crate::ApplyState;
 use bytesize::ByteSize;
 use itertools::Itertools;
@@ -13,7 +14,7 @@ use near_primitives::congestion_info::{CongestionControl, CongestionInfo, Conges
 use near_primitives::errors::{EpochError, IntegerOverflowError, RuntimeError};
 use near_primitives::receipt::{
     Receipt, ReceiptEnum, ReceiptOrStateStoredReceipt, StateStoredReceipt,
-    StateStoredReceiptMetadata,
+    StateStoredReceiptMetadata, TrieQueueIndices,
 };
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::types::{EpochId, EpochInfoProvider, Gas, ShardId};
@@ -59,6 +60,8 @@ pub(crate) struct ReceiptSinkV2 {
     pub(crate) outgoing_metadatas: OutgoingMetadatas,
     pub(crate) bandwidth_scheduler_output: Option<BandwidthSchedulerOutput>,
     pub(crate) protocol_version: ProtocolVersion,
+    /// Receipts added to and removed from the outgoing buffers, by receiver.
+    pub(crate) receiver_changes: ReceiverQueueDiff,
 }
 
 /// Limits for outgoing receipts to a shard.
@@ -134,6 +137,7 @@ impl ReceiptSink {
                 outgoing_metadatas,
                 bandwidth_scheduler_output,
                 protocol_version,
+                receiver_changes: ReceiverQueueDiff::new(apply_state.record_receiver_queue_changes),
             }))
         } else {
             debug_assert!(!ProtocolFeature::CongestionControl.enabled(protocol_version));
@@ -202,6 +206,14 @@ impl ReceiptSink {
         }
     }
 
+    /// Takes the receipts added to and removed from the outgoing buffers so far, by receiver.
+    pub(crate) fn take_receiver_changes(&mut self) -> ReceiverQueueDiff {
+        match self {
+            ReceiptSink::V1(_) => ReceiverQueueDiff::default(),
+            ReceiptSink::V2(inner) => std::mem::take(&mut inner.receiver_changes),
+        }
+    }
+
     pub(crate) fn bandwidth_scheduler_output(&self) -> Option<&BandwidthSchedulerOutput> {
         match self {
             ReceiptSink::V1(_) => None,
@@ -310,7 +322,8 @@ impl ReceiptSinkV2 {
             let size = receipt_size(&receipt)?;
             let should_update_outgoing_metadatas = receipt.should_update_outgoing_metadatas();
             let receipt = receipt.into_receipt();
-            let target_shard_id = shard_layout.account_id_to_shard_id(receipt.receiver_id());
+            let receiver_id = receipt.receiver_id().clone();
+            let target_shard_id = shard_layout.account_id_to_shard_id(&receiver_id);
 
             match Self::try_forward(
                 receipt,
@@ -324,6 +337,7 @@ impl ReceiptSinkV2 {
                 ReceiptForwarding::Forwarded => {
                     self.own_congestion_info.remove_receipt_bytes(size)?;
                     self.own_congestion_info.remove_buffered_receipt_gas(gas.into())?;
+                    self.receiver_changes.record_removed(&receiver_id, gas, size);
                     if should_update_outgoing_metadatas {
                         // Can't update metadatas immediately because state_update is borrowed by iterator.
                         outgoing_metadatas_updates.push((ByteSize::b(size), gas));
@@ -487,6 +501,7 @@ impl ReceiptSinkV2 {
 
         self.own_congestion_info.add_receipt_bytes(size)?;
         self.own_congestion_info.add_buffered_receipt_gas(gas)?;
+        self.receiver_changes.record_added(receipt.get_receipt().receiver_id(), gas, size);
 
         if receipt.should_update_outgoing_metadatas() {
             self.outgoing_metadatas.update_on_receipt_pushed(
@@ -728,6 +743,9 @@ pub(crate) struct DelayedReceiptQueueWrapper<'a> {
     new_delayed_bytes: u64,
     removed_delayed_gas: Gas,
     removed_delayed_bytes: u64,
+
+    // Receipts added to and removed from the queue, by receiver.
+    receiver_changes: ReceiverQueueDiff,
 }
 
 impl<'a> DelayedReceiptQueueWrapper<'a> {
@@ -736,6 +754,7 @@ impl<'a> DelayedReceiptQueueWrapper<'a> {
         epoch_info_provider: &'a dyn EpochInfoProvider,
         shard_id: ShardId,
         epoch_id: EpochId,
+        record_receiver_changes: bool,
     ) -> Self {
         Self {
             queue,
@@ -746,6 +765,7 @@ impl<'a> DelayedReceiptQueueWrapper<'a> {
             new_delayed_bytes: 0,
             removed_delayed_gas: 0,
             removed_delayed_bytes: 0,
+            receiver_changes: ReceiverQueueDiff::new(record_receiver_changes),
         }
     }
 
@@ -778,6 +798,7 @@ impl<'a> DelayedReceiptQueueWrapper<'a> {
 
         self.new_delayed_gas = safe_add_gas(self.new_delayed_gas, gas)?;
         self.new_delayed_bytes = safe_add_gas(self.new_delayed_bytes, size)?;
+        self.receiver_changes.record_added(receipt.get_receipt().receiver_id(), gas, size);
         self.queue.push_back(trie_update, &receipt)?;
         Ok(())
     }
@@ -809,6 +830,11 @@ impl<'a> DelayedReceiptQueueWrapper<'a> {
             let delayed_bytes = receipt_size(&receipt)? as u64;
             self.removed_delayed_gas = safe_add_gas(self.removed_delayed_gas, delayed_gas)?;
             self.removed_delayed_bytes = safe_add_gas(self.removed_delayed_bytes, delayed_bytes)?;
+            self.receiver_changes.record_removed(
+                receipt.get_receipt().receiver_id(),
+                delayed_gas,
+                delayed_bytes,
+            );
 
             // TODO(resharding): The filter function check here is bypassing the limit check for state witness.
             // Track gas and bytes for receipt above and return only receipt that belong to the shard.
@@ -838,6 +864,15 @@ impl<'a> DelayedReceiptQueueWrapper<'a> {
         self.queue.len()
     }
 
+    pub(crate) fn indices(&self) -> TrieQueueIndices {
+        self.queue.indices()
+    }
+
+    /// Takes the receipts added to and removed from the queue so far, by receiver.
+    pub(crate) fn take_receiver_changes(&mut self) -> ReceiverQueueDiff {
+        std::mem::take(&mut self.receiver_changes)
+    }
+
     pub(crate) fn apply_congestion_changes(
         self,
         congestion: &mut CongestionInfo,
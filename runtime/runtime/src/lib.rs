@@ -6,9 +6,11 @@ use crate::config::{
 };
 use crate::congestion_control::DelayedReceiptQueueWrapper;
 use crate::prefetch::TriePrefetcher;
+use crate::receiver_queue_stats::ReceiverQueueChanges;
 use crate::verifier::{check_storage_stake, validate_receipt, StorageStakingError};
 pub use crate::verifier::{
-    validate_transaction, verify_and_charge_transaction, ZERO_BALANCE_ACCOUNT_STORAGE_LIMIT,
+    get_storage_stake_reserve, validate_transaction, verify_and_charge_transaction,
+    ZERO_BALANCE_ACCOUNT_STORAGE_LIMIT,
 };
 use bandwidth_scheduler::{run_bandwidth_scheduler, BandwidthSchedulerOutput};
 use config::total_prepaid_send_fees;
@@ -30,7 +32,7 @@ use near_primitives::errors::{
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{
     ActionReceipt, DataReceipt, DelayedReceiptIndices, PromiseYieldIndices, PromiseYieldTimeout,
-    Receipt, ReceiptEnum, ReceiptOrStateStoredReceipt, ReceiptV0, ReceivedData,
+    Receipt, ReceiptEnum, ReceiptOrStateStoredReceipt, ReceiptV0, ReceivedData, TrieQueueIndices,
 };
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
@@ -88,6 +90,7 @@ mod metrics;
 mod pipelining;
 mod prefetch;
 pub mod receipt_manager;
+pub mod receiver_queue_stats;
 pub mod state_viewer;
 #[cfg(test)]
 mod tests;
@@ -144,6 +147,10 @@ pub struct ApplyState {
     /// Each shard requests some bandwidth to other shards and then the bandwidth scheduler
     /// decides how much each shard is allowed to send.
     pub bandwidth_requests: BlockBandwidthRequests,
+    /// Whether to collect the [ReceiverQueueChanges] of the chunk, which clones the receiver
+    /// of every receipt added to or removed from the queues. Only needed to keep the receiver
+    /// queue stats of the shard up to date once they were requested.
+    pub record_receiver_queue_changes: bool,
 }
 
 /// Contains information to update validators accounts at the first block of a new epoch.
@@ -196,6 +203,8 @@ pub struct ApplyResult {
     pub processed_yield_timeouts: Vec<PromiseYieldTimeout>,
     pub proof: Option<PartialStorage>,
     pub delayed_receipts_count: u64,
+    /// Indices of the delayed receipts queue after applying the chunk.
+    pub delayed_receipts_indices: TrieQueueIndices,
     pub metrics: Option<metrics::ApplyMetrics>,
     pub congestion_info: Option<CongestionInfo>,
     pub bandwidth_requests: Option<BandwidthRequests>,
@@ -203,6 +212,9 @@ pub struct ApplyResult {
     pub bandwidth_scheduler_state_hash: CryptoHash,
     /// Contracts accessed and deployed while applying the chunk.
     pub contract_updates: ContractUpdates,
+    /// Receipts added to and removed from the delayed receipts queue and the outgoing
+    /// buffers, by receiver.
+    pub receiver_queue_changes: ReceiverQueueChanges,
 }
 
 #[derive(Debug)]
@@ -1471,6 +1483,7 @@ impl Runtime {
             epoch_info_provider,
             apply_state.shard_id,
             apply_state.epoch_id,
+            apply_state.record_receiver_queue_changes,
         );
 
         // Bandwidth scheduler should be run for every chunk, including the missing ones.
@@ -2043,14 +2056,14 @@ impl Runtime {
         processing_state: ApplyProcessingReceiptState<'a>,
         process_receipts_result: ProcessReceiptsResult,
         validator_accounts_update: &Option<ValidatorAccountsUpdate>,
-        receipt_sink: ReceiptSink,
+        mut receipt_sink: ReceiptSink,
         state_patch: SandboxStatePatch,
     ) -> Result<ApplyResult, RuntimeError> {
         let _span = tracing::debug_span!(target: "runtime", "apply_commit").entered();
         let apply_state = processing_state.apply_state;
         let epoch_info_provider = processing_state.epoch_info_provider;
         let mut state_update = processing_state.state_update;
-        let pending_delayed_receipts = processing_state.delayed_receipts;
+        let mut pending_delayed_receipts = processing_state.delayed_receipts;
         let processed_delayed_receipts = process_receipts_result.processed_delayed_receipts;
         let promise_yield_result = process_receipts_result.promise_yield_result;
 
@@ -2067,6 +2080,11 @@ impl Runtime {
         // Congestion info needs a final touch to select an allowed shard if
         // this shard is fully congested.
         let delayed_receipts_count = pending_delayed_receipts.upper_bound_len();
+        let delayed_receipts_indices = pending_delayed_receipts.indices();
+        let receiver_queue_changes = ReceiverQueueChanges {
+            delayed: pending_delayed_receipts.take_receiver_changes(),
+            buffered: receipt_sink.take_receiver_changes(),
+        };
         let mut own_congestion_info = receipt_sink.own_congestion_info();
         if let Some(congestion_info) = &mut own_congestion_info {
             pending_delayed_receipts.apply_congestion_changes(congestion_info)?;
@@ -2167,7 +2185,7 @@ impl Runtime {
             .with_label_values(&[shard_id_str.as_str()])
             .observe(chunk_recorded_size_upper_bound / f64::max(1.0, chunk_recorded_size));
         metrics::report_recorded_column_sizes(&trie, &apply_state);
-        let proof = trie.recorded_storage();
+        let proof = trie.recorded_storage()?;
         let processed_yield_timeouts = promise_yield_result.processed_yield_timeouts;
         let bandwidth_scheduler_state_hash = receipt_sink
             .bandwidth_scheduler_output()
@@ -2185,11 +2203,13 @@ impl Runtime {
             processed_yield_timeouts,
             proof,
             delayed_receipts_count,
+            delayed_receipts_indices,
             metrics: Some(processing_state.metrics),
             congestion_info: own_congestion_info,
             bandwidth_requests,
             bandwidth_scheduler_state_hash,
             contract_updates,
+            receiver_queue_changes,
         })
     }
 }
@@ -2279,7 +2299,7 @@ fn missing_chunk_apply_result(
 ) -> Result<ApplyResult, RuntimeError> {
     let TrieUpdateResult { trie, trie_changes, state_changes, contract_updates } =
         processing_state.state_update.finalize()?;
-    let proof = trie.recorded_storage();
+    let proof = trie.recorded_storage()?;
 
     // For old chunks, copy the congestion info exactly as it came in,
     // potentially returning `None` even if the congestion control
@@ -2311,6 +2331,7 @@ fn missing_chunk_apply_result(
         processed_yield_timeouts: vec![],
         proof,
         delayed_receipts_count: delayed_receipts.upper_bound_len(),
+        delayed_receipts_indices: delayed_receipts.indices(),
         metrics: None,
         congestion_info,
         bandwidth_requests: previous_bandwidth_requests,
@@ -2319,6 +2340,8 @@ fn missing_chunk_apply_result(
             .map(|o| o.scheduler_state_hash)
             .unwrap_or_default(),
         contract_updates,
+        // The queues are only changed when applying new chunks.
+        receiver_queue_changes: ReceiverQueueChanges::default(),
     });
 }
 
@@ -2707,6 +2730,7 @@ pub mod estimator {
             outgoing_metadatas,
             bandwidth_scheduler_output: None,
             protocol_version: apply_state.current_protocol_version,
+            receiver_changes: Default::default(),
         });
         let empty_pipeline = ReceiptPreparationPipeline::new(
             std::sync::Arc::clone(&apply_state.config),
@@ -0,0 +1,229 @@
+//! Usage of the delayed receipts queue and of the outgoing buffers of a shard by receiver
+//! account, see [ReceiverQueueStats].
+//!
+//! Scanning the queues is IO intensive, so the stats are bootstrapped once and then kept up to
+//! date with the [ReceiverQueueChanges] collected while applying the chunks of the shard.
+
+use crate::congestion_control::{receipt_congestion_gas, receipt_size};
+use near_parameters::RuntimeConfig;
+use near_primitives::receipt::ReceiptOrStateStoredReceipt;
+use near_primitives::types::{AccountId, Gas};
+use near_store::trie::receipts_column_helper::{
+    DelayedReceiptQueue, ShardsOutgoingReceiptBuffer, TrieQueue,
+};
+use near_store::{StorageError, TrieAccess};
+use std::collections::HashMap;
+
+/// Receipts of one receiver account in a queue.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ReceiverQueueUsage {
+    pub num_receipts: u64,
+    /// Congestion gas of the receipts, as accounted by the congestion control.
+    pub gas: u128,
+    /// Size of the receipts, as accounted by the congestion control.
+    pub bytes: u64,
+}
+
+impl ReceiverQueueUsage {
+    fn add(&mut self, other: &ReceiverQueueUsage) {
+        self.num_receipts = self.num_receipts.saturating_add(other.num_receipts);
+        self.gas = self.gas.saturating_add(other.gas);
+        self.bytes = self.bytes.saturating_add(other.bytes);
+    }
+
+    /// Saturates, so that receipts which were in the queues before the stats were bootstrapped
+    /// can't make the usage wrap around.
+    fn remove(&mut self, other: &ReceiverQueueUsage) {
+        self.num_receipts = self.num_receipts.saturating_sub(other.num_receipts);
+        self.gas = self.gas.saturating_sub(other.gas);
+        self.bytes = self.bytes.saturating_sub(other.bytes);
+    }
+}
+
+/// Receipts added to and removed from a queue while applying a chunk, by receiver account.
+///
+/// Added and removed receipts are accumulated separately, like in `DelayedReceiptQueueWrapper`,
+/// to avoid signed arithmetic.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReceiverQueueDiff {
+    pub added: HashMap<AccountId, ReceiverQueueUsage>,
+    pub removed: HashMap<AccountId, ReceiverQueueUsage>,
+    /// Set when nothing is recorded, see `ApplyState::record_receiver_queue_changes`.
+    disabled: bool,
+}
+
+impl ReceiverQueueDiff {
+    /// Creates a diff which records the receipts only if `enabled`, so that the receiver
+    /// accounts aren't cloned while applying the chunks when nobody needs the stats.
+    pub(crate) fn new(enabled: bool) -> Self {
+        Self { disabled: !enabled, ..Self::default() }
+    }
+
+    pub(crate) fn record_added(&mut self, receiver_id: &AccountId, gas: Gas, bytes: u64) {
+        if !self.disabled {
+            record(&mut self.added, receiver_id, gas, bytes);
+        }
+    }
+
+    pub(crate) fn record_removed(&mut self, receiver_id: &AccountId, gas: Gas, bytes: u64) {
+        if !self.disabled {
+            record(&mut self.removed, receiver_id, gas, bytes);
+        }
+    }
+
+    fn apply_to(&self, usage: &mut HashMap<AccountId, ReceiverQueueUsage>) {
+        for (receiver_id, added) in &self.added {
+            usage.entry(receiver_id.clone()).or_default().add(added);
+        }
+        for (receiver_id, removed) in &self.removed {
+            if let Some(receiver_usage) = usage.get_mut(receiver_id) {
+                receiver_usage.remove(removed);
+                if receiver_usage.num_receipts == 0 {
+                    usage.remove(receiver_id);
+                }
+            }
+        }
+    }
+}
+
+fn record(
+    usage: &mut HashMap<AccountId, ReceiverQueueUsage>,
+    receiver_id: &AccountId,
+    gas: Gas,
+    bytes: u64,
+) {
+    let receipt_usage = ReceiverQueueUsage { num_receipts: 1, gas: gas.into(), bytes };
+    match usage.get_mut(receiver_id) {
+        Some(receiver_usage) => receiver_usage.add(&receipt_usage),
+        None => {
+            usage.insert(receiver_id.clone(), receipt_usage);
+        }
+    }
+}
+
+/// Changes of the delayed receipts queue and of the outgoing buffers of a shard made by
+/// applying a chunk.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReceiverQueueChanges {
+    pub delayed: ReceiverQueueDiff,
+    pub buffered: ReceiverQueueDiff,
+}
+
+/// Usage of the delayed receipts queue and of the outgoing buffers of a shard by receiver
+/// account, at some state root. Only the receivers with receipts in the queues are kept.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReceiverQueueStats {
+    pub delayed: HashMap<AccountId, ReceiverQueueUsage>,
+    /// Receipts in the outgoing buffers to all the shards, by their final receiver.
+    pub buffered: HashMap<AccountId, ReceiverQueueUsage>,
+}
+
+impl ReceiverQueueStats {
+    /// Iterates all the receipts of the delayed receipts queue and of the outgoing buffers.
+    ///
+    /// This is an IO intensive operation! Only do it to bootstrap the stats, and then keep them
+    /// up to date with [ReceiverQueueStats::apply_changes].
+    pub fn bootstrap(trie: &dyn TrieAccess, config: &RuntimeConfig) -> Result<Self, StorageError> {
+        let mut stats = Self::default();
+        let delayed_receipt_queue = DelayedReceiptQueue::load(trie)?;
+        for receipt in delayed_receipt_queue.iter(trie, false) {
+            record_stored_receipt(&mut stats.delayed, &receipt?, config)?;
+        }
+        let mut outgoing_buffers = ShardsOutgoingReceiptBuffer::load(trie)?;
+        for shard_id in outgoing_buffers.shards() {
+            for receipt in outgoing_buffers.to_shard(shard_id).iter(trie, false) {
+                record_stored_receipt(&mut stats.buffered, &receipt?, config)?;
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Updates the stats with the changes made by applying a chunk.
+    pub fn apply_changes(&mut self, changes: &ReceiverQueueChanges) {
+        changes.delayed.apply_to(&mut self.delayed);
+        changes.buffered.apply_to(&mut self.buffered);
+    }
+
+    /// Returns the `n` receivers with the highest `key` in `usage`, in descending order.
+    pub fn top_receivers<K: Ord>(
+        usage: &HashMap<AccountId, ReceiverQueueUsage>,
+        n: usize,
+        key: impl Fn(&ReceiverQueueUsage) -> K,
+    ) -> Vec<(AccountId, ReceiverQueueUsage)> {
+        let mut receivers: Vec<_> =
+            usage.iter().map(|(receiver_id, usage)| (receiver_id.clone(), *usage)).collect();
+        receivers.sort_by(|(a_id, a), (b_id, b)| key(b).cmp(&key(a)).then_with(|| a_id.cmp(b_id)));
+        receivers.truncate(n);
+        receivers
+    }
+}
+
+fn record_stored_receipt(
+    usage: &mut HashMap<AccountId, ReceiverQueueUsage>,
+    receipt: &ReceiptOrStateStoredReceipt,
+    config: &RuntimeConfig,
+) -> Result<(), StorageError> {
+    let overflow = |_| {
+        StorageError::StorageInconsistentState(
+            "Calculations on stored receipt overflows calculations".to_owned(),
+        )
+    };
+    let gas = receipt_congestion_gas(receipt, config).map_err(overflow)?;
+    let bytes = receipt_size(receipt).map_err(overflow)?;
+    record(usage, receipt.get_receipt().receiver_id(), gas, bytes);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ReceiverQueueChanges, ReceiverQueueDiff, ReceiverQueueStats, ReceiverQueueUsage};
+    use near_primitives::types::AccountId;
+
+    #[test]
+    fn test_apply_receiver_queue_changes() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let bob: AccountId = "bob.near".parse().unwrap();
+        let mut stats = ReceiverQueueStats::default();
+
+        let mut changes = ReceiverQueueChanges::default();
+        changes.delayed.record_added(&alice, 100, 10);
+        changes.delayed.record_added(&alice, 200, 20);
+        changes.delayed.record_added(&bob, 1000, 5);
+        changes.buffered.record_added(&bob, 50, 500);
+        stats.apply_changes(&changes);
+        assert_eq!(
+            stats.delayed[&alice],
+            ReceiverQueueUsage { num_receipts: 2, gas: 300, bytes: 30 }
+        );
+        assert_eq!(
+            stats.buffered[&bob],
+            ReceiverQueueUsage { num_receipts: 1, gas: 50, bytes: 500 }
+        );
+
+        let by_gas = ReceiverQueueStats::top_receivers(&stats.delayed, 1, |usage| usage.gas);
+        assert_eq!(by_gas, vec![(bob.clone(), stats.delayed[&bob])]);
+        let by_bytes = ReceiverQueueStats::top_receivers(&stats.delayed, 2, |usage| usage.bytes);
+        assert_eq!(by_bytes.iter().map(|(id, _)| id).collect::<Vec<_>>(), vec![&alice, &bob]);
+
+        // Receivers without receipts are dropped, and receipts queued before the stats were
+        // bootstrapped don't make the usage wrap around.
+        let mut changes = ReceiverQueueChanges::default();
+        changes.delayed.record_removed(&alice, 100, 10);
+        changes.delayed.record_removed(&bob, 1000, 5);
+        changes.buffered.record_removed(&bob, 50, 500);
+        changes.buffered.record_removed(&bob, 50, 500);
+        changes.buffered.record_removed(&alice, 50, 500);
+        stats.apply_changes(&changes);
+        assert_eq!(
+            stats.delayed[&alice],
+            ReceiverQueueUsage { num_receipts: 1, gas: 200, bytes: 20 }
+        );
+        assert!(!stats.delayed.contains_key(&bob));
+        assert!(stats.buffered.is_empty());
+
+        let mut disabled = ReceiverQueueDiff::new(false);
+        disabled.record_added(&alice, 100, 10);
+        disabled.record_removed(&bob, 100, 10);
+        assert!(disabled.added.is_empty() && disabled.removed.is_empty());
+    }
+}
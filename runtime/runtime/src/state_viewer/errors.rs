@@ -52,6 +52,8 @@ pub enum CallFunctionError {
     InternalError { error_message: String },
     #[error("VM error occurred: #{error_message}")]
     VMError { error_message: String },
+    #[error("Invalid state override: {error_message}")]
+    InvalidStateOverride { error_message: String },
 }
 
 impl From<ViewAccountError> for ViewContractCodeError {
@@ -2,10 +2,10 @@ use crate::actions::execute_function_call;
 use crate::ext::RuntimeExt;
 use crate::pipelining::ReceiptPreparationPipeline;
 use crate::receipt_manager::ReceiptManager;
-use crate::ApplyState;
+use crate::{get_storage_stake_reserve, ApplyState};
 use near_crypto::{KeyType, PublicKey};
-use near_parameters::RuntimeConfigStore;
-use near_primitives::account::{AccessKey, Account};
+use near_parameters::{RuntimeConfig, RuntimeConfigStore};
+use near_primitives::account::{AccessKey, AccessKeyPermission, Account};
 use near_primitives::apply::ApplyChunkReason;
 use near_primitives::bandwidth_scheduler::BlockBandwidthRequests;
 use near_primitives::borsh::BorshDeserialize;
@@ -13,17 +13,20 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum, ReceiptV1};
 use near_primitives::runtime::migration_data::{MigrationData, MigrationFlags};
 use near_primitives::transaction::FunctionCallAction;
-use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::trie_key::{trie_key_parsers, TrieKey};
 use near_primitives::types::{
-    AccountId, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, ShardId,
+    AccountId, Balance, BlockHeight, EpochHeight, EpochId, EpochInfoProvider, Gas, ShardId,
 };
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{StateItem, ViewStateResult};
+use near_primitives::views::{AccountBalanceView, StateItem, StateOverride, ViewStateResult};
 use near_primitives_core::config::ViewConfig;
-use near_store::{get_access_key, get_account, TrieUpdate};
+use near_store::{get_access_key, get_account, set_account, TrieUpdate};
 use near_vm_runner::logic::{ProtocolVersion, ReturnData};
-use near_vm_runner::{ContractCode, ContractRuntimeCache};
-use std::{str, sync::Arc, time::Instant};
+use near_vm_runner::{
+    precompile_contract, ContractCode, ContractRuntimeCache, MockContractRuntimeCache,
+};
+use std::time::{Duration, Instant};
+use std::{str, sync::Arc};
 use tracing::debug;
 
 pub mod errors;
@@ -51,11 +54,22 @@ pub struct ViewApplyState {
     pub cache: Option<Box<dyn ContractRuntimeCache>>,
 }
 
+/// Limits on the contract code provided in the state override of a simulated function call.
+#[derive(Clone, Debug)]
+pub struct SimulationLimits {
+    /// Maximum size of the contract code in bytes.
+    pub max_code_size: usize,
+    /// Maximum time spent compiling the contract code.
+    pub max_compile_time: Duration,
+}
+
 pub struct TrieViewer {
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     state_size_limit: Option<u64>,
     /// Gas limit used when handling call_function queries.
     max_gas_burnt_view: Gas,
+    /// Limits of simulated function calls. None if the simulations are disabled.
+    simulation_limits: Option<SimulationLimits>,
 }
 
 impl Default for TrieViewer {
@@ -63,7 +77,7 @@ impl Default for TrieViewer {
         let config_store = RuntimeConfigStore::new(None);
         let latest_runtime_config = config_store.get_config(PROTOCOL_VERSION);
         let max_gas_burnt = latest_runtime_config.wasm_config.limit_config.max_gas_burnt;
-        Self { state_size_limit: None, max_gas_burnt_view: max_gas_burnt }
+        Self { state_size_limit: None, max_gas_burnt_view: max_gas_burnt, simulation_limits: None }
     }
 }
 
@@ -71,7 +85,13 @@ impl TrieViewer {
     pub fn new(state_size_limit: Option<u64>, max_gas_burnt_view: Option<Gas>) -> Self {
         let max_gas_burnt_view =
             max_gas_burnt_view.unwrap_or_else(|| TrieViewer::default().max_gas_burnt_view);
-        Self { state_size_limit, max_gas_burnt_view }
+        Self { state_size_limit, max_gas_burnt_view, simulation_limits: None }
+    }
+
+    /// Enables simulated function calls with state overrides, see [`Self::apply_state_override`].
+    pub fn with_simulation_limits(mut self, simulation_limits: Option<SimulationLimits>) -> Self {
+        self.simulation_limits = simulation_limits;
+        self
     }
 
     pub fn view_account(
@@ -140,6 +160,41 @@ impl TrieViewer {
         access_keys
     }
 
+    /// Splits the balance of the account by what it can spend it on, by the same rules as
+    /// the runtime uses to check the storage staking of the account.
+    pub fn view_account_balance(
+        &self,
+        state_update: &TrieUpdate,
+        account_id: &AccountId,
+        runtime_config: &RuntimeConfig,
+        current_protocol_version: ProtocolVersion,
+    ) -> Result<AccountBalanceView, errors::ViewAccountError> {
+        let account = self.view_account(state_update, account_id)?;
+        let storage_reserve =
+            get_storage_stake_reserve(&account, runtime_config, current_protocol_version).map_err(
+                |error_message| errors::ViewAccountError::InternalError { error_message },
+            )?;
+        let liquid = account.amount().saturating_sub(storage_reserve);
+        let access_keys = self.view_access_keys(state_update, account_id).map_err(|err| {
+            errors::ViewAccountError::InternalError { error_message: err.to_string() }
+        })?;
+        let function_call_allowance = access_keys
+            .iter()
+            .filter_map(|(_, access_key)| match &access_key.permission {
+                AccessKeyPermission::FunctionCall(permission) => permission.allowance,
+                AccessKeyPermission::FullAccess => None,
+            })
+            .fold(0, Balance::saturating_add);
+        Ok(AccountBalanceView {
+            amount: account.amount(),
+            locked: account.locked(),
+            storage_reserve,
+            liquid,
+            function_call_allowance,
+            unpledged_liquid: liquid.saturating_sub(function_call_allowance),
+        })
+    }
+
     pub fn view_state(
         &self,
         state_update: &TrieUpdate,
@@ -181,6 +236,95 @@ impl TrieViewer {
         Ok(ViewStateResult { values, proof })
     }
 
+    /// Modifies the state of `account_id` according to `state_override`, so
+    /// that a subsequent `call_function` on the same `state_update` observes
+    /// the overridden state. The update is never committed, so nothing is
+    /// persisted.
+    ///
+    /// If the contract code is overridden, it is compiled into a new in-memory
+    /// cache which is returned. The call must use that cache instead of the
+    /// node's persistent one, so that compiled user-provided code is never
+    /// written to disk.
+    pub fn apply_state_override(
+        &self,
+        state_update: &mut TrieUpdate,
+        account_id: &AccountId,
+        state_override: &StateOverride,
+    ) -> Result<Option<Box<dyn ContractRuntimeCache>>, errors::CallFunctionError> {
+        let Some(limits) = &self.simulation_limits else {
+            return Err(errors::CallFunctionError::InvalidStateOverride {
+                error_message: "simulated function calls are disabled on this node".to_string(),
+            });
+        };
+        let mut account = get_account(state_update, account_id)?.ok_or_else(|| {
+            errors::CallFunctionError::AccountDoesNotExist {
+                requested_account_id: account_id.clone(),
+            }
+        })?;
+        if let Some(balance) = state_override.balance {
+            account.set_amount(balance);
+        }
+        let mut cache = None;
+        if let Some(code) = &state_override.code {
+            if code.len() > limits.max_code_size {
+                return Err(errors::CallFunctionError::InvalidStateOverride {
+                    error_message: format!(
+                        "contract code of {} bytes exceeds the limit of {} bytes",
+                        code.len(),
+                        limits.max_code_size
+                    ),
+                });
+            }
+            let code = ContractCode::new(code.clone(), None);
+            cache = Some(self.compile_override_code(&code, limits.max_compile_time)?);
+            account.set_code_hash(*code.hash());
+            state_update.set_code(account_id.clone(), &code);
+            // Makes the code visible to the receipt preparation pipeline.
+            state_update.record_contract_deploy(code);
+        }
+        for StateItem { key, value } in &state_override.state {
+            let key = TrieKey::ContractData { account_id: account_id.clone(), key: key.to_vec() };
+            state_update.set(key, value.to_vec());
+        }
+        set_account(state_update, account_id.clone(), &account);
+        Ok(cache)
+    }
+
+    /// Compiles the code into a new in-memory cache, giving up after `max_compile_time`.
+    ///
+    /// The compilation can't be interrupted, so on timeout it finishes in the background, but
+    /// its result is discarded. The code size limit bounds how long that can take.
+    fn compile_override_code(
+        &self,
+        code: &ContractCode,
+        max_compile_time: Duration,
+    ) -> Result<Box<dyn ContractRuntimeCache>, errors::CallFunctionError> {
+        let config = RuntimeConfigStore::new(None).get_config(PROTOCOL_VERSION).wasm_config.clone();
+        let cache = MockContractRuntimeCache::default();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let code = ContractCode::new(code.code().to_vec(), Some(*code.hash()));
+        let compile_cache = cache.clone();
+        rayon::spawn(move || {
+            let result = precompile_contract(&code, config, Some(&compile_cache));
+            // The receiver is gone if the compilation has timed out.
+            let _ = sender.send(result);
+        });
+        match receiver.recv_timeout(max_compile_time) {
+            Ok(Ok(Ok(_))) => Ok(cache.handle()),
+            Ok(Ok(Err(err))) => {
+                Err(errors::CallFunctionError::VMError { error_message: format!("{err:?}") })
+            }
+            Ok(Err(err)) => {
+                Err(errors::CallFunctionError::InternalError { error_message: err.to_string() })
+            }
+            Err(_) => Err(errors::CallFunctionError::InvalidStateOverride {
+                error_message: format!(
+                    "compilation of the contract code took longer than {max_compile_time:?}"
+                ),
+            }),
+        }
+    }
+
     pub fn call_function(
         &self,
         mut state_update: TrieUpdate,
@@ -226,6 +370,7 @@ impl TrieViewer {
             migration_flags: MigrationFlags::default(),
             congestion_info: Default::default(),
             bandwidth_requests: BlockBandwidthRequests::empty(),
+            record_receiver_queue_changes: false,
         };
         let function_call = FunctionCallAction {
             method_name: method_name.to_string(),
@@ -134,6 +134,7 @@ fn setup_runtime_for_shard(
         migration_flags: MigrationFlags::default(),
         congestion_info,
         bandwidth_requests: BlockBandwidthRequests::empty(),
+        record_receiver_queue_changes: false,
     };
 
     (runtime, tries, root, apply_state, signers)
@@ -44,20 +44,7 @@ pub fn check_storage_stake(
     runtime_config: &RuntimeConfig,
     current_protocol_version: ProtocolVersion,
 ) -> Result<(), StorageStakingError> {
-    #[cfg(not(feature = "protocol_feature_nonrefundable_transfer_nep491"))]
-    let billable_storage_bytes = account.storage_usage();
-    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
-    let billable_storage_bytes =
-        account.storage_usage().saturating_sub(account.permanent_storage_bytes());
-
-    let required_amount = Balance::from(billable_storage_bytes)
-        .checked_mul(runtime_config.storage_amount_per_byte())
-        .ok_or_else(|| {
-            format!(
-                "Account's billable storage usage {} overflows multiplication",
-                billable_storage_bytes
-            )
-        })
+    let required_amount = get_required_storage_stake(account, runtime_config)
         .map_err(StorageStakingError::StorageError)?;
     let available_amount = account
         .amount()
@@ -82,6 +69,44 @@ pub fn check_storage_stake(
     }
 }
 
+/// Returns the part of the account's amount which has to stay on the account for storage
+/// staking, i.e. the cost of its storage which isn't covered by its locked stake, by the same
+/// rules as `check_storage_stake`. The rest of the amount is liquid.
+pub fn get_storage_stake_reserve(
+    account: &Account,
+    runtime_config: &RuntimeConfig,
+    current_protocol_version: ProtocolVersion,
+) -> Result<Balance, String> {
+    if checked_feature!("stable", ZeroBalanceAccount, current_protocol_version)
+        && is_zero_balance_account(account)
+    {
+        return Ok(0);
+    }
+    let required_amount = get_required_storage_stake(account, runtime_config)?;
+    Ok(required_amount.saturating_sub(account.locked()))
+}
+
+/// Cost of the billable storage of the account.
+fn get_required_storage_stake(
+    account: &Account,
+    runtime_config: &RuntimeConfig,
+) -> Result<Balance, String> {
+    #[cfg(not(feature = "protocol_feature_nonrefundable_transfer_nep491"))]
+    let billable_storage_bytes = account.storage_usage();
+    #[cfg(feature = "protocol_feature_nonrefundable_transfer_nep491")]
+    let billable_storage_bytes =
+        account.storage_usage().saturating_sub(account.permanent_storage_bytes());
+
+    Balance::from(billable_storage_bytes)
+        .checked_mul(runtime_config.storage_amount_per_byte())
+        .ok_or_else(|| {
+            format!(
+                "Account's billable storage usage {} overflows multiplication",
+                billable_storage_bytes
+            )
+        })
+}
+
 /// Zero Balance Account introduced in NEP 448 https://github.com/near/NEPs/pull/448
 /// An account is a zero balance account if and only if the account uses no more than `ZERO_BALANCE_ACCOUNT_STORAGE_LIMIT` bytes
 fn is_zero_balance_account(account: &Account) -> bool {
@@ -854,6 +879,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_storage_stake_reserve() {
+        let config = RuntimeConfig::test();
+        let storage_usage = 1000;
+        let required = Balance::from(storage_usage) * config.storage_amount_per_byte();
+        let new_account = |locked| {
+            Account::new(
+                10 * required,
+                locked,
+                0,
+                CryptoHash::default(),
+                storage_usage,
+                PROTOCOL_VERSION,
+            )
+        };
+        // The locked stake covers the storage first.
+        let account = new_account(required / 4);
+        assert_eq!(
+            get_storage_stake_reserve(&account, &config, PROTOCOL_VERSION).unwrap(),
+            required - required / 4
+        );
+        let account = new_account(2 * required);
+        assert_eq!(get_storage_stake_reserve(&account, &config, PROTOCOL_VERSION).unwrap(), 0);
+        // Zero balance accounts don't reserve anything.
+        let account = Account::new(
+            0,
+            0,
+            0,
+            CryptoHash::default(),
+            ZERO_BALANCE_ACCOUNT_STORAGE_LIMIT,
+            PROTOCOL_VERSION,
+        );
+        assert_eq!(get_storage_stake_reserve(&account, &config, PROTOCOL_VERSION).unwrap(), 0);
+    }
+
     // Transactions
 
     #[test]
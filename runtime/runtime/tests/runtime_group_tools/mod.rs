@@ -125,6 +125,7 @@ impl StandaloneRuntime {
             migration_flags: MigrationFlags::default(),
             congestion_info,
             bandwidth_requests: BlockBandwidthRequests::empty(),
+            record_receiver_queue_changes: false,
         };
 
         Self {
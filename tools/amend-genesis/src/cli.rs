@@ -83,6 +83,10 @@ pub struct AmendGenesisCommand {
     /// max_gas_price to set in the output genesis file
     #[clap(long)]
     max_gas_price: Option<u128>,
+    /// rewrite the output records file in the canonical form, see `canonicalize-genesis-records`.
+    /// Note that this loads all the records in memory
+    #[clap(long)]
+    canonicalize_records: bool,
 }
 
 impl AmendGenesisCommand {
@@ -113,6 +117,35 @@ impl AmendGenesisCommand {
             &genesis_changes,
             self.num_bytes_account.unwrap_or(100),
             self.num_extra_bytes_record.unwrap_or(40),
+            self.canonicalize_records,
         )
     }
 }
+
+/// Rewrite a genesis records file in the canonical form shared by the genesis tools: the
+/// records of every account grouped and sorted by type and key, without duplicates, and the
+/// delayed receipts at the end. Access keys given several times with the same permission are
+/// merged, other conflicting records are an error. All the records are loaded in memory.
+#[derive(clap::Parser)]
+pub struct CanonicalizeRecordsCommand {
+    /// path to the input records file
+    #[clap(long)]
+    records_file_in: PathBuf,
+    /// path to the output records file, which may be the same as the input one
+    #[clap(long)]
+    records_file_out: PathBuf,
+}
+
+impl CanonicalizeRecordsCommand {
+    pub fn run(self) -> anyhow::Result<()> {
+        let stats = near_chain_configs::canonicalize_records_file(
+            &self.records_file_in,
+            &self.records_file_out,
+        )?;
+        println!(
+            "removed {} duplicate records and merged {} access keys",
+            stats.duplicates_removed, stats.access_keys_merged
+        );
+        Ok(())
+    }
+}
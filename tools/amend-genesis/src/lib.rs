@@ -15,12 +15,12 @@ use num_rational::Rational32;
 use serde::ser::{SerializeSeq, Serializer};
 use std::collections::{hash_map, HashMap};
 use std::fs::File;
-use std::io::{BufReader, BufWriter};
+use std::io::{BufReader, BufWriter, Write};
 use std::path::Path;
 
 mod cli;
 
-pub use cli::AmendGenesisCommand;
+pub use cli::{AmendGenesisCommand, CanonicalizeRecordsCommand};
 
 // while parsing the --extra-records file we will keep track of the records we see for each
 // account here, and then at the end figure out what to put in the storage_usage field
@@ -298,6 +298,7 @@ pub fn amend_genesis(
     genesis_changes: &GenesisChanges,
     num_bytes_account: u64,
     num_extra_bytes_record: u64,
+    canonicalize_records: bool,
 ) -> anyhow::Result<()> {
     let mut genesis = Genesis::from_file(genesis_file_in, GenesisValidationMode::UnsafeFast)?;
 
@@ -424,13 +425,22 @@ pub fn amend_genesis(
     }
     genesis.to_file(genesis_file_out);
     records_seq.end()?;
+    if canonicalize_records {
+        records_ser.into_inner().flush()?;
+        let stats =
+            near_chain_configs::canonicalize_records_file(records_file_out, records_file_out)
+                .context("failed canonicalizing the output records")?;
+        tracing::info!(?stats, "canonicalized the output records");
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod test {
     use anyhow::Context;
-    use near_chain_configs::{get_initial_supply, Genesis, GenesisConfig, NEAR_BASE};
+    use near_chain_configs::{
+        canonicalize_records, get_initial_supply, Genesis, GenesisConfig, NEAR_BASE,
+    };
     use near_primitives::hash::CryptoHash;
     use near_primitives::shard_layout::ShardLayout;
     use near_primitives::state_record::StateRecord;
@@ -715,6 +725,7 @@ mod test {
                 &crate::GenesisChanges::default(),
                 100,
                 40,
+                true,
             )
             .context("amend_genesis() failed")?;
 
@@ -723,6 +734,8 @@ mod test {
             let got_records: Vec<StateRecord> = serde_json::from_str(&got_records)
                 .context("failed deserializing --records-file-out")?;
 
+            let (canonical_records, _) = canonicalize_records(got_records.clone())?;
+            assert_eq!(canonical_records, got_records, "output records are not canonical");
             compare_records(got_records, wanted_records)
         }
     }
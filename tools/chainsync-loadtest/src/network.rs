@@ -207,6 +207,7 @@ impl Network {
                         shard_id: ch.shard_id(),
                         only_archival: false,
                         min_height: ch.height_included(),
+                        height: ch.height_created(),
                     },
                     request: PartialEncodedChunkRequestMsg {
                         chunk_hash: ch.chunk_hash(),
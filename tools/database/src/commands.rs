@@ -6,10 +6,14 @@ use crate::analyze_contract_sizes::AnalyzeContractSizesCommand;
 use crate::analyze_delayed_receipt::AnalyzeDelayedReceiptCommand;
 use crate::compact::RunCompactionCommand;
 use crate::corrupt::CorruptStateSnapshotCommand;
+use crate::encrypt_file::EncryptFileCommand;
 use crate::make_snapshot::MakeSnapshotCommand;
 use crate::memtrie::LoadMemTrieCommand;
+use crate::reencrypt::ReencryptCommand;
+use crate::resharding_dry_run::ReshardingDryRunCommand;
 use crate::resharding_v2::ReshardingV2Command;
 use crate::run_migrations::RunMigrationsCommand;
+use crate::shard_uid_mapping::CheckShardUIdMappingsCommand;
 use crate::state_perf::StatePerfCommand;
 use crate::write_to_db::WriteCryptoHashCommand;
 use clap::Parser;
@@ -34,15 +38,25 @@ enum SubCommand {
     /// Change DbKind of hot or cold db.
     ChangeDbKind(ChangeDbKindCommand),
 
+    /// Check the State mapping of the shards left by reshardings, and optionally repair it.
+    #[clap(name = "check-shard-uid-mappings")]
+    CheckShardUIdMappings(CheckShardUIdMappingsCommand),
+
     /// Run SST file compaction on database
     CompactDatabase(RunCompactionCommand),
 
     /// Corrupt the state snapshot.
     CorruptStateSnapshot(CorruptStateSnapshotCommand),
 
+    /// Encrypt a file, e.g. the validator key, with the store encryption key.
+    EncryptFile(EncryptFileCommand),
+
     /// Make snapshot of the database
     MakeSnapshot(MakeSnapshotCommand),
 
+    /// Re-encrypt the encrypted columns and the validator key with the current encryption key.
+    Reencrypt(ReencryptCommand),
+
     /// Run migrations
     RunMigrations(RunMigrationsCommand),
 
@@ -64,6 +78,9 @@ enum SubCommand {
 
     /// Perform on demand resharding V2
     Resharding(ReshardingV2Command),
+
+    /// Estimate the outcome of splitting a shard at a candidate boundary account.
+    ReshardingDryRun(ReshardingDryRunCommand),
 }
 
 impl DatabaseCommand {
@@ -76,12 +93,15 @@ impl DatabaseCommand {
             SubCommand::AnalyseDataSizeDistribution(cmd) => cmd.run(home),
             SubCommand::AnalyseGasUsage(cmd) => cmd.run(home, genesis_validation),
             SubCommand::ChangeDbKind(cmd) => cmd.run(home, genesis_validation),
+            SubCommand::CheckShardUIdMappings(cmd) => cmd.run(home, genesis_validation),
             SubCommand::CompactDatabase(cmd) => cmd.run(home),
             SubCommand::CorruptStateSnapshot(cmd) => cmd.run(home),
+            SubCommand::EncryptFile(cmd) => cmd.run(home),
             SubCommand::MakeSnapshot(cmd) => {
                 let near_config = load_config(home, genesis_validation);
                 cmd.run(home, &near_config.config.store, near_config.config.archival_config())
             }
+            SubCommand::Reencrypt(cmd) => cmd.run(home, genesis_validation),
             SubCommand::RunMigrations(cmd) => cmd.run(home, genesis_validation),
             SubCommand::StatePerf(cmd) => cmd.run(home),
             SubCommand::LoadMemTrie(cmd) => cmd.run(home, genesis_validation),
@@ -93,6 +113,7 @@ impl DatabaseCommand {
                 let near_config = load_config(home, genesis_validation);
                 cmd.run(near_config, home)
             }
+            SubCommand::ReshardingDryRun(cmd) => cmd.run(home, genesis_validation),
         }
     }
 }
@@ -0,0 +1,36 @@
+use anyhow::Context;
+use clap::Parser;
+use nearcore::config::{Config, CONFIG_FILENAME};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+pub(crate) struct EncryptFileCommand {
+    /// File to encrypt, e.g. the validator key file. If relative, resolved relative to the home
+    /// directory.
+    file: PathBuf,
+
+    /// Where to write the encrypted file. By default the file is encrypted in place.
+    #[arg(long)]
+    output: Option<PathBuf>,
+}
+
+impl EncryptFileCommand {
+    pub(crate) fn run(&self, home: &PathBuf) -> anyhow::Result<()> {
+        let config = Config::from_file_skip_validation(&home.join(CONFIG_FILENAME))?;
+        let encryption = config
+            .store
+            .encryption
+            .as_ref()
+            .context("store.encryption is not set in the config")?;
+        let cipher = encryption.load_cipher(home)?;
+        let file = home.join(&self.file);
+        let contents =
+            std::fs::read(&file).with_context(|| format!("failed to read {}", file.display()))?;
+        let encrypted = cipher.encrypt_validator_key(&contents)?;
+        let output = self.output.as_ref().map_or_else(|| file.clone(), |output| home.join(output));
+        std::fs::write(&output, encrypted)
+            .with_context(|| format!("failed to write {}", output.display()))?;
+        eprintln!("Encrypted {} into {}", file.display(), output.display());
+        Ok(())
+    }
+}
@@ -8,10 +8,14 @@ mod block_iterators;
 pub mod commands;
 mod compact;
 mod corrupt;
+mod encrypt_file;
 mod make_snapshot;
 mod memtrie;
+mod reencrypt;
+mod resharding_dry_run;
 mod resharding_v2;
 mod run_migrations;
+mod shard_uid_mapping;
 mod state_perf;
 mod utils;
 mod write_to_db;
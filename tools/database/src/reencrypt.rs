@@ -0,0 +1,63 @@
+use anyhow::Context;
+use near_chain_configs::GenesisValidationMode;
+use near_store::DBCol;
+use std::path::Path;
+
+/// Number of values rewritten in a single transaction.
+const BATCH_SIZE: usize = 10_000;
+
+#[derive(clap::Args)]
+pub(crate) struct ReencryptCommand {}
+
+impl ReencryptCommand {
+    pub(crate) fn run(
+        &self,
+        home_dir: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        let mut near_config = nearcore::config::load_config(&home_dir, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let encryption = near_config
+            .config
+            .store
+            .encryption
+            .clone()
+            .context("store.encryption is not set in the config")?;
+
+        let storage = nearcore::open_storage(home_dir, &mut near_config)?;
+        let store = storage.get_hot_store();
+        for col in encryption.columns()? {
+            let count = reencrypt_column(&store, col)?;
+            eprintln!("Re-encrypted {count} values of column {col}");
+        }
+
+        if encryption.encrypt_validator_key {
+            let cipher = encryption.load_cipher(home_dir)?;
+            let file = home_dir.join(&near_config.config.validator_key_file);
+            let contents = std::fs::read(&file)
+                .with_context(|| format!("failed to read {}", file.display()))?;
+            let contents = cipher.decrypt_validator_key(&contents)?;
+            std::fs::write(&file, cipher.encrypt_validator_key(&contents)?)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+            eprintln!("Re-encrypted {}", file.display());
+        }
+        Ok(())
+    }
+}
+
+/// Rewrites every value of the column. The store decrypts the values with whichever key they
+/// were written with and encrypts them again with the current key.
+fn reencrypt_column(store: &near_store::Store, col: DBCol) -> anyhow::Result<usize> {
+    let mut count = 0;
+    let mut update = store.store_update();
+    for item in store.iter_raw_bytes(col) {
+        let (key, value) = item?;
+        update.set_raw_bytes(col, &key, &value);
+        count += 1;
+        if count % BATCH_SIZE == 0 {
+            std::mem::replace(&mut update, store.store_update()).commit()?;
+        }
+    }
+    update.commit()?;
+    Ok(count)
+}
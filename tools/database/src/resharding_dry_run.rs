@@ -0,0 +1,110 @@
+use crate::utils::open_rocksdb;
+use anyhow::Context;
+use bytesize::ByteSize;
+use near_chain::resharding::dry_run::{estimate_split_shard, ChildShardEstimate};
+use near_chain::types::RuntimeAdapter;
+use near_chain_configs::GenesisValidationMode;
+use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_primitives::block::Tip;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::shard_layout::get_block_shard_uid;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{AccountId, ShardId};
+use near_store::adapter::StoreAdapter;
+use near_store::flat::FlatStorageStatus;
+use near_store::{DBCol, ShardUId, HEAD_KEY};
+use nearcore::{NightshadeRuntime, NightshadeRuntimeExt};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Command to estimate the outcome of splitting a shard at a candidate boundary account,
+/// without modifying the database.
+/// Example usage: neard database resharding-dry-run --shard-id 2 --boundary-account foo.near
+#[derive(clap::Parser)]
+pub struct ReshardingDryRunCommand {
+    /// Id of the shard to split, in the shard layout of the head.
+    #[clap(long)]
+    shard_id: ShardId,
+    /// Boundary account of the split.
+    #[clap(long)]
+    boundary_account: AccountId,
+    /// Load the memtrie of the shard and retain the children from it, like nodes tracking
+    /// the shard do. Otherwise the on-disk trie is used, which is slower.
+    #[clap(long)]
+    load_memtrie: bool,
+}
+
+impl ReshardingDryRunCommand {
+    pub fn run(
+        &self,
+        home: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        let near_config = nearcore::config::load_config(&home, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let rocksdb = Arc::new(open_rocksdb(home, near_store::Mode::ReadOnly)?);
+        let store = near_store::NodeStorage::new(rocksdb).get_hot_store();
+        let genesis_config = &near_config.genesis.config;
+
+        let head = store
+            .get_ser::<Tip>(DBCol::BlockMisc, HEAD_KEY)?
+            .ok_or_else(|| anyhow::anyhow!("Head not found"))?
+            .last_block_hash;
+        let block_header = store
+            .get_ser::<BlockHeader>(DBCol::BlockHeader, &borsh::to_vec(&head).unwrap())?
+            .ok_or_else(|| anyhow::anyhow!("Block header not found"))?;
+        let epoch_manager =
+            EpochManager::new_arc_handle(store.clone(), &genesis_config, Some(home));
+        let shard_layout = epoch_manager.get_shard_layout(block_header.epoch_id())?;
+        let shard_uid = ShardUId::from_shard_id_and_layout(self.shard_id, &shard_layout);
+
+        // The state is read at the flat storage head, so that the trie and the flat storage
+        // agree, and the memtrie can be loaded at that root.
+        let flat_head = match store.flat_store().get_flat_storage_status(shard_uid)? {
+            FlatStorageStatus::Ready(status) => status.flat_head,
+            status => anyhow::bail!("Flat storage of {shard_uid} is not ready: {status:?}"),
+        };
+        let chunk_extra = store
+            .get_ser::<ChunkExtra>(
+                DBCol::ChunkExtra,
+                &get_block_shard_uid(&flat_head.hash, &shard_uid),
+            )?
+            .ok_or_else(|| anyhow::anyhow!("Chunk extra of {shard_uid} not found"))?;
+        let state_root = *chunk_extra.state_root();
+
+        let runtime = NightshadeRuntime::from_config(home, store, &near_config, epoch_manager)
+            .context("could not create the transaction runtime")?;
+        let tries = runtime.get_tries();
+        if self.load_memtrie {
+            println!("Loading memtrie of {shard_uid}...");
+            tries.load_mem_trie(&shard_uid, None, true)?;
+        }
+
+        println!(
+            "Splitting {shard_uid} at {} at block {} (height {})...",
+            self.boundary_account, flat_head.hash, flat_head.height
+        );
+        let estimate = estimate_split_shard(
+            &tries,
+            shard_uid,
+            state_root,
+            &self.boundary_account,
+            &near_config.config.resharding_config,
+        )?;
+        print_child("Left child", &estimate.left_child);
+        print_child("Right child", &estimate.right_child);
+        println!("Retain duration: {:?}", estimate.retain_duration);
+        println!("Flat state scan duration: {:?}", estimate.flat_state_scan_duration);
+        println!("Estimated split duration: {:?}", estimate.estimated_split_duration);
+        Ok(())
+    }
+}
+
+fn print_child(name: &str, child: &ChildShardEstimate) {
+    println!("{name}:");
+    println!("  state root: {}", child.state_root);
+    println!("  keys: {}", child.num_keys);
+    println!("  state size: {}", ByteSize::b(child.state_size));
+    println!("  accounts: {}", child.num_accounts);
+    println!("  retain proof size: {}", ByteSize::b(child.proof_size as u64));
+}
@@ -148,8 +148,13 @@ impl ReshardingV2Command {
         let client_config = config.client_config;
         let chain_config = ChainConfig {
             save_trie_changes: client_config.save_trie_changes,
+            save_account_receipts: client_config.save_account_receipts,
+            secondary_indexes: client_config.secondary_indexes,
+            save_access_key_usage: client_config.save_access_key_usage,
             background_migration_threads: client_config.client_background_migration_threads,
             resharding_config: client_config.resharding_config,
+            skip_state_transition_data_for_shards: client_config
+                .skip_state_transition_data_for_shards,
         };
         let chain = Chain::new(
             Clock::real(),
@@ -0,0 +1,58 @@
+use crate::utils::open_rocksdb;
+use near_chain::resharding::shard_uid_mapping::{
+    check_shard_uid_mappings, repair_shard_uid_mappings,
+};
+use near_chain_configs::GenesisValidationMode;
+use near_epoch_manager::EpochManager;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Command to check the State mapping of the shards against the history of the shard layouts,
+/// and optionally repair the mappings left inconsistent by interrupted reshardings.
+/// Example usage: neard database check-shard-uid-mappings --repair
+#[derive(clap::Parser)]
+pub struct CheckShardUIdMappingsCommand {
+    /// Repair the inconsistent mappings where the fix is known.
+    #[clap(long)]
+    repair: bool,
+}
+
+impl CheckShardUIdMappingsCommand {
+    pub fn run(
+        &self,
+        home: &Path,
+        genesis_validation: GenesisValidationMode,
+    ) -> anyhow::Result<()> {
+        let near_config = nearcore::config::load_config(&home, genesis_validation)
+            .unwrap_or_else(|e| panic!("Error loading config: {:#}", e));
+        let mode =
+            if self.repair { near_store::Mode::ReadWrite } else { near_store::Mode::ReadOnly };
+        let rocksdb = Arc::new(open_rocksdb(home, mode)?);
+        let store = near_store::NodeStorage::new(rocksdb).get_hot_store();
+        let genesis_config = &near_config.genesis.config;
+        let epoch_manager = EpochManager::new_arc_handle(store.clone(), genesis_config, Some(home));
+
+        let issues = check_shard_uid_mappings(
+            &store,
+            epoch_manager.as_ref(),
+            genesis_config.protocol_version,
+        )?;
+        if issues.is_empty() {
+            println!("The State mapping of all the shards is consistent");
+            return Ok(());
+        }
+        for issue in &issues {
+            println!(
+                "{} is mapped to {}: {:?}",
+                issue.shard_uid, issue.mapped_shard_uid, issue.kind
+            );
+        }
+        if self.repair {
+            let num_repaired = repair_shard_uid_mappings(&store, &issues)?;
+            println!("Repaired {num_repaired} out of {} mappings", issues.len());
+        } else {
+            println!("Found {} inconsistent mappings, run with --repair to fix them", issues.len());
+        }
+        Ok(())
+    }
+}
@@ -4,7 +4,9 @@ use anyhow::Context;
 use chrono::{DateTime, Utc};
 use near_chain::types::{RuntimeAdapter, Tip};
 use near_chain::{ChainStore, ChainStoreAccess};
-use near_chain_configs::{Genesis, GenesisConfig, GenesisValidationMode, NEAR_BASE};
+use near_chain_configs::{
+    Genesis, GenesisConfig, GenesisValidationMode, ShardAssignmentPreview, NEAR_BASE,
+};
 use near_crypto::PublicKey;
 use near_epoch_manager::{EpochManager, EpochManagerAdapter, EpochManagerHandle};
 use near_mirror::key_mapping::{map_account, map_key};
@@ -17,7 +19,7 @@ use near_primitives::borsh;
 use near_primitives::epoch_manager::{EpochConfig, EpochConfigStore};
 use near_primitives::hash::CryptoHash;
 use near_primitives::serialize::dec_format;
-use near_primitives::shard_layout::ShardUId;
+use near_primitives::shard_layout::{ShardLayout, ShardUId};
 use near_primitives::state::FlatStateValue;
 use near_primitives::state_record::StateRecord;
 use near_primitives::trie_key::col;
@@ -80,6 +82,10 @@ enum SubCommand {
     /// Recovers from a snapshot.
     /// Deletes the snapshot.
     Reset(ResetCmd),
+
+    /// Prints which shard each of the given accounts ends up in and the number of accounts
+    /// and total stake per shard. Doesn't touch the DB.
+    PreviewShardAssignment(PreviewShardAssignmentCmd),
 }
 
 #[derive(clap::Parser)]
@@ -127,6 +133,47 @@ struct SetValidatorsCmd {
     pub num_seats: Option<NumSeats>,
 }
 
+#[derive(clap::Parser)]
+struct PreviewShardAssignmentCmd {
+    /// Path to a JSON-serialized shard layout.
+    #[arg(long)]
+    pub shard_layout_file: PathBuf,
+    /// Path to the JSON list of [`Validator`] structs, in the same format as for
+    /// the set-validators sub-command.
+    #[arg(long)]
+    pub validators: Option<PathBuf>,
+    /// Additional accounts without stake.
+    #[arg(long, value_delimiter = ',')]
+    pub accounts: Vec<AccountId>,
+}
+
+impl PreviewShardAssignmentCmd {
+    fn run(&self) -> anyhow::Result<()> {
+        let shard_layout = std::fs::read_to_string(&self.shard_layout_file).with_context(|| {
+            format!("failed reading shard layout file {}", self.shard_layout_file.display())
+        })?;
+        let shard_layout: ShardLayout =
+            serde_json::from_str(&shard_layout).context("failed deserializing the shard layout")?;
+
+        let mut accounts = vec![];
+        if let Some(validators_path) = &self.validators {
+            let file = File::open(validators_path)
+                .with_context(|| format!("failed opening {}", validators_path.display()))?;
+            let validators: Vec<Validator> = serde_json::from_reader(BufReader::new(file))
+                .with_context(|| {
+                    format!("failed reading validators from {}", validators_path.display())
+                })?;
+            accounts.extend(validators.into_iter().map(|validator| {
+                (validator.account_id, validator.amount.unwrap_or(50_000 * NEAR_BASE))
+            }));
+        }
+        accounts.extend(self.accounts.iter().map(|account_id| (account_id.clone(), 0)));
+
+        print!("{}", ShardAssignmentPreview::new(&shard_layout, accounts));
+        Ok(())
+    }
+}
+
 const FORKED_ROOTS_KEY_PREFIX: &str = "FORK_TOOL_SHARD_ID:";
 
 fn parse_state_roots_key(key: &[u8]) -> anyhow::Result<ShardId> {
@@ -163,6 +210,10 @@ impl ForkNetworkCommand {
         verbose_target: Option<&str>,
         o11y_opts: &near_o11y::Options,
     ) -> anyhow::Result<()> {
+        if let SubCommand::PreviewShardAssignment(cmd) = &self.command {
+            return cmd.run();
+        }
+
         // Load config and check flat storage param
         let mut near_config = load_config(home_dir, genesis_validation)
             .unwrap_or_else(|e| panic!("Error loading config: {e:#}"));
@@ -234,6 +285,9 @@ impl ForkNetworkCommand {
             SubCommand::Reset(ResetCmd) => {
                 self.reset(near_config, home_dir)?;
             }
+            SubCommand::PreviewShardAssignment(_) => {
+                unreachable!("handled before loading the config")
+            }
         };
         Ok(())
     }
@@ -922,8 +976,10 @@ impl ForkNetworkCommand {
             num_chunk_only_producer_seats: epoch_config.num_chunk_only_producer_seats,
             minimum_validators_per_shard: epoch_config.minimum_validators_per_shard,
             minimum_stake_ratio: epoch_config.minimum_stake_ratio,
+            chunk_validator_minimum_stake_ratio: epoch_config.chunk_validator_minimum_stake_ratio,
             shuffle_shard_assignment_for_chunk_producers: epoch_config
                 .shuffle_shard_assignment_for_chunk_producers,
+            validator_blocklist: epoch_config.validator_blocklist.clone(),
             dynamic_resharding: false,
             protocol_version: genesis_protocol_version,
             validators: new_validator_accounts,
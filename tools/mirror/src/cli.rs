@@ -113,6 +113,11 @@ struct PrepareCmd {
     /// longer be able to mirror any traffic.
     #[clap(long)]
     secret_file_out: PathBuf,
+    /// Rewrite the new records file in the canonical form shared by
+    /// the genesis tools, see `neard canonicalize-genesis-records`.
+    /// Note that this loads all the records in memory.
+    #[clap(long)]
+    canonicalize_records: bool,
 }
 
 impl PrepareCmd {
@@ -122,7 +127,15 @@ impl PrepareCmd {
             &self.records_file_out,
             self.no_secret,
             &self.secret_file_out,
-        )
+        )?;
+        if self.canonicalize_records {
+            let stats = near_chain_configs::canonicalize_records_file(
+                &self.records_file_out,
+                &self.records_file_out,
+            )?;
+            tracing::info!(?stats, "canonicalized the new records");
+        }
+        Ok(())
     }
 }
 
@@ -6,6 +6,7 @@ use near_chain::{ChainStore, ChainStoreAccess};
 use near_chain_configs::GenesisValidationMode;
 use near_crypto::{PublicKey, SecretKey};
 use near_epoch_manager::{EpochManager, EpochManagerAdapter};
+use near_jsonrpc_primitives::methods;
 use near_jsonrpc_primitives::types::query::{
     QueryResponseKind as RpcQueryResponseKind, RpcQueryRequest,
 };
@@ -129,9 +130,10 @@ pub(crate) async fn keys_from_rpc(
     let request = RpcQueryRequest {
         block_reference,
         request: QueryRequest::ViewAccessKeyList { account_id: account_id.clone() },
+        decode: None,
     };
 
-    let response = match rpc_client.query(request).await {
+    let response = match rpc_client.call::<methods::Query>(request).await {
         Ok(r) => r,
         Err(e) => anyhow::bail!("failed making RPC request: {:?}", e),
     };
@@ -241,6 +241,8 @@ struct SourceChunk {
 
 struct SourceBlock {
     hash: CryptoHash,
+    // timestamp of the source block in nanoseconds, used to replay the source chain's timing
+    timestamp: u64,
     chunks: Vec<SourceChunk>,
 }
 
@@ -405,6 +407,13 @@ struct MirrorConfig {
     /// wait this long before sending each mainnet block's worth of transactions.
     /// TODO: add an option to target a specific number of transactions per second
     tx_batch_interval: Option<Duration>,
+    /// If this is given, each mainnet block's worth of transactions is sent
+    /// after the time that passed between that block and the previous one
+    /// on the source chain, multiplied by this factor. So 1.0 replays the
+    /// transactions with the same arrival pattern as on the source chain,
+    /// and 0.5 replays them twice as fast. Can't be given together with
+    /// `tx_batch_interval`.
+    source_timing_scale: Option<f64>,
 }
 
 const CREATE_ACCOUNT_DELTA: usize = 5;
@@ -732,6 +741,7 @@ struct MappedChunk {
 struct MappedBlock {
     source_height: BlockHeight,
     source_hash: CryptoHash,
+    source_timestamp: u64,
     chunks: Vec<MappedChunk>,
 }
 
@@ -739,6 +749,7 @@ struct MappedBlock {
 struct TxBatch {
     source_height: BlockHeight,
     source_hash: CryptoHash,
+    source_timestamp: u64,
     txs: Vec<(TxRef, TargetChainTx)>,
 }
 
@@ -747,6 +758,7 @@ impl From<&MappedBlock> for TxBatch {
         Self {
             source_height: block.source_height,
             source_hash: block.source_hash,
+            source_timestamp: block.source_timestamp,
             txs: block
                 .chunks
                 .iter()
@@ -1577,7 +1589,12 @@ impl<T: ChainAccess> TxMirror<T> {
                 tracing::warn!("something is wrong as there are no chunks to send transactions for at height {}", source_height);
             }
         }
-        Ok(MappedBlock { source_height, source_hash: source_block.hash, chunks })
+        Ok(MappedBlock {
+            source_height,
+            source_hash: source_block.hash,
+            source_timestamp: source_block.timestamp,
+            chunks,
+        })
     }
 
     // Up to a certain capacity, prepare and queue up batches of
@@ -1693,9 +1710,13 @@ impl<T: ChainAccess> TxMirror<T> {
         tx_block_queue: Arc<Mutex<VecDeque<MappedBlock>>>,
         mut send_time: Pin<Box<tokio::time::Sleep>>,
         send_delay: Arc<Mutex<Duration>>,
+        source_timing_scale: Option<f64>,
         target_client: Addr<ClientActor>,
     ) -> anyhow::Result<()> {
         let mut sent_source_height = None;
+        // when replaying the source chain's timing, the time we sent the last batch at, and the
+        // timestamp of its source block
+        let mut last_sent: Option<(tokio::time::Instant, u64)> = None;
 
         loop {
             (&mut send_time).await;
@@ -1729,7 +1750,26 @@ impl<T: ChainAccess> TxMirror<T> {
                 }
             };
 
-            let start_time = tokio::time::Instant::now();
+            let start_time = match (source_timing_scale, last_sent) {
+                (Some(scale), Some((last_send_time, last_timestamp))) => {
+                    let source_delay = Duration::from_nanos(
+                        tx_batch.source_timestamp.saturating_sub(last_timestamp),
+                    );
+                    // schedule relative to the last send time rather than to now, so that the time
+                    // spent sending doesn't accumulate, and we catch up if we fell behind
+                    let scheduled_time = last_send_time + source_delay.mul_f64(scale);
+                    let now = tokio::time::Instant::now();
+                    if scheduled_time < now {
+                        tracing::debug!(
+                            target: "mirror", "Sending transactions for source block #{} {:?} behind the source chain's timing",
+                            tx_batch.source_height, now - scheduled_time,
+                        );
+                    }
+                    tokio::time::sleep_until(scheduled_time).await;
+                    scheduled_time
+                }
+                _ => tokio::time::Instant::now(),
+            };
 
             tracing::debug!(target: "mirror", "Sending transactions for source block #{}", tx_batch.source_height);
             Self::send_transactions(
@@ -1739,9 +1779,14 @@ impl<T: ChainAccess> TxMirror<T> {
             .await?;
             set_last_source_height(&db, tx_batch.source_height)?;
             sent_source_height = Some(tx_batch.source_height);
+            last_sent = Some((start_time, tx_batch.source_timestamp));
 
             blocks_sent.send(tx_batch).await.unwrap();
 
+            // when replaying the source chain's timing, we wait before sending each batch instead
+            if source_timing_scale.is_some() {
+                continue;
+            }
             let send_delay = *send_delay.lock().unwrap();
             tracing::debug!(target: "mirror", "Sleeping for {:?} until sending more transactions", &send_delay);
             let next_send_time = start_time + send_delay;
@@ -2022,6 +2067,7 @@ impl<T: ChainAccess> TxMirror<T> {
             // not transactions corresponding to some actual block, but just extra txs create account actions in the first few blocks.
             let mut block = MappedBlock {
                 source_hash: CryptoHash::default(),
+                source_timestamp: 0,
                 source_height: last_height,
                 chunks: vec![MappedChunk { shard_id: ShardId::new(0), txs: Vec::new() }],
             };
@@ -2073,6 +2119,7 @@ impl<T: ChainAccess> TxMirror<T> {
         .await?;
 
         let send_delay = Arc::new(Mutex::new(send_delay));
+        let source_timing_scale = self.config.source_timing_scale;
         let send_delay2 = send_delay.clone();
         let (blocks_sent_tx, blocks_sent_rx) = mpsc::channel(10);
         let tx_block_queue2 = tx_block_queue.clone();
@@ -2088,6 +2135,7 @@ impl<T: ChainAccess> TxMirror<T> {
                 tx_block_queue2,
                 send_time,
                 send_delay2,
+                source_timing_scale,
                 target_client2,
             )
             .await;
@@ -2134,6 +2182,14 @@ async fn run<P: AsRef<Path>>(
         }
         None => Default::default(),
     };
+    if let Some(scale) = config.source_timing_scale {
+        if config.tx_batch_interval.is_some() {
+            anyhow::bail!("tx_batch_interval and source_timing_scale can't both be given");
+        }
+        if !(scale.is_finite() && scale > 0.0) {
+            anyhow::bail!("source_timing_scale must be a positive number, got {}", scale);
+        }
+    }
     if !online_source {
         let source_chain_access = crate::offline::ChainAccess::new(source_home)?;
         let stop_height = stop_height.unwrap_or(
@@ -139,7 +139,7 @@ impl crate::ChainAccess for ChainAccess {
                 receipts: chunk.prev_outgoing_receipts().to_vec(),
             })
         }
-        Ok(SourceBlock { hash: block_hash, chunks })
+        Ok(SourceBlock { hash: block_hash, timestamp: block.header().raw_timestamp(), chunks })
     }
 
     async fn get_next_block_height(&self, height: BlockHeight) -> Result<BlockHeight, ChainError> {
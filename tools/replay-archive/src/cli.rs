@@ -314,6 +314,7 @@ impl ReplayController {
         let storage_context = StorageContext {
             storage_data_source: StorageDataSource::DbTrieOnly,
             state_patch: Default::default(),
+            keep_storage_proof: true,
         };
 
         let block_context = Chain::get_apply_chunk_block_context(
@@ -3,7 +3,9 @@ use near_async::messaging::{noop, IntoMultiSender};
 use near_chain::rayon_spawner::RayonAsyncComputationSpawner;
 use near_chain::types::{ChainConfig, Tip};
 use near_chain::{Chain, ChainGenesis, DoomslugThresholdMode};
-use near_chain_configs::{GenesisValidationMode, MutableConfigValue, ReshardingConfig};
+use near_chain_configs::{
+    GenesisValidationMode, MutableConfigValue, ReshardingConfig, SecondaryIndexesConfig,
+};
 use near_epoch_manager::shard_tracker::{ShardTracker, TrackedConfig};
 use near_epoch_manager::types::EpochInfoAggregator;
 use near_epoch_manager::EpochManager;
@@ -256,11 +258,15 @@ fn load_snapshot(load_cmd: LoadCmd) {
         DoomslugThresholdMode::TwoThirds,
         ChainConfig {
             save_trie_changes: near_config.client_config.save_trie_changes,
+            save_account_receipts: false,
+            secondary_indexes: SecondaryIndexesConfig::default(),
+            save_access_key_usage: false,
             background_migration_threads: 1,
             resharding_config: MutableConfigValue::new(
                 ReshardingConfig::default(),
                 "resharding_config",
             ),
+            skip_state_transition_data_for_shards: vec![],
         },
         None,
         Arc::new(RayonAsyncComputationSpawner),
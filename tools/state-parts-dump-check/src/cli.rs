@@ -1,6 +1,5 @@
 use actix_web::{web, App, HttpServer};
 use anyhow::anyhow;
-use borsh::BorshDeserialize;
 use near_client::sync::external::{
     create_bucket_readonly, external_storage_location, external_storage_location_directory,
     get_num_parts_from_filename, ExternalConnection, StateFileType,
@@ -10,7 +9,7 @@ use near_jsonrpc::primitives::types::config::RpcProtocolConfigRequest;
 use near_primitives::hash::CryptoHash;
 use near_primitives::shard_layout::ShardLayout;
 use near_primitives::state_part::PartId;
-use near_primitives::state_sync::ShardStateSyncResponseHeader;
+use near_primitives::state_sync::{decode_state_header, decode_state_part};
 use near_primitives::types::{
     BlockId, BlockReference, EpochId, EpochReference, Finality, ShardId, StateRoot,
 };
@@ -239,7 +238,7 @@ fn create_external_connection(
 }
 
 fn validate_state_part(state_root: &StateRoot, part_id: PartId, part: &[u8]) -> bool {
-    match BorshDeserialize::try_from_slice(part) {
+    match decode_state_part(part) {
         Ok(trie_nodes) => {
             match Trie::validate_state_part(state_root, part_id, trie_nodes) {
                 Ok(_) => true,
@@ -259,7 +258,7 @@ fn validate_state_part(state_root: &StateRoot, part_id: PartId, part: &[u8]) ->
 }
 
 fn validate_state_header(header: &[u8]) -> bool {
-    match ShardStateSyncResponseHeader::try_from_slice(&header) {
+    match decode_state_header(header) {
         Ok(_) => {
             // Nodes will be able to download and deserialize the header.
             true
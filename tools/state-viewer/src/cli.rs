@@ -1,8 +1,10 @@
 use crate::commands::*;
 use crate::congestion_control::CongestionControlCmd;
 use crate::contract_accounts::ContractAccountFilter;
+use crate::export_range::ExportRangeCmd;
 use crate::replay_headers::replay_headers;
-use crate::rocksdb_stats::get_rocksdb_stats;
+use crate::rocksdb_stats::{get_rocksdb_columns_summary, get_rocksdb_stats};
+use crate::storage_usage::AccountStorageUsageCmd;
 use crate::trie_iteration_benchmark::TrieIterationBenchmarkCmd;
 
 use crate::latest_witnesses::StateWitnessCmd;
@@ -29,6 +31,10 @@ use std::sync::Arc;
 #[derive(clap::Subcommand)]
 #[clap(subcommand_required = true, arg_required_else_help = true)]
 pub enum StateViewerSubCommand {
+    /// Break down the storage used by the contract data of an account by key prefix, with the
+    /// NEAR locked for the storage of each prefix.
+    #[clap(alias = "account_storage_usage")]
+    AccountStorageUsage(AccountStorageUsageCmd),
     /// Apply block at some height for shard.
     Apply(ApplyCmd),
     /// Apply a chunk, even if it's not included in any block on disk
@@ -82,6 +88,10 @@ pub enum StateViewerSubCommand {
     /// Regenerates epoch info based on previous epoch.
     #[clap(alias = "epoch_analysis")]
     EpochAnalysis(EpochAnalysisCmd),
+    /// Export blocks, chunks, transactions and execution outcomes for a range of heights as
+    /// newline-delimited JSON or Parquet.
+    #[clap(alias = "export_range")]
+    ExportRange(ExportRangeCmd),
     /// Looks up a certain partial chunk.
     #[clap(alias = "partial_chunks")]
     PartialChunks(PartialChunksCmd),
@@ -158,6 +168,9 @@ impl StateViewerSubCommand {
         };
 
         match self {
+            StateViewerSubCommand::AccountStorageUsage(cmd) => {
+                cmd.run(home_dir, near_config, store)
+            }
             StateViewerSubCommand::Apply(cmd) => cmd.run(home_dir, near_config, store, storage),
             StateViewerSubCommand::ApplyChunk(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::ApplyRange(cmd) => {
@@ -180,10 +193,13 @@ impl StateViewerSubCommand {
             StateViewerSubCommand::DumpTx(cmd) => cmd.run(home_dir, near_config, store),
             StateViewerSubCommand::EpochInfo(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::EpochAnalysis(cmd) => cmd.run(near_config, store),
+            StateViewerSubCommand::ExportRange(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::PartialChunks(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::Receipts(cmd) => cmd.run(near_config, store),
             StateViewerSubCommand::ReplayHeaders(cmd) => cmd.run(home_dir, near_config, store),
-            StateViewerSubCommand::RocksDBStats(cmd) => cmd.run(store_opener.path()),
+            StateViewerSubCommand::RocksDBStats(cmd) => {
+                cmd.run(store_opener.path(), &near_config.config.store)
+            }
             StateViewerSubCommand::ScanDbColumn(cmd) => cmd.run(store),
             StateViewerSubCommand::State => state(home_dir, near_config, store),
             StateViewerSubCommand::StateChanges(cmd) => cmd.run(home_dir, near_config, store),
@@ -698,11 +714,32 @@ pub struct RocksDBStatsCmd {
     /// Location of the dumped Rocks DB stats.
     #[clap(long, value_parser)]
     file: Option<PathBuf>,
+    /// Dump the statistics of `sst_dump` for every SST file instead of summarizing the columns.
+    /// Requires `sst_dump` in the PATH.
+    #[clap(long)]
+    sst_dump: bool,
+    /// Number of the largest entries to report for every column.
+    #[clap(long, default_value_t = 5)]
+    largest_entries: usize,
+    /// Number of entries read from the start of every SST file to find the largest ones.
+    #[clap(long, default_value_t = 100)]
+    samples_per_file: usize,
 }
 
 impl RocksDBStatsCmd {
-    pub fn run(self, store_dir: &Path) {
-        get_rocksdb_stats(store_dir, self.file).expect("Couldn't get RocksDB stats");
+    pub fn run(self, store_dir: &Path, store_config: &near_store::StoreConfig) {
+        if self.sst_dump {
+            get_rocksdb_stats(store_dir, self.file).expect("Couldn't get RocksDB stats");
+        } else {
+            get_rocksdb_columns_summary(
+                store_dir,
+                store_config,
+                self.file,
+                self.largest_entries,
+                self.samples_per_file,
+            )
+            .expect("Couldn't get RocksDB stats");
+        }
     }
 }
 
@@ -0,0 +1,386 @@
+use anyhow::Context;
+use near_chain::{ChainStore, ChainStoreAccess};
+use near_primitives::block::Block;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, ShardId};
+use near_primitives::views::{
+    BlockHeaderView, ChunkHeaderView, ExecutionOutcomeWithIdView, ReceiptView,
+    SignedTransactionView,
+};
+use near_store::Store;
+use nearcore::NearConfig;
+use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+use parquet::file::properties::WriterProperties;
+use parquet::file::writer::SerializedFileWriter;
+use parquet::schema::parser::parse_message_type;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Number of records buffered in memory before they are written as a Parquet row group.
+const PARQUET_ROW_GROUP_SIZE: usize = 10_000;
+
+const PARQUET_SCHEMA: &str = "
+    message near_export {
+        REQUIRED BINARY kind (UTF8);
+        REQUIRED INT64 height;
+        REQUIRED BINARY block_hash (UTF8);
+        OPTIONAL INT64 shard_id;
+        REQUIRED BINARY data (UTF8);
+    }
+";
+
+#[derive(clap::ValueEnum, Debug, Clone, Copy)]
+pub enum ExportFormat {
+    /// One JSON object per line.
+    Ndjson,
+    /// Parquet file with the `kind`, `height`, `block_hash`, `shard_id` and `data` columns,
+    /// `data` holding the JSON of the record.
+    Parquet,
+}
+
+/// What to export for every block. Each level includes the records of the previous ones.
+#[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ExportDetail {
+    /// Block headers.
+    Blocks,
+    /// Headers of the chunks included in the blocks.
+    Chunks,
+    /// Transactions and receipts of the chunks.
+    Transactions,
+    /// Execution outcomes of the transactions and receipts.
+    Outcomes,
+}
+
+/// Exports blocks, chunks, transactions and execution outcomes for an inclusive range of
+/// heights. Records are written as they are read, so arbitrarily large ranges can be exported.
+#[derive(clap::Args)]
+pub struct ExportRangeCmd {
+    /// First height to export, inclusive.
+    #[clap(long)]
+    start_height: BlockHeight,
+    /// Last height to export, inclusive.
+    #[clap(long)]
+    end_height: BlockHeight,
+    #[clap(long, value_enum, default_value = "ndjson")]
+    format: ExportFormat,
+    #[clap(long, value_enum, default_value = "transactions")]
+    detail: ExportDetail,
+    /// Where to write the records. NDJSON is written to stdout if not set.
+    #[clap(long)]
+    output: Option<PathBuf>,
+}
+
+impl ExportRangeCmd {
+    pub fn run(self, near_config: NearConfig, store: Store) {
+        self.export(near_config, store).expect("failed to export the height range");
+    }
+
+    fn export(self, near_config: NearConfig, store: Store) -> anyhow::Result<()> {
+        anyhow::ensure!(
+            self.start_height <= self.end_height,
+            "start height {} is greater than end height {}",
+            self.start_height,
+            self.end_height
+        );
+        let chain_store = ChainStore::new(
+            store,
+            near_config.genesis.config.genesis_height,
+            near_config.client_config.save_trie_changes,
+        );
+        let mut writer: Box<dyn RecordWriter> = match (self.format, &self.output) {
+            (ExportFormat::Ndjson, None) => Box::new(NdjsonWriter::new(std::io::stdout())),
+            (ExportFormat::Ndjson, Some(path)) => Box::new(NdjsonWriter::new(
+                File::create(path).with_context(|| format!("failed to create {path:?}"))?,
+            )),
+            (ExportFormat::Parquet, None) => {
+                anyhow::bail!("--output is required for the parquet format")
+            }
+            (ExportFormat::Parquet, Some(path)) => Box::new(ParquetWriter::new(
+                File::create(path).with_context(|| format!("failed to create {path:?}"))?,
+            )?),
+        };
+
+        let mut num_blocks = 0;
+        let mut num_records = 0;
+        for height in self.start_height..=self.end_height {
+            let block_hash = match chain_store.get_block_hash_by_height(height) {
+                Ok(block_hash) => block_hash,
+                // No block was produced at this height.
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(err) => return Err(err).context(format!("failed to read height {height}")),
+            };
+            let block = chain_store.get_block(&block_hash)?;
+            for record in block_records(&chain_store, &block, self.detail)? {
+                writer.write(record)?;
+                num_records += 1;
+            }
+            num_blocks += 1;
+        }
+        writer.finish()?;
+        tracing::info!(
+            target: "state_viewer",
+            start_height = self.start_height,
+            end_height = self.end_height,
+            num_blocks,
+            num_records,
+            "exported height range"
+        );
+        Ok(())
+    }
+}
+
+struct ExportRecord {
+    kind: &'static str,
+    height: BlockHeight,
+    block_hash: CryptoHash,
+    shard_id: Option<ShardId>,
+    data: serde_json::Value,
+}
+
+impl ExportRecord {
+    fn new(
+        kind: &'static str,
+        block: &Block,
+        shard_id: Option<ShardId>,
+        data: impl serde::Serialize,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            kind,
+            height: block.header().height(),
+            block_hash: *block.hash(),
+            shard_id,
+            data: serde_json::to_value(data)?,
+        })
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "kind": self.kind,
+            "height": self.height,
+            "block_hash": self.block_hash,
+            "shard_id": self.shard_id,
+            "data": self.data,
+        })
+    }
+}
+
+/// Returns the records of a block, up to the given level of detail.
+fn block_records(
+    chain_store: &ChainStore,
+    block: &Block,
+    detail: ExportDetail,
+) -> anyhow::Result<Vec<ExportRecord>> {
+    let mut records = vec![ExportRecord::new(
+        "block",
+        block,
+        None,
+        BlockHeaderView::from(block.header().clone()),
+    )?];
+    if detail < ExportDetail::Chunks {
+        return Ok(records);
+    }
+
+    let height = block.header().height();
+    for chunk_header in block.chunks().iter_deprecated() {
+        // Only the chunks included in this block, missing chunks repeat older headers.
+        if !chunk_header.is_new_chunk(height) {
+            continue;
+        }
+        let shard_id = Some(chunk_header.shard_id());
+        records.push(ExportRecord::new(
+            "chunk",
+            block,
+            shard_id,
+            ChunkHeaderView::from(chunk_header.clone()),
+        )?);
+        if detail < ExportDetail::Transactions {
+            continue;
+        }
+        let chunk = chain_store.get_chunk(&chunk_header.chunk_hash())?;
+        for transaction in chunk.transactions() {
+            records.push(ExportRecord::new(
+                "transaction",
+                block,
+                shard_id,
+                SignedTransactionView::from(transaction.clone()),
+            )?);
+        }
+        for receipt in chunk.prev_outgoing_receipts() {
+            records.push(ExportRecord::new(
+                "receipt",
+                block,
+                shard_id,
+                ReceiptView::from(receipt.clone()),
+            )?);
+        }
+    }
+    if detail < ExportDetail::Outcomes {
+        return Ok(records);
+    }
+
+    let mut outcomes =
+        chain_store.get_block_execution_outcomes(block.hash())?.into_iter().collect::<Vec<_>>();
+    outcomes.sort_by_key(|(shard_id, _)| *shard_id);
+    for (shard_id, shard_outcomes) in outcomes {
+        for outcome in shard_outcomes {
+            records.push(ExportRecord::new(
+                "outcome",
+                block,
+                Some(shard_id),
+                ExecutionOutcomeWithIdView::from(outcome),
+            )?);
+        }
+    }
+    Ok(records)
+}
+
+trait RecordWriter {
+    fn write(&mut self, record: ExportRecord) -> anyhow::Result<()>;
+    fn finish(self: Box<Self>) -> anyhow::Result<()>;
+}
+
+struct NdjsonWriter<W: Write> {
+    out: BufWriter<W>,
+}
+
+impl<W: Write> NdjsonWriter<W> {
+    fn new(out: W) -> Self {
+        Self { out: BufWriter::new(out) }
+    }
+}
+
+impl<W: Write> RecordWriter for NdjsonWriter<W> {
+    fn write(&mut self, record: ExportRecord) -> anyhow::Result<()> {
+        serde_json::to_writer(&mut self.out, &record.to_json())?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+struct ParquetWriter {
+    writer: SerializedFileWriter<File>,
+    buffer: Vec<ExportRecord>,
+}
+
+impl ParquetWriter {
+    fn new(file: File) -> anyhow::Result<Self> {
+        let schema = Arc::new(parse_message_type(PARQUET_SCHEMA)?);
+        let properties = Arc::new(WriterProperties::builder().build());
+        let writer = SerializedFileWriter::new(file, schema, properties)?;
+        Ok(Self { writer, buffer: Vec::with_capacity(PARQUET_ROW_GROUP_SIZE) })
+    }
+
+    /// Writes the buffered records as a row group.
+    fn flush(&mut self) -> anyhow::Result<()> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let records = std::mem::take(&mut self.buffer);
+        let mut row_group = self.writer.next_row_group()?;
+        let mut column_index = 0;
+        while let Some(mut column) = row_group.next_column()? {
+            match column_index {
+                0 => {
+                    let kinds = records.iter().map(|r| ByteArray::from(r.kind)).collect::<Vec<_>>();
+                    column.typed::<ByteArrayType>().write_batch(&kinds, None, None)?;
+                }
+                1 => {
+                    let heights = records.iter().map(|r| r.height as i64).collect::<Vec<_>>();
+                    column.typed::<Int64Type>().write_batch(&heights, None, None)?;
+                }
+                2 => {
+                    let hashes = records
+                        .iter()
+                        .map(|r| ByteArray::from(r.block_hash.to_string()))
+                        .collect::<Vec<_>>();
+                    column.typed::<ByteArrayType>().write_batch(&hashes, None, None)?;
+                }
+                3 => {
+                    let shard_ids = records
+                        .iter()
+                        .filter_map(|r| r.shard_id.map(|shard_id| u64::from(shard_id) as i64))
+                        .collect::<Vec<_>>();
+                    let definition_levels =
+                        records.iter().map(|r| r.shard_id.is_some() as i16).collect::<Vec<_>>();
+                    column.typed::<Int64Type>().write_batch(
+                        &shard_ids,
+                        Some(&definition_levels),
+                        None,
+                    )?;
+                }
+                4 => {
+                    let data = records
+                        .iter()
+                        .map(|r| ByteArray::from(r.data.to_string()))
+                        .collect::<Vec<_>>();
+                    column.typed::<ByteArrayType>().write_batch(&data, None, None)?;
+                }
+                _ => anyhow::bail!("unexpected parquet column {column_index}"),
+            }
+            column.close()?;
+            column_index += 1;
+        }
+        row_group.close()?;
+        Ok(())
+    }
+}
+
+impl RecordWriter for ParquetWriter {
+    fn write(&mut self, record: ExportRecord) -> anyhow::Result<()> {
+        self.buffer.push(record);
+        if self.buffer.len() >= PARQUET_ROW_GROUP_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> anyhow::Result<()> {
+        self.flush()?;
+        self.writer.close()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ExportRecord, ParquetWriter, RecordWriter, PARQUET_ROW_GROUP_SIZE};
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::ShardId;
+    use parquet::file::reader::{FileReader, SerializedFileReader};
+
+    fn record(height: u64, shard_id: Option<ShardId>) -> ExportRecord {
+        ExportRecord {
+            kind: "chunk",
+            height,
+            block_hash: CryptoHash::default(),
+            shard_id,
+            data: serde_json::json!({ "height": height }),
+        }
+    }
+
+    #[test]
+    fn parquet_export_writes_all_records() {
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let mut writer: Box<dyn RecordWriter> =
+            Box::new(ParquetWriter::new(file.reopen().unwrap()).unwrap());
+        let num_records = PARQUET_ROW_GROUP_SIZE + 3;
+        for height in 0..num_records as u64 {
+            let shard_id = (height % 2 == 0).then(|| ShardId::new(height));
+            writer.write(record(height, shard_id)).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let reader = SerializedFileReader::new(file.reopen().unwrap()).unwrap();
+        let metadata = reader.metadata();
+        assert_eq!(metadata.num_row_groups(), 2);
+        assert_eq!(metadata.file_metadata().num_rows(), num_records as i64);
+        assert_eq!(metadata.file_metadata().schema_descr().num_columns(), 5);
+    }
+}
@@ -7,6 +7,7 @@ mod commands;
 mod congestion_control;
 mod contract_accounts;
 mod epoch_info;
+mod export_range;
 mod latest_witnesses;
 pub mod progress_reporter;
 mod replay_headers;
@@ -15,6 +16,7 @@ mod scan_db;
 mod state_changes;
 mod state_dump;
 mod state_parts;
+mod storage_usage;
 mod trie_iteration_benchmark;
 mod tx_dump;
 pub mod util;
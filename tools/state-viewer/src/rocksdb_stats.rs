@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use bytesize::ByteSize;
+use near_store::db::{Database, RocksDB};
+use near_store::{DBCol, Mode, StoreConfig, Temperature};
+use std::collections::{BTreeMap, HashMap};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
@@ -99,6 +102,189 @@ impl ColumnsData {
     }
 }
 
+/// Summary of the SST files of a column.
+#[derive(serde::Serialize, Debug)]
+struct ColumnSummary {
+    col: String,
+    sst_size: u64,
+    num_files: usize,
+    num_entries: u64,
+    num_deletions: u64,
+    /// Number and size of the files of every LSM level.
+    levels: BTreeMap<i32, LevelSummary>,
+    /// RocksDB's estimate of the size of the data once the overwritten and deleted entries are
+    /// compacted away.
+    estimated_live_data_size: Option<u64>,
+    /// Size of the SST files over the estimated size of the live data.
+    space_amplification: Option<f64>,
+    /// Largest entries among the ones sampled at the start of every SST file, largest first.
+    largest_sampled_entries: Vec<SampledEntry>,
+}
+
+#[derive(serde::Serialize, Debug, Default)]
+struct LevelSummary {
+    num_files: usize,
+    size: u64,
+}
+
+#[derive(serde::Serialize, Debug, PartialEq)]
+struct SampledEntry {
+    /// Hex-encoded key.
+    key: String,
+    key_size: usize,
+    value_size: usize,
+}
+
+impl SampledEntry {
+    fn size(&self) -> usize {
+        self.key_size + self.value_size
+    }
+}
+
+impl ColumnSummary {
+    fn new(col: DBCol) -> Self {
+        Self {
+            col: col.to_string(),
+            sst_size: 0,
+            num_files: 0,
+            num_entries: 0,
+            num_deletions: 0,
+            levels: BTreeMap::new(),
+            estimated_live_data_size: None,
+            space_amplification: None,
+            largest_sampled_entries: vec![],
+        }
+    }
+
+    /// Keeps the entry if it's among the `limit` largest ones sampled so far.
+    fn add_sample(&mut self, key: &[u8], value: &[u8], limit: usize) {
+        let entry = SampledEntry {
+            key: key.iter().map(|byte| format!("{byte:02x}")).collect(),
+            key_size: key.len(),
+            value_size: value.len(),
+        };
+        // Files of different levels overlap, so the same entry can be sampled more than once.
+        if self.largest_sampled_entries.contains(&entry) {
+            return;
+        }
+        let index =
+            self.largest_sampled_entries.partition_point(|sampled| sampled.size() >= entry.size());
+        if index < limit {
+            self.largest_sampled_entries.insert(index, entry);
+            self.largest_sampled_entries.truncate(limit);
+        }
+    }
+}
+
+/// Summarizes the SST files of every column of the database, sorted by decreasing size.
+fn get_columns_summary(
+    db: &RocksDB,
+    num_largest_entries: usize,
+    samples_per_file: usize,
+) -> anyhow::Result<Vec<ColumnSummary>> {
+    let mut summaries: HashMap<DBCol, ColumnSummary> = HashMap::new();
+    for file in db.get_sst_files()? {
+        let summary = summaries.entry(file.col).or_insert_with(|| ColumnSummary::new(file.col));
+        summary.sst_size += file.size;
+        summary.num_files += 1;
+        summary.num_entries += file.num_entries;
+        summary.num_deletions += file.num_deletions;
+        let level = summary.levels.entry(file.level).or_default();
+        level.num_files += 1;
+        level.size += file.size;
+
+        if num_largest_entries == 0 {
+            continue;
+        }
+        let Some(start_key) = &file.start_key else {
+            continue;
+        };
+        for item in db.iter_range(file.col, Some(start_key), None).take(samples_per_file) {
+            let (key, value) = item?;
+            summary.add_sample(&key, &value, num_largest_entries);
+        }
+    }
+
+    for (col, summary) in &mut summaries {
+        summary.estimated_live_data_size = db.get_estimated_live_data_size(*col)?;
+        summary.space_amplification = summary
+            .estimated_live_data_size
+            .filter(|size| *size > 0)
+            .map(|size| summary.sst_size as f64 / size as f64);
+    }
+    let mut summaries: Vec<_> = summaries.into_values().collect();
+    summaries.sort_by_key(|summary| std::cmp::Reverse(summary.sst_size));
+    Ok(summaries)
+}
+
+fn print_columns_summary(summaries: &[ColumnSummary]) {
+    println!(
+        "{:<32} {:>12} {:>8} {:>14} {:>12} {:>10}  {}",
+        "Column", "SST size", "Files", "Entries", "Live data", "Space amp", "Levels (files, size)"
+    );
+    for summary in summaries {
+        let live_data_size = summary
+            .estimated_live_data_size
+            .map_or_else(|| "-".to_string(), |size| ByteSize(size).to_string());
+        let space_amplification =
+            summary.space_amplification.map_or_else(|| "-".to_string(), |amp| format!("{amp:.2}"));
+        let levels = summary
+            .levels
+            .iter()
+            .map(|(level, data)| format!("L{level}: {}, {}", data.num_files, ByteSize(data.size)))
+            .collect::<Vec<_>>()
+            .join("; ");
+        let sst_size = ByteSize(summary.sst_size).to_string();
+        println!(
+            "{:<32} {:>12} {:>8} {:>14} {:>12} {:>10}  {}",
+            summary.col,
+            sst_size,
+            summary.num_files,
+            summary.num_entries,
+            live_data_size,
+            space_amplification,
+            levels,
+        );
+    }
+    for summary in summaries.iter().filter(|summary| !summary.largest_sampled_entries.is_empty()) {
+        println!();
+        println!("Largest sampled entries of {}:", summary.col);
+        for entry in &summary.largest_sampled_entries {
+            // Long keys are abbreviated, the full keys are in the JSON output.
+            let key = if entry.key.len() > 64 {
+                format!("{}…", &entry.key[..64])
+            } else {
+                entry.key.clone()
+            };
+            let size = ByteSize(entry.size() as u64).to_string();
+            println!(
+                "  {size:>12}  key: {key} ({} B), value: {} B",
+                entry.key_size, entry.value_size
+            );
+        }
+    }
+}
+
+/// Prints the size, files, level occupancy, space amplification and largest sampled entries of
+/// every column, and writes them as JSON to `file` if given.
+pub fn get_rocksdb_columns_summary(
+    store_dir: &Path,
+    store_config: &StoreConfig,
+    file: Option<PathBuf>,
+    num_largest_entries: usize,
+    samples_per_file: usize,
+) -> anyhow::Result<()> {
+    let db = RocksDB::open(store_dir, store_config, Mode::ReadOnly, Temperature::Hot)?;
+    eprintln!("Collecting stats ...");
+    let summaries = get_columns_summary(&db, num_largest_entries, samples_per_file)?;
+    print_columns_summary(&summaries);
+    if let Some(file) = file {
+        std::fs::write(file, serde_json::to_string_pretty(&summaries)?)?;
+    }
+    Ok(())
+}
+
+/// Dumps the statistics reported by `sst_dump` for the SST files of every column.
 pub fn get_rocksdb_stats(store_dir: &Path, file: Option<PathBuf>) -> anyhow::Result<()> {
     let mut data = ColumnsData::new();
 
@@ -120,3 +306,20 @@ pub fn get_rocksdb_stats(store_dir: &Path, file: Option<PathBuf>) -> anyhow::Res
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::ColumnSummary;
+    use near_store::DBCol;
+
+    #[test]
+    fn test_largest_sampled_entries() {
+        let mut summary = ColumnSummary::new(DBCol::State);
+        for (key, value_size) in [(1u8, 10), (2, 30), (3, 20), (2, 30), (4, 5), (5, 25)] {
+            summary.add_sample(&[key], &vec![0; value_size], 3);
+        }
+        let keys: Vec<_> =
+            summary.largest_sampled_entries.iter().map(|entry| entry.key.as_str()).collect();
+        assert_eq!(keys, ["02", "05", "03"]);
+    }
+}
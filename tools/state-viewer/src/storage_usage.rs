@@ -0,0 +1,303 @@
+//! State viewer command breaking down the storage used by the contract data of an account by
+//! key prefix, with the amount of NEAR locked to pay for the storage of each prefix.
+
+use crate::util::{load_trie_stop_at_height, LoadTrieMode};
+use near_chain::types::RuntimeAdapter;
+use near_epoch_manager::EpochManagerAdapter;
+use near_primitives::account::Account;
+use near_primitives::trie_key::trie_key_parsers;
+use near_primitives::types::{AccountId, Balance, BlockHeight, StorageUsage};
+use near_store::{get_account, StorageError, Store, Trie};
+use nearcore::NearConfig;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Yocto NEAR in one NEAR.
+const ONE_NEAR: Balance = 10u128.pow(24);
+
+#[derive(clap::Parser)]
+pub struct AccountStorageUsageCmd {
+    /// Account whose contract data is inspected.
+    #[clap(long)]
+    account_id: AccountId,
+    /// Number of leading bytes of the keys by which they are grouped, e.g. 1 for the
+    /// single-byte prefixes which near-sdk collections typically use.
+    #[clap(long, default_value = "1")]
+    prefix_len: usize,
+    /// Prefixes (as UTF-8 strings) by which the keys are grouped instead, e.g. the prefixes of
+    /// the collections of the contract. A key is counted under its longest matching prefix.
+    /// Can be repeated.
+    #[clap(long = "prefix")]
+    prefixes: Vec<String>,
+    /// Height of the block whose state is inspected. The head of the chain by default.
+    #[clap(long)]
+    block_height: Option<BlockHeight>,
+}
+
+impl AccountStorageUsageCmd {
+    pub fn run(self, home_dir: &Path, near_config: NearConfig, store: Store) {
+        let mode = match self.block_height {
+            Some(height) => LoadTrieMode::Height(height),
+            None => LoadTrieMode::Latest,
+        };
+        let (epoch_manager, runtime, state_roots, header) =
+            load_trie_stop_at_height(store, home_dir, &near_config, mode);
+        let epoch_id = header.epoch_id();
+        let shard_layout = epoch_manager.get_shard_layout(epoch_id).unwrap();
+        let shard_id = shard_layout.account_id_to_shard_id(&self.account_id);
+        let shard_index = shard_layout.get_shard_index(shard_id).unwrap();
+        let trie = runtime
+            .get_trie_for_shard(shard_id, header.prev_hash(), state_roots[shard_index], false)
+            .unwrap();
+        let runtime_config = runtime.get_protocol_config(epoch_id).unwrap().runtime_config;
+        let storage_config = &runtime_config.fees.storage_usage_config;
+
+        let Some(account) = get_account(&trie, &self.account_id).unwrap() else {
+            println!("Account {} does not exist at height {}", self.account_id, header.height());
+            std::process::exit(1);
+        };
+        let grouping = if self.prefixes.is_empty() {
+            KeyGrouping::Length(self.prefix_len)
+        } else {
+            KeyGrouping::Prefixes(self.prefixes.into_iter().map(String::into_bytes).collect())
+        };
+        let usage = contract_data_usage(&trie, &self.account_id, &grouping).unwrap();
+
+        println!(
+            "Storage of {} at height {} ({} yN locked per byte):",
+            self.account_id,
+            header.height(),
+            storage_config.storage_amount_per_byte
+        );
+        print_storage_usage(
+            &account,
+            &usage,
+            storage_config.num_extra_bytes_record,
+            storage_config.storage_amount_per_byte,
+        );
+    }
+}
+
+/// How the keys of the contract data are grouped into prefixes.
+pub(crate) enum KeyGrouping {
+    /// By their first bytes, keys shorter than that are their own prefix.
+    Length(usize),
+    /// By the longest of the prefixes that they start with, if any.
+    Prefixes(Vec<Vec<u8>>),
+}
+
+impl KeyGrouping {
+    /// Returns the prefix of `key`, `None` if it doesn't match any of the prefixes.
+    fn prefix<'a>(&self, key: &'a [u8]) -> Option<&'a [u8]> {
+        match self {
+            KeyGrouping::Length(len) => Some(&key[..key.len().min(*len)]),
+            KeyGrouping::Prefixes(prefixes) => prefixes
+                .iter()
+                .filter(|prefix| key.starts_with(prefix))
+                .max_by_key(|prefix| prefix.len())
+                .map(|prefix| &key[..prefix.len()]),
+        }
+    }
+}
+
+/// Contract data stored under a prefix.
+#[derive(Default, Debug, PartialEq, Eq)]
+pub(crate) struct PrefixUsage {
+    pub(crate) num_keys: u64,
+    pub(crate) key_bytes: u64,
+    pub(crate) value_bytes: u64,
+}
+
+impl PrefixUsage {
+    /// Storage usage of the data as counted by the runtime, i.e. with the extra bytes of every
+    /// record.
+    pub(crate) fn storage_usage(&self, num_extra_bytes_record: StorageUsage) -> StorageUsage {
+        self.num_keys * num_extra_bytes_record + self.key_bytes + self.value_bytes
+    }
+}
+
+/// Contract data of an account by prefix. The keys matching none of the prefixes are under
+/// `None`.
+pub(crate) type ContractDataUsage = BTreeMap<Option<Vec<u8>>, PrefixUsage>;
+
+pub(crate) fn contract_data_usage(
+    trie: &Trie,
+    account_id: &AccountId,
+    grouping: &KeyGrouping,
+) -> Result<ContractDataUsage, StorageError> {
+    let mut usage = ContractDataUsage::new();
+    let raw_prefix = trie_key_parsers::get_raw_prefix_for_contract_data(account_id, &[]);
+    let mut iter = trie.disk_iter()?;
+    iter.seek_prefix(&raw_prefix)?;
+    for item in iter {
+        let (raw_key, value) = item?;
+        let key = &raw_key[raw_prefix.len()..];
+        let prefix_usage = usage.entry(grouping.prefix(key).map(<[u8]>::to_vec)).or_default();
+        prefix_usage.num_keys += 1;
+        prefix_usage.key_bytes += key.len() as u64;
+        prefix_usage.value_bytes += value.len() as u64;
+    }
+    Ok(usage)
+}
+
+/// Prints the prefix as a string if it is printable ASCII, as hex otherwise.
+fn format_prefix(prefix: &Option<Vec<u8>>) -> String {
+    match prefix {
+        None => "(other)".to_string(),
+        Some(prefix) if prefix.iter().all(|b| b.is_ascii_graphic() || *b == b' ') => {
+            format!("{:?}", String::from_utf8_lossy(prefix))
+        }
+        Some(prefix) => {
+            format!("0x{}", prefix.iter().map(|b| format!("{b:02x}")).collect::<String>())
+        }
+    }
+}
+
+fn format_near(amount: Balance) -> String {
+    format!("{}.{:05}", amount / ONE_NEAR, amount % ONE_NEAR / (ONE_NEAR / 100_000))
+}
+
+fn print_storage_usage(
+    account: &Account,
+    usage: &ContractDataUsage,
+    num_extra_bytes_record: StorageUsage,
+    storage_amount_per_byte: Balance,
+) {
+    let total_storage_usage = account.storage_usage();
+    let mut rows: Vec<_> = usage
+        .iter()
+        .map(|(prefix, prefix_usage)| {
+            (
+                format_prefix(prefix),
+                prefix_usage,
+                prefix_usage.storage_usage(num_extra_bytes_record),
+            )
+        })
+        .collect();
+    rows.sort_by_key(|(_, _, storage_usage)| std::cmp::Reverse(*storage_usage));
+
+    println!(
+        "{:<24} {:>10} {:>14} {:>14} {:>14} {:>8} {:>16}",
+        "prefix", "keys", "key bytes", "value bytes", "storage bytes", "share", "NEAR locked"
+    );
+    let share = |storage_usage: StorageUsage| {
+        if total_storage_usage == 0 {
+            0.
+        } else {
+            100. * storage_usage as f64 / total_storage_usage as f64
+        }
+    };
+    let mut contract_data_usage = 0;
+    for (prefix, prefix_usage, storage_usage) in rows {
+        contract_data_usage += storage_usage;
+        println!(
+            "{:<24} {:>10} {:>14} {:>14} {:>14} {:>7.2}% {:>16}",
+            prefix,
+            prefix_usage.num_keys,
+            prefix_usage.key_bytes,
+            prefix_usage.value_bytes,
+            storage_usage,
+            share(storage_usage),
+            format_near(storage_usage as Balance * storage_amount_per_byte)
+        );
+    }
+    // The account record, the contract code and the access keys.
+    let other_usage = total_storage_usage.saturating_sub(contract_data_usage);
+    println!(
+        "{:<24} {:>10} {:>14} {:>14} {:>14} {:>7.2}% {:>16}",
+        "(account, code, keys)",
+        "",
+        "",
+        "",
+        other_usage,
+        share(other_usage),
+        format_near(other_usage as Balance * storage_amount_per_byte)
+    );
+    println!(
+        "{:<24} {:>10} {:>14} {:>14} {:>14} {:>8} {:>16}",
+        "total",
+        "",
+        "",
+        "",
+        total_storage_usage,
+        "",
+        format_near(total_storage_usage as Balance * storage_amount_per_byte)
+    );
+    println!(
+        "Balance: {} NEAR, locked: {} NEAR",
+        format_near(account.amount()),
+        format_near(account.locked())
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contract_data_usage, format_near, KeyGrouping, PrefixUsage, ONE_NEAR};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::AccountId;
+    use near_store::test_utils::{test_populate_trie, TestTriesBuilder};
+    use near_store::{ShardUId, Trie};
+
+    fn create_trie(data: &[(&str, &[u8], usize)]) -> Trie {
+        let tries = TestTriesBuilder::new().build();
+        let changes = data
+            .iter()
+            .map(|(account_id, key, value_len)| {
+                let account_id: AccountId = account_id.parse().unwrap();
+                let key = TrieKey::ContractData { account_id, key: key.to_vec() };
+                (key.to_vec(), Some(vec![1; *value_len]))
+            })
+            .collect();
+        let root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, ShardUId::single_shard(), changes);
+        tries.get_trie_for_shard(ShardUId::single_shard(), root)
+    }
+
+    fn usage(num_keys: u64, key_bytes: u64, value_bytes: u64) -> PrefixUsage {
+        PrefixUsage { num_keys, key_bytes, value_bytes }
+    }
+
+    #[test]
+    fn test_contract_data_usage() {
+        let trie = create_trie(&[
+            ("alice.near", b"a1", 10),
+            ("alice.near", b"a22", 20),
+            ("alice.near", b"bb", 5),
+            ("alice.near", b"", 1),
+            // Data of other accounts, including one whose id starts with the id of alice.
+            ("alice.nearx", b"a1", 100),
+            ("bob.near", b"a1", 100),
+        ]);
+        let account_id = "alice.near".parse().unwrap();
+
+        let by_first_byte =
+            contract_data_usage(&trie, &account_id, &KeyGrouping::Length(1)).unwrap();
+        assert_eq!(
+            by_first_byte.into_iter().collect::<Vec<_>>(),
+            vec![
+                (Some(b"".to_vec()), usage(1, 0, 1)),
+                (Some(b"a".to_vec()), usage(2, 5, 30)),
+                (Some(b"b".to_vec()), usage(1, 2, 5)),
+            ]
+        );
+
+        let grouping = KeyGrouping::Prefixes(vec![b"a".to_vec(), b"a2".to_vec()]);
+        let by_prefixes = contract_data_usage(&trie, &account_id, &grouping).unwrap();
+        assert_eq!(
+            by_prefixes.into_iter().collect::<Vec<_>>(),
+            vec![
+                (None, usage(2, 2, 6)),
+                (Some(b"a".to_vec()), usage(1, 2, 10)),
+                (Some(b"a2".to_vec()), usage(1, 3, 20)),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_storage_usage_and_format() {
+        assert_eq!(usage(2, 5, 30).storage_usage(40), 115);
+        assert_eq!(format_near(0), "0.00000");
+        assert_eq!(format_near(ONE_NEAR * 3 / 2), "1.50000");
+        // 115 bytes at 10^19 yN per byte.
+        assert_eq!(format_near(115 * 10u128.pow(19)), "0.00115");
+    }
+}
@@ -0,0 +1,68 @@
+//! Pinning of threads to sets of CPUs.
+//!
+//! Operators of big machines can isolate the consensus-critical work of the node by pinning the
+//! thread pools doing it to dedicated CPUs. The threads pinned so far are recorded, so that the
+//! current assignments can be inspected at runtime.
+
+use std::io;
+use std::sync::Mutex;
+
+/// A thread pinned to a set of CPUs.
+#[derive(Clone, Debug)]
+pub struct ThreadAssignment {
+    /// Name of the thread pool the thread belongs to.
+    pub pool: String,
+    /// Name of the thread, if it has one.
+    pub thread: Option<String>,
+    /// CPUs the thread is allowed to run on.
+    pub cpu_set: Vec<usize>,
+}
+
+static ASSIGNMENTS: Mutex<Vec<ThreadAssignment>> = Mutex::new(Vec::new());
+
+/// Pins the current thread, belonging to the thread pool `pool`, to the CPUs in `cpu_set`.
+///
+/// Pinning is only an optimization, so failures are logged rather than returned.
+pub fn pin_current_thread(pool: &str, cpu_set: &[usize]) {
+    if let Err(err) = set_current_thread_affinity(cpu_set) {
+        tracing::warn!(target: "performance_metrics", pool, ?cpu_set, %err, "Failed to pin thread");
+        return;
+    }
+    let thread = std::thread::current().name().map(str::to_string);
+    tracing::debug!(target: "performance_metrics", pool, ?thread, ?cpu_set, "Pinned thread");
+    ASSIGNMENTS.lock().unwrap().push(ThreadAssignment {
+        pool: pool.to_string(),
+        thread,
+        cpu_set: cpu_set.to_vec(),
+    });
+}
+
+/// Returns all the threads pinned so far.
+pub fn thread_assignments() -> Vec<ThreadAssignment> {
+    ASSIGNMENTS.lock().unwrap().clone()
+}
+
+#[cfg(target_os = "linux")]
+fn set_current_thread_affinity(cpu_set: &[usize]) -> io::Result<()> {
+    if let Some(cpu) = cpu_set.iter().find(|&&cpu| cpu >= libc::CPU_SETSIZE as usize) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("invalid CPU {cpu}")));
+    }
+    // SAFETY: `cpu_set_t` is a plain bitmask for which all zeroes is a valid value, all the CPUs
+    // set are within its bounds, and pid 0 refers to the calling thread.
+    let res = unsafe {
+        let mut set: libc::cpu_set_t = std::mem::zeroed();
+        for &cpu in cpu_set {
+            libc::CPU_SET(cpu, &mut set);
+        }
+        libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set)
+    };
+    if res != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn set_current_thread_affinity(_cpu_set: &[usize]) -> io::Result<()> {
+    Err(io::Error::new(io::ErrorKind::Unsupported, "thread pinning is only supported on Linux"))
+}
@@ -1,6 +1,7 @@
 pub mod actix_disabled;
 #[cfg(feature = "performance_stats")]
 pub mod actix_enabled;
+pub mod affinity;
 pub mod process;
 pub mod stats_disabled;
 #[cfg(feature = "performance_stats")]